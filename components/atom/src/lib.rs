@@ -0,0 +1,233 @@
+/// Interned strings for the names repeated all over a document: tag
+/// names, attribute names, ids, classes and CSS identifiers.
+///
+/// Every distinct string is stored once and all the atoms of a string
+/// share its allocation, so comparing two atoms is a pointer comparison
+/// and cloning an atom doesn't copy the string. The strings no atom
+/// uses anymore are freed as other strings are interned, so the ids &
+/// classes of the documents rendered one after the other don't pile up.
+use std::borrow::Borrow;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashSet;
+use std::fmt;
+use std::hash::{Hash, Hasher};
+use std::ops::Deref;
+use std::sync::{Arc, Mutex};
+
+#[macro_use]
+extern crate lazy_static;
+
+/// The atoms are split in shards by the hash of their string, so the
+/// threads interning strings at once rarely wait for each other
+const SHARD_COUNT: usize = 16;
+
+/// The atoms of a shard before the unused ones are first freed
+const MIN_SWEEP_LEN: usize = 256;
+
+lazy_static! {
+    /// The atoms are shared between threads, e.g. the threads
+    /// parsing stylesheets and the one building the DOM tree
+    static ref SHARDS: Vec<Mutex<Shard>> = (0..SHARD_COUNT)
+        .map(|_| Mutex::new(Shard::default()))
+        .collect();
+}
+
+#[derive(Default)]
+struct Shard {
+    atoms: HashSet<Arc<str>>,
+    /// The atoms left by the last sweep
+    swept_len: usize,
+}
+
+impl Shard {
+    /// Free the strings only referenced by the shard. An atom can't be
+    /// cloned from the shard while it's locked, so they can't be used.
+    fn sweep(&mut self) {
+        self.atoms.retain(|atom| Arc::strong_count(atom) > 1);
+        self.swept_len = self.atoms.len();
+        if self.atoms.capacity() > 4 * self.swept_len.max(MIN_SWEEP_LEN) {
+            self.atoms.shrink_to_fit();
+        }
+    }
+}
+
+fn shard(value: &str) -> &'static Mutex<Shard> {
+    let mut hasher = DefaultHasher::new();
+    value.hash(&mut hasher);
+    &SHARDS[hasher.finish() as usize % SHARD_COUNT]
+}
+
+#[derive(Clone)]
+pub struct Atom(Arc<str>);
+
+impl Atom {
+    pub fn new(value: &str) -> Self {
+        let mut shard = shard(value).lock().unwrap();
+        if let Some(atom) = shard.atoms.get(value) {
+            return Self(atom.clone());
+        }
+        // the cost of a sweep is spread over the atoms interned since
+        // the last one, as the shard doubled in the meantime
+        if shard.atoms.len() >= 2 * shard.swept_len.max(MIN_SWEEP_LEN / 2) {
+            shard.sweep();
+        }
+        let atom: Arc<str> = Arc::from(value);
+        shard.atoms.insert(atom.clone());
+        Self(atom)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Default for Atom {
+    fn default() -> Self {
+        Self::new("")
+    }
+}
+
+impl PartialEq for Atom {
+    fn eq(&self, other: &Self) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Atom {}
+
+impl PartialEq<str> for Atom {
+    fn eq(&self, other: &str) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<&str> for Atom {
+    fn eq(&self, other: &&str) -> bool {
+        self.as_str() == *other
+    }
+}
+
+impl PartialEq<String> for Atom {
+    fn eq(&self, other: &String) -> bool {
+        self.as_str() == other
+    }
+}
+
+impl PartialEq<Atom> for &str {
+    fn eq(&self, other: &Atom) -> bool {
+        *self == other.as_str()
+    }
+}
+
+impl PartialEq<Atom> for String {
+    fn eq(&self, other: &Atom) -> bool {
+        self == other.as_str()
+    }
+}
+
+/// Hashed like the string so maps of atoms can be queried with a `&str`
+impl Hash for Atom {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.as_str().hash(state);
+    }
+}
+
+impl PartialOrd for Atom {
+    fn partial_cmp(&self, other: &Self) -> Option<std::cmp::Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Atom {
+    fn cmp(&self, other: &Self) -> std::cmp::Ordering {
+        self.as_str().cmp(other.as_str())
+    }
+}
+
+impl Deref for Atom {
+    type Target = str;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl Borrow<str> for Atom {
+    fn borrow(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for Atom {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl From<&str> for Atom {
+    fn from(value: &str) -> Self {
+        Self::new(value)
+    }
+}
+
+impl From<String> for Atom {
+    fn from(value: String) -> Self {
+        Self::new(&value)
+    }
+}
+
+impl From<&String> for Atom {
+    fn from(value: &String) -> Self {
+        Self::new(value)
+    }
+}
+
+impl fmt::Debug for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Debug::fmt(self.as_str(), f)
+    }
+}
+
+impl fmt::Display for Atom {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        fmt::Display::fmt(self.as_str(), f)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashMap;
+
+    #[test]
+    fn intern_strings() {
+        let div = Atom::from("div");
+        let other_div = Atom::from("div".to_string());
+        assert_eq!(div, other_div);
+        assert!(Arc::ptr_eq(&div.0, &other_div.0));
+        assert_ne!(div, Atom::from("span"));
+
+        assert_eq!(div, "div");
+        assert_eq!("div", div);
+        assert_eq!(div.to_ascii_uppercase(), "DIV");
+
+        let mut map = HashMap::new();
+        map.insert(div, 1);
+        assert_eq!(map.get("div"), Some(&1));
+    }
+
+    #[test]
+    fn free_unused_atoms() {
+        let kept = Atom::from("kept-id");
+        for index in 0..100_000 {
+            Atom::from(format!("id-{}", index));
+        }
+        let len = SHARDS
+            .iter()
+            .map(|shard| shard.lock().unwrap().atoms.len())
+            .sum::<usize>();
+        // the shards only grow to twice the atoms in use between sweeps
+        assert!(len <= SHARD_COUNT * MIN_SWEEP_LEN * 2, "{} atoms kept", len);
+        assert!(Arc::ptr_eq(&kept.0, &Atom::from("kept-id").0));
+    }
+}
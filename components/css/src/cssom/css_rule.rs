@@ -1,6 +1,16 @@
+use super::keyframes_rule::KeyframesRule;
+use super::media_rule::MediaRule;
+use super::namespace_rule::NamespaceRule;
+use super::page_rule::PageRule;
 use super::style_rule::StyleRule;
+use super::supports_rule::SupportsRule;
 
 #[derive(Debug, PartialEq)]
 pub enum CSSRule {
     Style(StyleRule),
+    Keyframes(KeyframesRule),
+    Supports(SupportsRule),
+    Media(MediaRule),
+    Namespace(NamespaceRule),
+    Page(PageRule),
 }
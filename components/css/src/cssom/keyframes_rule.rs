@@ -0,0 +1,41 @@
+use crate::parser::structs::Declaration;
+use io::position::SourceSpan;
+
+/// A `@keyframes` rule, defining the values of the properties
+/// animated by the animations using its name
+/// https://www.w3.org/TR/css-animations-1/#keyframes
+#[derive(Debug, PartialEq)]
+pub struct KeyframesRule {
+    pub name: String,
+    pub keyframes: Vec<Keyframe>,
+    /// Where the rule is defined in the stylesheet source, if known
+    pub span: Option<SourceSpan>,
+}
+
+/// A keyframe block of a `@keyframes` rule
+#[derive(Debug, PartialEq)]
+pub struct Keyframe {
+    /// The offsets of the keyframe in the animation, between 0 & 1.
+    /// `from` is 0 and `to` is 1.
+    pub offsets: Vec<f32>,
+    pub declarations: Vec<Declaration>,
+}
+
+impl KeyframesRule {
+    pub fn new(name: String, keyframes: Vec<Keyframe>) -> Self {
+        Self {
+            name,
+            keyframes,
+            span: None,
+        }
+    }
+}
+
+impl Keyframe {
+    pub fn new(offsets: Vec<f32>, declarations: Vec<Declaration>) -> Self {
+        Self {
+            offsets,
+            declarations,
+        }
+    }
+}
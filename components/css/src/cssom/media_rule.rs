@@ -0,0 +1,128 @@
+use super::css_rule_list::CSSRuleList;
+use crate::parser::structs::ComponentValue;
+use io::position::SourceSpan;
+
+/// A `@media` rule, whose rules apply only if the environment the
+/// document is rendered in matches its media queries
+/// https://www.w3.org/TR/css-conditional-3/#at-media
+#[derive(Debug, PartialEq)]
+pub struct MediaRule {
+    pub queries: MediaQueryList,
+    pub css_rules: CSSRuleList,
+    /// Where the rule is defined in the stylesheet source, if known
+    pub span: Option<SourceSpan>,
+}
+
+/// The comma separated media queries of a `@media` rule, matching if
+/// any of them matches. An empty list matches every environment.
+/// https://www.w3.org/TR/mediaqueries-4/#mq-list
+#[derive(Debug, PartialEq)]
+pub struct MediaQueryList(pub Vec<MediaQuery>);
+
+/// A media query, e.g. `screen and (min-width: 600px)`
+#[derive(Debug, PartialEq)]
+pub struct MediaQuery {
+    /// Whether the query starts with `not`, negating the whole query
+    pub negated: bool,
+    pub media_type: MediaType,
+    pub condition: Option<MediaCondition>,
+}
+
+/// The type of device a media query is for. The documents are rendered
+/// for a screen.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MediaType {
+    All,
+    Screen,
+    Print,
+    /// The unknown & the deprecated media types, which never match
+    Unknown,
+}
+
+/// The condition of a media query on the features of the environment
+#[derive(Debug, PartialEq)]
+pub enum MediaCondition {
+    Not(Box<MediaCondition>),
+    And(Vec<MediaCondition>),
+    Or(Vec<MediaCondition>),
+    Feature(MediaFeature),
+    /// A condition in the syntax reserved for future extensions or in
+    /// the range syntax, always false
+    Unknown,
+}
+
+/// A media feature, `(name)` in a boolean context or `(name: value)`.
+/// The name is in lowercase, with its `min-` or `max-` prefix.
+#[derive(Debug, PartialEq)]
+pub struct MediaFeature {
+    pub name: String,
+    /// The value without whitespace
+    pub value: Option<Vec<ComponentValue>>,
+}
+
+impl MediaRule {
+    pub fn new(queries: MediaQueryList, css_rules: CSSRuleList) -> Self {
+        Self {
+            queries,
+            css_rules,
+            span: None,
+        }
+    }
+}
+
+impl MediaQueryList {
+    /// Whether any query of the list matches, evaluating the features
+    /// with the values of the environment
+    pub fn evaluate(&self, feature_matches: &dyn Fn(&MediaFeature) -> bool) -> bool {
+        self.0.is_empty() || self.0.iter().any(|query| query.evaluate(feature_matches))
+    }
+}
+
+impl MediaQuery {
+    /// The query of an invalid media query, which never matches
+    /// https://www.w3.org/TR/mediaqueries-4/#error-handling
+    pub fn not_all() -> Self {
+        Self {
+            negated: true,
+            media_type: MediaType::All,
+            condition: None,
+        }
+    }
+
+    pub fn evaluate(&self, feature_matches: &dyn Fn(&MediaFeature) -> bool) -> bool {
+        let type_matches = matches!(self.media_type, MediaType::All | MediaType::Screen);
+        let matches = type_matches
+            && self
+                .condition
+                .as_ref()
+                .map_or(true, |condition| condition.evaluate(feature_matches));
+        matches != self.negated
+    }
+}
+
+impl MediaCondition {
+    pub fn evaluate(&self, feature_matches: &dyn Fn(&MediaFeature) -> bool) -> bool {
+        match self {
+            MediaCondition::Not(condition) => !condition.evaluate(feature_matches),
+            MediaCondition::And(conditions) => conditions
+                .iter()
+                .all(|condition| condition.evaluate(feature_matches)),
+            MediaCondition::Or(conditions) => conditions
+                .iter()
+                .any(|condition| condition.evaluate(feature_matches)),
+            MediaCondition::Feature(feature) => feature_matches(feature),
+            MediaCondition::Unknown => false,
+        }
+    }
+}
+
+impl MediaType {
+    pub fn from_str(name: &str) -> Self {
+        match name.to_ascii_lowercase().as_str() {
+            "all" => MediaType::All,
+            "screen" => MediaType::Screen,
+            "print" => MediaType::Print,
+            _ => MediaType::Unknown,
+        }
+    }
+}
@@ -1,4 +1,9 @@
 pub mod css_rule;
 pub mod css_rule_list;
+pub mod keyframes_rule;
+pub mod media_rule;
+pub mod namespace_rule;
+pub mod page_rule;
 pub mod style_rule;
 pub mod stylesheet;
+pub mod supports_rule;
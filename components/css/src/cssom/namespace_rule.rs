@@ -0,0 +1,22 @@
+use io::position::SourceSpan;
+
+/// A `@namespace` rule, declaring the default namespace or the namespace
+/// of a prefix for the selectors of its stylesheet
+/// https://www.w3.org/TR/css-namespaces-3/#declaration
+#[derive(Debug, PartialEq)]
+pub struct NamespaceRule {
+    pub prefix: Option<String>,
+    pub url: String,
+    /// Where the rule is defined in the stylesheet source, if known
+    pub span: Option<SourceSpan>,
+}
+
+impl NamespaceRule {
+    pub fn new(prefix: Option<String>, url: String) -> Self {
+        Self {
+            prefix,
+            url,
+            span: None,
+        }
+    }
+}
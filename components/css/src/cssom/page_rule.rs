@@ -0,0 +1,25 @@
+use crate::parser::structs::Declaration;
+use io::position::SourceSpan;
+
+/// A `@page` rule, setting the size & the margins of the pages of a
+/// paginated document
+/// https://www.w3.org/TR/css-page-3/#at-page-rule
+#[derive(Debug, PartialEq)]
+pub struct PageRule {
+    /// The pseudo-classes of the page selector, e.g. `first` for
+    /// `@page :first`. The rule applies to every page when empty.
+    pub pseudo_classes: Vec<String>,
+    pub declarations: Vec<Declaration>,
+    /// Where the rule is defined in the stylesheet source, if known
+    pub span: Option<SourceSpan>,
+}
+
+impl PageRule {
+    pub fn new(pseudo_classes: Vec<String>, declarations: Vec<Declaration>) -> Self {
+        Self {
+            pseudo_classes,
+            declarations,
+            span: None,
+        }
+    }
+}
@@ -1,10 +1,13 @@
 use crate::parser::structs::Declaration;
 use crate::selector::structs::{Selector, Specificity};
+use io::position::SourceSpan;
 
 #[derive(Debug, PartialEq)]
 pub struct StyleRule {
     pub selectors: Vec<Selector>,
     pub declarations: Vec<Declaration>,
+    /// Where the rule is defined in the stylesheet source, if known
+    pub span: Option<SourceSpan>,
 }
 
 impl StyleRule {
@@ -12,6 +15,7 @@ impl StyleRule {
         Self {
             selectors,
             declarations,
+            span: None,
         }
     }
 
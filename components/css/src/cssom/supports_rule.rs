@@ -0,0 +1,57 @@
+use super::css_rule_list::CSSRuleList;
+use crate::parser::structs::Declaration;
+use io::position::SourceSpan;
+
+/// A `@supports` rule, whose rules apply only if the engine
+/// supports its condition
+/// https://www.w3.org/TR/css-conditional-3/#at-supports
+#[derive(Debug, PartialEq)]
+pub struct SupportsRule {
+    pub condition: SupportsCondition,
+    pub css_rules: CSSRuleList,
+    /// Where the rule is defined in the stylesheet source, if known
+    pub span: Option<SourceSpan>,
+}
+
+/// The condition of a `@supports` rule
+#[derive(Debug, PartialEq)]
+pub enum SupportsCondition {
+    Not(Box<SupportsCondition>),
+    And(Vec<SupportsCondition>),
+    Or(Vec<SupportsCondition>),
+    /// `(property: value)`, true if the property accepts the value
+    Declaration(Declaration),
+    /// `selector(...)`, true if the selector is valid
+    Selector(bool),
+    /// A condition in the syntax reserved for future extensions, always false
+    Unknown,
+}
+
+impl SupportsRule {
+    pub fn new(condition: SupportsCondition, css_rules: CSSRuleList) -> Self {
+        Self {
+            condition,
+            css_rules,
+            span: None,
+        }
+    }
+}
+
+impl SupportsCondition {
+    /// Evaluate the condition, checking the declarations with
+    /// the property parsing of the engine
+    pub fn evaluate(&self, supports_declaration: &dyn Fn(&Declaration) -> bool) -> bool {
+        match self {
+            SupportsCondition::Not(condition) => !condition.evaluate(supports_declaration),
+            SupportsCondition::And(conditions) => conditions
+                .iter()
+                .all(|condition| condition.evaluate(supports_declaration)),
+            SupportsCondition::Or(conditions) => conditions
+                .iter()
+                .any(|condition| condition.evaluate(supports_declaration)),
+            SupportsCondition::Declaration(declaration) => supports_declaration(declaration),
+            SupportsCondition::Selector(supported) => *supported,
+            SupportsCondition::Unknown => false,
+        }
+    }
+}
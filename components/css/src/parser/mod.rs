@@ -1,34 +1,25 @@
 pub mod structs;
 
 use super::cssom::css_rule::CSSRule;
+use super::cssom::css_rule_list::CSSRuleList;
+use super::cssom::keyframes_rule::{Keyframe, KeyframesRule};
+use super::cssom::media_rule::{
+    MediaCondition, MediaFeature, MediaQuery, MediaQueryList, MediaRule, MediaType,
+};
+use super::cssom::namespace_rule::NamespaceRule;
+use super::cssom::page_rule::PageRule;
 use super::cssom::style_rule::StyleRule;
 use super::cssom::stylesheet::StyleSheet;
-use super::selector::parse_selectors;
+use super::cssom::supports_rule::{SupportsCondition, SupportsRule};
+use super::selector::parse_selectors_with_namespaces;
+use super::selector::structs::NamespaceMap;
 use super::tokenizer::token::Token;
+use atom::Atom;
 use io::data_stream::DataStream;
-use std::env;
+use io::parse_error::{ParseError, ParseErrorSinkRef};
+use io::position::SourceSpan;
 use structs::*;
-
-fn is_trace() -> bool {
-    match env::var("TRACE_CSS_PARSER") {
-        Ok(s) => s == "true" || s == "",
-        _ => false,
-    }
-}
-
-macro_rules! trace {
-    ($err:expr) => {
-        println!("[ParseError][CSS Parsing]: {}", $err);
-    };
-}
-
-macro_rules! emit_error {
-    ($err:expr) => {
-        if is_trace() {
-            trace!($err)
-        }
-    };
-}
+use url::Url;
 
 pub struct SyntaxError;
 
@@ -49,10 +40,84 @@ pub struct Parser<T: Clone> {
     tokens: DataStream<T>,
     /// Top level flag
     top_level: bool,
-    /// Reconsume current input token
-    reconsume: bool,
-    /// Current token to return if being reconsumed
-    current_token: Option<T>,
+    /// Where to report parse errors. Errors are dropped if not set
+    error_sink: Option<ParseErrorSinkRef>,
+    /// Source spans of the tokens, if known
+    token_spans: Vec<SourceSpan>,
+    /// The URL the relative URLs of the `url()` values are resolved against
+    base_url: Option<Url>,
+}
+
+impl<T: Clone> Parser<T> {
+    pub fn set_error_sink(&mut self, sink: ParseErrorSinkRef) {
+        self.error_sink = Some(sink);
+    }
+
+    /// Set the source spans of the tokens, as returned by `Tokenizer::run_with_spans`
+    pub fn set_token_spans(&mut self, spans: Vec<SourceSpan>) {
+        self.token_spans = spans;
+    }
+
+    /// Resolve the `url()` values of the declarations against a URL, the
+    /// URL of the stylesheet or the base URL of its document
+    pub fn set_base_url(&mut self, url: Url) {
+        self.base_url = Some(url);
+    }
+
+    /// Turn the relative URLs of the `url()` values of a declaration into
+    /// absolute URLs. The URLs are kept as is without a base URL.
+    /// https://www.w3.org/TR/css-values-4/#relative-urls
+    fn resolve_urls(&self, values: &mut [ComponentValue]) {
+        let base = match &self.base_url {
+            Some(base) => base,
+            None => return,
+        };
+        for value in values {
+            match value {
+                ComponentValue::PerservedToken(Token::Url(url)) => resolve_url(base, url),
+                ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("url") => {
+                    for argument in &mut function.value {
+                        if let ComponentValue::PerservedToken(Token::Str(url)) = argument {
+                            resolve_url(base, url);
+                        }
+                    }
+                }
+                ComponentValue::Function(function) => self.resolve_urls(&mut function.value),
+                ComponentValue::SimpleBlock(block) => self.resolve_urls(&mut block.value),
+                _ => {}
+            }
+        }
+    }
+
+    /// The source span of the last consumed token
+    fn current_token_span(&self) -> Option<SourceSpan> {
+        let index = self.tokens.index().checked_sub(1)?;
+        self.token_spans.get(index).cloned()
+    }
+
+    fn emit_error(&self, code: &'static str, message: &str) {
+        let position = self.current_token_span().map(|span| span.start);
+        match position {
+            Some(position) => log::debug!("{}: {} ({})", position, message, code),
+            None => log::debug!("{} ({})", message, code),
+        }
+        if let Some(sink) = &self.error_sink {
+            let mut error = ParseError::new("css-parser", code, message);
+            error.position = position;
+            sink.borrow_mut().report(error);
+        }
+    }
+
+    /// Create a parser for a nested list of tokens, sharing the error sink
+    fn nested<U: Clone>(&self, tokens: DataStream<U>) -> Parser<U> {
+        Parser {
+            tokens,
+            top_level: false,
+            error_sink: self.error_sink.clone(),
+            token_spans: Vec::new(),
+            base_url: self.base_url.clone(),
+        }
+    }
 }
 
 impl Parser<Token> {
@@ -60,61 +125,67 @@ impl Parser<Token> {
         Self {
             tokens,
             top_level: false,
-            reconsume: false,
-            current_token: None,
+            error_sink: None,
+            token_spans: Vec::new(),
+            base_url: None,
         }
     }
 
+    /// Move the next token out of the stream. The tokens are consumed
+    /// once, so they are never cloned
     fn consume_next_token(&mut self) -> Token {
-        if self.reconsume {
-            self.reconsume = false;
-            return self.current_token.clone().unwrap();
-        }
-        let token = self.tokens.next().unwrap_or(&Token::EOF);
-        self.current_token = Some(token.clone());
-        return token.clone();
+        self.tokens.take_next(Token::EOF).unwrap_or(Token::EOF)
     }
 
-    fn peek_next_token(&mut self) -> Token {
-        if self.reconsume {
-            return self.current_token.clone().unwrap();
-        }
-        return self.tokens.peek().unwrap_or(&Token::EOF).clone();
+    fn peek_next_token(&self) -> &Token {
+        self.tokens.peek().unwrap_or(&Token::EOF)
     }
 
-    fn reconsume(&mut self) {
-        self.reconsume = true;
+    /// The source span of the token that will be returned by `consume_next_token`
+    fn peek_token_span(&self) -> Option<SourceSpan> {
+        self.token_spans.get(self.tokens.index()).cloned()
+    }
+
+    /// The span from the start of `start` to the end of the last consumed token
+    fn span_from(&self, start: Option<SourceSpan>) -> Option<SourceSpan> {
+        match (start, self.current_token_span()) {
+            (Some(start), Some(end)) => Some(SourceSpan::new(start.start, end.end)),
+            _ => None,
+        }
     }
 
-    fn ending_token(&self) -> Token {
-        match self.current_token {
-            Some(Token::BracketOpen) => Token::BracketClose,
-            Some(Token::BraceOpen) => Token::BraceClose,
-            Some(Token::ParentheseOpen) => Token::ParentheseClose,
-            _ => panic!("Can't get ending token"),
+    fn ending_token(opening_token: &Token) -> Option<Token> {
+        match opening_token {
+            Token::BracketOpen => Some(Token::BracketClose),
+            Token::BraceOpen => Some(Token::BraceClose),
+            Token::ParentheseOpen => Some(Token::ParentheseClose),
+            _ => None,
         }
     }
 
     fn consume_a_qualified_rule(&mut self) -> Option<QualifiedRule> {
         let mut qualified_rule = QualifiedRule::new();
+        let start = self.peek_token_span();
 
         loop {
-            let next_token = self.consume_next_token();
-
-            if let Token::EOF = next_token {
-                emit_error!("Unexpected EOF while consuming a qualified rule");
-                return None;
-            }
-
-            if let Token::BraceOpen = next_token {
-                qualified_rule.set_block(self.consume_a_simple_block());
-                return Some(qualified_rule);
+            match self.peek_next_token() {
+                Token::EOF => {
+                    self.consume_next_token();
+                    self.emit_error(
+                        "eof-in-qualified-rule",
+                        "Unexpected EOF while consuming a qualified rule",
+                    );
+                    return None;
+                }
+                Token::BraceOpen => {
+                    let opening_token = self.consume_next_token();
+                    qualified_rule.set_block(self.consume_a_simple_block(opening_token));
+                    qualified_rule.span = self.span_from(start);
+                    return Some(qualified_rule);
+                }
+                // TODO: What is simple block with an associated token of <{-token>? How is it a token?
+                _ => qualified_rule.append_prelude(self.consume_a_component_value()),
             }
-
-            // TODO: What is simple block with an associated token of <{-token>? How is it a token?
-
-            self.reconsume();
-            qualified_rule.append_prelude(self.consume_a_component_value());
         }
     }
 
@@ -123,10 +194,10 @@ impl Parser<Token> {
 
         match token {
             Token::BraceOpen | Token::BracketOpen | Token::ParentheseOpen => {
-                return ComponentValue::SimpleBlock(self.consume_a_simple_block());
+                return ComponentValue::SimpleBlock(self.consume_a_simple_block(token));
             }
-            Token::Function(_) => {
-                return ComponentValue::Function(self.consume_a_function());
+            Token::Function(name) => {
+                return ComponentValue::Function(self.consume_a_function(name));
             }
             t => ComponentValue::PerservedToken(t),
         }
@@ -136,20 +207,20 @@ impl Parser<Token> {
         let mut result = Vec::new();
 
         loop {
-            let next_token = self.consume_next_token();
-
-            match next_token {
-                Token::Whitespace | Token::Semicolon => {}
+            match self.peek_next_token() {
+                Token::Whitespace | Token::Semicolon => {
+                    self.consume_next_token();
+                }
                 Token::EOF => {
+                    self.consume_next_token();
                     return result;
                 }
                 Token::AtKeyword(_) => {
-                    self.reconsume();
                     let rule = self.consume_an_at_rule();
                     result.push(DeclarationOrAtRule::AtRule(rule));
                 }
                 Token::Ident(_) => {
-                    let mut tmp = vec![self.current_token.clone().unwrap()];
+                    let mut tmp = vec![self.consume_next_token()];
                     loop {
                         match self.peek_next_token() {
                             Token::Semicolon | Token::EOF => break,
@@ -162,14 +233,18 @@ impl Parser<Token> {
                             }
                         }
                     }
-                    let mut parser = Parser::<Token>::new(DataStream::new(tmp));
+                    let mut parser = self.nested(DataStream::new(tmp));
                     if let Some(declaration) = parser.consume_a_declaration() {
                         result.push(DeclarationOrAtRule::Declaration(declaration));
                     }
                 }
                 _ => {
-                    emit_error!("Unexpected token while consuming a list of declarations");
-                    self.reconsume();
+                    // throw away the declaration
+                    self.consume_a_component_value();
+                    self.emit_error(
+                        "invalid-declaration-start",
+                        "Unexpected token while consuming a list of declarations",
+                    );
                     loop {
                         match self.peek_next_token() {
                             Token::Semicolon | Token::EOF => break,
@@ -184,86 +259,100 @@ impl Parser<Token> {
         }
     }
 
-    fn consume_a_function(&mut self) -> Function {
-        let current_token = self.current_token.clone().unwrap();
-        let function_name = if let Token::Function(name) = current_token {
-            name
-        } else {
-            panic!("The current token is not a function");
-        };
-
+    fn consume_a_function(&mut self, function_name: String) -> Function {
         let mut function = Function::new(function_name);
 
-        // consume `(`
-        self.consume_next_token();
+        // consume `(`, which the tokenizer already consumed for `url("`
+        if let Token::ParentheseOpen = self.peek_next_token() {
+            self.consume_next_token();
+        }
         loop {
-            let next_token = self.consume_next_token();
-
-            match next_token {
+            match self.peek_next_token() {
                 Token::ParentheseClose => {
+                    self.consume_next_token();
                     return function;
                 }
                 Token::EOF => {
-                    emit_error!("Unexpected EOF while consuming a function");
+                    self.consume_next_token();
+                    self.emit_error(
+                        "eof-in-function",
+                        "Unexpected EOF while consuming a function",
+                    );
                     return function;
                 }
-                _ => {
-                    self.reconsume();
-                    function.append_value(self.consume_a_component_value());
-                }
+                _ => function.append_value(self.consume_a_component_value()),
             }
         }
     }
 
-    fn consume_a_simple_block(&mut self) -> SimpleBlock {
-        let ending_token = self.ending_token();
-        let mut simple_block = SimpleBlock::new(self.current_token.clone().unwrap());
+    fn consume_a_simple_block(&mut self, opening_token: Token) -> SimpleBlock {
+        let ending_token = Self::ending_token(&opening_token);
+        let mut simple_block = SimpleBlock::new(opening_token);
+        let ending_token = match ending_token {
+            Some(token) => token,
+            None => {
+                self.emit_error(
+                    "invalid-simple-block-start",
+                    "A simple block must start with a bracket, brace or parenthesis",
+                );
+                return simple_block;
+            }
+        };
 
         loop {
-            let next_token = self.consume_next_token();
-
-            if next_token == ending_token {
+            if *self.peek_next_token() == ending_token {
+                self.consume_next_token();
                 return simple_block;
             }
 
-            if let Token::EOF = next_token {
-                emit_error!("Unexpected EOF while consuming a simple block");
+            if let Token::EOF = self.peek_next_token() {
+                self.consume_next_token();
+                self.emit_error(
+                    "eof-in-simple-block",
+                    "Unexpected EOF while consuming a simple block",
+                );
                 return simple_block;
             }
 
-            self.reconsume();
             simple_block.append_value(self.consume_a_component_value());
         }
     }
 
     fn consume_an_at_rule(&mut self) -> AtRule {
-        self.consume_next_token();
-        let current_token = self.current_token.clone().unwrap();
-        let keyword_name = if let Token::AtKeyword(name) = current_token {
-            name
-        } else {
-            panic!("The current token is not a function");
+        let start = self.peek_token_span();
+        let keyword_name = match self.consume_next_token() {
+            Token::AtKeyword(name) => name,
+            _ => {
+                self.emit_error("expected-at-keyword", "Expected an at-keyword");
+                String::new()
+            }
         };
         let mut at_rule = AtRule::new(keyword_name);
 
         loop {
-            let next_token = self.consume_next_token();
-
-            match next_token {
-                Token::Semicolon => return at_rule,
+            match self.peek_next_token() {
+                Token::Semicolon => {
+                    self.consume_next_token();
+                    at_rule.span = self.span_from(start);
+                    return at_rule;
+                }
                 Token::EOF => {
-                    emit_error!("Unexpected EOF while consuming an at-rule");
+                    self.consume_next_token();
+                    self.emit_error(
+                        "eof-in-at-rule",
+                        "Unexpected EOF while consuming an at-rule",
+                    );
+                    at_rule.span = self.span_from(start);
                     return at_rule;
                 }
                 Token::BraceOpen => {
-                    at_rule.set_block(self.consume_a_simple_block());
+                    let opening_token = self.consume_next_token();
+                    at_rule.set_block(self.consume_a_simple_block(opening_token));
+                    at_rule.span = self.span_from(start);
                     return at_rule;
                 }
                 // TODO: How is a simple block a token?
-                _ => {
-                    self.reconsume();
-                    at_rule.append_prelude(self.consume_a_component_value());
-                }
+                _ => at_rule.append_prelude(self.consume_a_component_value()),
             }
         }
     }
@@ -271,26 +360,19 @@ impl Parser<Token> {
     fn consume_a_list_of_rules(&mut self) -> ListOfRules {
         let mut rules = Vec::new();
         loop {
-            let next_token = self.consume_next_token();
-            match next_token {
-                Token::Whitespace => continue,
+            match self.peek_next_token() {
+                Token::Whitespace => {
+                    self.consume_next_token();
+                }
                 Token::EOF => return rules,
-                Token::CDO | Token::CDC => {
-                    if self.top_level {
-                        continue;
-                    }
-                    self.reconsume();
-                    if let Some(rule) = self.consume_a_qualified_rule() {
-                        rules.push(Rule::QualifiedRule(rule));
-                    }
+                Token::CDO | Token::CDC if self.top_level => {
+                    self.consume_next_token();
                 }
                 Token::AtKeyword(_) => {
-                    self.reconsume();
                     let at_rule = self.consume_an_at_rule();
                     rules.push(Rule::AtRule(at_rule));
                 }
                 _ => {
-                    self.reconsume();
                     if let Some(rule) = self.consume_a_qualified_rule() {
                         rules.push(Rule::QualifiedRule(rule));
                     }
@@ -304,7 +386,8 @@ impl Parser<Token> {
         let declaration_name = if let Token::Ident(name) = next_token {
             name
         } else {
-            panic!("Token is not a indent token");
+            self.emit_error("expected-ident", "Expected an identifier in declaration");
+            return None;
         };
         let mut declaration = Declaration::new(declaration_name);
         self.consume_while_next_token_is(Token::Whitespace);
@@ -314,7 +397,10 @@ impl Parser<Token> {
                 self.consume_next_token();
             }
             _ => {
-                emit_error!("Expected Colon in declaration");
+                self.emit_error(
+                    "missing-colon-in-declaration",
+                    "Expected Colon in declaration",
+                );
                 return None;
             }
         }
@@ -346,11 +432,12 @@ impl Parser<Token> {
             declaration.pop_last(1);
         }
 
+        self.resolve_urls(&mut declaration.value);
         return Some(declaration);
     }
 
     fn consume_while_next_token_is(&mut self, token: Token) {
-        while self.peek_next_token() == token {
+        while *self.peek_next_token() == token {
             self.consume_next_token();
         }
     }
@@ -364,39 +451,480 @@ impl Parser<Token> {
     }
 
     pub fn parse_a_css_stylesheet(&mut self) -> StyleSheet {
-        let mut stylesheet = StyleSheet::new();
-        let rules = self.parse_a_stylesheet();
+        let mut rules = self.parse_a_stylesheet().into_iter().peekable();
+        let mut css_rules = CSSRuleList::new();
+        let mut namespaces = NamespaceMap::new();
+
+        // the @namespace rules are valid only before the other rules
+        while let Some(Rule::AtRule(rule)) = rules.next_if(|rule| {
+            matches!(rule, Rule::AtRule(rule) if rule.name.eq_ignore_ascii_case("namespace"))
+        }) {
+            if let Some(namespace_rule) = self.parse_namespace_rule(rule) {
+                namespaces.declare(
+                    namespace_rule.prefix.as_deref().map(Atom::from),
+                    Atom::from(namespace_rule.url.as_str()),
+                );
+                css_rules.append_rule(CSSRule::Namespace(namespace_rule));
+            }
+        }
+
+        let other_rules = self.parse_css_rules(rules.collect(), &namespaces);
+        css_rules.0.extend(other_rules.0);
+        StyleSheet { css_rules }
+    }
+
+    /// Turn the rules of a stylesheet or of the block of a group rule
+    /// into CSS rules, dropping the invalid & unsupported rules
+    fn parse_css_rules(&self, rules: ListOfRules, namespaces: &NamespaceMap) -> CSSRuleList {
+        let mut css_rules = CSSRuleList::new();
         for rule in rules {
-            if let Rule::QualifiedRule(rule) = rule {
-                let selectors = parse_selectors(&rule.prelude);
-                if selectors.len() == 0 {
-                    // invalid rule
-                    continue;
-                }
-                let content = if let Some(block) = rule.block {
-                    let mut parser =
-                        Parser::<ComponentValue>::new(DataStream::new(block.value.clone()));
-
-                    let declarations = parser.parse_a_list_of_declarations();
-
-                    // take only declaration
-                    declarations
-                        .into_iter()
-                        .filter_map(|declaration| match declaration {
-                            DeclarationOrAtRule::Declaration(d) => Some(d),
-                            _ => None,
-                        })
-                        .collect()
-                } else {
-                    Vec::new()
-                };
-                let style_rule = StyleRule::new(selectors, content);
-                stylesheet.append_rule(CSSRule::Style(style_rule));
-            } else {
-                continue;
+            match rule {
+                Rule::QualifiedRule(rule) => {
+                    let selectors = parse_selectors_with_namespaces(&rule.prelude, namespaces);
+                    if selectors.is_empty() {
+                        // invalid rule
+                        continue;
+                    }
+                    let content = match rule.block {
+                        Some(block) => self.parse_block_declarations(block),
+                        None => Vec::new(),
+                    };
+                    let mut style_rule = StyleRule::new(selectors, content);
+                    style_rule.span = rule.span;
+                    css_rules.append_rule(CSSRule::Style(style_rule));
+                }
+                Rule::AtRule(rule) if rule.name.eq_ignore_ascii_case("keyframes") => {
+                    if let Some(keyframes_rule) = self.parse_keyframes_rule(rule) {
+                        css_rules.append_rule(CSSRule::Keyframes(keyframes_rule));
+                    }
+                }
+                Rule::AtRule(rule) if rule.name.eq_ignore_ascii_case("supports") => {
+                    if let Some(supports_rule) = self.parse_supports_rule(rule, namespaces) {
+                        css_rules.append_rule(CSSRule::Supports(supports_rule));
+                    }
+                }
+                Rule::AtRule(rule) if rule.name.eq_ignore_ascii_case("media") => {
+                    if let Some(media_rule) = self.parse_media_rule(rule, namespaces) {
+                        css_rules.append_rule(CSSRule::Media(media_rule));
+                    }
+                }
+                Rule::AtRule(rule) if rule.name.eq_ignore_ascii_case("page") => {
+                    if let Some(page_rule) = self.parse_page_rule(rule) {
+                        css_rules.append_rule(CSSRule::Page(page_rule));
+                    }
+                }
+                Rule::AtRule(rule) if rule.name.eq_ignore_ascii_case("namespace") => {
+                    self.emit_error(
+                        "misplaced-namespace-rule",
+                        "A @namespace rule must precede the other rules",
+                    );
+                }
+                _ => continue,
+            }
+        }
+        css_rules
+    }
+
+    /// Parse the declarations in the block of a rule, ignoring the nested at-rules
+    fn parse_block_declarations(&self, block: SimpleBlock) -> Vec<Declaration> {
+        let mut parser = self.nested(DataStream::new(block.value));
+
+        let declarations = parser.parse_a_list_of_declarations();
+
+        // take only declaration
+        declarations
+            .into_iter()
+            .filter_map(|declaration| match declaration {
+                DeclarationOrAtRule::Declaration(d) => Some(d),
+                _ => None,
+            })
+            .collect()
+    }
+
+    /// Parse `@keyframes <name> { <keyframe-block-list> }`
+    /// https://www.w3.org/TR/css-animations-1/#keyframes
+    fn parse_keyframes_rule(&self, rule: AtRule) -> Option<KeyframesRule> {
+        let prelude = rule
+            .prelude
+            .iter()
+            .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+            .collect::<Vec<_>>();
+        let name = match prelude.as_slice() {
+            [ComponentValue::PerservedToken(Token::Ident(name))]
+                if !name.eq_ignore_ascii_case("none") =>
+            {
+                name.to_string()
+            }
+            [ComponentValue::PerservedToken(Token::Str(name))] => name.clone(),
+            _ => {
+                self.emit_error(
+                    "invalid-keyframes-name",
+                    "Invalid name of a @keyframes rule",
+                );
+                return None;
+            }
+        };
+        let block = rule.block?;
+
+        let mut parser = self.nested(DataStream::new(block.value));
+        let keyframes = parser
+            .parse_a_list_of_rules()
+            .into_iter()
+            .filter_map(|rule| match rule {
+                Rule::QualifiedRule(rule) => {
+                    // an invalid keyframe selector invalidates only its block
+                    let offsets = parse_keyframe_selectors(&rule.prelude)?;
+                    let declarations = match rule.block {
+                        Some(block) => self.parse_block_declarations(block),
+                        None => Vec::new(),
+                    };
+                    Some(Keyframe::new(offsets, declarations))
+                }
+                _ => None,
+            })
+            .collect();
+
+        let mut keyframes_rule = KeyframesRule::new(name, keyframes);
+        keyframes_rule.span = rule.span;
+        Some(keyframes_rule)
+    }
+
+    /// Parse `@page <page-selector-list>? { <declaration-list> }`, with
+    /// a page selector made of pseudo-classes only
+    /// https://www.w3.org/TR/css-page-3/#syntax-page-selector
+    fn parse_page_rule(&self, rule: AtRule) -> Option<PageRule> {
+        let mut pseudo_classes = Vec::new();
+        let mut prelude = rule
+            .prelude
+            .iter()
+            .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace));
+        while let Some(value) = prelude.next() {
+            match (value, prelude.next()) {
+                (
+                    ComponentValue::PerservedToken(Token::Colon),
+                    Some(ComponentValue::PerservedToken(Token::Ident(name))),
+                ) => pseudo_classes.push(name.to_ascii_lowercase()),
+                _ => {
+                    self.emit_error("invalid-page-selector", "Invalid selector of a @page rule");
+                    return None;
+                }
+            }
+        }
+        let declarations = match rule.block {
+            Some(block) => self.parse_block_declarations(block),
+            None => Vec::new(),
+        };
+
+        let mut page_rule = PageRule::new(pseudo_classes, declarations);
+        page_rule.span = rule.span;
+        Some(page_rule)
+    }
+
+    /// Parse `@namespace <prefix>? [ <string> | <url> ];`
+    /// https://www.w3.org/TR/css-namespaces-3/#syntax
+    fn parse_namespace_rule(&self, rule: AtRule) -> Option<NamespaceRule> {
+        let prelude = rule
+            .prelude
+            .iter()
+            .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+            .collect::<Vec<_>>();
+        let (prefix, url) = match prelude.as_slice() {
+            [url] => (None, parse_namespace_url(url)),
+            [ComponentValue::PerservedToken(Token::Ident(prefix)), url] => {
+                (Some(prefix.to_string()), parse_namespace_url(url))
+            }
+            _ => (None, None),
+        };
+        match url {
+            Some(url) if rule.block.is_none() => {
+                let mut namespace_rule = NamespaceRule::new(prefix, url);
+                namespace_rule.span = rule.span;
+                Some(namespace_rule)
+            }
+            _ => {
+                self.emit_error("invalid-namespace-rule", "Invalid @namespace rule");
+                None
+            }
+        }
+    }
+
+    /// Parse `@supports <supports-condition> { <rule-list> }`
+    /// https://www.w3.org/TR/css-conditional-3/#at-supports
+    fn parse_supports_rule(&self, rule: AtRule, namespaces: &NamespaceMap) -> Option<SupportsRule> {
+        let condition = match self.parse_supports_condition(&rule.prelude, namespaces) {
+            Some(condition) => condition,
+            None => {
+                self.emit_error(
+                    "invalid-supports-condition",
+                    "Invalid condition of a @supports rule",
+                );
+                return None;
+            }
+        };
+        let block = rule.block?;
+
+        let rules = self
+            .nested(DataStream::new(block.value))
+            .parse_a_list_of_rules();
+        let mut supports_rule =
+            SupportsRule::new(condition, self.parse_css_rules(rules, namespaces));
+        supports_rule.span = rule.span;
+        Some(supports_rule)
+    }
+
+    /// Parse `not <supports-in-parens>`, or `<supports-in-parens>`
+    /// joined by either `and` or `or`
+    fn parse_supports_condition(
+        &self,
+        values: &[ComponentValue],
+        namespaces: &NamespaceMap,
+    ) -> Option<SupportsCondition> {
+        let values = values
+            .iter()
+            .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+            .collect::<Vec<_>>();
+        let is_keyword = |value: &ComponentValue, keyword: &str| match value {
+            ComponentValue::PerservedToken(Token::Ident(ident)) => {
+                ident.eq_ignore_ascii_case(keyword)
+            }
+            _ => false,
+        };
+
+        let (first, rest) = values.split_first()?;
+        if is_keyword(first, "not") {
+            return match rest {
+                [condition] => Some(SupportsCondition::Not(Box::new(
+                    self.parse_supports_in_parens(condition, namespaces)?,
+                ))),
+                _ => None,
+            };
+        }
+
+        let first = self.parse_supports_in_parens(first, namespaces)?;
+        let operator = match rest.first() {
+            Some(value) if is_keyword(value, "and") => "and",
+            Some(value) if is_keyword(value, "or") => "or",
+            Some(_) => return None,
+            None => return Some(first),
+        };
+        let mut conditions = vec![first];
+        for pair in rest.chunks(2) {
+            match pair {
+                [keyword, condition] if is_keyword(keyword, operator) => {
+                    conditions.push(self.parse_supports_in_parens(condition, namespaces)?)
+                }
+                _ => return None,
+            }
+        }
+        match operator {
+            "and" => Some(SupportsCondition::And(conditions)),
+            _ => Some(SupportsCondition::Or(conditions)),
+        }
+    }
+
+    /// Parse a nested condition, a declaration, a `selector()` function
+    /// or anything else in parentheses, which is never supported
+    fn parse_supports_in_parens(
+        &self,
+        value: &ComponentValue,
+        namespaces: &NamespaceMap,
+    ) -> Option<SupportsCondition> {
+        match value {
+            ComponentValue::SimpleBlock(block) if block.token == Token::ParentheseOpen => {
+                if let Some(condition) = self.parse_supports_condition(&block.value, namespaces) {
+                    return Some(condition);
+                }
+                let mut values = block
+                    .value
+                    .iter()
+                    .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace));
+                let is_declaration = matches!(
+                    (values.next(), values.next()),
+                    (
+                        Some(ComponentValue::PerservedToken(Token::Ident(_))),
+                        Some(ComponentValue::PerservedToken(Token::Colon))
+                    )
+                );
+                if !is_declaration {
+                    return Some(SupportsCondition::Unknown);
+                }
+                let mut parser = self.nested(DataStream::new(block.value.clone()));
+                parser.consume_while_next_token_is(Token::Whitespace);
+                parser
+                    .consume_a_declaration()
+                    .map(SupportsCondition::Declaration)
+            }
+            ComponentValue::Function(function)
+                if function.name.eq_ignore_ascii_case("selector") =>
+            {
+                let selectors = parse_selectors_with_namespaces(&function.value, namespaces);
+                Some(SupportsCondition::Selector(selectors.len() == 1))
+            }
+            ComponentValue::Function(_) => Some(SupportsCondition::Unknown),
+            _ => None,
+        }
+    }
+
+    /// Parse `@media <media-query-list> { <rule-list> }`
+    /// https://www.w3.org/TR/css-conditional-3/#at-media
+    fn parse_media_rule(&self, rule: AtRule, namespaces: &NamespaceMap) -> Option<MediaRule> {
+        let block = rule.block?;
+        let rules = self
+            .nested(DataStream::new(block.value))
+            .parse_a_list_of_rules();
+        let mut media_rule = MediaRule::new(
+            self.parse_media_query_list(&rule.prelude),
+            self.parse_css_rules(rules, namespaces),
+        );
+        media_rule.span = rule.span;
+        Some(media_rule)
+    }
+
+    /// Parse the comma separated media queries. The invalid queries are
+    /// replaced by `not all`, without dropping the others.
+    fn parse_media_query_list(&self, values: &[ComponentValue]) -> MediaQueryList {
+        let values = values
+            .iter()
+            .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+            .collect::<Vec<_>>();
+        if values.is_empty() {
+            return MediaQueryList(Vec::new());
+        }
+        MediaQueryList(
+            values
+                .split(|value| **value == ComponentValue::PerservedToken(Token::Comma))
+                .map(|query| {
+                    self.parse_media_query(query).unwrap_or_else(|| {
+                        self.emit_error("invalid-media-query", "Invalid media query");
+                        MediaQuery::not_all()
+                    })
+                })
+                .collect(),
+        )
+    }
+
+    /// Parse a media condition, or `[not | only]? <media-type>` followed
+    /// by `and <media-condition-without-or>`
+    /// https://www.w3.org/TR/mediaqueries-4/#mq-syntax
+    fn parse_media_query(&self, values: &[&ComponentValue]) -> Option<MediaQuery> {
+        let ident = |value: Option<&&ComponentValue>| match value {
+            Some(ComponentValue::PerservedToken(Token::Ident(ident))) => {
+                Some(ident.to_ascii_lowercase())
+            }
+            _ => None,
+        };
+
+        let (negated, values) = match ident(values.first()).as_deref() {
+            Some("not") if ident(values.get(1)).is_some() => (true, &values[1..]),
+            Some("only") => (false, &values[1..]),
+            Some(_) => (false, values),
+            None => {
+                return Some(MediaQuery {
+                    negated: false,
+                    media_type: MediaType::All,
+                    condition: Some(self.parse_media_condition(values, true)?),
+                })
+            }
+        };
+
+        let media_type = match ident(values.first()).as_deref() {
+            Some("not") | Some("only") | Some("and") | Some("or") | None => return None,
+            Some(name) => MediaType::from_str(name),
+        };
+        let condition = match &values[1..] {
+            [] => None,
+            [keyword, condition @ ..] if ident(Some(keyword)).as_deref() == Some("and") => {
+                Some(self.parse_media_condition(condition, false)?)
+            }
+            _ => return None,
+        };
+        Some(MediaQuery {
+            negated,
+            media_type,
+            condition,
+        })
+    }
+
+    /// Parse `not <media-in-parens>`, or `<media-in-parens>` joined by
+    /// either `and` or `or`
+    fn parse_media_condition(
+        &self,
+        values: &[&ComponentValue],
+        allow_or: bool,
+    ) -> Option<MediaCondition> {
+        let is_keyword = |value: &ComponentValue, keyword: &str| match value {
+            ComponentValue::PerservedToken(Token::Ident(ident)) => {
+                ident.eq_ignore_ascii_case(keyword)
+            }
+            _ => false,
+        };
+
+        let (first, rest) = values.split_first()?;
+        if is_keyword(first, "not") {
+            return match rest {
+                [condition] => Some(MediaCondition::Not(Box::new(
+                    self.parse_media_in_parens(condition)?,
+                ))),
+                _ => None,
+            };
+        }
+
+        let first = self.parse_media_in_parens(first)?;
+        let operator = match rest.first() {
+            Some(value) if is_keyword(value, "and") => "and",
+            Some(value) if allow_or && is_keyword(value, "or") => "or",
+            Some(_) => return None,
+            None => return Some(first),
+        };
+        let mut conditions = vec![first];
+        for pair in rest.chunks(2) {
+            match pair {
+                [keyword, condition] if is_keyword(keyword, operator) => {
+                    conditions.push(self.parse_media_in_parens(condition)?)
+                }
+                _ => return None,
+            }
+        }
+        match operator {
+            "and" => Some(MediaCondition::And(conditions)),
+            _ => Some(MediaCondition::Or(conditions)),
+        }
+    }
+
+    /// Parse a nested condition, a media feature or anything else in
+    /// parentheses, which never matches
+    fn parse_media_in_parens(&self, value: &ComponentValue) -> Option<MediaCondition> {
+        match value {
+            ComponentValue::SimpleBlock(block) if block.token == Token::ParentheseOpen => {
+                let values = block
+                    .value
+                    .iter()
+                    .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+                    .collect::<Vec<_>>();
+                if let Some(condition) = self.parse_media_condition(&values, true) {
+                    return Some(condition);
+                }
+                match values.as_slice() {
+                    [ComponentValue::PerservedToken(Token::Ident(name))] => {
+                        Some(MediaCondition::Feature(MediaFeature {
+                            name: name.to_ascii_lowercase(),
+                            value: None,
+                        }))
+                    }
+                    [ComponentValue::PerservedToken(Token::Ident(name)), ComponentValue::PerservedToken(Token::Colon), value @ ..]
+                        if !value.is_empty() =>
+                    {
+                        Some(MediaCondition::Feature(MediaFeature {
+                            name: name.to_ascii_lowercase(),
+                            value: Some(value.iter().map(|value| (*value).clone()).collect()),
+                        }))
+                    }
+                    _ => Some(MediaCondition::Unknown),
+                }
             }
+            ComponentValue::Function(_) => Some(MediaCondition::Unknown),
+            _ => None,
         }
-        stylesheet
     }
 
     pub fn parse_a_list_of_rules(&mut self) -> ListOfRules {
@@ -478,12 +1006,11 @@ impl Parser<Token> {
         loop {
             let value = self.consume_a_component_value();
             if let ComponentValue::PerservedToken(Token::EOF) = value {
-                return_values.push(values.clone());
+                return_values.push(values);
                 break;
             }
             if let ComponentValue::PerservedToken(Token::Comma) = value {
-                return_values.push(values.clone());
-                values.clear();
+                return_values.push(std::mem::take(&mut values));
                 continue;
             }
             values.push(value);
@@ -497,56 +1024,91 @@ impl Parser<ComponentValue> {
         Self {
             tokens,
             top_level: false,
-            reconsume: false,
-            current_token: None,
+            error_sink: None,
+            token_spans: Vec::new(),
+            base_url: None,
         }
     }
 
     fn consume_next_token(&mut self) -> ComponentValue {
-        if self.reconsume {
-            self.reconsume = false;
-            return self.current_token.clone().unwrap();
-        }
-        let token = self
-            .tokens
-            .next()
-            .unwrap_or(&ComponentValue::PerservedToken(Token::EOF));
-        self.current_token = Some(token.clone());
-        return token.clone();
+        let eof = ComponentValue::PerservedToken(Token::EOF);
+        self.tokens
+            .take_next(eof)
+            .unwrap_or(ComponentValue::PerservedToken(Token::EOF))
     }
 
-    fn peek_next_token(&mut self) -> ComponentValue {
-        if self.reconsume {
-            return self.current_token.clone().unwrap();
-        }
-        return self
-            .tokens
+    fn peek_next_token(&self) -> &ComponentValue {
+        self.tokens
             .peek()
             .unwrap_or(&ComponentValue::PerservedToken(Token::EOF))
-            .clone();
-    }
-
-    fn reconsume(&mut self) {
-        self.reconsume = true;
     }
 
     fn parse_a_list_of_declarations(&mut self) -> Vec<DeclarationOrAtRule> {
         self.consume_a_list_of_declarations()
     }
 
+    /// Parse the rules nested in the block of an at-rule, e.g. the keyframes of `@keyframes`
+    fn parse_a_list_of_rules(&mut self) -> ListOfRules {
+        self.consume_a_list_of_rules()
+    }
+
     fn consume_a_component_value(&mut self) -> ComponentValue {
         self.consume_next_token()
     }
 
+    fn consume_a_list_of_rules(&mut self) -> ListOfRules {
+        let mut rules = Vec::new();
+        loop {
+            match self.peek_next_token() {
+                ComponentValue::PerservedToken(Token::Whitespace) => {
+                    self.consume_next_token();
+                }
+                ComponentValue::PerservedToken(Token::EOF) => return rules,
+                ComponentValue::PerservedToken(Token::AtKeyword(_)) => {
+                    let at_rule = self.consume_an_at_rule();
+                    rules.push(Rule::AtRule(at_rule));
+                }
+                _ => {
+                    if let Some(rule) = self.consume_a_qualified_rule() {
+                        rules.push(Rule::QualifiedRule(rule));
+                    }
+                }
+            }
+        }
+    }
+
+    fn consume_a_qualified_rule(&mut self) -> Option<QualifiedRule> {
+        let mut qualified_rule = QualifiedRule::new();
+
+        loop {
+            let next_token = self.consume_next_token();
+
+            match next_token {
+                ComponentValue::PerservedToken(Token::EOF) => {
+                    self.emit_error(
+                        "eof-in-qualified-rule",
+                        "Unexpected EOF while consuming a qualified rule",
+                    );
+                    return None;
+                }
+                // the blocks are already consumed in this mode
+                ComponentValue::SimpleBlock(block) if block.token == Token::BraceOpen => {
+                    qualified_rule.set_block(block);
+                    return Some(qualified_rule);
+                }
+                value => qualified_rule.append_prelude(value),
+            }
+        }
+    }
+
     fn consume_an_at_rule(&mut self) -> AtRule {
-        self.consume_next_token();
-        let current_token = self.current_token.clone().unwrap();
-        let keyword_name =
-            if let ComponentValue::PerservedToken(Token::AtKeyword(name)) = current_token {
-                name
-            } else {
-                panic!("The current token is not a function");
-            };
+        let keyword_name = match self.consume_next_token() {
+            ComponentValue::PerservedToken(Token::AtKeyword(name)) => name,
+            _ => {
+                self.emit_error("expected-at-keyword", "Expected an at-keyword");
+                String::new()
+            }
+        };
         let mut at_rule = AtRule::new(keyword_name);
 
         loop {
@@ -555,14 +1117,18 @@ impl Parser<ComponentValue> {
             match next_token {
                 ComponentValue::PerservedToken(Token::Semicolon) => return at_rule,
                 ComponentValue::PerservedToken(Token::EOF) => {
-                    emit_error!("Unexpected EOF while consuming an at-rule");
+                    self.emit_error(
+                        "eof-in-at-rule",
+                        "Unexpected EOF while consuming an at-rule",
+                    );
                     return at_rule;
                 }
-                // TODO: How is a simple block a token?
-                _ => {
-                    self.reconsume();
-                    at_rule.append_prelude(self.consume_a_component_value());
+                // the blocks are already consumed in this mode
+                ComponentValue::SimpleBlock(block) if block.token == Token::BraceOpen => {
+                    at_rule.set_block(block);
+                    return at_rule;
                 }
+                value => at_rule.append_prelude(value),
             }
         }
     }
@@ -571,21 +1137,21 @@ impl Parser<ComponentValue> {
         let mut result = Vec::new();
 
         loop {
-            let next_token = self.consume_next_token();
-
-            match next_token {
+            match self.peek_next_token() {
                 ComponentValue::PerservedToken(Token::Whitespace)
-                | ComponentValue::PerservedToken(Token::Semicolon) => {}
+                | ComponentValue::PerservedToken(Token::Semicolon) => {
+                    self.consume_next_token();
+                }
                 ComponentValue::PerservedToken(Token::EOF) => {
+                    self.consume_next_token();
                     return result;
                 }
                 ComponentValue::PerservedToken(Token::AtKeyword(_)) => {
-                    self.reconsume();
                     let rule = self.consume_an_at_rule();
                     result.push(DeclarationOrAtRule::AtRule(rule));
                 }
                 ComponentValue::PerservedToken(Token::Ident(_)) => {
-                    let mut tmp = vec![self.current_token.clone().unwrap()];
+                    let mut tmp = vec![self.consume_next_token()];
                     loop {
                         match self.peek_next_token() {
                             ComponentValue::PerservedToken(Token::Semicolon)
@@ -593,14 +1159,18 @@ impl Parser<ComponentValue> {
                             _ => tmp.push(self.consume_a_component_value()),
                         }
                     }
-                    let mut parser = Parser::<ComponentValue>::new(DataStream::new(tmp));
+                    let mut parser = self.nested(DataStream::new(tmp));
                     if let Some(declaration) = parser.consume_a_declaration() {
                         result.push(DeclarationOrAtRule::Declaration(declaration));
                     }
                 }
                 _ => {
-                    emit_error!("Unexpected token while consuming a list of declarations");
-                    self.reconsume();
+                    // throw away the declaration
+                    self.consume_a_component_value();
+                    self.emit_error(
+                        "invalid-declaration-start",
+                        "Unexpected token while consuming a list of declarations",
+                    );
                     loop {
                         match self.peek_next_token() {
                             ComponentValue::PerservedToken(Token::Semicolon)
@@ -622,7 +1192,8 @@ impl Parser<ComponentValue> {
             if let ComponentValue::PerservedToken(Token::Ident(name)) = next_token {
                 name
             } else {
-                panic!("Token is not a indent token");
+                self.emit_error("expected-ident", "Expected an identifier in declaration");
+                return None;
             };
         let mut declaration = Declaration::new(declaration_name);
         self.consume_while_next_token_is(Token::Whitespace);
@@ -632,7 +1203,10 @@ impl Parser<ComponentValue> {
                 self.consume_next_token();
             }
             _ => {
-                emit_error!("Expected Colon in declaration");
+                self.emit_error(
+                    "missing-colon-in-declaration",
+                    "Expected Colon in declaration",
+                );
                 return None;
             }
         }
@@ -664,17 +1238,76 @@ impl Parser<ComponentValue> {
             declaration.pop_last(1);
         }
 
+        self.resolve_urls(&mut declaration.value);
         return Some(declaration);
     }
 
     fn consume_while_next_token_is(&mut self, token: Token) {
         let token = ComponentValue::PerservedToken(token);
-        while self.peek_next_token() == token {
+        while *self.peek_next_token() == token {
             self.consume_next_token();
         }
     }
 }
 
+/// Resolve a URL of a stylesheet, the empty URLs & the invalid URLs are
+/// kept as is
+fn resolve_url(base: &Url, url: &mut String) {
+    if url.trim().is_empty() {
+        return;
+    }
+    if let Ok(resolved) = base.join(url) {
+        *url = resolved.raw().to_string();
+    }
+}
+
+/// Parse the URL of a namespace, either a string or a URL
+fn parse_namespace_url(value: &ComponentValue) -> Option<String> {
+    match value {
+        ComponentValue::PerservedToken(Token::Str(url))
+        | ComponentValue::PerservedToken(Token::Url(url)) => Some(url.clone()),
+        ComponentValue::Function(function) if function.name.eq_ignore_ascii_case("url") => {
+            match function.value.as_slice() {
+                [ComponentValue::PerservedToken(Token::Str(url))] => Some(url.clone()),
+                _ => None,
+            }
+        }
+        _ => None,
+    }
+}
+
+/// Parse the comma separated selectors of a keyframe block
+/// into offsets, `from` being 0 and `to` being 1
+fn parse_keyframe_selectors(prelude: &[ComponentValue]) -> Option<Vec<f32>> {
+    prelude
+        .split(|value| *value == ComponentValue::PerservedToken(Token::Comma))
+        .map(|selector| {
+            let mut values = selector
+                .iter()
+                .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace));
+            let offset = match values.next()? {
+                ComponentValue::PerservedToken(Token::Ident(keyword)) => {
+                    match keyword.to_ascii_lowercase().as_str() {
+                        "from" => 0.,
+                        "to" => 1.,
+                        _ => return None,
+                    }
+                }
+                ComponentValue::PerservedToken(Token::Percentage(percentage))
+                    if (0. ..=100.).contains(percentage) =>
+                {
+                    percentage / 100.
+                }
+                _ => return None,
+            };
+            match values.next() {
+                Some(_) => None,
+                None => Some(offset),
+            }
+        })
+        .collect()
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -684,6 +1317,7 @@ mod tests {
     use crate::selector::structs::*;
     use crate::tokenizer::token::HashType;
     use crate::tokenizer::Tokenizer;
+    use atom::Atom;
 
     #[test]
     fn parse_a_stylesheet() {
@@ -698,21 +1332,22 @@ mod tests {
             rules[0],
             Rule::QualifiedRule(QualifiedRule {
                 prelude: vec![
-                    ComponentValue::PerservedToken(Token::Ident("div".to_string())),
+                    ComponentValue::PerservedToken(Token::Ident(Atom::from("div"))),
                     ComponentValue::PerservedToken(Token::Whitespace)
                 ],
                 block: Some(SimpleBlock {
                     token: Token::BraceOpen,
                     value: vec![
                         ComponentValue::PerservedToken(Token::Whitespace),
-                        ComponentValue::PerservedToken(Token::Ident("color".to_string())),
+                        ComponentValue::PerservedToken(Token::Ident(Atom::from("color"))),
                         ComponentValue::PerservedToken(Token::Colon),
                         ComponentValue::PerservedToken(Token::Whitespace),
-                        ComponentValue::PerservedToken(Token::Ident("black".to_string())),
+                        ComponentValue::PerservedToken(Token::Ident(Atom::from("black"))),
                         ComponentValue::PerservedToken(Token::Semicolon),
                         ComponentValue::PerservedToken(Token::Whitespace),
                     ]
-                })
+                }),
+                span: None
             })
         );
     }
@@ -726,10 +1361,71 @@ mod tests {
         let component_values = parser.parse_a_list_of_component_values();
         assert_eq!(
             component_values[0],
-            ComponentValue::PerservedToken(Token::Ident("p".to_string()))
+            ComponentValue::PerservedToken(Token::Ident(Atom::from("p")))
         );
     }
 
+    #[test]
+    fn parse_nested_blocks() {
+        let css = "[a (b {c})] d";
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let component_values = parser.parse_a_list_of_component_values();
+
+        let ident = |name: &str| ComponentValue::PerservedToken(Token::Ident(Atom::from(name)));
+        let whitespace = ComponentValue::PerservedToken(Token::Whitespace);
+        // the tokens after a nested block belong to the block around it
+        assert_eq!(
+            component_values,
+            vec![
+                ComponentValue::SimpleBlock(SimpleBlock {
+                    token: Token::BracketOpen,
+                    value: vec![
+                        ident("a"),
+                        whitespace.clone(),
+                        ComponentValue::SimpleBlock(SimpleBlock {
+                            token: Token::ParentheseOpen,
+                            value: vec![
+                                ident("b"),
+                                whitespace.clone(),
+                                ComponentValue::SimpleBlock(SimpleBlock {
+                                    token: Token::BraceOpen,
+                                    value: vec![ident("c")]
+                                })
+                            ]
+                        })
+                    ]
+                }),
+                whitespace,
+                ident("d"),
+            ]
+        );
+    }
+
+    #[test]
+    fn recover_from_invalid_declarations() {
+        let css = "p { 12px; color: red; @media print { x: y } margin: 0; } a { width: 0 }";
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let names = |rule: &CSSRule| match rule {
+            CSSRule::Style(rule) => rule
+                .declarations
+                .iter()
+                .map(|declaration| declaration.name.to_string())
+                .collect::<Vec<_>>(),
+            _ => Vec::new(),
+        };
+        // the token starting an invalid declaration or an at-rule is read
+        // again by the parser of what it starts
+        assert_eq!(stylesheet.css_rules.0.len(), 2);
+        assert_eq!(names(&stylesheet.css_rules.0[0]), ["color", "margin"]);
+        assert_eq!(names(&stylesheet.css_rules.0[1]), ["width"]);
+    }
+
     #[test]
     fn parse_a_class() {
         let css = ".className { color: black; }";
@@ -744,21 +1440,22 @@ mod tests {
             Rule::QualifiedRule(QualifiedRule {
                 prelude: vec![
                     ComponentValue::PerservedToken(Token::Delim('.')),
-                    ComponentValue::PerservedToken(Token::Ident("className".to_string())),
+                    ComponentValue::PerservedToken(Token::Ident(Atom::from("className"))),
                     ComponentValue::PerservedToken(Token::Whitespace)
                 ],
                 block: Some(SimpleBlock {
                     token: Token::BraceOpen,
                     value: vec![
                         ComponentValue::PerservedToken(Token::Whitespace),
-                        ComponentValue::PerservedToken(Token::Ident("color".to_string())),
+                        ComponentValue::PerservedToken(Token::Ident(Atom::from("color"))),
                         ComponentValue::PerservedToken(Token::Colon),
                         ComponentValue::PerservedToken(Token::Whitespace),
-                        ComponentValue::PerservedToken(Token::Ident("black".to_string())),
+                        ComponentValue::PerservedToken(Token::Ident(Atom::from("black"))),
                         ComponentValue::PerservedToken(Token::Semicolon),
                         ComponentValue::PerservedToken(Token::Whitespace),
                     ]
-                })
+                }),
+                span: None
             })
         );
     }
@@ -786,18 +1483,65 @@ mod tests {
                     token: Token::BraceOpen,
                     value: vec![
                         ComponentValue::PerservedToken(Token::Whitespace),
-                        ComponentValue::PerservedToken(Token::Ident("color".to_string())),
+                        ComponentValue::PerservedToken(Token::Ident(Atom::from("color"))),
                         ComponentValue::PerservedToken(Token::Colon),
                         ComponentValue::PerservedToken(Token::Whitespace),
-                        ComponentValue::PerservedToken(Token::Ident("black".to_string())),
+                        ComponentValue::PerservedToken(Token::Ident(Atom::from("black"))),
                         ComponentValue::PerservedToken(Token::Semicolon),
                         ComponentValue::PerservedToken(Token::Whitespace),
                     ]
-                })
+                }),
+                span: None
             })
         );
     }
 
+    #[test]
+    fn resolve_declaration_urls() {
+        let css = "@namespace url(ns);\
+            a { background-image: url(img/a.png); }\
+            b { background-image: url( '../b.png' ); content: 'c.png'; }\
+            @supports (color: red) { i { background-image: url(\"\") } }";
+        let tokens = Tokenizer::new(css.chars()).run();
+        let mut parser = Parser::<Token>::new(tokens);
+        parser.set_base_url(Url::parse("https://moon.dev/css/main.css").unwrap());
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let value = |rule: &CSSRule| match rule {
+            CSSRule::Style(style) => style.declarations[0].value.clone(),
+            _ => unreachable!(),
+        };
+        match &stylesheet.css_rules.0[0] {
+            CSSRule::Namespace(rule) => assert_eq!(rule.url, "ns"),
+            _ => unreachable!(),
+        }
+        assert_eq!(
+            value(&stylesheet.css_rules.0[1]),
+            vec![ComponentValue::PerservedToken(Token::Url(
+                "https://moon.dev/css/img/a.png".to_string()
+            ))]
+        );
+        let mut function = Function::new("url".to_string());
+        function.append_value(ComponentValue::PerservedToken(Token::Whitespace));
+        function.append_value(ComponentValue::PerservedToken(Token::Str(
+            "https://moon.dev/b.png".to_string(),
+        )));
+        function.append_value(ComponentValue::PerservedToken(Token::Whitespace));
+        assert_eq!(
+            value(&stylesheet.css_rules.0[2]),
+            vec![ComponentValue::Function(function)]
+        );
+        match &stylesheet.css_rules.0[2] {
+            CSSRule::Style(style) => assert_eq!(
+                style.declarations[1].value,
+                vec![ComponentValue::PerservedToken(Token::Str(
+                    "c.png".to_string()
+                ))]
+            ),
+            _ => unreachable!(),
+        }
+    }
+
     #[test]
     fn parse_css_stylesheet() {
         let css = "#elementId { color: black !important; }";
@@ -811,21 +1555,271 @@ mod tests {
                 vec![Selector::new(vec![(
                     SimpleSelectorSequence::new(vec![SimpleSelector::new(
                         SimpleSelectorType::ID,
-                        Some("elementId".to_string())
+                        Some(Atom::from("elementId"))
                     )]),
                     None
                 )])],
                 vec![Declaration {
-                    name: "color".to_string(),
+                    name: Atom::from("color"),
                     important: true,
-                    value: vec![ComponentValue::PerservedToken(Token::Ident(
-                        "black".to_string()
-                    ))]
+                    value: vec![ComponentValue::PerservedToken(Token::Ident(Atom::from(
+                        "black"
+                    )))]
                 }]
             ))])
         );
     }
 
+    #[test]
+    fn parse_keyframes_rule() {
+        let css = r#"
+        @keyframes slide {
+            from { width: 10px; }
+            50%, 75% { width: 20px }
+            200% { width: 30px; }
+            to { width: 40px; }
+        }
+        @keyframes none { to { width: 0; } }
+        "#;
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let width = |value: f32| Declaration {
+            name: Atom::from("width"),
+            important: false,
+            value: vec![ComponentValue::PerservedToken(Token::Dimension {
+                value,
+                type_: crate::tokenizer::token::NumberType::Integer,
+                unit: "px".to_string(),
+            })],
+        };
+
+        // the keyframe with an invalid selector is dropped
+        assert_eq!(
+            stylesheet.css_rules,
+            CSSRuleList(vec![CSSRule::Keyframes(KeyframesRule::new(
+                "slide".to_string(),
+                vec![
+                    Keyframe::new(vec![0.], vec![width(10.)]),
+                    Keyframe::new(vec![0.5, 0.75], vec![width(20.)]),
+                    Keyframe::new(vec![1.], vec![width(40.)]),
+                ]
+            ))])
+        );
+    }
+
+    #[test]
+    fn parse_page_rule() {
+        let css = r#"
+        @page { margin: 1in; }
+        @page :first { margin-top: 2in; }
+        @page toc { margin: 0; }
+        "#;
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let margin = |name: &str, value: f32| Declaration {
+            name: Atom::from(name),
+            important: false,
+            value: vec![ComponentValue::PerservedToken(Token::Dimension {
+                value,
+                type_: crate::tokenizer::token::NumberType::Integer,
+                unit: "in".to_string(),
+            })],
+        };
+
+        // the named pages aren't supported
+        assert_eq!(
+            stylesheet.css_rules,
+            CSSRuleList(vec![
+                CSSRule::Page(PageRule::new(vec![], vec![margin("margin", 1.)])),
+                CSSRule::Page(PageRule::new(
+                    vec!["first".to_string()],
+                    vec![margin("margin-top", 2.)]
+                )),
+            ])
+        );
+    }
+
+    #[test]
+    fn parse_supports_rule() {
+        let css = r#"
+        @supports (display: flex) and (not (color: foo)) {
+            div { width: 10px; }
+            @supports selector(a > b) or (--unknown) { p { width: 20px; } }
+        }
+        @supports display: flex { div { width: 30px; } }
+        "#;
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let declaration = |name: &str, value: &str| Declaration {
+            name: Atom::from(name),
+            important: false,
+            value: vec![ComponentValue::PerservedToken(Token::Ident(Atom::from(
+                value,
+            )))],
+        };
+
+        // the rule with an invalid condition is dropped
+        assert_eq!(stylesheet.len(), 1);
+        let supports = match &stylesheet[0] {
+            CSSRule::Supports(supports) => supports,
+            rule => panic!("Expected a @supports rule, got {:?}", rule),
+        };
+        assert_eq!(
+            supports.condition,
+            SupportsCondition::And(vec![
+                SupportsCondition::Declaration(declaration("display", "flex")),
+                SupportsCondition::Not(Box::new(SupportsCondition::Declaration(declaration(
+                    "color", "foo"
+                )))),
+            ])
+        );
+        assert_eq!(supports.css_rules.len(), 2);
+        assert!(matches!(supports.css_rules[0], CSSRule::Style(_)));
+
+        let nested = match &supports.css_rules[1] {
+            CSSRule::Supports(supports) => supports,
+            rule => panic!("Expected a @supports rule, got {:?}", rule),
+        };
+        assert_eq!(
+            nested.condition,
+            SupportsCondition::Or(vec![
+                SupportsCondition::Selector(true),
+                SupportsCondition::Unknown
+            ])
+        );
+        assert_eq!(nested.css_rules.len(), 1);
+
+        let supports_color = |declaration: &Declaration| declaration.name.as_ref() == "color";
+        assert!(!supports.condition.evaluate(&supports_color));
+        assert!(nested.condition.evaluate(&supports_color));
+    }
+
+    #[test]
+    fn parse_media_rule() {
+        let css = r#"
+        @media screen and (min-width: 600px), not print, (color) or ((hover)) {
+            div { width: 10px; }
+            @media (prefers-color-scheme: dark) { p { width: 20px; } }
+        }
+        @media foo bar, (width >= 600px) { div { width: 30px; } }
+        "#;
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let feature = |name: &str, value: Option<Token>| MediaFeature {
+            name: name.to_string(),
+            value: value.map(|value| vec![ComponentValue::PerservedToken(value)]),
+        };
+        fn media(rule: &CSSRule) -> Vec<&MediaQuery> {
+            match rule {
+                CSSRule::Media(media) => media.queries.0.iter().collect(),
+                rule => panic!("Expected a @media rule, got {:?}", rule),
+            }
+        }
+
+        assert_eq!(stylesheet.len(), 2);
+        let queries = media(&stylesheet[0]);
+        assert_eq!(queries.len(), 3);
+        assert_eq!(queries[0].media_type, MediaType::Screen);
+        assert_eq!(
+            queries[0].condition,
+            Some(MediaCondition::Feature(feature(
+                "min-width",
+                Some(Token::Dimension {
+                    value: 600.,
+                    type_: crate::tokenizer::token::NumberType::Integer,
+                    unit: "px".to_string(),
+                })
+            )))
+        );
+        assert!(queries[1].negated);
+        assert_eq!(queries[1].media_type, MediaType::Print);
+        assert_eq!(
+            queries[2].condition,
+            Some(MediaCondition::Or(vec![
+                MediaCondition::Feature(feature("color", None)),
+                MediaCondition::Feature(feature("hover", None)),
+            ]))
+        );
+
+        let nested = match &stylesheet[0] {
+            CSSRule::Media(media) => &media.css_rules[1],
+            _ => unreachable!(),
+        };
+        assert_eq!(
+            media(nested)[0].condition,
+            Some(MediaCondition::Feature(feature(
+                "prefers-color-scheme",
+                Some(Token::Ident(Atom::from("dark")))
+            )))
+        );
+
+        // the invalid query never matches, the range syntax is unknown
+        let queries = media(&stylesheet[1]);
+        assert_eq!(*queries[0], MediaQuery::not_all());
+        assert_eq!(queries[1].condition, Some(MediaCondition::Unknown));
+        assert!(MediaQueryList(Vec::new()).evaluate(&|_| false));
+    }
+
+    #[test]
+    fn parse_namespace_rules() {
+        let css = r#"
+        @namespace url("http://www.w3.org/1999/xhtml");
+        @namespace svg url(http://www.w3.org/2000/svg);
+        @namespace "invalid" "prefix";
+        svg|rect { width: 10px; }
+        @namespace math "http://www.w3.org/1998/Math/MathML";
+        math|mi { width: 10px; }
+        "#;
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        // the rules after the first style rule can't declare namespaces
+        assert_eq!(stylesheet.len(), 3);
+        assert_eq!(
+            stylesheet[0],
+            CSSRule::Namespace(NamespaceRule::new(
+                None,
+                "http://www.w3.org/1999/xhtml".to_string()
+            ))
+        );
+        assert_eq!(
+            stylesheet[1],
+            CSSRule::Namespace(NamespaceRule::new(
+                Some("svg".to_string()),
+                "http://www.w3.org/2000/svg".to_string()
+            ))
+        );
+        let selectors = match &stylesheet[2] {
+            CSSRule::Style(style) => &style.selectors,
+            rule => panic!("Expected a style rule, got {:?}", rule),
+        };
+        assert_eq!(
+            selectors,
+            &vec![Selector::new(vec![(
+                SimpleSelectorSequence::new(vec![SimpleSelector::with_namespace(
+                    SimpleSelectorType::Type,
+                    Some(Atom::from("rect")),
+                    NamespaceConstraint::Url(Atom::from("http://www.w3.org/2000/svg"))
+                )]),
+                None
+            )])]
+        );
+    }
+
     #[test]
     fn parse_function() {
         let css = "#elementId { color: rgba(0 0 0 0); }";
@@ -839,12 +1833,12 @@ mod tests {
                 vec![Selector::new(vec![(
                     SimpleSelectorSequence::new(vec![SimpleSelector::new(
                         SimpleSelectorType::ID,
-                        Some("elementId".to_string())
+                        Some(Atom::from("elementId"))
                     )]),
                     None
                 )])],
                 vec![Declaration {
-                    name: "color".to_string(),
+                    name: Atom::from("color"),
                     important: false,
                     value: vec![ComponentValue::Function(Function {
                         name: "rgba".to_string(),
@@ -874,4 +1868,27 @@ mod tests {
             ))])
         );
     }
+
+    #[test]
+    fn track_rule_source_spans() {
+        let css = "/* header */\ndiv {\n  color: red;\n}\n\n#id { color: blue; }";
+        let tokenizer = Tokenizer::new(css.chars());
+        let (tokens, spans) = tokenizer.run_with_spans();
+        let mut parser = Parser::<Token>::new(tokens);
+        parser.set_token_spans(spans);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let spans = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(style.span.unwrap()),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+
+        assert_eq!((spans[0].start.line, spans[0].start.column), (2, 1));
+        assert_eq!((spans[0].end.line, spans[0].end.column), (4, 2));
+        assert_eq!(spans[0].start.offset, 13);
+        assert_eq!((spans[1].start.line, spans[1].start.column), (6, 1));
+    }
 }
@@ -1,4 +1,6 @@
 use crate::tokenizer::token::Token;
+use atom::Atom;
+use io::position::SourceSpan;
 
 #[derive(Debug, PartialEq)]
 pub enum Rule {
@@ -37,6 +39,7 @@ pub struct Function {
 pub struct QualifiedRule {
     pub prelude: Vec<ComponentValue>,
     pub block: Option<SimpleBlock>,
+    pub span: Option<SourceSpan>,
 }
 
 /// AtRule
@@ -46,13 +49,14 @@ pub struct AtRule {
     pub name: String,
     pub prelude: Vec<ComponentValue>,
     pub block: Option<SimpleBlock>,
+    pub span: Option<SourceSpan>,
 }
 
 /// Declaration
 /// https://www.w3.org/TR/css-syntax-3/#declaration
 #[derive(Debug, PartialEq)]
 pub struct Declaration {
-    pub name: String,
+    pub name: Atom,
     pub value: Vec<ComponentValue>,
     pub important: bool,
 }
@@ -71,6 +75,7 @@ impl QualifiedRule {
         Self {
             prelude: Vec::new(),
             block: None,
+            span: None,
         }
     }
 
@@ -89,6 +94,7 @@ impl AtRule {
             name,
             prelude: Vec::new(),
             block: None,
+            span: None,
         }
     }
 
@@ -115,7 +121,7 @@ impl SimpleBlock {
 }
 
 impl Declaration {
-    pub fn new(name: String) -> Self {
+    pub fn new(name: Atom) -> Self {
         Self {
             name,
             value: Vec::new(),
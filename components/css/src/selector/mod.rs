@@ -4,6 +4,7 @@ use super::parser::structs::ComponentValue;
 use super::parser::Parser;
 use super::tokenizer::token::Token;
 use super::tokenizer::Tokenizer;
+use atom::Atom;
 use io::data_stream::DataStream;
 use structs::*;
 
@@ -18,24 +19,40 @@ pub fn parse_selector_str(selector: &str) -> Option<Selector> {
     let mut parser = Parser::<Token>::new(tokenizer.run());
     let values = parser.parse_a_list_of_component_values();
     let mut data_stream = DataStream::new(values);
-    parse_selector(&mut data_stream)
+    parse_selector(&mut data_stream, &NamespaceMap::new())
 }
 
 pub fn parse_selectors(values: &Vec<ComponentValue>) -> Vec<Selector> {
+    parse_selectors_with_namespaces(values, &NamespaceMap::new())
+}
+
+/// Parse selectors with the namespaces declared by the `@namespace` rules
+/// of their stylesheet. A selector with an undeclared prefix is invalid,
+/// and so is its whole list.
+pub fn parse_selectors_with_namespaces(
+    values: &Vec<ComponentValue>,
+    namespaces: &NamespaceMap,
+) -> Vec<Selector> {
     let mut selectors = Vec::new();
 
+    let has_undeclared_prefix = values.windows(2).any(|pair| match pair {
+        [token_value!(Token::Ident(prefix)), token_value!(Token::Delim('|'))] => {
+            namespaces.resolve(prefix).is_none()
+        }
+        _ => false,
+    });
+    if has_undeclared_prefix {
+        return selectors;
+    }
+
     let mut data_stream = DataStream::new(values.clone());
 
     loop {
-        if let Some(selector) = parse_selector(&mut data_stream) {
+        if let Some(selector) = parse_selector(&mut data_stream, namespaces) {
             selectors.push(selector);
-            loop {
-                // consume all white space
-                if let Some(token_value!(Token::Whitespace)) = data_stream.peek() {
-                    data_stream.next();
-                } else {
-                    break;
-                }
+            // consume all white space
+            while let Some(token_value!(Token::Whitespace)) = data_stream.peek() {
+                data_stream.next();
             }
             if let Some(token_value!(Token::Comma)) = data_stream.next() {
                 // there is a comma, let the parsing continue
@@ -54,10 +71,13 @@ pub fn parse_selectors(values: &Vec<ComponentValue>) -> Vec<Selector> {
     return selectors;
 }
 
-pub fn parse_selector(data_stream: &mut DataStream<ComponentValue>) -> Option<Selector> {
+pub fn parse_selector(
+    data_stream: &mut DataStream<ComponentValue>,
+    namespaces: &NamespaceMap,
+) -> Option<Selector> {
     let mut selector_seqs: SelectorData = Vec::new();
     loop {
-        if let Some(selector_seq) = parse_simple_selector_seq(data_stream) {
+        if let Some(selector_seq) = parse_simple_selector_seq(data_stream, namespaces) {
             if let Some(combinator) = parse_combinator(data_stream) {
                 selector_seqs.push((selector_seq, Some(combinator)));
                 continue;
@@ -83,7 +103,7 @@ pub fn parse_combinator(data_stream: &mut DataStream<ComponentValue>) -> Option<
     let next_values = data_stream.peek_next(4);
 
     if next_values.len() == 4 {
-        return match (
+        let combinator = match (
             next_values[0],
             next_values[1],
             next_values[2],
@@ -125,6 +145,9 @@ pub fn parse_combinator(data_stream: &mut DataStream<ComponentValue>) -> Option<
             }
             _ => None,
         };
+        if combinator.is_some() {
+            return combinator;
+        }
     }
 
     let next_values = data_stream.peek_next(2);
@@ -157,10 +180,11 @@ pub fn parse_combinator(data_stream: &mut DataStream<ComponentValue>) -> Option<
 
 pub fn parse_simple_selector_seq(
     data_stream: &mut DataStream<ComponentValue>,
+    namespaces: &NamespaceMap,
 ) -> Option<SimpleSelectorSequence> {
     let mut seq = Vec::new();
     loop {
-        if let Some(simple_selector) = parse_simple_selector(data_stream) {
+        if let Some(simple_selector) = parse_simple_selector(data_stream, namespaces) {
             seq.push(simple_selector);
         } else {
             if let Some(value) = data_stream.peek() {
@@ -186,38 +210,230 @@ pub fn parse_simple_selector_seq(
 
 pub fn parse_simple_selector(
     data_stream: &mut DataStream<ComponentValue>,
+    namespaces: &NamespaceMap,
 ) -> Option<SimpleSelector> {
+    if let Some(selector) = parse_type_selector(data_stream, namespaces) {
+        return Some(selector);
+    }
     let next_token = data_stream.peek_clone();
     match next_token {
-        Some(token_value!(Token::Ident(data))) => {
-            data_stream.next();
-            Some(SimpleSelector::new(SimpleSelectorType::Type, Some(data)))
-        }
-        Some(token_value!(Token::Delim('*'))) => {
-            data_stream.next();
-            Some(SimpleSelector::new(SimpleSelectorType::Universal, None))
-        }
         Some(token_value!(Token::Hash(data, _))) => {
             data_stream.next();
-            Some(SimpleSelector::new(SimpleSelectorType::ID, Some(data)))
+            Some(SimpleSelector::new(
+                SimpleSelectorType::ID,
+                Some(Atom::from(data)),
+            ))
         }
-        Some(token_value!(Token::Delim('.'))) => {
-            let next_values = data_stream.peek_next(2);
-            if next_values.len() != 2 {
-                return None;
-            }
-            if let token_value!(Token::Ident(data)) = next_values[1].clone() {
+        Some(token_value!(Token::Delim('.'))) => match data_stream.lookahead(1).cloned() {
+            Some(token_value!(Token::Ident(data))) => {
                 data_stream.next();
                 data_stream.next();
-                return Some(SimpleSelector::new(SimpleSelectorType::Class, Some(data)));
+                Some(SimpleSelector::new(SimpleSelectorType::Class, Some(data)))
             }
-            None
-        }
+            _ => None,
+        },
+        Some(token_value!(Token::Colon)) => parse_pseudo_selector(data_stream, namespaces),
         // TODO: Support other selectors too
         _ => None,
     }
 }
 
+/// Parse a pseudo-class like `:hover` or a pseudo-element like `::before`.
+/// The pseudo-elements of CSS 2 can also be written with a single colon.
+fn parse_pseudo_selector(
+    data_stream: &mut DataStream<ComponentValue>,
+    namespaces: &NamespaceMap,
+) -> Option<SimpleSelector> {
+    if let Some(ComponentValue::Function(function)) = data_stream.lookahead(1) {
+        let selector = parse_functional_pseudo_class(&function.name, &function.value, namespaces);
+        data_stream.next();
+        data_stream.next();
+        return Some(selector);
+    }
+
+    let next_values = data_stream.peek_next(3);
+    let (type_, name, length) = match next_values.as_slice() {
+        [token_value!(Token::Colon), token_value!(Token::Colon), token_value!(Token::Ident(name))] => {
+            (SimpleSelectorType::PseudoElement, name.clone(), 3)
+        }
+        [token_value!(Token::Colon), token_value!(Token::Ident(name)), ..] => {
+            let is_legacy_pseudo_element = ["before", "after", "first-line", "first-letter"]
+                .iter()
+                .any(|legacy| name.eq_ignore_ascii_case(legacy));
+            let type_ = if is_legacy_pseudo_element {
+                SimpleSelectorType::PseudoElement
+            } else {
+                SimpleSelectorType::Pseudo
+            };
+            (type_, name.clone(), 2)
+        }
+        _ => return None,
+    };
+    for _ in 0..length {
+        data_stream.next();
+    }
+    let name = Atom::from(name.to_ascii_lowercase());
+    Some(SimpleSelector::new(type_, Some(name)))
+}
+
+/// Parse a pseudo-class with arguments, e.g. `:not(.hidden)`. The
+/// pseudo-classes with unknown or invalid arguments never match.
+fn parse_functional_pseudo_class(
+    name: &str,
+    arguments: &[ComponentValue],
+    namespaces: &NamespaceMap,
+) -> SimpleSelector {
+    let name = Atom::from(name.to_ascii_lowercase());
+    let arguments = match name.as_ref() {
+        "not" | "is" | "where" => {
+            let selectors = parse_selectors_with_namespaces(&arguments.to_vec(), namespaces);
+            // the pseudo-elements can't be arguments
+            let has_pseudo_element = selectors
+                .iter()
+                .any(|selector| selector.pseudo_element().is_some());
+            if selectors.is_empty() || has_pseudo_element {
+                None
+            } else {
+                Some(PseudoClassArguments::Selectors(selectors))
+            }
+        }
+        "nth-child" | "nth-last-child" => parse_an_plus_b(arguments).map(PseudoClassArguments::Nth),
+        "lang" => parse_language_ranges(arguments).map(PseudoClassArguments::Languages),
+        _ => None,
+    };
+    match arguments {
+        Some(arguments) => SimpleSelector::with_arguments(name, arguments),
+        None => SimpleSelector::new(SimpleSelectorType::Pseudo, Some(name)),
+    }
+}
+
+/// Parse the comma separated language ranges of `:lang()`, each an ident
+/// or a string
+/// https://www.w3.org/TR/selectors-4/#the-lang-pseudo
+fn parse_language_ranges(values: &[ComponentValue]) -> Option<Vec<String>> {
+    let values = values
+        .iter()
+        .filter(|value| !matches!(value, token_value!(Token::Whitespace)))
+        .collect::<Vec<_>>();
+    values
+        .split(|value| matches!(value, token_value!(Token::Comma)))
+        .map(|range| match range {
+            [token_value!(Token::Ident(range))] => Some(range.to_string()),
+            [token_value!(Token::Str(range))] => Some(range.to_string()),
+            _ => None,
+        })
+        .collect()
+}
+
+/// Parse the `An+B` notation of positions, e.g. `odd`, `3` or `-n + 2`
+/// https://www.w3.org/TR/css-syntax-3/#anb-microsyntax
+pub fn parse_an_plus_b(values: &[ComponentValue]) -> Option<AnPlusB> {
+    // the notation is read from its text, the tokenizer splits it into
+    // numbers, dimensions & idents in many ways
+    let mut text = String::new();
+    for value in values {
+        let after_sign = text.ends_with('+') || text.ends_with('-');
+        match value {
+            token_value!(Token::Whitespace) => continue,
+            // the `n` follows its number or its sign
+            token_value!(Token::Ident(ident)) if text.is_empty() || after_sign => {
+                text.push_str(ident)
+            }
+            token_value!(Token::Delim(sign)) if *sign == '+' || *sign == '-' => text.push(*sign),
+            token_value!(Token::Number { value, .. }) if value.fract() == 0. => {
+                // the sign of a number after a sign is a sign too many
+                if after_sign && value.is_sign_negative() {
+                    return None;
+                }
+                if text.is_empty() || after_sign {
+                    text.push_str(&format!("{}", *value as i32));
+                } else {
+                    text.push_str(&format!("{:+}", *value as i32));
+                }
+            }
+            token_value!(Token::Dimension { value, unit, .. })
+                if text.is_empty() && value.fract() == 0. =>
+            {
+                text.push_str(&format!("{}{}", *value as i32, unit));
+            }
+            _ => return None,
+        }
+    }
+
+    let text = text.to_ascii_lowercase();
+    match text.as_str() {
+        "odd" => return Some(AnPlusB::new(2, 1)),
+        "even" => return Some(AnPlusB::new(2, 0)),
+        _ => {}
+    }
+    let (a, b) = match text.find('n') {
+        Some(index) => {
+            let a = match &text[..index] {
+                "" | "+" => 1,
+                "-" => -1,
+                a => a.parse().ok()?,
+            };
+            let b = match &text[index + 1..] {
+                "" => 0,
+                b if b.starts_with('+') || b.starts_with('-') => b.parse().ok()?,
+                _ => return None,
+            };
+            (a, b)
+        }
+        None => (0, text.parse().ok()?),
+    };
+    Some(AnPlusB::new(a, b))
+}
+
+/// Parse a type or a universal selector, with an optional namespace
+/// prefix: `ns|E`, `*|E` or `|E`
+fn parse_type_selector(
+    data_stream: &mut DataStream<ComponentValue>,
+    namespaces: &NamespaceMap,
+) -> Option<SimpleSelector> {
+    let next_values = data_stream.peek_next(3);
+    let (namespace, prefix_length) = match next_values.as_slice() {
+        [token_value!(Token::Ident(prefix)), token_value!(Token::Delim('|')), name, ..]
+            if is_type_name(name) =>
+        {
+            (namespaces.resolve(prefix)?, 2)
+        }
+        [token_value!(Token::Delim('*')), token_value!(Token::Delim('|')), name, ..]
+            if is_type_name(name) =>
+        {
+            (NamespaceConstraint::Any, 2)
+        }
+        [token_value!(Token::Delim('|')), name, ..] if is_type_name(name) => {
+            (NamespaceConstraint::None, 1)
+        }
+        [name, ..] if is_type_name(name) => (namespaces.default_namespace(), 0),
+        _ => return None,
+    };
+    for _ in 0..prefix_length {
+        data_stream.next();
+    }
+
+    match data_stream.next() {
+        Some(token_value!(Token::Ident(name))) => Some(SimpleSelector::with_namespace(
+            SimpleSelectorType::Type,
+            Some(name.clone()),
+            namespace,
+        )),
+        _ => Some(SimpleSelector::with_namespace(
+            SimpleSelectorType::Universal,
+            None,
+            namespace,
+        )),
+    }
+}
+
+fn is_type_name(value: &ComponentValue) -> bool {
+    matches!(
+        value,
+        token_value!(Token::Ident(_)) | token_value!(Token::Delim('*'))
+    )
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -241,9 +457,9 @@ mod tests {
 
             let expected = Selector::new(vec![(
                 SimpleSelectorSequence::new(vec![
-                    SimpleSelector::new(SimpleSelectorType::Type, Some("div".to_string())),
-                    SimpleSelector::new(SimpleSelectorType::Class, Some("class".to_string())),
-                    SimpleSelector::new(SimpleSelectorType::ID, Some("id".to_string())),
+                    SimpleSelector::new(SimpleSelectorType::Type, Some(Atom::from("div"))),
+                    SimpleSelector::new(SimpleSelectorType::Class, Some(Atom::from("class"))),
+                    SimpleSelector::new(SimpleSelectorType::ID, Some(Atom::from("id"))),
                 ]),
                 None,
             )]);
@@ -269,15 +485,15 @@ mod tests {
             let expected = Selector::new(vec![
                 (
                     SimpleSelectorSequence::new(vec![
-                        SimpleSelector::new(SimpleSelectorType::Type, Some("div".to_string())),
-                        SimpleSelector::new(SimpleSelectorType::Class, Some("class".to_string())),
+                        SimpleSelector::new(SimpleSelectorType::Type, Some(Atom::from("div"))),
+                        SimpleSelector::new(SimpleSelectorType::Class, Some(Atom::from("class"))),
                     ]),
                     Some(Combinator::Descendant),
                 ),
                 (
                     SimpleSelectorSequence::new(vec![SimpleSelector::new(
                         SimpleSelectorType::ID,
-                        Some("id".to_string()),
+                        Some(Atom::from("id")),
                     )]),
                     None,
                 ),
@@ -304,15 +520,15 @@ mod tests {
             let expected = Selector::new(vec![
                 (
                     SimpleSelectorSequence::new(vec![
-                        SimpleSelector::new(SimpleSelectorType::Type, Some("div".to_string())),
-                        SimpleSelector::new(SimpleSelectorType::Class, Some("class".to_string())),
+                        SimpleSelector::new(SimpleSelectorType::Type, Some(Atom::from("div"))),
+                        SimpleSelector::new(SimpleSelectorType::Class, Some(Atom::from("class"))),
                     ]),
                     Some(Combinator::Child),
                 ),
                 (
                     SimpleSelectorSequence::new(vec![SimpleSelector::new(
                         SimpleSelectorType::ID,
-                        Some("id".to_string()),
+                        Some(Atom::from("id")),
                     )]),
                     None,
                 ),
@@ -339,29 +555,29 @@ mod tests {
             let expected = Selector::new(vec![
                 (
                     SimpleSelectorSequence::new(vec![
-                        SimpleSelector::new(SimpleSelectorType::Type, Some("div".to_string())),
-                        SimpleSelector::new(SimpleSelectorType::Class, Some("class".to_string())),
+                        SimpleSelector::new(SimpleSelectorType::Type, Some(Atom::from("div"))),
+                        SimpleSelector::new(SimpleSelectorType::Class, Some(Atom::from("class"))),
                     ]),
                     Some(Combinator::Child),
                 ),
                 (
                     SimpleSelectorSequence::new(vec![SimpleSelector::new(
                         SimpleSelectorType::ID,
-                        Some("id".to_string()),
+                        Some(Atom::from("id")),
                     )]),
                     Some(Combinator::Child),
                 ),
                 (
                     SimpleSelectorSequence::new(vec![SimpleSelector::new(
                         SimpleSelectorType::ID,
-                        Some("name".to_string()),
+                        Some(Atom::from("name")),
                     )]),
                     Some(Combinator::NextSibling),
                 ),
                 (
                     SimpleSelectorSequence::new(vec![SimpleSelector::new(
                         SimpleSelectorType::Type,
-                        Some("div".to_string()),
+                        Some(Atom::from("div")),
                     )]),
                     None,
                 ),
@@ -387,8 +603,8 @@ mod tests {
 
             let expected = Selector::new(vec![(
                 SimpleSelectorSequence::new(vec![
-                    SimpleSelector::new(SimpleSelectorType::Type, Some("div".to_string())),
-                    SimpleSelector::new(SimpleSelectorType::Class, Some("class".to_string())),
+                    SimpleSelector::new(SimpleSelectorType::Type, Some(Atom::from("div"))),
+                    SimpleSelector::new(SimpleSelectorType::Class, Some(Atom::from("class"))),
                 ]),
                 None,
             )]);
@@ -396,7 +612,7 @@ mod tests {
             let expected2 = Selector::new(vec![(
                 SimpleSelectorSequence::new(vec![SimpleSelector::new(
                     SimpleSelectorType::ID,
-                    Some("name".to_string()),
+                    Some(Atom::from("name")),
                 )]),
                 None,
             )]);
@@ -430,4 +646,87 @@ mod tests {
 
         assert_eq!(specificity, Specificity::new(2, 1, 0));
     }
+
+    #[test]
+    fn parse_pseudo_elements() {
+        let selector = parse_selector_str("p.note::before").unwrap();
+        assert_eq!(selector.pseudo_element(), Some(&Atom::from("before")));
+        assert_eq!(selector.specificity(), Specificity::new(0, 1, 2));
+
+        let selector = parse_selector_str("a:AFTER").unwrap();
+        assert_eq!(selector.pseudo_element(), Some(&Atom::from("after")));
+
+        let selector = parse_selector_str("a:hover").unwrap();
+        assert_eq!(selector.pseudo_element(), None);
+        assert_eq!(selector.specificity(), Specificity::new(0, 1, 1));
+    }
+
+    #[test]
+    fn parse_functional_pseudo_classes() {
+        let selector = parse_selector_str("p:not(.note, #main)").unwrap();
+        let (sequence, _) = &selector.values()[0];
+        match sequence.values()[1].arguments() {
+            Some(PseudoClassArguments::Selectors(selectors)) => assert_eq!(selectors.len(), 2),
+            arguments => panic!("Unexpected arguments {:?}", arguments),
+        }
+        assert_eq!(selector.specificity(), Specificity::new(1, 0, 1));
+
+        let selector = parse_selector_str(":is(div p, .note)").unwrap();
+        assert_eq!(selector.specificity(), Specificity::new(0, 1, 0));
+        let selector = parse_selector_str(":where(#main) a").unwrap();
+        assert_eq!(selector.specificity(), Specificity::new(0, 0, 1));
+
+        // the arguments must be valid
+        let selector = parse_selector_str("li:not(::before)").unwrap();
+        let (sequence, _) = &selector.values()[0];
+        assert!(sequence.values()[1].is_pseudo_class("not"));
+        assert_eq!(sequence.values()[1].arguments(), None);
+
+        let selector = parse_selector_str("li:NTH-CHILD(2n + 1)").unwrap();
+        let (sequence, _) = &selector.values()[0];
+        assert_eq!(
+            sequence.values()[1].arguments(),
+            Some(&PseudoClassArguments::Nth(AnPlusB::new(2, 1)))
+        );
+        assert_eq!(selector.specificity(), Specificity::new(0, 1, 1));
+
+        let selector = parse_selector_str(r#"q:lang(fr, "*-CH")"#).unwrap();
+        let (sequence, _) = &selector.values()[0];
+        assert_eq!(
+            sequence.values()[1].arguments(),
+            Some(&PseudoClassArguments::Languages(vec![
+                "fr".to_string(),
+                "*-CH".to_string()
+            ]))
+        );
+        assert_eq!(selector.specificity(), Specificity::new(0, 1, 1));
+        let selector = parse_selector_str("q:lang(fr en)").unwrap();
+        let (sequence, _) = &selector.values()[0];
+        assert_eq!(sequence.values()[1].arguments(), None);
+    }
+
+    #[test]
+    fn parse_an_plus_b_notations() {
+        let parse = |css: &str| {
+            let tokenizer = Tokenizer::new(css.chars());
+            let mut parser = Parser::<Token>::new(tokenizer.run());
+            parse_an_plus_b(&parser.parse_a_list_of_component_values())
+        };
+
+        assert_eq!(parse("odd"), Some(AnPlusB::new(2, 1)));
+        assert_eq!(parse("EVEN"), Some(AnPlusB::new(2, 0)));
+        assert_eq!(parse("5"), Some(AnPlusB::new(0, 5)));
+        assert_eq!(parse("-2"), Some(AnPlusB::new(0, -2)));
+        assert_eq!(parse("n"), Some(AnPlusB::new(1, 0)));
+        assert_eq!(parse("+n"), Some(AnPlusB::new(1, 0)));
+        assert_eq!(parse("-n+3"), Some(AnPlusB::new(-1, 3)));
+        assert_eq!(parse("3n-2"), Some(AnPlusB::new(3, -2)));
+        assert_eq!(parse("3n - 2"), Some(AnPlusB::new(3, -2)));
+        assert_eq!(parse("3n- 2"), Some(AnPlusB::new(3, -2)));
+        assert_eq!(parse("3n +2"), Some(AnPlusB::new(3, 2)));
+        assert_eq!(parse("n-"), None);
+        assert_eq!(parse("3n - -2"), None);
+        assert_eq!(parse("2.5n"), None);
+        assert_eq!(parse("3 n"), None);
+    }
 }
@@ -1,4 +1,6 @@
+use atom::Atom;
 use std::cmp::{Ord, Ordering};
+use std::collections::HashMap;
 
 pub type SelectorData = Vec<(SimpleSelectorSequence, Option<Combinator>)>;
 
@@ -23,13 +25,67 @@ pub enum SimpleSelectorType {
     Attribute,
     Class,
     ID,
+    /// A pseudo-class, e.g. `:hover`
     Pseudo,
+    /// A pseudo-element, e.g. `::before`
+    PseudoElement,
+}
+
+/// The pseudo-elements generating boxes
+/// https://www.w3.org/TR/css-pseudo-4/#generated-content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum PseudoElement {
+    Before,
+    After,
 }
 
 #[derive(Debug, PartialEq)]
 pub struct SimpleSelector {
     type_: SimpleSelectorType,
-    value: Option<String>,
+    value: Option<Atom>,
+    /// The namespace of the elements matched by a type or universal selector
+    namespace: NamespaceConstraint,
+    /// The arguments of a functional pseudo-class, e.g. `:not(.hidden)`
+    arguments: Option<PseudoClassArguments>,
+}
+
+/// The arguments of a functional pseudo-class
+#[derive(Debug, PartialEq)]
+pub enum PseudoClassArguments {
+    /// The selectors of `:not()`, `:is()` & `:where()`
+    Selectors(Vec<Selector>),
+    /// The positions matched by `:nth-child()` & `:nth-last-child()`
+    Nth(AnPlusB),
+    /// The language ranges of `:lang()`, e.g. `en` or `*-CH`
+    Languages(Vec<String>),
+}
+
+/// The positions `An+B` for every positive or zero integer `n`, counted
+/// from 1
+/// https://www.w3.org/TR/css-syntax-3/#anb-microsyntax
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AnPlusB {
+    pub a: i32,
+    pub b: i32,
+}
+
+/// The namespaces matched by a type or universal selector
+/// https://www.w3.org/TR/selectors-3/#typenmsp
+#[derive(Debug, Clone, PartialEq)]
+pub enum NamespaceConstraint {
+    /// `*|E`, or `E` without a default namespace
+    Any,
+    /// `|E`, the elements without a namespace
+    None,
+    /// `ns|E`, or `E` with a default namespace
+    Url(Atom),
+}
+
+/// The namespaces declared by the `@namespace` rules of a stylesheet
+#[derive(Debug, Default)]
+pub struct NamespaceMap {
+    default: Option<Atom>,
+    prefixes: HashMap<Atom, Atom>,
 }
 
 /// CSS Selector specificity
@@ -69,6 +125,16 @@ impl Selector {
         &self.0
     }
 
+    /// The name of the pseudo-element the selector matches, if any
+    pub fn pseudo_element(&self) -> Option<&Atom> {
+        let (subject, _) = self.values().last()?;
+        subject
+            .values()
+            .iter()
+            .find(|simple| *simple.selector_type() == SimpleSelectorType::PseudoElement)
+            .and_then(|simple| simple.value().as_ref())
+    }
+
     pub fn specificity(&self) -> Specificity {
         let (a, b, c) = self.values().iter().fold((0, 0, 0), |acc, (selector, _)| {
             let specificity = selector.specificity();
@@ -97,10 +163,29 @@ impl SimpleSelectorSequence {
                 .iter()
                 .fold((0, 0, 0), |acc, curr| match curr.selector_type() {
                     SimpleSelectorType::ID => (acc.0 + 1, acc.1, acc.2),
+                    // `:not()` & `:is()` are as specific as their most
+                    // specific argument, `:where()` is never specific
+                    SimpleSelectorType::Pseudo => match curr.arguments() {
+                        Some(PseudoClassArguments::Selectors(selectors)) => {
+                            let Specificity(a, b, c) = if curr.is_pseudo_class("where") {
+                                Specificity::new(0, 0, 0)
+                            } else {
+                                selectors
+                                    .iter()
+                                    .map(|selector| selector.specificity())
+                                    .max()
+                                    .unwrap_or_else(|| Specificity::new(0, 0, 0))
+                            };
+                            (acc.0 + a, acc.1 + b, acc.2 + c)
+                        }
+                        _ => (acc.0, acc.1 + 1, acc.2),
+                    },
                     SimpleSelectorType::Class | SimpleSelectorType::Attribute => {
                         (acc.0, acc.1 + 1, acc.2)
                     }
-                    SimpleSelectorType::Type => (acc.0, acc.1, acc.2 + 1),
+                    SimpleSelectorType::Type | SimpleSelectorType::PseudoElement => {
+                        (acc.0, acc.1, acc.2 + 1)
+                    }
                     _ => acc,
                 });
         Specificity(a, b, c)
@@ -108,17 +193,114 @@ impl SimpleSelectorSequence {
 }
 
 impl SimpleSelector {
-    pub fn new(type_: SimpleSelectorType, value: Option<String>) -> Self {
-        Self { type_, value }
+    pub fn new(type_: SimpleSelectorType, value: Option<Atom>) -> Self {
+        Self {
+            type_,
+            value,
+            namespace: NamespaceConstraint::Any,
+            arguments: None,
+        }
     }
 
-    pub fn value(&self) -> &Option<String> {
+    /// A functional pseudo-class, e.g. `:nth-child(2n+1)`
+    pub fn with_arguments(name: Atom, arguments: PseudoClassArguments) -> Self {
+        Self {
+            type_: SimpleSelectorType::Pseudo,
+            value: Some(name),
+            namespace: NamespaceConstraint::Any,
+            arguments: Some(arguments),
+        }
+    }
+
+    pub fn with_namespace(
+        type_: SimpleSelectorType,
+        value: Option<Atom>,
+        namespace: NamespaceConstraint,
+    ) -> Self {
+        Self {
+            type_,
+            value,
+            namespace,
+            arguments: None,
+        }
+    }
+
+    pub fn namespace(&self) -> &NamespaceConstraint {
+        &self.namespace
+    }
+
+    pub fn value(&self) -> &Option<Atom> {
         &self.value
     }
 
     pub fn selector_type(&self) -> &SimpleSelectorType {
         &self.type_
     }
+
+    pub fn arguments(&self) -> Option<&PseudoClassArguments> {
+        self.arguments.as_ref()
+    }
+
+    /// Whether the selector is the pseudo-class of a name
+    pub fn is_pseudo_class(&self, name: &str) -> bool {
+        self.type_ == SimpleSelectorType::Pseudo
+            && self.value.as_ref().map_or(false, |value| value == name)
+    }
+}
+
+impl PseudoElement {
+    pub fn name(&self) -> &'static str {
+        match self {
+            PseudoElement::Before => "before",
+            PseudoElement::After => "after",
+        }
+    }
+}
+
+impl AnPlusB {
+    pub fn new(a: i32, b: i32) -> Self {
+        Self { a, b }
+    }
+
+    /// Whether a position, counted from 1, is one of the positions
+    pub fn matches(&self, position: i32) -> bool {
+        if self.a == 0 {
+            return position == self.b;
+        }
+        let steps = position - self.b;
+        steps % self.a == 0 && steps / self.a >= 0
+    }
+}
+
+impl NamespaceMap {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Declare the default namespace, or the namespace of a prefix
+    pub fn declare(&mut self, prefix: Option<Atom>, url: Atom) {
+        match prefix {
+            Some(prefix) => {
+                self.prefixes.insert(prefix, url);
+            }
+            None => self.default = Some(url),
+        }
+    }
+
+    /// The namespace of the type selectors without a prefix
+    pub fn default_namespace(&self) -> NamespaceConstraint {
+        match &self.default {
+            Some(url) => NamespaceConstraint::Url(url.clone()),
+            None => NamespaceConstraint::Any,
+        }
+    }
+
+    /// The namespace of a prefix, if it is declared
+    pub fn resolve(&self, prefix: &str) -> Option<NamespaceConstraint> {
+        self.prefixes
+            .get(prefix)
+            .map(|url| NamespaceConstraint::Url(url.clone()))
+    }
 }
 
 #[cfg(test)]
@@ -130,4 +312,17 @@ mod tests {
         let b = Specificity::new(0, 0, 1);
         assert!(a < b);
     }
+
+    #[test]
+    fn match_positions() {
+        let odd = AnPlusB::new(2, 1);
+        assert!(odd.matches(1) && odd.matches(5) && !odd.matches(4));
+        let first_three = AnPlusB::new(-1, 3);
+        assert!(first_three.matches(3) && first_three.matches(1));
+        assert!(!first_three.matches(4));
+        let second = AnPlusB::new(0, 2);
+        assert!(second.matches(2) && !second.matches(4));
+        let from_fourth = AnPlusB::new(1, 4);
+        assert!(!from_fourth.matches(3) && from_fourth.matches(9));
+    }
 }
@@ -1,34 +1,15 @@
 pub mod token;
 
+use atom::Atom;
+use io::parse_error::{ParseError, ParseErrorSinkRef};
+use io::position::SourceSpan;
 use io::{data_stream::DataStream, input_stream::CharInputStream};
 use regex::Regex;
-use std::env;
 use std::str::FromStr;
 use token::HashType;
 use token::NumberType;
 use token::Token;
 
-fn is_trace() -> bool {
-    match env::var("TRACE_CSS_TOKENIZER") {
-        Ok(s) => s == "true" || s == "",
-        _ => false,
-    }
-}
-
-macro_rules! trace {
-    ($err:expr) => {
-        println!("[ParseError][CSS Tokenization]: {}", $err);
-    };
-}
-
-macro_rules! emit_error {
-    ($err:expr) => {
-        if is_trace() {
-            trace!($err)
-        }
-    };
-}
-
 // TODO: replace with char::REPLACEMENT_CHARACTER when stable
 const REPLACEMENT_CHARACTER: char = '\u{FFFD}';
 
@@ -163,6 +144,9 @@ where
 
     /// Output tokens
     output: Vec<Token>,
+
+    /// Where to report parse errors. Errors are dropped if not set
+    error_sink: Option<ParseErrorSinkRef>,
 }
 
 impl<T> Tokenizer<T>
@@ -174,18 +158,34 @@ where
             input: CharInputStream::new(input),
             current_character: '\0',
             output: Vec::new(),
+            error_sink: None,
         }
     }
 
+    pub fn set_error_sink(&mut self, sink: ParseErrorSinkRef) {
+        self.error_sink = Some(sink);
+    }
+
     /// Constantly running the tokenizer and produce a list of tokens
-    pub fn run(mut self) -> DataStream<Token> {
+    pub fn run(self) -> DataStream<Token> {
+        self.run_with_spans().0
+    }
+
+    /// Same as `run()`, but also return the source span of each token
+    pub fn run_with_spans(mut self) -> (DataStream<Token>, Vec<SourceSpan>) {
+        let mut spans = Vec::new();
         loop {
+            // comments are not part of any token
+            self.consume_comments();
+
+            let start = self.input.next_position();
             let token = self.consume_token();
-            self.output.push(token.clone());
+            spans.push(SourceSpan::new(start, self.input.next_position()));
+            let is_eof = token == Token::EOF;
+            self.output.push(token);
 
-            match token {
-                Token::EOF => return DataStream::new(self.output),
-                _ => {}
+            if is_eof {
+                return (DataStream::new(self.output), spans);
             }
         }
     }
@@ -214,6 +214,13 @@ where
     fn reconsume(&mut self) {
         self.input.reconsume();
     }
+
+    fn emit_error(&self, code: &'static str, message: &str) {
+        if let Some(sink) = &self.error_sink {
+            let error = ParseError::new("css-tokenizer", code, message).at(self.input.position());
+            sink.borrow_mut().report(error);
+        }
+    }
 }
 
 impl<T> Tokenizer<T>
@@ -326,7 +333,7 @@ where
                         return self.consume_ident_like();
                     }
                 }
-                emit_error!("Unexpected escape sequence");
+                self.emit_error("invalid-escape", "Unexpected escape sequence");
                 return Token::Delim(self.current_character);
             }
             Char::ch(']') => Token::BracketClose,
@@ -363,7 +370,10 @@ where
                             self.consume_next();
                         }
                     } else {
-                        emit_error!("Unexpected EOF while consume_comments");
+                        self.emit_error(
+                            "eof-in-comment",
+                            "Unexpected EOF while consuming a comment",
+                        );
                         break 'outer;
                     }
                 }
@@ -479,30 +489,33 @@ where
             if let Some('(') = self.input.peek() {
                 self.consume_next();
                 loop {
-                    if let Some(next_2_chars) = self.input.peek_next_as::<String>(2) {
-                        let mut chars = next_2_chars.chars();
-                        let first = chars.next().unwrap();
-                        let second = chars.next().unwrap();
-                        if is_whitespace(first) && is_whitespace(second) {
+                    let next_2_chars = self.peek_chars(2);
+                    let mut chars = next_2_chars.chars();
+                    match (chars.next(), chars.next()) {
+                        (Some(first), Some(second))
+                            if is_whitespace(first) && is_whitespace(second) =>
+                        {
                             self.consume_next();
-                        } else {
-                            break;
                         }
+                        _ => break,
                     }
                 }
-                if let Some(next_2_chars) = self.input.peek_next_as::<String>(2) {
-                    let re = Regex::new("^ ?('|\")$").unwrap();
-                    if re.is_match(&next_2_chars) {
-                        return Token::Function(string);
+                // a quoted URL is a function whose argument is a string
+                let next_2_chars = self.peek_chars(2);
+                let mut chars = next_2_chars.chars();
+                return match (chars.next(), chars.next()) {
+                    (Some('"' | '\''), _) => Token::Function(string),
+                    (Some(first), Some('"' | '\'')) if is_whitespace(first) => {
+                        Token::Function(string)
                     }
-                    return self.consume_url();
-                }
+                    _ => self.consume_url(),
+                };
             }
         }
         if let Some('(') = self.input.peek() {
             return Token::Function(string);
         }
-        return Token::Ident(string);
+        return Token::Ident(Atom::from(string));
     }
 
     fn consume_string(&mut self, ending: Option<char>) -> Token {
@@ -519,11 +532,11 @@ where
                     return token;
                 }
                 Char::eof => {
-                    emit_error!("Unexpected EOF");
+                    self.emit_error("eof-in-string", "Unexpected EOF");
                     return token;
                 }
                 Char::ch('\n') => {
-                    emit_error!("Unexpected newline");
+                    self.emit_error("newline-in-string", "Unexpected newline");
                     self.reconsume();
                     return Token::BadStr;
                 }
@@ -545,6 +558,14 @@ where
         }
     }
 
+    /// The next characters of the input, fewer than `n` at its end
+    fn peek_chars(&mut self, n: usize) -> String {
+        (1..=n)
+            .rev()
+            .find_map(|n| self.input.peek_next_as::<String>(n))
+            .unwrap_or_default()
+    }
+
     fn consume_url(&mut self) -> Token {
         let mut token = Token::Url(String::new());
         self.consume_while(is_whitespace);
@@ -552,29 +573,33 @@ where
             match self.consume_next() {
                 Char::ch(')') => return token,
                 Char::eof => {
-                    emit_error!("Unexpected EOF");
+                    self.emit_error("eof-in-url", "Unexpected EOF");
                     return token;
                 }
                 Char::ch(c) if is_whitespace(c) => {
                     self.consume_while(is_whitespace);
                     if let Some(c) = self.input.peek() {
                         if c == ')' {
+                            self.consume_next();
                             return token;
                         }
                     } else {
-                        emit_error!("Unexpected EOF");
+                        self.emit_error("eof-in-url", "Unexpected EOF");
                         return token;
                     }
                     self.consume_bad_url();
                     return Token::BadUrl;
                 }
                 Char::ch('"') | Char::ch('\'') | Char::ch('(') => {
-                    emit_error!("Unexpected character");
+                    self.emit_error("unexpected-character-in-url", "Unexpected character");
                     self.consume_bad_url();
                     return Token::BadUrl;
                 }
                 Char::ch(c) if is_non_printable(c) => {
-                    emit_error!("Unexpected non-printable character");
+                    self.emit_error(
+                        "non-printable-character-in-url",
+                        "Unexpected non-printable character",
+                    );
                     self.consume_bad_url();
                     return Token::BadUrl;
                 }
@@ -583,7 +608,7 @@ where
                         if is_valid_escape(&format!("\\{}", c)) {
                             token.append_to_url_token(self.consume_escaped());
                         } else {
-                            emit_error!("Unexpected escape sequence");
+                            self.emit_error("invalid-escape-in-url", "Unexpected escape sequence");
                             self.consume_bad_url();
                             return Token::BadUrl;
                         }
@@ -614,26 +639,19 @@ where
         let ch = self.consume_next();
         match ch {
             Char::eof => {
-                emit_error!("Unexpected EOF");
+                self.emit_error("eof-in-escape", "Unexpected EOF");
                 REPLACEMENT_CHARACTER
             }
             Char::ch(c) if c.is_ascii_hexdigit() => {
                 let mut hex_value: u32 = c.to_digit(16).unwrap();
+                // the code point following the hex digits isn't part of the escape
                 for _ in 0..5 {
-                    match self.consume_next() {
-                        Char::ch(c) => {
-                            if c.is_ascii_hexdigit() {
-                                hex_value *= 16;
-                                hex_value += c.to_digit(16).unwrap();
-                                continue;
-                            }
-                            break;
-                        }
-                        Char::eof => {
-                            emit_error!("Unexpected EOF");
-                            hex_value = 0xFFFD;
-                            break;
+                    match self.input.peek().and_then(|c| c.to_digit(16)) {
+                        Some(digit) => {
+                            self.consume_next();
+                            hex_value = hex_value * 16 + digit;
                         }
+                        None => break,
                     }
                 }
                 if let Some(c) = self.input.peek() {
@@ -660,6 +678,38 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn report_parse_errors() {
+        let css = "a {\n  content: \"abc".chars();
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let mut tokenizer = Tokenizer::new(css);
+        tokenizer.set_error_sink(errors.clone());
+        tokenizer.run();
+
+        let errors = errors.borrow();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "eof-in-string");
+        assert_eq!(errors[0].position.map(|p| p.line), Some(2));
+    }
+
+    #[test]
+    fn tokenize_escapes() {
+        let mut tokenizer = Tokenizer::new(r#""\f101" \2192 x"#.chars());
+        assert_eq!(
+            tokenizer.consume_token(),
+            Token::Str("\u{f101}".to_string())
+        );
+        assert_eq!(tokenizer.consume_token(), Token::Whitespace);
+        // a whitespace after the hex digits ends the escape
+        assert_eq!(
+            tokenizer.consume_token(),
+            Token::Ident(Atom::from("\u{2192}x"))
+        );
+        assert_eq!(tokenizer.consume_token(), Token::EOF);
+    }
 
     #[test]
     fn tokenize_simple_css() {
@@ -678,7 +728,7 @@ mod tests {
         assert_eq!(tokenizer.consume_token(), Token::Delim('.'));
         assert_eq!(
             tokenizer.consume_token(),
-            Token::Ident("class_selector".to_string())
+            Token::Ident(Atom::from("class_selector"))
         );
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
 
@@ -686,17 +736,17 @@ mod tests {
 
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
 
-        assert_eq!(tokenizer.consume_token(), Token::Ident("color".to_string()));
+        assert_eq!(tokenizer.consume_token(), Token::Ident(Atom::from("color")));
         assert_eq!(tokenizer.consume_token(), Token::Colon);
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
-        assert_eq!(tokenizer.consume_token(), Token::Ident("red".to_string()));
+        assert_eq!(tokenizer.consume_token(), Token::Ident(Atom::from("red")));
         assert_eq!(tokenizer.consume_token(), Token::Semicolon);
 
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
 
         assert_eq!(
             tokenizer.consume_token(),
-            Token::Ident("background".to_string())
+            Token::Ident(Atom::from("background"))
         );
         assert_eq!(tokenizer.consume_token(), Token::Colon);
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
@@ -712,6 +762,41 @@ mod tests {
         assert_eq!(tokenizer.consume_token(), Token::EOF);
     }
 
+    #[test]
+    fn tokenize_urls() {
+        let tokens = |css: &str| {
+            let mut tokenizer = Tokenizer::new(css.chars());
+            let mut tokens = Vec::new();
+            loop {
+                match tokenizer.consume_token() {
+                    Token::EOF => return tokens,
+                    token => tokens.push(token),
+                }
+            }
+        };
+        assert_eq!(
+            tokens("url(  a.png  )"),
+            vec![Token::Url("a.png".to_string())]
+        );
+        assert_eq!(
+            tokens("URL(a\\)b.png)"),
+            vec![Token::Url("a)b.png".to_string())]
+        );
+        assert_eq!(
+            tokens("url(\t'a b.png')"),
+            vec![
+                Token::Function("url".to_string()),
+                Token::Whitespace,
+                Token::Str("a b.png".to_string()),
+                Token::ParentheseClose
+            ]
+        );
+        assert_eq!(tokens("url(a b)"), vec![Token::BadUrl]);
+        // the URLs cut by the end of the input end there
+        assert_eq!(tokens("url()"), vec![Token::Url(String::new())]);
+        assert_eq!(tokens("url(a"), vec![Token::Url("a".to_string())]);
+    }
+
     #[test]
     fn tokenize_css_function() {
         let css = r"#id_selector .class_selector {
@@ -728,7 +813,7 @@ mod tests {
         assert_eq!(tokenizer.consume_token(), Token::Delim('.'));
         assert_eq!(
             tokenizer.consume_token(),
-            Token::Ident("class_selector".to_string())
+            Token::Ident(Atom::from("class_selector"))
         );
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
 
@@ -736,7 +821,7 @@ mod tests {
 
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
 
-        assert_eq!(tokenizer.consume_token(), Token::Ident("color".to_string()));
+        assert_eq!(tokenizer.consume_token(), Token::Ident(Atom::from("color")));
         assert_eq!(tokenizer.consume_token(), Token::Colon);
         assert_eq!(tokenizer.consume_token(), Token::Whitespace);
         assert_eq!(
@@ -1,6 +1,8 @@
+use atom::Atom;
+
 #[derive(Clone, Debug, PartialEq)]
 pub enum Token {
-    Ident(String),
+    Ident(Atom),
     Function(String),
     AtKeyword(String),
     Hash(String, HashType),
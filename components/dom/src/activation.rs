@@ -0,0 +1,195 @@
+/// Activation of elements: what happens when an element is clicked, or
+/// activated with the keyboard while it's focused.
+/// https://html.spec.whatwg.org/multipage/interaction.html#activation
+use super::dom_ref::NodeRef;
+use super::elements::{ElementData, InputType};
+use super::event::{KeyEventKind, KeyboardEvent};
+use super::forms;
+
+/// Run the activation behavior of an element. Checking a radio button
+/// unchecks the other radio buttons of its group, submit buttons submit
+/// their form & links are followed.
+pub fn activate(node: &NodeRef) {
+    match node.borrow_mut().as_element_mut_opt() {
+        Some(element) => element.activate(),
+        None => return,
+    }
+
+    if follow_link(node) {
+        return;
+    }
+
+    if forms::is_submit_button(node) {
+        if let Some(form) = forms::form_owner(node) {
+            forms::submit(&form, Some(node));
+        }
+        return;
+    }
+
+    let group = match node.borrow().as_element().data() {
+        ElementData::Input(input) if input.input_type() == InputType::Radio && input.checked() => {
+            input.name().to_string()
+        }
+        _ => return,
+    };
+    if group.is_empty() {
+        return;
+    }
+    let root = node.borrow().owner_document();
+    if let Some(root) = root {
+        uncheck_radio_group(&root, &group, node);
+    }
+}
+
+/// Follow the link of an anchor, the page navigating to its URL resolved
+/// against the base URL of the document. Returns whether the element is
+/// a link.
+/// https://html.spec.whatwg.org/multipage/links.html#following-hyperlinks-2
+fn follow_link(node: &NodeRef) -> bool {
+    let href = match node.borrow().as_element().data() {
+        ElementData::Anchor(anchor) => match anchor.href() {
+            Some(href) => href.to_string(),
+            None => return false,
+        },
+        _ => return false,
+    };
+    let document = match node.borrow().owner_document() {
+        Some(document) => document,
+        None => return true,
+    };
+    let url = document.borrow().as_document().parse_url(&href);
+    match url {
+        Some(url) => document.borrow_mut().as_document_mut().set_navigation(url),
+        None => log::info!("Invalid link URL: {}", href),
+    }
+    true
+}
+
+/// Whether a node is a link: an anchor with an href
+pub fn is_link(node: &NodeRef) -> bool {
+    match node.borrow().as_element_opt().map(|element| element.data()) {
+        Some(ElementData::Anchor(anchor)) => anchor.href().is_some(),
+        _ => false,
+    }
+}
+
+/// The closest inclusive ancestor of a node that's a link, e.g. the link
+/// the mouse is over when it's over the text of the link
+pub fn link_ancestor(node: &NodeRef) -> Option<NodeRef> {
+    let mut current = Some(node.clone());
+    while let Some(node) = current {
+        if is_link(&node) {
+            return Some(node);
+        }
+        current = node.borrow().parent();
+    }
+    None
+}
+
+/// Whether a key event activates an element: releasing the space key on
+/// buttons, checkboxes & radio buttons, or pressing enter on buttons &
+/// links
+pub fn is_activation_key(node: &NodeRef, event: &KeyboardEvent) -> bool {
+    let node = node.borrow();
+    let data = match node.as_element_opt() {
+        Some(element) => element.data(),
+        None => return false,
+    };
+    match (event.kind, event.key.as_str(), data) {
+        (KeyEventKind::KeyUp, " ", ElementData::Button(_)) => true,
+        (KeyEventKind::KeyDown, "Enter", ElementData::Button(_) | ElementData::Anchor(_)) => true,
+        (KeyEventKind::KeyUp, " ", ElementData::Input(input)) => {
+            input.input_type() != InputType::Text
+        }
+        _ => false,
+    }
+}
+
+// TODO: limit the group to the form of the radio button when we support forms
+fn uncheck_radio_group(node: &NodeRef, group: &str, checked: &NodeRef) {
+    if node != checked {
+        if let Some(element) = node.borrow_mut().as_element_mut_opt() {
+            if let ElementData::Input(input) = element.data_mut() {
+                if input.input_type() == InputType::Radio && input.name() == group {
+                    input.set_checked(false);
+                }
+            }
+        }
+    }
+    for child in node.borrow().child_nodes() {
+        uncheck_radio_group(&child, group, checked);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::element_factory::create_element;
+    use crate::node::{Node, NodeData};
+    use crate::text::Text;
+
+    fn radio(document: &NodeRef, name: &str) -> NodeRef {
+        let node = create_element(document.clone().downgrade(), "input");
+        Node::set_attribute(&node, "type", "radio");
+        Node::set_attribute(&node, "name", name);
+        Node::append_child(document.clone(), node.clone());
+        node
+    }
+
+    fn is_checked(node: &NodeRef) -> bool {
+        match node.borrow().as_element().data() {
+            ElementData::Input(input) => input.checked(),
+            _ => false,
+        }
+    }
+
+    #[test]
+    fn check_radio_group() {
+        let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let first = radio(&document, "color");
+        let second = radio(&document, "color");
+        let other = radio(&document, "size");
+
+        activate(&first);
+        activate(&other);
+        assert!(is_checked(&first));
+        activate(&second);
+        assert!(!is_checked(&first));
+        assert!(is_checked(&second));
+        // radio buttons of other groups stay checked
+        assert!(is_checked(&other));
+
+        assert!(is_activation_key(&second, &KeyboardEvent::key_up(" ")));
+        assert!(!is_activation_key(
+            &second,
+            &KeyboardEvent::key_down("Enter")
+        ));
+    }
+
+    #[test]
+    fn follow_links() {
+        let mut page = Document::new();
+        page.set_url(url::Url::parse("https://moon.dev/docs/").unwrap());
+        let document = NodeRef::new(Node::new(NodeData::Document(page)));
+        let link = create_element(document.clone().downgrade(), "a");
+        Node::append_child(document.clone(), link.clone());
+
+        activate(&link);
+        assert!(!is_link(&link));
+        assert!(document
+            .borrow_mut()
+            .as_document_mut()
+            .take_navigation()
+            .is_none());
+
+        Node::set_attribute(&link, "href", " ../about.html ");
+        let text = NodeRef::new(Node::new(NodeData::Text(Text::new("About".to_string()))));
+        Node::append_child(link.clone(), text.clone());
+        assert_eq!(link_ancestor(&text), Some(link.clone()));
+        assert!(is_activation_key(&link, &KeyboardEvent::key_down("Enter")));
+        activate(&link);
+        let url = document.borrow_mut().as_document_mut().take_navigation();
+        assert_eq!(url.unwrap().raw(), "https://moon.dev/about.html");
+    }
+}
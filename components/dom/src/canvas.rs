@@ -0,0 +1,383 @@
+/// The 2D context of a `<canvas>` element. The drawing methods record
+/// commands with the state of the context at the time they are called &
+/// the commands are rasterized into the bitmap of the canvas when it's
+/// painted.
+/// https://html.spec.whatwg.org/multipage/canvas.html#canvasrenderingcontext2d
+use super::images::Image;
+use std::cell::RefCell;
+use std::f32::consts::PI;
+use std::rc::Rc;
+use svg::{parse_color, stroke_polygons, Canvas, Color, FillRule, Path, Point};
+
+/// The distance a flattened curve may be off the curve, in pixels
+const TOLERANCE: f32 = 0.1;
+
+type Rect = (f32, f32, f32, f32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum CanvasCommand {
+    FillRect(Rect, Color),
+    /// The outline of a rect, with a line width
+    StrokeRect(Rect, Color, f32),
+    ClearRect(Rect),
+    FillPath(Path, FillRule, Color),
+    StrokePath(Path, Color, f32),
+    /// RGBA pixels stretched over a rect
+    DrawImage {
+        pixels: Rc<Vec<u8>>,
+        width: u32,
+        height: u32,
+        rect: Rect,
+    },
+    /// A text with the left of its baseline at a point
+    FillText(String, Point, Color),
+}
+
+#[derive(Debug)]
+pub struct CanvasRenderingContext2D {
+    width: u32,
+    height: u32,
+    fill_style: Color,
+    stroke_style: Color,
+    line_width: f32,
+    /// The current default path
+    path: Path,
+    commands: Vec<CanvasCommand>,
+    bitmap: RefCell<Bitmap>,
+}
+
+/// The pixels of the canvas with the commands drawn so far
+struct Bitmap {
+    canvas: Canvas,
+    /// The number of commands drawn on the canvas
+    drawn: usize,
+    pixels: Rc<Vec<u8>>,
+}
+
+impl std::fmt::Debug for Bitmap {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "Bitmap({} commands drawn)", self.drawn)
+    }
+}
+
+impl Bitmap {
+    fn new(width: u32, height: u32) -> Self {
+        let canvas = Canvas::new(width, height);
+        let pixels = Rc::new(canvas.to_rgba());
+        Self {
+            canvas,
+            drawn: 0,
+            pixels,
+        }
+    }
+}
+
+impl CanvasRenderingContext2D {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            fill_style: Color::black(),
+            stroke_style: Color::black(),
+            line_width: 1.,
+            path: Path::new(),
+            commands: Vec::new(),
+            bitmap: RefCell::new(Bitmap::new(width, height)),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.width
+    }
+
+    pub fn height(&self) -> u32 {
+        self.height
+    }
+
+    /// The commands recorded since the canvas was created or resized
+    pub fn commands(&self) -> &[CanvasCommand] {
+        &self.commands
+    }
+
+    /// Set the color of the fills. Invalid colors are ignored.
+    pub fn set_fill_style(&mut self, color: &str) {
+        if let Some(color) = parse_color(color) {
+            self.fill_style = color;
+        }
+    }
+
+    pub fn set_stroke_style(&mut self, color: &str) {
+        if let Some(color) = parse_color(color) {
+            self.stroke_style = color;
+        }
+    }
+
+    /// Set the width of the strokes. Widths that aren't positive are ignored.
+    pub fn set_line_width(&mut self, width: f32) {
+        if width > 0. && width.is_finite() {
+            self.line_width = width;
+        }
+    }
+
+    pub fn fill_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.commands.push(CanvasCommand::FillRect(
+            (x, y, width, height),
+            self.fill_style,
+        ));
+    }
+
+    pub fn stroke_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.commands.push(CanvasCommand::StrokeRect(
+            (x, y, width, height),
+            self.stroke_style,
+            self.line_width,
+        ));
+    }
+
+    pub fn clear_rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        self.commands
+            .push(CanvasCommand::ClearRect((x, y, width, height)));
+    }
+
+    pub fn begin_path(&mut self) {
+        self.path = Path::new();
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.path.move_to(x, y);
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) {
+        self.ensure_subpath(x, y);
+        self.path.line_to(x, y);
+    }
+
+    pub fn quadratic_curve_to(&mut self, cpx: f32, cpy: f32, x: f32, y: f32) {
+        self.ensure_subpath(cpx, cpy);
+        self.path.quad_to(cpx, cpy, x, y);
+    }
+
+    pub fn bezier_curve_to(&mut self, cp1x: f32, cp1y: f32, cp2x: f32, cp2y: f32, x: f32, y: f32) {
+        self.ensure_subpath(cp1x, cp1y);
+        self.path.cubic_to(cp1x, cp1y, cp2x, cp2y, x, y);
+    }
+
+    /// Add a circular arc around a center, from an angle to another in
+    /// radians, connected to the current subpath by a line
+    pub fn arc(
+        &mut self,
+        x: f32,
+        y: f32,
+        radius: f32,
+        start_angle: f32,
+        end_angle: f32,
+        anticlockwise: bool,
+    ) {
+        let point = |angle: f32| (x + radius * angle.cos(), y + radius * angle.sin());
+        let start = point(start_angle);
+        match self.current_point() {
+            Some(_) => self.path.line_to(start.0, start.1),
+            None => self.path.move_to(start.0, start.1),
+        }
+        if radius <= 0. {
+            return;
+        }
+
+        let mut sweep = if anticlockwise {
+            start_angle - end_angle
+        } else {
+            end_angle - start_angle
+        };
+        if sweep >= 2. * PI {
+            sweep = 2. * PI;
+        } else {
+            sweep = sweep.rem_euclid(2. * PI);
+        }
+        let direction = if anticlockwise { -1. } else { 1. };
+
+        // a full circle is drawn as two halves, an arc can't end where
+        // it starts
+        let mut from = start;
+        let mut angle = start_angle;
+        let mut remaining = sweep;
+        while remaining > 0. {
+            let step = remaining.min(PI);
+            angle += step * direction;
+            let to = point(angle);
+            self.path
+                .arc_to(from, radius, radius, 0., false, !anticlockwise, to);
+            from = to;
+            remaining -= step;
+        }
+    }
+
+    pub fn rect(&mut self, x: f32, y: f32, width: f32, height: f32) {
+        add_rect(&mut self.path, &(x, y, width, height));
+    }
+
+    pub fn close_path(&mut self) {
+        if !self.path.is_empty() {
+            self.path.close();
+        }
+    }
+
+    pub fn fill(&mut self, rule: FillRule) {
+        self.commands.push(CanvasCommand::FillPath(
+            self.path.clone(),
+            rule,
+            self.fill_style,
+        ));
+    }
+
+    pub fn stroke(&mut self) {
+        self.commands.push(CanvasCommand::StrokePath(
+            self.path.clone(),
+            self.stroke_style,
+            self.line_width,
+        ));
+    }
+
+    /// Draw an image stretched over a rect
+    pub fn draw_image(&mut self, image: &Image, x: f32, y: f32, width: f32, height: f32) {
+        let (pixel_width, pixel_height) = (width.abs().round(), height.abs().round());
+        if pixel_width < 1. || pixel_height < 1. {
+            return;
+        }
+        let (pixel_width, pixel_height) = (pixel_width as u32, pixel_height as u32);
+        self.commands.push(CanvasCommand::DrawImage {
+            pixels: image.rasterize(pixel_width, pixel_height),
+            width: pixel_width,
+            height: pixel_height,
+            rect: (x, y, width, height),
+        });
+    }
+
+    pub fn fill_text(&mut self, text: &str, x: f32, y: f32) {
+        self.commands.push(CanvasCommand::FillText(
+            text.to_string(),
+            (x, y),
+            self.fill_style,
+        ));
+    }
+
+    /// The RGBA pixels of the canvas, row by row. Only the commands
+    /// recorded since the last call are drawn.
+    pub fn pixels(&self) -> Rc<Vec<u8>> {
+        let mut bitmap = self.bitmap.borrow_mut();
+        if bitmap.drawn == self.commands.len() {
+            return bitmap.pixels.clone();
+        }
+        for command in &self.commands[bitmap.drawn..] {
+            draw(&mut bitmap.canvas, command);
+        }
+        bitmap.drawn = self.commands.len();
+        bitmap.pixels = Rc::new(bitmap.canvas.to_rgba());
+        bitmap.pixels.clone()
+    }
+
+    /// Start a subpath at a point if there's none, like the drawing
+    /// methods do when the path is empty
+    fn ensure_subpath(&mut self, x: f32, y: f32) {
+        if self.path.is_empty() {
+            self.path.move_to(x, y);
+        }
+    }
+
+    fn current_point(&self) -> Option<Point> {
+        self.path.flatten(TOLERANCE).last()?.points.last().copied()
+    }
+}
+
+fn draw(canvas: &mut Canvas, command: &CanvasCommand) {
+    match command {
+        CanvasCommand::FillRect(rect, color) => {
+            let mut path = Path::new();
+            add_rect(&mut path, rect);
+            canvas.fill(&path.flatten(TOLERANCE), FillRule::NonZero, *color);
+        }
+        CanvasCommand::StrokeRect(rect, color, width) => {
+            let mut path = Path::new();
+            add_rect(&mut path, rect);
+            let outline = stroke_polygons(&path.flatten(TOLERANCE), *width);
+            canvas.fill(&outline, FillRule::NonZero, *color);
+        }
+        CanvasCommand::ClearRect(rect) => canvas.clear_rect(*rect),
+        CanvasCommand::FillPath(path, rule, color) => {
+            canvas.fill(&path.flatten(TOLERANCE), *rule, *color);
+        }
+        CanvasCommand::StrokePath(path, color, width) => {
+            let outline = stroke_polygons(&path.flatten(TOLERANCE), *width);
+            canvas.fill(&outline, FillRule::NonZero, *color);
+        }
+        CanvasCommand::DrawImage {
+            pixels,
+            width,
+            height,
+            rect,
+        } => canvas.draw_image(pixels, *width, *height, *rect),
+        // TODO: draw the glyphs when the painter supports fonts
+        CanvasCommand::FillText(..) => {}
+    }
+}
+
+fn add_rect(path: &mut Path, &(x, y, width, height): &Rect) {
+    path.move_to(x, y);
+    path.line_to(x + width, y);
+    path.line_to(x + width, y + height);
+    path.line_to(x, y + height);
+    path.close();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(context: &CanvasRenderingContext2D, x: u32, y: u32) -> [u8; 4] {
+        let index = ((y * context.width() + x) * 4) as usize;
+        let pixels = context.pixels();
+        [
+            pixels[index],
+            pixels[index + 1],
+            pixels[index + 2],
+            pixels[index + 3],
+        ]
+    }
+
+    #[test]
+    fn draw_commands() {
+        let mut context = CanvasRenderingContext2D::new(10, 10);
+        context.set_fill_style("red");
+        context.fill_rect(0., 0., 5., 5.);
+        assert_eq!(pixel(&context, 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&context, 7, 7), [0, 0, 0, 0]);
+
+        // the state is recorded with the commands
+        context.set_fill_style("#00f");
+        context.begin_path();
+        context.rect(5., 5., 5., 5.);
+        context.fill(FillRule::NonZero);
+        context.clear_rect(0., 0., 2., 2.);
+        assert_eq!(pixel(&context, 7, 7), [0, 0, 255, 255]);
+        assert_eq!(pixel(&context, 2, 2), [255, 0, 0, 255]);
+        assert_eq!(pixel(&context, 1, 1), [0, 0, 0, 0]);
+        assert_eq!(context.commands().len(), 3);
+    }
+
+    #[test]
+    fn arcs() {
+        let mut context = CanvasRenderingContext2D::new(20, 20);
+        context.arc(10., 10., 8., 0., 2. * PI, false);
+        context.fill(FillRule::NonZero);
+        assert_eq!(pixel(&context, 10, 10)[3], 255);
+        assert_eq!(pixel(&context, 10, 4)[3], 255);
+        assert_eq!(pixel(&context, 1, 1)[3], 0);
+
+        context.begin_path();
+        context.set_stroke_style("lime");
+        context.set_line_width(2.);
+        context.move_to(0., 19.);
+        context.line_to(20., 19.);
+        context.stroke();
+        assert_eq!(pixel(&context, 1, 19), [0, 255, 0, 255]);
+    }
+}
@@ -1,8 +1,16 @@
+/// The data of text & comment nodes. The offsets & counts of the methods
+/// are in characters, the counts are clamped to the end of the data.
+/// https://dom.spec.whatwg.org/#interface-characterdata
 #[derive(Debug)]
 pub struct CharacterData {
     data: String,
 }
 
+/// An offset is greater than the length of the data
+/// https://webidl.spec.whatwg.org/#indexsizeerror
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct IndexSizeError;
+
 impl CharacterData {
     pub fn new(data: String) -> Self {
         Self { data }
@@ -12,7 +20,77 @@ impl CharacterData {
         return self.data.clone();
     }
 
+    pub fn set_data(&mut self, data: String) {
+        self.data = data;
+    }
+
+    pub fn length(&self) -> usize {
+        self.data.chars().count()
+    }
+
+    /// The byte range of the characters from an offset
+    fn byte_range(&self, offset: usize, count: usize) -> Result<(usize, usize), IndexSizeError> {
+        let byte_offset = |offset: usize| {
+            self.data
+                .char_indices()
+                .map(|(index, _)| index)
+                .chain(std::iter::once(self.data.len()))
+                .nth(offset)
+        };
+        let start = byte_offset(offset).ok_or(IndexSizeError)?;
+        let end = byte_offset(offset.saturating_add(count)).unwrap_or_else(|| self.data.len());
+        Ok((start, end))
+    }
+
+    pub fn substring_data(&self, offset: usize, count: usize) -> Result<String, IndexSizeError> {
+        let (start, end) = self.byte_range(offset, count)?;
+        Ok(self.data[start..end].to_string())
+    }
+
     pub fn append_data(&mut self, data: &str) {
         self.data.push_str(data);
     }
+
+    pub fn insert_data(&mut self, offset: usize, data: &str) -> Result<(), IndexSizeError> {
+        self.replace_data(offset, 0, data)
+    }
+
+    pub fn delete_data(&mut self, offset: usize, count: usize) -> Result<(), IndexSizeError> {
+        self.replace_data(offset, count, "")
+    }
+
+    /// https://dom.spec.whatwg.org/#concept-cd-replace
+    pub fn replace_data(
+        &mut self,
+        offset: usize,
+        count: usize,
+        data: &str,
+    ) -> Result<(), IndexSizeError> {
+        let (start, end) = self.byte_range(offset, count)?;
+        self.data.replace_range(start..end, data);
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edit_data() {
+        let mut data = CharacterData::new("héllo".to_string());
+        assert_eq!(data.length(), 5);
+        assert_eq!(data.substring_data(1, 3), Ok("éll".to_string()));
+        assert_eq!(data.substring_data(3, 100), Ok("lo".to_string()));
+        assert_eq!(data.substring_data(6, 1), Err(IndexSizeError));
+
+        data.append_data(" world");
+        data.insert_data(0, "¡").unwrap();
+        data.delete_data(6, 6).unwrap();
+        data.replace_data(1, 1, "H").unwrap();
+        assert_eq!(data.get_data(), "¡Héllo");
+        assert_eq!(data.insert_data(7, "!"), Err(IndexSizeError));
+        data.insert_data(6, "!").unwrap();
+        assert_eq!(data.get_data(), "¡Héllo!");
+    }
 }
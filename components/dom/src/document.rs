@@ -1,14 +1,61 @@
 use super::document_loader::DocumentLoader;
+use super::dom_ref::{NodeRef, WeakNodeRef};
+use super::element::Namespace;
+use super::forms::FormSubmission;
+use super::images::{Image, ImageState};
 use super::node::NodeHooks;
+use super::visited::VisitedLinksRef;
 use css::cssom::stylesheet::StyleSheet;
+use css::parser::Parser;
+use css::tokenizer::{token::Token, Tokenizer};
+use io::parse_error::{ParseError, ParseErrorSinkRef};
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+use url::Url;
+
+/// A stylesheet being parsed on another thread, with the errors found while parsing it
+type StyleSheetParser = JoinHandle<(StyleSheet, Vec<ParseError>)>;
+
+/// A stylesheet keeping its place among the stylesheets of the document
+/// until it's appended: still loading, or being parsed
+struct PendingStyleSheet {
+    id: usize,
+    parser: Option<StyleSheetParser>,
+}
 
 pub struct Document {
     doctype: Option<DocumentType>,
     mode: QuirksMode,
+    /// The URL the document was loaded from, if any
+    url: Option<Url>,
+    /// The URL of the first `<base>` element with a `href`
+    base_element_url: Option<Url>,
+    /// The navigation requested by the first `<meta http-equiv=refresh>`
+    refresh: Option<Refresh>,
+    /// The shadow roots attached to the elements of the document
+    shadow_roots: Vec<WeakNodeRef>,
+    /// The name of the character encoding the document was decoded with
+    encoding: String,
     loader: Option<Rc<RefCell<dyn DocumentLoader>>>,
-    stylesheets: Vec<StyleSheet>,
+    /// Shared so the styles can be resolved without borrowing the document
+    stylesheets: Vec<Rc<StyleSheet>>,
+    /// The stylesheets still being loaded or parsed, in document order
+    pending_stylesheets: Vec<PendingStyleSheet>,
+    next_stylesheet_id: usize,
+    error_sink: Option<ParseErrorSinkRef>,
+    /// The element receiving the keyboard events
+    focused_element: Option<WeakNodeRef>,
+    /// The form submitted last, until its request is sent
+    form_submission: Option<FormSubmission>,
+    /// The URL of the link followed last, until the page navigates to it
+    navigation: Option<Url>,
+    /// The images used by the document, by URL
+    images: HashMap<String, ImageState>,
+    /// The URLs visited by the user, shared with the other documents
+    visited_links: Option<VisitedLinksRef>,
 }
 
 pub struct DocumentType {
@@ -17,6 +64,64 @@ pub struct DocumentType {
     system_id: String,
 }
 
+/// A navigation requested by a document after a delay since it was
+/// loaded, to the document itself if there's no URL
+#[derive(Debug, Clone)]
+pub struct Refresh {
+    pub delay: Duration,
+    pub url: Option<Url>,
+}
+
+/// Parse the content of a `<meta http-equiv=refresh>`, e.g. `5; url=/next`,
+/// into the delay & the URL to navigate to
+/// https://html.spec.whatwg.org/multipage/semantics.html#shared-declarative-refresh-steps
+pub fn parse_refresh(content: &str) -> Option<(Duration, Option<String>)> {
+    let input = content.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let digits_end = input
+        .find(|c: char| !c.is_ascii_digit())
+        .unwrap_or_else(|| input.len());
+    let (seconds, input) = input.split_at(digits_end);
+    let seconds = if seconds.is_empty() {
+        // a delay of `.5` is valid & rounded down to zero
+        if !input.starts_with('.') {
+            return None;
+        }
+        0
+    } else {
+        seconds.parse::<u64>().ok()?
+    };
+    let input = input.trim_start_matches(|c: char| c.is_ascii_digit() || c == '.');
+    let delay = Duration::from_secs(seconds);
+
+    let input = input.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    let input = match input.chars().next() {
+        None => return Some((delay, None)),
+        Some(';') | Some(',') => &input[1..],
+        Some(_) => return None,
+    };
+    let mut input = input.trim_start_matches(|c: char| c.is_ascii_whitespace());
+    if input.len() >= 3 && input[..3].eq_ignore_ascii_case("url") {
+        let rest = input[3..].trim_start_matches(|c: char| c.is_ascii_whitespace());
+        if let Some(rest) = rest.strip_prefix('=') {
+            input = rest.trim_start_matches(|c: char| c.is_ascii_whitespace());
+        }
+    }
+    let url = match input.chars().next() {
+        Some(quote) if quote == '"' || quote == '\'' => {
+            let input = &input[1..];
+            &input[..input.find(quote).unwrap_or_else(|| input.len())]
+        }
+        _ => input.trim_end_matches(|c: char| c.is_ascii_whitespace()),
+    };
+    if url.is_empty() {
+        return Some((delay, None));
+    }
+    Some((delay, Some(url.to_string())))
+}
+
+/// The rendering mode of a document, picked from its doctype
+/// https://dom.spec.whatwg.org/#concept-document-quirks
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
 pub enum QuirksMode {
     Quirks,
     NoQuirks,
@@ -36,8 +141,21 @@ impl Document {
         Self {
             doctype: None,
             mode: QuirksMode::NoQuirks,
+            url: None,
+            base_element_url: None,
+            refresh: None,
+            shadow_roots: Vec::new(),
+            encoding: String::from("UTF-8"),
             loader: None,
             stylesheets: Vec::new(),
+            pending_stylesheets: Vec::new(),
+            next_stylesheet_id: 0,
+            error_sink: None,
+            focused_element: None,
+            form_submission: None,
+            navigation: None,
+            images: HashMap::new(),
+            visited_links: None,
         }
     }
 
@@ -45,6 +163,10 @@ impl Document {
         self.doctype = Some(doctype);
     }
 
+    pub fn doctype(&self) -> Option<&DocumentType> {
+        self.doctype.as_ref()
+    }
+
     pub fn set_mode(&mut self, mode: QuirksMode) {
         self.mode = mode;
     }
@@ -53,6 +175,119 @@ impl Document {
         &self.mode
     }
 
+    pub fn is_quirks_mode(&self) -> bool {
+        self.mode == QuirksMode::Quirks
+    }
+
+    pub fn url(&self) -> Option<&Url> {
+        self.url.as_ref()
+    }
+
+    pub fn set_url(&mut self, url: Url) {
+        self.url = Some(url);
+    }
+
+    /// The URL the relative URLs of the document are resolved against
+    /// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#document-base-url
+    pub fn base_url(&self) -> Option<&Url> {
+        self.base_element_url.as_ref().or_else(|| self.url())
+    }
+
+    /// Set the base URL from the `href` of a `<base>` element. Only the
+    /// first `<base>` element of the document sets the base URL.
+    pub fn set_base_element_href(&mut self, href: &str) {
+        if self.base_element_url.is_some() {
+            return;
+        }
+        self.base_element_url = self.parse_url(href);
+    }
+
+    /// Share the store of the URLs visited by the user, which the
+    /// `:visited` links of the document are matched with
+    pub fn set_visited_links(&mut self, visited_links: VisitedLinksRef) {
+        self.visited_links = Some(visited_links);
+    }
+
+    pub fn is_visited(&self, url: &Url) -> bool {
+        self.visited_links
+            .as_ref()
+            .map_or(false, |links| links.borrow().contains(url))
+    }
+
+    /// Parse a URL of the document, resolving relative URLs against its base URL
+    /// https://html.spec.whatwg.org/multipage/urls-and-fetching.html#encoding-parsing-a-url
+    pub fn parse_url(&self, input: &str) -> Option<Url> {
+        let url = match self.base_url() {
+            Some(base) => base.join(input),
+            None => Url::parse(input),
+        };
+        url.ok()
+    }
+
+    /// Request a navigation from the content of a `<meta http-equiv=refresh>`.
+    /// Only the first valid refresh of the document is kept.
+    pub fn set_refresh(&mut self, content: &str) {
+        if self.refresh.is_some() {
+            return;
+        }
+        if let Some((delay, url)) = parse_refresh(content) {
+            let url = match url {
+                Some(url) => match self.parse_url(&url) {
+                    Some(url) => Some(url),
+                    None => return,
+                },
+                None => None,
+            };
+            self.refresh = Some(Refresh { delay, url });
+        }
+    }
+
+    pub fn refresh(&self) -> Option<&Refresh> {
+        self.refresh.as_ref()
+    }
+
+    pub fn take_refresh(&mut self) -> Option<Refresh> {
+        self.refresh.take()
+    }
+
+    pub fn encoding(&self) -> &str {
+        &self.encoding
+    }
+
+    pub fn set_encoding(&mut self, encoding: &str) {
+        self.encoding = encoding.to_string();
+    }
+
+    /// The text of the first `<title>` element of a document, with its
+    /// whitespace stripped & collapsed
+    /// https://html.spec.whatwg.org/multipage/dom.html#document.title
+    pub fn title(document: &NodeRef) -> String {
+        fn find_title(node: &NodeRef) -> Option<NodeRef> {
+            for child in node.borrow().child_nodes() {
+                let is_title = child.borrow().as_element_opt().map_or(false, |element| {
+                    element.namespace() == Namespace::Html && element.has_tag_name("title")
+                });
+                if is_title {
+                    return Some(child);
+                }
+                if let Some(title) = find_title(&child) {
+                    return Some(title);
+                }
+            }
+            None
+        }
+
+        match find_title(document) {
+            Some(title) => title
+                .borrow()
+                .child_text_content()
+                .split_ascii_whitespace()
+                .collect::<Vec<_>>()
+                .join(" "),
+            None => String::new(),
+        }
+    }
+
     pub fn loader(&self) -> Option<Rc<RefCell<dyn DocumentLoader>>> {
         self.loader.clone()
     }
@@ -61,13 +296,173 @@ impl Document {
         self.loader = Some(Rc::new(RefCell::new(loader)));
     }
 
+    /// Where to report parse errors of resources loaded by this document
+    pub fn error_sink(&self) -> Option<ParseErrorSinkRef> {
+        self.error_sink.clone()
+    }
+
+    pub fn set_error_sink(&mut self, sink: ParseErrorSinkRef) {
+        self.error_sink = Some(sink);
+    }
+
     pub fn append_stylesheet(&mut self, stylesheet: StyleSheet) {
-        self.stylesheets.push(stylesheet);
+        self.stylesheets.push(Rc::new(stylesheet));
+    }
+
+    /// Parse a stylesheet of the document on another thread while the
+    /// document keeps loading, its URLs resolved against the base URL of
+    /// the document. The stylesheet is appended when the pending
+    /// stylesheets are joined, after the stylesheets appended before it.
+    pub fn append_stylesheet_source(&mut self, css: String) {
+        let id = self.reserve_stylesheet();
+        let base_url = self.base_url().cloned();
+        self.set_stylesheet_source(id, css, base_url);
+    }
+
+    /// Keep the place of a stylesheet that is still loading, so it's
+    /// appended in document order whenever it arrives
+    pub fn reserve_stylesheet(&mut self) -> usize {
+        let id = self.next_stylesheet_id;
+        self.next_stylesheet_id += 1;
+        self.pending_stylesheets
+            .push(PendingStyleSheet { id, parser: None });
+        id
+    }
+
+    /// Parse the source of a reserved stylesheet on another thread, with
+    /// the URL its relative URLs are resolved against
+    pub fn set_stylesheet_source(&mut self, id: usize, css: String, base_url: Option<Url>) {
+        let pending = match self.pending_stylesheets.iter_mut().find(|p| p.id == id) {
+            Some(pending) => pending,
+            None => return,
+        };
+        pending.parser = Some(thread::spawn(move || {
+            // the parse errors are collected on the parser thread
+            // and reported to the document sink when joined
+            let errors = Rc::new(RefCell::new(Vec::new()));
+            let mut tokenizer = Tokenizer::new(css.chars());
+            tokenizer.set_error_sink(errors.clone());
+            let (tokens, spans) = tokenizer.run_with_spans();
+            let mut parser = Parser::<Token>::new(tokens);
+            parser.set_token_spans(spans);
+            parser.set_error_sink(errors.clone());
+            if let Some(base_url) = base_url {
+                parser.set_base_url(base_url);
+            }
+            let stylesheet = parser.parse_a_css_stylesheet();
+
+            let errors = errors.borrow().clone();
+            (stylesheet, errors)
+        }));
+    }
+
+    /// Give up a reserved stylesheet that couldn't be loaded
+    pub fn cancel_stylesheet(&mut self, id: usize) {
+        self.pending_stylesheets.retain(|pending| pending.id != id);
     }
 
-    pub fn stylesheets(&self) -> &[StyleSheet] {
+    /// Whether stylesheets are being parsed & can be joined
+    pub fn has_pending_stylesheets(&self) -> bool {
+        self.pending_stylesheets
+            .first()
+            .map_or(false, |pending| pending.parser.is_some())
+    }
+
+    /// Whether stylesheets are still loading
+    pub fn is_loading_stylesheets(&self) -> bool {
+        self.pending_stylesheets
+            .iter()
+            .any(|pending| pending.parser.is_none())
+    }
+
+    /// Wait for the stylesheets being parsed & append them in document
+    /// order, up to the first stylesheet still loading. Must be called
+    /// before resolving the styles of the document. Returns whether
+    /// stylesheets were appended.
+    pub fn join_stylesheets(&mut self) -> bool {
+        let ready = self
+            .pending_stylesheets
+            .iter()
+            .take_while(|pending| pending.parser.is_some())
+            .count();
+        let parsers = self
+            .pending_stylesheets
+            .drain(..ready)
+            .filter_map(|pending| pending.parser)
+            .collect::<Vec<StyleSheetParser>>();
+
+        for handle in parsers {
+            match handle.join() {
+                Ok((stylesheet, errors)) => {
+                    if let Some(sink) = &self.error_sink {
+                        for error in errors {
+                            sink.borrow_mut().report(error);
+                        }
+                    }
+                    self.stylesheets.push(Rc::new(stylesheet));
+                }
+                Err(_) => log::error!("Unable to parse stylesheet: the parser thread panicked"),
+            }
+        }
+        ready > 0
+    }
+
+    pub fn stylesheets(&self) -> &[Rc<StyleSheet>] {
         &self.stylesheets
     }
+
+    pub(crate) fn add_shadow_root(&mut self, shadow_root: &NodeRef) {
+        self.shadow_roots.push(shadow_root.clone().downgrade());
+    }
+
+    /// The shadow roots attached to the elements of the document, whose
+    /// stylesheets are scoped to their tree
+    pub fn shadow_roots(&self) -> Vec<NodeRef> {
+        self.shadow_roots
+            .iter()
+            .filter_map(|shadow_root| shadow_root.clone().upgrade())
+            .collect()
+    }
+
+    pub fn focused_element(&self) -> Option<NodeRef> {
+        self.focused_element.clone()?.upgrade()
+    }
+
+    pub fn set_focused_element(&mut self, element: Option<NodeRef>) {
+        self.focused_element = element.map(NodeRef::downgrade);
+    }
+
+    pub fn set_form_submission(&mut self, submission: FormSubmission) {
+        self.form_submission = Some(submission);
+    }
+
+    pub fn image_state(&self, url: &str) -> Option<&ImageState> {
+        self.images.get(url)
+    }
+
+    pub fn set_image_state(&mut self, url: &str, state: ImageState) {
+        self.images.insert(url.to_string(), state);
+    }
+
+    /// The image loaded from a URL, if it's loaded
+    pub fn image(&self, url: &str) -> Option<Rc<Image>> {
+        match self.images.get(url) {
+            Some(ImageState::Loaded(image)) => Some(image.clone()),
+            _ => None,
+        }
+    }
+
+    pub fn take_form_submission(&mut self) -> Option<FormSubmission> {
+        self.form_submission.take()
+    }
+
+    pub fn set_navigation(&mut self, url: Url) {
+        self.navigation = Some(url);
+    }
+
+    pub fn take_navigation(&mut self) -> Option<Url> {
+        self.navigation.take()
+    }
 }
 
 impl core::fmt::Debug for DocumentType {
@@ -84,4 +479,158 @@ impl DocumentType {
             system_id: system_id.unwrap_or_default(),
         }
     }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn public_id(&self) -> &str {
+        &self.public_id
+    }
+
+    pub fn system_id(&self) -> &str {
+        &self.system_id
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::cssom::css_rule::CSSRule;
+
+    #[test]
+    fn join_stylesheets_in_order() {
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let mut document = Document::new();
+        document.set_error_sink(errors.clone());
+
+        document.append_stylesheet_source("a { color: red; }".to_string());
+        document.append_stylesheet_source("b { color: blue; } @".to_string());
+        document.append_stylesheet_source("c { color: green; }".to_string());
+        assert!(document.has_pending_stylesheets());
+        assert!(document.stylesheets().is_empty());
+
+        document.join_stylesheets();
+        assert!(!document.has_pending_stylesheets());
+
+        let rule_counts = document
+            .stylesheets()
+            .iter()
+            .map(|stylesheet| stylesheet.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(rule_counts, vec![1, 1, 1]);
+        assert!(!errors.borrow().is_empty());
+    }
+
+    #[test]
+    fn resolve_stylesheet_urls() {
+        let mut document = Document::new();
+        document.set_url(Url::parse("https://moon.dev/docs/index.html").unwrap());
+        document.append_stylesheet_source("a { background-image: url(a.png); }".to_string());
+        let id = document.reserve_stylesheet();
+        let stylesheet_url = Url::parse("https://cdn.moon.dev/css/main.css").unwrap();
+        document.set_stylesheet_source(
+            id,
+            "b { background-image: url('../img/b.png'); }".to_string(),
+            Some(stylesheet_url),
+        );
+        document.join_stylesheets();
+
+        let urls = document
+            .stylesheets()
+            .iter()
+            .map(|stylesheet| match &stylesheet.css_rules.0[0] {
+                CSSRule::Style(rule) => format!("{:?}", rule.declarations[0].value),
+                _ => unreachable!(),
+            })
+            .collect::<Vec<_>>();
+        assert!(urls[0].contains("\"https://moon.dev/docs/a.png\""));
+        assert!(urls[1].contains("\"https://cdn.moon.dev/img/b.png\""));
+    }
+
+    #[test]
+    fn keep_order_of_loading_stylesheets() {
+        let mut document = Document::new();
+
+        let first = document.reserve_stylesheet();
+        let failed = document.reserve_stylesheet();
+        document.append_stylesheet_source("c { color: green; }".to_string());
+        assert!(document.is_loading_stylesheets());
+        assert!(!document.has_pending_stylesheets());
+        assert!(!document.join_stylesheets());
+
+        document.set_stylesheet_source(
+            first,
+            "a { color: red; } b { color: blue; }".to_string(),
+            None,
+        );
+        assert!(document.join_stylesheets());
+        assert_eq!(document.stylesheets().len(), 1);
+
+        document.cancel_stylesheet(failed);
+        assert!(!document.is_loading_stylesheets());
+        assert!(document.join_stylesheets());
+
+        let rule_counts = document
+            .stylesheets()
+            .iter()
+            .map(|stylesheet| stylesheet.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(rule_counts, vec![2, 1]);
+    }
+
+    #[test]
+    fn title_of_document() {
+        use crate::node::{Node, NodeData};
+        use crate::text::Text;
+
+        let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        assert_eq!(Document::title(&document), "");
+
+        let head = crate::create_element(document.clone().downgrade(), "head");
+        let title = crate::create_element(document.clone().downgrade(), "title");
+        let text = NodeRef::new(Node::new(NodeData::Text(Text::new(
+            "\n  Moon \t browser ".to_string(),
+        ))));
+        Node::append_child(title.clone(), text);
+        Node::append_child(head.clone(), title);
+        Node::append_child(document.clone(), head);
+        assert_eq!(Document::title(&document), "Moon browser");
+    }
+
+    #[test]
+    fn parse_refresh_content() {
+        assert_eq!(parse_refresh("5"), Some((Duration::from_secs(5), None)));
+        assert_eq!(
+            parse_refresh(" 3.5; URL = 'next.html' "),
+            Some((Duration::from_secs(3), Some("next.html".to_string())))
+        );
+        assert_eq!(
+            parse_refresh("0,http://moon.dev/"),
+            Some((Duration::ZERO, Some("http://moon.dev/".to_string())))
+        );
+        assert_eq!(parse_refresh("soon"), None);
+        assert_eq!(parse_refresh("1 url=next.html"), None);
+    }
+
+    #[test]
+    fn resolve_against_base_url() {
+        let mut document = Document::new();
+        document.set_url(Url::parse("http://moon.dev/docs/index.html").unwrap());
+        document.set_base_element_href("../static/");
+        document.set_base_element_href("http://other.dev/");
+        assert_eq!(
+            document.parse_url("a.css").unwrap().raw(),
+            "http://moon.dev/static/a.css"
+        );
+
+        document.set_refresh("2; url=next.html");
+        document.set_refresh("0");
+        let refresh = document.take_refresh().unwrap();
+        assert_eq!(refresh.delay, Duration::from_secs(2));
+        assert_eq!(
+            refresh.url.unwrap().raw(),
+            "http://moon.dev/static/next.html"
+        );
+    }
 }
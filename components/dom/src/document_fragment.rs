@@ -0,0 +1,55 @@
+use super::dom_ref::{NodeRef, WeakNodeRef};
+use super::node::NodeHooks;
+use css::cssom::stylesheet::StyleSheet;
+use std::rc::Rc;
+
+/// A node holding a list of nodes without a document, e.g. the nodes
+/// parsed from a fragment of HTML
+/// https://dom.spec.whatwg.org/#interface-documentfragment
+#[derive(Debug)]
+pub struct DocumentFragment {
+    /// The element the fragment is the shadow root of
+    host: Option<WeakNodeRef>,
+    /// The stylesheets of a shadow root, which only apply to its tree
+    stylesheets: Vec<Rc<StyleSheet>>,
+}
+
+impl NodeHooks for DocumentFragment {}
+
+impl DocumentFragment {
+    pub fn new() -> Self {
+        Self {
+            host: None,
+            stylesheets: Vec::new(),
+        }
+    }
+
+    /// A shadow root, rendered in place of the children of its host. The
+    /// selectors of the stylesheets outside of the shadow root don't match
+    /// its nodes, except for the user agent stylesheet, & its stylesheets
+    /// don't match the nodes outside of it. The slots aren't supported,
+    /// the children of the host aren't rendered.
+    /// https://dom.spec.whatwg.org/#concept-shadow-root
+    pub fn new_shadow_root(host: WeakNodeRef) -> Self {
+        Self {
+            host: Some(host),
+            stylesheets: Vec::new(),
+        }
+    }
+
+    pub fn is_shadow_root(&self) -> bool {
+        self.host.is_some()
+    }
+
+    pub fn host(&self) -> Option<NodeRef> {
+        self.host.clone()?.upgrade()
+    }
+
+    pub fn append_stylesheet(&mut self, stylesheet: StyleSheet) {
+        self.stylesheets.push(Rc::new(stylesheet));
+    }
+
+    pub fn stylesheets(&self) -> &[Rc<StyleSheet>] {
+        &self.stylesheets
+    }
+}
@@ -4,12 +4,57 @@ type Bytes = Vec<u8>;
 type SuccessCallback = Box<dyn FnOnce(Bytes)>;
 type ErrorCallback = Box<dyn FnOnce(String)>;
 
+/// The callback of a finished request, run by the owner of the loader
+/// once the loader isn't borrowed anymore
+pub type ResponseTask = Box<dyn FnOnce()>;
+
 pub trait DocumentLoader {
     fn load(&mut self, request: LoadRequest);
+
+    /// The callbacks of the requests that finished loading since the last
+    /// call. Loaders responding before `load` returns have none.
+    fn take_responses(&mut self) -> Vec<ResponseTask> {
+        Vec::new()
+    }
+
+    /// Block until the requests for a destination finished loading,
+    /// taking the callbacks of all the finished requests
+    fn wait_for(&mut self, _destination: Destination) -> Vec<ResponseTask> {
+        Vec::new()
+    }
+}
+
+/// What a request is loading, which decides how soon it's loaded
+/// https://fetch.spec.whatwg.org/#concept-request-destination
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Destination {
+    Document,
+    Style,
+    Font,
+    Image,
+    Other,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Get,
+    Post,
+}
+
+/// The content sent with a request, e.g. the data of a submitted form
+#[derive(Debug, Clone, PartialEq)]
+pub struct RequestBody {
+    pub content_type: String,
+    pub bytes: Bytes,
 }
 
 pub struct LoadRequest {
     pub url: Url,
+    pub method: Method,
+    pub destination: Destination,
+    /// Headers added to the request, e.g. the cookies of the URL
+    pub headers: Vec<(String, String)>,
+    pub body: Option<RequestBody>,
     pub success_callback: Option<SuccessCallback>,
     pub error_callback: Option<ErrorCallback>,
 }
@@ -18,11 +63,35 @@ impl LoadRequest {
     pub fn new(url: Url) -> Self {
         Self {
             url,
+            method: Method::Get,
+            destination: Destination::Other,
+            headers: Vec::new(),
+            body: None,
             success_callback: None,
             error_callback: None,
         }
     }
 
+    pub fn with_method(mut self, method: Method) -> Self {
+        self.method = method;
+        self
+    }
+
+    pub fn with_destination(mut self, destination: Destination) -> Self {
+        self.destination = destination;
+        self
+    }
+
+    pub fn with_header(mut self, name: &str, value: &str) -> Self {
+        self.headers.push((name.to_string(), value.to_string()));
+        self
+    }
+
+    pub fn with_body(mut self, body: RequestBody) -> Self {
+        self.body = Some(body);
+        self
+    }
+
     pub fn on_success(mut self, callback: SuccessCallback) -> Self {
         self.success_callback = Some(callback);
         self
@@ -48,6 +48,8 @@ impl PartialEq for NodeRef {
     }
 }
 
+impl Eq for NodeRef {}
+
 impl WeakNodeRef {
     pub fn upgrade(self) -> Option<NodeRef> {
         match self.0.upgrade() {
@@ -1,6 +1,16 @@
-#[derive(Debug)]
+use atom::Atom;
+
+/// An ordered set of tokens, e.g. the classes of an element.
+/// Tokens that are empty or contain ASCII whitespace are invalid &
+/// are ignored by the methods updating the list.
+/// https://dom.spec.whatwg.org/#interface-domtokenlist
+#[derive(Debug, Clone, PartialEq)]
 pub struct DOMTokenList {
-    items: Vec<String>,
+    items: Vec<Atom>,
+}
+
+fn is_valid_token(token: &str) -> bool {
+    !token.is_empty() && !token.contains(|c: char| c.is_ascii_whitespace())
 }
 
 impl DOMTokenList {
@@ -12,26 +22,82 @@ impl DOMTokenList {
         self.items.len()
     }
 
-    pub fn item(&self, index: usize) -> Option<String> {
-        match self.items.get(index) {
-            Some(item) => Some(item.clone()),
-            _ => None,
-        }
+    pub fn item(&self, index: usize) -> Option<Atom> {
+        self.items.get(index).cloned()
+    }
+
+    pub fn iter(&self) -> impl Iterator<Item = &Atom> {
+        self.items.iter()
     }
 
     pub fn contains(&self, token: &str) -> bool {
-        self.items.contains(&token.to_owned())
+        self.items.iter().any(|item| item == token)
     }
 
-    pub fn add(&mut self, tokens: Vec<String>) {
-        let mut tokens = tokens;
-        self.items.append(&mut tokens);
+    /// Same as `contains()`, comparing the atoms instead of the strings
+    pub fn contains_atom(&self, token: &Atom) -> bool {
+        self.items.contains(token)
     }
 
-    pub fn remove(&mut self, tokens: Vec<String>) {
+    pub fn add(&mut self, tokens: Vec<Atom>) {
+        for token in tokens {
+            if is_valid_token(&token) && !self.items.contains(&token) {
+                self.items.push(token);
+            }
+        }
+    }
+
+    pub fn remove(&mut self, tokens: Vec<Atom>) {
         self.items.retain(|item| !tokens.contains(item));
     }
 
+    /// Remove the token if it's in the list, add it otherwise. When `force`
+    /// is set, the token is only added or only removed. Returns whether the
+    /// token is in the list afterward.
+    /// https://dom.spec.whatwg.org/#dom-domtokenlist-toggle
+    pub fn toggle(&mut self, token: Atom, force: Option<bool>) -> bool {
+        if !is_valid_token(&token) {
+            return false;
+        }
+        if self.contains_atom(&token) {
+            if force == Some(true) {
+                return true;
+            }
+            self.remove(vec![token]);
+            return false;
+        }
+        if force == Some(false) {
+            return false;
+        }
+        self.items.push(token);
+        true
+    }
+
+    /// Replace a token by a new token, keeping its position. Returns
+    /// whether the token was in the list.
+    /// https://dom.spec.whatwg.org/#dom-domtokenlist-replace
+    pub fn replace(&mut self, token: &Atom, new_token: Atom) -> bool {
+        if !is_valid_token(token) || !is_valid_token(&new_token) {
+            return false;
+        }
+        if !self.contains_atom(token) {
+            return false;
+        }
+        // The first of the token & the new token is replaced, the other is removed
+        let index = self
+            .items
+            .iter()
+            .position(|item| item == token || *item == new_token)
+            .unwrap();
+        self.items[index] = new_token.clone();
+        let mut position = 0;
+        self.items.retain(|item| {
+            position += 1;
+            position - 1 == index || (item != token && *item != new_token)
+        });
+        true
+    }
+
     pub fn value(&self) -> String {
         self.items.join(" ")
     }
@@ -39,12 +105,37 @@ impl DOMTokenList {
 
 impl From<&str> for DOMTokenList {
     fn from(data: &str) -> Self {
-        Self {
-            items: data
-                .split(' ')
-                .filter(|class| class.len() > 0)
-                .map(String::from)
+        let mut list = Self::new();
+        list.add(
+            data.split(|c: char| c.is_ascii_whitespace())
+                .map(Atom::from)
                 .collect(),
-        }
+        );
+        list
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn update_tokens() {
+        let mut list = DOMTokenList::from("a  b\ta c");
+        assert_eq!(list.value(), "a b c");
+
+        list.add(vec![Atom::from("d"), Atom::from("b"), Atom::from("e f")]);
+        assert_eq!(list.value(), "a b c d");
+
+        assert!(!list.toggle(Atom::from("a"), None));
+        assert!(list.toggle(Atom::from("a"), None));
+        assert!(list.toggle(Atom::from("a"), Some(true)));
+        assert!(!list.toggle(Atom::from("x"), Some(false)));
+        assert_eq!(list.value(), "b c d a");
+
+        assert!(list.replace(&Atom::from("c"), Atom::from("z")));
+        assert!(list.replace(&Atom::from("b"), Atom::from("a")));
+        assert!(!list.replace(&Atom::from("x"), Atom::from("y")));
+        assert_eq!(list.value(), "a z d");
     }
 }
@@ -1,17 +1,45 @@
 use super::dom_ref::NodeRef;
 use super::dom_token_list::DOMTokenList;
 use super::elements::{ElementData, ElementMethods};
+use super::event::KeyboardEvent;
 use super::node::NodeHooks;
-use std::collections::HashMap;
+use super::reflection::dataset_property_name;
+use atom::Atom;
+use std::collections::{BTreeMap, HashMap};
 use std::ops::{Deref, DerefMut};
 
-pub struct AttributeMap(HashMap<String, String>);
+pub struct AttributeMap(HashMap<Atom, String>);
+
+/// The namespace of an element. Elements are HTML elements unless they
+/// are inside an `<svg>` or a `<math>` element.
+/// https://infra.spec.whatwg.org/#namespaces
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Namespace {
+    Html,
+    Svg,
+    MathML,
+}
+
+impl Namespace {
+    pub fn url(&self) -> &'static str {
+        match self {
+            Namespace::Html => "http://www.w3.org/1999/xhtml",
+            Namespace::Svg => "http://www.w3.org/2000/svg",
+            Namespace::MathML => "http://www.w3.org/1998/Math/MathML",
+        }
+    }
+}
 
 pub struct Element {
+    tag_name: Atom,
+    namespace: Namespace,
     attributes: AttributeMap,
-    id: String,
+    id: Atom,
     class_list: DOMTokenList,
     data: ElementData,
+    /// The shadow root attached to the element, rendered in place of
+    /// its children
+    shadow_root: Option<NodeRef>,
 }
 
 impl AttributeMap {
@@ -37,7 +65,7 @@ impl AttributeMap {
 }
 
 impl Deref for AttributeMap {
-    type Target = HashMap<String, String>;
+    type Target = HashMap<Atom, String>;
     fn deref(&self) -> &Self::Target {
         &self.0
     }
@@ -63,29 +91,89 @@ impl NodeHooks for Element {
 
 impl Element {
     pub fn new(data: ElementData) -> Self {
+        Self::new_ns(data, Namespace::Html)
+    }
+
+    pub fn new_ns(data: ElementData, namespace: Namespace) -> Self {
         Self {
+            tag_name: Atom::from(data.tag_name()),
+            namespace,
             attributes: AttributeMap::new(),
-            id: String::new(),
+            id: Atom::default(),
             class_list: DOMTokenList::new(),
             data,
+            shadow_root: None,
         }
     }
 
-    pub fn tag_name(&self) -> String {
-        self.data.tag_name()
+    pub fn tag_name(&self) -> Atom {
+        self.tag_name.clone()
+    }
+
+    pub fn shadow_root(&self) -> Option<NodeRef> {
+        self.shadow_root.clone()
+    }
+
+    pub(crate) fn set_shadow_root(&mut self, shadow_root: NodeRef) {
+        self.shadow_root = Some(shadow_root);
+    }
+
+    /// Whether the element has a tag name. The names of the HTML elements
+    /// are ASCII case-insensitive, the names of the SVG & MathML elements
+    /// are case-sensitive, e.g. `foreignObject`.
+    /// https://html.spec.whatwg.org/multipage/dom.html#elements-in-the-dom
+    pub fn has_tag_name(&self, name: &str) -> bool {
+        match self.namespace {
+            Namespace::Html => self.tag_name.eq_ignore_ascii_case(name),
+            Namespace::Svg | Namespace::MathML => self.tag_name == name,
+        }
+    }
+
+    /// The name an attribute is stored with. The attribute names of the
+    /// HTML elements are lowercased, like the names parsed by the tokenizer.
+    pub fn attribute_name(&self, name: &str) -> Atom {
+        match self.namespace {
+            Namespace::Html => Atom::from(name.to_ascii_lowercase()),
+            Namespace::Svg | Namespace::MathML => Atom::from(name),
+        }
+    }
+
+    pub fn get_attribute(&self, name: &str) -> Option<&String> {
+        self.attributes.get(&self.attribute_name(name))
+    }
+
+    pub fn namespace(&self) -> Namespace {
+        self.namespace
     }
 
     pub fn set_attribute(&mut self, name: &str, value: &str) {
+        let name = self.attribute_name(name);
+        self.attributes.insert(name.clone(), value.to_owned());
         if name == "id" {
-            self.id = value.to_string();
+            self.id = Atom::from(value);
             return;
         }
         if name == "class" {
             self.class_list = DOMTokenList::from(value);
             return;
         }
-        self.attributes.insert(name.to_owned(), value.to_owned());
-        self.data.handle_attribute_change(name, value);
+        self.data.handle_attribute_change(&name, value);
+    }
+
+    pub fn remove_attribute(&mut self, name: &str) {
+        let name = self.attribute_name(name);
+        if self.attributes.remove(&name).is_none() {
+            return;
+        }
+        if name == "id" {
+            self.id = Atom::default();
+            return;
+        }
+        if name == "class" {
+            self.class_list = DOMTokenList::new();
+            return;
+        }
+        self.data.handle_attribute_remove(&name);
     }
 
     pub fn attributes(&self) -> &AttributeMap {
@@ -93,18 +181,61 @@ impl Element {
     }
 
     pub fn has_attribute(&self, name: &str) -> bool {
-        self.attributes.contains_key(name)
+        self.attributes.contains_key(&self.attribute_name(name))
+    }
+
+    /// The `data-*` attributes, by their names without the prefix in camel case
+    /// https://html.spec.whatwg.org/multipage/dom.html#dom-dataset
+    pub fn dataset(&self) -> BTreeMap<String, String> {
+        self.attributes
+            .iter()
+            .filter_map(|(name, value)| {
+                let name = name.strip_prefix("data-")?;
+                Some((dataset_property_name(name), value.clone()))
+            })
+            .collect()
     }
 
     pub fn class_list(&self) -> &DOMTokenList {
         &self.class_list
     }
 
-    pub fn id(&self) -> &String {
+    /// Update the class list & reflect it into the `class` attribute
+    /// https://dom.spec.whatwg.org/#concept-dtl-update
+    pub fn update_class_list<R, F>(&mut self, update: F) -> R
+    where
+        F: FnOnce(&mut DOMTokenList) -> R,
+    {
+        let result = update(&mut self.class_list);
+        if self.has_attribute("class") || self.class_list.length() > 0 {
+            self.attributes
+                .insert(Atom::from("class"), self.class_list.value());
+        }
+        result
+    }
+
+    pub fn id(&self) -> &Atom {
         &self.id
     }
 
     pub fn handle_on_inserted(&mut self, document: NodeRef) {
         self.data.handle_on_inserted(document);
     }
+
+    pub fn handle_key_event(&mut self, event: &KeyboardEvent) -> bool {
+        self.data.handle_key_event(event)
+    }
+
+    pub fn activate(&mut self) {
+        self.data.handle_activation();
+    }
+
+    /// The state specific to the kind of the element
+    pub fn data(&self) -> &ElementData {
+        &self.data
+    }
+
+    pub fn data_mut(&mut self) -> &mut ElementData {
+        &mut self.data
+    }
 }
@@ -1,34 +1,221 @@
 use crate::dom_ref::{NodeRef, WeakNodeRef};
-use crate::element::Element;
+use crate::element::{Element, Namespace};
 use crate::node::{Node, NodeData};
 
 use super::elements::*;
 
+/// The known HTML elements that have the `HTMLElement` interface
+/// https://html.spec.whatwg.org/multipage/dom.html#elements-in-the-dom
+const GENERIC_ELEMENTS: [&str; 51] = [
+    "abbr",
+    "acronym",
+    "address",
+    "article",
+    "aside",
+    "b",
+    "basefont",
+    "bdi",
+    "bdo",
+    "big",
+    "center",
+    "cite",
+    "code",
+    "dd",
+    "dfn",
+    "dt",
+    "em",
+    "figcaption",
+    "figure",
+    "footer",
+    "header",
+    "hgroup",
+    "i",
+    "kbd",
+    "main",
+    "mark",
+    "nav",
+    "nobr",
+    "noembed",
+    "noframes",
+    "noscript",
+    "plaintext",
+    "rb",
+    "rp",
+    "rt",
+    "rtc",
+    "ruby",
+    "s",
+    "samp",
+    "search",
+    "section",
+    "small",
+    "strike",
+    "strong",
+    "sub",
+    "summary",
+    "sup",
+    "tt",
+    "u",
+    "var",
+    "wbr",
+];
+
 macro_rules! translate {
     ($tag_name:ident, {$($($matcher:pat)|* => $dataKey:ident > $result:ident),*}) => {
         match $tag_name {
             $(
-                $($matcher)|* => translate!($tag_name, $dataKey, $result)
+                $($matcher)|* => ElementData::$dataKey($result::empty())
             ),*,
-            _ => Node::new(NodeData::Element(Element::new(ElementData::Unknown(HTMLUnknownElement::new($tag_name.to_string())))))
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => ElementData::Heading(HTMLHeadingElement::new($tag_name.to_string())),
+            "thead" | "tbody" | "tfoot" => ElementData::TableSection(HTMLTableSectionElement::new($tag_name.to_string())),
+            "td" | "th" => ElementData::TableCell(HTMLTableCellElement::new($tag_name.to_string())),
+            "q" | "blockquote" => ElementData::Quote(HTMLQuoteElement::new($tag_name.to_string())),
+            "ins" | "del" => ElementData::Mod(HTMLModElement::new($tag_name.to_string())),
+            tag_name if GENERIC_ELEMENTS.contains(&tag_name) => ElementData::Generic(HTMLGenericElement::new(tag_name.to_string())),
+            _ => ElementData::Unknown(HTMLUnknownElement::new($tag_name.to_string()))
         }
     };
-    ($tag_name:ident, $dataKey:ident, $struct:ident) => {
-        Node::new(NodeData::Element(Element::new(ElementData::$dataKey($struct::empty()))))
-    }
 }
 
 pub fn create_element(document: WeakNodeRef, tag_name: &str) -> NodeRef {
-    let mut node = translate!(tag_name, {
+    let data = translate!(tag_name, {
         "html" => Html > HTMLHtmlElement,
         "head" => Head > HTMLHeadElement,
         "title" => Title > HTMLTitleElement,
+        "style" => Style > HTMLStyleElement,
+        "script" => Script > HTMLScriptElement,
+        "meta" => Meta > HTMLMetaElement,
+        "base" => Base > HTMLBaseElement,
         "body" => Body > HTMLBodyElement,
         "div" => Div > HTMLDivElement,
+        "span" => Span > HTMLSpanElement,
+        "p" => Paragraph > HTMLParagraphElement,
+        "pre" => Pre > HTMLPreElement,
+        "br" => Br > HTMLBRElement,
+        "hr" => Hr > HTMLHRElement,
+        "ul" => UList > HTMLUListElement,
+        "ol" => OList > HTMLOListElement,
+        "dl" => DList > HTMLDListElement,
+        "li" => Li > HTMLLIElement,
+        "table" => Table > HTMLTableElement,
+        "tr" => TableRow > HTMLTableRowElement,
+        "label" => Label > HTMLLabelElement,
         "a" => Anchor > HTMLAnchorElement,
-        "link" => Link > HTMLLinkElement
+        "link" => Link > HTMLLinkElement,
+        "img" => Image > HTMLImageElement,
+        "canvas" => Canvas > HTMLCanvasElement,
+        "input" => Input > HTMLInputElement,
+        "button" => Button > HTMLButtonElement,
+        "form" => Form > HTMLFormElement
     });
 
+    let mut node = Node::new(NodeData::Element(Element::new(data)));
     node.set_document(document);
     NodeRef::new(node)
 }
+
+/// Create an element in a namespace. SVG & MathML elements don't have
+/// the behavior of the HTML elements with the same tag name.
+pub fn create_element_ns(document: WeakNodeRef, tag_name: &str, namespace: Namespace) -> NodeRef {
+    let data = match namespace {
+        Namespace::Html => return create_element(document, tag_name),
+        Namespace::Svg if tag_name == "svg" => ElementData::Svg(SVGSvgElement::empty()),
+        Namespace::Svg => ElementData::SvgElement(SVGElement::new(tag_name.to_string())),
+        Namespace::MathML => ElementData::Unknown(HTMLUnknownElement::new(tag_name.to_string())),
+    };
+    let mut node = Node::new(NodeData::Element(Element::new_ns(data, namespace)));
+    node.set_document(document);
+    NodeRef::new(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+
+    #[test]
+    fn create_html_elements() {
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let heading = create_element(doc.clone().downgrade(), "h2");
+        assert!(matches!(
+            heading.borrow().as_element().data(),
+            ElementData::Heading(_)
+        ));
+        assert_eq!(heading.borrow().as_element().tag_name(), "h2");
+
+        let list = create_element(doc.clone().downgrade(), "ol");
+        Node::set_attribute(&list, "reversed", "");
+        assert!(list.reversed());
+
+        let section = create_element(doc.clone().downgrade(), "section");
+        assert!(section
+            .borrow()
+            .as_element()
+            .data()
+            .downcast_ref::<HTMLGenericElement>()
+            .is_some());
+        assert_eq!(section.borrow().as_element().tag_name(), "section");
+
+        let image = create_element(doc.clone().downgrade(), "img");
+        Node::set_attribute(&image, "width", "30");
+        let width = image
+            .borrow()
+            .as_element()
+            .data()
+            .downcast_ref::<HTMLImageElement>()
+            .and_then(|image| image.width());
+        assert_eq!(width, Some(30));
+
+        let unknown = create_element(doc.downgrade(), "blink");
+        assert!(unknown
+            .borrow()
+            .as_element()
+            .data()
+            .downcast_ref::<HTMLUnknownElement>()
+            .is_some());
+    }
+    #[test]
+    fn create_the_interface_of_every_tag() {
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let create = |tag_name: &str| {
+            let element = create_element(doc.clone().downgrade(), tag_name);
+            assert_eq!(element.borrow().as_element().tag_name(), tag_name);
+            element
+        };
+
+        macro_rules! assert_interfaces {
+            ($($tag_name:expr => $variant:ident),* $(,)?) => {
+                $(
+                    assert!(
+                        matches!(
+                            create($tag_name).borrow().as_element().data(),
+                            ElementData::$variant(_)
+                        ),
+                        "<{}> isn't created as {}",
+                        $tag_name,
+                        stringify!($variant)
+                    );
+                )*
+            };
+        }
+
+        assert_interfaces! {
+            "html" => Html, "head" => Head, "title" => Title, "style" => Style,
+            "script" => Script, "meta" => Meta, "base" => Base, "body" => Body,
+            "div" => Div, "span" => Span, "p" => Paragraph, "pre" => Pre,
+            "br" => Br, "hr" => Hr, "ul" => UList, "ol" => OList, "dl" => DList,
+            "li" => Li, "table" => Table, "tr" => TableRow, "label" => Label,
+            "a" => Anchor, "link" => Link, "img" => Image, "canvas" => Canvas,
+            "input" => Input, "button" => Button, "form" => Form,
+            "h1" => Heading, "h2" => Heading, "h3" => Heading,
+            "h4" => Heading, "h5" => Heading, "h6" => Heading,
+            "thead" => TableSection, "tbody" => TableSection, "tfoot" => TableSection,
+            "td" => TableCell, "th" => TableCell,
+            "q" => Quote, "blockquote" => Quote, "ins" => Mod, "del" => Mod,
+            "blink" => Unknown, "marquee" => Unknown, "my-element" => Unknown,
+        }
+        for tag_name in GENERIC_ELEMENTS.iter() {
+            assert_interfaces!(*tag_name => Generic);
+        }
+    }
+}
@@ -1,23 +1,36 @@
 use super::ElementHooks;
 use super::ElementMethods;
 use crate::node::NodeHooks;
-use url::Url;
 
+/// An `<a>` element. Activating an anchor with a `href` follows its link,
+/// the `href` being resolved against the base URL of the document when
+/// the link is followed.
+/// https://html.spec.whatwg.org/multipage/text-level-semantics.html#the-a-element
 #[derive(Debug)]
 pub struct HTMLAnchorElement {
-    href: Option<Url>,
+    href: Option<String>,
 }
 
 impl HTMLAnchorElement {
     pub fn empty() -> Self {
         Self { href: None }
     }
+
+    pub fn href(&self) -> Option<&str> {
+        self.href.as_deref()
+    }
 }
 
 impl ElementHooks for HTMLAnchorElement {
     fn on_attribute_change(&mut self, attr: &str, value: &str) {
         if attr == "href" {
-            self.href = Url::parse(value).ok();
+            self.href = Some(value.trim().to_string());
+        }
+    }
+
+    fn on_attribute_remove(&mut self, attr: &str) {
+        if attr == "href" {
+            self.href = None;
         }
     }
 }
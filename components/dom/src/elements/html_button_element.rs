@@ -0,0 +1,73 @@
+use super::ElementHooks;
+use super::ElementMethods;
+use crate::event::{KeyEventKind, KeyboardEvent};
+use crate::node::NodeHooks;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ButtonType {
+    Submit,
+    Reset,
+    Button,
+}
+
+#[derive(Debug)]
+pub struct HTMLButtonElement {
+    button_type: ButtonType,
+    /// Whether the button is held down, painted as pressed
+    pressed: bool,
+}
+
+impl HTMLButtonElement {
+    pub fn empty() -> Self {
+        Self {
+            button_type: ButtonType::Submit,
+            pressed: false,
+        }
+    }
+
+    pub fn button_type(&self) -> ButtonType {
+        self.button_type
+    }
+
+    pub fn pressed(&self) -> bool {
+        self.pressed
+    }
+}
+
+impl ElementHooks for HTMLButtonElement {
+    fn on_attribute_change(&mut self, attr: &str, value: &str) {
+        if attr == "type" {
+            self.button_type = match value.to_ascii_lowercase().as_str() {
+                "reset" => ButtonType::Reset,
+                "button" => ButtonType::Button,
+                _ => ButtonType::Submit,
+            }
+        }
+    }
+
+    fn on_key_event(&mut self, event: &KeyboardEvent) -> bool {
+        if event.key != " " {
+            return false;
+        }
+        match event.kind {
+            KeyEventKind::KeyDown => {
+                self.pressed = true;
+                true
+            }
+            // the button is activated when the space key is released,
+            // which is left to the default action of the event
+            KeyEventKind::KeyUp => {
+                self.pressed = false;
+                false
+            }
+        }
+    }
+}
+
+impl NodeHooks for HTMLButtonElement {}
+
+impl ElementMethods for HTMLButtonElement {
+    fn tag_name(&self) -> String {
+        "button".to_string()
+    }
+}
@@ -0,0 +1,83 @@
+use super::html_image_element::parse_dimension;
+use super::ElementHooks;
+use super::ElementMethods;
+use crate::canvas::CanvasRenderingContext2D;
+use crate::node::NodeHooks;
+
+const DEFAULT_WIDTH: u32 = 300;
+const DEFAULT_HEIGHT: u32 = 150;
+
+/// A `<canvas>` element, painted with the bitmap of its 2D context. The
+/// bitmap is as large as the `width` & `height` attributes & setting
+/// them clears it.
+/// https://html.spec.whatwg.org/multipage/canvas.html#the-canvas-element
+#[derive(Debug)]
+pub struct HTMLCanvasElement {
+    context: CanvasRenderingContext2D,
+}
+
+impl HTMLCanvasElement {
+    pub fn empty() -> Self {
+        Self {
+            context: CanvasRenderingContext2D::new(DEFAULT_WIDTH, DEFAULT_HEIGHT),
+        }
+    }
+
+    pub fn width(&self) -> u32 {
+        self.context.width()
+    }
+
+    pub fn height(&self) -> u32 {
+        self.context.height()
+    }
+
+    pub fn context_2d(&self) -> &CanvasRenderingContext2D {
+        &self.context
+    }
+
+    pub fn context_2d_mut(&mut self) -> &mut CanvasRenderingContext2D {
+        &mut self.context
+    }
+}
+
+impl ElementHooks for HTMLCanvasElement {
+    fn on_attribute_change(&mut self, attr: &str, value: &str) {
+        let (width, height) = match attr {
+            "width" => (
+                parse_dimension(value).unwrap_or(DEFAULT_WIDTH),
+                self.height(),
+            ),
+            "height" => (
+                self.width(),
+                parse_dimension(value).unwrap_or(DEFAULT_HEIGHT),
+            ),
+            _ => return,
+        };
+        self.context = CanvasRenderingContext2D::new(width, height);
+    }
+}
+
+impl NodeHooks for HTMLCanvasElement {}
+
+impl ElementMethods for HTMLCanvasElement {
+    fn tag_name(&self) -> String {
+        "canvas".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resize_clears_the_bitmap() {
+        let mut canvas = HTMLCanvasElement::empty();
+        canvas.context_2d_mut().fill_rect(0., 0., 10., 10.);
+        assert_eq!((canvas.width(), canvas.height()), (300, 150));
+
+        canvas.on_attribute_change("width", "20");
+        canvas.on_attribute_change("height", "x");
+        assert_eq!((canvas.width(), canvas.height()), (20, 150));
+        assert!(canvas.context_2d().commands().is_empty());
+    }
+}
@@ -0,0 +1,203 @@
+/// The HTML element interfaces without state of their own. The state of
+/// these elements is in their attributes, reflected by the accessor traits.
+use super::{ElementHooks, ElementMethods};
+use crate::dom_ref::NodeRef;
+use crate::node::NodeHooks;
+use crate::reflect_attributes;
+
+html_element!(HTMLHtmlElement, "html");
+html_element!(HTMLHeadElement, "head");
+html_element!(HTMLTitleElement, "title");
+html_element!(HTMLStyleElement, "style", HTMLStyleElementAttributes {
+    string media / set_media: "media",
+});
+html_element!(HTMLScriptElement, "script", HTMLScriptElementAttributes {
+    url src / set_src: "src",
+    string script_type / set_script_type: "type",
+    bool defer / set_defer: "defer",
+    bool is_async / set_async: "async",
+});
+/// A `<meta>` element. A `<meta http-equiv=refresh>` requests the
+/// document to navigate after a delay when the element is inserted.
+/// https://html.spec.whatwg.org/multipage/semantics.html#the-meta-element
+#[derive(Debug)]
+pub struct HTMLMetaElement {
+    http_equiv: Option<String>,
+    content: Option<String>,
+}
+
+impl HTMLMetaElement {
+    pub fn empty() -> Self {
+        Self {
+            http_equiv: None,
+            content: None,
+        }
+    }
+}
+
+impl ElementHooks for HTMLMetaElement {
+    fn on_attribute_change(&mut self, attr: &str, value: &str) {
+        match attr {
+            "http-equiv" => self.http_equiv = Some(value.to_string()),
+            "content" => self.content = Some(value.to_string()),
+            _ => {}
+        }
+    }
+
+    fn on_attribute_remove(&mut self, attr: &str) {
+        match attr {
+            "http-equiv" => self.http_equiv = None,
+            "content" => self.content = None,
+            _ => {}
+        }
+    }
+}
+
+impl NodeHooks for HTMLMetaElement {
+    fn on_inserted(&mut self, document: NodeRef) {
+        let is_refresh = self
+            .http_equiv
+            .as_ref()
+            .map_or(false, |value| value.eq_ignore_ascii_case("refresh"));
+        if let (true, Some(content)) = (is_refresh, &self.content) {
+            document.borrow_mut().as_document_mut().set_refresh(content);
+        }
+    }
+}
+
+impl ElementMethods for HTMLMetaElement {
+    fn tag_name(&self) -> String {
+        "meta".to_string()
+    }
+}
+
+reflect_attributes! {
+    pub trait HTMLMetaElementAttributes {
+        string name / set_name: "name",
+        string http_equiv / set_http_equiv: "http-equiv",
+        string content / set_content: "content",
+        string charset / set_charset: "charset",
+    }
+}
+
+/// A `<base>` element. The first `<base>` with a `href` sets the base URL
+/// of the document when it's inserted.
+/// https://html.spec.whatwg.org/multipage/semantics.html#the-base-element
+#[derive(Debug)]
+pub struct HTMLBaseElement {
+    href: Option<String>,
+}
+
+impl HTMLBaseElement {
+    pub fn empty() -> Self {
+        Self { href: None }
+    }
+}
+
+impl ElementHooks for HTMLBaseElement {
+    fn on_attribute_change(&mut self, attr: &str, value: &str) {
+        if attr == "href" {
+            self.href = Some(value.trim().to_string());
+        }
+    }
+
+    fn on_attribute_remove(&mut self, attr: &str) {
+        if attr == "href" {
+            self.href = None;
+        }
+    }
+}
+
+impl NodeHooks for HTMLBaseElement {
+    fn on_inserted(&mut self, document: NodeRef) {
+        if let Some(href) = &self.href {
+            document
+                .borrow_mut()
+                .as_document_mut()
+                .set_base_element_href(href);
+        }
+    }
+}
+
+impl ElementMethods for HTMLBaseElement {
+    fn tag_name(&self) -> String {
+        "base".to_string()
+    }
+}
+
+reflect_attributes! {
+    pub trait HTMLBaseElementAttributes {
+        url href / set_href: "href",
+        string target / set_target: "target",
+    }
+}
+
+html_element!(HTMLBodyElement, "body");
+html_element!(
+    /// The known elements without an interface of their own, which have
+    /// the `HTMLElement` interface, e.g. `<section>` or `<em>`
+    HTMLGenericElement,
+    tag_name
+);
+html_element!(HTMLDivElement, "div");
+html_element!(HTMLSpanElement, "span");
+html_element!(HTMLParagraphElement, "p");
+html_element!(HTMLPreElement, "pre");
+html_element!(
+    /// `<q>` & `<blockquote>`
+    HTMLQuoteElement,
+    tag_name,
+    HTMLQuoteElementAttributes {
+        url cite / set_cite: "cite",
+    }
+);
+html_element!(
+    /// `<ins>` & `<del>`
+    HTMLModElement,
+    tag_name,
+    HTMLModElementAttributes {
+        url cite / set_cite: "cite",
+        string date_time / set_date_time: "datetime",
+    }
+);
+html_element!(HTMLBRElement, "br");
+html_element!(HTMLHRElement, "hr");
+html_element!(
+    /// `<h1>` to `<h6>`
+    HTMLHeadingElement,
+    tag_name
+);
+html_element!(HTMLUListElement, "ul");
+html_element!(HTMLDListElement, "dl");
+html_element!(HTMLOListElement, "ol", HTMLOListElementAttributes {
+    bool reversed / set_reversed: "reversed",
+    string start / set_start: "start",
+    string list_type / set_list_type: "type",
+});
+html_element!(HTMLLIElement, "li", HTMLLIElementAttributes {
+    string value / set_value: "value",
+});
+html_element!(HTMLTableElement, "table");
+html_element!(
+    /// `<thead>`, `<tbody>` & `<tfoot>`
+    HTMLTableSectionElement,
+    tag_name
+);
+html_element!(HTMLTableRowElement, "tr");
+html_element!(
+    /// `<td>` & `<th>`
+    HTMLTableCellElement,
+    tag_name,
+    HTMLTableCellElementAttributes {
+        string col_span / set_col_span: "colspan",
+        string row_span / set_row_span: "rowspan",
+    }
+);
+html_element!(HTMLLabelElement, "label", HTMLLabelElementAttributes {
+    string html_for / set_html_for: "for",
+});
+html_element!(
+    /// Any element without an interface of its own
+    HTMLUnknownElement,
+    tag_name
+);
@@ -0,0 +1,76 @@
+use super::ElementHooks;
+use super::ElementMethods;
+use crate::node::NodeHooks;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormMethod {
+    Get,
+    Post,
+}
+
+/// How the data of a form is encoded when it's posted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FormEnctype {
+    UrlEncoded,
+    Multipart,
+}
+
+#[derive(Debug)]
+pub struct HTMLFormElement {
+    action: String,
+    method: FormMethod,
+    enctype: FormEnctype,
+}
+
+impl HTMLFormElement {
+    pub fn empty() -> Self {
+        Self {
+            action: String::new(),
+            method: FormMethod::Get,
+            enctype: FormEnctype::UrlEncoded,
+        }
+    }
+
+    /// The URL the form is submitted to, empty for the URL of the document
+    pub fn action(&self) -> &str {
+        &self.action
+    }
+
+    pub fn method(&self) -> FormMethod {
+        self.method
+    }
+
+    pub fn enctype(&self) -> FormEnctype {
+        self.enctype
+    }
+}
+
+impl ElementHooks for HTMLFormElement {
+    fn on_attribute_change(&mut self, attr: &str, value: &str) {
+        match attr {
+            "action" => self.action = value.trim().to_string(),
+            "method" => {
+                self.method = match value.to_ascii_lowercase().as_str() {
+                    "post" => FormMethod::Post,
+                    _ => FormMethod::Get,
+                }
+            }
+            "enctype" => {
+                self.enctype = match value.to_ascii_lowercase().as_str() {
+                    "multipart/form-data" => FormEnctype::Multipart,
+                    // TODO: support text/plain
+                    _ => FormEnctype::UrlEncoded,
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+impl NodeHooks for HTMLFormElement {}
+
+impl ElementMethods for HTMLFormElement {
+    fn tag_name(&self) -> String {
+        "form".to_string()
+    }
+}
@@ -0,0 +1,102 @@
+use super::ElementHooks;
+use super::ElementMethods;
+use crate::dom_ref::NodeRef;
+use crate::images::load_image;
+use crate::node::NodeHooks;
+use url::Url;
+
+/// An `<img>` element. The image is loaded into the images of the
+/// document when the element is inserted, the `width` & `height`
+/// attributes giving its size until it's loaded.
+/// https://html.spec.whatwg.org/multipage/embedded-content.html#the-img-element
+#[derive(Debug)]
+pub struct HTMLImageElement {
+    src_attribute: Option<String>,
+    src: Option<Url>,
+    width: Option<u32>,
+    height: Option<u32>,
+}
+
+impl HTMLImageElement {
+    pub fn empty() -> Self {
+        Self {
+            src_attribute: None,
+            src: None,
+            width: None,
+            height: None,
+        }
+    }
+
+    pub fn src(&self) -> Option<&Url> {
+        self.src.as_ref()
+    }
+
+    pub fn width(&self) -> Option<u32> {
+        self.width
+    }
+
+    pub fn height(&self) -> Option<u32> {
+        self.height
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/common-microsyntaxes.html#rules-for-parsing-non-negative-integers
+pub(crate) fn parse_dimension(value: &str) -> Option<u32> {
+    let value = value.trim();
+    let end = value
+        .find(|ch: char| !ch.is_ascii_digit())
+        .unwrap_or_else(|| value.len());
+    value[..end].parse().ok()
+}
+
+impl ElementHooks for HTMLImageElement {
+    fn on_attribute_change(&mut self, attr: &str, value: &str) {
+        match attr {
+            "src" => {
+                self.src_attribute = Some(value.trim().to_string()).filter(|src| !src.is_empty());
+                self.src = match Url::parse(value) {
+                    Ok(url) => Some(url),
+                    Err(_) => {
+                        log::info!("Invalid src URL: {}", value);
+                        None
+                    }
+                }
+            }
+            "width" => self.width = parse_dimension(value),
+            "height" => self.height = parse_dimension(value),
+            _ => {}
+        }
+    }
+}
+
+impl NodeHooks for HTMLImageElement {
+    fn on_inserted(&mut self, document: NodeRef) {
+        // relative URLs can only be resolved once the document is known
+        if let Some(src) = &self.src_attribute {
+            self.src = document.borrow().as_document().parse_url(src);
+        }
+        if let Some(url) = &self.src {
+            load_image(&document, url);
+        }
+    }
+}
+
+impl ElementMethods for HTMLImageElement {
+    fn tag_name(&self) -> String {
+        "img".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_dimensions() {
+        let mut image = HTMLImageElement::empty();
+        image.on_attribute_change("width", " 120px");
+        image.on_attribute_change("height", "abc");
+        assert_eq!(image.width(), Some(120));
+        assert_eq!(image.height(), None);
+    }
+}
@@ -0,0 +1,334 @@
+use super::ElementHooks;
+use super::ElementMethods;
+use crate::event::{KeyEventKind, KeyboardEvent};
+use crate::node::NodeHooks;
+
+/// The number of characters a text input shows without a `size` attribute
+pub const DEFAULT_INPUT_SIZE: usize = 20;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum InputType {
+    Text,
+    Checkbox,
+    Radio,
+}
+
+/// The state of an `<input>` element. The value & checkedness start as
+/// the ones of the `value` & `checked` attributes and stop following the
+/// attributes once the user changes them.
+/// https://html.spec.whatwg.org/multipage/input.html#the-input-element
+#[derive(Debug)]
+pub struct HTMLInputElement {
+    input_type: InputType,
+    name: String,
+    size: usize,
+    value: String,
+    dirty_value: bool,
+    checked: bool,
+    dirty_checkedness: bool,
+    /// The position of the caret, in characters
+    caret: usize,
+    /// The other end of the selection, the caret being the end that moves
+    selection_anchor: Option<usize>,
+}
+
+impl HTMLInputElement {
+    pub fn empty() -> Self {
+        Self {
+            input_type: InputType::Text,
+            name: String::new(),
+            size: DEFAULT_INPUT_SIZE,
+            value: String::new(),
+            dirty_value: false,
+            checked: false,
+            dirty_checkedness: false,
+            caret: 0,
+            selection_anchor: None,
+        }
+    }
+
+    pub fn input_type(&self) -> InputType {
+        self.input_type
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// The width of a text input, in characters
+    pub fn size(&self) -> usize {
+        self.size
+    }
+
+    pub fn value(&self) -> &str {
+        &self.value
+    }
+
+    /// Set the value as the user would, the caret moves to the end
+    pub fn set_value(&mut self, value: &str) {
+        self.value = value.to_string();
+        self.dirty_value = true;
+        self.caret = self.len();
+        self.selection_anchor = None;
+    }
+
+    pub fn checked(&self) -> bool {
+        self.checked
+    }
+
+    pub fn set_checked(&mut self, checked: bool) {
+        self.checked = checked;
+        self.dirty_checkedness = true;
+    }
+
+    pub fn caret(&self) -> usize {
+        self.caret
+    }
+
+    /// The selected characters, from the start to the end of the selection
+    pub fn selection(&self) -> Option<(usize, usize)> {
+        let anchor = self.selection_anchor?;
+        match anchor {
+            anchor if anchor < self.caret => Some((anchor, self.caret)),
+            anchor if anchor > self.caret => Some((self.caret, anchor)),
+            _ => None,
+        }
+    }
+
+    /// The text of the selected characters, e.g. to copy it
+    pub fn selected_text(&self) -> Option<String> {
+        let (start, end) = self.selection()?;
+        Some(self.value.chars().skip(start).take(end - start).collect())
+    }
+
+    pub fn select_all(&mut self) {
+        self.selection_anchor = Some(0);
+        self.caret = self.len();
+    }
+
+    fn len(&self) -> usize {
+        self.value.chars().count()
+    }
+
+    fn byte_offset(&self, index: usize) -> usize {
+        self.value
+            .char_indices()
+            .nth(index)
+            .map(|(offset, _)| offset)
+            .unwrap_or_else(|| self.value.len())
+    }
+
+    /// Remove the selected characters, returning whether there were any
+    fn delete_selection(&mut self) -> bool {
+        match self.selection() {
+            Some((start, end)) => {
+                let range = self.byte_offset(start)..self.byte_offset(end);
+                self.value.replace_range(range, "");
+                self.caret = start;
+                self.selection_anchor = None;
+                true
+            }
+            None => false,
+        }
+    }
+
+    fn insert(&mut self, text: &str) {
+        self.delete_selection();
+        let offset = self.byte_offset(self.caret);
+        self.value.insert_str(offset, text);
+        self.caret += text.chars().count();
+        self.dirty_value = true;
+    }
+
+    fn delete_backward(&mut self) {
+        if !self.delete_selection() && self.caret > 0 {
+            let range = self.byte_offset(self.caret - 1)..self.byte_offset(self.caret);
+            self.value.replace_range(range, "");
+            self.caret -= 1;
+        }
+        self.dirty_value = true;
+    }
+
+    fn delete_forward(&mut self) {
+        if !self.delete_selection() && self.caret < self.len() {
+            let range = self.byte_offset(self.caret)..self.byte_offset(self.caret + 1);
+            self.value.replace_range(range, "");
+        }
+        self.dirty_value = true;
+    }
+
+    /// Move the caret, extending the selection when the shift key is held.
+    /// Without shift, moving by one character from a selection collapses
+    /// it to its start or end.
+    fn move_caret(&mut self, position: usize, extend: bool) {
+        if extend {
+            if self.selection_anchor.is_none() {
+                self.selection_anchor = Some(self.caret);
+            }
+        } else {
+            self.selection_anchor = None;
+        }
+        self.caret = position.min(self.len());
+    }
+
+    fn handle_text_key(&mut self, event: &KeyboardEvent) -> bool {
+        let extend = event.modifiers.shift;
+        let shortcut = event.modifiers.ctrl || event.modifiers.meta;
+        match event.key.as_str() {
+            "Backspace" => self.delete_backward(),
+            "Delete" => self.delete_forward(),
+            "ArrowLeft" => match self.selection() {
+                Some((start, _)) if !extend => self.move_caret(start, false),
+                _ => self.move_caret(self.caret.saturating_sub(1), extend),
+            },
+            "ArrowRight" => match self.selection() {
+                Some((_, end)) if !extend => self.move_caret(end, false),
+                _ => self.move_caret(self.caret + 1, extend),
+            },
+            "Home" => self.move_caret(0, extend),
+            "End" => self.move_caret(self.len(), extend),
+            "a" | "A" if shortcut => self.select_all(),
+            key if key.chars().count() == 1 && !shortcut && !event.modifiers.alt => {
+                self.insert(key)
+            }
+            _ => return false,
+        }
+        true
+    }
+}
+
+impl ElementHooks for HTMLInputElement {
+    fn on_attribute_change(&mut self, attr: &str, value: &str) {
+        match attr {
+            "type" => {
+                self.input_type = match value.to_ascii_lowercase().as_str() {
+                    "checkbox" => InputType::Checkbox,
+                    "radio" => InputType::Radio,
+                    // TODO: support the other input types
+                    _ => InputType::Text,
+                }
+            }
+            "name" => self.name = value.to_string(),
+            "size" => {
+                self.size = match value.trim().parse::<usize>() {
+                    Ok(size) if size > 0 => size,
+                    _ => DEFAULT_INPUT_SIZE,
+                }
+            }
+            "value" if !self.dirty_value => {
+                self.value = value.to_string();
+                self.caret = self.len();
+                self.selection_anchor = None;
+            }
+            "checked" if !self.dirty_checkedness => self.checked = true,
+            _ => {}
+        }
+    }
+
+    fn on_attribute_remove(&mut self, attr: &str) {
+        match attr {
+            "checked" if !self.dirty_checkedness => self.checked = false,
+            "checked" => {}
+            _ => self.on_attribute_change(attr, ""),
+        }
+    }
+
+    fn on_key_event(&mut self, event: &KeyboardEvent) -> bool {
+        if self.input_type != InputType::Text || event.kind != KeyEventKind::KeyDown {
+            return false;
+        }
+        self.handle_text_key(event)
+    }
+
+    fn on_activate(&mut self) {
+        match self.input_type {
+            InputType::Checkbox => self.set_checked(!self.checked),
+            InputType::Radio => self.set_checked(true),
+            InputType::Text => {}
+        }
+    }
+}
+
+impl NodeHooks for HTMLInputElement {}
+
+impl ElementMethods for HTMLInputElement {
+    fn tag_name(&self) -> String {
+        "input".to_string()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::event::Modifiers;
+
+    fn type_keys(input: &mut HTMLInputElement, keys: &[&str]) {
+        for key in keys {
+            input.on_key_event(&KeyboardEvent::key_down(key));
+        }
+    }
+
+    fn shift(key: &str) -> KeyboardEvent {
+        KeyboardEvent::key_down(key).with_modifiers(Modifiers {
+            shift: true,
+            ..Modifiers::default()
+        })
+    }
+
+    #[test]
+    fn edit_text() {
+        let mut input = HTMLInputElement::empty();
+        input.on_attribute_change("value", "héllo");
+        assert_eq!(input.caret(), 5);
+
+        type_keys(&mut input, &["Backspace", "ArrowLeft", "ArrowLeft", "x"]);
+        assert_eq!(input.value(), "héxll");
+        assert_eq!(input.caret(), 3);
+        type_keys(&mut input, &["Home", "Delete", "End", " ", "!"]);
+        assert_eq!(input.value(), "éxll !");
+
+        // the typed value isn't replaced by the attribute anymore
+        input.on_attribute_change("value", "reset");
+        assert_eq!(input.value(), "éxll !");
+    }
+
+    #[test]
+    fn replace_selection() {
+        let mut input = HTMLInputElement::empty();
+        input.set_value("hello world");
+
+        input.on_key_event(&shift("ArrowLeft"));
+        input.on_key_event(&shift("ArrowLeft"));
+        assert_eq!(input.selection(), Some((9, 11)));
+        assert_eq!(input.selected_text().as_deref(), Some("ld"));
+        type_keys(&mut input, &["X"]);
+        assert_eq!(input.value(), "hello worX");
+        assert_eq!(input.selection(), None);
+
+        input.on_key_event(&shift("Home"));
+        assert_eq!(input.selection(), Some((0, 10)));
+        // moving without shift collapses the selection
+        type_keys(&mut input, &["ArrowRight"]);
+        assert_eq!(input.caret(), 10);
+        assert_eq!(input.selection(), None);
+
+        input.select_all();
+        type_keys(&mut input, &["Backspace"]);
+        assert_eq!(input.value(), "");
+    }
+
+    #[test]
+    fn toggle_checkbox() {
+        let mut input = HTMLInputElement::empty();
+        input.on_attribute_change("type", "checkbox");
+        input.on_attribute_change("checked", "");
+        assert!(input.checked());
+
+        // checkboxes aren't edited with the keyboard, they're activated
+        assert!(!input.on_key_event(&KeyboardEvent::key_down("a")));
+        input.on_activate();
+        assert!(!input.checked());
+        input.on_attribute_change("checked", "");
+        assert!(!input.checked());
+    }
+}
@@ -1,16 +1,13 @@
 use super::ElementHooks;
 use super::ElementMethods;
-use crate::document_loader::LoadRequest;
+use crate::document_loader::{Destination, LoadRequest};
 use crate::dom_ref::NodeRef;
 use crate::node::NodeHooks;
 use url::Url;
 
-use css::parser::Parser;
-use css::tokenizer::{token::Token, Tokenizer};
-
 #[derive(Debug)]
 pub struct HTMLLinkElement {
-    href: Option<Url>,
+    href: Option<String>,
     relationship: Option<HTMLLinkRelationship>,
 }
 
@@ -28,25 +25,30 @@ impl HTMLLinkElement {
     }
 
     pub fn load_stylesheet(&self, url: &Url, document: NodeRef) {
-        let cloned_doc = document.clone();
+        let success_doc = document.clone();
+        let error_doc = document.clone();
         let raw_url = url.raw().to_string();
+        let base_url = url.clone();
 
         log::info!("Loading stylesheet from: {}", raw_url);
 
+        // the stylesheet keeps its place in the cascade while it's loading
+        let id = document.borrow_mut().as_document_mut().reserve_stylesheet();
         let request = LoadRequest::new(url.clone())
+            .with_destination(Destination::Style)
             .on_success(Box::new(move |bytes| {
-                let css = String::from_utf8(bytes).unwrap();
-                let tokenizer = Tokenizer::new(css.chars());
-                let mut parser = Parser::<Token>::new(tokenizer.run());
-                let stylesheet = parser.parse_a_css_stylesheet();
-
-                cloned_doc
+                let css = String::from_utf8_lossy(&bytes).to_string();
+                success_doc
                     .borrow_mut()
                     .as_document_mut()
-                    .append_stylesheet(stylesheet);
+                    .set_stylesheet_source(id, css, Some(base_url));
             }))
             .on_error(Box::new(move |e| {
-                log::info!("Unable to load CSS: {} ({})", e, raw_url)
+                log::info!("Unable to load CSS: {} ({})", e, raw_url);
+                error_doc
+                    .borrow_mut()
+                    .as_document_mut()
+                    .cancel_stylesheet(id);
             }));
 
         let loader = document
@@ -62,17 +64,17 @@ impl ElementHooks for HTMLLinkElement {
     fn on_attribute_change(&mut self, attr: &str, value: &str) {
         match attr {
             "href" => {
-                self.href = match Url::parse(value) {
-                    Ok(url) => Some(url),
-                    Err(_) => {
-                        log::info!("Invalid href URL: {}", value);
-                        None
-                    }
+                let href = value.trim();
+                self.href = if href.is_empty() {
+                    None
+                } else {
+                    Some(href.to_string())
                 }
             }
             "rel" => {
-                if value == "stylesheet" {
-                    self.relationship = Some(HTMLLinkRelationship::Stylesheet);
+                self.relationship = match value {
+                    "stylesheet" => Some(HTMLLinkRelationship::Stylesheet),
+                    _ => None,
                 }
             }
             _ => {}
@@ -82,12 +84,21 @@ impl ElementHooks for HTMLLinkElement {
 
 impl NodeHooks for HTMLLinkElement {
     fn on_inserted(&mut self, document: NodeRef) {
-        match &self.href {
+        let href = match &self.href {
+            Some(href) => href,
+            None => {
+                log::info!("No URL found, ignoring");
+                return;
+            }
+        };
+        // the href is resolved against the base URL of the document
+        let url = document.borrow().as_document().parse_url(href);
+        match url {
             Some(url) => match self.relationship {
-                Some(HTMLLinkRelationship::Stylesheet) => self.load_stylesheet(url, document),
+                Some(HTMLLinkRelationship::Stylesheet) => self.load_stylesheet(&url, document),
                 _ => {}
             },
-            None => log::info!("No URL found, ignoring"),
+            None => log::info!("Invalid href URL: {}", href),
         }
     }
 }
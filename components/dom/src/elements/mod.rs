@@ -1,42 +1,189 @@
 use super::dom_ref::NodeRef;
+use super::event::KeyboardEvent;
 use super::node::NodeHooks;
 use enum_dispatch::enum_dispatch;
 
+/// Generate an element interface without state of its own: the struct,
+/// its constructor & the element hooks. The interfaces shared by several
+/// tags keep their tag name, e.g. `html_element!(HTMLHeadingElement, tag_name)`.
+/// The attributes of the interface are reflected by a trait generated
+/// with `reflect_attributes!`.
+macro_rules! html_element {
+    ($(#[$meta:meta])* $name:ident, $tag:literal) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub struct $name {}
+
+        impl $name {
+            pub fn empty() -> Self {
+                Self {}
+            }
+        }
+
+        impl super::ElementHooks for $name {}
+
+        impl crate::node::NodeHooks for $name {}
+
+        impl super::ElementMethods for $name {
+            fn tag_name(&self) -> String {
+                $tag.to_string()
+            }
+        }
+    };
+    ($(#[$meta:meta])* $name:ident, tag_name) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub struct $name {
+            tag_name: String,
+        }
+
+        impl $name {
+            pub fn new(tag_name: String) -> Self {
+                Self { tag_name }
+            }
+        }
+
+        impl super::ElementHooks for $name {}
+
+        impl crate::node::NodeHooks for $name {}
+
+        impl super::ElementMethods for $name {
+            fn tag_name(&self) -> String {
+                self.tag_name.clone()
+            }
+        }
+    };
+    ($(#[$meta:meta])* $name:ident, $tag:tt, $attributes:ident { $($accessors:tt)* }) => {
+        html_element!($(#[$meta])* $name, $tag);
+
+        reflect_attributes! {
+            pub trait $attributes {
+                $($accessors)*
+            }
+        }
+    };
+}
+
 mod html_anchor_element;
-mod html_body_element;
-mod html_div_element;
-mod html_head_element;
-mod html_html_element;
+mod html_button_element;
+mod html_canvas_element;
+mod html_elements;
+mod html_form_element;
+mod html_image_element;
+mod html_input_element;
 mod html_link_element;
-mod html_title_element;
-mod html_unknown_element;
+mod svg_element;
+mod svg_svg_element;
 
 pub use html_anchor_element::*;
-pub use html_body_element::*;
-pub use html_div_element::*;
-pub use html_head_element::*;
-pub use html_html_element::*;
+pub use html_button_element::*;
+pub use html_canvas_element::*;
+pub use html_elements::*;
+pub use html_form_element::*;
+pub use html_image_element::*;
+pub use html_input_element::*;
 pub use html_link_element::*;
-pub use html_title_element::*;
-pub use html_unknown_element::*;
+pub use svg_element::*;
+pub use svg_svg_element::*;
+
+/// Generate the element data enum & the downcasts of the data to the
+/// element interfaces
+macro_rules! element_data {
+    ($($variant:ident($interface:ident)),* $(,)?) => {
+        #[enum_dispatch(ElementHooks, NodeHooks, ElementMethods)]
+        #[derive(Debug)]
+        pub enum ElementData {
+            $($variant($interface)),*
+        }
+
+        $(
+            impl ElementInterface for $interface {
+                fn from_data(data: &ElementData) -> Option<&Self> {
+                    match data {
+                        ElementData::$variant(element) => Some(element),
+                        _ => None,
+                    }
+                }
+
+                fn from_data_mut(data: &mut ElementData) -> Option<&mut Self> {
+                    match data {
+                        ElementData::$variant(element) => Some(element),
+                        _ => None,
+                    }
+                }
+            }
+        )*
+    };
+}
+
+/// An element interface that the element data can be downcasted to,
+/// e.g. `data.downcast_ref::<HTMLImageElement>()`
+pub trait ElementInterface {
+    fn from_data(data: &ElementData) -> Option<&Self>;
+    fn from_data_mut(data: &mut ElementData) -> Option<&mut Self>;
+}
 
-#[enum_dispatch(ElementHooks, NodeHooks, ElementMethods)]
-#[derive(Debug)]
-pub enum ElementData {
+element_data! {
     Anchor(HTMLAnchorElement),
+    Base(HTMLBaseElement),
     Body(HTMLBodyElement),
+    Br(HTMLBRElement),
+    Button(HTMLButtonElement),
+    Canvas(HTMLCanvasElement),
+    DList(HTMLDListElement),
     Div(HTMLDivElement),
+    Form(HTMLFormElement),
+    Generic(HTMLGenericElement),
     Head(HTMLHeadElement),
+    Heading(HTMLHeadingElement),
+    Hr(HTMLHRElement),
     Html(HTMLHtmlElement),
+    Image(HTMLImageElement),
+    Input(HTMLInputElement),
+    Label(HTMLLabelElement),
+    Li(HTMLLIElement),
+    Meta(HTMLMetaElement),
+    Mod(HTMLModElement),
+    OList(HTMLOListElement),
+    Paragraph(HTMLParagraphElement),
+    Pre(HTMLPreElement),
+    Quote(HTMLQuoteElement),
+    Script(HTMLScriptElement),
+    Span(HTMLSpanElement),
+    Style(HTMLStyleElement),
+    Table(HTMLTableElement),
+    TableCell(HTMLTableCellElement),
+    TableRow(HTMLTableRowElement),
+    TableSection(HTMLTableSectionElement),
     Title(HTMLTitleElement),
+    UList(HTMLUListElement),
     Unknown(HTMLUnknownElement),
     Link(HTMLLinkElement),
+    Svg(SVGSvgElement),
+    SvgElement(SVGElement),
 }
 
 #[enum_dispatch]
 trait ElementHooks {
     #[allow(unused_variables)]
     fn on_attribute_change(&mut self, attr: &str, value: &str) {}
+
+    /// Handle the removal of an attribute, which resets the state like an
+    /// empty value unless the element overrides it
+    fn on_attribute_remove(&mut self, attr: &str) {
+        self.on_attribute_change(attr, "");
+    }
+
+    /// Handle a keyboard event delivered to the element, returning
+    /// whether the event was handled
+    #[allow(unused_variables)]
+    fn on_key_event(&mut self, event: &KeyboardEvent) -> bool {
+        false
+    }
+
+    /// The activation behavior of the element, run when it's clicked or
+    /// activated with the keyboard
+    fn on_activate(&mut self) {}
 }
 
 #[enum_dispatch]
@@ -47,11 +194,31 @@ pub trait ElementMethods {
 }
 
 impl ElementData {
+    pub fn downcast_ref<T: ElementInterface>(&self) -> Option<&T> {
+        T::from_data(self)
+    }
+
+    pub fn downcast_mut<T: ElementInterface>(&mut self) -> Option<&mut T> {
+        T::from_data_mut(self)
+    }
+
     pub fn handle_attribute_change(&mut self, attr: &str, value: &str) {
         self.on_attribute_change(attr, value);
     }
 
+    pub fn handle_attribute_remove(&mut self, attr: &str) {
+        self.on_attribute_remove(attr);
+    }
+
     pub fn handle_on_inserted(&mut self, document: NodeRef) {
         self.on_inserted(document);
     }
+
+    pub fn handle_key_event(&mut self, event: &KeyboardEvent) -> bool {
+        self.on_key_event(event)
+    }
+
+    pub fn handle_activation(&mut self) {
+        self.on_activate();
+    }
 }
@@ -0,0 +1,26 @@
+use super::ElementHooks;
+use super::ElementMethods;
+use crate::node::NodeHooks;
+
+/// Any element inside an `<svg>` element, e.g. a shape or a group. The
+/// shapes are built from their attributes when the image is painted.
+#[derive(Debug)]
+pub struct SVGElement {
+    tag_name: String,
+}
+
+impl SVGElement {
+    pub fn new(tag_name: String) -> Self {
+        Self { tag_name }
+    }
+}
+
+impl ElementHooks for SVGElement {}
+
+impl NodeHooks for SVGElement {}
+
+impl ElementMethods for SVGElement {
+    fn tag_name(&self) -> String {
+        self.tag_name.clone()
+    }
+}
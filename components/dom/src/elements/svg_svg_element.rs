@@ -0,0 +1,26 @@
+use super::ElementHooks;
+use super::ElementMethods;
+use crate::node::NodeHooks;
+
+/// The root `<svg>` element of an inline SVG image. Its descendants
+/// aren't rendered as boxes: the element is a replaced element painting
+/// the image they describe.
+/// https://svgwg.org/svg2-draft/struct.html#SVGElement
+#[derive(Debug)]
+pub struct SVGSvgElement {}
+
+impl SVGSvgElement {
+    pub fn empty() -> Self {
+        Self {}
+    }
+}
+
+impl ElementHooks for SVGSvgElement {}
+
+impl NodeHooks for SVGSvgElement {}
+
+impl ElementMethods for SVGSvgElement {
+    fn tag_name(&self) -> String {
+        "svg".to_string()
+    }
+}
@@ -0,0 +1,47 @@
+/// A keyboard event delivered to the focused element of a document.
+/// The key is the value of the `key` attribute of the DOM event, e.g.
+/// `"a"`, `"Enter"` or `"Tab"`.
+/// https://w3c.github.io/uievents/#events-keyboardevents
+#[derive(Debug, Clone, PartialEq)]
+pub struct KeyboardEvent {
+    pub kind: KeyEventKind,
+    pub key: String,
+    pub modifiers: Modifiers,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum KeyEventKind {
+    KeyDown,
+    KeyUp,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Modifiers {
+    pub shift: bool,
+    pub ctrl: bool,
+    pub alt: bool,
+    pub meta: bool,
+}
+
+impl KeyboardEvent {
+    pub fn key_down(key: &str) -> Self {
+        Self {
+            kind: KeyEventKind::KeyDown,
+            key: key.to_string(),
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    pub fn key_up(key: &str) -> Self {
+        Self {
+            kind: KeyEventKind::KeyUp,
+            key: key.to_string(),
+            modifiers: Modifiers::default(),
+        }
+    }
+
+    pub fn with_modifiers(mut self, modifiers: Modifiers) -> Self {
+        self.modifiers = modifiers;
+        self
+    }
+}
@@ -0,0 +1,211 @@
+/// Focus management. The focused element of a document receives the
+/// keyboard events; the focus moves through the focusable elements
+/// with the Tab key or to an element when it is clicked.
+/// https://html.spec.whatwg.org/multipage/interaction.html#focus
+use super::activation;
+use super::dom_ref::NodeRef;
+use super::element::Element;
+use super::event::{KeyEventKind, KeyboardEvent};
+use super::forms;
+
+/// The tabindex of an element: the value of its `tabindex` attribute,
+/// or 0 for the elements focusable by default. `None` if the element
+/// isn't focusable. Elements with a negative index can be focused by
+/// clicking but are skipped by the Tab key.
+pub fn tab_index(element: &Element) -> Option<i32> {
+    if let Some(value) = element.get_attribute("tabindex") {
+        if let Ok(index) = value.trim().parse::<i32>() {
+            return Some(index);
+        }
+    }
+    let focusable = match element.tag_name().as_str() {
+        "a" | "area" => element.has_attribute("href"),
+        "input" | "button" | "select" | "textarea" => !element.has_attribute("disabled"),
+        _ => false,
+    };
+    if focusable {
+        Some(0)
+    } else {
+        None
+    }
+}
+
+pub fn is_focusable(node: &NodeRef) -> bool {
+    match node.borrow().as_element_opt() {
+        Some(element) => tab_index(element).is_some(),
+        None => false,
+    }
+}
+
+/// The closest focusable inclusive ancestor of a node, the element
+/// focused when the node is clicked
+pub fn focusable_ancestor(node: &NodeRef) -> Option<NodeRef> {
+    let mut current = Some(node.clone());
+    while let Some(node) = current {
+        if is_focusable(&node) {
+            return Some(node);
+        }
+        current = node.borrow().parent();
+    }
+    None
+}
+
+/// The elements the Tab key moves through: the elements with a positive
+/// tabindex in increasing order, then the ones with a tabindex of 0, in
+/// tree order
+/// https://html.spec.whatwg.org/multipage/interaction.html#sequential-focus-navigation-order
+pub fn sequential_focus_order(root: &NodeRef) -> Vec<NodeRef> {
+    let mut elements = Vec::new();
+    collect_tab_indices(root, &mut elements);
+    // the sort is stable so the elements of an index stay in tree order
+    elements.sort_by_key(|(index, _)| if *index > 0 { (0, *index) } else { (1, 0) });
+    elements.into_iter().map(|(_, node)| node).collect()
+}
+
+fn collect_tab_indices(node: &NodeRef, elements: &mut Vec<(i32, NodeRef)>) {
+    if let Some(element) = node.borrow().as_element_opt() {
+        match tab_index(element) {
+            Some(index) if index >= 0 => elements.push((index, node.clone())),
+            _ => {}
+        }
+    }
+    for child in node.borrow().child_nodes() {
+        collect_tab_indices(&child, elements);
+    }
+}
+
+pub fn focused_element(document: &NodeRef) -> Option<NodeRef> {
+    document.borrow().as_document().focused_element()
+}
+
+/// Focus an element of a document, or unfocus the focused element if
+/// the element isn't focusable. Returns whether the element is focused.
+pub fn focus(document: &NodeRef, element: Option<NodeRef>) -> bool {
+    let element = element.filter(is_focusable);
+    let focused = element.is_some();
+    document
+        .borrow_mut()
+        .as_document_mut()
+        .set_focused_element(element);
+    focused
+}
+
+/// Move the focus to the next element in the sequential focus order,
+/// or the previous one when going backwards. The focus wraps around at
+/// the end of the document.
+pub fn focus_next(document: &NodeRef, backwards: bool) -> Option<NodeRef> {
+    let order = sequential_focus_order(document);
+    if order.is_empty() {
+        focus(document, None);
+        return None;
+    }
+
+    let current = focused_element(document)
+        .and_then(|focused| order.iter().position(|node| *node == focused));
+    let last = order.len() - 1;
+    let next = match (current, backwards) {
+        (Some(index), false) if index < last => index + 1,
+        (Some(index), true) if index > 0 => index - 1,
+        (_, false) => 0,
+        (_, true) => last,
+    };
+
+    let element = order[next].clone();
+    focus(document, Some(element.clone()));
+    Some(element)
+}
+
+/// Deliver a keyboard event to the focused element of a document. The
+/// event bubbles up the ancestors of the element until one of them
+/// handles it. An unhandled Tab key moves the focus, an unhandled
+/// activation key activates the focused element & an unhandled Enter key
+/// in a text input submits its form. Returns whether the event was
+/// handled.
+pub fn dispatch_key_event(document: &NodeRef, event: &KeyboardEvent) -> bool {
+    let focused = focused_element(document);
+    let mut current = focused.clone();
+    while let Some(node) = current {
+        let handled = match node.borrow_mut().as_element_mut_opt() {
+            Some(element) => element.handle_key_event(event),
+            None => false,
+        };
+        if handled {
+            return true;
+        }
+        current = node.borrow().parent();
+    }
+
+    if event.kind == KeyEventKind::KeyDown && event.key == "Tab" {
+        focus_next(document, event.modifiers.shift);
+        return true;
+    }
+    if let Some(focused) = focused {
+        if activation::is_activation_key(&focused, event) {
+            activation::activate(&focused);
+            return true;
+        }
+        if forms::is_implicit_submission(&focused, event) {
+            if let Some(form) = forms::form_owner(&focused) {
+                forms::submit(&form, None);
+                return true;
+            }
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::element_factory::create_element;
+    use crate::event::Modifiers;
+    use crate::node::{Node, NodeData};
+
+    fn element(document: &NodeRef, parent: &NodeRef, tag_name: &str) -> NodeRef {
+        let node = create_element(document.clone().downgrade(), tag_name);
+        Node::append_child(parent.clone(), node.clone());
+        node
+    }
+
+    #[test]
+    fn cycle_focus_with_tab() {
+        let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let body = element(&document, &document, "body");
+        let link = element(&document, &body, "a");
+        Node::set_attribute(&link, "href", "https://example.com");
+        let div = element(&document, &body, "div");
+        let first = element(&document, &body, "div");
+        Node::set_attribute(&first, "tabindex", "1");
+        let skipped = element(&document, &body, "div");
+        Node::set_attribute(&skipped, "tabindex", "-1");
+
+        assert!(!is_focusable(&div));
+        assert_eq!(
+            sequential_focus_order(&document),
+            vec![first.clone(), link.clone()]
+        );
+
+        let tab = KeyboardEvent::key_down("Tab");
+        assert!(dispatch_key_event(&document, &tab));
+        assert_eq!(focused_element(&document), Some(first.clone()));
+        dispatch_key_event(&document, &tab);
+        assert_eq!(focused_element(&document), Some(link.clone()));
+        // the focus wraps around
+        dispatch_key_event(&document, &tab);
+        assert_eq!(focused_element(&document), Some(first.clone()));
+
+        let shift_tab = tab.with_modifiers(Modifiers {
+            shift: true,
+            ..Modifiers::default()
+        });
+        dispatch_key_event(&document, &shift_tab);
+        assert_eq!(focused_element(&document), Some(link));
+
+        // clicking focuses the closest focusable element
+        assert!(focus(&document, focusable_ancestor(&skipped)));
+        assert_eq!(focused_element(&document), Some(skipped));
+        assert!(!focus(&document, focusable_ancestor(&div)));
+        assert_eq!(focused_element(&document), None);
+    }
+}
@@ -0,0 +1,321 @@
+/// Form submission: the data of a form is collected from its controls
+/// when it's submitted and encoded into the request navigating to the
+/// action of the form. The page loading the document sends the request.
+/// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#form-submission-2
+use super::document::Document;
+use super::document_loader::{Method, RequestBody};
+use super::dom_ref::NodeRef;
+use super::elements::{ButtonType, ElementData, FormEnctype, FormMethod, InputType};
+use super::event::{KeyEventKind, KeyboardEvent};
+use url::Url;
+
+/// A name & value submitted with a form
+pub type FormEntry = (String, String);
+
+/// A submitted form waiting for the page to send its request
+#[derive(Debug, Clone, PartialEq)]
+pub struct FormSubmission {
+    /// The URL of the form action, empty to submit to the URL of the document
+    pub action: String,
+    pub method: Method,
+    /// The query replacing the one of the action URL, for GET
+    pub query: Option<String>,
+    /// The encoded entries, for POST
+    pub body: Option<RequestBody>,
+}
+
+impl FormSubmission {
+    /// The URL the request is sent to, the action resolved against
+    /// the base URL of the document
+    pub fn url(&self, document: &Document) -> Option<Url> {
+        let action = if self.action.is_empty() {
+            document.url().cloned()?
+        } else {
+            document.parse_url(&self.action)?
+        };
+        match &self.query {
+            Some(query) => action.with_query(query).ok(),
+            None => Some(action),
+        }
+    }
+}
+
+/// The form a control belongs to, its closest form ancestor
+pub fn form_owner(node: &NodeRef) -> Option<NodeRef> {
+    let mut current = node.borrow().parent();
+    while let Some(node) = current {
+        let is_form = matches!(
+            node.borrow().as_element_opt().map(|e| e.data()),
+            Some(ElementData::Form(_))
+        );
+        if is_form {
+            return Some(node);
+        }
+        current = node.borrow().parent();
+    }
+    None
+}
+
+pub fn is_submit_button(node: &NodeRef) -> bool {
+    match node.borrow().as_element_opt().map(|e| e.data()) {
+        Some(ElementData::Button(button)) => button.button_type() == ButtonType::Submit,
+        _ => false,
+    }
+}
+
+/// Whether a key event submits the form of an element: pressing enter
+/// in a text input
+/// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#implicit-submission
+pub fn is_implicit_submission(node: &NodeRef, event: &KeyboardEvent) -> bool {
+    if event.kind != KeyEventKind::KeyDown || event.key != "Enter" {
+        return false;
+    }
+    match node.borrow().as_element_opt().map(|e| e.data()) {
+        Some(ElementData::Input(input)) => input.input_type() == InputType::Text,
+        _ => false,
+    }
+}
+
+/// Submit a form, by a submit button or implicitly. The submission is
+/// kept by the document of the form until the page sends it.
+pub fn submit(form: &NodeRef, submitter: Option<&NodeRef>) {
+    let (action, method, enctype) = match form.borrow().as_element().data() {
+        ElementData::Form(form) => (form.action().to_string(), form.method(), form.enctype()),
+        _ => return,
+    };
+    let entries = entry_list(form, submitter);
+
+    let submission = match method {
+        FormMethod::Get => FormSubmission {
+            action,
+            method: Method::Get,
+            query: Some(urlencode(&entries)),
+            body: None,
+        },
+        FormMethod::Post => {
+            let body = match enctype {
+                FormEnctype::UrlEncoded => RequestBody {
+                    content_type: "application/x-www-form-urlencoded".to_string(),
+                    bytes: urlencode(&entries).into_bytes(),
+                },
+                FormEnctype::Multipart => {
+                    let boundary = multipart_boundary(&entries);
+                    RequestBody {
+                        content_type: format!("multipart/form-data; boundary={}", boundary),
+                        bytes: encode_multipart(&entries, &boundary),
+                    }
+                }
+            };
+            FormSubmission {
+                action,
+                method: Method::Post,
+                query: None,
+                body: Some(body),
+            }
+        }
+    };
+
+    let document = form.borrow().owner_document();
+    if let Some(document) = document {
+        document
+            .borrow_mut()
+            .as_document_mut()
+            .set_form_submission(submission);
+    }
+}
+
+/// The names & values of the controls of a form, in tree order. Only the
+/// button submitting the form is part of the entries.
+/// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#constructing-the-form-data-set
+pub fn entry_list(form: &NodeRef, submitter: Option<&NodeRef>) -> Vec<FormEntry> {
+    let mut entries = Vec::new();
+    for child in form.borrow().child_nodes() {
+        collect_entries(&child, submitter, &mut entries);
+    }
+    entries
+}
+
+// TODO: include the controls associated with the `form` attribute
+fn collect_entries(node: &NodeRef, submitter: Option<&NodeRef>, entries: &mut Vec<FormEntry>) {
+    if let Some(element) = node.borrow().as_element_opt() {
+        let attributes = element.attributes();
+        let name = attributes.get_str("name");
+        let is_candidate = !name.is_empty() && !element.has_attribute("disabled");
+
+        let value = match element.data() {
+            // the controls of a nested form belong to that form
+            ElementData::Form(_) => return,
+            ElementData::Input(input) if is_candidate => match input.input_type() {
+                InputType::Text => Some(input.value().to_string()),
+                InputType::Checkbox | InputType::Radio if input.checked() => {
+                    match attributes.get("value") {
+                        Some(value) => Some(value.to_string()),
+                        None => Some("on".to_string()),
+                    }
+                }
+                _ => None,
+            },
+            ElementData::Button(_) if is_candidate && submitter == Some(node) => {
+                Some(attributes.get_str("value"))
+            }
+            _ => None,
+        };
+        if let Some(value) = value {
+            entries.push((name, value));
+        }
+    }
+    for child in node.borrow().child_nodes() {
+        collect_entries(&child, submitter, entries);
+    }
+}
+
+/// Serialize entries as `application/x-www-form-urlencoded`
+/// https://url.spec.whatwg.org/#concept-urlencoded-serializer
+pub fn urlencode(entries: &[FormEntry]) -> String {
+    entries
+        .iter()
+        .map(|(name, value)| format!("{}={}", urlencode_str(name), urlencode_str(value)))
+        .collect::<Vec<String>>()
+        .join("&")
+}
+
+fn urlencode_str(input: &str) -> String {
+    let mut result = String::new();
+    for byte in input.bytes() {
+        match byte {
+            b' ' => result.push('+'),
+            b'*' | b'-' | b'.' | b'_' => result.push(byte as char),
+            byte if byte.is_ascii_alphanumeric() => result.push(byte as char),
+            byte => result.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    result
+}
+
+/// A boundary that doesn't appear in the entries
+fn multipart_boundary(entries: &[FormEntry]) -> String {
+    let mut boundary = String::from("----MoonFormBoundary");
+    while entries
+        .iter()
+        .any(|(name, value)| name.contains(&boundary) || value.contains(&boundary))
+    {
+        boundary.push('x');
+    }
+    boundary
+}
+
+/// Serialize entries as `multipart/form-data`
+/// https://html.spec.whatwg.org/multipage/form-control-infrastructure.html#multipart-form-data
+pub fn encode_multipart(entries: &[FormEntry], boundary: &str) -> Vec<u8> {
+    let mut body = String::new();
+    for (name, value) in entries {
+        let name = name
+            .replace('"', "%22")
+            .replace('\r', "%0D")
+            .replace('\n', "%0A");
+        body.push_str(&format!(
+            "--{}\r\nContent-Disposition: form-data; name=\"{}\"\r\n\r\n{}\r\n",
+            boundary, name, value
+        ));
+    }
+    body.push_str(&format!("--{}--\r\n", boundary));
+    body.into_bytes()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::element_factory::create_element;
+    use crate::node::{Node, NodeData};
+
+    fn element(document: &NodeRef, parent: &NodeRef, tag_name: &str) -> NodeRef {
+        let node = create_element(document.clone().downgrade(), tag_name);
+        Node::append_child(parent.clone(), node.clone());
+        node
+    }
+
+    fn input(document: &NodeRef, form: &NodeRef, attributes: &[(&str, &str)]) -> NodeRef {
+        let node = element(document, form, "input");
+        for (name, value) in attributes {
+            Node::set_attribute(&node, name, value);
+        }
+        node
+    }
+
+    #[test]
+    fn submit_form() {
+        let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let form = element(&document, &document, "form");
+        Node::set_attribute(&form, "action", "https://example.com/search?old#top");
+        input(&document, &form, &[("name", "q"), ("value", "a b&c")]);
+        input(&document, &form, &[("name", "off"), ("type", "checkbox")]);
+        input(
+            &document,
+            &form,
+            &[("name", "on"), ("type", "checkbox"), ("checked", "")],
+        );
+        input(&document, &form, &[("value", "no name")]);
+        input(&document, &form, &[("name", "disabled"), ("disabled", "")]);
+        let other = element(&document, &form, "button");
+        Node::set_attribute(&other, "name", "other");
+        let button = element(&document, &form, "button");
+        Node::set_attribute(&button, "name", "go");
+        Node::set_attribute(&button, "value", "1");
+        assert_eq!(form_owner(&button), Some(form.clone()));
+
+        assert_eq!(
+            entry_list(&form, Some(&button)),
+            vec![
+                ("q".to_string(), "a b&c".to_string()),
+                ("on".to_string(), "on".to_string()),
+                ("go".to_string(), "1".to_string()),
+            ]
+        );
+
+        submit(&form, Some(&button));
+        let submission = document
+            .borrow_mut()
+            .as_document_mut()
+            .take_form_submission()
+            .unwrap();
+        assert_eq!(submission.method, Method::Get);
+        let url = submission.url(document.borrow().as_document());
+        assert_eq!(
+            url.unwrap().raw(),
+            "https://example.com/search?q=a+b%26c&on=on&go=1"
+        );
+
+        let mut page = Document::new();
+        page.set_url(Url::parse("https://example.com/forms/new.html").unwrap());
+        let relative = FormSubmission {
+            action: "../save".to_string(),
+            method: Method::Post,
+            query: None,
+            body: None,
+        };
+        assert_eq!(
+            relative.url(&page).unwrap().raw(),
+            "https://example.com/save"
+        );
+    }
+
+    #[test]
+    fn post_multipart() {
+        let entries = vec![
+            ("a\"".to_string(), "é".to_string()),
+            ("b".to_string(), "----MoonFormBoundary".to_string()),
+        ];
+        assert_eq!(urlencode(&entries[..1]), "a%22=%C3%A9");
+
+        let boundary = multipart_boundary(&entries);
+        assert_eq!(boundary, "----MoonFormBoundaryx");
+        assert_eq!(
+            String::from_utf8(encode_multipart(&entries[..1], &boundary)).unwrap(),
+            "------MoonFormBoundaryx\r\n\
+             Content-Disposition: form-data; name=\"a%22\"\r\n\r\n\
+             é\r\n\
+             ------MoonFormBoundaryx--\r\n"
+        );
+    }
+}
@@ -0,0 +1,174 @@
+/// The images of a document, loaded once per URL & shared by the
+/// elements & the backgrounds painting them. Only SVG images are decoded
+/// for now, other formats fail to load.
+use super::document_loader::{Destination, LoadRequest};
+use super::dom_ref::NodeRef;
+use std::cell::RefCell;
+use std::rc::Rc;
+use svg::{xml, SvgImage};
+use url::Url;
+
+#[derive(Debug, Clone)]
+pub enum ImageState {
+    Loading,
+    Loaded(Rc<Image>),
+    Failed,
+}
+
+#[derive(Debug)]
+pub struct Image {
+    svg: SvgImage,
+    /// The pixels of the last size the image was painted at
+    raster: RefCell<Option<Raster>>,
+}
+
+#[derive(Debug)]
+struct Raster {
+    width: u32,
+    height: u32,
+    pixels: Rc<Vec<u8>>,
+}
+
+impl Image {
+    pub fn decode(bytes: &[u8]) -> Result<Self, String> {
+        if !is_svg(bytes) {
+            return Err("Unsupported image format".to_string());
+        }
+        Ok(Self {
+            svg: SvgImage::parse(bytes)?,
+            raster: RefCell::new(None),
+        })
+    }
+
+    /// The image drawn by an inline `<svg>` element & its descendants
+    pub fn from_svg_element(node: &NodeRef) -> Result<Self, String> {
+        let root = to_xml_element(node).ok_or_else(|| "Expected an element".to_string())?;
+        Ok(Self {
+            svg: SvgImage::from_element(&root)?,
+            raster: RefCell::new(None),
+        })
+    }
+
+    pub fn intrinsic_width(&self) -> Option<f32> {
+        self.svg.intrinsic_width()
+    }
+
+    pub fn intrinsic_height(&self) -> Option<f32> {
+        self.svg.intrinsic_height()
+    }
+
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        self.svg.aspect_ratio()
+    }
+
+    /// The RGBA pixels of the image painted at a size. Vector images are
+    /// rasterized again only when the size changes.
+    pub fn rasterize(&self, width: u32, height: u32) -> Rc<Vec<u8>> {
+        let mut raster = self.raster.borrow_mut();
+        match &*raster {
+            Some(raster) if raster.width == width && raster.height == height => {
+                raster.pixels.clone()
+            }
+            _ => {
+                let pixels = Rc::new(self.svg.rasterize(width, height));
+                *raster = Some(Raster {
+                    width,
+                    height,
+                    pixels: pixels.clone(),
+                });
+                pixels
+            }
+        }
+    }
+}
+
+/// The SVG document made of an element & its descendant elements
+fn to_xml_element(node: &NodeRef) -> Option<xml::Element> {
+    let node = node.borrow();
+    let element = node.as_element_opt()?;
+    let mut xml_element = xml::Element::new(element.tag_name().as_str());
+    xml_element.attributes = element
+        .attributes()
+        .iter()
+        .map(|(name, value)| (name.to_string(), value.clone()))
+        .collect();
+    xml_element.children = node
+        .child_nodes()
+        .into_iter()
+        .filter_map(|child| to_xml_element(&child))
+        .collect();
+    Some(xml_element)
+}
+
+/// Whether the content is an SVG document rather than a raster image,
+/// which starts with the signature of its format
+fn is_svg(bytes: &[u8]) -> bool {
+    let text = String::from_utf8_lossy(&bytes[..bytes.len().min(512)]);
+    let text = text.trim_start_matches('\u{feff}').trim_start();
+    text.starts_with('<') && text.contains("<svg")
+}
+
+/// Load an image of a document unless it's already loaded or loading
+pub fn load_image(document: &NodeRef, url: &Url) {
+    let key = url.raw().to_string();
+    if document.borrow().as_document().image_state(&key).is_some() {
+        return;
+    }
+    log::info!("Loading image from: {}", key);
+    document
+        .borrow_mut()
+        .as_document_mut()
+        .set_image_state(&key, ImageState::Loading);
+
+    let success_doc = document.clone();
+    let error_doc = document.clone();
+    let success_key = key.clone();
+    let request = LoadRequest::new(url.clone())
+        .with_destination(Destination::Image)
+        .on_success(Box::new(move |bytes| {
+            let state = match Image::decode(&bytes) {
+                Ok(image) => ImageState::Loaded(Rc::new(image)),
+                Err(e) => {
+                    log::info!("Unable to decode image: {} ({})", e, success_key);
+                    ImageState::Failed
+                }
+            };
+            success_doc
+                .borrow_mut()
+                .as_document_mut()
+                .set_image_state(&success_key, state);
+        }))
+        .on_error(Box::new(move |e| {
+            log::info!("Unable to load image: {} ({})", e, key);
+            error_doc
+                .borrow_mut()
+                .as_document_mut()
+                .set_image_state(&key, ImageState::Failed);
+        }));
+
+    let loader = document
+        .borrow()
+        .as_document()
+        .loader()
+        .expect("Document loader is not set");
+    loader.borrow_mut().load(request);
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decode_svg_only() {
+        let image =
+            Image::decode(b"<?xml version=\"1.0\"?>\n<svg width=\"4\" height=\"2\"/>").unwrap();
+        assert_eq!(image.aspect_ratio(), Some(2.));
+        assert_eq!(image.rasterize(4, 2).len(), 4 * 2 * 4);
+
+        let png = b"\x89PNG\r\n\x1a\n";
+        assert_eq!(
+            Image::decode(png).err(),
+            Some("Unsupported image format".to_string())
+        );
+    }
+}
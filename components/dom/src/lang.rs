@@ -0,0 +1,102 @@
+/// The language of the content of the nodes, set by the `lang` attribute
+/// of an element & inherited by its descendants.
+/// https://html.spec.whatwg.org/multipage/dom.html#the-lang-and-xml:lang-attributes
+use super::dom_ref::NodeRef;
+
+/// The language of a node, from the `lang` attribute of its closest
+/// inclusive ancestor with one. An empty `lang` means the language is
+/// unknown, like a document without any `lang`.
+pub fn language(node: &NodeRef) -> Option<String> {
+    let mut current = Some(node.clone());
+    while let Some(node) = current {
+        if let Some(element) = node.borrow().as_element_opt() {
+            if let Some(lang) = element.get_attribute("lang") {
+                let lang = lang.trim();
+                return if lang.is_empty() {
+                    None
+                } else {
+                    Some(lang.to_string())
+                };
+            }
+        }
+        current = node.borrow().parent();
+    }
+    None
+}
+
+/// Whether a language tag matches a language range with the extended
+/// filtering of BCP 47: the subtags of the range are found in the tag in
+/// order, its first subtag being the first of the tag or `*`.
+/// https://www.rfc-editor.org/rfc/rfc4647#section-3.3.2
+pub fn matches_language_range(tag: &str, range: &str) -> bool {
+    let tag = tag.to_ascii_lowercase();
+    let range = range.to_ascii_lowercase();
+    let mut tag_subtags = tag.split('-');
+    let mut range_subtags = range.split('-');
+
+    match (range_subtags.next(), tag_subtags.next()) {
+        (Some(""), _) | (_, Some("")) => return false,
+        (Some("*"), Some(_)) => {}
+        (Some(range), Some(tag)) if range == tag => {}
+        _ => return false,
+    }
+
+    for range_subtag in range_subtags {
+        if range_subtag == "*" {
+            continue;
+        }
+        loop {
+            match tag_subtags.next() {
+                Some(tag_subtag) if tag_subtag == range_subtag => break,
+                // a singleton starts the extensions the range can't skip
+                Some(tag_subtag) if tag_subtag.len() == 1 => return false,
+                Some(_) => continue,
+                None => return false,
+            }
+        }
+    }
+    true
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::element_factory::create_element;
+    use crate::node::{Node, NodeData};
+
+    fn element(document: &NodeRef, parent: &NodeRef, tag_name: &str) -> NodeRef {
+        let node = create_element(document.clone().downgrade(), tag_name);
+        Node::append_child(parent.clone(), node.clone());
+        node
+    }
+
+    #[test]
+    fn inherit_language() {
+        let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let html = element(&document, &document, "html");
+        let p = element(&document, &html, "p");
+        let span = element(&document, &p, "span");
+        assert_eq!(language(&span), None);
+
+        Node::set_attribute(&html, "lang", "en-US");
+        assert_eq!(language(&span).as_deref(), Some("en-US"));
+        Node::set_attribute(&p, "lang", " fr ");
+        assert_eq!(language(&span).as_deref(), Some("fr"));
+        // the language is unknown again
+        Node::set_attribute(&span, "lang", "");
+        assert_eq!(language(&span), None);
+    }
+
+    #[test]
+    fn match_language_ranges() {
+        assert!(matches_language_range("en", "en"));
+        assert!(matches_language_range("en-US", "EN"));
+        assert!(!matches_language_range("english", "en"));
+        assert!(matches_language_range("de-Latn-CH", "de-CH"));
+        assert!(matches_language_range("de-CH", "*-CH"));
+        assert!(!matches_language_range("de-x-CH", "de-CH"));
+        assert!(!matches_language_range("de", "de-CH"));
+        assert!(!matches_language_range("de", ""));
+    }
+}
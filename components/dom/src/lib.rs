@@ -3,16 +3,28 @@ pub mod dom_token_list;
 pub mod elements;
 pub mod node_list;
 
+pub mod activation;
+pub mod canvas;
 pub mod character_data;
 pub mod comment;
 pub mod document;
+pub mod document_fragment;
 pub mod element;
+pub mod event;
+pub mod focus;
+pub mod forms;
+pub mod images;
+pub mod lang;
+pub mod mutation;
 pub mod node;
+pub mod reflection;
+pub mod testing;
 pub mod text;
+pub mod visited;
 
 pub mod conversion;
 
 pub mod document_loader;
 mod element_factory;
 
-pub use element_factory::create_element;
+pub use element_factory::{create_element, create_element_ns};
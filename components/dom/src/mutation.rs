@@ -0,0 +1,17 @@
+/// A DOM mutation that may change the computed styles of a node.
+/// Mutations are recorded on the mutated node and consumed on the next restyle.
+use atom::Atom;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum StyleMutation {
+    /// The id of the element changed
+    Id { old: Atom, new: Atom },
+    /// The class list of the element changed
+    Class { old: Vec<Atom>, new: Vec<Atom> },
+    /// Any other attribute of the element changed
+    Attribute(Atom),
+    /// Children were inserted into or removed from the node
+    ChildList,
+    /// The data of the text or comment node changed
+    CharacterData,
+}
@@ -1,11 +1,55 @@
+use super::character_data::{CharacterData, IndexSizeError};
 use super::comment::Comment;
 use super::document::Document;
+use super::document_fragment::DocumentFragment;
 use super::dom_ref::{NodeRef, WeakNodeRef};
-use super::element::Element;
+use super::dom_token_list::DOMTokenList;
+use super::element::{Element, Namespace};
 use super::elements::ElementData;
+use super::mutation::StyleMutation;
 use super::node_list::NodeList;
 use super::text::Text;
+use atom::Atom;
 use enum_dispatch::enum_dispatch;
+use io::position::{SourcePosition, SourceSpan};
+use std::cell::Cell;
+
+/// The elements that can host a shadow root, with the custom elements
+/// https://dom.spec.whatwg.org/#valid-shadow-host-name
+const VALID_SHADOW_HOSTS: [&str; 18] = [
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "div",
+    "footer",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "main",
+    "nav",
+    "p",
+    "section",
+    "span",
+];
+
+thread_local! {
+    /// The nodes alive on this thread. The nodes are reference counted &
+    /// never sent to another thread, so every node is counted on the thread
+    /// that created it.
+    static LIVE_NODES: Cell<usize> = Cell::new(0);
+}
+
+/// The number of nodes alive on this thread, inside a document or not.
+/// More nodes alive than the nodes of the documents means some nodes
+/// are kept alive, e.g. by a reference cycle.
+pub fn live_node_count() -> usize {
+    LIVE_NODES.with(|count| count.get())
+}
 
 pub struct Node {
     parent_node: Option<WeakNodeRef>,
@@ -15,6 +59,9 @@ pub struct Node {
     prev_sibling: Option<WeakNodeRef>,
     owner_document: Option<WeakNodeRef>,
     data: Option<NodeData>,
+    source_span: Option<SourceSpan>,
+    style_mutations: Vec<StyleMutation>,
+    dirty_descendants: bool,
 }
 
 #[enum_dispatch(NodeHooks)]
@@ -22,6 +69,7 @@ pub enum NodeData {
     Element(Element),
     Text(Text),
     Document(Document),
+    DocumentFragment(DocumentFragment),
     Comment(Comment),
 }
 
@@ -37,6 +85,12 @@ impl core::fmt::Debug for Node {
     }
 }
 
+impl Drop for Node {
+    fn drop(&mut self) {
+        LIVE_NODES.with(|count| count.set(count.get() - 1));
+    }
+}
+
 impl NodeData {
     pub fn handle_on_inserted(&mut self, document: NodeRef) {
         self.on_inserted(document);
@@ -49,6 +103,7 @@ impl core::fmt::Debug for NodeData {
             NodeData::Text(text) => write!(f, "Text({:?})", text.get_data()),
             NodeData::Comment(comment) => write!(f, "Comment({:?})", comment.get_data()),
             NodeData::Document(_) => write!(f, "Document"),
+            NodeData::DocumentFragment(_) => write!(f, "DocumentFragment"),
             NodeData::Element(element) => write!(f, "Element({:?})", element.tag_name()),
         }
     }
@@ -62,6 +117,7 @@ impl Node {
     }
 
     pub fn empty() -> Self {
+        LIVE_NODES.with(|count| count.set(count.get() + 1));
         Self {
             parent_node: None,
             first_child: None,
@@ -70,6 +126,258 @@ impl Node {
             prev_sibling: None,
             owner_document: None,
             data: None,
+            source_span: None,
+            style_mutations: Vec::new(),
+            dirty_descendants: false,
+        }
+    }
+
+    /// Where the node was defined in the source, if it was created by the parser
+    pub fn source_span(&self) -> Option<SourceSpan> {
+        self.source_span
+    }
+
+    pub fn set_source_span(&mut self, span: SourceSpan) {
+        self.source_span = Some(span);
+    }
+
+    /// Extend the source span of the node to `end`. For example, when
+    /// more characters are appended to a text node while parsing.
+    pub fn extend_source_span(&mut self, end: SourcePosition) {
+        if let Some(span) = &mut self.source_span {
+            span.end = end;
+        }
+    }
+
+    /// Mutations of this node since it was last styled
+    pub fn style_mutations(&self) -> &[StyleMutation] {
+        &self.style_mutations
+    }
+
+    pub fn take_style_mutations(&mut self) -> Vec<StyleMutation> {
+        std::mem::take(&mut self.style_mutations)
+    }
+
+    /// Whether a descendant of this node has been mutated since it was last styled
+    pub fn has_dirty_descendants(&self) -> bool {
+        self.dirty_descendants
+    }
+
+    pub fn clear_dirty_descendants(&mut self) {
+        self.dirty_descendants = false;
+    }
+
+    /// Record a mutation that may change the styles of a node and flag
+    /// its ancestors so the next restyle can find it
+    pub fn record_style_mutation(node_ref: &NodeRef, mutation: StyleMutation) {
+        {
+            let mut node = node_ref.borrow_mut();
+            if node.style_mutations.contains(&mutation) {
+                return;
+            }
+            node.style_mutations.push(mutation);
+        }
+
+        let mut parent = node_ref.borrow().parent_or_shadow_host();
+        while let Some(node) = parent {
+            let mut node_mut = node.borrow_mut();
+            if node_mut.dirty_descendants {
+                break;
+            }
+            node_mut.dirty_descendants = true;
+            parent = node_mut.parent_or_shadow_host();
+        }
+    }
+
+    /// Attach a shadow root to an element, which is rendered in place of
+    /// its children. Returns `None` if the element can't host a shadow
+    /// root or already has one.
+    /// https://dom.spec.whatwg.org/#dom-element-attachshadow
+    pub fn attach_shadow(host: &NodeRef) -> Option<NodeRef> {
+        let (shadow_root, document) = {
+            let mut node = host.borrow_mut();
+            let document = node.owner_document();
+            let element = node.as_element_mut_opt()?;
+            let is_valid_host = element.namespace() == Namespace::Html
+                && (VALID_SHADOW_HOSTS.contains(&element.tag_name().as_ref())
+                    || element.tag_name().contains('-'));
+            if !is_valid_host || element.shadow_root().is_some() {
+                return None;
+            }
+            let shadow_root = NodeRef::new(Node::new(NodeData::DocumentFragment(
+                DocumentFragment::new_shadow_root(host.clone().downgrade()),
+            )));
+            element.set_shadow_root(shadow_root.clone());
+            (shadow_root, document)
+        };
+        if let Some(document) = document {
+            shadow_root
+                .borrow_mut()
+                .set_document(document.clone().downgrade());
+            if let Some(document) = document.borrow_mut().as_document_mut_opt() {
+                document.add_shadow_root(&shadow_root);
+            }
+        }
+        // the host renders the shadow tree instead of its children
+        Node::record_style_mutation(host, StyleMutation::ChildList);
+        Some(shadow_root)
+    }
+
+    /// The host of a shadow root
+    pub fn shadow_host(&self) -> Option<NodeRef> {
+        self.as_document_fragment_opt()?.host()
+    }
+
+    /// The parent of a node, or the host of a shadow root
+    pub fn parent_or_shadow_host(&self) -> Option<NodeRef> {
+        self.parent().or_else(|| self.shadow_host())
+    }
+
+    /// Set an attribute of an element node and record the style mutation
+    pub fn set_attribute(node_ref: &NodeRef, name: &str, value: &str) {
+        let mutation = {
+            let mut node = node_ref.borrow_mut();
+            let element = match node.as_element_mut_opt() {
+                Some(element) => element,
+                None => return,
+            };
+            let name = element.attribute_name(name);
+            let mutation = match name.as_ref() {
+                "id" => StyleMutation::Id {
+                    old: element.id().clone(),
+                    new: Atom::from(value),
+                },
+                "class" => StyleMutation::Class {
+                    old: element.class_list().iter().cloned().collect(),
+                    new: DOMTokenList::from(value).iter().cloned().collect(),
+                },
+                _ => StyleMutation::Attribute(name.clone()),
+            };
+            element.set_attribute(&name, value);
+            mutation
+        };
+        Node::record_style_mutation(node_ref, mutation);
+    }
+
+    /// Remove an attribute of an element node and record the style mutation
+    pub fn remove_attribute(node_ref: &NodeRef, name: &str) {
+        let mutation = {
+            let mut node = node_ref.borrow_mut();
+            let element = match node.as_element_mut_opt() {
+                Some(element) => element,
+                None => return,
+            };
+            let name = element.attribute_name(name);
+            if !element.has_attribute(&name) {
+                return;
+            }
+            let mutation = match name.as_ref() {
+                "id" => StyleMutation::Id {
+                    old: element.id().clone(),
+                    new: Atom::default(),
+                },
+                "class" => StyleMutation::Class {
+                    old: element.class_list().iter().cloned().collect(),
+                    new: Vec::new(),
+                },
+                _ => StyleMutation::Attribute(name.clone()),
+            };
+            element.remove_attribute(&name);
+            mutation
+        };
+        Node::record_style_mutation(node_ref, mutation);
+    }
+
+    /// Update the class list of an element node, e.g. with `toggle()`, &
+    /// record the style mutation when the classes changed
+    pub fn update_class_list<R, F>(node_ref: &NodeRef, update: F) -> Option<R>
+    where
+        F: FnOnce(&mut DOMTokenList) -> R,
+    {
+        let (result, old, new) = {
+            let mut node = node_ref.borrow_mut();
+            let element = node.as_element_mut_opt()?;
+            let old = element.class_list().iter().cloned().collect::<Vec<_>>();
+            let result = element.update_class_list(update);
+            let new = element.class_list().iter().cloned().collect::<Vec<_>>();
+            (result, old, new)
+        };
+        if old != new {
+            Node::record_style_mutation(node_ref, StyleMutation::Class { old, new });
+        }
+        Some(result)
+    }
+
+    /// Update the data of a text or comment node, e.g. with
+    /// `replace_data()`, & record the style mutation when the data changed
+    pub fn update_character_data<R, F>(node_ref: &NodeRef, update: F) -> Option<R>
+    where
+        F: FnOnce(&mut CharacterData) -> R,
+    {
+        let (result, changed) = {
+            let mut node = node_ref.borrow_mut();
+            let data = node.character_data_mut()?;
+            let old = data.get_data();
+            let result = update(data);
+            (result, data.get_data() != old)
+        };
+        if changed {
+            Node::record_style_mutation(node_ref, StyleMutation::CharacterData);
+        }
+        Some(result)
+    }
+
+    /// Split a text node at an offset. The data after the offset moves to
+    /// a new text node, inserted after the node if it has a parent.
+    /// https://dom.spec.whatwg.org/#concept-text-split
+    pub fn split_text(node_ref: &NodeRef, offset: usize) -> Result<NodeRef, IndexSizeError> {
+        let new_data = node_ref
+            .borrow()
+            .as_text()
+            .character_data
+            .substring_data(offset, usize::MAX)?;
+        let new_node = NodeRef::new(Node::new(NodeData::Text(Text::new(new_data))));
+        if let Some(document) = node_ref.borrow().owner_document() {
+            new_node.borrow_mut().set_document(document.downgrade());
+        }
+
+        let (parent, next_sibling) = {
+            let node = node_ref.borrow();
+            (node.parent(), node.next_sibling())
+        };
+        if let Some(parent) = parent {
+            Node::insert_before(parent, new_node.clone(), next_sibling);
+        }
+        Node::update_character_data(node_ref, |data| data.delete_data(offset, usize::MAX));
+        Ok(new_node)
+    }
+
+    /// Remove the empty text nodes of a subtree & merge the adjacent ones
+    /// https://dom.spec.whatwg.org/#dom-node-normalize
+    pub fn normalize(node_ref: &NodeRef) {
+        let mut child = node_ref.borrow().first_child();
+        while let Some(node) = child {
+            let next = node.borrow().next_sibling();
+            if !node.is_text() {
+                Node::normalize(&node);
+                child = next;
+                continue;
+            }
+            if node.borrow().as_text().character_data.length() == 0 {
+                Node::detach(&node);
+                child = next;
+                continue;
+            }
+
+            let mut data = String::new();
+            let mut sibling = next;
+            while let Some(text) = sibling.clone().filter(NodeRef::is_text) {
+                data.push_str(&text.borrow().as_text().get_data());
+                sibling = text.borrow().next_sibling();
+                Node::detach(&text);
+            }
+            Node::update_character_data(&node, |character_data| character_data.append_data(&data));
+            child = sibling;
         }
     }
 
@@ -150,55 +458,58 @@ impl Node {
         result
     }
 
+    /// The first element with an id among the descendants of the node,
+    /// in tree order
+    /// https://dom.spec.whatwg.org/#dom-nonelementparentnode-getelementbyid
+    pub fn get_element_by_id(&self, id: &str) -> Option<NodeRef> {
+        for child in self.child_nodes() {
+            if let Some(element) = child.borrow().as_element_opt() {
+                if element.id().as_str() == id {
+                    return Some(child.clone());
+                }
+            }
+            if let Some(element) = child.borrow().get_element_by_id(id) {
+                return Some(element);
+            }
+        }
+        None
+    }
+
     /// Detach node from the parent
     pub fn detach(node_ref: &NodeRef) {
         let mut node = node_ref.borrow_mut();
 
-        let parent = node.parent();
+        if let Some(parent) = node.parent() {
+            let prev_sibling = node.prev_sibling();
+            let next_sibling = node.next_sibling();
 
-        if let Some(parent) = parent {
-            let mut parent_node = parent.borrow_mut();
             {
-                let first_child = parent_node.first_child().unwrap();
-                let last_child = parent_node.last_child().unwrap();
-
-                if *node_ref == first_child {
-                    parent_node.first_child = node.next_sibling();
-                } else if *node_ref == last_child {
+                let mut parent_node = parent.borrow_mut();
+                if parent_node.first_child().as_ref() == Some(node_ref) {
+                    parent_node.first_child = next_sibling.clone();
+                }
+                if parent_node.last_child().as_ref() == Some(node_ref) {
                     parent_node.last_child = node.prev_sibling.clone();
                 }
             }
 
-            let mut child = parent_node.first_child();
-
-            while let Some(c) = child {
-                if c == *node_ref {
-                    let inner = c.borrow_mut();
-                    let previous_sibling = inner.prev_sibling();
-                    let next_sibling = inner.next_sibling();
-
-                    if let Some(prev) = previous_sibling {
-                        if let Some(next) = next_sibling {
-                            prev.borrow_mut().next_sibling = Some(next.clone());
-                            next.borrow_mut().prev_sibling = Some(prev.downgrade());
-                        } else {
-                            prev.borrow_mut().next_sibling = None;
-                        }
-                    } else {
-                        if let Some(next) = next_sibling {
-                            next.borrow_mut().prev_sibling = None;
-                        }
-                    }
-                    break;
-                } else {
-                    child = c.borrow().next_sibling();
-                }
+            if let Some(prev) = prev_sibling {
+                prev.borrow_mut().next_sibling = next_sibling.clone();
+            }
+            if let Some(next) = next_sibling {
+                next.borrow_mut().prev_sibling = node.prev_sibling.clone();
             }
         }
 
+        let parent = node.parent();
         node.parent_node = None;
         node.prev_sibling = None;
         node.next_sibling = None;
+        drop(node);
+
+        if let Some(parent) = parent {
+            Node::record_style_mutation(&parent, StyleMutation::ChildList);
+        }
     }
 
     /// Transfer parent of nodes
@@ -211,6 +522,11 @@ impl Node {
         for child in new.child_nodes() {
             child.borrow_mut().parent_node = Some(new_parent.clone().downgrade());
         }
+        drop(old);
+        drop(new);
+
+        Node::record_style_mutation(&old_parent, StyleMutation::ChildList);
+        Node::record_style_mutation(&new_parent, StyleMutation::ChildList);
     }
 
     /// Append a child node to a parent node
@@ -241,10 +557,15 @@ impl Node {
         }
 
         parent_node.last_child = Some(child.clone().downgrade());
-        let document = child_node.owner_document().clone().unwrap();
-        if let Some(data) = &mut child_node.data {
-            data.handle_on_inserted(document);
+        if let Some(document) = child_node.owner_document() {
+            if let Some(data) = &mut child_node.data {
+                data.handle_on_inserted(document);
+            }
         }
+        drop(parent_node);
+        drop(child_node);
+
+        Node::record_style_mutation(&parent, StyleMutation::ChildList);
     }
 
     /// Insert a child node to a parent node before a reference child node
@@ -284,6 +605,11 @@ impl Node {
             // set inserted child to be new previous sibling of ref child
             ref_child_node.prev_sibling = Some(child.clone().downgrade());
             child_node.next_sibling = Some(ref_child.clone());
+            drop(ref_child_node);
+            drop(parent_node);
+            drop(child_node);
+
+            Node::record_style_mutation(&parent, StyleMutation::ChildList);
         } else {
             Node::append_child(parent, child);
         }
@@ -333,6 +659,13 @@ impl Node {
         }
     }
 
+    pub fn as_document_fragment_opt(&self) -> Option<&DocumentFragment> {
+        match &self.data {
+            Some(NodeData::DocumentFragment(fragment)) => Some(fragment),
+            _ => None,
+        }
+    }
+
     pub fn as_comment_opt(&self) -> Option<&Comment> {
         match &self.data {
             Some(NodeData::Comment(com)) => Some(com),
@@ -374,6 +707,15 @@ impl Node {
     pub fn as_text(&self) -> &Text {
         self.as_text_opt().expect("Node is not a Text")
     }
+
+    /// The data of a text or comment node
+    pub fn character_data_mut(&mut self) -> Option<&mut CharacterData> {
+        match &mut self.data {
+            Some(NodeData::Text(text)) => Some(&mut text.character_data),
+            Some(NodeData::Comment(comment)) => Some(&mut comment.character_data),
+            _ => None,
+        }
+    }
 }
 
 #[cfg(test)]
@@ -493,4 +835,152 @@ mod test {
         assert_eq!(new_parent.borrow().first_child(), Some(child.clone()));
         assert_eq!(child.borrow().parent(), Some(new_parent.clone()));
     }
+
+    #[test]
+    fn detach_last_child() {
+        let parent = NodeRef::new(Node::empty());
+        let child1 = NodeRef::new(Node::empty());
+        let child2 = NodeRef::new(Node::empty());
+
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+
+        parent.borrow_mut().set_document(doc.clone().downgrade());
+        child1.borrow_mut().set_document(doc.clone().downgrade());
+        child2.borrow_mut().set_document(doc.clone().downgrade());
+
+        Node::append_child(parent.clone(), child1.clone());
+        Node::append_child(parent.clone(), child2.clone());
+        Node::detach(&child2);
+
+        assert_eq!(parent.borrow().first_child(), Some(child1.clone()));
+        assert_eq!(parent.borrow().last_child(), Some(child1.clone()));
+        assert_eq!(child1.borrow().next_sibling(), None);
+        assert_eq!(child2.borrow().parent(), None);
+        assert_eq!(child2.borrow().prev_sibling(), None);
+    }
+
+    #[test]
+    fn count_live_nodes() {
+        let count = live_node_count();
+        let parent = NodeRef::new(Node::empty());
+        let child = NodeRef::new(Node::empty());
+        Node::append_child(parent.clone(), child.clone());
+        assert_eq!(live_node_count(), count + 2);
+
+        drop(child);
+        assert_eq!(live_node_count(), count + 2);
+        drop(parent);
+        assert_eq!(live_node_count(), count);
+    }
+
+    #[test]
+    fn case_insensitive_names() {
+        use crate::element::Namespace;
+
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let div = crate::create_element(doc.clone().downgrade(), "div");
+        Node::set_attribute(&div, "Data-Label", "a");
+        Node::set_attribute(&div, "CLASS", "note");
+        assert_eq!(
+            div.borrow_mut().take_style_mutations()[0],
+            StyleMutation::Attribute(Atom::from("data-label"))
+        );
+        {
+            let node = div.borrow();
+            let element = node.as_element();
+            assert!(element.has_tag_name("DIV"));
+            assert!(element.has_attribute("DATA-LABEL"));
+            assert_eq!(element.get_attribute("data-LABEL"), Some(&"a".to_string()));
+            assert!(element.class_list().contains_atom(&Atom::from("note")));
+        }
+
+        let svg = crate::create_element_ns(doc.downgrade(), "foreignObject", Namespace::Svg);
+        Node::set_attribute(&svg, "viewBox", "0 0 1 1");
+        let node = svg.borrow();
+        let element = node.as_element();
+        assert!(element.has_tag_name("foreignObject"));
+        assert!(!element.has_tag_name("foreignobject"));
+        assert!(element.has_attribute("viewBox"));
+        assert!(!element.has_attribute("viewbox"));
+    }
+
+    #[test]
+    fn update_class_list() {
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let div = crate::create_element(doc.downgrade(), "div");
+        assert_eq!(
+            Node::update_class_list(&div, |list| list.toggle(Atom::from("open"), None)),
+            Some(true)
+        );
+        assert_eq!(
+            div.borrow_mut().take_style_mutations(),
+            vec![StyleMutation::Class {
+                old: Vec::new(),
+                new: vec![Atom::from("open")]
+            }]
+        );
+
+        Node::update_class_list(&div, |list| list.add(vec![Atom::from("open")]));
+        assert!(div.borrow_mut().take_style_mutations().is_empty());
+
+        Node::update_class_list(&div, |list| {
+            list.replace(&Atom::from("open"), Atom::from("closed"))
+        });
+        assert_eq!(
+            div.borrow().as_element().get_attribute("class"),
+            Some(&"closed".to_string())
+        );
+        assert_eq!(div.borrow_mut().take_style_mutations().len(), 1);
+    }
+
+    #[test]
+    fn split_and_normalize_text() {
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let text = |data: &str| {
+            let text = NodeRef::new(Node::new(NodeData::Text(Text::new(data.to_string()))));
+            text.borrow_mut().set_document(doc.clone().downgrade());
+            text
+        };
+        let p = crate::create_element(doc.clone().downgrade(), "p");
+        let hello = text("Hello world");
+        Node::append_child(p.clone(), hello.clone());
+        Node::append_child(
+            p.clone(),
+            crate::create_element(doc.clone().downgrade(), "br"),
+        );
+        p.borrow_mut().take_style_mutations();
+
+        let world = Node::split_text(&hello, 5).unwrap();
+        assert_eq!(hello.borrow().as_text().get_data(), "Hello");
+        assert_eq!(world.borrow().as_text().get_data(), " world");
+        assert_eq!(hello.borrow().next_sibling(), Some(world.clone()));
+        assert_eq!(
+            hello.borrow_mut().take_style_mutations(),
+            vec![StyleMutation::CharacterData]
+        );
+        assert_eq!(Node::split_text(&hello, 6), Err(IndexSizeError));
+
+        Node::update_character_data(&world, |data| data.insert_data(6, "!"))
+            .unwrap()
+            .unwrap();
+        Node::insert_before(p.clone(), text(""), Some(hello.clone()));
+        Node::append_child(p.clone(), text("a"));
+        Node::append_child(p.clone(), text("b"));
+
+        Node::normalize(&p);
+        let children = p
+            .borrow()
+            .child_nodes()
+            .into_iter()
+            .map(|child| format!("{:?}", child.borrow()))
+            .collect::<Vec<String>>();
+        assert_eq!(
+            children,
+            vec![
+                "Node(Some(Text(\"Hello world!\")))",
+                "Node(Some(Element(\"br\")))",
+                "Node(Some(Text(\"ab\")))",
+            ]
+        );
+    }
 }
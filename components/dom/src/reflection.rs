@@ -0,0 +1,300 @@
+/// Typed getters & setters reflecting the content attributes of elements.
+/// https://html.spec.whatwg.org/multipage/common-dom-interfaces.html#reflecting-content-attributes-in-idl-attributes
+use super::dom_ref::NodeRef;
+use super::node::Node;
+use std::collections::BTreeMap;
+use url::Url;
+
+/// Access to the content attributes of an element. The setters of the
+/// node references record the style mutations like `Node::set_attribute`.
+pub trait Reflect {
+    fn reflected_attribute(&self, name: &str) -> Option<String>;
+
+    /// Set an attribute, removing it when the value is `None`
+    fn set_reflected_attribute(&self, name: &str, value: Option<&str>);
+
+    /// The value of a URL attribute resolved against the base URL of the
+    /// document. Invalid URLs reflect as `None`.
+    fn reflected_url(&self, name: &str) -> Option<Url>;
+
+    fn dataset(&self) -> BTreeMap<String, String>;
+
+    /// Set a `data-*` attribute by the name of its dataset property, e.g.
+    /// `fooBar` for `data-foo-bar`. Returns false when the name can't be
+    /// the name of a dataset property.
+    fn set_dataset_value(&self, name: &str, value: Option<&str>) -> bool {
+        match dataset_attribute_name(name) {
+            Some(attribute) => {
+                self.set_reflected_attribute(&attribute, value);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+impl Reflect for NodeRef {
+    fn reflected_attribute(&self, name: &str) -> Option<String> {
+        self.borrow()
+            .as_element_opt()
+            .and_then(|element| element.get_attribute(name).cloned())
+    }
+
+    fn set_reflected_attribute(&self, name: &str, value: Option<&str>) {
+        match value {
+            Some(value) => Node::set_attribute(self, name, value),
+            None => Node::remove_attribute(self, name),
+        }
+    }
+
+    fn reflected_url(&self, name: &str) -> Option<Url> {
+        let value = self.reflected_attribute(name)?;
+        match self.borrow().owner_document() {
+            Some(document) => document.borrow().as_document().parse_url(&value),
+            None => Url::parse(&value).ok(),
+        }
+    }
+
+    fn dataset(&self) -> BTreeMap<String, String> {
+        self.borrow()
+            .as_element_opt()
+            .map(|element| element.dataset())
+            .unwrap_or_default()
+    }
+}
+
+/// The name of the dataset property of a `data-*` attribute, without the
+/// prefix, e.g. `fooBar` for `foo-bar`
+pub fn dataset_property_name(name: &str) -> String {
+    let mut result = String::new();
+    let mut chars = name.chars().peekable();
+    while let Some(ch) = chars.next() {
+        match chars.peek() {
+            Some(next) if ch == '-' && next.is_ascii_lowercase() => {
+                result.push(next.to_ascii_uppercase());
+                chars.next();
+            }
+            _ => result.push(ch),
+        }
+    }
+    result
+}
+
+/// The name of the `data-*` attribute of a dataset property, e.g.
+/// `data-foo-bar` for `fooBar`
+/// https://html.spec.whatwg.org/multipage/dom.html#dom-domstringmap-setitem
+pub fn dataset_attribute_name(name: &str) -> Option<String> {
+    let mut chars = name.chars().peekable();
+    let mut result = String::from("data-");
+    while let Some(ch) = chars.next() {
+        if ch == '-' && chars.peek().map_or(false, |next| next.is_ascii_lowercase()) {
+            return None;
+        }
+        if ch.is_ascii_uppercase() {
+            result.push('-');
+        }
+        result.push(ch.to_ascii_lowercase());
+    }
+    Some(result)
+}
+
+/// Generate a trait of typed getters & setters for the attributes of an
+/// element interface, implemented for the node references, e.g.
+///
+/// ```ignore
+/// reflect_attributes! {
+///     pub trait HTMLAnchorElementAttributes {
+///         url href / set_href: "href",
+///         string target / set_target: "target",
+///     }
+/// }
+/// ```
+///
+/// The attributes are `string`, `bool` for the presence of an attribute &
+/// `url` for an attribute parsed as a URL.
+#[macro_export]
+macro_rules! reflect_attributes {
+    (
+        $(#[$meta:meta])*
+        pub trait $name:ident {
+            $($kind:ident $getter:ident / $setter:ident: $attr:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        pub trait $name: $crate::reflection::Reflect {
+            $($crate::reflect_attributes!(@accessors $kind $getter $setter $attr);)*
+        }
+
+        impl $name for $crate::dom_ref::NodeRef {}
+    };
+    (@accessors string $getter:ident $setter:ident $attr:literal) => {
+        fn $getter(&self) -> String {
+            self.reflected_attribute($attr).unwrap_or_default()
+        }
+
+        fn $setter(&self, value: &str) {
+            self.set_reflected_attribute($attr, Some(value));
+        }
+    };
+    (@accessors bool $getter:ident $setter:ident $attr:literal) => {
+        fn $getter(&self) -> bool {
+            self.reflected_attribute($attr).is_some()
+        }
+
+        fn $setter(&self, value: bool) {
+            self.set_reflected_attribute($attr, if value { Some("") } else { None });
+        }
+    };
+    (@accessors url $getter:ident $setter:ident $attr:literal) => {
+        fn $getter(&self) -> Option<url::Url> {
+            self.reflected_url($attr)
+        }
+
+        fn $setter(&self, value: &str) {
+            self.set_reflected_attribute($attr, Some(value));
+        }
+    };
+}
+
+reflect_attributes! {
+    /// The attributes of all the HTML elements
+    /// https://html.spec.whatwg.org/multipage/dom.html#htmlelement
+    pub trait HTMLElementAttributes {
+        string title / set_title: "title",
+        string lang / set_lang: "lang",
+        string dir / set_dir: "dir",
+        bool hidden / set_hidden: "hidden",
+    }
+}
+
+reflect_attributes! {
+    /// https://html.spec.whatwg.org/multipage/text-level-semantics.html#htmlanchorelement
+    pub trait HTMLAnchorElementAttributes {
+        url href / set_href: "href",
+        string target / set_target: "target",
+        string rel / set_rel: "rel",
+        string download / set_download: "download",
+    }
+}
+
+reflect_attributes! {
+    /// https://html.spec.whatwg.org/multipage/semantics.html#htmllinkelement
+    pub trait HTMLLinkElementAttributes {
+        url href / set_href: "href",
+        string rel / set_rel: "rel",
+        string media / set_media: "media",
+    }
+}
+
+reflect_attributes! {
+    /// https://html.spec.whatwg.org/multipage/embedded-content.html#htmlimageelement
+    pub trait HTMLImageElementAttributes {
+        url src / set_src: "src",
+        string alt / set_alt: "alt",
+    }
+}
+
+reflect_attributes! {
+    /// https://html.spec.whatwg.org/multipage/input.html#htmlinputelement
+    pub trait HTMLInputElementAttributes {
+        string default_value / set_default_value: "value",
+        bool default_checked / set_default_checked: "checked",
+        bool disabled / set_disabled: "disabled",
+        bool read_only / set_read_only: "readonly",
+        bool required / set_required: "required",
+        string placeholder / set_placeholder: "placeholder",
+    }
+}
+
+reflect_attributes! {
+    /// https://html.spec.whatwg.org/multipage/form-elements.html#htmlbuttonelement
+    pub trait HTMLButtonElementAttributes {
+        bool disabled / set_disabled: "disabled",
+        string name / set_name: "name",
+        string value / set_value: "value",
+    }
+}
+
+reflect_attributes! {
+    /// https://html.spec.whatwg.org/multipage/forms.html#htmlformelement
+    pub trait HTMLFormElementAttributes {
+        string name / set_name: "name",
+        string target / set_target: "target",
+        bool no_validate / set_no_validate: "novalidate",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::{
+        dataset_attribute_name, dataset_property_name, HTMLAnchorElementAttributes,
+        HTMLElementAttributes, HTMLInputElementAttributes, Reflect,
+    };
+    use crate::document::Document;
+    use crate::dom_ref::NodeRef;
+    use crate::mutation::StyleMutation;
+    use crate::node::{Node, NodeData};
+    use atom::Atom;
+
+    #[test]
+    fn reflect_typed_attributes() {
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let input = crate::create_element(doc.downgrade(), "input");
+
+        input.set_disabled(true);
+        assert!(input.disabled());
+        assert_eq!(input.reflected_attribute("disabled"), Some(String::new()));
+        input.set_disabled(false);
+        assert!(!input.disabled());
+        assert_eq!(
+            input.borrow_mut().take_style_mutations(),
+            vec![StyleMutation::Attribute(Atom::from("disabled"))]
+        );
+
+        input.set_title("Name");
+        assert_eq!(input.title(), "Name");
+        assert_eq!(input.placeholder(), "");
+    }
+
+    #[test]
+    fn reflect_urls() {
+        let mut document = Document::new();
+        document.set_url(url::Url::parse("http://moon.dev/docs/index.html").unwrap());
+        let doc = NodeRef::new(Node::new(NodeData::Document(document)));
+        let anchor = crate::create_element(doc.clone().downgrade(), "a");
+        Node::append_child(doc.clone(), anchor.clone());
+
+        anchor.set_href("../about.html#team");
+        assert_eq!(
+            anchor.href().map(|url| url.raw().to_string()),
+            Some("http://moon.dev/about.html#team".to_string())
+        );
+        assert_eq!(
+            anchor.reflected_attribute("href").unwrap(),
+            "../about.html#team"
+        );
+    }
+
+    #[test]
+    fn reflect_dataset() {
+        assert_eq!(dataset_property_name("foo-bar-1"), "fooBar-1");
+        assert_eq!(
+            dataset_attribute_name("fooBar"),
+            Some("data-foo-bar".to_string())
+        );
+        assert_eq!(dataset_attribute_name("foo-bar"), None);
+
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let div = crate::create_element(doc.downgrade(), "div");
+        Node::set_attribute(&div, "data-user-id", "7");
+        assert!(div.set_dataset_value("userName", Some("moon")));
+        assert!(!div.set_dataset_value("user-name", Some("moon")));
+
+        let dataset = div.dataset();
+        assert_eq!(dataset.get("userId"), Some(&"7".to_string()));
+        assert_eq!(dataset.get("userName"), Some(&"moon".to_string()));
+
+        div.set_dataset_value("userId", None);
+        assert_eq!(div.dataset().len(), 1);
+    }
+}
@@ -0,0 +1,404 @@
+/// Snapshots of DOM trees for tests, so a test can compare a whole tree
+/// with the tree it expects. The snapshots are in the format of the
+/// html5lib tree construction tests: a line per node starting with `| `,
+/// indented by two spaces per level, with the attributes of an element
+/// sorted & indented below it.
+/// https://github.com/html5lib/html5lib-tests/tree/master/tree-construction
+use super::dom_ref::NodeRef;
+use super::element::Namespace;
+use super::node::Node;
+use std::fmt;
+
+/// Write the snapshot of a node & its descendants. The snapshot of a
+/// document starts with its doctype & its children, the snapshot of a
+/// document fragment is the snapshot of its children.
+pub fn snapshot(node: &NodeRef) -> String {
+    let mut output = String::new();
+    let node = node.borrow();
+
+    match node.as_document_opt() {
+        Some(document) => {
+            if let Some(doctype) = document.doctype() {
+                if doctype.public_id().is_empty() && doctype.system_id().is_empty() {
+                    output.push_str(&format!("| <!DOCTYPE {}>\n", doctype.name()));
+                } else {
+                    output.push_str(&format!(
+                        "| <!DOCTYPE {} \"{}\" \"{}\">\n",
+                        doctype.name(),
+                        doctype.public_id(),
+                        doctype.system_id()
+                    ));
+                }
+            }
+            for child in node.child_nodes() {
+                write_node(&child.borrow(), 0, &mut output);
+            }
+        }
+        None if node.as_document_fragment_opt().is_some() => {
+            for child in node.child_nodes() {
+                write_node(&child.borrow(), 0, &mut output);
+            }
+        }
+        None => write_node(&node, 0, &mut output),
+    }
+    output
+}
+
+fn write_node(node: &Node, depth: usize, output: &mut String) {
+    let indent = format!("| {}", "  ".repeat(depth));
+
+    if let Some(element) = node.as_element_opt() {
+        let prefix = match element.namespace() {
+            Namespace::Html => "",
+            Namespace::Svg => "svg ",
+            Namespace::MathML => "math ",
+        };
+        output.push_str(&format!("{}<{}{}>\n", indent, prefix, element.tag_name()));
+
+        let mut attributes = element.attributes().iter().collect::<Vec<_>>();
+        attributes.sort();
+        for (name, value) in attributes {
+            output.push_str(&format!("{}  {}=\"{}\"\n", indent, name, value));
+        }
+    } else if let Some(text) = node.as_text_opt() {
+        output.push_str(&format!("{}\"{}\"\n", indent, text.get_data()));
+    } else if let Some(comment) = node.as_comment_opt() {
+        output.push_str(&format!("{}<!-- {} -->\n", indent, comment.get_data()));
+    }
+
+    for child in node.child_nodes() {
+        write_node(&child.borrow(), depth + 1, output);
+    }
+}
+
+/// A node of a snapshot, e.g. `<div>` with its attributes & children
+#[derive(Debug, Clone, PartialEq)]
+struct SnapshotNode {
+    label: String,
+    attributes: Vec<String>,
+    children: Vec<SnapshotNode>,
+}
+
+impl SnapshotNode {
+    fn is_element(&self) -> bool {
+        self.label.starts_with('<') && !self.label.starts_with("<!")
+    }
+}
+
+/// Read the nodes of a snapshot, with the depth of each node
+fn parse_snapshot(snapshot: &str) -> Vec<SnapshotNode> {
+    let mut lines: Vec<(usize, String)> = Vec::new();
+    for line in snapshot.lines() {
+        match line.strip_prefix("| ") {
+            Some(line) => {
+                let content = line.trim_start_matches(' ');
+                let depth = (line.len() - content.len()) / 2;
+                lines.push((depth, content.to_string()));
+            }
+            // the text of a node spanning multiple lines
+            None => match lines.last_mut() {
+                Some((_, content)) => {
+                    content.push('\n');
+                    content.push_str(line);
+                }
+                None if line.trim().is_empty() => {}
+                None => panic!("Invalid snapshot line: {:?}", line),
+            },
+        }
+    }
+
+    // the nodes of each depth being built, from the root
+    let mut stack: Vec<SnapshotNode> = Vec::new();
+    let mut roots = Vec::new();
+    let mut stack_depths: Vec<usize> = Vec::new();
+
+    for (depth, content) in lines {
+        let is_attribute = !content.starts_with('<') && !content.starts_with('"');
+        if is_attribute {
+            if let (Some(node), Some(node_depth)) = (stack.last_mut(), stack_depths.last()) {
+                if *node_depth + 1 == depth && node.is_element() {
+                    node.attributes.push(content);
+                    continue;
+                }
+            }
+        }
+
+        while stack_depths.last().map_or(false, |d| *d >= depth) {
+            pop_node(&mut stack, &mut stack_depths, &mut roots);
+        }
+        stack.push(SnapshotNode {
+            label: content,
+            attributes: Vec::new(),
+            children: Vec::new(),
+        });
+        stack_depths.push(depth);
+    }
+
+    while !stack.is_empty() {
+        pop_node(&mut stack, &mut stack_depths, &mut roots);
+    }
+    roots
+}
+
+fn pop_node(
+    stack: &mut Vec<SnapshotNode>,
+    stack_depths: &mut Vec<usize>,
+    roots: &mut Vec<SnapshotNode>,
+) {
+    let node = stack.pop().unwrap();
+    stack_depths.pop();
+    match stack.last_mut() {
+        Some(parent) => parent.children.push(node),
+        None => roots.push(node),
+    }
+}
+
+/// A difference between the expected tree & the actual tree
+#[derive(Debug, Clone, PartialEq)]
+pub enum Change {
+    /// A node of the actual tree that isn't expected
+    Added { path: String, node: String },
+    /// An expected node missing from the actual tree
+    Removed { path: String, node: String },
+    /// A node expected with other attributes, or in place of another node
+    Changed {
+        path: String,
+        expected: String,
+        actual: String,
+    },
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Change::Added { path, node } => write!(f, "+ {} in {}", node, path),
+            Change::Removed { path, node } => write!(f, "- {} in {}", node, path),
+            Change::Changed {
+                path,
+                expected,
+                actual,
+            } => write!(f, "~ {} became {} in {}", expected, actual, path),
+        }
+    }
+}
+
+/// The differences between two snapshots. The children of each node are
+/// matched by their longest common subsequence, so a node added in a
+/// list of siblings is reported once instead of changing every node
+/// after it.
+pub fn diff(expected: &str, actual: &str) -> Vec<Change> {
+    let mut changes = Vec::new();
+    diff_children(
+        &parse_snapshot(expected),
+        &parse_snapshot(actual),
+        "#document",
+        &mut changes,
+    );
+    changes
+}
+
+fn describe(node: &SnapshotNode) -> String {
+    if node.attributes.is_empty() {
+        return node.label.clone();
+    }
+    format!("{} [{}]", node.label, node.attributes.join(" "))
+}
+
+fn diff_children(
+    expected: &[SnapshotNode],
+    actual: &[SnapshotNode],
+    path: &str,
+    changes: &mut Vec<Change>,
+) {
+    let pairs = matching_pairs(expected, actual);
+    let (mut e, mut a) = (0, 0);
+
+    for (matched_e, matched_a) in pairs
+        .into_iter()
+        .chain(std::iter::once((expected.len(), actual.len())))
+    {
+        // the nodes between two matched nodes, changed in place when the
+        // two trees have nodes at the same position
+        while e < matched_e && a < matched_a {
+            diff_nodes(&expected[e], &actual[a], path, changes);
+            e += 1;
+            a += 1;
+        }
+        for node in &expected[e..matched_e] {
+            changes.push(Change::Removed {
+                path: path.to_string(),
+                node: describe(node),
+            });
+        }
+        for node in &actual[a..matched_a] {
+            changes.push(Change::Added {
+                path: path.to_string(),
+                node: describe(node),
+            });
+        }
+
+        if matched_e < expected.len() {
+            diff_nodes(&expected[matched_e], &actual[matched_a], path, changes);
+        }
+        e = matched_e + 1;
+        a = matched_a + 1;
+    }
+}
+
+fn diff_nodes(
+    expected: &SnapshotNode,
+    actual: &SnapshotNode,
+    path: &str,
+    changes: &mut Vec<Change>,
+) {
+    if expected.label != actual.label || expected.attributes != actual.attributes {
+        changes.push(Change::Changed {
+            path: path.to_string(),
+            expected: describe(expected),
+            actual: describe(actual),
+        });
+    }
+    if expected.label == actual.label {
+        let path = format!("{} > {}", path, actual.label);
+        diff_children(&expected.children, &actual.children, &path, changes);
+    }
+}
+
+/// The indices of the nodes with the same label in both lists, in order
+fn matching_pairs(expected: &[SnapshotNode], actual: &[SnapshotNode]) -> Vec<(usize, usize)> {
+    let (n, m) = (expected.len(), actual.len());
+    // lengths[i][j] is the longest common subsequence of expected[i..] & actual[j..]
+    let mut lengths = vec![vec![0; m + 1]; n + 1];
+    for i in (0..n).rev() {
+        for j in (0..m).rev() {
+            lengths[i][j] = if expected[i].label == actual[j].label {
+                lengths[i + 1][j + 1] + 1
+            } else {
+                lengths[i + 1][j].max(lengths[i][j + 1])
+            };
+        }
+    }
+
+    let mut pairs = Vec::new();
+    let (mut i, mut j) = (0, 0);
+    while i < n && j < m {
+        if expected[i].label == actual[j].label {
+            pairs.push((i, j));
+            i += 1;
+            j += 1;
+        } else if lengths[i + 1][j] >= lengths[i][j + 1] {
+            i += 1;
+        } else {
+            j += 1;
+        }
+    }
+    pairs
+}
+
+/// Assert a node has the tree of a snapshot, listing the differences
+/// between the trees otherwise
+pub fn assert_snapshot(node: &NodeRef, expected: &str) {
+    let actual = snapshot(node);
+    let changes = diff(expected, &actual);
+    if !changes.is_empty() {
+        let changes = changes
+            .iter()
+            .map(|change| change.to_string())
+            .collect::<Vec<_>>()
+            .join("\n");
+        panic!(
+            "The tree doesn't match the snapshot:\n{}\n\nActual tree:\n{}",
+            changes, actual
+        );
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::comment::Comment;
+    use crate::document::Document;
+    use crate::node::NodeData;
+    use crate::text::Text;
+
+    #[test]
+    fn snapshot_trees() {
+        let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let div = crate::create_element(document.clone().downgrade(), "div");
+        div.borrow_mut()
+            .as_element_mut()
+            .set_attribute("title", "main");
+        div.borrow_mut()
+            .as_element_mut()
+            .set_attribute("lang", "a b");
+        let text = NodeRef::new(Node::new(NodeData::Text(Text::new(
+            "hi\nthere".to_string(),
+        ))));
+        let comment = NodeRef::new(Node::new(NodeData::Comment(Comment::new("c".to_string()))));
+        Node::append_child(document.clone(), div.clone());
+        Node::append_child(div.clone(), text);
+        Node::append_child(document.clone(), comment);
+
+        let expected = "\
+| <div>
+|   lang=\"a b\"
+|   title=\"main\"
+|   \"hi
+there\"
+| <!-- c -->
+";
+        assert_eq!(snapshot(&document), expected);
+        assert_eq!(snapshot(&div).lines().next(), Some("| <div>"));
+        assert_snapshot(&document, expected);
+    }
+
+    #[test]
+    fn diff_snapshots() {
+        let expected = "\
+| <html>
+|   <head>
+|   <body>
+|     <p>
+|       id=\"a\"
+|     <div>
+|     \"text\"
+";
+        let actual = "\
+| <html>
+|   <head>
+|   <body>
+|     <p>
+|       id=\"b\"
+|     <span>
+|     <div>
+|       <i>
+";
+        assert_eq!(diff(expected, expected), []);
+        assert_eq!(
+            diff(expected, actual),
+            [
+                Change::Changed {
+                    path: "#document > <html> > <body>".to_string(),
+                    expected: "<p> [id=\"a\"]".to_string(),
+                    actual: "<p> [id=\"b\"]".to_string(),
+                },
+                Change::Added {
+                    path: "#document > <html> > <body>".to_string(),
+                    node: "<span>".to_string(),
+                },
+                Change::Added {
+                    path: "#document > <html> > <body> > <div>".to_string(),
+                    node: "<i>".to_string(),
+                },
+                Change::Removed {
+                    path: "#document > <html> > <body>".to_string(),
+                    node: "\"text\"".to_string(),
+                },
+            ]
+        );
+        assert_eq!(
+            diff(expected, actual)[1].to_string(),
+            "+ <span> in #document > <html> > <body>"
+        );
+    }
+}
@@ -0,0 +1,100 @@
+/// The URLs visited by the user, matching the links to them with
+/// `:visited`. To keep it simple, the store only knows the URLs visited
+/// during the session, without their fragment, & it's only read by the
+/// selector matching.
+/// https://html.spec.whatwg.org/multipage/semantics-other.html#selector-visited
+use super::activation::is_link;
+use super::dom_ref::NodeRef;
+use super::elements::ElementData;
+use std::cell::RefCell;
+use std::collections::HashSet;
+use std::rc::Rc;
+use url::Url;
+
+pub type VisitedLinksRef = Rc<RefCell<VisitedLinks>>;
+
+#[derive(Debug, Default)]
+pub struct VisitedLinks {
+    urls: HashSet<String>,
+}
+
+impl VisitedLinks {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn add(&mut self, url: &Url) {
+        self.urls.insert(url.without_fragment().to_string());
+    }
+
+    pub fn contains(&self, url: &Url) -> bool {
+        self.urls.contains(url.without_fragment())
+    }
+}
+
+/// Whether a node is a link to a visited URL, its `href` resolved against
+/// the base URL of its document
+pub fn is_visited_link(node: &NodeRef) -> bool {
+    let href = match node.borrow().as_element_opt().map(|element| element.data()) {
+        Some(ElementData::Anchor(anchor)) => match anchor.href() {
+            Some(href) => href.to_string(),
+            None => return false,
+        },
+        _ => return false,
+    };
+    let document = match node.borrow().owner_document() {
+        Some(document) => document,
+        None => return false,
+    };
+    let document = document.borrow();
+    let document = document.as_document();
+    match document.parse_url(&href) {
+        Some(url) => document.is_visited(&url),
+        None => false,
+    }
+}
+
+/// Whether a node is a link to a URL that isn't visited
+pub fn is_unvisited_link(node: &NodeRef) -> bool {
+    is_link(node) && !is_visited_link(node)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::document::Document;
+    use crate::element_factory::create_element;
+    use crate::node::{Node, NodeData};
+
+    fn anchor(document: &NodeRef) -> NodeRef {
+        let node = create_element(document.clone().downgrade(), "a");
+        Node::append_child(document.clone(), node.clone());
+        node
+    }
+
+    #[test]
+    fn match_visited_links() {
+        let doc = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+        let visited = anchor(&doc);
+        let unvisited = anchor(&doc);
+        let anchor = anchor(&doc);
+        Node::set_attribute(&visited, "href", "https://example.com/moon#top");
+        Node::set_attribute(&unvisited, "href", "https://example.com/sun");
+
+        let links = Rc::new(RefCell::new(VisitedLinks::new()));
+        links
+            .borrow_mut()
+            .add(&Url::parse("https://example.com/moon").unwrap());
+        doc.borrow_mut()
+            .as_document_mut()
+            .set_visited_links(links.clone());
+
+        assert!(is_visited_link(&visited));
+        assert!(!is_unvisited_link(&visited));
+        assert!(!is_visited_link(&unvisited));
+        assert!(is_unvisited_link(&unvisited));
+        // an anchor without a href isn't a link
+        assert!(!is_visited_link(&anchor));
+        assert!(!is_unvisited_link(&anchor));
+    }
+}
@@ -0,0 +1,44 @@
+struct VertexOutput {
+  [[location(0)]] uv: vec2<f32>;
+  [[builtin(position)]] position: vec4<f32>;
+};
+
+[[block]]
+struct Uniforms {
+  transform: mat4x4<f32>;
+  screen_size: vec2<f32>;
+};
+
+[[group(0), binding(0)]]
+var uniforms: Uniforms;
+
+[[group(1), binding(0)]]
+var image_texture: texture_2d<f32>;
+
+[[group(1), binding(1)]]
+var image_sampler: sampler;
+
+[[stage(vertex)]]
+fn vs_main(
+  [[location(0)]] corner: vec2<f32>,
+  [[location(1)]] rect: vec4<f32>,
+  [[location(2)]] uv_rect: vec4<f32>,
+) -> VertexOutput {
+  // scale the unit quad to the instance rect & its region of the texture
+  let local = vec4<f32>(rect.x + corner.x * rect.z, rect.y + corner.y * rect.w, 0.0, 1.0);
+  let position = (uniforms.transform * local).xy;
+
+  // map position to NDC
+  let x = position.x / uniforms.screen_size.x * 2.0 - 1.0;
+  let y = 1.0 - position.y / uniforms.screen_size.y * 2.0;
+
+  var out: VertexOutput;
+  out.uv = vec2<f32>(uv_rect.x + corner.x * uv_rect.z, uv_rect.y + corner.y * uv_rect.w);
+  out.position = vec4<f32>(x, y, 0.0, 1.0);
+  return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+  return textureSample(image_texture, image_sampler, in.uv);
+}
@@ -0,0 +1,38 @@
+struct VertexOutput {
+  [[location(0)]] color: vec4<f32>;
+  [[builtin(position)]] position: vec4<f32>;
+};
+
+[[block]]
+struct Uniforms {
+  transform: mat4x4<f32>;
+  screen_size: vec2<f32>;
+};
+
+[[group(0), binding(0)]]
+var uniforms: Uniforms;
+
+[[stage(vertex)]]
+fn vs_main(
+  [[location(0)]] corner: vec2<f32>,
+  [[location(1)]] rect: vec4<f32>,
+  [[location(2)]] color: vec4<f32>,
+) -> VertexOutput {
+  // scale the unit quad to the instance rect
+  let local = vec4<f32>(rect.x + corner.x * rect.z, rect.y + corner.y * rect.w, 0.0, 1.0);
+  let position = (uniforms.transform * local).xy;
+
+  // map position to NDC
+  let x = position.x / uniforms.screen_size.x * 2.0 - 1.0;
+  let y = 1.0 - position.y / uniforms.screen_size.y * 2.0;
+
+  var out: VertexOutput;
+  out.color = color;
+  out.position = vec4<f32>(x, y, 0.0, 1.0);
+  return out;
+}
+
+[[stage(fragment)]]
+fn fs_main(in: VertexOutput) -> [[location(0)]] vec4<f32> {
+  return in.color;
+}
@@ -5,6 +5,7 @@ struct VertexOutput {
 
 [[block]]
 struct Uniforms {
+  transform: mat4x4<f32>;
   screen_size: vec2<f32>;
 };
 
@@ -20,9 +21,11 @@ fn vs_main(
   [[location(0)]] position: vec2<f32>,
   [[location(1)]] color: vec4<f32>,
 ) -> VertexOutput {
+  let transformed = (uniforms.transform * vec4<f32>(position, 0.0, 1.0)).xy;
+
   // map position to NDC
-  let x = map(position.x, 0.0, uniforms.screen_size.x, -1.0, 1.0);
-  let y = map(position.y, 0.0, uniforms.screen_size.y, 1.0, -1.0);
+  let x = map(transformed.x, 0.0, uniforms.screen_size.x, -1.0, 1.0);
+  let y = map(transformed.y, 0.0, uniforms.screen_size.y, 1.0, -1.0);
 
   let full_position = vec4<f32>(x, y, 0.0, 1.0);
 
@@ -0,0 +1,40 @@
+use std::str::FromStr;
+
+/// Anti-aliasing method used to smooth the edges of painted shapes
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Antialiasing {
+    Off,
+    /// Multisample anti-aliasing with 4 samples per pixel
+    Msaa4,
+    /// Multisample anti-aliasing with 8 samples per pixel
+    Msaa8,
+}
+
+impl Antialiasing {
+    pub fn sample_count(&self) -> u32 {
+        match self {
+            Antialiasing::Off => 1,
+            Antialiasing::Msaa4 => 4,
+            Antialiasing::Msaa8 => 8,
+        }
+    }
+}
+
+impl Default for Antialiasing {
+    fn default() -> Self {
+        Antialiasing::Off
+    }
+}
+
+impl FromStr for Antialiasing {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "off" => Ok(Antialiasing::Off),
+            "msaa4" => Ok(Antialiasing::Msaa4),
+            "msaa8" => Ok(Antialiasing::Msaa8),
+            _ => Err(format!("Unknown anti-aliasing method: {}", value)),
+        }
+    }
+}
@@ -1,39 +1,170 @@
+use super::image;
+use super::painters::rect::DrawItem;
+use super::quad;
 use super::triangle;
+use super::uniforms::Constants;
 use lyon_tessellation::VertexBuffers;
+use painting::{Rect, Transform};
 
 pub struct Backend {
+    constants: Constants,
     triangle_pipeline: triangle::Pipeline,
+    quad_pipeline: quad::Pipeline,
+    image_pipeline: image::Pipeline,
 }
 
 pub struct DrawRequest<'a> {
     pub triangles: &'a [VertexBuffers<triangle::Vertex, triangle::Index>],
+    pub instances: &'a [quad::Instance],
+    pub image_instances: &'a [image::Instance],
+    /// The quads & meshes to draw, in paint order
+    pub items: &'a [DrawItem],
+    /// The transforms referenced by the draw items
+    pub transforms: &'a [Transform],
+    /// The clips referenced by the draw items, in frame coordinates
+    pub clips: &'a [Option<Rect>],
+    /// The region of the target to draw into as (x, y, width, height)
+    pub clip: Option<(u32, u32, u32, u32)>,
 }
 
 impl Backend {
-    pub fn new(device: &wgpu::Device, texture_format: wgpu::TextureFormat) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        texture_format: wgpu::TextureFormat,
+        sample_count: u32,
+    ) -> Self {
+        let constants = Constants::new(device);
         Self {
-            triangle_pipeline: triangle::Pipeline::new(device, texture_format),
+            triangle_pipeline: triangle::Pipeline::new(
+                device,
+                texture_format,
+                sample_count,
+                constants.layout(),
+            ),
+            quad_pipeline: quad::Pipeline::new(
+                device,
+                texture_format,
+                sample_count,
+                constants.layout(),
+            ),
+            image_pipeline: image::Pipeline::new(
+                device,
+                texture_format,
+                sample_count,
+                constants.layout(),
+            ),
+            constants,
         }
     }
 
+    /// The bytes of the buffers growing with the shapes drawn
+    pub fn buffer_size(&self) -> usize {
+        self.constants.buffer_size()
+            + self.triangle_pipeline.buffer_size()
+            + self.quad_pipeline.buffer_size()
+            + self.image_pipeline.buffer_size()
+    }
+
+    /// The pipeline drawing the images, which uploads them before they
+    /// are painted
+    pub fn image_pipeline(&mut self) -> &mut image::Pipeline {
+        &mut self.image_pipeline
+    }
+
     pub fn draw(
         &mut self,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
         staging_belt: &mut wgpu::util::StagingBelt,
         target: &wgpu::TextureView,
+        resolve_target: Option<&wgpu::TextureView>,
         size: (u32, u32),
         request: DrawRequest,
     ) {
-        if !request.triangles.is_empty() {
-            self.triangle_pipeline.draw(
-                device,
-                encoder,
-                staging_belt,
-                &request.triangles,
-                target,
-                size,
-            );
+        if request.items.is_empty() {
+            return;
         }
+
+        self.constants
+            .prepare(device, encoder, staging_belt, request.transforms, size);
+        self.triangle_pipeline
+            .prepare(device, encoder, staging_belt, request.triangles);
+        self.quad_pipeline
+            .prepare(device, encoder, staging_belt, request.instances);
+        self.image_pipeline
+            .prepare(device, encoder, staging_belt, request.image_instances);
+
+        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+            label: Some("moon::gfx render pass"),
+            color_attachments: &[wgpu::RenderPassColorAttachment {
+                view: target,
+                resolve_target,
+                ops: wgpu::Operations {
+                    load: wgpu::LoadOp::Load,
+                    store: true,
+                },
+            }],
+            depth_stencil_attachment: None,
+        });
+
+        let mut current_scissor = None;
+        for item in request.items {
+            let state = match item {
+                DrawItem::Quads(_, state) | DrawItem::Mesh(_, state) => *state,
+                DrawItem::Images(_, _, state) => *state,
+            };
+            let scissor = scissor_rect(request.clips[state].as_ref(), request.clip, size);
+            let (_, _, width, height) = scissor;
+            if width == 0 || height == 0 {
+                continue;
+            }
+            if current_scissor != Some(scissor) {
+                let (x, y, width, height) = scissor;
+                render_pass.set_scissor_rect(x, y, width, height);
+                current_scissor = Some(scissor);
+            }
+
+            match item {
+                DrawItem::Quads(range, transform) => {
+                    self.constants.bind(&mut render_pass, *transform);
+                    self.quad_pipeline.render(&mut render_pass, range.clone());
+                }
+                DrawItem::Mesh(index, transform) => {
+                    self.constants.bind(&mut render_pass, *transform);
+                    self.triangle_pipeline.render(&mut render_pass, *index);
+                }
+                DrawItem::Images(range, texture, transform) => {
+                    self.constants.bind(&mut render_pass, *transform);
+                    self.image_pipeline
+                        .render(&mut render_pass, *texture, range.clone());
+                }
+            }
+        }
+    }
+}
+
+/// The pixels of the target a draw item is drawn into as (x, y, width,
+/// height), inside its clip & the region of the request
+fn scissor_rect(
+    clip: Option<&Rect>,
+    region: Option<(u32, u32, u32, u32)>,
+    size: (u32, u32),
+) -> (u32, u32, u32, u32) {
+    let (mut left, mut top, mut right, mut bottom) = match region {
+        Some((x, y, width, height)) => (x, y, x + width, y + height),
+        None => (0, 0, size.0, size.1),
+    };
+    if let Some(clip) = clip {
+        let to_pixel = |value: f32, max: u32| (value.max(0.) as u32).min(max);
+        left = left.max(to_pixel(clip.x.floor(), size.0));
+        top = top.max(to_pixel(clip.y.floor(), size.1));
+        right = right.min(to_pixel((clip.x + clip.width).ceil(), size.0));
+        bottom = bottom.min(to_pixel((clip.y + clip.height).ceil(), size.1));
     }
+    (
+        left,
+        top,
+        right.saturating_sub(left),
+        bottom.saturating_sub(top),
+    )
 }
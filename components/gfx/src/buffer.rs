@@ -0,0 +1,55 @@
+/// A GPU buffer that can grow to hold more elements
+#[derive(Debug)]
+pub struct Buffer<T> {
+    label: &'static str,
+    pub raw: wgpu::Buffer,
+    size: usize,
+    usage: wgpu::BufferUsage,
+    _type: std::marker::PhantomData<T>,
+}
+
+impl<T> Buffer<T> {
+    pub fn new(
+        label: &'static str,
+        device: &wgpu::Device,
+        size: usize,
+        usage: wgpu::BufferUsage,
+    ) -> Self {
+        let raw = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some(label),
+            size: (std::mem::size_of::<T>() * size) as u64,
+            usage,
+            mapped_at_creation: false,
+        });
+
+        Buffer {
+            label,
+            raw,
+            size,
+            usage,
+            _type: std::marker::PhantomData,
+        }
+    }
+
+    /// The bytes allocated for the buffer on the GPU
+    pub fn byte_size(&self) -> usize {
+        std::mem::size_of::<T>() * self.size
+    }
+
+    pub fn expand(&mut self, device: &wgpu::Device, size: usize) -> bool {
+        let needs_resize = self.size < size;
+
+        if needs_resize {
+            self.raw = device.create_buffer(&wgpu::BufferDescriptor {
+                label: Some(self.label),
+                size: (std::mem::size_of::<T>() * size) as u64,
+                usage: self.usage,
+                mapped_at_creation: false,
+            });
+
+            self.size = size;
+        }
+
+        needs_resize
+    }
+}
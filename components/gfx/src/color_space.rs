@@ -0,0 +1,99 @@
+use super::Bitmap;
+use std::str::FromStr;
+use ultraviolet as uv;
+
+/// The encoding of the pixels of the output bitmaps. The GPU stores the
+/// colors of the shapes & images in linear space and blends them there,
+/// the frame encodes the blended colors into the color space.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorSpace {
+    /// The gamma encoded colors of the CSS colors & the images
+    Srgb,
+    /// The colors proportional to the light, e.g. for compositing the
+    /// bitmaps in another linear pipeline
+    LinearSrgb,
+}
+
+impl ColorSpace {
+    /// The format of the frame texture writing the colors in the color space
+    pub fn texture_format(&self) -> wgpu::TextureFormat {
+        match self {
+            ColorSpace::Srgb => wgpu::TextureFormat::Rgba8UnormSrgb,
+            ColorSpace::LinearSrgb => wgpu::TextureFormat::Rgba8Unorm,
+        }
+    }
+}
+
+impl Default for ColorSpace {
+    fn default() -> Self {
+        ColorSpace::Srgb
+    }
+}
+
+impl FromStr for ColorSpace {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "srgb" => Ok(ColorSpace::Srgb),
+            "linear" => Ok(ColorSpace::LinearSrgb),
+            _ => Err(format!("Unknown color space: {}", value)),
+        }
+    }
+}
+
+/// Decode a gamma encoded sRGB channel between 0 & 1 into linear space
+pub fn srgb_to_linear(value: f32) -> f32 {
+    if value <= 0.04045 {
+        value / 12.92
+    } else {
+        ((value + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+/// A CSS color with channels between 0 & 255 in linear space, as given
+/// to the shaders. The alpha isn't gamma encoded & is kept as is.
+pub fn linear_color(r: f32, g: f32, b: f32, a: f32) -> uv::Vec4 {
+    uv::Vec4::new(
+        srgb_to_linear(r / 255.),
+        srgb_to_linear(g / 255.),
+        srgb_to_linear(b / 255.),
+        a / 255.,
+    )
+}
+
+/// Encode an sRGB bitmap into a color space
+pub fn encode(mut bitmap: Bitmap, color_space: ColorSpace) -> Bitmap {
+    if color_space == ColorSpace::LinearSrgb {
+        let table: Vec<u8> = (0..=255)
+            .map(|value| (srgb_to_linear(value as f32 / 255.) * 255.).round() as u8)
+            .collect();
+        for pixel in bitmap.chunks_exact_mut(4) {
+            for channel in &mut pixel[..3] {
+                *channel = table[*channel as usize];
+            }
+        }
+    }
+    bitmap
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn linearize_colors() {
+        let gray = linear_color(128., 0., 255., 128.);
+        assert!((gray.x - 0.2158).abs() < 0.001);
+        assert_eq!(gray.y, 0.);
+        assert_eq!(gray.z, 1.);
+        assert!((gray.w - 0.502).abs() < 0.001);
+
+        let bitmap = vec![128, 255, 0, 128];
+        assert_eq!(encode(bitmap.clone(), ColorSpace::Srgb), bitmap);
+        assert_eq!(
+            encode(bitmap, ColorSpace::LinearSrgb),
+            vec![55, 255, 0, 128]
+        );
+    }
+}
@@ -0,0 +1,321 @@
+use super::color_space::{self, ColorSpace};
+use super::Bitmap;
+use painting::{Color, RRect, Rect, Transform};
+use svg::{stroke_polygons, Canvas, FillRule, Path, Polyline};
+
+/// The distance a flattened corner may be off the curve, in pixels
+const TOLERANCE: f32 = 0.1;
+
+/// The largest width or height painted at once. The canvas keeps 16 bytes
+/// per pixel, which bounds it to a gigabyte.
+const MAX_DIMENSION: u32 = 8192;
+
+/// A painter rasterizing the shapes on the CPU. The same shapes always
+/// give the same pixels, whatever the GPU & the drivers of the machine.
+pub struct CpuPainter {
+    size: (u32, u32),
+    /// The shapes painted since the last frame, in frame coordinates
+    shapes: Vec<(Vec<Polyline>, Color)>,
+    transform: Transform,
+    /// The rect of the frame the shapes are clipped to
+    clip: Option<Rect>,
+    /// The RGBA pixels of the last frame
+    frame: Bitmap,
+    color_space: ColorSpace,
+}
+
+impl CpuPainter {
+    pub fn new() -> Self {
+        Self {
+            size: (0, 0),
+            shapes: Vec::new(),
+            transform: Transform::identity(),
+            clip: None,
+            frame: Vec::new(),
+            color_space: ColorSpace::default(),
+        }
+    }
+
+    pub fn resize(&mut self, size: (u32, u32)) {
+        self.size = size;
+        self.frame = vec![255; (size.0 * size.1 * 4) as usize];
+    }
+
+    pub fn max_texture_dimension(&self) -> u32 {
+        MAX_DIMENSION
+    }
+
+    /// The bytes of the last frame, kept in memory to paint regions
+    pub fn memory_size(&self) -> usize {
+        self.frame.len()
+    }
+
+    /// Paint the whole frame
+    pub fn paint(&mut self) {
+        let (width, height) = self.size;
+        self.frame = self.rasterize(&Rect::new(0., 0., width as f32, height as f32));
+    }
+
+    /// Paint a region of the frame, keeping the rest of the previous frame
+    pub fn paint_region(&mut self, region: &Rect) {
+        let (width, height) = self.size;
+        let x = region.x.max(0.).floor() as u32;
+        let y = region.y.max(0.).floor() as u32;
+        let right = ((region.x + region.width).ceil().max(0.) as u32).min(width);
+        let bottom = ((region.y + region.height).ceil().max(0.) as u32).min(height);
+
+        if x >= right || y >= bottom {
+            self.shapes.clear();
+            return;
+        }
+
+        let region = Rect::new(x as f32, y as f32, (right - x) as f32, (bottom - y) as f32);
+        let pixels = self.rasterize(&region);
+        let row_size = (width * 4) as usize;
+        for row in y as usize..bottom as usize {
+            let range = row * row_size + (x * 4) as usize..row * row_size + (right * 4) as usize;
+            self.frame[range.clone()].copy_from_slice(&pixels[range]);
+        }
+    }
+
+    /// The shapes are blended in sRGB, the output is only encoded into
+    /// the color space
+    pub fn set_output_color_space(&mut self, color_space: ColorSpace) {
+        self.color_space = color_space;
+    }
+
+    pub async fn output(&mut self) -> Bitmap {
+        color_space::encode(self.frame.clone(), self.color_space)
+    }
+
+    /// Draw the shapes over a white region of an empty canvas
+    fn rasterize(&mut self, region: &Rect) -> Bitmap {
+        let (width, height) = self.size;
+        let mut canvas = Canvas::new(width, height);
+        let white = svg::Color::new(255, 255, 255, 255);
+        canvas.fill(&[rect_polyline(region)], FillRule::NonZero, white);
+        for (polygons, color) in self.shapes.drain(..) {
+            let color = svg::Color::new(color.r, color.g, color.b, color.a);
+            canvas.fill(&polygons, FillRule::NonZero, color);
+        }
+        canvas.to_rgba()
+    }
+
+    /// The polygons of a path in the current transform, inside the
+    /// current clip
+    fn transformed(&self, polygons: Vec<Polyline>) -> Vec<Polyline> {
+        let polygons = if self.transform.is_identity() {
+            polygons
+        } else {
+            polygons
+                .into_iter()
+                .map(|polygon| Polyline {
+                    points: polygon
+                        .points
+                        .into_iter()
+                        .map(|(x, y)| self.transform.map_point(x, y))
+                        .collect(),
+                    closed: polygon.closed,
+                })
+                .collect()
+        };
+        match &self.clip {
+            Some(clip) => clip_polygons(polygons, clip),
+            None => polygons,
+        }
+    }
+}
+
+/// Clip polygons to a rect, one edge of the rect after the other. The
+/// parts of a polygon outside of the rect become edges along the rect,
+/// which keep the area filled inside of it.
+/// https://en.wikipedia.org/wiki/Sutherland%E2%80%93Hodgman_algorithm
+fn clip_polygons(polygons: Vec<Polyline>, clip: &Rect) -> Vec<Polyline> {
+    let edges = [
+        (0, clip.x, true),
+        (0, clip.x + clip.width, false),
+        (1, clip.y, true),
+        (1, clip.y + clip.height, false),
+    ];
+    polygons
+        .into_iter()
+        .filter_map(|polygon| {
+            let mut points = polygon.points;
+            for (axis, value, keep_greater) in edges {
+                points = clip_to_edge(&points, axis, value, keep_greater);
+            }
+            if points.len() < 3 {
+                return None;
+            }
+            Some(Polyline {
+                points,
+                closed: true,
+            })
+        })
+        .collect()
+}
+
+/// Keep the part of a polygon on a side of a horizontal or vertical edge
+fn clip_to_edge(
+    points: &[(f32, f32)],
+    axis: usize,
+    value: f32,
+    keep_greater: bool,
+) -> Vec<(f32, f32)> {
+    let coordinate = |point: (f32, f32)| if axis == 0 { point.0 } else { point.1 };
+    let is_inside = |point: (f32, f32)| {
+        if keep_greater {
+            coordinate(point) >= value
+        } else {
+            coordinate(point) <= value
+        }
+    };
+    let intersection = |from: (f32, f32), to: (f32, f32)| {
+        let t = (value - coordinate(from)) / (coordinate(to) - coordinate(from));
+        (from.0 + t * (to.0 - from.0), from.1 + t * (to.1 - from.1))
+    };
+
+    let mut clipped = Vec::with_capacity(points.len() + 2);
+    for (index, point) in points.iter().enumerate() {
+        let previous = points[(index + points.len() - 1) % points.len()];
+        if is_inside(*point) {
+            if !is_inside(previous) {
+                clipped.push(intersection(previous, *point));
+            }
+            clipped.push(*point);
+        } else if is_inside(previous) {
+            clipped.push(intersection(previous, *point));
+        }
+    }
+    clipped
+}
+
+fn rect_polyline(rect: &Rect) -> Polyline {
+    Polyline {
+        points: vec![
+            (rect.x, rect.y),
+            (rect.x + rect.width, rect.y),
+            (rect.x + rect.width, rect.y + rect.height),
+            (rect.x, rect.y + rect.height),
+        ],
+        closed: true,
+    }
+}
+
+fn rrect_path(rect: &RRect) -> Path {
+    let corners = &rect.corners;
+    let (left, top) = (rect.x, rect.y);
+    let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+
+    let mut path = Path::new();
+    path.move_to(left + corners.top_left.horizontal_r(), top);
+    path.line_to(right - corners.top_right.horizontal_r(), top);
+    path.quad_to(right, top, right, top + corners.top_right.vertical_r());
+    path.line_to(right, bottom - corners.bottom_right.vertical_r());
+    path.quad_to(
+        right,
+        bottom,
+        right - corners.bottom_right.horizontal_r(),
+        bottom,
+    );
+    path.line_to(left + corners.bottom_left.horizontal_r(), bottom);
+    path.quad_to(
+        left,
+        bottom,
+        left,
+        bottom - corners.bottom_left.vertical_r(),
+    );
+    path.line_to(left, top + corners.top_left.vertical_r());
+    path.quad_to(left, top, left + corners.top_left.horizontal_r(), top);
+    path.close();
+    path
+}
+
+impl painting::Painter for CpuPainter {
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        let polygons = self.transformed(vec![rect_polyline(&rect)]);
+        self.shapes.push((polygons, color));
+    }
+
+    fn fill_rrect(&mut self, rect: RRect, color: Color) {
+        let polygons = self.transformed(rrect_path(&rect).flatten(TOLERANCE));
+        self.shapes.push((polygons, color));
+    }
+
+    fn stroke_rrect(&mut self, rect: RRect, color: Color, width: f32) {
+        let outline = stroke_polygons(&rrect_path(&rect).flatten(TOLERANCE), width);
+        let polygons = self.transformed(outline);
+        self.shapes.push((polygons, color));
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+    use painting::Painter;
+
+    const RED: Color = Color {
+        r: 255,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+
+    fn pixel(frame: &Bitmap, width: u32, x: u32, y: u32) -> &[u8] {
+        let index = ((y * width + x) * 4) as usize;
+        &frame[index..index + 4]
+    }
+
+    #[test]
+    fn paint_frames() {
+        let mut painter = CpuPainter::new();
+        painter.resize((4, 4));
+        painter.set_transform(Transform::translate(1., 1.));
+        painter.fill_rect(Rect::new(0., 0., 2., 2.), RED);
+        painter.paint();
+
+        let frame = block_on(painter.output());
+        assert_eq!(frame.len(), 4 * 4 * 4);
+        assert_eq!(pixel(&frame, 4, 0, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&frame, 4, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 4, 2, 2), [255, 0, 0, 255]);
+
+        // only the region is painted again
+        painter.set_transform(Transform::identity());
+        painter.fill_rect(Rect::new(0., 0., 4., 4.), RED);
+        painter.paint_region(&Rect::new(0., 0., 1., 4.));
+        let frame = block_on(painter.output());
+        assert_eq!(pixel(&frame, 4, 0, 3), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 4, 3, 3), [255, 255, 255, 255]);
+        assert_eq!(pixel(&frame, 4, 1, 1), [255, 0, 0, 255]);
+    }
+
+    #[test]
+    fn clip_shapes() {
+        let mut painter = CpuPainter::new();
+        painter.resize((4, 4));
+        painter.set_transform(Transform::translate(1., 0.));
+        painter.set_clip(Some(Rect::new(0., 0., 2., 2.)));
+        painter.fill_rect(Rect::new(0., 0., 4., 4.), RED);
+        painter.set_clip(None);
+        painter.fill_rect(Rect::new(0., 3., 1., 1.), RED);
+        painter.paint();
+
+        // the clip is in frame coordinates, whatever the transform
+        let frame = block_on(painter.output());
+        assert_eq!(pixel(&frame, 4, 0, 0), [255, 255, 255, 255]);
+        assert_eq!(pixel(&frame, 4, 1, 1), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frame, 4, 2, 1), [255, 255, 255, 255]);
+        assert_eq!(pixel(&frame, 4, 1, 2), [255, 255, 255, 255]);
+        assert_eq!(pixel(&frame, 4, 1, 3), [255, 0, 0, 255]);
+    }
+}
@@ -0,0 +1,94 @@
+use std::str::FromStr;
+
+/// The graphics API the GPU painter runs on
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum GpuBackend {
+    Vulkan,
+    Metal,
+    Dx12,
+    /// OpenGL, available on the machines without the primary APIs
+    Gl,
+}
+
+impl GpuBackend {
+    pub fn backend_bit(&self) -> wgpu::BackendBit {
+        match self {
+            GpuBackend::Vulkan => wgpu::BackendBit::VULKAN,
+            GpuBackend::Metal => wgpu::BackendBit::METAL,
+            GpuBackend::Dx12 => wgpu::BackendBit::DX12,
+            GpuBackend::Gl => wgpu::BackendBit::GL,
+        }
+    }
+}
+
+impl FromStr for GpuBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "vulkan" => Ok(GpuBackend::Vulkan),
+            "metal" => Ok(GpuBackend::Metal),
+            "dx12" => Ok(GpuBackend::Dx12),
+            "gl" => Ok(GpuBackend::Gl),
+            _ => Err(format!("Unknown GPU backend: {}", value)),
+        }
+    }
+}
+
+/// The most suitable adapter of a graphics API, or of the primary APIs
+/// with the GL fallback when none is given
+pub fn find_adapter(backend: Option<GpuBackend>) -> Result<wgpu::Adapter, String> {
+    let backends = match backend {
+        Some(backend) => backend.backend_bit(),
+        None => wgpu::BackendBit::PRIMARY | wgpu::BackendBit::GL,
+    };
+    wgpu::Instance::new(backends)
+        .enumerate_adapters(backends)
+        .max_by_key(|adapter| adapter_score(&adapter.get_info().device_type))
+        .ok_or_else(|| format!("No GPU adapter found for {:?}", backends))
+}
+
+/// The name of the adapter the GPU painter would paint on, or why the
+/// machine can't paint on the GPU
+pub fn gpu_adapter(backend: Option<GpuBackend>) -> Result<String, String> {
+    find_adapter(backend).map(|adapter| adapter.get_info().name)
+}
+
+/// How suitable an adapter is to paint, the highest score is picked.
+/// Software adapters are only used when there is no hardware one.
+pub fn adapter_score(device_type: &wgpu::DeviceType) -> u32 {
+    match device_type {
+        wgpu::DeviceType::DiscreteGpu => 4,
+        wgpu::DeviceType::IntegratedGpu => 3,
+        wgpu::DeviceType::VirtualGpu => 2,
+        wgpu::DeviceType::Other => 1,
+        wgpu::DeviceType::Cpu => 0,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prefer_hardware_adapters() {
+        let mut device_types = vec![
+            wgpu::DeviceType::Cpu,
+            wgpu::DeviceType::IntegratedGpu,
+            wgpu::DeviceType::DiscreteGpu,
+            wgpu::DeviceType::VirtualGpu,
+        ];
+        device_types.sort_by_key(|device_type| std::cmp::Reverse(adapter_score(device_type)));
+        assert_eq!(
+            device_types,
+            vec![
+                wgpu::DeviceType::DiscreteGpu,
+                wgpu::DeviceType::IntegratedGpu,
+                wgpu::DeviceType::VirtualGpu,
+                wgpu::DeviceType::Cpu,
+            ]
+        );
+        assert_eq!("gl".parse(), Ok(GpuBackend::Gl));
+        assert!("opengl".parse::<GpuBackend>().is_err());
+    }
+}
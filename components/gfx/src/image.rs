@@ -0,0 +1,386 @@
+use crate::buffer::Buffer;
+use crate::texture_cache::{Region, TextureCache, TextureId, TextureKey, ATLAS};
+use bytemuck::{Pod, Zeroable};
+use painting::Image;
+use std::borrow::Cow;
+use std::collections::HashMap;
+use std::ops::Range;
+use ultraviolet as uv;
+
+const INSTANCE_BUFFER_SIZE: usize = 100;
+
+const INDEX_FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
+
+/// Corners of the unit quad that is scaled to the rect of each instance
+const QUAD_VERTICES: [[f32; 2]; 4] = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+/// The width & height of the atlas of the small images
+const ATLAS_SIZE: u32 = 2048;
+
+/// The bytes of the images kept on the GPU across paints
+const TEXTURE_BUDGET: usize = 128 * 1024 * 1024;
+
+/// An image drawn as an instance of the unit quad textured with a
+/// region of a texture
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instance {
+    /// x, y, width & height of the rect
+    pub rect: uv::Vec4,
+    /// x, y, width & height of the region of the texture, from 0 to 1
+    pub uv: uv::Vec4,
+}
+
+unsafe impl Pod for Instance {}
+unsafe impl Zeroable for Instance {}
+
+/// A texture with the bind group to sample it
+struct Texture {
+    texture: wgpu::Texture,
+    size: (u32, u32),
+    bind_group: wgpu::BindGroup,
+}
+
+pub struct Pipeline {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    instance_buffer: Buffer<Instance>,
+    texture_layout: wgpu::BindGroupLayout,
+    sampler: wgpu::Sampler,
+    textures: HashMap<TextureId, Texture>,
+    cache: TextureCache,
+    atlas_size: u32,
+}
+
+impl Pipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        constants_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("image shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/shaders/image.wgsl"
+            )))),
+            flags: wgpu::ShaderFlags::default(),
+        });
+
+        let texture_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("moon::gfx::image texture layout"),
+            entries: &[
+                wgpu::BindGroupLayoutEntry {
+                    binding: 0,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Texture {
+                        sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                        view_dimension: wgpu::TextureViewDimension::D2,
+                        multisampled: false,
+                    },
+                    count: None,
+                },
+                wgpu::BindGroupLayoutEntry {
+                    binding: 1,
+                    visibility: wgpu::ShaderStage::FRAGMENT,
+                    ty: wgpu::BindingType::Sampler {
+                        filtering: false,
+                        comparison: false,
+                    },
+                    count: None,
+                },
+            ],
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("moon::gfx::image pipeline layout"),
+            bind_group_layouts: &[constants_layout, &texture_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("moon::gfx::image pipeline"),
+            layout: Some(&layout),
+
+            // Vertex shader
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            1 => Float32x4,
+                            2 => Float32x4
+                        ],
+                    },
+                ],
+            },
+
+            // Fragment shader
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        });
+
+        let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
+            device,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("moon::gfx::image vertex buffer"),
+                contents: bytemuck::cast_slice(&QUAD_VERTICES),
+                usage: wgpu::BufferUsage::VERTEX,
+            },
+        );
+
+        let index_buffer = wgpu::util::DeviceExt::create_buffer_init(
+            device,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("moon::gfx::image index buffer"),
+                contents: bytemuck::cast_slice(&QUAD_INDICES),
+                usage: wgpu::BufferUsage::INDEX,
+            },
+        );
+
+        // the images are painted one texel per pixel
+        let sampler = device.create_sampler(&wgpu::SamplerDescriptor {
+            label: Some("moon::gfx::image sampler"),
+            address_mode_u: wgpu::AddressMode::ClampToEdge,
+            address_mode_v: wgpu::AddressMode::ClampToEdge,
+            mag_filter: wgpu::FilterMode::Nearest,
+            min_filter: wgpu::FilterMode::Nearest,
+            ..Default::default()
+        });
+
+        let atlas_size = ATLAS_SIZE.min(device.limits().max_texture_dimension_2d);
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer: Buffer::new(
+                "moon::gfx::image instance buffer",
+                device,
+                INSTANCE_BUFFER_SIZE,
+                wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            ),
+            texture_layout,
+            sampler,
+            textures: HashMap::new(),
+            cache: TextureCache::new(atlas_size, TEXTURE_BUDGET),
+            atlas_size,
+        }
+    }
+
+    /// The bytes of the instance buffer & of the textures of the images
+    pub fn buffer_size(&self) -> usize {
+        let textures = self
+            .textures
+            .values()
+            .map(|texture| (texture.size.0 * texture.size.1 * 4) as usize)
+            .sum::<usize>();
+        self.instance_buffer.byte_size() + textures
+    }
+
+    /// Start a new frame, the images of the previous frames can be evicted
+    /// to upload the images of the new frame
+    pub fn next_frame(&mut self) {
+        self.cache.next_frame();
+    }
+
+    /// The texture & the texture coordinates of an image, uploaded unless
+    /// it's already cached
+    pub fn upload(
+        &mut self,
+        device: &wgpu::Device,
+        queue: &wgpu::Queue,
+        image: &Image,
+    ) -> Option<(TextureId, uv::Vec4)> {
+        let max_size = device.limits().max_texture_dimension_2d;
+        if image.width == 0 || image.height == 0 || image.width.max(image.height) > max_size {
+            return None;
+        }
+
+        let key = TextureKey::new(
+            image.key.as_deref(),
+            image.width,
+            image.height,
+            &image.pixels,
+        );
+        let cached = match self.cache.get(&key) {
+            Some(cached) => cached,
+            None => {
+                let allocation = self.cache.allocate(key, image.width, image.height);
+                for texture in allocation.evicted {
+                    if let Some(texture) = self.textures.remove(&texture) {
+                        texture.texture.destroy();
+                    }
+                }
+                if !self.textures.contains_key(&allocation.texture.texture) {
+                    let size = match allocation.texture.texture {
+                        ATLAS => (self.atlas_size, self.atlas_size),
+                        _ => (image.width, image.height),
+                    };
+                    let texture = self.create_texture(device, size);
+                    self.textures.insert(allocation.texture.texture, texture);
+                }
+                write_image(
+                    queue,
+                    &self.textures[&allocation.texture.texture],
+                    image,
+                    allocation.texture.region,
+                );
+                allocation.texture
+            }
+        };
+
+        let texture = &self.textures[&cached.texture];
+        let (x, y, width, height) = cached.region;
+        let (texture_width, texture_height) = texture.size;
+        let uv = uv::Vec4::new(
+            x as f32 / texture_width as f32,
+            y as f32 / texture_height as f32,
+            width as f32 / texture_width as f32,
+            height as f32 / texture_height as f32,
+        );
+        Some((cached.texture, uv))
+    }
+
+    fn create_texture(&self, device: &wgpu::Device, size: (u32, u32)) -> Texture {
+        let texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("moon::gfx::image texture"),
+            size: wgpu::Extent3d {
+                width: size.0,
+                height: size.1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            // the pixels are decoded into linear space when sampled
+            format: wgpu::TextureFormat::Rgba8UnormSrgb,
+            usage: wgpu::TextureUsage::SAMPLED | wgpu::TextureUsage::COPY_DST,
+        });
+        let view = texture.create_view(&Default::default());
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("moon::gfx::image texture bind group"),
+            layout: &self.texture_layout,
+            entries: &[
+                wgpu::BindGroupEntry {
+                    binding: 0,
+                    resource: wgpu::BindingResource::TextureView(&view),
+                },
+                wgpu::BindGroupEntry {
+                    binding: 1,
+                    resource: wgpu::BindingResource::Sampler(&self.sampler),
+                },
+            ],
+        });
+        Texture {
+            texture,
+            size,
+            bind_group,
+        }
+    }
+
+    /// Upload the instances to draw in the next render pass
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        instances: &[Instance],
+    ) {
+        self.instance_buffer.expand(device, instances.len());
+
+        let bytes = bytemuck::cast_slice(instances);
+        if let Some(size) = wgpu::BufferSize::new(bytes.len() as u64) {
+            let mut instance_buffer =
+                staging_belt.write_buffer(encoder, &self.instance_buffer.raw, 0, size, device);
+            instance_buffer.copy_from_slice(bytes);
+        }
+    }
+
+    /// Draw a range of the prepared instances with the image of a texture
+    pub fn render<'a>(
+        &'a self,
+        render_pass: &mut wgpu::RenderPass<'a>,
+        texture: TextureId,
+        instances: Range<u32>,
+    ) {
+        let texture = match self.textures.get(&texture) {
+            Some(texture) => texture,
+            None => return,
+        };
+        if instances.start >= instances.end {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+        render_pass.set_bind_group(1, &texture.bind_group, &[]);
+
+        render_pass.set_index_buffer(self.index_buffer.slice(..), INDEX_FORMAT);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.raw.slice(..));
+
+        render_pass.draw_indexed(0..QUAD_INDICES.len() as u32, 0, instances);
+    }
+}
+
+/// Write the pixels of an image into a region of a texture
+fn write_image(queue: &wgpu::Queue, texture: &Texture, image: &Image, region: Region) {
+    let (x, y, width, height) = region;
+    queue.write_texture(
+        wgpu::ImageCopyTexture {
+            texture: &texture.texture,
+            mip_level: 0,
+            origin: wgpu::Origin3d { x, y, z: 0 },
+        },
+        &image.pixels,
+        wgpu::ImageDataLayout {
+            offset: 0,
+            bytes_per_row: std::num::NonZeroU32::new(4 * width),
+            rows_per_image: std::num::NonZeroU32::new(height),
+        },
+        wgpu::Extent3d {
+            width,
+            height,
+            depth_or_array_layers: 1,
+        },
+    );
+}
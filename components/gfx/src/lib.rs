@@ -1,8 +1,27 @@
+mod antialiasing;
 mod backend;
+mod buffer;
+mod color_space;
+mod cpu_painter;
+mod gpu_backend;
+mod image;
 mod painter;
 mod painters;
+mod pixel_format;
+mod quad;
+mod texture_cache;
+mod tiling;
 mod triangle;
+mod uniforms;
+mod wgpu_painter;
 
 pub type Bitmap = Vec<u8>;
 
-pub use painter::Painter;
+pub use antialiasing::Antialiasing;
+pub use color_space::ColorSpace;
+pub use cpu_painter::CpuPainter;
+pub use gpu_backend::{gpu_adapter, GpuBackend};
+pub use painter::{PaintBackend, Painter};
+pub use pixel_format::PixelFormat;
+pub use tiling::{stitch, tiles};
+pub use wgpu_painter::WgpuPainter;
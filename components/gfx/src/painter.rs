@@ -1,203 +1,167 @@
-use super::backend::{Backend, DrawRequest};
-use super::Bitmap;
-use crate::painters::rect::RectPainter;
-use futures::task::SpawnExt;
-use painting::{Color, RRect, Rect};
-
-pub struct Painter<'a> {
-    rect_painter: RectPainter,
-    backend: Backend,
-    device: wgpu::Device,
-    queue: wgpu::Queue,
-    staging_belt: wgpu::util::StagingBelt,
-    local_pool: futures::executor::LocalPool,
-    frame_desc: wgpu::TextureDescriptor<'a>,
-    frame: wgpu::Texture,
-    frame_texture_view: wgpu::TextureView,
-    output_buffer: wgpu::Buffer,
-    output_buffer_desc: wgpu::BufferDescriptor<'a>,
+use super::color_space::ColorSpace;
+use super::cpu_painter::CpuPainter;
+use super::gpu_backend::GpuBackend;
+use super::pixel_format::{self, PixelFormat};
+use super::wgpu_painter::WgpuPainter;
+use super::{Antialiasing, Bitmap};
+use painting::{Color, Image, RRect, Rect, Transform};
+use std::str::FromStr;
+
+/// Where the frames are painted
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PaintBackend {
+    Gpu,
+    /// The rasterizer of the CPU, whose output doesn't depend on the
+    /// machine
+    Cpu,
 }
 
-pub const TEXTURE_FORMAT: wgpu::TextureFormat = wgpu::TextureFormat::Rgba8Unorm;
+impl Default for PaintBackend {
+    fn default() -> Self {
+        PaintBackend::Gpu
+    }
+}
+
+impl FromStr for PaintBackend {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value {
+            "gpu" => Ok(PaintBackend::Gpu),
+            "cpu" => Ok(PaintBackend::Cpu),
+            _ => Err(format!("Unknown paint backend: {}", value)),
+        }
+    }
+}
+
+/// The painter of the frames of a renderer, on one of the backends
+pub enum Painter<'a> {
+    Wgpu(WgpuPainter<'a>),
+    Cpu(CpuPainter),
+}
 
 impl<'a> Painter<'a> {
-    const CHUNK_SIZE: u64 = 10 * 1024;
-
-    pub async fn new() -> Painter<'a> {
-        let instance = wgpu::Instance::new(wgpu::BackendBit::PRIMARY);
-        let adapter = instance
-            .request_adapter(&wgpu::RequestAdapterOptions {
-                power_preference: wgpu::PowerPreference::default(),
-                compatible_surface: None,
-            })
-            .await
-            .unwrap();
-
-        let (device, queue) = adapter
-            .request_device(&Default::default(), None)
-            .await
-            .unwrap();
-
-        let staging_belt = wgpu::util::StagingBelt::new(Self::CHUNK_SIZE);
-        let local_pool = futures::executor::LocalPool::new();
-
-        let frame_desc = wgpu::TextureDescriptor {
-            label: Some("moon output texture"),
-            size: wgpu::Extent3d {
-                width: 1,
-                height: 1,
-                depth_or_array_layers: 1,
+    /// Create a painter on a backend. The CPU backend has its own
+    /// antialiasing, the method is only used on the GPU. The frames are
+    /// painted on the CPU when the machine has no GPU adapter.
+    pub async fn new(
+        backend: PaintBackend,
+        gpu_backend: Option<GpuBackend>,
+        antialiasing: Antialiasing,
+    ) -> Painter<'a> {
+        match backend {
+            PaintBackend::Gpu => match WgpuPainter::new(antialiasing, gpu_backend).await {
+                Ok(painter) => Painter::Wgpu(painter),
+                Err(e) => {
+                    log::warn!("{}, painting on the CPU instead", e);
+                    Painter::Cpu(CpuPainter::new())
+                }
             },
-            mip_level_count: 1,
-            sample_count: 1,
-            dimension: wgpu::TextureDimension::D2,
-            format: TEXTURE_FORMAT,
-            usage: wgpu::TextureUsage::COPY_SRC | wgpu::TextureUsage::RENDER_ATTACHMENT,
-        };
-
-        let frame = device.create_texture(&frame_desc);
-
-        let frame_texture_view = frame.create_view(&Default::default());
-        let output_buffer_desc = wgpu::BufferDescriptor {
-            label: Some("moon output buffer"),
-            size: 1,
-            usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::MAP_READ,
-            mapped_at_creation: false,
-        };
-        let output_buffer = device.create_buffer(&output_buffer_desc);
-
-        Self {
-            backend: Backend::new(&device, TEXTURE_FORMAT),
-            rect_painter: RectPainter::new(),
-            device,
-            queue,
-            staging_belt,
-            local_pool,
-            frame_desc,
-            frame,
-            frame_texture_view,
-            output_buffer,
-            output_buffer_desc,
+            PaintBackend::Cpu => Painter::Cpu(CpuPainter::new()),
         }
     }
 
-    pub fn resize(&mut self, size: (u32, u32)) {
-        let (width, height) = size;
-        self.frame_desc.size.width = width;
-        self.frame_desc.size.height = height;
-
-        self.output_buffer_desc.size = (self.get_bytes_per_row() * height) as u64;
+    /// The backend the frames are painted on, which is the CPU when the
+    /// GPU was asked for but isn't available
+    pub fn backend(&self) -> PaintBackend {
+        match self {
+            Painter::Wgpu(_) => PaintBackend::Gpu,
+            Painter::Cpu(_) => PaintBackend::Cpu,
+        }
+    }
 
-        self.frame = self.device.create_texture(&self.frame_desc);
-        self.frame_texture_view = self.frame.create_view(&Default::default());
-        self.output_buffer = self.device.create_buffer(&self.output_buffer_desc);
+    pub fn resize(&mut self, size: (u32, u32)) {
+        match self {
+            Painter::Wgpu(painter) => painter.resize(size),
+            Painter::Cpu(painter) => painter.resize(size),
+        }
     }
 
-    pub fn paint(&mut self) {
-        let triangles = &self.rect_painter.vertex_buffers();
-
-        let request = DrawRequest { triangles };
-
-        let mut encoder = self
-            .device
-            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
-                label: Some("moon wgpu encoder"),
-            });
-
-        // Background clear
-        encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("moon::gfx clear bg render pass"),
-            color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: &self.frame_texture_view,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: None,
-        });
-
-        self.backend.draw(
-            &self.device,
-            &mut encoder,
-            &mut self.staging_belt,
-            &self.frame.create_view(&Default::default()),
-            (self.frame_desc.size.width, self.frame_desc.size.height),
-            request,
-        );
-
-        encoder.copy_texture_to_buffer(
-            wgpu::ImageCopyTexture {
-                texture: &self.frame,
-                mip_level: 0,
-                origin: wgpu::Origin3d::ZERO,
-            },
-            wgpu::ImageCopyBuffer {
-                buffer: &self.output_buffer,
-                layout: wgpu::ImageDataLayout {
-                    offset: 0,
-                    bytes_per_row: core::num::NonZeroU32::new(self.get_bytes_per_row()),
-                    rows_per_image: core::num::NonZeroU32::new(self.frame_desc.size.height),
-                },
-            },
-            self.frame_desc.size,
-        );
+    /// Encode the pixels of the output bitmaps into a color space
+    pub fn set_output_color_space(&mut self, color_space: ColorSpace) {
+        match self {
+            Painter::Wgpu(painter) => painter.set_output_color_space(color_space),
+            Painter::Cpu(painter) => painter.set_output_color_space(color_space),
+        }
+    }
 
-        self.staging_belt.finish();
-        self.queue.submit(Some(encoder.finish()));
-        self.local_pool
-            .spawner()
-            .spawn(self.staging_belt.recall())
-            .expect("Recall staging belt");
+    /// The largest width or height the frame can be resized to
+    pub fn max_texture_dimension(&self) -> u32 {
+        match self {
+            Painter::Wgpu(painter) => painter.max_texture_dimension(),
+            Painter::Cpu(painter) => painter.max_texture_dimension(),
+        }
+    }
 
-        self.local_pool.run_until_stalled();
+    /// The approximate bytes of the frames & buffers of the painter
+    pub fn memory_size(&self) -> usize {
+        match self {
+            Painter::Wgpu(painter) => painter.memory_size(),
+            Painter::Cpu(painter) => painter.memory_size(),
+        }
     }
 
-    fn get_bytes_per_row(&self) -> u32 {
-        let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-        let unpadded_bytes_per_row = 4 * self.frame_desc.size.width;
-        let padding = alignment - unpadded_bytes_per_row % alignment;
-        let bytes_per_row = padding + unpadded_bytes_per_row;
+    /// Paint the whole frame
+    pub fn paint(&mut self) {
+        match self {
+            Painter::Wgpu(painter) => painter.paint(),
+            Painter::Cpu(painter) => painter.paint(),
+        }
+    }
 
-        bytes_per_row
+    /// Paint a region of the frame, keeping the rest of the previous frame
+    pub fn paint_region(&mut self, region: &Rect) {
+        match self {
+            Painter::Wgpu(painter) => painter.paint_region(region),
+            Painter::Cpu(painter) => painter.paint_region(region),
+        }
     }
 
+    /// The pixels of the frame as a tightly packed RGBA bitmap of
+    /// `width * height * 4` bytes, which can be given as is to
+    /// `ImageBuffer::from_raw`
     pub async fn output(&mut self) -> Bitmap {
-        let buffer_slice = self.output_buffer.slice(..);
-
-        // NOTE: We have to create the mapping THEN device.poll() before await
-        // the future. Otherwise the application will freeze.
-        let mapping = buffer_slice.map_async(wgpu::MapMode::Read);
-        self.device.poll(wgpu::Maintain::Wait);
-
-        mapping.await.unwrap();
-
-        let aligned_output = buffer_slice.get_mapped_range().to_vec();
-
-        let mut output = Vec::new();
-        let mut row_pointer: usize = 0;
-
-        let unpadded_bytes_per_row = 4 * self.frame_desc.size.width;
-
-        for _ in 0..self.frame_desc.size.height {
-            let row = &aligned_output[row_pointer..row_pointer + unpadded_bytes_per_row as usize];
-            output.extend_from_slice(row);
-            row_pointer += self.get_bytes_per_row() as usize;
+        match self {
+            Painter::Wgpu(painter) => painter.output().await,
+            Painter::Cpu(painter) => painter.output().await,
         }
+    }
 
-        self.output_buffer.unmap();
+    /// The pixels of the frame as a tightly packed bitmap of a format
+    pub async fn output_as(&mut self, format: PixelFormat) -> Bitmap {
+        pixel_format::convert(self.output().await, format)
+    }
 
-        output
+    fn inner(&mut self) -> &mut dyn painting::Painter {
+        match self {
+            Painter::Wgpu(painter) => painter,
+            Painter::Cpu(painter) => painter,
+        }
     }
 }
 
 impl<'a> painting::Painter for Painter<'a> {
     fn fill_rect(&mut self, rect: Rect, color: Color) {
-        self.rect_painter.draw_solid_rect(&rect, &color);
+        self.inner().fill_rect(rect, color);
     }
 
     fn fill_rrect(&mut self, rect: RRect, color: Color) {
-        self.rect_painter.draw_solid_rrect(&rect, &color);
+        self.inner().fill_rrect(rect, color);
+    }
+
+    fn stroke_rrect(&mut self, rect: RRect, color: Color, width: f32) {
+        self.inner().stroke_rrect(rect, color, width);
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.inner().set_transform(transform);
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.inner().set_clip(clip);
+    }
+
+    fn draw_image(&mut self, rect: Rect, image: &Image) {
+        self.inner().draw_image(rect, image);
     }
 }
@@ -1,20 +1,57 @@
 use lyon_tessellation::geom::point;
 use lyon_tessellation::path::Path;
-use lyon_tessellation::{BuffersBuilder, FillOptions, FillTessellator, VertexBuffers};
-use painting::{Color, RRect, Rect};
+use lyon_tessellation::{
+    BuffersBuilder, FillOptions, FillTessellator, StrokeOptions, StrokeTessellator, VertexBuffers,
+};
+use painting::{Color, RRect, Rect, Transform};
+use std::ops::Range;
+use ultraviolet as uv;
 
+use crate::color_space::linear_color;
+use crate::image;
+use crate::quad::Instance;
+use crate::texture_cache::TextureId;
 use crate::triangle::{Index, Vertex, VertexConstructor};
 
+/// A draw call of the painted rects, in paint order. Each item is drawn
+/// with the transform & the clip at the given index.
+#[derive(Debug, Clone, PartialEq)]
+pub enum DrawItem {
+    /// A range of consecutive solid rects drawn as quad instances
+    Quads(Range<u32>, usize),
+    /// A tessellated mesh
+    Mesh(usize, usize),
+    /// A range of consecutive images of the same texture
+    Images(Range<u32>, TextureId, usize),
+}
+
 pub struct RectPainter {
     fill_tess: FillTessellator,
+    stroke_tess: StrokeTessellator,
     vertex_buffers: Vec<VertexBuffers<Vertex, Index>>,
+    instances: Vec<Instance>,
+    image_instances: Vec<image::Instance>,
+    items: Vec<DrawItem>,
+    /// The transforms used by the draw items, starting with the identity
+    transforms: Vec<Transform>,
+    /// The clips of the draw items in frame coordinates, along with the
+    /// transforms of the same index
+    clips: Vec<Option<Rect>>,
+    current_transform: usize,
 }
 
 impl RectPainter {
     pub fn new() -> Self {
         Self {
             fill_tess: FillTessellator::new(),
+            stroke_tess: StrokeTessellator::new(),
             vertex_buffers: Vec::new(),
+            instances: Vec::new(),
+            image_instances: Vec::new(),
+            items: Vec::new(),
+            transforms: vec![Transform::identity()],
+            clips: vec![None],
+            current_transform: 0,
         }
     }
 
@@ -22,107 +59,146 @@ impl RectPainter {
         &self.vertex_buffers
     }
 
-    pub fn draw_solid_rect(&mut self, rect: &Rect, color: &Color) {
-        let color_arr: [f32; 4] = [
-            color.r.into(),
-            color.g.into(),
-            color.b.into(),
-            color.a.into(),
-        ];
+    pub fn instances(&self) -> &[Instance] {
+        &self.instances
+    }
 
-        let mut path_builder = Path::builder_with_attributes(4);
-        path_builder.begin(point(rect.x, rect.y), &color_arr);
-        path_builder.line_to(point(rect.x + rect.width, rect.y), &color_arr);
-        path_builder.line_to(point(rect.x + rect.width, rect.y + rect.height), &color_arr);
-        path_builder.line_to(point(rect.x, rect.y + rect.height), &color_arr);
-        path_builder.end(true);
+    pub fn image_instances(&self) -> &[image::Instance] {
+        &self.image_instances
+    }
 
-        let path = path_builder.build();
-        self.tessellate_path(path);
+    pub fn items(&self) -> &[DrawItem] {
+        &self.items
     }
 
-    pub fn draw_solid_rrect(&mut self, rect: &RRect, color: &Color) {
-        let color_arr: [f32; 4] = [
-            color.r.into(),
-            color.g.into(),
-            color.b.into(),
-            color.a.into(),
-        ];
-
-        let corners = &rect.corners;
-
-        let mut path_builder = Path::builder_with_attributes(4);
-        path_builder.begin(
-            point(rect.x + rect.corners.top_left.horizontal_r(), rect.y),
-            &color_arr,
-        );
+    pub fn transforms(&self) -> &[Transform] {
+        &self.transforms
+    }
 
-        path_builder.line_to(
-            point(
-                rect.x + rect.width - corners.top_right.horizontal_r(),
-                rect.y,
-            ),
-            &color_arr,
-        );
+    pub fn clips(&self) -> &[Option<Rect>] {
+        &self.clips
+    }
 
-        path_builder.quadratic_bezier_to(
-            point(rect.x + rect.width, rect.y),
-            point(rect.x + rect.width, rect.y + corners.top_right.vertical_r()),
-            &color_arr,
-        );
+    /// Set the transform of the rects painted after this call
+    pub fn set_transform(&mut self, transform: Transform) {
+        let clip = self.clips[self.current_transform].clone();
+        self.set_state(transform, clip);
+    }
 
-        path_builder.line_to(
-            point(
-                rect.x + rect.width,
-                rect.y + rect.height - corners.bottom_right.vertical_r(),
-            ),
-            &color_arr,
-        );
+    /// Set the clip of the rects painted after this call
+    pub fn set_clip(&mut self, clip: Option<Rect>) {
+        let transform = self.transforms[self.current_transform].clone();
+        self.set_state(transform, clip);
+    }
 
-        path_builder.quadratic_bezier_to(
-            point(rect.x + rect.width, rect.y + rect.height),
-            point(
-                rect.x + rect.width - corners.bottom_right.horizontal_r(),
-                rect.y + rect.height,
-            ),
-            &color_arr,
-        );
+    fn set_state(&mut self, transform: Transform, clip: Option<Rect>) {
+        let index = self
+            .transforms
+            .iter()
+            .zip(&self.clips)
+            .position(|state| state == (&transform, &clip));
+        self.current_transform = match index {
+            Some(index) => index,
+            None => {
+                self.transforms.push(transform);
+                self.clips.push(clip);
+                self.transforms.len() - 1
+            }
+        };
+    }
 
-        path_builder.line_to(
-            point(
-                rect.x + corners.bottom_left.horizontal_r(),
-                rect.y + rect.height,
-            ),
-            &color_arr,
-        );
+    /// Remove the painted rects, e.g. once they are drawn
+    pub fn clear(&mut self) {
+        self.vertex_buffers.clear();
+        self.instances.clear();
+        self.image_instances.clear();
+        self.items.clear();
+        self.transforms.truncate(1);
+        self.clips.truncate(1);
+        self.current_transform = 0;
+    }
 
-        path_builder.quadratic_bezier_to(
-            point(rect.x, rect.y + rect.height),
-            point(
-                rect.x,
-                rect.y + rect.height - corners.bottom_left.vertical_r(),
-            ),
-            &color_arr,
-        );
+    /// Draw a rect below all the rects painted so far
+    pub fn draw_solid_rect_below(&mut self, rect: &Rect, color: &Color) {
+        self.instances.insert(0, to_instance(rect, color));
 
-        path_builder.line_to(
-            point(rect.x, rect.y + corners.top_left.vertical_r()),
-            &color_arr,
-        );
+        for item in self.items.iter_mut() {
+            if let DrawItem::Quads(range, _) = item {
+                *range = range.start + 1..range.end + 1;
+            }
+        }
 
-        path_builder.quadratic_bezier_to(
-            point(rect.x, rect.y),
-            point(rect.x + corners.top_left.horizontal_r(), rect.y),
-            &color_arr,
+        // the rect is drawn without transform
+        match self.items.first_mut() {
+            Some(DrawItem::Quads(range, 0)) => range.start = 0,
+            _ => self.items.insert(0, DrawItem::Quads(0..1, 0)),
+        }
+    }
+
+    pub fn draw_solid_rect(&mut self, rect: &Rect, color: &Color) {
+        self.instances.push(to_instance(rect, color));
+        let end = self.instances.len() as u32;
+
+        // consecutive rects with the same transform are drawn in the same draw call
+        match self.items.last_mut() {
+            Some(DrawItem::Quads(range, transform)) if *transform == self.current_transform => {
+                range.end = end
+            }
+            _ => self
+                .items
+                .push(DrawItem::Quads(end - 1..end, self.current_transform)),
+        }
+    }
+
+    /// Draw a region of a texture, given in texture coordinates, over a rect
+    pub fn draw_texture(&mut self, rect: &Rect, texture: TextureId, uv: uv::Vec4) {
+        self.image_instances.push(image::Instance {
+            rect: uv::Vec4::new(rect.x, rect.y, rect.width, rect.height),
+            uv,
+        });
+        let end = self.image_instances.len() as u32;
+
+        // consecutive images of a texture are drawn in the same draw call
+        match self.items.last_mut() {
+            Some(DrawItem::Images(range, last_texture, transform))
+                if *last_texture == texture && *transform == self.current_transform =>
+            {
+                range.end = end
+            }
+            _ => self.items.push(DrawItem::Images(
+                end - 1..end,
+                texture,
+                self.current_transform,
+            )),
+        }
+    }
+
+    pub fn draw_solid_rrect(&mut self, rect: &RRect, color: &Color) {
+        let path = rrect_path(rect, color);
+        self.fill_path(path);
+    }
+
+    pub fn draw_stroked_rrect(&mut self, rect: &RRect, color: &Color, line_width: f32) {
+        let path = rrect_path(rect, color);
+        let mut buffer: VertexBuffers<Vertex, Index> = VertexBuffers::new();
+
+        let result = self.stroke_tess.tessellate_with_ids(
+            path.id_iter(),
+            &path,
+            Some(&path),
+            &StrokeOptions::DEFAULT.with_line_width(line_width),
+            &mut BuffersBuilder::new(&mut buffer, VertexConstructor),
         );
 
-        path_builder.end(true);
+        if let Err(e) = result {
+            log::error!("Tessellation failed: {:?}", e);
+            return;
+        }
 
-        let path = path_builder.build();
-        self.tessellate_path(path);
+        self.push_mesh(buffer);
     }
 
-    fn tessellate_path(&mut self, path: Path) {
+    fn fill_path(&mut self, path: Path) {
         let mut buffer: VertexBuffers<Vertex, Index> = VertexBuffers::new();
 
         let result = self.fill_tess.tessellate_with_ids(
@@ -138,6 +214,195 @@ impl RectPainter {
             return;
         }
 
+        self.push_mesh(buffer);
+    }
+
+    fn push_mesh(&mut self, buffer: VertexBuffers<Vertex, Index>) {
+        self.items.push(DrawItem::Mesh(
+            self.vertex_buffers.len(),
+            self.current_transform,
+        ));
         self.vertex_buffers.push(buffer);
     }
 }
+
+fn rrect_path(rect: &RRect, color: &Color) -> Path {
+    let color_arr: [f32; 4] = [
+        color.r.into(),
+        color.g.into(),
+        color.b.into(),
+        color.a.into(),
+    ];
+
+    let corners = &rect.corners;
+
+    let mut path_builder = Path::builder_with_attributes(4);
+    path_builder.begin(
+        point(rect.x + rect.corners.top_left.horizontal_r(), rect.y),
+        &color_arr,
+    );
+
+    path_builder.line_to(
+        point(
+            rect.x + rect.width - corners.top_right.horizontal_r(),
+            rect.y,
+        ),
+        &color_arr,
+    );
+
+    path_builder.quadratic_bezier_to(
+        point(rect.x + rect.width, rect.y),
+        point(rect.x + rect.width, rect.y + corners.top_right.vertical_r()),
+        &color_arr,
+    );
+
+    path_builder.line_to(
+        point(
+            rect.x + rect.width,
+            rect.y + rect.height - corners.bottom_right.vertical_r(),
+        ),
+        &color_arr,
+    );
+
+    path_builder.quadratic_bezier_to(
+        point(rect.x + rect.width, rect.y + rect.height),
+        point(
+            rect.x + rect.width - corners.bottom_right.horizontal_r(),
+            rect.y + rect.height,
+        ),
+        &color_arr,
+    );
+
+    path_builder.line_to(
+        point(
+            rect.x + corners.bottom_left.horizontal_r(),
+            rect.y + rect.height,
+        ),
+        &color_arr,
+    );
+
+    path_builder.quadratic_bezier_to(
+        point(rect.x, rect.y + rect.height),
+        point(
+            rect.x,
+            rect.y + rect.height - corners.bottom_left.vertical_r(),
+        ),
+        &color_arr,
+    );
+
+    path_builder.line_to(
+        point(rect.x, rect.y + corners.top_left.vertical_r()),
+        &color_arr,
+    );
+
+    path_builder.quadratic_bezier_to(
+        point(rect.x, rect.y),
+        point(rect.x + corners.top_left.horizontal_r(), rect.y),
+        &color_arr,
+    );
+
+    path_builder.end(true);
+
+    path_builder.build()
+}
+
+fn to_instance(rect: &Rect, color: &Color) -> Instance {
+    Instance {
+        rect: uv::Vec4::new(rect.x, rect.y, rect.width, rect.height),
+        color: linear_color(
+            color.r as f32,
+            color.g as f32,
+            color.b as f32,
+            color.a as f32,
+        ),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use painting::{Corners, Radii};
+
+    const BLACK: Color = Color {
+        r: 0,
+        g: 0,
+        b: 0,
+        a: 255,
+    };
+
+    fn rect(x: f32) -> Rect {
+        Rect::new(x, 0., 10., 10.)
+    }
+
+    fn rrect(x: f32) -> RRect {
+        let radii = || Radii::new(2., 2.);
+        RRect::new(
+            x,
+            0.,
+            10.,
+            10.,
+            Corners::new(radii(), radii(), radii(), radii()),
+        )
+    }
+
+    #[test]
+    fn interleave_quads_and_meshes() {
+        let mut painter = RectPainter::new();
+        painter.draw_solid_rect(&rect(0.), &BLACK);
+        painter.draw_solid_rect(&rect(10.), &BLACK);
+        painter.draw_solid_rrect(&rrect(20.), &BLACK);
+        painter.draw_solid_rect(&rect(30.), &BLACK);
+        painter.set_transform(Transform::translate(5., 5.));
+        painter.draw_solid_rect(&rect(40.), &BLACK);
+        painter.draw_stroked_rrect(&rrect(50.), &BLACK, 1.);
+        painter.set_transform(Transform::identity());
+        painter.draw_solid_rect(&rect(60.), &BLACK);
+
+        // the rects after a mesh are drawn over it, in a new draw call
+        assert_eq!(
+            painter.items(),
+            [
+                DrawItem::Quads(0..2, 0),
+                DrawItem::Mesh(0, 0),
+                DrawItem::Quads(2..3, 0),
+                DrawItem::Quads(3..4, 1),
+                DrawItem::Mesh(1, 1),
+                DrawItem::Quads(4..5, 0),
+            ]
+        );
+        assert_eq!(painter.instances().len(), 5);
+        assert_eq!(painter.vertex_buffers().len(), 2);
+        assert_eq!(painter.transforms().len(), 2);
+
+        // a rect below the others shifts the instances of the quads
+        painter.draw_solid_rect_below(&rect(0.), &BLACK);
+        assert_eq!(painter.items()[0], DrawItem::Quads(0..3, 0));
+        assert_eq!(painter.items()[2], DrawItem::Quads(3..4, 0));
+        assert_eq!(painter.items()[5], DrawItem::Quads(5..6, 0));
+
+        painter.clear();
+        assert!(painter.items().is_empty());
+        assert_eq!(painter.transforms().len(), 1);
+    }
+
+    #[test]
+    fn batch_images_of_a_texture() {
+        let mut painter = RectPainter::new();
+        let uv = uv::Vec4::new(0., 0., 1., 1.);
+        painter.draw_texture(&rect(0.), 1, uv);
+        painter.draw_texture(&rect(10.), 1, uv);
+        painter.draw_texture(&rect(20.), 2, uv);
+        painter.draw_solid_rect(&rect(30.), &BLACK);
+        painter.draw_texture(&rect(40.), 2, uv);
+
+        assert_eq!(
+            painter.items(),
+            [
+                DrawItem::Images(0..2, 1, 0),
+                DrawItem::Images(2..3, 2, 0),
+                DrawItem::Quads(0..1, 0),
+                DrawItem::Images(3..4, 2, 0),
+            ]
+        );
+    }
+}
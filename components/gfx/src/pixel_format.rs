@@ -0,0 +1,66 @@
+use super::Bitmap;
+
+/// The order of the channels of the pixels of an output bitmap. The
+/// bitmaps are always tightly packed, with 4 bytes per pixel and no
+/// padding at the end of the rows.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum PixelFormat {
+    Rgba8,
+    /// The format of most window surfaces
+    Bgra8,
+}
+
+/// Convert a tightly packed RGBA bitmap into a format
+pub fn convert(mut bitmap: Bitmap, format: PixelFormat) -> Bitmap {
+    match format {
+        PixelFormat::Rgba8 => bitmap,
+        PixelFormat::Bgra8 => {
+            for pixel in bitmap.chunks_exact_mut(4) {
+                pixel.swap(0, 2);
+            }
+            bitmap
+        }
+    }
+}
+
+/// The bytes of a row of a texture copied into a buffer, whose rows
+/// must be aligned to `wgpu::COPY_BYTES_PER_ROW_ALIGNMENT`
+pub fn padded_bytes_per_row(width: u32) -> u32 {
+    let alignment = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    let unpadded_bytes_per_row = 4 * width;
+    (unpadded_bytes_per_row + alignment - 1) / alignment * alignment
+}
+
+/// Strip the padding at the end of the rows of a texture copied into a
+/// buffer, producing a tightly packed bitmap
+pub fn depad(padded: &[u8], width: u32, height: u32) -> Bitmap {
+    let bytes_per_row = padded_bytes_per_row(width) as usize;
+    let unpadded_bytes_per_row = 4 * width as usize;
+
+    let mut output = Vec::with_capacity(unpadded_bytes_per_row * height as usize);
+    for row in padded.chunks(bytes_per_row).take(height as usize) {
+        output.extend_from_slice(&row[..unpadded_bytes_per_row]);
+    }
+    output
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn depad_rows() {
+        assert_eq!(padded_bytes_per_row(1), 256);
+        assert_eq!(padded_bytes_per_row(64), 256);
+        assert_eq!(padded_bytes_per_row(65), 512);
+
+        let mut padded = vec![0; 512];
+        padded[..8].copy_from_slice(&[1, 2, 3, 4, 5, 6, 7, 8]);
+        padded[256..264].copy_from_slice(&[9, 10, 11, 12, 13, 14, 15, 16]);
+        let bitmap = depad(&padded, 2, 2);
+        assert_eq!(bitmap, (1..=16).collect::<Vec<u8>>());
+
+        let bgra = convert(bitmap, PixelFormat::Bgra8);
+        assert_eq!(&bgra[..8], &[3, 2, 1, 4, 7, 6, 5, 8]);
+    }
+}
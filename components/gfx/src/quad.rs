@@ -0,0 +1,214 @@
+use crate::buffer::Buffer;
+use bytemuck::{Pod, Zeroable};
+use std::borrow::Cow;
+use std::ops::Range;
+use ultraviolet as uv;
+
+const INSTANCE_BUFFER_SIZE: usize = 1_000;
+
+const INDEX_FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
+
+/// Corners of the unit quad that is scaled to the rect of each instance
+const QUAD_VERTICES: [[f32; 2]; 4] = [[0.0, 0.0], [1.0, 0.0], [1.0, 1.0], [0.0, 1.0]];
+const QUAD_INDICES: [u16; 6] = [0, 1, 2, 0, 2, 3];
+
+/// A solid rect drawn as an instance of the unit quad
+#[repr(C)]
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Instance {
+    /// x, y, width & height of the rect
+    pub rect: uv::Vec4,
+    pub color: uv::Vec4,
+}
+
+unsafe impl Pod for Instance {}
+unsafe impl Zeroable for Instance {}
+
+pub struct Pipeline {
+    pipeline: wgpu::RenderPipeline,
+    vertex_buffer: wgpu::Buffer,
+    index_buffer: wgpu::Buffer,
+    instance_buffer: Buffer<Instance>,
+    /// The instances currently in the instance buffer
+    uploaded_instances: Vec<Instance>,
+}
+
+impl Pipeline {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        constants_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
+        let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
+            label: Some("quad shader"),
+            source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(concat!(
+                env!("CARGO_MANIFEST_DIR"),
+                "/shaders/quad.wgsl"
+            )))),
+            flags: wgpu::ShaderFlags::default(),
+        });
+
+        let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+            label: Some("moon::gfx::quad pipeline layout"),
+            bind_group_layouts: &[constants_layout],
+            push_constant_ranges: &[],
+        });
+
+        let pipeline = device.create_render_pipeline(&wgpu::RenderPipelineDescriptor {
+            label: Some("moon::gfx::quad pipeline"),
+            layout: Some(&layout),
+
+            // Vertex shader
+            vertex: wgpu::VertexState {
+                module: &shader,
+                entry_point: "vs_main",
+                buffers: &[
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<[f32; 2]>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Vertex,
+                        attributes: &wgpu::vertex_attr_array![0 => Float32x2],
+                    },
+                    wgpu::VertexBufferLayout {
+                        array_stride: std::mem::size_of::<Instance>() as wgpu::BufferAddress,
+                        step_mode: wgpu::InputStepMode::Instance,
+                        attributes: &wgpu::vertex_attr_array![
+                            1 => Float32x4,
+                            2 => Float32x4
+                        ],
+                    },
+                ],
+            },
+
+            // Fragment shader
+            fragment: Some(wgpu::FragmentState {
+                module: &shader,
+                entry_point: "fs_main",
+                targets: &[wgpu::ColorTargetState {
+                    format,
+                    blend: Some(wgpu::BlendState {
+                        color: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::SrcAlpha,
+                            dst_factor: wgpu::BlendFactor::OneMinusSrcAlpha,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                        alpha: wgpu::BlendComponent {
+                            src_factor: wgpu::BlendFactor::One,
+                            dst_factor: wgpu::BlendFactor::One,
+                            operation: wgpu::BlendOperation::Add,
+                        },
+                    }),
+                    write_mask: wgpu::ColorWrite::ALL,
+                }],
+            }),
+            primitive: wgpu::PrimitiveState {
+                topology: wgpu::PrimitiveTopology::TriangleList,
+                ..Default::default()
+            },
+            depth_stencil: None,
+            multisample: wgpu::MultisampleState {
+                count: sample_count,
+                mask: !0,
+                alpha_to_coverage_enabled: false,
+            },
+        });
+
+        let vertex_buffer = wgpu::util::DeviceExt::create_buffer_init(
+            device,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("moon::gfx::quad vertex buffer"),
+                contents: bytemuck::cast_slice(&QUAD_VERTICES),
+                usage: wgpu::BufferUsage::VERTEX,
+            },
+        );
+
+        let index_buffer = wgpu::util::DeviceExt::create_buffer_init(
+            device,
+            &wgpu::util::BufferInitDescriptor {
+                label: Some("moon::gfx::quad index buffer"),
+                contents: bytemuck::cast_slice(&QUAD_INDICES),
+                usage: wgpu::BufferUsage::INDEX,
+            },
+        );
+
+        Self {
+            pipeline,
+            vertex_buffer,
+            index_buffer,
+            instance_buffer: Buffer::new(
+                "moon::gfx::quad instance buffer",
+                device,
+                INSTANCE_BUFFER_SIZE,
+                wgpu::BufferUsage::VERTEX | wgpu::BufferUsage::COPY_DST,
+            ),
+            uploaded_instances: Vec::new(),
+        }
+    }
+
+    /// The bytes of the instance buffer, which grows with the instances
+    pub fn buffer_size(&self) -> usize {
+        self.instance_buffer.byte_size()
+    }
+
+    /// Upload the instances to draw in the next render pass. Only the
+    /// range of instances that changed since the last frame is uploaded.
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        instances: &[Instance],
+    ) {
+        // new buffers don't contain anything yet
+        if self.instance_buffer.expand(device, instances.len()) {
+            self.uploaded_instances.clear();
+        }
+
+        let first_changed = instances
+            .iter()
+            .zip(&self.uploaded_instances)
+            .take_while(|(instance, uploaded)| instance == uploaded)
+            .count();
+        let unchanged_suffix = if instances.len() == self.uploaded_instances.len() {
+            instances[first_changed..]
+                .iter()
+                .rev()
+                .zip(self.uploaded_instances[first_changed..].iter().rev())
+                .take_while(|(instance, uploaded)| instance == uploaded)
+                .count()
+        } else {
+            0
+        };
+        let changed = &instances[first_changed..instances.len() - unchanged_suffix];
+
+        let changed_bytes = bytemuck::cast_slice(changed);
+        if let Some(changed_size) = wgpu::BufferSize::new(changed_bytes.len() as u64) {
+            let mut instance_buffer = staging_belt.write_buffer(
+                encoder,
+                &self.instance_buffer.raw,
+                (std::mem::size_of::<Instance>() * first_changed) as u64,
+                changed_size,
+                device,
+            );
+
+            instance_buffer.copy_from_slice(changed_bytes);
+        }
+        self.uploaded_instances.clear();
+        self.uploaded_instances.extend_from_slice(instances);
+    }
+
+    /// Draw a range of the prepared instances in a single draw call
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, instances: Range<u32>) {
+        if instances.start >= instances.end {
+            return;
+        }
+
+        render_pass.set_pipeline(&self.pipeline);
+
+        render_pass.set_index_buffer(self.index_buffer.slice(..), INDEX_FORMAT);
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.slice(..));
+        render_pass.set_vertex_buffer(1, self.instance_buffer.raw.slice(..));
+
+        render_pass.draw_indexed(0..QUAD_INDICES.len() as u32, 0, instances);
+    }
+}
@@ -0,0 +1,331 @@
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+/// The texture shared by the small images
+pub const ATLAS: TextureId = 0;
+
+/// The images larger than this in either dimension get their own texture
+const MAX_ATLAS_IMAGE_SIZE: u32 = 256;
+
+pub type TextureId = usize;
+
+/// Identifies the pixels of a cached image
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TextureKey {
+    /// The pixels decoded from a resource, e.g. the URL of an image, at a size
+    Resource(String, u32, u32),
+    /// The pixels of an image without resource, by their hash
+    Pixels(u64, u32, u32),
+}
+
+impl TextureKey {
+    pub fn new(key: Option<&str>, width: u32, height: u32, pixels: &[u8]) -> Self {
+        match key {
+            Some(key) => TextureKey::Resource(key.to_string(), width, height),
+            None => {
+                let mut hasher = DefaultHasher::new();
+                pixels.hash(&mut hasher);
+                TextureKey::Pixels(hasher.finish(), width, height)
+            }
+        }
+    }
+}
+
+/// A rectangle of pixels of a texture as (x, y, width, height)
+pub type Region = (u32, u32, u32, u32);
+
+/// Where the pixels of a cached image are
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct CachedTexture {
+    pub texture: TextureId,
+    pub region: Region,
+}
+
+#[derive(Debug)]
+struct Entry {
+    texture: CachedTexture,
+    /// The frame the image was last painted in
+    last_used: u64,
+}
+
+/// An allocation of the cache, whose pixels must be uploaded
+#[derive(Debug, PartialEq, Eq)]
+pub struct Allocation {
+    pub texture: CachedTexture,
+    /// Whether the texture has to be created for the image
+    pub is_new_texture: bool,
+    /// The textures of the evicted images to destroy
+    pub evicted: Vec<TextureId>,
+}
+
+/// The images uploaded to the GPU, kept across paints until the bytes
+/// of the images exceed the memory budget. The least recently painted
+/// images are evicted first, but never the ones of the current frame.
+/// Small images share the atlas, the others have their own texture.
+pub struct TextureCache {
+    entries: HashMap<TextureKey, Entry>,
+    atlas: ShelfAllocator,
+    budget: usize,
+    used: usize,
+    frame: u64,
+    next_texture: TextureId,
+}
+
+impl TextureCache {
+    pub fn new(atlas_size: u32, budget: usize) -> Self {
+        Self {
+            entries: HashMap::new(),
+            atlas: ShelfAllocator::new(atlas_size),
+            budget,
+            used: 0,
+            frame: 0,
+            next_texture: ATLAS + 1,
+        }
+    }
+
+    /// Start a new frame, the images of the previous frames can be evicted
+    pub fn next_frame(&mut self) {
+        self.frame += 1;
+    }
+
+    /// The texture of a cached image, marked as used by the current frame
+    pub fn get(&mut self, key: &TextureKey) -> Option<CachedTexture> {
+        let entry = self.entries.get_mut(key)?;
+        entry.last_used = self.frame;
+        Some(entry.texture)
+    }
+
+    /// Make room for an image of a size, evicting the least recently
+    /// used images over the budget
+    pub fn allocate(&mut self, key: TextureKey, width: u32, height: u32) -> Allocation {
+        let bytes = image_bytes(width, height);
+        let mut evicted = Vec::new();
+        while self.used + bytes > self.budget {
+            match self.least_recently_used(|_| true) {
+                Some(key) => evicted.extend(self.remove(&key)),
+                None => break,
+            }
+        }
+
+        let fits_atlas = width <= MAX_ATLAS_IMAGE_SIZE && height <= MAX_ATLAS_IMAGE_SIZE;
+        let mut region = None;
+        while fits_atlas && region.is_none() {
+            region = self.atlas.allocate(width, height);
+            if region.is_some() {
+                break;
+            }
+            // the atlas is full of the images of the current frame
+            match self.least_recently_used(|entry| entry.texture.texture == ATLAS) {
+                Some(key) => {
+                    self.remove(&key);
+                }
+                None => break,
+            }
+        }
+
+        let (texture, is_new_texture) = match region {
+            Some(region) => (
+                CachedTexture {
+                    texture: ATLAS,
+                    region,
+                },
+                false,
+            ),
+            None => {
+                let texture = CachedTexture {
+                    texture: self.next_texture,
+                    region: (0, 0, width, height),
+                };
+                self.next_texture += 1;
+                (texture, true)
+            }
+        };
+
+        self.used += bytes;
+        self.entries.insert(
+            key,
+            Entry {
+                texture,
+                last_used: self.frame,
+            },
+        );
+        Allocation {
+            texture,
+            is_new_texture,
+            evicted,
+        }
+    }
+
+    fn least_recently_used<F>(&self, filter: F) -> Option<TextureKey>
+    where
+        F: Fn(&Entry) -> bool,
+    {
+        self.entries
+            .iter()
+            .filter(|(_, entry)| entry.last_used < self.frame && filter(entry))
+            .min_by_key(|(_, entry)| entry.last_used)
+            .map(|(key, _)| key.clone())
+    }
+
+    /// Remove an image from the cache. Returns the texture to destroy if
+    /// the image had its own texture.
+    fn remove(&mut self, key: &TextureKey) -> Option<TextureId> {
+        let entry = self.entries.remove(key)?;
+        let (_, _, width, height) = entry.texture.region;
+        self.used -= image_bytes(width, height);
+        if entry.texture.texture == ATLAS {
+            self.atlas.free(entry.texture.region);
+            return None;
+        }
+        Some(entry.texture.texture)
+    }
+}
+
+fn image_bytes(width: u32, height: u32) -> usize {
+    (width * height * 4) as usize
+}
+
+/// Allocates the regions of the atlas in rows of images of similar
+/// heights. The slots of the freed regions are reused by the images
+/// fitting in them.
+#[derive(Debug)]
+struct ShelfAllocator {
+    size: u32,
+    shelves: Vec<Shelf>,
+    /// The top of the space below the shelves
+    next_y: u32,
+}
+
+#[derive(Debug)]
+struct Shelf {
+    y: u32,
+    height: u32,
+    /// The slots as (x, width, is_free)
+    slots: Vec<(u32, u32, bool)>,
+    /// The left of the space right of the slots
+    next_x: u32,
+}
+
+impl ShelfAllocator {
+    fn new(size: u32) -> Self {
+        Self {
+            size,
+            shelves: Vec::new(),
+            next_y: 0,
+        }
+    }
+
+    fn allocate(&mut self, width: u32, height: u32) -> Option<Region> {
+        if width > self.size || height > self.size {
+            return None;
+        }
+        // images much smaller than a shelf would waste its height
+        let fits = |shelf: &Shelf| height <= shelf.height && height * 2 > shelf.height;
+
+        for shelf in self.shelves.iter_mut().filter(|shelf| fits(shelf)) {
+            let free_slot = shelf
+                .slots
+                .iter_mut()
+                .find(|(_, slot_width, is_free)| *is_free && width <= *slot_width);
+            if let Some((x, _, is_free)) = free_slot {
+                *is_free = false;
+                return Some((*x, shelf.y, width, height));
+            }
+        }
+
+        for shelf in self.shelves.iter_mut().filter(|shelf| fits(shelf)) {
+            if shelf.next_x + width <= self.size {
+                let x = shelf.next_x;
+                shelf.slots.push((x, width, false));
+                shelf.next_x += width;
+                return Some((x, shelf.y, width, height));
+            }
+        }
+
+        if self.next_y + height > self.size {
+            return None;
+        }
+        let y = self.next_y;
+        self.next_y += height;
+        self.shelves.push(Shelf {
+            y,
+            height,
+            slots: vec![(0, width, false)],
+            next_x: width,
+        });
+        Some((0, y, width, height))
+    }
+
+    fn free(&mut self, region: Region) {
+        let (x, y, _, _) = region;
+        let slot = self
+            .shelves
+            .iter_mut()
+            .find(|shelf| shelf.y == y)
+            .and_then(|shelf| shelf.slots.iter_mut().find(|(slot_x, ..)| *slot_x == x));
+        if let Some((_, _, is_free)) = slot {
+            *is_free = true;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn key(url: &str) -> TextureKey {
+        TextureKey::new(Some(url), 0, 0, &[])
+    }
+
+    #[test]
+    fn allocate_shelves() {
+        let mut atlas = ShelfAllocator::new(100);
+        assert_eq!(atlas.allocate(60, 20), Some((0, 0, 60, 20)));
+        assert_eq!(atlas.allocate(30, 15), Some((60, 0, 30, 15)));
+        // too wide for the first shelf
+        assert_eq!(atlas.allocate(20, 20), Some((0, 20, 20, 20)));
+        // too small for the height of the shelves
+        assert_eq!(atlas.allocate(10, 5), Some((0, 40, 10, 5)));
+        assert_eq!(atlas.allocate(10, 80), None);
+
+        atlas.free((60, 0, 30, 15));
+        assert_eq!(atlas.allocate(25, 18), Some((60, 0, 25, 18)));
+    }
+
+    #[test]
+    fn evict_least_recently_used_images() {
+        // the budget fits three images of 10x10
+        let mut cache = TextureCache::new(1024, 1200);
+        let first = cache.allocate(key("first"), 10, 10);
+        assert_eq!(first.texture.texture, ATLAS);
+        assert!(!first.is_new_texture);
+        cache.allocate(key("second"), 10, 10);
+        // the images of the current frame are never evicted
+        let large = cache.allocate(key("large"), 300, 1);
+        assert!(large.is_new_texture);
+        assert_eq!(large.evicted, Vec::<TextureId>::new());
+        assert_eq!(cache.used, 2000);
+
+        cache.next_frame();
+        assert!(cache.get(&key("first")).is_some());
+        assert!(cache.get(&key("second")).is_some());
+        let third = cache.allocate(key("third"), 10, 10);
+        assert_eq!(third.evicted, vec![large.texture.texture]);
+        assert!(cache.get(&key("large")).is_none());
+
+        // the region of an evicted image is reused
+        cache.next_frame();
+        cache.get(&key("first"));
+        cache.get(&key("third"));
+        let second = cache.get(&key("second")).unwrap();
+        cache.next_frame();
+        cache.get(&key("first"));
+        cache.get(&key("third"));
+        let fourth = cache.allocate(key("fourth"), 10, 10);
+        assert_eq!(fourth.evicted, Vec::<TextureId>::new());
+        assert_eq!(fourth.texture, second);
+        assert!(cache.get(&key("second")).is_none());
+        assert_eq!(cache.used, 1200);
+    }
+}
@@ -0,0 +1,60 @@
+use super::Bitmap;
+
+/// The tiles covering a bitmap too large for the frame of the painter,
+/// as (x, y, width, height). The tiles are at most `max_dimension` wide
+/// and tall, row by row from the top left corner.
+pub fn tiles(size: (u32, u32), max_dimension: u32) -> Vec<(u32, u32, u32, u32)> {
+    let (width, height) = size;
+    let max_dimension = max_dimension.max(1);
+    let mut tiles = Vec::new();
+    for y in (0..height).step_by(max_dimension as usize) {
+        for x in (0..width).step_by(max_dimension as usize) {
+            tiles.push((
+                x,
+                y,
+                max_dimension.min(width - x),
+                max_dimension.min(height - y),
+            ));
+        }
+    }
+    tiles
+}
+
+/// Copy a painted tile into a tightly packed RGBA bitmap of a width. The
+/// tile is read from the top left corner of a frame of `frame_width`,
+/// which can be wider than the tile at the edges of the bitmap.
+pub fn stitch(
+    bitmap: &mut Bitmap,
+    width: u32,
+    frame: &[u8],
+    frame_width: u32,
+    tile: (u32, u32, u32, u32),
+) {
+    let (x, y, tile_width, tile_height) = tile;
+    let row_size = (tile_width * 4) as usize;
+    for row in 0..tile_height as usize {
+        let source = row * (frame_width * 4) as usize;
+        let target = ((y as usize + row) * width as usize + x as usize) * 4;
+        bitmap[target..target + row_size].copy_from_slice(&frame[source..source + row_size]);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stitch_tiles() {
+        assert_eq!(tiles((5, 3), 4), vec![(0, 0, 4, 3), (4, 0, 1, 3)]);
+        assert_eq!(tiles((2, 2), 4), vec![(0, 0, 2, 2)]);
+        assert_eq!(tiles((0, 2), 4), vec![]);
+
+        let mut bitmap = vec![0; 3 * 2 * 4];
+        let frame = (1..=16).collect::<Vec<u8>>();
+        // the left column of a 2x2 frame is the right column of the bitmap
+        stitch(&mut bitmap, 3, &frame, 2, (2, 0, 1, 2));
+        assert_eq!(&bitmap[8..12], &[1, 2, 3, 4]);
+        assert_eq!(&bitmap[20..24], &[9, 10, 11, 12]);
+        assert_eq!(&bitmap[..8], &[0; 8]);
+    }
+}
@@ -1,94 +1,136 @@
+use crate::buffer::Buffer;
+use crate::color_space::linear_color;
 use bytemuck::{Pod, Zeroable};
-use lyon_tessellation::{FillVertex, FillVertexConstructor, VertexBuffers};
+use lyon_tessellation::{
+    FillVertex, FillVertexConstructor, StrokeVertex, StrokeVertexConstructor, VertexBuffers,
+};
 use std::borrow::Cow;
 use ultraviolet as uv;
 
 const VERTEX_BUFFER_SIZE: usize = 10_000;
 const INDEX_BUFFER_SIZE: usize = 10_000;
-const UNIFORM_BUFFER_SIZE: usize = 50;
 
 const INDEX_FORMAT: wgpu::IndexFormat = wgpu::IndexFormat::Uint16;
 pub type Index = u16;
 
 #[repr(C)]
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct Vertex {
     pub pos: uv::Vec2,
     pub color: uv::Vec4,
 }
 
-#[repr(C)]
-#[derive(Clone, Copy)]
-pub struct Uniforms {
-    pub screen_size: uv::Vec2,
-}
-
 unsafe impl Pod for Vertex {}
 unsafe impl Zeroable for Vertex {}
 
-unsafe impl Pod for Uniforms {}
-unsafe impl Zeroable for Uniforms {}
-
-#[derive(Debug)]
-struct Buffer<T> {
-    label: &'static str,
-    raw: wgpu::Buffer,
-    size: usize,
-    usage: wgpu::BufferUsage,
-    _type: std::marker::PhantomData<T>,
-}
-
 pub struct Pipeline {
     pipeline: wgpu::RenderPipeline,
     vertex_buffer: Buffer<Vertex>,
     index_buffer: Buffer<Index>,
-    constants: wgpu::BindGroup,
-    uniforms_buffer: Buffer<Uniforms>,
+    /// The meshes currently in the vertex & index buffers
+    uploaded_meshes: MeshCache,
+    /// Vertex offset, index offset & number of indices of each prepared mesh
+    offsets: Vec<(wgpu::BufferAddress, wgpu::BufferAddress, usize)>,
 }
 
-impl<T> Buffer<T> {
-    pub fn new(
-        label: &'static str,
-        device: &wgpu::Device,
-        size: usize,
-        usage: wgpu::BufferUsage,
-    ) -> Self {
-        let raw = device.create_buffer(&wgpu::BufferDescriptor {
-            label: Some(label),
-            size: (std::mem::size_of::<T>() * size) as u64,
-            usage,
-            mapped_at_creation: false,
-        });
-
-        Buffer {
-            label,
-            raw,
-            size,
-            usage,
-            _type: std::marker::PhantomData,
-        }
-    }
+/// A mesh retained in the GPU buffers across frames so it's only
+/// uploaded again when it changes or moves in the buffers
+struct UploadedMesh {
+    vertices: Vec<Vertex>,
+    indices: Vec<Index>,
+    vertex_offset: usize,
+    index_offset: usize,
+}
 
-    pub fn expand(&mut self, device: &wgpu::Device, size: usize) -> bool {
-        let needs_resize = self.size < size;
+/// Where a mesh of a frame is in the vertex & index buffers
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Placement {
+    vertex_offset: usize,
+    index_offset: usize,
+    /// Whether the mesh isn't in the buffers yet
+    upload: bool,
+}
 
-        if needs_resize {
-            self.raw = device.create_buffer(&wgpu::BufferDescriptor {
-                label: Some(self.label),
-                size: (std::mem::size_of::<T>() * size) as u64,
-                usage: self.usage,
-                mapped_at_creation: false,
-            });
+/// The meshes in the vertex & index buffers, in draw order. The meshes
+/// past the ones of the last frame are evicted.
+#[derive(Default)]
+struct MeshCache {
+    meshes: Vec<UploadedMesh>,
+}
 
-            self.size = size;
+impl MeshCache {
+    /// Lay the meshes of a frame out one after the other in the buffers,
+    /// reusing the meshes already uploaded at the same place. The empty
+    /// meshes aren't uploaded.
+    fn place(
+        &mut self,
+        triangles: &[VertexBuffers<Vertex, Index>],
+        buffers_resized: bool,
+    ) -> Vec<Placement> {
+        // new buffers don't contain anything yet
+        if buffers_resized {
+            self.meshes.clear();
         }
+        self.meshes.truncate(triangles.len());
 
-        needs_resize
+        let mut last_vertex = 0;
+        let mut last_index = 0;
+        // the padding of odd index lists overwrites the first index of the next mesh
+        let mut next_index_overwritten = false;
+
+        let mut placements = Vec::with_capacity(triangles.len());
+        for (mesh_index, buffers) in triangles.iter().enumerate() {
+            let mut placement = Placement {
+                vertex_offset: last_vertex,
+                index_offset: last_index,
+                upload: false,
+            };
+            if buffers.vertices.is_empty() || buffers.indices.is_empty() {
+                placements.push(placement);
+                continue;
+            }
+            last_vertex += buffers.vertices.len();
+            last_index += buffers.indices.len();
+
+            let is_uploaded = !next_index_overwritten
+                && match self.meshes.get(mesh_index) {
+                    Some(mesh) => {
+                        mesh.vertex_offset == placement.vertex_offset
+                            && mesh.index_offset == placement.index_offset
+                            && mesh.vertices == buffers.vertices
+                            && mesh.indices == buffers.indices
+                    }
+                    None => false,
+                };
+            if !is_uploaded {
+                let mesh = UploadedMesh {
+                    vertices: buffers.vertices.clone(),
+                    indices: buffers.indices.clone(),
+                    vertex_offset: placement.vertex_offset,
+                    index_offset: placement.index_offset,
+                };
+                if mesh_index < self.meshes.len() {
+                    self.meshes[mesh_index] = mesh;
+                } else {
+                    self.meshes.push(mesh);
+                }
+                // the indices are padded to 4 bytes (COPY_BUFFER_ALIGNMENT)
+                next_index_overwritten = buffers.indices.len() % 2 == 1;
+                placement.upload = true;
+            }
+            placements.push(placement);
+        }
+        placements
     }
 }
 
 impl Pipeline {
-    pub fn new(device: &wgpu::Device, format: wgpu::TextureFormat) -> Self {
+    pub fn new(
+        device: &wgpu::Device,
+        format: wgpu::TextureFormat,
+        sample_count: u32,
+        constants_layout: &wgpu::BindGroupLayout,
+    ) -> Self {
         let shader = device.create_shader_module(&wgpu::ShaderModuleDescriptor {
             label: Some("triangle shader"),
             source: wgpu::ShaderSource::Wgsl(Cow::Borrowed(include_str!(concat!(
@@ -98,43 +140,9 @@ impl Pipeline {
             flags: wgpu::ShaderFlags::default(),
         });
 
-        let constants_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
-            label: Some("moon::gfx::triangle uniforms layout"),
-            entries: &[wgpu::BindGroupLayoutEntry {
-                binding: 0,
-                visibility: wgpu::ShaderStage::VERTEX,
-                ty: wgpu::BindingType::Buffer {
-                    ty: wgpu::BufferBindingType::Uniform,
-                    has_dynamic_offset: false,
-                    min_binding_size: None,
-                },
-                count: None,
-            }],
-        });
-
-        let constants_buffer = Buffer::new(
-            "moon::gfx::triangle uniforms buffer",
-            device,
-            UNIFORM_BUFFER_SIZE,
-            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
-        );
-
-        let constant_bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("iced_wgpu::triangle uniforms bind group"),
-            layout: &constants_layout,
-            entries: &[wgpu::BindGroupEntry {
-                binding: 0,
-                resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
-                    buffer: &constants_buffer.raw,
-                    offset: 0,
-                    size: wgpu::BufferSize::new(std::mem::size_of::<Uniforms>() as u64),
-                }),
-            }],
-        });
-
         let layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
             label: Some("moon::gfx::triangle pipeline layout"),
-            bind_group_layouts: &[&constants_layout],
+            bind_group_layouts: &[constants_layout],
             push_constant_ranges: &[],
         });
 
@@ -183,7 +191,7 @@ impl Pipeline {
             },
             depth_stencil: None,
             multisample: wgpu::MultisampleState {
-                count: 1,
+                count: sample_count,
                 mask: !0,
                 alpha_to_coverage_enabled: false,
             },
@@ -191,8 +199,6 @@ impl Pipeline {
 
         Self {
             pipeline,
-            constants: constant_bind_group,
-            uniforms_buffer: constants_buffer,
             vertex_buffer: Buffer::new(
                 "moon::gfx::triangle vertex buffer",
                 device,
@@ -205,17 +211,23 @@ impl Pipeline {
                 INDEX_BUFFER_SIZE,
                 wgpu::BufferUsage::INDEX | wgpu::BufferUsage::COPY_DST,
             ),
+            uploaded_meshes: MeshCache::default(),
+            offsets: Vec::new(),
         }
     }
 
-    pub fn draw(
+    /// The bytes of the vertex & index buffers
+    pub fn buffer_size(&self) -> usize {
+        self.vertex_buffer.byte_size() + self.index_buffer.byte_size()
+    }
+
+    /// Upload the meshes to draw in the next render pass
+    pub fn prepare(
         &mut self,
         device: &wgpu::Device,
         encoder: &mut wgpu::CommandEncoder,
         staging_belt: &mut wgpu::util::StagingBelt,
         triangles: &[VertexBuffers<Vertex, Index>],
-        target: &wgpu::TextureView,
-        size: (u32, u32),
     ) {
         let (total_vertices, total_indices) = triangles
             .iter()
@@ -226,108 +238,76 @@ impl Pipeline {
 
         // Then we ensure the current buffers are big enough, resizing if
         // necessary
-        self.vertex_buffer.expand(device, total_vertices);
-        self.index_buffer.expand(device, total_indices);
-
-        let mut offsets: Vec<(wgpu::BufferAddress, wgpu::BufferAddress, usize)> =
-            Vec::with_capacity(triangles.len());
-
-        let mut last_vertex = 0;
-        let mut last_index = 0;
-
-        for buffers in triangles {
-            let vertices = bytemuck::cast_slice(&buffers.vertices);
-
-            // Align indices by 4 (COPY_BUFFER_ALIGNMENT)
-            let indices_size = (buffers.indices.len() * 2) as f32;
-            let indices_buffer_capacity = (4 * (indices_size / 4.).ceil() as usize) / 2;
-
-            let mut indices_buffer = Vec::from(buffers.indices.as_slice());
-            indices_buffer.resize(indices_buffer_capacity, 0);
-
-            let indices = bytemuck::cast_slice(&indices_buffer);
-
-            match (
-                wgpu::BufferSize::new(vertices.len() as u64),
-                wgpu::BufferSize::new(indices.len() as u64),
-            ) {
-                (Some(vertices_size), Some(indices_size)) => {
-                    {
-                        let mut vertex_buffer = staging_belt.write_buffer(
+        let vertex_buffer_resized = self.vertex_buffer.expand(device, total_vertices);
+        let index_buffer_resized = self.index_buffer.expand(device, total_indices);
+
+        let placements = self
+            .uploaded_meshes
+            .place(triangles, vertex_buffer_resized || index_buffer_resized);
+
+        self.offsets.clear();
+        for (buffers, placement) in triangles.iter().zip(placements) {
+            let (vertex_offset, index_offset) = (placement.vertex_offset, placement.index_offset);
+            if placement.upload {
+                let vertices = bytemuck::cast_slice(&buffers.vertices);
+
+                // Align indices by 4 (COPY_BUFFER_ALIGNMENT)
+                let mut indices_buffer = Vec::from(buffers.indices.as_slice());
+                indices_buffer.resize((buffers.indices.len() + 1) / 2 * 2, 0);
+                let indices = bytemuck::cast_slice(&indices_buffer);
+
+                if let (Some(vertices_size), Some(indices_size)) = (
+                    wgpu::BufferSize::new(vertices.len() as u64),
+                    wgpu::BufferSize::new(indices.len() as u64),
+                ) {
+                    staging_belt
+                        .write_buffer(
                             encoder,
                             &self.vertex_buffer.raw,
-                            (std::mem::size_of::<Vertex>() * last_vertex) as u64,
+                            (std::mem::size_of::<Vertex>() * vertex_offset) as u64,
                             vertices_size,
                             device,
-                        );
-
-                        vertex_buffer.copy_from_slice(vertices);
-                    }
-                    {
-                        let mut index_buffer = staging_belt.write_buffer(
+                        )
+                        .copy_from_slice(vertices);
+                    staging_belt
+                        .write_buffer(
                             encoder,
                             &self.index_buffer.raw,
-                            (std::mem::size_of::<Index>() * last_index) as u64,
+                            (std::mem::size_of::<Index>() * index_offset) as u64,
                             indices_size,
                             device,
-                        );
-
-                        index_buffer.copy_from_slice(indices);
-                    }
-
-                    offsets.push((last_vertex as u64, last_index as u64, buffers.indices.len()));
-
-                    last_vertex += buffers.vertices.len();
-                    last_index += buffers.indices.len();
+                        )
+                        .copy_from_slice(indices);
                 }
-                _ => {}
             }
+            // the empty meshes aren't drawn
+            let indices = if buffers.vertices.is_empty() {
+                0
+            } else {
+                buffers.indices.len()
+            };
+            self.offsets
+                .push((vertex_offset as u64, index_offset as u64, indices));
         }
+    }
 
-        let uniforms = [Uniforms {
-            screen_size: uv::Vec2::new(size.0 as f32, size.1 as f32),
-        }];
-
-        let uniforms = bytemuck::cast_slice(&uniforms);
-
-        if let Some(uniforms_size) = wgpu::BufferSize::new(uniforms.len() as u64) {
-            let mut uniforms_buffer = staging_belt.write_buffer(
-                encoder,
-                &self.uniforms_buffer.raw,
-                0,
-                uniforms_size,
-                device,
-            );
-
-            uniforms_buffer.copy_from_slice(uniforms);
-        }
+    /// Draw a prepared mesh
+    pub fn render<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, mesh_index: usize) {
+        let (vertex_offset, index_offset, indices) = match self.offsets.get(mesh_index) {
+            Some(&(_, _, 0)) | None => return,
+            Some(offset) => *offset,
+        };
 
-        let mut render_pass = encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
-            label: Some("moon::gfx::triangle renderpass"),
-            color_attachments: &[wgpu::RenderPassColorAttachment {
-                view: target,
-                resolve_target: None,
-                ops: wgpu::Operations {
-                    load: wgpu::LoadOp::Load,
-                    store: true,
-                },
-            }],
-            depth_stencil_attachment: None,
-        });
+        let start_index = index_offset * std::mem::size_of::<Index>() as u64;
+        let start_vertex = vertex_offset * std::mem::size_of::<Vertex>() as u64;
 
         render_pass.set_pipeline(&self.pipeline);
-        render_pass.set_bind_group(0, &self.constants, &[]);
-
-        for (vertex_offset, index_offset, indices) in offsets {
-            let start_index = index_offset * std::mem::size_of::<Index>() as u64;
-            let start_vertex = vertex_offset * std::mem::size_of::<Vertex>() as u64;
 
-            render_pass.set_index_buffer(self.index_buffer.raw.slice(start_index..), INDEX_FORMAT);
+        render_pass.set_index_buffer(self.index_buffer.raw.slice(start_index..), INDEX_FORMAT);
 
-            render_pass.set_vertex_buffer(0, self.vertex_buffer.raw.slice(start_vertex..));
+        render_pass.set_vertex_buffer(0, self.vertex_buffer.raw.slice(start_vertex..));
 
-            render_pass.draw_indexed(0..indices as u32, 0, 0..1);
-        }
+        render_pass.draw_indexed(0..indices as u32, 0, 0..1);
     }
 }
 
@@ -339,12 +319,77 @@ impl FillVertexConstructor<Vertex> for VertexConstructor {
         let attrs = vertex.interpolated_attributes();
         Vertex {
             pos: uv::Vec2::from(position),
-            color: uv::Vec4::from([
-                attrs[0] / 255.0,
-                attrs[1] / 255.0,
-                attrs[2] / 255.0,
-                attrs[3] / 255.0,
-            ]),
+            color: linear_color(attrs[0], attrs[1], attrs[2], attrs[3]),
+        }
+    }
+}
+
+impl StrokeVertexConstructor<Vertex> for VertexConstructor {
+    fn new_vertex(&mut self, mut vertex: StrokeVertex) -> Vertex {
+        let position = vertex.position().to_array();
+        let attrs = vertex.interpolated_attributes();
+        Vertex {
+            pos: uv::Vec2::from(position),
+            color: linear_color(attrs[0], attrs[1], attrs[2], attrs[3]),
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn mesh(x: f32, index_count: usize) -> VertexBuffers<Vertex, Index> {
+        let mut buffers = VertexBuffers::new();
+        buffers.vertices = vec![
+            Vertex {
+                pos: uv::Vec2::new(x, 0.),
+                color: uv::Vec4::one(),
+            };
+            3
+        ];
+        buffers.indices = (0..index_count as Index).map(|index| index % 3).collect();
+        buffers
+    }
+
+    fn uploads(placements: &[Placement]) -> Vec<bool> {
+        placements
+            .iter()
+            .map(|placement| placement.upload)
+            .collect()
+    }
+
+    #[test]
+    fn retain_uploaded_meshes() {
+        let mut cache = MeshCache::default();
+        let frame = [mesh(0., 3), mesh(1., 6)];
+        let placements = cache.place(&frame, false);
+        assert_eq!(uploads(&placements), [true, true]);
+        assert_eq!(
+            (placements[1].vertex_offset, placements[1].index_offset),
+            (3, 3)
+        );
+
+        // the meshes of the next frame are already in the buffers
+        assert_eq!(uploads(&cache.place(&frame, false)), [false, false]);
+        assert_eq!(uploads(&cache.place(&frame, true)), [true, true]);
+
+        // the padding of the odd indices of a changed mesh overwrites the next one
+        assert_eq!(
+            uploads(&cache.place(&[mesh(2., 3), mesh(1., 6)], false)),
+            [true, true]
+        );
+        assert_eq!(
+            uploads(&cache.place(&[mesh(2., 3), mesh(3., 6)], false)),
+            [false, true]
+        );
+
+        // the meshes no longer drawn are evicted
+        cache.place(&[mesh(2., 3)], false);
+        assert_eq!(cache.meshes.len(), 1);
+        assert_eq!(
+            uploads(&cache.place(&[mesh(2., 3), mesh(3., 6)], false)),
+            [false, true]
+        );
+    }
+}
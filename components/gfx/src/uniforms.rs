@@ -0,0 +1,159 @@
+use crate::buffer::Buffer;
+use bytemuck::{Pod, Zeroable};
+use painting::Transform;
+use ultraviolet as uv;
+
+const UNIFORM_BUFFER_SIZE: usize = 16;
+
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Uniforms {
+    transform: uv::Mat4,
+    screen_size: uv::Vec2,
+    _padding: uv::Vec2,
+}
+
+/// Uniforms of a transform, padded so each slot can be bound with a dynamic offset
+#[repr(C)]
+#[derive(Clone, Copy)]
+struct Slot {
+    uniforms: Uniforms,
+    _padding: [u8; SLOT_PADDING],
+}
+
+const SLOT_PADDING: usize = wgpu::BIND_BUFFER_ALIGNMENT as usize - std::mem::size_of::<Uniforms>();
+
+unsafe impl Pod for Slot {}
+unsafe impl Zeroable for Slot {}
+
+/// The uniforms shared by the pipelines. Every transform used in a frame
+/// has its own slot in the uniforms buffer, which is selected when drawing.
+pub struct Constants {
+    layout: wgpu::BindGroupLayout,
+    buffer: Buffer<Slot>,
+    bind_group: wgpu::BindGroup,
+    /// The transforms & screen size currently in the uniforms buffer
+    uploaded: Option<(Vec<Transform>, (u32, u32))>,
+}
+
+impl Constants {
+    pub fn new(device: &wgpu::Device) -> Self {
+        let layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+            label: Some("moon::gfx uniforms layout"),
+            entries: &[wgpu::BindGroupLayoutEntry {
+                binding: 0,
+                visibility: wgpu::ShaderStage::VERTEX,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: true,
+                    min_binding_size: wgpu::BufferSize::new(std::mem::size_of::<Uniforms>() as u64),
+                },
+                count: None,
+            }],
+        });
+
+        let buffer = Buffer::new(
+            "moon::gfx uniforms buffer",
+            device,
+            UNIFORM_BUFFER_SIZE,
+            wgpu::BufferUsage::UNIFORM | wgpu::BufferUsage::COPY_DST,
+        );
+        let bind_group = create_bind_group(device, &layout, &buffer);
+
+        Self {
+            layout,
+            buffer,
+            bind_group,
+            uploaded: None,
+        }
+    }
+
+    pub fn layout(&self) -> &wgpu::BindGroupLayout {
+        &self.layout
+    }
+
+    pub fn buffer_size(&self) -> usize {
+        self.buffer.byte_size()
+    }
+
+    /// Upload the transforms to draw with in the next render pass
+    pub fn prepare(
+        &mut self,
+        device: &wgpu::Device,
+        encoder: &mut wgpu::CommandEncoder,
+        staging_belt: &mut wgpu::util::StagingBelt,
+        transforms: &[Transform],
+        size: (u32, u32),
+    ) {
+        if self.buffer.expand(device, transforms.len()) {
+            self.bind_group = create_bind_group(device, &self.layout, &self.buffer);
+            self.uploaded = None;
+        }
+
+        let is_uploaded = match &self.uploaded {
+            Some((uploaded_transforms, uploaded_size)) => {
+                uploaded_transforms.as_slice() == transforms && *uploaded_size == size
+            }
+            None => false,
+        };
+        if is_uploaded {
+            return;
+        }
+
+        let slots = transforms
+            .iter()
+            .map(|transform| Slot {
+                uniforms: Uniforms {
+                    transform: to_matrix(transform),
+                    screen_size: uv::Vec2::new(size.0 as f32, size.1 as f32),
+                    _padding: uv::Vec2::zero(),
+                },
+                _padding: [0; SLOT_PADDING],
+            })
+            .collect::<Vec<Slot>>();
+
+        let slots = bytemuck::cast_slice(&slots);
+
+        if let Some(slots_size) = wgpu::BufferSize::new(slots.len() as u64) {
+            let mut uniforms_buffer =
+                staging_belt.write_buffer(encoder, &self.buffer.raw, 0, slots_size, device);
+
+            uniforms_buffer.copy_from_slice(slots);
+        }
+        self.uploaded = Some((transforms.to_vec(), size));
+    }
+
+    /// Use the uniforms of a prepared transform for the next draw calls
+    pub fn bind<'a>(&'a self, render_pass: &mut wgpu::RenderPass<'a>, transform_index: usize) {
+        let offset = transform_index * std::mem::size_of::<Slot>();
+        render_pass.set_bind_group(0, &self.bind_group, &[offset as wgpu::DynamicOffset]);
+    }
+}
+
+fn create_bind_group(
+    device: &wgpu::Device,
+    layout: &wgpu::BindGroupLayout,
+    buffer: &Buffer<Slot>,
+) -> wgpu::BindGroup {
+    device.create_bind_group(&wgpu::BindGroupDescriptor {
+        label: Some("moon::gfx uniforms bind group"),
+        layout,
+        entries: &[wgpu::BindGroupEntry {
+            binding: 0,
+            resource: wgpu::BindingResource::Buffer(wgpu::BufferBinding {
+                buffer: &buffer.raw,
+                offset: 0,
+                size: wgpu::BufferSize::new(std::mem::size_of::<Uniforms>() as u64),
+            }),
+        }],
+    })
+}
+
+fn to_matrix(transform: &Transform) -> uv::Mat4 {
+    uv::Mat4::new(
+        uv::Vec4::new(transform.a, transform.b, 0.0, 0.0),
+        uv::Vec4::new(transform.c, transform.d, 0.0, 0.0),
+        uv::Vec4::new(0.0, 0.0, 1.0, 0.0),
+        uv::Vec4::new(transform.e, transform.f, 0.0, 1.0),
+    )
+}
@@ -0,0 +1,323 @@
+use super::backend::{Backend, DrawRequest};
+use super::color_space::ColorSpace;
+use super::gpu_backend::{find_adapter, GpuBackend};
+use super::pixel_format::{depad, padded_bytes_per_row};
+use super::{Antialiasing, Bitmap};
+use crate::painters::rect::RectPainter;
+use futures::task::SpawnExt;
+use painting::{Color, Image, RRect, Rect, Transform};
+
+/// A painter drawing the shapes with wgpu into a texture read back into
+/// a bitmap
+pub struct WgpuPainter<'a> {
+    rect_painter: RectPainter,
+    backend: Backend,
+    device: wgpu::Device,
+    queue: wgpu::Queue,
+    staging_belt: wgpu::util::StagingBelt,
+    local_pool: futures::executor::LocalPool,
+    frame_desc: wgpu::TextureDescriptor<'a>,
+    frame: wgpu::Texture,
+    frame_texture_view: wgpu::TextureView,
+    /// Multisampled texture that is resolved into the frame when MSAA is enabled
+    msaa_frame_texture_view: Option<wgpu::TextureView>,
+    sample_count: u32,
+    output_buffer: wgpu::Buffer,
+    output_buffer_desc: wgpu::BufferDescriptor<'a>,
+}
+
+impl<'a> WgpuPainter<'a> {
+    const CHUNK_SIZE: u64 = 10 * 1024;
+
+    /// Create a painter on the best adapter of a backend, or of the
+    /// primary backends with the GL fallback when none is given. Fails
+    /// when the machine has no adapter, e.g. on headless CI boxes.
+    pub async fn new(
+        antialiasing: Antialiasing,
+        backend: Option<GpuBackend>,
+    ) -> Result<WgpuPainter<'a>, String> {
+        let adapter = find_adapter(backend)?;
+
+        let info = adapter.get_info();
+        log::debug!("Painting on {} ({:?})", info.name, info.backend);
+
+        let (device, queue) = adapter
+            .request_device(&Default::default(), None)
+            .await
+            .map_err(|e| format!("{} on {}", e, info.name))?;
+
+        let staging_belt = wgpu::util::StagingBelt::new(Self::CHUNK_SIZE);
+        let local_pool = futures::executor::LocalPool::new();
+
+        let frame_desc = wgpu::TextureDescriptor {
+            label: Some("moon output texture"),
+            size: wgpu::Extent3d {
+                width: 1,
+                height: 1,
+                depth_or_array_layers: 1,
+            },
+            mip_level_count: 1,
+            sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: ColorSpace::default().texture_format(),
+            usage: wgpu::TextureUsage::COPY_SRC | wgpu::TextureUsage::RENDER_ATTACHMENT,
+        };
+
+        let frame = device.create_texture(&frame_desc);
+
+        let frame_texture_view = frame.create_view(&Default::default());
+        let sample_count = antialiasing.sample_count();
+        let msaa_frame_texture_view =
+            create_msaa_frame_texture_view(&device, &frame_desc, sample_count);
+
+        let output_buffer_desc = wgpu::BufferDescriptor {
+            label: Some("moon output buffer"),
+            size: 1,
+            usage: wgpu::BufferUsage::COPY_DST | wgpu::BufferUsage::MAP_READ,
+            mapped_at_creation: false,
+        };
+        let output_buffer = device.create_buffer(&output_buffer_desc);
+
+        Ok(Self {
+            backend: Backend::new(&device, frame_desc.format, sample_count),
+            rect_painter: RectPainter::new(),
+            device,
+            queue,
+            staging_belt,
+            local_pool,
+            frame_desc,
+            frame,
+            frame_texture_view,
+            msaa_frame_texture_view,
+            sample_count,
+            output_buffer,
+            output_buffer_desc,
+        })
+    }
+
+    pub fn resize(&mut self, size: (u32, u32)) {
+        let (width, height) = size;
+        self.frame_desc.size.width = width;
+        self.frame_desc.size.height = height;
+
+        self.output_buffer_desc.size = (self.get_bytes_per_row() * height) as u64;
+
+        self.frame = self.device.create_texture(&self.frame_desc);
+        self.frame_texture_view = self.frame.create_view(&Default::default());
+        self.msaa_frame_texture_view =
+            create_msaa_frame_texture_view(&self.device, &self.frame_desc, self.sample_count);
+        self.output_buffer = self.device.create_buffer(&self.output_buffer_desc);
+    }
+
+    /// Encode the blended colors of the frame into a color space. The
+    /// pipelines writing into the frame are created again for its format.
+    pub fn set_output_color_space(&mut self, color_space: ColorSpace) {
+        let format = color_space.texture_format();
+        if self.frame_desc.format == format {
+            return;
+        }
+        self.frame_desc.format = format;
+        self.backend = Backend::new(&self.device, format, self.sample_count);
+        let size = self.frame_desc.size;
+        self.resize((size.width, size.height));
+    }
+
+    /// The largest width or height the frame can be resized to
+    pub fn max_texture_dimension(&self) -> u32 {
+        self.device.limits().max_texture_dimension_2d
+    }
+
+    /// The approximate bytes of the textures & buffers on the GPU
+    pub fn memory_size(&self) -> usize {
+        let size = self.frame_desc.size;
+        let frame = (size.width * size.height * 4) as usize;
+        let msaa_frame = match self.msaa_frame_texture_view {
+            Some(_) => frame * self.sample_count as usize,
+            None => 0,
+        };
+        frame + msaa_frame + self.output_buffer_desc.size as usize + self.backend.buffer_size()
+    }
+
+    /// Paint the whole frame
+    pub fn paint(&mut self) {
+        self.paint_with_clip(None);
+    }
+
+    /// Paint a region of the frame, keeping the rest of the previous frame
+    pub fn paint_region(&mut self, region: &Rect) {
+        let (width, height) = (self.frame_desc.size.width, self.frame_desc.size.height);
+        let x = region.x.max(0.).floor() as u32;
+        let y = region.y.max(0.).floor() as u32;
+        let right = ((region.x + region.width).ceil().max(0.) as u32).min(width);
+        let bottom = ((region.y + region.height).ceil().max(0.) as u32).min(height);
+
+        if x >= right || y >= bottom {
+            self.rect_painter.clear();
+            return;
+        }
+
+        // the damaged region is cleared by drawing the background below everything
+        let white = Color {
+            r: 255,
+            g: 255,
+            b: 255,
+            a: 255,
+        };
+        self.rect_painter.draw_solid_rect_below(region, &white);
+        self.paint_with_clip(Some((x, y, right - x, bottom - y)));
+    }
+
+    fn paint_with_clip(&mut self, clip: Option<(u32, u32, u32, u32)>) {
+        let request = DrawRequest {
+            triangles: self.rect_painter.vertex_buffers(),
+            instances: self.rect_painter.instances(),
+            image_instances: self.rect_painter.image_instances(),
+            items: self.rect_painter.items(),
+            transforms: self.rect_painter.transforms(),
+            clips: self.rect_painter.clips(),
+            clip,
+        };
+
+        let mut encoder = self
+            .device
+            .create_command_encoder(&wgpu::CommandEncoderDescriptor {
+                label: Some("moon wgpu encoder"),
+            });
+
+        // with MSAA, shapes are drawn into the multisampled texture
+        // and resolved into the frame
+        let (target, resolve_target) = match &self.msaa_frame_texture_view {
+            Some(msaa_view) => (msaa_view, Some(&self.frame_texture_view)),
+            None => (&self.frame_texture_view, None),
+        };
+
+        // Background clear
+        if clip.is_none() {
+            encoder.begin_render_pass(&wgpu::RenderPassDescriptor {
+                label: Some("moon::gfx clear bg render pass"),
+                color_attachments: &[wgpu::RenderPassColorAttachment {
+                    view: target,
+                    resolve_target,
+                    ops: wgpu::Operations {
+                        load: wgpu::LoadOp::Clear(wgpu::Color::WHITE),
+                        store: true,
+                    },
+                }],
+                depth_stencil_attachment: None,
+            });
+        }
+
+        self.backend.draw(
+            &self.device,
+            &mut encoder,
+            &mut self.staging_belt,
+            target,
+            resolve_target,
+            (self.frame_desc.size.width, self.frame_desc.size.height),
+            request,
+        );
+
+        encoder.copy_texture_to_buffer(
+            wgpu::ImageCopyTexture {
+                texture: &self.frame,
+                mip_level: 0,
+                origin: wgpu::Origin3d::ZERO,
+            },
+            wgpu::ImageCopyBuffer {
+                buffer: &self.output_buffer,
+                layout: wgpu::ImageDataLayout {
+                    offset: 0,
+                    bytes_per_row: core::num::NonZeroU32::new(self.get_bytes_per_row()),
+                    rows_per_image: core::num::NonZeroU32::new(self.frame_desc.size.height),
+                },
+            },
+            self.frame_desc.size,
+        );
+
+        self.staging_belt.finish();
+        self.queue.submit(Some(encoder.finish()));
+        self.local_pool
+            .spawner()
+            .spawn(self.staging_belt.recall())
+            .expect("Recall staging belt");
+
+        self.local_pool.run_until_stalled();
+        self.rect_painter.clear();
+        self.backend.image_pipeline().next_frame();
+    }
+
+    fn get_bytes_per_row(&self) -> u32 {
+        padded_bytes_per_row(self.frame_desc.size.width)
+    }
+
+    /// The pixels of the frame as a tightly packed RGBA bitmap, without
+    /// the padding of the rows of the output buffer
+    pub async fn output(&mut self) -> Bitmap {
+        let buffer_slice = self.output_buffer.slice(..);
+
+        // NOTE: We have to create the mapping THEN device.poll() before await
+        // the future. Otherwise the application will freeze.
+        let mapping = buffer_slice.map_async(wgpu::MapMode::Read);
+        self.device.poll(wgpu::Maintain::Wait);
+
+        mapping.await.unwrap();
+
+        let size = self.frame_desc.size;
+        let output = depad(&buffer_slice.get_mapped_range(), size.width, size.height);
+
+        self.output_buffer.unmap();
+
+        output
+    }
+}
+
+fn create_msaa_frame_texture_view(
+    device: &wgpu::Device,
+    frame_desc: &wgpu::TextureDescriptor,
+    sample_count: u32,
+) -> Option<wgpu::TextureView> {
+    if sample_count <= 1 {
+        return None;
+    }
+
+    let texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("moon multisampled texture"),
+        sample_count,
+        usage: wgpu::TextureUsage::RENDER_ATTACHMENT,
+        ..frame_desc.clone()
+    });
+    Some(texture.create_view(&Default::default()))
+}
+
+impl<'a> painting::Painter for WgpuPainter<'a> {
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        self.rect_painter.draw_solid_rect(&rect, &color);
+    }
+
+    fn fill_rrect(&mut self, rect: RRect, color: Color) {
+        self.rect_painter.draw_solid_rrect(&rect, &color);
+    }
+
+    fn stroke_rrect(&mut self, rect: RRect, color: Color, width: f32) {
+        self.rect_painter.draw_stroked_rrect(&rect, &color, width);
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.rect_painter.set_transform(transform);
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.rect_painter.set_clip(clip);
+    }
+
+    fn draw_image(&mut self, rect: Rect, image: &Image) {
+        let uploaded = self
+            .backend
+            .image_pipeline()
+            .upload(&self.device, &self.queue, image);
+        match uploaded {
+            Some((texture, uv)) => self.rect_painter.draw_texture(&rect, texture, uv),
+            None => log::info!("Unable to upload image of {}x{}", image.width, image.height),
+        }
+    }
+}
@@ -0,0 +1,34 @@
+use std::fmt;
+
+/// Errors that can happen while tokenizing or building the DOM tree.
+/// These never abort parsing, the caller decides how to recover.
+#[derive(Debug, Clone, PartialEq)]
+pub enum HtmlError {
+    /// A tag operation was performed on a token that is not a tag
+    NotATag,
+    /// The stack of open elements was empty when a node was required
+    NoCurrentNode,
+}
+
+pub type Result<T> = std::result::Result<T, HtmlError>;
+
+impl HtmlError {
+    /// Short error code, used when reporting through a `ParseErrorSink`
+    pub fn code(&self) -> &'static str {
+        match self {
+            HtmlError::NotATag => "not-a-tag",
+            HtmlError::NoCurrentNode => "no-current-node",
+        }
+    }
+}
+
+impl fmt::Display for HtmlError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HtmlError::NotATag => write!(f, "Token is not a tag"),
+            HtmlError::NoCurrentNode => write!(f, "The stack of open elements is empty"),
+        }
+    }
+}
+
+impl std::error::Error for HtmlError {}
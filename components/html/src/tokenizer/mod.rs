@@ -3,33 +3,13 @@ pub mod token;
 
 use super::entities::ENTITIES;
 use io::input_stream::CharInputStream;
+use io::parse_error::{ParseError, ParseErrorSinkRef};
+use io::position::{SourcePosition, SourceSpan};
 use state::State;
 use std::collections::{HashSet, VecDeque};
-use std::env;
 use token::Attribute;
 use token::Token;
 
-fn is_trace() -> bool {
-    match env::var("TRACE_TOKENIZER") {
-        Ok(s) => s == "true",
-        _ => false,
-    }
-}
-
-macro_rules! trace {
-    ($err:expr) => {
-        println!("[ParseError][Tokenization]: {}", $err);
-    };
-}
-
-macro_rules! emit_error {
-    ($err:expr) => {
-        if is_trace() {
-            trace!($err)
-        }
-    };
-}
-
 fn is_surrogate(n: u32) -> bool {
     match n {
         0xD800..=0xDFFF => true,
@@ -128,12 +108,22 @@ where
     // chars input stream for tokenizer
     input: CharInputStream<T>,
 
-    // A list of tokenized tokens
-    output: VecDeque<Token>,
+    // A list of tokenized tokens & where they are in the source
+    output: VecDeque<(Token, SourceSpan)>,
+
+    // Where the next emitted token starts in the source
+    token_start: SourcePosition,
+
+    // The source span of the last token returned by `next_token`
+    current_token_span: SourceSpan,
 
     // Current consumed character. Might reconsume later
     current_character: char,
 
+    // Whether the last consumed character was the end of the input, so
+    // reconsuming it yields EOF again instead of the previous character
+    current_is_eof: bool,
+
     // The state for tokenizing
     state: State,
 
@@ -154,11 +144,22 @@ where
 
     // Code for a character reference. Example: &#228;
     character_reference_code: u32,
+
+    // Where to report parse errors. Errors are dropped if not set
+    error_sink: Option<ParseErrorSinkRef>,
+
+    // Whether CDATA sections are allowed, which is when the tree builder
+    // is inside an SVG or MathML element
+    allow_cdata: bool,
 }
 
 pub trait Tokenizing {
     fn next_token(&mut self) -> Token;
     fn switch_to(&mut self, state: State);
+    fn set_error_sink(&mut self, sink: ParseErrorSinkRef);
+    fn position(&self) -> SourcePosition;
+    fn current_token_span(&self) -> SourceSpan;
+    fn set_allow_cdata(&mut self, allow: bool);
 }
 
 impl<T> Tokenizing for Tokenizer<T>
@@ -167,7 +168,7 @@ where
 {
     fn next_token(&mut self) -> Token {
         if !self.output.is_empty() {
-            return self.output.pop_front().unwrap();
+            return self.pop_token();
         }
         loop {
             match self.state {
@@ -180,7 +181,7 @@ where
                         }
                         Char::ch('<') => self.switch_to(State::TagOpen),
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             return self.emit_current_char();
                         }
                         Char::eof => return self.emit_eof(),
@@ -196,7 +197,7 @@ where
                         }
                         Char::ch('<') => self.switch_to(State::RCDATALessThanSign),
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => return self.emit_eof(),
@@ -208,7 +209,7 @@ where
                     match ch {
                         Char::ch('<') => self.switch_to(State::RAWTEXTLessThanSign),
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => return self.emit_eof(),
@@ -220,7 +221,7 @@ where
                     match ch {
                         Char::ch('<') => self.switch_to(State::ScriptDataLessThanSign),
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => return self.emit_eof(),
@@ -231,7 +232,7 @@ where
                     let ch = self.consume_next();
                     match ch {
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => return self.emit_eof(),
@@ -248,17 +249,17 @@ where
                             self.reconsume_in(State::TagName);
                         }
                         Char::ch('?') => {
-                            emit_error!("unexpected-question-mark-instead-of-tag-name");
+                            self.emit_error("unexpected-question-mark-instead-of-tag-name");
                             self.new_token(Token::new_comment(""));
                             self.reconsume_in(State::BogusComment);
                         }
                         Char::eof => {
-                            emit_error!("eof-before-tag-name");
+                            self.emit_error("eof-before-tag-name");
                             self.will_emit(Token::Character('<'));
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("invalid-first-character-of-tag-name");
+                            self.emit_error("invalid-first-character-of-tag-name");
                             self.will_emit(Token::Character('<'));
                             self.reconsume_in(State::Data);
                         }
@@ -272,17 +273,17 @@ where
                             self.reconsume_in(State::TagName);
                         }
                         Char::ch('>') => {
-                            emit_error!("missing-end-tag-name");
+                            self.emit_error("missing-end-tag-name");
                             self.switch_to(State::Data);
                         }
                         Char::eof => {
-                            emit_error!("eof-before-tag-name");
+                            self.emit_error("eof-before-tag-name");
                             self.will_emit(Token::Character('<'));
                             self.will_emit(Token::Character('/'));
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("invalid-first-character-of-tag-name");
+                            self.emit_error("invalid-first-character-of-tag-name");
                             self.new_token(Token::new_comment(""));
                             self.reconsume_in(State::BogusComment);
                         }
@@ -305,11 +306,11 @@ where
                             self.append_character_to_tag_name(c.to_ascii_lowercase());
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_tag_name(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-tag");
+                            self.emit_error("eof-in-tag");
                             return self.emit_eof();
                         }
                         _ => {
@@ -586,11 +587,11 @@ where
                             self.switch_to(State::ScriptDataEscapedLessThanSign);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-script-html-comment-like-text");
+                            self.emit_error("eof-in-script-html-comment-like-text");
                             return self.emit_eof();
                         }
                         _ => {
@@ -609,12 +610,12 @@ where
                             self.switch_to(State::ScriptDataEscapedLessThanSign);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.switch_to(State::ScriptDataEscaped);
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-script-html-comment-like-text");
+                            self.emit_error("eof-in-script-html-comment-like-text");
                             return self.emit_eof();
                         }
                         _ => {
@@ -637,12 +638,12 @@ where
                             return self.emit_char('>');
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.switch_to(State::ScriptDataEscaped);
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-script-html-comment-like-text");
+                            self.emit_error("eof-in-script-html-comment-like-text");
                             return self.emit_eof();
                         }
                         _ => {
@@ -769,11 +770,11 @@ where
                             return self.emit_char('<');
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-script-html-comment-like-text");
+                            self.emit_error("eof-in-script-html-comment-like-text");
                             return self.emit_eof();
                         }
                         _ => {
@@ -793,12 +794,12 @@ where
                             return self.emit_char('<');
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.switch_to(State::ScriptDataDoubleEscaped);
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-script-html-comment-like-text");
+                            self.emit_error("eof-in-script-html-comment-like-text");
                             return self.emit_eof();
                         }
                         _ => {
@@ -822,12 +823,12 @@ where
                             return self.emit_char('>');
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.switch_to(State::ScriptDataDoubleEscaped);
                             return self.emit_char(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-script-html-comment-like-text");
+                            self.emit_error("eof-in-script-html-comment-like-text");
                             return self.emit_eof();
                         }
                         _ => {
@@ -881,7 +882,7 @@ where
                             self.reconsume_in(State::AfterAttributeName);
                         }
                         Char::ch('=') => {
-                            emit_error!("unexpected-equals-sign-before-attribute-name");
+                            self.emit_error("unexpected-equals-sign-before-attribute-name");
                             let mut attribute = Attribute::new();
                             attribute.name.push(self.current_character);
                             self.new_attribute(attribute);
@@ -907,11 +908,11 @@ where
                             self.append_character_to_attribute_name(c.to_ascii_lowercase());
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_attribute_name(REPLACEMENT_CHARACTER);
                         }
                         Char::ch('"') | Char::ch('\'') | Char::ch('<') => {
-                            emit_error!("unexpected-character-in-attribute-name");
+                            self.emit_error("unexpected-character-in-attribute-name");
                             self.append_character_to_attribute_name(self.current_character);
                         }
                         _ => {
@@ -934,7 +935,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-tag");
+                            self.emit_error("eof-in-tag");
                             return self.emit_eof();
                         }
                         _ => {
@@ -955,7 +956,7 @@ where
                             self.switch_to(State::AttributeValueSingleQuoted);
                         }
                         Char::ch('>') => {
-                            emit_error!("missing-attribute-value");
+                            self.emit_error("missing-attribute-value");
                             self.switch_to(State::Data);
                             return self.emit_current_token();
                         }
@@ -975,11 +976,11 @@ where
                             self.switch_to(State::CharacterReference);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_attribute_value(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-tag");
+                            self.emit_error("eof-in-tag");
                             return self.emit_eof();
                         }
                         _ => {
@@ -998,11 +999,11 @@ where
                             self.switch_to(State::CharacterReference);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_attribute_value(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-tag");
+                            self.emit_error("eof-in-tag");
                             return self.emit_eof();
                         }
                         _ => {
@@ -1025,7 +1026,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_attribute_value(REPLACEMENT_CHARACTER);
                         }
                         Char::ch('"')
@@ -1033,11 +1034,11 @@ where
                         | Char::ch('<')
                         | Char::ch('=')
                         | Char::ch('`') => {
-                            emit_error!("unexpected-character-in-unquoted-attribute-value");
+                            self.emit_error("unexpected-character-in-unquoted-attribute-value");
                             self.append_character_to_attribute_value(self.current_character);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-tag");
+                            self.emit_error("eof-in-tag");
                             return self.emit_eof();
                         }
                         _ => {
@@ -1059,11 +1060,11 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-tag");
+                            self.emit_error("eof-in-tag");
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-whitespace-between-attributes");
+                            self.emit_error("missing-whitespace-between-attributes");
                             self.reconsume_in(State::BeforeAttributeName);
                         }
                     }
@@ -1084,11 +1085,11 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-tag");
+                            self.emit_error("eof-in-tag");
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("unexpected-solidus-in-tag");
+                            self.emit_error("unexpected-solidus-in-tag");
                             self.reconsume_in(State::BeforeAttributeName);
                         }
                     }
@@ -1105,7 +1106,7 @@ where
                             return self.emit_eof();
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_token_data(REPLACEMENT_CHARACTER);
                         }
                         _ => {
@@ -1120,10 +1121,15 @@ where
                     } else if self.consume_if_match("doctype", true) {
                         self.switch_to(State::DOCTYPE);
                     } else if self.consume_if_match("[CDATA[", false) {
-                        // TODO: implement this
-                        unimplemented!();
+                        if self.allow_cdata {
+                            self.switch_to(State::CDATASection);
+                        } else {
+                            self.emit_error("cdata-in-html-content");
+                            self.new_token(Token::new_comment("[CDATA["));
+                            self.switch_to(State::BogusComment);
+                        }
                     } else {
-                        emit_error!("incorrectly-opened-comment");
+                        self.emit_error("incorrectly-opened-comment");
                         self.new_token(Token::new_comment(""));
                         self.switch_to(State::BogusComment);
                     }
@@ -1135,7 +1141,7 @@ where
                             self.switch_to(State::CommentStartDash);
                         }
                         Char::ch('>') => {
-                            emit_error!("abrupt-closing-of-empty-comment");
+                            self.emit_error("abrupt-closing-of-empty-comment");
                             self.switch_to(State::Data);
                             return self.emit_current_token();
                         }
@@ -1151,12 +1157,12 @@ where
                             self.switch_to(State::CommentEnd);
                         }
                         Char::ch('>') => {
-                            emit_error!("abrupt-closing-of-empty-comment");
+                            self.emit_error("abrupt-closing-of-empty-comment");
                             self.switch_to(State::Data);
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-comment");
+                            self.emit_error("eof-in-comment");
                             self.will_emit(self.current_token.clone().unwrap());
                             return self.emit_eof();
                         }
@@ -1177,11 +1183,11 @@ where
                             self.switch_to(State::CommentEndDash);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_token_data(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-comment");
+                            self.emit_error("eof-in-comment");
                             self.will_emit(self.current_token.clone().unwrap());
                             return self.emit_eof();
                         }
@@ -1234,7 +1240,7 @@ where
                             self.reconsume_in(State::CommentEnd);
                         }
                         _ => {
-                            emit_error!("nested-comment");
+                            self.emit_error("nested-comment");
                             self.reconsume_in(State::CommentEnd);
                         }
                     }
@@ -1246,7 +1252,7 @@ where
                             self.switch_to(State::CommentEnd);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-comment");
+                            self.emit_error("eof-in-comment");
                             self.will_emit(self.current_token.clone().unwrap());
                             return self.emit_eof();
                         }
@@ -1270,7 +1276,7 @@ where
                             self.append_character_to_token_data('-');
                         }
                         Char::eof => {
-                            emit_error!("eof-in-comment");
+                            self.emit_error("eof-in-comment");
                             self.will_emit(self.current_token.clone().unwrap());
                             return self.emit_eof();
                         }
@@ -1291,12 +1297,12 @@ where
                             self.switch_to(State::CommentEndDash);
                         }
                         Char::ch('>') => {
-                            emit_error!("incorrectly-closed-comment");
+                            self.emit_error("incorrectly-closed-comment");
                             self.switch_to(State::Data);
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-comment");
+                            self.emit_error("eof-in-comment");
                             self.will_emit(self.current_token.clone().unwrap());
                             return self.emit_eof();
                         }
@@ -1318,7 +1324,7 @@ where
                             self.reconsume_in(State::BeforeDOCTYPEName);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let mut token = Token::new_doctype();
                             token.set_force_quirks(true);
                             self.new_token(token);
@@ -1326,7 +1332,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-whitespace-before-doctype-name");
+                            self.emit_error("missing-whitespace-before-doctype-name");
                             self.reconsume_in(State::BeforeDOCTYPEName);
                         }
                     }
@@ -1346,7 +1352,7 @@ where
                             self.switch_to(State::DOCTYPEName);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             let mut token = Token::new_doctype();
                             if let Token::DOCTYPE { ref mut name, .. } = token {
                                 let mut new_name = String::new();
@@ -1357,7 +1363,7 @@ where
                             self.switch_to(State::DOCTYPEName);
                         }
                         Char::ch('>') => {
-                            emit_error!("missing-doctype-name");
+                            self.emit_error("missing-doctype-name");
                             let mut token = Token::new_doctype();
                             token.set_force_quirks(true);
                             self.new_token(token);
@@ -1365,7 +1371,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let mut token = Token::new_doctype();
                             token.set_force_quirks(true);
                             self.new_token(token);
@@ -1398,11 +1404,11 @@ where
                             self.append_character_to_doctype_name(c.to_ascii_lowercase());
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_doctype_name(REPLACEMENT_CHARACTER);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1428,7 +1434,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1446,7 +1452,7 @@ where
                             } else if self.consume_from_current_if_match("SYSTEM", true) {
                                 self.switch_to(State::AfterDOCTYPESystemKeyword);
                             } else {
-                                emit_error!("invalid-character-sequence-after-doctype-name");
+                                self.emit_error("invalid-character-sequence-after-doctype-name");
                                 let token = self.current_token.as_mut().unwrap();
                                 if let Token::DOCTYPE {
                                     ref mut force_quirks,
@@ -1467,7 +1473,7 @@ where
                             self.switch_to(State::BeforeDOCTYPEPublicIdentifier);
                         }
                         Char::ch('"') => {
-                            emit_error!("missing-whitespace-after-doctype-public-keyword");
+                            self.emit_error("missing-whitespace-after-doctype-public-keyword");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut public_identifier,
@@ -1479,7 +1485,7 @@ where
                             self.switch_to(State::DOCTYPEPublicIdentifierDoubleQuoted);
                         }
                         Char::ch('\'') => {
-                            emit_error!("missing-whitespace-after-doctype-public-keyword");
+                            self.emit_error("missing-whitespace-after-doctype-public-keyword");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut public_identifier,
@@ -1491,7 +1497,7 @@ where
                             self.switch_to(State::DOCTYPEPublicIdentifierSingleQuoted);
                         }
                         Char::ch('>') => {
-                            emit_error!("missing-doctype-public-identifier");
+                            self.emit_error("missing-doctype-public-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1504,7 +1510,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1517,7 +1523,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-quote-before-doctype-public-identifier");
+                            self.emit_error("missing-quote-before-doctype-public-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1557,7 +1563,7 @@ where
                             self.switch_to(State::DOCTYPEPublicIdentifierSingleQuoted);
                         }
                         Char::ch('>') => {
-                            emit_error!("missing-doctype-public-identifier");
+                            self.emit_error("missing-doctype-public-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1570,7 +1576,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1583,7 +1589,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-quote-before-doctype-public-identifier");
+                            self.emit_error("missing-quote-before-doctype-public-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1603,13 +1609,13 @@ where
                             self.switch_to(State::AfterDOCTYPEPublicIdentifier);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_doctype_public_identifier(
                                 REPLACEMENT_CHARACTER,
                             );
                         }
                         Char::ch('>') => {
-                            emit_error!("abrupt-doctype-public-identifier");
+                            self.emit_error("abrupt-doctype-public-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1622,7 +1628,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1648,13 +1654,13 @@ where
                             self.switch_to(State::AfterDOCTYPEPublicIdentifier);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_doctype_public_identifier(
                                 REPLACEMENT_CHARACTER,
                             );
                         }
                         Char::ch('>') => {
-                            emit_error!("abrupt-doctype-public-identifier");
+                            self.emit_error("abrupt-doctype-public-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1667,7 +1673,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1697,8 +1703,8 @@ where
                             return self.emit_current_token();
                         }
                         Char::ch('"') => {
-                            emit_error!(
-                                "missing-whitespace-between-doctype-public-and-system-identifiers"
+                            self.emit_error(
+                                "missing-whitespace-between-doctype-public-and-system-identifiers",
                             );
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
@@ -1711,8 +1717,8 @@ where
                             self.switch_to(State::DOCTYPESytemIdentifierDoubleQuoted);
                         }
                         Char::ch('\'') => {
-                            emit_error!(
-                                "missing-whitespace-between-doctype-public-and-system-identifiers"
+                            self.emit_error(
+                                "missing-whitespace-between-doctype-public-and-system-identifiers",
                             );
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
@@ -1725,7 +1731,7 @@ where
                             self.switch_to(State::DOCTYPESytemIdentifierSingleQuoted);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1738,7 +1744,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-quote-before-doctype-system-identifier");
+                            self.emit_error("missing-quote-before-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1782,7 +1788,7 @@ where
                             self.switch_to(State::DOCTYPESytemIdentifierSingleQuoted);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1795,7 +1801,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-quote-before-doctype-system-identifier");
+                            self.emit_error("missing-quote-before-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1815,7 +1821,7 @@ where
                             self.switch_to(State::BeforeDOCTYPESystemIdentifier);
                         }
                         Char::ch('"') => {
-                            emit_error!("missing-whitespace-after-doctype-system-keyword");
+                            self.emit_error("missing-whitespace-after-doctype-system-keyword");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut system_identifier,
@@ -1827,7 +1833,7 @@ where
                             self.switch_to(State::DOCTYPESytemIdentifierDoubleQuoted);
                         }
                         Char::ch('\'') => {
-                            emit_error!("missing-whitespace-after-doctype-system-keyword");
+                            self.emit_error("missing-whitespace-after-doctype-system-keyword");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut system_identifier,
@@ -1839,7 +1845,7 @@ where
                             self.switch_to(State::DOCTYPESytemIdentifierSingleQuoted);
                         }
                         Char::ch('>') => {
-                            emit_error!("missing-doctype-system-identifier");
+                            self.emit_error("missing-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1852,7 +1858,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1865,7 +1871,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-quote-before-doctype-system-identifier");
+                            self.emit_error("missing-quote-before-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1905,7 +1911,7 @@ where
                             self.switch_to(State::DOCTYPESytemIdentifierSingleQuoted);
                         }
                         Char::ch('>') => {
-                            emit_error!("missing-doctype-system-identifier");
+                            self.emit_error("missing-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1918,7 +1924,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1931,7 +1937,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("missing-quote-before-doctype-system-identifier");
+                            self.emit_error("missing-quote-before-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1951,13 +1957,13 @@ where
                             self.switch_to(State::AfterDOCTYPESystemIdentifier);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_doctype_system_identifier(
                                 REPLACEMENT_CHARACTER,
                             );
                         }
                         Char::ch('>') => {
-                            emit_error!("abrupt-doctype-system-identifier");
+                            self.emit_error("abrupt-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1970,7 +1976,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -1996,13 +2002,13 @@ where
                             self.switch_to(State::AfterDOCTYPESystemIdentifier);
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             self.append_character_to_doctype_system_identifier(
                                 REPLACEMENT_CHARACTER,
                             );
                         }
                         Char::ch('>') => {
-                            emit_error!("abrupt-doctype-system-identifier");
+                            self.emit_error("abrupt-doctype-system-identifier");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -2015,7 +2021,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -2043,7 +2049,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::eof => {
-                            emit_error!("eof-in-doctype");
+                            self.emit_error("eof-in-doctype");
                             let token = self.current_token.as_mut().unwrap();
                             if let Token::DOCTYPE {
                                 ref mut force_quirks,
@@ -2056,7 +2062,7 @@ where
                             return self.emit_eof();
                         }
                         _ => {
-                            emit_error!("unexpected-character-after-doctype-system-identifier");
+                            self.emit_error("unexpected-character-after-doctype-system-identifier");
                             self.reconsume_in(State::BogusDOCTYPE);
                         }
                     }
@@ -2069,7 +2075,7 @@ where
                             return self.emit_current_token();
                         }
                         Char::null => {
-                            emit_error!("unexpected-null-character");
+                            self.emit_error("unexpected-null-character");
                             continue;
                         }
                         Char::eof => {
@@ -2088,7 +2094,7 @@ where
                             self.switch_to(State::CDATASectionBracket);
                         }
                         Char::eof => {
-                            emit_error!("eof-in-cdata");
+                            self.emit_error("eof-in-cdata");
                             return self.emit_eof();
                         }
                         _ => {
@@ -2183,7 +2189,7 @@ where
                         }
 
                         if last_match_ch != ';' {
-                            emit_error!("missing-semicolon-after-character-reference");
+                            self.emit_error("missing-semicolon-after-character-reference");
                         }
 
                         self.temp_buffer.clear();
@@ -2211,7 +2217,7 @@ where
                             }
                         }
                         Char::ch(';') => {
-                            emit_error!("unknown-named-character-reference");
+                            self.emit_error("unknown-named-character-reference");
                             self.reconsume_in_return_state();
                         }
                         _ => {
@@ -2239,7 +2245,7 @@ where
                             self.reconsume_in(State::HexadecimalCharacterReference);
                         }
                         _ => {
-                            emit_error!("absence-of-digits-in-numeric-character-reference");
+                            self.emit_error("absence-of-digits-in-numeric-character-reference");
                             self.flush_code_points_consumed_as_a_character_reference();
                             self.reconsume_in_return_state();
                         }
@@ -2252,7 +2258,7 @@ where
                             self.reconsume_in(State::DecimalCharacterReference);
                         }
                         _ => {
-                            emit_error!("absence-of-digits-in-numeric-character-reference");
+                            self.emit_error("absence-of-digits-in-numeric-character-reference");
                             self.flush_code_points_consumed_as_a_character_reference();
                             self.reconsume_in_return_state();
                         }
@@ -2266,7 +2272,9 @@ where
                             if let Some(d) = self.current_character.to_digit(10) {
                                 self.character_reference_code += d;
                             } else {
-                                emit_error!("Can't convert current character to digit");
+                                self.emit_internal_error(
+                                    "Can't convert current character to digit",
+                                );
                             }
                         }
                         Char::ch(c) if c.is_ascii_hexdigit() => {
@@ -2274,14 +2282,16 @@ where
                             if let Some(d) = self.current_character.to_digit(16) {
                                 self.character_reference_code += d;
                             } else {
-                                emit_error!("Can't convert current character to digit");
+                                self.emit_internal_error(
+                                    "Can't convert current character to digit",
+                                );
                             }
                         }
                         Char::ch(';') => {
                             self.switch_to(State::NumericCharacterReferenceEnd);
                         }
                         _ => {
-                            emit_error!("missing-semicolon-after-character-reference");
+                            self.emit_error("missing-semicolon-after-character-reference");
                             self.reconsume_in(State::NumericCharacterReferenceEnd);
                         }
                     }
@@ -2294,14 +2304,16 @@ where
                             if let Some(d) = self.current_character.to_digit(10) {
                                 self.character_reference_code += d;
                             } else {
-                                emit_error!("Can't convert current character to digit");
+                                self.emit_internal_error(
+                                    "Can't convert current character to digit",
+                                );
                             }
                         }
                         Char::ch(';') => {
                             self.switch_to(State::NumericCharacterReferenceEnd);
                         }
                         _ => {
-                            emit_error!("missing-semicolon-after-character-reference");
+                            self.emit_error("missing-semicolon-after-character-reference");
                             self.reconsume_in(State::NumericCharacterReferenceEnd);
                         }
                     }
@@ -2309,22 +2321,22 @@ where
                 State::NumericCharacterReferenceEnd => {
                     let code = self.character_reference_code;
                     if code == 0x00 {
-                        emit_error!("null-character-reference");
+                        self.emit_error("null-character-reference");
                         self.character_reference_code = 0xFFFD;
                     }
                     if code > 0x10FFFF {
-                        emit_error!("character-reference-outside-unicode-range");
+                        self.emit_error("character-reference-outside-unicode-range");
                         self.character_reference_code = 0xFFFD;
                     }
                     if is_surrogate(code) {
-                        emit_error!("surrogate-character-reference");
+                        self.emit_error("surrogate-character-reference");
                         self.character_reference_code = 0xFFFD;
                     }
                     if is_nonecharacter(code) {
-                        emit_error!("noncharacter-character-reference");
+                        self.emit_error("noncharacter-character-reference");
                     }
                     if code == 0x0D || (is_control(code) && !is_whitespace(code)) {
-                        emit_error!("control-character-reference");
+                        self.emit_error("control-character-reference");
                         if let Some(new_code) = replace_control_codes(code) {
                             self.character_reference_code = new_code;
                         }
@@ -2341,11 +2353,25 @@ where
     }
 
     fn switch_to(&mut self, state: State) {
-        if is_trace() {
-            println!("Switch to: {:#?}", state);
-        }
+        log::trace!("Switch to: {:?}", state);
         self.state = state;
     }
+
+    fn set_error_sink(&mut self, sink: ParseErrorSinkRef) {
+        self.error_sink = Some(sink);
+    }
+
+    fn position(&self) -> SourcePosition {
+        self.input.position()
+    }
+
+    fn current_token_span(&self) -> SourceSpan {
+        self.current_token_span
+    }
+
+    fn set_allow_cdata(&mut self, allow: bool) {
+        self.allow_cdata = allow;
+    }
 }
 
 impl<T> Tokenizer<T>
@@ -2356,7 +2382,10 @@ where
         Self {
             input: CharInputStream::new(input),
             output: VecDeque::new(),
+            token_start: SourcePosition::start(),
+            current_token_span: SourceSpan::default(),
             current_character: '\0',
+            current_is_eof: false,
             state: State::Data,
             return_state: None,
             current_token: None,
@@ -2364,9 +2393,35 @@ where
             temp_buffer: String::new(),
             last_emitted_start_tag: None,
             character_reference_code: 0,
+            error_sink: None,
+            allow_cdata: false,
+        }
+    }
+
+    fn emit_error(&self, code: &'static str) {
+        let mut message = code.replace('-', " ");
+        message[..1].make_ascii_uppercase();
+        self.report(ParseError::new("html-tokenizer", code, &message));
+    }
+
+    /// Report a broken invariant of the tokenizer itself
+    fn emit_internal_error(&self, message: &str) {
+        self.report(ParseError::new("html-tokenizer", "internal-error", message));
+    }
+
+    fn report(&self, error: ParseError) {
+        if let Some(sink) = &self.error_sink {
+            sink.borrow_mut().report(error.at(self.input.position()));
         }
     }
 
+    /// Pretend that a start tag with the given name was the last one emitted.
+    /// This is needed when tokenizing starts in a state other than the data
+    /// state (e.g. RCDATA), where end tags are only appropriate if they match.
+    pub fn set_last_start_tag(&mut self, tag_name: &str) {
+        self.last_emitted_start_tag = Some(Token::new_start_tag_with_name(tag_name));
+    }
+
     fn reconsume_in_return_state(&mut self) {
         self.reconsume_in(self.return_state.clone().unwrap());
     }
@@ -2405,16 +2460,30 @@ where
                 _ => false,
             };
         }
-        emit_error!("No return state found");
+        self.emit_internal_error("No return state found");
         false
     }
 
     fn emit_temp_buffer(&mut self) {
+        let span = self.take_token_span();
         for c in self.temp_buffer.chars() {
-            self.output.push_back(Token::Character(c));
+            self.output.push_back((Token::Character(c), span));
         }
     }
 
+    /// Get the span of the token being emitted, which covers everything
+    /// consumed since the last emitted token.
+    fn take_token_span(&mut self) -> SourceSpan {
+        let end = if self.reconsume_char {
+            self.input.position()
+        } else {
+            self.input.next_position()
+        };
+        let span = SourceSpan::new(self.token_start, end);
+        self.token_start = end;
+        span
+    }
+
     fn append_character_to_doctype_name(&mut self, ch: char) {
         let token = self.current_token.as_mut().unwrap();
         if let Token::DOCTYPE { ref mut name, .. } = token {
@@ -2454,7 +2523,7 @@ where
             tag_name.push(ch);
         } else {
             // hope that this never fire
-            emit_error!("No tag found");
+            self.emit_internal_error("No tag found");
         }
     }
 
@@ -2464,7 +2533,7 @@ where
             data.push(ch);
         } else {
             // hope that this never fire
-            emit_error!("No tag found");
+            self.emit_internal_error("No tag found");
         }
     }
 
@@ -2525,7 +2594,7 @@ where
             let mut remove_indexes = Vec::new();
             for (index, attribute) in attributes.iter().enumerate() {
                 if seen.contains(&attribute.name) {
-                    emit_error!("duplicate-attribute");
+                    self.emit_error("duplicate-attribute");
                     remove_indexes.push(index);
                 } else {
                     seen.insert(attribute.name.clone());
@@ -2538,7 +2607,8 @@ where
                 self.last_emitted_start_tag = Some(token.clone());
             }
         }
-        self.output.push_back(token);
+        let span = self.take_token_span();
+        self.output.push_back((token, span));
     }
 
     fn is_end_tag_appropriate(&mut self) -> bool {
@@ -2559,7 +2629,9 @@ where
     }
 
     fn pop_token(&mut self) -> Token {
-        self.output.pop_front().unwrap()
+        let (token, span) = self.output.pop_front().unwrap();
+        self.current_token_span = span;
+        token
     }
 
     fn reconsume_in(&mut self, state: State) {
@@ -2608,7 +2680,11 @@ where
             // reset reconsume flag
             self.reconsume_char = false;
 
-            Some(self.current_character)
+            if self.current_is_eof {
+                None
+            } else {
+                Some(self.current_character)
+            }
         } else {
             self.input.next()
         };
@@ -2622,7 +2698,10 @@ where
                     _ => Char::ch(c),
                 }
             }
-            None => Char::eof,
+            None => {
+                self.current_is_eof = true;
+                Char::eof
+            }
         }
     }
 }
@@ -2630,6 +2709,30 @@ where
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+
+    #[test]
+    fn report_parse_errors() {
+        let html = "<div>\n\0";
+        let errors = Rc::new(RefCell::new(Vec::new()));
+        let mut tokenizer = Tokenizer::new(html.chars());
+        tokenizer.set_error_sink(errors.clone());
+        while !tokenizer.next_token().is_eof() {}
+
+        let errors = errors.borrow();
+        assert_eq!(errors.len(), 1);
+        assert_eq!(errors[0].code, "unexpected-null-character");
+        assert_eq!(errors[0].message, "Unexpected null character");
+        assert_eq!(
+            errors[0].position,
+            Some(SourcePosition {
+                offset: 6,
+                line: 2,
+                column: 1
+            })
+        );
+    }
 
     #[test]
     fn parse_comment() {
@@ -1,3 +1,5 @@
+use crate::error::{HtmlError, Result};
+
 #[derive(Clone, Debug, PartialEq)]
 pub struct Attribute {
     pub name: String,
@@ -101,21 +103,23 @@ impl Token {
         return false;
     }
 
-    pub fn tag_name(&self) -> &String {
+    /// The tag name of the token, or an empty string if it is not a tag
+    pub fn tag_name(&self) -> &str {
         if let Token::Tag { tag_name, .. } = self {
             return tag_name;
         }
-        panic!("Token is not a tag");
+        ""
     }
 
-    pub fn set_tag_name(&mut self, new_name: &str) {
+    pub fn set_tag_name(&mut self, new_name: &str) -> Result<()> {
         if let Token::Tag {
             ref mut tag_name, ..
         } = self
         {
             *tag_name = new_name.to_owned();
+            return Ok(());
         }
-        panic!("Token is not a tag");
+        Err(HtmlError::NotATag)
     }
 
     pub fn is_eof(&self) -> bool {
@@ -125,41 +129,34 @@ impl Token {
         return false;
     }
 
-    pub fn attributes(&self) -> &Vec<Attribute> {
+    /// The attributes of the token, or an empty list if it is not a tag
+    pub fn attributes(&self) -> &[Attribute] {
         if let Token::Tag { attributes, .. } = self {
             return attributes;
         }
-        panic!("Token is not a tag");
+        &[]
     }
 
-    pub fn attributes_mut(&mut self) -> &mut Vec<Attribute> {
+    pub fn attributes_mut(&mut self) -> Result<&mut Vec<Attribute>> {
         if let Token::Tag {
             ref mut attributes, ..
         } = self
         {
-            return attributes;
+            return Ok(attributes);
         }
-        panic!("Token is not a tag");
+        Err(HtmlError::NotATag)
     }
 
     pub fn attribute(&self, name: &str) -> Option<&String> {
-        if let Token::Tag { attributes, .. } = self {
-            return match attributes.iter().find(|attr| attr.name == name) {
-                Some(attr) => Some(&attr.name),
-                _ => None,
-            };
-        }
-        panic!("Token is not a tag");
+        self.attributes()
+            .iter()
+            .find(|attr| attr.name == name)
+            .map(|attr| &attr.value)
     }
 
-    pub fn drop_attributes(&mut self) {
-        if let Token::Tag {
-            ref mut attributes, ..
-        } = self
-        {
-            *attributes = Vec::new();
-        }
-        panic!("Token is not a tag");
+    pub fn drop_attributes(&mut self) -> Result<()> {
+        self.attributes_mut()?.clear();
+        Ok(())
     }
 
     pub fn acknowledge_self_closing_if_set(&mut self) {
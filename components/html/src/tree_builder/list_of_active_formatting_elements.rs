@@ -47,13 +47,8 @@ impl ListOfActiveFormattingElements {
     }
 
     pub fn remove_element(&mut self, element: &NodeRef) {
-        for (index, entry) in self.entries.iter().rev().enumerate() {
-            if let Entry::Element(el) = entry {
-                if el == element {
-                    self.entries.remove(index);
-                    return;
-                }
-            }
+        if let Some(index) = self.get_index_of_node(element) {
+            self.entries.remove(index);
         }
     }
 
@@ -69,14 +64,10 @@ impl ListOfActiveFormattingElements {
     }
 
     pub fn get_index_of_node(&self, node: &NodeRef) -> Option<usize> {
-        for (index, entry) in self.entries.iter().rev().enumerate() {
-            if let Entry::Element(el) = entry {
-                if el == node {
-                    return Some(index);
-                }
-            }
-        }
-        None
+        self.entries.iter().rposition(|entry| match entry {
+            Entry::Element(el) => el == node,
+            Entry::Marker => false,
+        })
     }
 }
 
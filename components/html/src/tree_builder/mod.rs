@@ -1,46 +1,30 @@
 mod insert_mode;
 mod list_of_active_formatting_elements;
 mod open_element_types;
+mod quirks;
 mod stack_of_open_elements;
 
 use super::tokenizer::state::State;
 use super::tokenizer::token::Attribute;
 use super::tokenizer::token::Token;
+use crate::error::HtmlError;
 use crate::tokenizer::Tokenizing;
 use dom::comment::Comment;
 use dom::document::{Document, DocumentType, QuirksMode};
+use dom::document_fragment::DocumentFragment;
 use dom::dom_ref::NodeRef;
-use dom::element::Element;
+use dom::element::{Element, Namespace};
 use dom::node::{Node, NodeData};
 use dom::text::Text;
 use insert_mode::InsertMode;
+use io::cancellation::CancellationToken;
+use io::parse_error::{ParseError, ParseErrorSinkRef};
 use list_of_active_formatting_elements::Entry;
 use list_of_active_formatting_elements::ListOfActiveFormattingElements;
 use open_element_types::is_special_element;
 use phf::phf_map;
+use quirks::quirks_mode;
 use stack_of_open_elements::StackOfOpenElements;
-use std::env;
-
-fn is_trace() -> bool {
-    match env::var("TRACE_HTML_TREE_BUILDER") {
-        Ok(s) => s == "true",
-        _ => false,
-    }
-}
-
-macro_rules! trace {
-    ($err:expr) => {
-        println!("[ParseError][HTML TreeBuilding]: {}", $err);
-    };
-}
-
-macro_rules! emit_error {
-    ($err:expr) => {
-        if is_trace() {
-            trace!($err)
-        }
-    };
-}
 
 macro_rules! match_any {
     ($target:ident, $($cmp:expr), *) => {
@@ -110,6 +94,12 @@ pub struct TreeBuilder<T: Tokenizing> {
 
     /// Context element for fragment html
     context_element: Option<NodeRef>,
+
+    /// Where to report parse errors. Errors are dropped if not set
+    error_sink: Option<ParseErrorSinkRef>,
+
+    /// Stop parsing once cancelled, keeping the nodes inserted so far
+    cancellation: Option<CancellationToken>,
 }
 
 /// The adjusted location to insert a node as mentioned the specs
@@ -208,9 +198,11 @@ static SVG_ATTRIBUTE_MAP: phf::Map<&str, &str> = phf_map! {
 };
 
 fn adjust_svg_attributes(token: &mut Token) {
-    for attr in token.attributes_mut() {
-        if let Some(rname) = SVG_ATTRIBUTE_MAP.get(attr.name.as_str()) {
-            attr.name = rname.to_string();
+    if let Ok(attributes) = token.attributes_mut() {
+        for attr in attributes {
+            if let Some(rname) = SVG_ATTRIBUTE_MAP.get(attr.name.as_str()) {
+                attr.name = rname.to_string();
+            }
         }
     }
 }
@@ -234,16 +226,101 @@ static FOREIGN_ATTRIBUTE_MAP: phf::Map<&str, (&str, &str, &str)> = phf_map! {
 };
 
 fn adjust_foreign_attributes(token: &mut Token) {
-    for attr in token.attributes_mut() {
-        if let Some(data) = FOREIGN_ATTRIBUTE_MAP.get(attr.name.as_str()) {
-            let (prefix, name, namespace) = data;
-            attr.name = name.to_string();
-            attr.prefix = prefix.to_string();
-            attr.namespace = namespace.to_string();
+    if let Ok(attributes) = token.attributes_mut() {
+        for attr in attributes {
+            if let Some(data) = FOREIGN_ATTRIBUTE_MAP.get(attr.name.as_str()) {
+                let (prefix, name, namespace) = data;
+                attr.name = name.to_string();
+                attr.prefix = prefix.to_string();
+                attr.namespace = namespace.to_string();
+            }
+        }
+    }
+}
+
+static SVG_TAG_NAME_MAP: phf::Map<&str, &str> = phf_map! {
+    "altglyph" => "altGlyph",
+    "altglyphdef" => "altGlyphDef",
+    "altglyphitem" => "altGlyphItem",
+    "animatecolor" => "animateColor",
+    "animatemotion" => "animateMotion",
+    "animatetransform" => "animateTransform",
+    "clippath" => "clipPath",
+    "feblend" => "feBlend",
+    "fecolormatrix" => "feColorMatrix",
+    "fecomponenttransfer" => "feComponentTransfer",
+    "fecomposite" => "feComposite",
+    "feconvolvematrix" => "feConvolveMatrix",
+    "fediffuselighting" => "feDiffuseLighting",
+    "fedisplacementmap" => "feDisplacementMap",
+    "fedistantlight" => "feDistantLight",
+    "fedropshadow" => "feDropShadow",
+    "feflood" => "feFlood",
+    "fefunca" => "feFuncA",
+    "fefuncb" => "feFuncB",
+    "fefuncg" => "feFuncG",
+    "fefuncr" => "feFuncR",
+    "fegaussianblur" => "feGaussianBlur",
+    "feimage" => "feImage",
+    "femerge" => "feMerge",
+    "femergenode" => "feMergeNode",
+    "femorphology" => "feMorphology",
+    "feoffset" => "feOffset",
+    "fepointlight" => "fePointLight",
+    "fespecularlighting" => "feSpecularLighting",
+    "fespotlight" => "feSpotLight",
+    "fetile" => "feTile",
+    "feturbulence" => "feTurbulence",
+    "foreignobject" => "foreignObject",
+    "glyphref" => "glyphRef",
+    "lineargradient" => "linearGradient",
+    "radialgradient" => "radialGradient",
+    "textpath" => "textPath",
+};
+
+fn adjust_svg_tag_name(token: &mut Token) {
+    if let Some(name) = SVG_TAG_NAME_MAP.get(token.tag_name()) {
+        token.set_tag_name(name).unwrap();
+    }
+}
+
+fn adjust_mathml_attributes(token: &mut Token) {
+    if let Ok(attributes) = token.attributes_mut() {
+        for attr in attributes {
+            if attr.name == "definitionurl" {
+                attr.name = "definitionURL".to_string();
+            }
         }
     }
 }
 
+/// Whether HTML elements can be inserted in a foreign element
+/// https://html.spec.whatwg.org/multipage/parsing.html#html-integration-point
+fn is_html_integration_point(element: &Element) -> bool {
+    match element.namespace() {
+        Namespace::MathML => {
+            element.tag_name() == "annotation-xml"
+                && match_any!(
+                    element
+                        .attributes()
+                        .get_str("encoding")
+                        .to_ascii_lowercase()
+                        .as_str(),
+                    "text/html",
+                    "application/xhtml+xml"
+                )
+        }
+        Namespace::Svg => match_any!(element.tag_name(), "foreignObject", "desc", "title"),
+        Namespace::Html => false,
+    }
+}
+
+/// https://html.spec.whatwg.org/multipage/parsing.html#mathml-text-integration-point
+fn is_mathml_text_integration_point(element: &Element) -> bool {
+    element.namespace() == Namespace::MathML
+        && match_any!(element.tag_name(), "mi", "mo", "mn", "ms", "mtext")
+}
+
 impl<T: Tokenizing> TreeBuilder<T> {
     pub fn new(tokenizer: T, document: NodeRef) -> Self {
         Self {
@@ -263,9 +340,23 @@ impl<T: Tokenizing> TreeBuilder<T> {
             table_character_tokens: Vec::new(),
             is_fragment_case: false,
             context_element: None,
+            error_sink: None,
+            cancellation: None,
         }
     }
 
+    /// Report parse errors from both the tree builder & its tokenizer to `sink`
+    pub fn set_error_sink(&mut self, sink: ParseErrorSinkRef) {
+        self.tokenizer.set_error_sink(sink.clone());
+        self.error_sink = Some(sink);
+    }
+
+    /// Stop building the tree when a token is cancelled, as if the input
+    /// ended there
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancellation = Some(token);
+    }
+
     /// Create a HTML tree builder with default document & no loader.
     /// This should only be used for testing
     pub fn default(tokenizer: T) -> Self {
@@ -273,6 +364,81 @@ impl<T: Tokenizing> TreeBuilder<T> {
         Self::new(tokenizer, document)
     }
 
+    /// Create a HTML tree builder parsing a fragment of HTML as the
+    /// children of a context element. The tokenizer starts in the state
+    /// of the content of the context element.
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-html-fragments
+    pub fn new_fragment(tokenizer: T, context_element: NodeRef) -> Self {
+        let mut document = Document::new();
+        if let Some(context_document) = context_element.borrow().owner_document() {
+            if let Some(context_document) = context_document.borrow().as_document_opt() {
+                document.set_mode(*context_document.get_mode());
+            }
+        }
+        let document = NodeRef::new(Node::new(NodeData::Document(document)));
+        let mut builder = Self::new(tokenizer, document);
+
+        let (tag_name, namespace) = {
+            let context = context_element.borrow();
+            let element = context.as_element();
+            (element.tag_name(), element.namespace())
+        };
+        if namespace == Namespace::Html {
+            match tag_name.as_ref() {
+                "title" | "textarea" => builder.tokenizer.switch_to(State::RCDATA),
+                "style" | "xmp" | "iframe" | "noembed" | "noframes" => {
+                    builder.tokenizer.switch_to(State::RAWTEXT)
+                }
+                "noscript" if builder.scripting => builder.tokenizer.switch_to(State::RAWTEXT),
+                "script" => builder.tokenizer.switch_to(State::ScriptData),
+                "plaintext" => builder.tokenizer.switch_to(State::PLAINTEXT),
+                _ => {}
+            }
+        }
+
+        let root = builder.create_element_from_tag_name("html");
+        Node::append_child(builder.document.clone(), root.clone());
+        builder.open_elements.push(root);
+        if namespace == Namespace::Html && tag_name == "template" {
+            builder
+                .stack_of_template_insert_mode
+                .push(InsertMode::InTemplate);
+        }
+
+        builder.is_fragment_case = true;
+        builder.context_element = Some(context_element.clone());
+        builder.reset_insertion_mode_appropriately();
+
+        let mut ancestor = Some(context_element);
+        while let Some(node) = ancestor {
+            let is_form = node
+                .borrow()
+                .as_element_opt()
+                .map_or(false, |element| element.has_tag_name("form"));
+            if is_form {
+                builder.form_pointer = Some(node);
+                break;
+            }
+            ancestor = node.borrow().parent();
+        }
+        builder
+    }
+
+    /// Parse a fragment of HTML, returning a document fragment with the
+    /// parsed nodes
+    pub fn run_fragment(self) -> NodeRef {
+        let document = self.run();
+        let root = document
+            .borrow()
+            .first_child()
+            .expect("The fragment has no root element");
+        let fragment = NodeRef::new(Node::new(NodeData::DocumentFragment(
+            DocumentFragment::new(),
+        )));
+        Node::reparent_nodes_in_node(root, fragment.clone());
+        fragment
+    }
+
     /// Start the main loop for parsing DOM tree
     pub fn run(mut self) -> NodeRef {
         loop {
@@ -280,15 +446,65 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
             self.process(token);
 
-            if self.should_stop {
+            let is_cancelled = self
+                .cancellation
+                .as_ref()
+                .map_or(false, |token| token.is_cancelled());
+            if self.should_stop || is_cancelled {
                 break;
             }
         }
         return self.document;
     }
 
-    /// (Re)process a token in the current insert mode
+    /// (Re)process a token in the current insert mode, or with the rules
+    /// for foreign content when it's inside an SVG or MathML element
+    /// https://html.spec.whatwg.org/multipage/parsing.html#tree-construction-dispatcher
     pub fn process(&mut self, token: Token) {
+        if self.is_in_foreign_content(&token) {
+            self.handle_in_foreign_content(token);
+        } else {
+            self.process_in_insert_mode(token);
+        }
+        self.tokenizer.set_allow_cdata(
+            self.adjusted_current_node()
+                .map(|node| get_element!(node).namespace() != Namespace::Html)
+                .unwrap_or(false),
+        );
+    }
+
+    fn is_in_foreign_content(&self, token: &Token) -> bool {
+        let node = match self.adjusted_current_node() {
+            Some(node) => node,
+            None => return false,
+        };
+        let node = node.borrow();
+        let element = node.as_element();
+
+        if element.namespace() == Namespace::Html || token.is_eof() {
+            return false;
+        }
+        let is_character = matches!(token, Token::Character(_));
+        if is_mathml_text_integration_point(element)
+            && (is_character
+                || token.is_start_tag() && !match_any!(token.tag_name(), "mglyph", "malignmark"))
+        {
+            return false;
+        }
+        if element.namespace() == Namespace::MathML
+            && element.tag_name() == "annotation-xml"
+            && token.is_start_tag()
+            && token.tag_name() == "svg"
+        {
+            return false;
+        }
+        if is_html_integration_point(element) && (is_character || token.is_start_tag()) {
+            return false;
+        }
+        true
+    }
+
+    fn process_in_insert_mode(&mut self, token: Token) {
         match self.insert_mode {
             InsertMode::Initial => self.handle_initial(token),
             InsertMode::BeforeHtml => self.handle_before_html(token),
@@ -308,8 +524,18 @@ impl<T: Tokenizing> TreeBuilder<T> {
             InsertMode::InSelect => self.handle_in_select(token),
             InsertMode::InSelectInTable => self.handle_in_select_in_table(token),
             InsertMode::AfterBody => self.handle_after_body(token),
+            InsertMode::InTemplate => self.handle_in_template(token),
             InsertMode::AfterAfterBody => self.handle_after_after_body(token),
-            _ => unimplemented!(),
+            _ => {
+                // TODO: support frameset insertion modes
+                self.emit_error(
+                    "unsupported-insert-mode",
+                    &format!("Unsupported insert mode: {:?}", self.insert_mode),
+                );
+                if token.is_eof() {
+                    self.stop_parsing();
+                }
+            }
         }
     }
 
@@ -319,22 +545,24 @@ impl<T: Tokenizing> TreeBuilder<T> {
     }
 
     fn which_quirks_mode(&self, token: Token) -> QuirksMode {
-        if let Token::DOCTYPE {
-            name, force_quirks, ..
-        } = token
-        {
-            // TODO: Implement full stpecs detection
-            if force_quirks || name.unwrap_or_default() != "html" {
-                return QuirksMode::Quirks;
-            }
+        match token {
+            Token::DOCTYPE {
+                name,
+                public_identifier,
+                system_identifier,
+                force_quirks,
+            } => quirks_mode(
+                name.as_deref(),
+                public_identifier.as_deref(),
+                system_identifier.as_deref(),
+                force_quirks,
+            ),
+            _ => QuirksMode::Quirks,
         }
-        QuirksMode::NoQuirks
     }
 
     fn switch_to(&mut self, mode: InsertMode) {
-        if is_trace() {
-            println!("Switch to: {:#?}", mode);
-        }
+        log::trace!("Switch to: {:?}", mode);
         self.insert_mode = mode;
     }
 
@@ -343,6 +571,10 @@ impl<T: Tokenizing> TreeBuilder<T> {
     }
 
     fn create_element(&self, tag_token: Token) -> NodeRef {
+        self.create_element_ns(tag_token, Namespace::Html)
+    }
+
+    fn create_element_ns(&self, tag_token: Token, namespace: Namespace) -> NodeRef {
         let (tag_name, attributes) = if let Token::Tag {
             tag_name,
             attributes,
@@ -353,7 +585,9 @@ impl<T: Tokenizing> TreeBuilder<T> {
         } else {
             ("".to_string(), Vec::new())
         };
-        let element_ref = dom::create_element(self.document.clone().downgrade(), &tag_name);
+        let element_ref =
+            dom::create_element_ns(self.document.clone().downgrade(), &tag_name, namespace);
+        self.set_source_span(&element_ref);
         {
             let mut element = element_ref.borrow_mut();
             let element = element.as_element_mut();
@@ -364,6 +598,12 @@ impl<T: Tokenizing> TreeBuilder<T> {
         element_ref
     }
 
+    /// Mark the node as created by the token currently being processed
+    fn set_source_span(&self, node: &NodeRef) {
+        node.borrow_mut()
+            .set_source_span(self.tokenizer.current_token_span());
+    }
+
     fn create_element_from_tag_name(&self, tag_name: &str) -> NodeRef {
         self.create_element(Token::Tag {
             tag_name: tag_name.to_owned(),
@@ -378,7 +618,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         &self,
         target: Option<NodeRef>,
     ) -> AdjustedInsertionLocation {
-        let target = target.unwrap_or(self.open_elements.current_node().unwrap());
+        let target = target.unwrap_or_else(|| self.current_node());
 
         let adjusted_location = if self.foster_parenting
             && match_any!(
@@ -422,8 +662,12 @@ impl<T: Tokenizing> TreeBuilder<T> {
     }
 
     fn insert_html_element(&mut self, token: Token) -> NodeRef {
+        self.insert_foreign_element(token, Namespace::Html)
+    }
+
+    fn insert_foreign_element(&mut self, token: Token, namespace: Namespace) -> NodeRef {
         let insert_position = self.get_appropriate_place_for_inserting_a_node(None);
-        let element = self.create_element(token);
+        let element = self.create_element_ns(token, namespace);
         let return_ref = element.clone();
 
         // TODO: check if location is possible to insert node (Idk why so we just leave it for now)
@@ -455,33 +699,44 @@ impl<T: Tokenizing> TreeBuilder<T> {
             AdjustedInsertionLocation::LastChild(parent) => {
                 let parent_node = parent.borrow();
                 if let Some(last_child) = parent_node.last_child() {
-                    if let Some(text) = last_child.borrow_mut().as_text_mut_opt() {
+                    let mut last_child = last_child.borrow_mut();
+                    if let Some(text) = last_child.as_text_mut_opt() {
                         text.character_data.append_data(&ch.to_string());
+                        last_child.extend_source_span(self.tokenizer.current_token_span().end);
                         return;
                     }
                 }
             }
             AdjustedInsertionLocation::BeforeSibling(_, sibling) => {
                 if let Some(prev_sibling) = sibling.borrow().prev_sibling() {
-                    if let Some(text) = prev_sibling.borrow_mut().as_text_mut_opt() {
+                    let mut prev_sibling = prev_sibling.borrow_mut();
+                    if let Some(text) = prev_sibling.as_text_mut_opt() {
                         text.character_data.append_data(&ch.to_string());
+                        prev_sibling.extend_source_span(self.tokenizer.current_token_span().end);
                         return;
                     }
                 }
             }
         }
         let text = NodeRef::new(Node::new(NodeData::Text(Text::new(ch.to_string()))));
+        self.set_source_span(&text);
         text.borrow_mut()
             .set_document(self.document.clone().downgrade());
         self.insert_at(insert_position, text);
     }
 
-    fn insert_comment(&mut self, data: String) {
-        let insert_position = self.get_appropriate_place_for_inserting_a_node(None);
+    fn create_comment(&self, data: String) -> NodeRef {
         let comment = NodeRef::new(Node::new(NodeData::Comment(Comment::new(data))));
+        self.set_source_span(&comment);
         comment
             .borrow_mut()
             .set_document(self.document.clone().downgrade());
+        comment
+    }
+
+    fn insert_comment(&mut self, data: String) {
+        let insert_position = self.get_appropriate_place_for_inserting_a_node(None);
+        let comment = self.create_comment(data);
         self.insert_at(insert_position, comment);
     }
 
@@ -517,7 +772,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         for (index, node) in self.open_elements.0.iter().enumerate().rev() {
             let last = index == 0;
 
-            let node = if self.is_fragment_case {
+            let node = if last && self.is_fragment_case {
                 self.context_element.clone().unwrap()
             } else {
                 node.clone()
@@ -573,8 +828,10 @@ impl<T: Tokenizing> TreeBuilder<T> {
             }
 
             if element.tag_name() == "template" {
-                self.switch_to(self.stack_of_template_insert_mode.last().unwrap().clone());
-                return;
+                if let Some(mode) = self.stack_of_template_insert_mode.last().cloned() {
+                    self.switch_to(mode);
+                    return;
+                }
             }
 
             if element.tag_name() == "head" {
@@ -659,61 +916,58 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 self.unexpected(&token);
             }
 
-            let (mut furthest_block, mut furthest_block_index) = {
-                let mut found_element = None;
-                let mut found_index = None;
-                for (index, element) in self.open_elements.iter().rev().enumerate() {
-                    if *element == fmt_element {
-                        break;
-                    }
-                    if is_special_element(&get_element!(element).tag_name()) {
-                        found_element = Some(element.clone());
-                        found_index = Some(index);
-                    }
-                }
-                (found_element, found_index)
+            let fmt_index = match self.open_elements.iter().position(|el| *el == fmt_element) {
+                Some(index) => index,
+                None => return AdoptionAgencyOutcome::DoNothing,
             };
 
-            if furthest_block.is_none() {
-                while self.current_node() != fmt_element {
-                    self.open_elements.pop();
-                }
-                self.open_elements.pop();
-                self.active_formatting_elements.remove_element(&fmt_element);
-                return AdoptionAgencyOutcome::DoNothing;
-            }
+            // The topmost special element that is lower in the stack than the
+            // formatting element
+            let furthest_block = self
+                .open_elements
+                .iter()
+                .skip(fmt_index + 1)
+                .find(|el| is_special_element(&get_element!(el).tag_name()))
+                .cloned();
 
-            let mut common_ancestor = {
-                let mut found_element = None;
-                for (index, el) in self.open_elements.iter().rev().enumerate() {
-                    if *el == fmt_element {
-                        if index < self.open_elements.len() - 1 {
-                            found_element = Some(self.open_elements.get(index + 1));
-                        }
-                        break;
+            let furthest_block = match furthest_block {
+                Some(block) => block,
+                None => {
+                    while self.current_node() != fmt_element {
+                        self.open_elements.pop();
                     }
+                    self.open_elements.pop();
+                    self.active_formatting_elements.remove_element(&fmt_element);
+                    return AdoptionAgencyOutcome::DoNothing;
                 }
-                found_element
             };
 
-            let common_ancestor = common_ancestor
-                .take()
-                .expect("Common ancestor doesn't exists in agency adoption algo");
+            if fmt_index == 0 {
+                // The formatting element has no common ancestor to insert into
+                self.emit_error(
+                    "missing-common-ancestor",
+                    "Formatting element has no common ancestor",
+                );
+                return AdoptionAgencyOutcome::DoNothing;
+            }
+            let common_ancestor = self.open_elements.get(fmt_index - 1);
 
-            let mut bookmark = self
+            let mut bookmark = match self
                 .active_formatting_elements
-                .iter()
-                .rposition(|el| match el {
-                    Entry::Element(e) => *e == fmt_element,
-                    _ => false,
-                })
-                .unwrap();
-
-            let furthest_block = furthest_block.take().unwrap();
-            let furthest_block_index = furthest_block_index.take().unwrap();
+                .get_index_of_node(&fmt_element)
+            {
+                Some(index) => index,
+                None => return AdoptionAgencyOutcome::DoNothing,
+            };
 
-            let mut node;
-            let mut node_index = furthest_block_index;
+            let mut node_index = match self
+                .open_elements
+                .iter()
+                .position(|el| *el == furthest_block)
+            {
+                Some(index) => index,
+                None => return AdoptionAgencyOutcome::DoNothing,
+            };
             let mut last_node = furthest_block.clone();
 
             let mut inner_counter = 0;
@@ -722,86 +976,119 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 inner_counter += 1;
 
                 node_index -= 1;
-                node = self.open_elements.get(node_index);
+                let node = self.open_elements.get(node_index);
 
                 if node == fmt_element {
                     break;
                 }
 
-                if inner_counter > 3 && self.active_formatting_elements.contains_node(&node) {
-                    self.active_formatting_elements.remove_element(&node);
-                    continue;
-                }
-
-                let node_formatting_index = {
+                if inner_counter > 3 {
                     if let Some(index) = self.active_formatting_elements.get_index_of_node(&node) {
-                        index
-                    } else {
-                        self.open_elements.remove_first_matching(|n| *n == node);
-                        continue;
+                        self.active_formatting_elements.remove(index);
+                        if index < bookmark {
+                            bookmark -= 1;
+                        }
                     }
-                };
+                }
 
-                let node_clone = node.clone();
-                let node_borrow = node_clone.borrow();
-                let node_element = node_borrow.as_element();
-                let new_element = self.create_element(Token::Tag {
-                    tag_name: node_element.tag_name().to_string(),
-                    self_closing: false,
-                    is_end_tag: false,
-                    self_closing_acknowledged: false,
-                    attributes: node_element
-                        .attributes()
-                        .iter()
-                        .map(|(k, v)| Attribute::from_name_value(k.clone(), v.clone()))
-                        .collect(),
-                });
+                let node_formatting_index =
+                    match self.active_formatting_elements.get_index_of_node(&node) {
+                        Some(index) => index,
+                        None => {
+                            self.open_elements.remove(node_index);
+                            continue;
+                        }
+                    };
+
+                let new_element = {
+                    let node_borrow = node.borrow();
+                    let node_element = node_borrow.as_element();
+                    self.create_element(Token::Tag {
+                        tag_name: node_element.tag_name().to_string(),
+                        self_closing: false,
+                        is_end_tag: false,
+                        self_closing_acknowledged: false,
+                        attributes: node_element
+                            .attributes()
+                            .iter()
+                            .map(|(k, v)| Attribute::from_name_value(k.to_string(), v.clone()))
+                            .collect(),
+                    })
+                };
 
                 self.open_elements[node_index] = new_element.clone();
                 self.active_formatting_elements[node_formatting_index] =
                     Entry::Element(new_element.clone());
 
-                node = new_element;
-
                 if last_node == furthest_block {
                     bookmark = node_formatting_index + 1;
                 }
 
-                Node::append_child(node.clone(), last_node.clone());
-                last_node = node;
+                Node::append_child(new_element.clone(), last_node.clone());
+                last_node = new_element;
             }
 
             let insert_place =
                 self.get_appropriate_place_for_inserting_a_node(Some(common_ancestor));
             self.insert_at(insert_place, last_node);
 
-            let node_borrow = fmt_element.borrow();
-            let node_element = node_borrow.as_element();
-            let new_element = self.create_element(Token::Tag {
-                tag_name: node_element.tag_name().to_string(),
-                self_closing: false,
-                is_end_tag: false,
-                self_closing_acknowledged: false,
-                attributes: node_element
-                    .attributes()
-                    .iter()
-                    .map(|(k, v)| Attribute::from_name_value(k.clone(), v.clone()))
-                    .collect(),
-            });
+            let new_element = {
+                let node_borrow = fmt_element.borrow();
+                let node_element = node_borrow.as_element();
+                self.create_element(Token::Tag {
+                    tag_name: node_element.tag_name().to_string(),
+                    self_closing: false,
+                    is_end_tag: false,
+                    self_closing_acknowledged: false,
+                    attributes: node_element
+                        .attributes()
+                        .iter()
+                        .map(|(k, v)| Attribute::from_name_value(k.to_string(), v.clone()))
+                        .collect(),
+                })
+            };
 
             Node::reparent_nodes_in_node(furthest_block.clone(), new_element.clone());
             Node::append_child(furthest_block.clone(), new_element.clone());
 
-            self.active_formatting_elements.remove_element(&fmt_element);
-            self.active_formatting_elements[bookmark] = Entry::Element(new_element.clone());
+            if let Some(index) = self
+                .active_formatting_elements
+                .get_index_of_node(&fmt_element)
+            {
+                self.active_formatting_elements.remove(index);
+                if index < bookmark {
+                    bookmark -= 1;
+                }
+            }
+            let bookmark = bookmark.min(self.active_formatting_elements.len());
+            self.active_formatting_elements
+                .insert(bookmark, Entry::Element(new_element.clone()));
+
             self.open_elements
                 .remove_first_matching(|n| *n == fmt_element);
-            self.open_elements
-                .insert(furthest_block_index + 1, new_element);
+            if let Some(index) = self
+                .open_elements
+                .iter()
+                .position(|el| *el == furthest_block)
+            {
+                self.open_elements.insert(index + 1, new_element);
+            }
         }
         AdoptionAgencyOutcome::DoNothing
     }
 
+    fn emit_error(&self, code: &'static str, message: &str) {
+        if let Some(sink) = &self.error_sink {
+            let error = ParseError::new("html-tree-builder", code, message)
+                .at(self.tokenizer.current_token_span().start);
+            sink.borrow_mut().report(error);
+        }
+    }
+
+    fn report_error(&self, error: HtmlError) {
+        self.emit_error(error.code(), &error.to_string());
+    }
+
     fn unexpected(&self, token: &Token) {
         match token {
             Token::Tag {
@@ -810,15 +1097,21 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 ..
             } => {
                 if *is_end_tag {
-                    emit_error!(format!("Unexpected end tag: {}", tag_name))
+                    self.emit_error(
+                        "unexpected-end-tag",
+                        &format!("Unexpected end tag: {}", tag_name),
+                    )
                 } else {
-                    emit_error!(format!("Unexpected start tag: {}", tag_name))
+                    self.emit_error(
+                        "unexpected-start-tag",
+                        &format!("Unexpected start tag: {}", tag_name),
+                    )
                 }
             }
-            Token::DOCTYPE { .. } => emit_error!("Unexpected DOCTYPE"),
-            Token::Comment(_) => emit_error!("Unexpected comment"),
-            Token::Character(_) => emit_error!("Unexpected character"),
-            Token::EOF => emit_error!("Unexpected EOF"),
+            Token::DOCTYPE { .. } => self.emit_error("unexpected-doctype", "Unexpected DOCTYPE"),
+            Token::Comment(_) => self.emit_error("unexpected-comment", "Unexpected comment"),
+            Token::Character(_) => self.emit_error("unexpected-character", "Unexpected character"),
+            Token::EOF => self.emit_error("unexpected-eof", "Unexpected EOF"),
         }
     }
 
@@ -826,7 +1119,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         self.generate_implied_end_tags("p");
 
         if get_element!(self.open_elements.current_node().unwrap()).tag_name() != "p" {
-            emit_error!("Expected p element");
+            self.emit_error("expected-p-element", "Expected p element");
         }
 
         self.open_elements.pop_until("p");
@@ -853,6 +1146,15 @@ impl<T: Tokenizing> TreeBuilder<T> {
         self.open_elements.current_node().unwrap()
     }
 
+    /// The context element when parsing a fragment with only the root
+    /// element open, the current node otherwise
+    fn adjusted_current_node(&self) -> Option<NodeRef> {
+        if self.is_fragment_case && self.open_elements.len() == 1 {
+            return self.context_element.clone();
+        }
+        self.open_elements.current_node()
+    }
+
     fn reconstruct_active_formatting_elements(&mut self) {
         if self.active_formatting_elements.len() == 0 {
             return;
@@ -883,14 +1185,16 @@ impl<T: Tokenizing> TreeBuilder<T> {
         loop {
             let element = match &self.active_formatting_elements[last_index] {
                 Entry::Element(element) => element.clone(),
-                Entry::Marker => panic!("Unexpected marker while building DOM tree!"),
+                Entry::Marker => return,
             };
 
-            let new_element = {
+            // The old element must not stay borrowed while inserting, it
+            // could be the last child of the insertion target
+            let token = {
                 let element = element.borrow();
                 let element = element.as_element();
 
-                self.insert_html_element(Token::Tag {
+                Token::Tag {
                     is_end_tag: false,
                     self_closing: false,
                     self_closing_acknowledged: false,
@@ -899,14 +1203,15 @@ impl<T: Tokenizing> TreeBuilder<T> {
                         .attributes()
                         .iter()
                         .map(|entry| Attribute {
-                            name: entry.0.clone(),
+                            name: entry.0.to_string(),
                             value: entry.1.clone(),
                             prefix: String::new(),
                             namespace: String::new(),
                         })
                         .collect(),
-                })
+                }
             };
+            let new_element = self.insert_html_element(token);
 
             self.active_formatting_elements[last_index] = Entry::Element(new_element);
 
@@ -921,7 +1226,10 @@ impl<T: Tokenizing> TreeBuilder<T> {
         self.generate_implied_end_tags("");
         let current_tag_name = get_element!(self.current_node()).tag_name();
         if current_tag_name != "td" || current_tag_name != "th" {
-            emit_error!("Unexpected node encountered while closing cell");
+            self.emit_error(
+                "unexpected-node-closing-cell",
+                "Unexpected node encountered while closing cell",
+            );
         }
         self.open_elements.pop_until_match(|element| {
             let tag_name = element.tag_name();
@@ -941,7 +1249,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         if let Token::Comment(data) = token {
-            let comment = NodeRef::new(Node::new(NodeData::Comment(Comment::new(data))));
+            let comment = self.create_comment(data);
             comment
                 .borrow_mut()
                 .set_document(self.document.clone().downgrade());
@@ -984,6 +1292,9 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         self.unexpected(&token);
+        if let Some(doc) = self.document.borrow_mut().as_document_mut_opt() {
+            doc.set_mode(QuirksMode::Quirks);
+        }
         self.switch_to(InsertMode::BeforeHtml);
         self.process(token)
     }
@@ -1004,7 +1315,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         if let Token::Comment(data) = token {
-            let comment = NodeRef::new(Node::new(NodeData::Comment(Comment::new(data))));
+            let comment = self.create_comment(data);
             Node::append_child(self.document.clone(), comment);
             return;
         }
@@ -1182,7 +1493,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
         if token.is_end_tag() && token.tag_name() == "template" {
             if !self.open_elements.contains("template") {
-                emit_error!("No template tag found");
+                self.emit_error("missing-template-element", "No template tag found");
                 return;
             }
 
@@ -1192,7 +1503,10 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 let node = node.borrow();
                 let element = node.as_element();
                 if element.tag_name() != "template" {
-                    emit_error!("Expected current node to be template");
+                    self.emit_error(
+                        "expected-template-element",
+                        "Expected current node to be template",
+                    );
                 }
             }
 
@@ -1388,7 +1702,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 }
 
                 if is_special_element(&current_tag_name) {
-                    emit_error!("Unexpected special element");
+                    this.emit_error("unexpected-special-element", "Unexpected special element");
                     return;
                 }
             }
@@ -1410,7 +1724,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
         if let Token::Character(c) = token {
             if c == '\0' {
-                emit_error!("Unexpected null character");
+                self.emit_error("unexpected-null-character", "Unexpected null character");
                 return;
             }
 
@@ -1432,12 +1746,12 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         if let Token::DOCTYPE { .. } = token {
-            emit_error!("Unexpected DOCTYPE");
+            self.emit_error("unexpected-doctype", "Unexpected DOCTYPE");
             return;
         }
 
         if token.is_start_tag() && token.tag_name() == "html" {
-            emit_error!("Unexpected HTML tag");
+            self.emit_error("unexpected-html-start-tag", "Unexpected HTML tag");
             if self.open_elements.contains("template") {
                 return;
             }
@@ -1570,7 +1884,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         if token.is_end_tag() && token.tag_name() == "body" {
-            if self.open_elements.has_element_name_in_scope("body") {
+            if !self.open_elements.has_element_name_in_scope("body") {
                 self.unexpected(&token);
                 return;
             }
@@ -1604,7 +1918,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         if token.is_end_tag() && token.tag_name() == "html" {
-            if self.open_elements.has_element_name_in_scope("body") {
+            if !self.open_elements.has_element_name_in_scope("body") {
                 self.unexpected(&token);
                 return;
             }
@@ -1738,7 +2052,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 if element_tag_name == "li" {
                     self.generate_implied_end_tags("li");
                     if get_element!(self.current_node()).tag_name() != "li" {
-                        emit_error!("Expected 'li' tag");
+                        self.emit_error("expected-li-element", "Expected 'li' tag");
                     }
                     self.open_elements.pop_until("li");
                     break;
@@ -1765,7 +2079,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 if element_tag_name == "dd" {
                     self.generate_implied_end_tags("dd");
                     if get_element!(self.current_node()).tag_name() != "dd" {
-                        emit_error!("Expected 'dd' tag");
+                        self.emit_error("expected-dd-element", "Expected 'dd' tag");
                     }
                     self.open_elements.pop_until("dd");
                     break;
@@ -1774,7 +2088,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 if element_tag_name == "dt" {
                     self.generate_implied_end_tags("dt");
                     if get_element!(self.current_node()).tag_name() != "dt" {
-                        emit_error!("Expected 'dt' tag");
+                        self.emit_error("expected-dt-element", "Expected 'dt' tag");
                     }
                     self.open_elements.pop_until("dt");
                     break;
@@ -2080,13 +2394,12 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         if token.is_start_tag() && token.tag_name() == "table" {
-            let document = self.document.clone();
-            let document = document.borrow();
-            let document = document.as_document();
-            if let QuirksMode::NoQuirks = document.get_mode() {
-                if self.open_elements.has_element_name_in_button_scope("p") {
-                    self.close_p_element();
-                }
+            let no_quirks = match self.document.borrow().as_document().get_mode() {
+                QuirksMode::NoQuirks => true,
+                _ => false,
+            };
+            if no_quirks && self.open_elements.has_element_name_in_button_scope("p") {
+                self.close_p_element();
             }
             self.insert_html_element(token);
             self.frameset_ok = false;
@@ -2096,7 +2409,9 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
         if token.is_end_tag() && token.tag_name() == "br" {
             self.unexpected(&token);
-            token.drop_attributes();
+            if let Err(error) = token.drop_attributes() {
+                self.report_error(error);
+            }
             self.reconstruct_active_formatting_elements();
             token.acknowledge_self_closing_if_set();
             self.insert_html_element(token);
@@ -2162,7 +2477,10 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
         if token.is_start_tag() && token.tag_name() == "image" {
             self.unexpected(&token);
-            token.set_tag_name("img"); // But why?? :troll:
+            // But why?? :troll:
+            if let Err(error) = token.set_tag_name("img") {
+                self.report_error(error);
+            }
             return self.process(token);
         }
 
@@ -2254,23 +2572,25 @@ impl<T: Tokenizing> TreeBuilder<T> {
             return;
         }
 
-        if token.is_start_tag() && token.tag_name() == "math" {
-            // TODO: support math
-            unimplemented!();
-        }
-
-        if token.is_start_tag() && token.tag_name() == "svg" {
+        if token.is_start_tag() && match_any!(token.tag_name(), "math", "svg") {
             self.reconstruct_active_formatting_elements();
-            adjust_svg_attributes(&mut token);
+            let namespace = if token.tag_name() == "svg" {
+                adjust_svg_attributes(&mut token);
+                Namespace::Svg
+            } else {
+                adjust_mathml_attributes(&mut token);
+                Namespace::MathML
+            };
             adjust_foreign_attributes(&mut token);
 
-            if token.is_self_closing() {
+            let self_closing = token.is_self_closing();
+            token.acknowledge_self_closing_if_set();
+
+            self.insert_foreign_element(token, namespace);
+
+            if self_closing {
                 self.open_elements.pop();
-                token.acknowledge_self_closing_if_set();
             }
-
-            // TODO: change this to insert foreign element
-            self.insert_html_element(token);
             return;
         }
 
@@ -2508,15 +2828,18 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 });
 
         if has_non_whitespace_char {
-            emit_error!("Non-whitespace in table text");
-            let table_character_tokens = self.table_character_tokens.clone();
+            self.emit_error(
+                "unexpected-character-in-table",
+                "Non-whitespace in table text",
+            );
+            let table_character_tokens = std::mem::take(&mut self.table_character_tokens);
             for c_token in table_character_tokens {
                 self.foster_parenting = true;
                 self.handle_in_body(c_token.clone());
                 self.foster_parenting = false;
             }
         } else {
-            let table_character_tokens = self.table_character_tokens.clone();
+            let table_character_tokens = std::mem::take(&mut self.table_character_tokens);
             for c_token in table_character_tokens {
                 if let Token::Character(c) = c_token {
                     self.insert_character(c);
@@ -2525,6 +2848,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         self.switch_to(self.original_insert_mode.clone().unwrap());
+        self.process(token);
     }
 
     fn handle_in_caption(&mut self, token: Token) {
@@ -2608,7 +2932,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
         }
 
         if let Token::Comment(data) = token {
-            let comment = NodeRef::new(Node::new(NodeData::Comment(Comment::new(data))));
+            let comment = self.create_comment(data);
             let html_el = self.open_elements.get(0);
             Node::append_child(html_el, comment);
             return;
@@ -2629,6 +2953,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 return;
             }
             self.switch_to(InsertMode::AfterAfterBody);
+            return;
         }
 
         if let Token::EOF = token {
@@ -2643,7 +2968,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
     fn handle_after_after_body(&mut self, token: Token) {
         if let Token::Comment(data) = token {
-            let comment = NodeRef::new(Node::new(NodeData::Comment(Comment::new(data))));
+            let comment = self.create_comment(data);
             Node::append_child(self.document.clone(), comment);
             return;
         }
@@ -2734,7 +3059,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
     fn handle_in_table_body(&mut self, token: Token) {
         if token.is_start_tag() && token.tag_name() == "tr" {
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_body_context();
             self.insert_html_element(token);
             self.switch_to(InsertMode::InRow);
             return;
@@ -2742,7 +3067,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
         if token.is_start_tag() && match_any!(token.tag_name(), "th", "td") {
             self.unexpected(&token);
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_body_context();
             self.insert_html_element(Token::new_start_tag_with_name("tr"));
             self.switch_to(InsertMode::InRow);
             return self.process(token);
@@ -2757,7 +3082,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 return;
             }
 
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_body_context();
             self.open_elements.pop();
             self.switch_to(InsertMode::InTable);
             return;
@@ -2783,7 +3108,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 return;
             }
 
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_body_context();
             self.open_elements.pop();
             self.switch_to(InsertMode::InTable);
             return self.process(token);
@@ -2811,7 +3136,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
 
     fn handle_in_row(&mut self, token: Token) {
         if token.is_start_tag() && match_any!(token.tag_name(), "th", "td") {
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_row_context();
             self.insert_html_element(token);
             self.switch_to(InsertMode::InCell);
             self.active_formatting_elements.add_marker();
@@ -2824,7 +3149,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 return;
             }
 
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_row_context();
             self.open_elements.pop();
             self.switch_to(InsertMode::InTableBody);
             return;
@@ -2847,7 +3172,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 self.unexpected(&token);
                 return;
             }
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_row_context();
             self.open_elements.pop();
             self.switch_to(InsertMode::InTableBody);
             return self.process(token);
@@ -2865,7 +3190,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
                 self.unexpected(&token);
                 return;
             }
-            self.open_elements.clear_back_to_table_context();
+            self.open_elements.clear_back_to_table_row_context();
             self.open_elements.pop();
             self.switch_to(InsertMode::InTableBody);
             return self.process(token);
@@ -2903,7 +3228,10 @@ impl<T: Tokenizing> TreeBuilder<T> {
             self.generate_implied_end_tags("");
 
             if get_element!(self.current_node()).tag_name() != *token.tag_name() {
-                emit_error!("Expected current node to have same tag name as token");
+                self.emit_error(
+                    "end-tag-mismatch",
+                    "Expected current node to have same tag name as token",
+                );
             }
             self.open_elements.pop_until(token.tag_name());
             self.active_formatting_elements.clear_up_to_last_marker();
@@ -3019,7 +3347,7 @@ impl<T: Tokenizing> TreeBuilder<T> {
             if get_element!(self.current_node()).tag_name() == "optgroup" {
                 self.open_elements.pop();
             } else {
-                emit_error!("expected optgroup");
+                self.emit_error("expected-optgroup-element", "Expected optgroup element");
             }
             return;
         }
@@ -3223,6 +3551,155 @@ impl<T: Tokenizing> TreeBuilder<T> {
             return self.process(token);
         }
     }
+
+    /// https://html.spec.whatwg.org/multipage/parsing.html#parsing-main-inforeign
+    fn handle_in_foreign_content(&mut self, mut token: Token) {
+        if let Token::Character(c) = token {
+            if c == '\0' {
+                self.emit_error("unexpected-null-character", "Unexpected null character");
+                self.insert_character('\u{FFFD}');
+                return;
+            }
+            self.insert_character(c);
+            if !is_whitespace(c) {
+                self.frameset_ok = false;
+            }
+            return;
+        }
+
+        if let Token::Comment(data) = token {
+            self.insert_comment(data);
+            return;
+        }
+
+        if let Token::DOCTYPE { .. } = token {
+            self.emit_error("unexpected-doctype", "Unexpected DOCTYPE");
+            return;
+        }
+
+        let breaks_out = token.is_start_tag()
+            && (match_any!(
+                token.tag_name(),
+                "b",
+                "big",
+                "blockquote",
+                "body",
+                "br",
+                "center",
+                "code",
+                "dd",
+                "div",
+                "dl",
+                "dt",
+                "em",
+                "embed",
+                "h1",
+                "h2",
+                "h3",
+                "h4",
+                "h5",
+                "h6",
+                "head",
+                "hr",
+                "i",
+                "img",
+                "li",
+                "listing",
+                "menu",
+                "meta",
+                "nobr",
+                "ol",
+                "p",
+                "pre",
+                "ruby",
+                "s",
+                "small",
+                "span",
+                "strong",
+                "strike",
+                "sub",
+                "sup",
+                "table",
+                "tt",
+                "u",
+                "ul",
+                "var"
+            ) || token.tag_name() == "font"
+                && token
+                    .attributes()
+                    .iter()
+                    .any(|attr| match_any!(attr.name, "color", "face", "size")))
+            || token.is_end_tag() && match_any!(token.tag_name(), "br", "p");
+
+        if breaks_out {
+            self.unexpected(&token);
+            while let Some(node) = self.open_elements.current_node() {
+                let node = node.borrow();
+                let element = node.as_element();
+                if element.namespace() == Namespace::Html
+                    || is_mathml_text_integration_point(element)
+                    || is_html_integration_point(element)
+                {
+                    break;
+                }
+                drop(node);
+                self.open_elements.pop();
+            }
+            return self.process_in_insert_mode(token);
+        }
+
+        if token.is_start_tag() {
+            let namespace = match self.adjusted_current_node() {
+                Some(node) => get_element!(node).namespace(),
+                None => Namespace::Html,
+            };
+            match namespace {
+                Namespace::MathML => adjust_mathml_attributes(&mut token),
+                Namespace::Svg => {
+                    adjust_svg_tag_name(&mut token);
+                    adjust_svg_attributes(&mut token);
+                }
+                Namespace::Html => {}
+            }
+            adjust_foreign_attributes(&mut token);
+
+            let self_closing = token.is_self_closing();
+            token.acknowledge_self_closing_if_set();
+            self.insert_foreign_element(token, namespace);
+
+            if self_closing {
+                self.open_elements.pop();
+            }
+            return;
+        }
+
+        if token.is_end_tag() {
+            let mut index = self.open_elements.len() - 1;
+            if get_element!(self.open_elements.get(index))
+                .tag_name()
+                .to_ascii_lowercase()
+                != token.tag_name()
+            {
+                self.unexpected(&token);
+            }
+            loop {
+                if index == 0 {
+                    return;
+                }
+                let node = self.open_elements.get(index);
+                if get_element!(node).tag_name().to_ascii_lowercase() == token.tag_name() {
+                    while self.open_elements.len() > index {
+                        self.open_elements.pop();
+                    }
+                    return;
+                }
+                index -= 1;
+                if get_element!(self.open_elements.get(index)).namespace() == Namespace::Html {
+                    return self.process_in_insert_mode(token);
+                }
+            }
+        }
+    }
 }
 
 #[cfg(test)]
@@ -3284,4 +3761,91 @@ mod test {
             "This is a link".to_string()
         );
     }
+
+    #[test]
+    fn stop_parsing_once_cancelled() {
+        let html = "<div></div><p></p>";
+        let tokenizer = Tokenizer::new(html.chars());
+        let mut tree_builder = TreeBuilder::default(tokenizer);
+        let token = CancellationToken::new();
+        token.cancel();
+        tree_builder.set_cancellation(token);
+        let document = tree_builder.run();
+
+        // the first token is processed before the token is checked
+        let html = document.borrow().first_child().unwrap();
+        let body = html.borrow().last_child().unwrap();
+        assert_eq!(body.borrow().child_nodes().length(), 1);
+    }
+
+    #[test]
+    fn track_node_source_spans() {
+        let html = "<div>\n  <a href=\"#\">link</a></div>";
+        let tokenizer = Tokenizer::new(html.chars());
+        let tree_builder = TreeBuilder::default(tokenizer);
+        let document = tree_builder.run();
+
+        let html = document.borrow().first_child().unwrap();
+        let body = html.borrow().last_child().unwrap();
+        let div = body.borrow().first_child().unwrap();
+        let a = div.borrow().last_child().unwrap();
+        let text = a.borrow().first_child().unwrap();
+
+        let div_span = div.borrow().source_span().unwrap();
+        assert_eq!((div_span.start.offset, div_span.end.offset), (0, 5));
+
+        let a_span = a.borrow().source_span().unwrap();
+        assert_eq!((a_span.start.line, a_span.start.column), (2, 3));
+        assert_eq!(a_span.end.offset, 20);
+
+        let text_span = text.borrow().source_span().unwrap();
+        assert_eq!((text_span.start.offset, text_span.end.offset), (20, 24));
+    }
+
+    #[test]
+    fn reconstruct_misnested_formatting_elements() {
+        let html = "<b><p>1<i>2</b>3</i>";
+        let tokenizer = Tokenizer::new(html.chars());
+        let tree_builder = TreeBuilder::default(tokenizer);
+        let document = tree_builder.run();
+
+        dom::testing::assert_snapshot(
+            &document,
+            "\
+| <html>
+|   <head>
+|   <body>
+|     <b>
+|     <p>
+|       <b>
+|         \"1\"
+|         <i>
+|           \"2\"
+|       <i>
+|         \"3\"
+",
+        );
+    }
+
+    #[test]
+    fn handle_malformed_documents() {
+        let documents = [
+            "<table><",
+            "<marquee></body></html>x",
+            "</body></html><!--c-->",
+            "<math><mi>x</mi></math><![CDATA[x]]>",
+            "<i><frameset>",
+            "<a><body><a></table>",
+            "<table><b><tbody>x",
+            "<table><tfoot><tbody><col>",
+            "<b><p>1<i>2</b>3</i>",
+        ];
+
+        for html in documents.iter() {
+            let tokenizer = Tokenizer::new(html.chars());
+            let tree_builder = TreeBuilder::default(tokenizer);
+            let document = tree_builder.run();
+            assert!(document.borrow().first_child().is_some(), "{}", html);
+        }
+    }
 }
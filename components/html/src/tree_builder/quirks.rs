@@ -0,0 +1,171 @@
+use dom::document::QuirksMode;
+
+/// The public identifiers of the doctypes putting a document in quirks
+/// mode when they start the public identifier of its doctype
+const QUIRKS_PUBLIC_ID_PREFIXES: [&str; 55] = [
+    "+//silmaril//dtd html pro v0r11 19970101//",
+    "-//as//dtd html 3.0 aswedit + extensions//",
+    "-//advasoft ltd//dtd html 3.0 aswedit + extensions//",
+    "-//ietf//dtd html 2.0 level 1//",
+    "-//ietf//dtd html 2.0 level 2//",
+    "-//ietf//dtd html 2.0 strict level 1//",
+    "-//ietf//dtd html 2.0 strict level 2//",
+    "-//ietf//dtd html 2.0 strict//",
+    "-//ietf//dtd html 2.0//",
+    "-//ietf//dtd html 2.1e//",
+    "-//ietf//dtd html 3.0//",
+    "-//ietf//dtd html 3.2 final//",
+    "-//ietf//dtd html 3.2//",
+    "-//ietf//dtd html 3//",
+    "-//ietf//dtd html level 0//",
+    "-//ietf//dtd html level 1//",
+    "-//ietf//dtd html level 2//",
+    "-//ietf//dtd html level 3//",
+    "-//ietf//dtd html strict level 0//",
+    "-//ietf//dtd html strict level 1//",
+    "-//ietf//dtd html strict level 2//",
+    "-//ietf//dtd html strict level 3//",
+    "-//ietf//dtd html strict//",
+    "-//ietf//dtd html//",
+    "-//metrius//dtd metrius presentational//",
+    "-//microsoft//dtd internet explorer 2.0 html strict//",
+    "-//microsoft//dtd internet explorer 2.0 html//",
+    "-//microsoft//dtd internet explorer 2.0 tables//",
+    "-//microsoft//dtd internet explorer 3.0 html strict//",
+    "-//microsoft//dtd internet explorer 3.0 html//",
+    "-//microsoft//dtd internet explorer 3.0 tables//",
+    "-//netscape comm. corp.//dtd html//",
+    "-//netscape comm. corp.//dtd strict html//",
+    "-//o'reilly and associates//dtd html 2.0//",
+    "-//o'reilly and associates//dtd html extended 1.0//",
+    "-//o'reilly and associates//dtd html extended relaxed 1.0//",
+    "-//sq//dtd html 2.0 hotmetal + extensions//",
+    "-//softquad software//dtd hotmetal pro 6.0::19990601::extensions to html 4.0//",
+    "-//softquad//dtd hotmetal pro 4.0::19971010::extensions to html 4.0//",
+    "-//spyglass//dtd html 2.0 extended//",
+    "-//sun microsystems corp.//dtd hotjava html//",
+    "-//sun microsystems corp.//dtd hotjava strict html//",
+    "-//w3c//dtd html 3 1995-03-24//",
+    "-//w3c//dtd html 3.2 draft//",
+    "-//w3c//dtd html 3.2 final//",
+    "-//w3c//dtd html 3.2//",
+    "-//w3c//dtd html 3.2s draft//",
+    "-//w3c//dtd html 4.0 frameset//",
+    "-//w3c//dtd html 4.0 transitional//",
+    "-//w3c//dtd html experimental 19960712//",
+    "-//w3c//dtd html experimental 970421//",
+    "-//w3c//dtd w3 html//",
+    "-//w3o//dtd w3 html 3.0//",
+    "-//webtechs//dtd mozilla html 2.0//",
+    "-//webtechs//dtd mozilla html//",
+];
+
+/// The public identifiers of the HTML 4.01 doctypes, putting a document
+/// in quirks mode without a system identifier & in limited quirks mode
+/// with one
+const HTML4_PUBLIC_ID_PREFIXES: [&str; 2] = [
+    "-//w3c//dtd html 4.01 frameset//",
+    "-//w3c//dtd html 4.01 transitional//",
+];
+
+const LIMITED_QUIRKS_PUBLIC_ID_PREFIXES: [&str; 2] = [
+    "-//w3c//dtd xhtml 1.0 frameset//",
+    "-//w3c//dtd xhtml 1.0 transitional//",
+];
+
+/// The mode of a document with a doctype. The identifiers are compared
+/// ASCII case-insensitively.
+/// https://html.spec.whatwg.org/multipage/parsing.html#the-initial-insertion-mode
+pub fn quirks_mode(
+    name: Option<&str>,
+    public_id: Option<&str>,
+    system_id: Option<&str>,
+    force_quirks: bool,
+) -> QuirksMode {
+    let public_id = public_id.map(|id| id.to_ascii_lowercase());
+    let system_id = system_id.map(|id| id.to_ascii_lowercase());
+    let public_id_starts_with = |prefixes: &[&str]| match &public_id {
+        Some(id) => prefixes.iter().any(|prefix| id.starts_with(prefix)),
+        None => false,
+    };
+
+    let is_quirks = force_quirks
+        || name != Some("html")
+        || matches!(
+            public_id.as_deref(),
+            Some("-//w3o//dtd w3 html strict 3.0//en//")
+                | Some("-/w3c/dtd html 4.0 transitional/en")
+                | Some("html")
+        )
+        || system_id.as_deref()
+            == Some("http://www.ibm.com/data/dtd/v11/ibmxhtml1-transitional.dtd")
+        || public_id_starts_with(&QUIRKS_PUBLIC_ID_PREFIXES)
+        || system_id.is_none() && public_id_starts_with(&HTML4_PUBLIC_ID_PREFIXES);
+    if is_quirks {
+        return QuirksMode::Quirks;
+    }
+
+    let is_limited_quirks = public_id_starts_with(&LIMITED_QUIRKS_PUBLIC_ID_PREFIXES)
+        || system_id.is_some() && public_id_starts_with(&HTML4_PUBLIC_ID_PREFIXES);
+    if is_limited_quirks {
+        return QuirksMode::LimitedQuirks;
+    }
+    QuirksMode::NoQuirks
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn pick_quirks_mode() {
+        assert_eq!(
+            quirks_mode(Some("html"), None, None, false),
+            QuirksMode::NoQuirks
+        );
+        assert_eq!(
+            quirks_mode(Some("html"), None, None, true),
+            QuirksMode::Quirks
+        );
+        assert_eq!(
+            quirks_mode(Some("svg"), None, None, false),
+            QuirksMode::Quirks
+        );
+        assert_eq!(
+            quirks_mode(
+                Some("html"),
+                Some("-//W3C//DTD HTML 3.2 Final//EN"),
+                None,
+                false
+            ),
+            QuirksMode::Quirks
+        );
+        assert_eq!(
+            quirks_mode(
+                Some("html"),
+                Some("-//W3C//DTD HTML 4.01 Transitional//EN"),
+                None,
+                false
+            ),
+            QuirksMode::Quirks
+        );
+        assert_eq!(
+            quirks_mode(
+                Some("html"),
+                Some("-//W3C//DTD HTML 4.01 Transitional//EN"),
+                Some("http://www.w3.org/TR/html4/loose.dtd"),
+                false
+            ),
+            QuirksMode::LimitedQuirks
+        );
+        assert_eq!(
+            quirks_mode(
+                Some("html"),
+                Some("-//W3C//DTD XHTML 1.0 Strict//EN"),
+                Some("http://www.w3.org/TR/xhtml1/DTD/xhtml1-strict.dtd"),
+                false
+            ),
+            QuirksMode::NoQuirks
+        );
+    }
+}
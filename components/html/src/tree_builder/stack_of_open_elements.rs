@@ -39,7 +39,7 @@ impl StackOfOpenElements {
     }
 
     pub fn last_element_with_tag_name(&self, tag_name: &str) -> Option<(&NodeRef, usize)> {
-        for (i, node) in self.0.iter().rev().enumerate() {
+        for (i, node) in self.0.iter().enumerate().rev() {
             let node_borrow = node.borrow();
             let element = node_borrow.as_element();
             if element.tag_name() == tag_name {
@@ -77,14 +77,20 @@ impl StackOfOpenElements {
     }
 
     pub fn clear_back_to_table_context(&mut self) {
+        self.clear_back_to(&["table", "template", "html"]);
+    }
+
+    pub fn clear_back_to_table_body_context(&mut self) {
+        self.clear_back_to(&["tbody", "tfoot", "thead", "template", "html"]);
+    }
+
+    pub fn clear_back_to_table_row_context(&mut self) {
+        self.clear_back_to(&["tr", "template", "html"]);
+    }
+
+    fn clear_back_to(&mut self, tag_names: &[&str]) {
         while let Some(node) = self.current_node() {
-            let node = node.borrow();
-            let element = node.as_element();
-            let element_tag_name = element.tag_name();
-            if element_tag_name == "table"
-                || element_tag_name == "template"
-                || element_tag_name == "html"
-            {
+            if tag_names.contains(&node.borrow().as_element().tag_name().as_str()) {
                 break;
             }
             self.0.pop();
@@ -95,11 +101,8 @@ impl StackOfOpenElements {
     where
         F: Fn(&NodeRef) -> bool,
     {
-        for (i, node) in self.0.iter().rev().enumerate() {
-            if test(node) {
-                self.0.remove(i);
-                return;
-            }
+        if let Some(index) = self.0.iter().rposition(test) {
+            self.0.remove(index);
         }
     }
 
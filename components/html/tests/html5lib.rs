@@ -0,0 +1,466 @@
+//! Conformance runner for the html5lib-tests suite.
+//!
+//! By default only the small subset of fixtures bundled in
+//! `tests/fixtures/html5lib` is run and every case there must pass, so
+//! regressions in the parser are caught by `cargo test`.
+//!
+//! To track coverage against the full suite, point `HTML5LIB_TESTS_DIR` to
+//! a checkout of https://github.com/html5lib/html5lib-tests and run:
+//!
+//! ```sh
+//! HTML5LIB_TESTS_DIR=/path/to/html5lib-tests cargo test -p html --test html5lib -- --nocapture
+//! ```
+//!
+//! In that mode the pass/fail counts are only reported, not asserted.
+use dom::document::Document;
+use dom::dom_ref::NodeRef;
+use dom::element::Namespace;
+use dom::node::{Node, NodeData};
+use dom::testing;
+use html::tokenizer::state::State;
+use html::tokenizer::token::Token;
+use html::tokenizer::{Tokenizer, Tokenizing};
+use html::tree_builder::TreeBuilder;
+use serde_json::{json, Map, Value};
+use std::cell::Cell;
+use std::fs;
+use std::panic::{self, AssertUnwindSafe};
+use std::path::{Path, PathBuf};
+use std::sync::Once;
+
+#[derive(Default)]
+struct Report {
+    passed: usize,
+    failed: Vec<String>,
+    skipped: usize,
+}
+
+impl Report {
+    fn print(&self, suite: &str) {
+        println!(
+            "[html5lib][{}] {} passed, {} failed, {} skipped",
+            suite,
+            self.passed,
+            self.failed.len(),
+            self.skipped
+        );
+        for failure in &self.failed {
+            println!("  FAIL {}", failure);
+        }
+    }
+}
+
+enum Outcome {
+    Pass,
+    Fail,
+    Skip,
+}
+
+impl Report {
+    fn record(&mut self, name: String, outcome: Outcome) {
+        match outcome {
+            Outcome::Pass => self.passed += 1,
+            Outcome::Fail => self.failed.push(name),
+            Outcome::Skip => self.skipped += 1,
+        }
+    }
+}
+
+thread_local! {
+    static SILENCE_PANICS: Cell<bool> = Cell::new(false);
+}
+
+/// Run the closure, turning a panic into `None`. The panic message is not
+/// printed since a lot of unsupported cases are expected to panic.
+fn run_guarded<R, F: FnOnce() -> R>(f: F) -> Option<R> {
+    static INSTALL_HOOK: Once = Once::new();
+    INSTALL_HOOK.call_once(|| {
+        let default_hook = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            if !SILENCE_PANICS.with(|silence| silence.get()) {
+                default_hook(info);
+            }
+        }));
+    });
+
+    SILENCE_PANICS.with(|silence| silence.set(true));
+    let result = panic::catch_unwind(AssertUnwindSafe(f)).ok();
+    SILENCE_PANICS.with(|silence| silence.set(false));
+    result
+}
+
+fn fixtures_dir() -> (PathBuf, bool) {
+    match std::env::var("HTML5LIB_TESTS_DIR") {
+        Ok(dir) => (PathBuf::from(dir), false),
+        _ => (
+            Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/fixtures/html5lib"),
+            true,
+        ),
+    }
+}
+
+fn list_files(dir: &Path, extension: &str) -> Vec<PathBuf> {
+    let mut files = fs::read_dir(dir)
+        .unwrap_or_else(|e| panic!("Unable to read {}: {}", dir.display(), e))
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().map_or(false, |ext| ext == extension))
+        .collect::<Vec<_>>();
+    files.sort();
+    files
+}
+
+fn file_name(path: &Path) -> String {
+    path.file_name().unwrap().to_string_lossy().to_string()
+}
+
+// ---------------------------------------------------------------------------
+// Tokenizer tests (tokenizer/*.test)
+// ---------------------------------------------------------------------------
+
+fn initial_state(name: &str) -> Option<State> {
+    match name {
+        "Data state" => Some(State::Data),
+        "PLAINTEXT state" => Some(State::PLAINTEXT),
+        "RCDATA state" => Some(State::RCDATA),
+        "RAWTEXT state" => Some(State::RAWTEXT),
+        "Script data state" => Some(State::ScriptData),
+        "CDATA section state" => Some(State::CDATASection),
+        _ => None,
+    }
+}
+
+/// Undo the extra escaping of `\uXXXX` sequences used by tests that have
+/// `doubleEscaped` set. Returns `None` for lone surrogates since they can't
+/// be represented in a Rust string.
+fn unescape(input: &str) -> Option<String> {
+    let mut result = String::new();
+    let mut chars = input.chars().peekable();
+
+    while let Some(ch) = chars.next() {
+        if ch == '\\' && chars.peek() == Some(&'u') {
+            chars.next();
+            let code: String = chars.by_ref().take(4).collect();
+            let code = u32::from_str_radix(&code, 16).ok()?;
+            result.push(std::char::from_u32(code)?);
+        } else {
+            result.push(ch);
+        }
+    }
+    Some(result)
+}
+
+fn unescape_value(value: &Value) -> Option<Value> {
+    Some(match value {
+        Value::String(s) => Value::String(unescape(s)?),
+        Value::Array(values) => Value::Array(
+            values
+                .iter()
+                .map(unescape_value)
+                .collect::<Option<Vec<_>>>()?,
+        ),
+        Value::Object(map) => {
+            let mut result = Map::new();
+            for (key, value) in map {
+                result.insert(unescape(key)?, unescape_value(value)?);
+            }
+            Value::Object(result)
+        }
+        other => other.clone(),
+    })
+}
+
+/// Convert a token into the html5lib-tests output format
+fn token_to_json(token: Token) -> Value {
+    match token {
+        Token::DOCTYPE {
+            name,
+            public_identifier,
+            system_identifier,
+            force_quirks,
+        } => json!([
+            "DOCTYPE",
+            name,
+            public_identifier,
+            system_identifier,
+            !force_quirks
+        ]),
+        Token::Tag {
+            tag_name,
+            self_closing,
+            attributes,
+            is_end_tag,
+            ..
+        } => {
+            if is_end_tag {
+                return json!(["EndTag", tag_name]);
+            }
+            let mut attrs = Map::new();
+            for attr in attributes {
+                attrs.insert(attr.name, Value::String(attr.value));
+            }
+            if self_closing {
+                json!(["StartTag", tag_name, attrs, true])
+            } else {
+                json!(["StartTag", tag_name, attrs])
+            }
+        }
+        Token::Comment(data) => json!(["Comment", data]),
+        Token::Character(c) => json!(["Character", c.to_string()]),
+        Token::EOF => unreachable!(),
+    }
+}
+
+/// Merge adjacent character tokens, as expected by html5lib-tests
+fn merge_characters(tokens: Vec<Value>) -> Vec<Value> {
+    let mut result: Vec<Value> = Vec::new();
+
+    for token in tokens {
+        if token[0] == "Character" {
+            if let Some(last) = result.last_mut() {
+                if last[0] == "Character" {
+                    let merged = format!(
+                        "{}{}",
+                        last[1].as_str().unwrap(),
+                        token[1].as_str().unwrap()
+                    );
+                    last[1] = Value::String(merged);
+                    continue;
+                }
+            }
+        }
+        result.push(token);
+    }
+    result
+}
+
+fn tokenize(input: &str, state: State, last_start_tag: Option<&str>) -> Vec<Value> {
+    let mut tokenizer = Tokenizer::new(input.chars());
+    tokenizer.switch_to(state);
+    if let Some(tag_name) = last_start_tag {
+        tokenizer.set_last_start_tag(tag_name);
+    }
+
+    // every token consumes at least one character so anything beyond that
+    // means the tokenizer is stuck emitting the same thing over and over
+    let max_tokens = input.chars().count() + 1;
+
+    let mut tokens = Vec::new();
+    loop {
+        let token = tokenizer.next_token();
+        if token.is_eof() {
+            break;
+        }
+        if tokens.len() > max_tokens {
+            panic!("Tokenizer emitted too many tokens");
+        }
+        tokens.push(token_to_json(token));
+    }
+    merge_characters(tokens)
+}
+
+fn run_tokenizer_test(test: &Value, state_name: &str) -> Outcome {
+    let state = match initial_state(state_name) {
+        Some(state) => state,
+        None => return Outcome::Skip,
+    };
+
+    let double_escaped = test["doubleEscaped"].as_bool().unwrap_or(false);
+    let (input, expected) = if double_escaped {
+        match (
+            test["input"].as_str().and_then(unescape),
+            unescape_value(&test["output"]),
+        ) {
+            (Some(input), Some(output)) => (input, output),
+            _ => return Outcome::Skip,
+        }
+    } else {
+        (
+            test["input"].as_str().unwrap_or_default().to_string(),
+            test["output"].clone(),
+        )
+    };
+    let last_start_tag = test["lastStartTag"].as_str();
+
+    match run_guarded(|| tokenize(&input, state, last_start_tag)) {
+        Some(tokens) if Some(&tokens) == expected.as_array() => Outcome::Pass,
+        _ => Outcome::Fail,
+    }
+}
+
+#[test]
+fn html5lib_tokenizer() {
+    let (root, bundled) = fixtures_dir();
+    let mut report = Report::default();
+
+    for path in list_files(&root.join("tokenizer"), "test") {
+        let content = fs::read_to_string(&path).unwrap();
+        let suite: Value = serde_json::from_str(&content)
+            .unwrap_or_else(|e| panic!("Invalid test file {}: {}", path.display(), e));
+
+        let tests = match suite["tests"].as_array() {
+            Some(tests) => tests,
+            None => continue,
+        };
+
+        for test in tests {
+            let states = match test["initialStates"].as_array() {
+                Some(states) => states
+                    .iter()
+                    .filter_map(|s| s.as_str().map(|s| s.to_string()))
+                    .collect(),
+                None => vec!["Data state".to_string()],
+            };
+
+            for state in states {
+                let name = format!(
+                    "{}: {} ({})",
+                    file_name(&path),
+                    test["description"].as_str().unwrap_or_default(),
+                    state
+                );
+                report.record(name, run_tokenizer_test(test, &state));
+            }
+        }
+    }
+
+    report.print("tokenizer");
+    if bundled {
+        assert!(report.failed.is_empty(), "html5lib tokenizer tests failed");
+    }
+}
+
+// ---------------------------------------------------------------------------
+// Tree construction tests (tree-construction/*.dat)
+// ---------------------------------------------------------------------------
+
+#[derive(Default)]
+struct TreeTest {
+    data: String,
+    document: String,
+    fragment: Option<String>,
+    script_on: bool,
+}
+
+/// Split a `.dat` file into test cases. Each test starts with `#data` and
+/// is made up of sections, each starting with a `#section-name` line.
+fn parse_dat(content: &str) -> Vec<TreeTest> {
+    let mut tests = Vec::new();
+    let mut current: Option<TreeTest> = None;
+    let mut section = String::new();
+
+    let finish = |test: Option<TreeTest>, tests: &mut Vec<TreeTest>| {
+        if let Some(mut test) = test {
+            // the line break before the next test belongs to neither
+            if test.document.ends_with("\n\n") {
+                test.document.pop();
+            }
+            if test.data.ends_with('\n') {
+                test.data.pop();
+            }
+            tests.push(test);
+        }
+    };
+
+    for line in content.split('\n') {
+        if line == "#data" {
+            finish(current.take(), &mut tests);
+            current = Some(TreeTest::default());
+            section = line.to_string();
+            continue;
+        }
+
+        let test = match current.as_mut() {
+            Some(test) => test,
+            None => continue,
+        };
+
+        let is_section_header = line.starts_with('#') && section != "#data"
+            || (section == "#data" && line == "#errors");
+
+        if is_section_header {
+            section = line.to_string();
+            match line {
+                "#script-on" => test.script_on = true,
+                "#script-off" => test.script_on = false,
+                _ => {}
+            }
+            continue;
+        }
+
+        match section.as_str() {
+            "#data" => {
+                test.data.push_str(line);
+                test.data.push('\n');
+            }
+            "#document" => {
+                test.document.push_str(line);
+                test.document.push('\n');
+            }
+            "#document-fragment" => test.fragment = Some(line.to_string()),
+            _ => {}
+        }
+    }
+    finish(current, &mut tests);
+
+    tests
+}
+
+/// The context element of a fragment test, e.g. `td` or `svg path`
+fn context_element(context: &str) -> NodeRef {
+    let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
+    match context.split_once(' ') {
+        Some(("svg", tag_name)) => {
+            dom::create_element_ns(document.downgrade(), tag_name, Namespace::Svg)
+        }
+        Some(("math", tag_name)) => {
+            dom::create_element_ns(document.downgrade(), tag_name, Namespace::MathML)
+        }
+        _ => dom::create_element(document.downgrade(), context),
+    }
+}
+
+fn run_tree_test(test: &TreeTest) -> Outcome {
+    // scripting is not supported by the tree builder
+    if test.script_on {
+        return Outcome::Skip;
+    }
+
+    let result = run_guarded(|| {
+        let tokenizer = Tokenizer::new(test.data.chars());
+        let tree = match &test.fragment {
+            Some(context) => {
+                TreeBuilder::new_fragment(tokenizer, context_element(context)).run_fragment()
+            }
+            None => TreeBuilder::default(tokenizer).run(),
+        };
+        testing::snapshot(&tree)
+    });
+
+    match result {
+        Some(tree) if tree == test.document => Outcome::Pass,
+        _ => Outcome::Fail,
+    }
+}
+
+#[test]
+fn html5lib_tree_construction() {
+    let (root, bundled) = fixtures_dir();
+    let mut report = Report::default();
+
+    for path in list_files(&root.join("tree-construction"), "dat") {
+        let content = fs::read_to_string(&path).unwrap();
+
+        for (index, test) in parse_dat(&content).iter().enumerate() {
+            let name = format!("{} #{}: {:?}", file_name(&path), index + 1, test.data);
+            report.record(name, run_tree_test(test));
+        }
+    }
+
+    report.print("tree-construction");
+    if bundled {
+        assert!(
+            report.failed.is_empty(),
+            "html5lib tree construction tests failed"
+        );
+    }
+}
@@ -0,0 +1,89 @@
+/// Cooperative cancellation of the work on a document. A token is shared
+/// between the job & whoever can abort it, e.g. another thread or a
+/// timer, and the long running stages check it as they go, stopping
+/// early with what they have done so far.
+use std::cell::RefCell;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Clone, Default)]
+pub struct CancellationToken {
+    cancelled: Arc<AtomicBool>,
+    /// The time after which the token is cancelled on its own
+    deadline: Option<Instant>,
+}
+
+impl CancellationToken {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A token cancelled once a duration elapsed from now, or before
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            cancelled: Arc::default(),
+            deadline: Some(Instant::now() + timeout),
+        }
+    }
+
+    /// Cancel the job of the token & of its clones
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Relaxed);
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Relaxed)
+            || self
+                .deadline
+                .map_or(false, |deadline| Instant::now() >= deadline)
+    }
+}
+
+thread_local! {
+    /// The token of the work running on the thread, checked by the
+    /// stages without a token of their own
+    static CURRENT: RefCell<Option<CancellationToken>> = RefCell::new(None);
+}
+
+/// Run a function with a token as the token of the thread, e.g. to style
+/// & lay out a document until the token is cancelled
+pub fn with_token<T>(token: &CancellationToken, f: impl FnOnce() -> T) -> T {
+    let previous = CURRENT.with(|current| current.replace(Some(token.clone())));
+    let result = f();
+    CURRENT.with(|current| *current.borrow_mut() = previous);
+    result
+}
+
+/// Whether the token of the thread is cancelled. The work outside of
+/// `with_token` is never cancelled.
+pub fn is_cancelled() -> bool {
+    CURRENT.with(|current| {
+        current
+            .borrow()
+            .as_ref()
+            .map_or(false, |token| token.is_cancelled())
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cancel_tokens() {
+        let token = CancellationToken::new();
+        let clone = token.clone();
+        assert!(!token.is_cancelled());
+        clone.cancel();
+        assert!(token.is_cancelled());
+
+        assert!(CancellationToken::with_timeout(Duration::ZERO).is_cancelled());
+        assert!(!CancellationToken::with_timeout(Duration::from_secs(60)).is_cancelled());
+
+        assert!(!is_cancelled());
+        assert!(with_token(&token, is_cancelled));
+        assert!(!with_token(&CancellationToken::new(), is_cancelled));
+        assert!(!is_cancelled());
+    }
+}
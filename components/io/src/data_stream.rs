@@ -25,6 +25,49 @@ impl<T: Clone> DataStream<T> {
         self.data.iter().skip(self.index).take(len).collect()
     }
 
+    /// The item `n` items after the next item, `lookahead(0)` being the
+    /// item returned by `peek()`
+    pub fn lookahead(&self, n: usize) -> Option<&T> {
+        self.data.get(self.index + n)
+    }
+
+    /// Save the position of the stream, to go back to it with `rollback()`
+    pub fn checkpoint(&self) -> Checkpoint {
+        Checkpoint(self.index)
+    }
+
+    /// Go back to a saved position, so the items consumed since are
+    /// returned again. The items moved out with `take_next()` since the
+    /// checkpoint are not restored.
+    pub fn rollback(&mut self, checkpoint: Checkpoint) {
+        self.index = checkpoint.0;
+    }
+
+    /// Iterate over the remaining items, consuming each item returned
+    pub fn iter(&mut self) -> Iter<'_, T> {
+        Iter {
+            data: &self.data,
+            index: &mut self.index,
+        }
+    }
+
+    /// Consume the next item if it matches a predicate
+    pub fn next_if<F>(&mut self, predicate: F) -> Option<&T>
+    where
+        F: FnOnce(&T) -> bool,
+    {
+        let current = self.data.get(self.index).filter(|item| predicate(item));
+        if current.is_some() {
+            self.index += 1;
+        }
+        current
+    }
+
+    /// Index of the next item to be returned by `next()`
+    pub fn index(&self) -> usize {
+        self.index
+    }
+
     pub fn is_eos(&self) -> bool {
         self.index >= self.data.len()
     }
@@ -34,4 +77,74 @@ impl<T: Clone> DataStream<T> {
         self.index += 1;
         return current;
     }
+
+    /// Same as `next()`, but move the item out of the stream instead of
+    /// borrowing it, leaving `placeholder` in its place
+    pub fn take_next(&mut self, placeholder: T) -> Option<T> {
+        let current = self
+            .data
+            .get_mut(self.index)
+            .map(|item| std::mem::replace(item, placeholder));
+        self.index += 1;
+        return current;
+    }
+}
+
+/// A saved position of a data stream
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Checkpoint(usize);
+
+/// The remaining items of a data stream, consumed as they are iterated
+pub struct Iter<'a, T> {
+    data: &'a [T],
+    index: &'a mut usize,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let current = self.data.get(*self.index)?;
+        *self.index += 1;
+        Some(current)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn look_ahead_and_roll_back() {
+        let mut stream = DataStream::new(vec![1, 2, 3, 4]);
+        assert_eq!(stream.lookahead(0), Some(&1));
+        assert_eq!(stream.lookahead(2), Some(&3));
+        assert_eq!(stream.lookahead(4), None);
+
+        let checkpoint = stream.checkpoint();
+        assert_eq!(stream.iter().take(2).copied().collect::<Vec<_>>(), [1, 2]);
+        assert_eq!(stream.next_if(|item| *item == 4), None);
+        assert_eq!(stream.next_if(|item| *item == 3), Some(&3));
+        assert_eq!(stream.peek(), Some(&4));
+
+        stream.rollback(checkpoint);
+        assert_eq!(stream.index(), 0);
+        assert_eq!(stream.iter().count(), 4);
+        assert!(stream.is_eos());
+    }
+
+    #[test]
+    fn take_items_out() {
+        let mut stream = DataStream::new(vec!["a".to_string(), "b".to_string()]);
+        let checkpoint = stream.checkpoint();
+        assert_eq!(stream.take_next(String::new()), Some("a".to_string()));
+        assert_eq!(stream.peek(), Some(&"b".to_string()));
+
+        // the items taken out are replaced by the placeholder
+        stream.rollback(checkpoint);
+        assert_eq!(stream.take_next(String::new()), Some(String::new()));
+        assert_eq!(stream.take_next(String::new()), Some("b".to_string()));
+        assert_eq!(stream.take_next(String::new()), None);
+        assert!(stream.is_eos());
+    }
 }
@@ -1,14 +1,19 @@
+use super::position::{Positioned, SourcePosition};
 use std::{collections::VecDeque, iter::FromIterator};
 
 pub struct InputStream<T, I>
 where
     T: Iterator<Item = I>,
-    I: Clone,
+    I: Clone + Positioned,
 {
     source: T,
     is_reconsume: bool,
     last_consumed: Option<I>,
     buffer: VecDeque<I>,
+    // position of the next item to be consumed
+    next_position: SourcePosition,
+    // position of the last consumed item
+    position: SourcePosition,
 }
 
 pub type CharInputStream<T> = InputStream<T, char>;
@@ -16,7 +21,7 @@ pub type CharInputStream<T> = InputStream<T, char>;
 impl<T, I> InputStream<T, I>
 where
     T: Iterator<Item = I>,
-    I: Clone,
+    I: Clone + Positioned,
 {
     pub fn new(source: T) -> Self {
         Self {
@@ -24,6 +29,22 @@ where
             is_reconsume: false,
             last_consumed: None,
             buffer: VecDeque::new(),
+            next_position: SourcePosition::start(),
+            position: SourcePosition::start(),
+        }
+    }
+
+    /// The position of the last consumed item in the source
+    pub fn position(&self) -> SourcePosition {
+        self.position
+    }
+
+    /// The position of the item that will be returned by `next()`
+    pub fn next_position(&self) -> SourcePosition {
+        if self.is_reconsume {
+            self.position
+        } else {
+            self.next_position
         }
     }
 
@@ -46,6 +67,10 @@ where
         self.consume_source_to_buffer();
 
         let consumed = self.buffer.pop_front();
+        self.position = self.next_position;
+        if let Some(item) = &consumed {
+            item.advance(&mut self.next_position);
+        }
         self.last_consumed = consumed.clone();
         consumed
     }
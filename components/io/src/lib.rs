@@ -1,2 +1,5 @@
+pub mod cancellation;
 pub mod data_stream;
 pub mod input_stream;
+pub mod parse_error;
+pub mod position;
@@ -0,0 +1,59 @@
+use super::position::SourcePosition;
+use std::cell::RefCell;
+use std::rc::Rc;
+
+/// A parse error reported by one of the tokenizers or parsers
+#[derive(Debug, Clone, PartialEq)]
+pub struct ParseError {
+    /// The stage that reported the error. For example: `html-tokenizer`
+    pub origin: &'static str,
+    /// A short, machine readable error code. For example: `unexpected-null-character`
+    pub code: &'static str,
+    /// A human readable description of the error
+    pub message: String,
+    /// Where the error occurred in the source, if known
+    pub position: Option<SourcePosition>,
+}
+
+/// Receiver of the parse errors emitted during parsing
+pub trait ParseErrorSink {
+    fn report(&mut self, error: ParseError);
+}
+
+pub type ParseErrorSinkRef = Rc<RefCell<dyn ParseErrorSink>>;
+
+/// Collect all reported errors
+impl ParseErrorSink for Vec<ParseError> {
+    fn report(&mut self, error: ParseError) {
+        self.push(error);
+    }
+}
+
+impl ParseError {
+    pub fn new(origin: &'static str, code: &'static str, message: &str) -> Self {
+        Self {
+            origin,
+            code,
+            message: message.to_string(),
+            position: None,
+        }
+    }
+
+    pub fn at(mut self, position: SourcePosition) -> Self {
+        self.position = Some(position);
+        self
+    }
+}
+
+impl std::fmt::Display for ParseError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match &self.position {
+            Some(position) => write!(
+                f,
+                "[{}] {} (offset {}): {}: {}",
+                self.origin, position, position.offset, self.code, self.message
+            ),
+            None => write!(f, "[{}] {}: {}", self.origin, self.code, self.message),
+        }
+    }
+}
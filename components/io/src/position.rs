@@ -0,0 +1,69 @@
+/// A position in the source text
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct SourcePosition {
+    /// Byte offset from the start of the source
+    pub offset: usize,
+    /// 1-based line number
+    pub line: usize,
+    /// 1-based column number, counted in characters
+    pub column: usize,
+}
+
+impl SourcePosition {
+    pub fn start() -> Self {
+        Self {
+            offset: 0,
+            line: 1,
+            column: 1,
+        }
+    }
+}
+
+impl Default for SourcePosition {
+    fn default() -> Self {
+        Self::start()
+    }
+}
+
+impl std::fmt::Display for SourcePosition {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A range in the source text, from `start` (inclusive) to `end` (exclusive)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct SourceSpan {
+    pub start: SourcePosition,
+    pub end: SourcePosition,
+}
+
+impl SourceSpan {
+    pub fn new(start: SourcePosition, end: SourcePosition) -> Self {
+        Self { start, end }
+    }
+}
+
+impl std::fmt::Display for SourceSpan {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        write!(f, "{}", self.start)
+    }
+}
+
+/// Items of an input stream that take up space in the source text
+pub trait Positioned {
+    /// Move the position past this item
+    fn advance(&self, position: &mut SourcePosition);
+}
+
+impl Positioned for char {
+    fn advance(&self, position: &mut SourcePosition) {
+        position.offset += self.len_utf8();
+        if *self == '\n' {
+            position.line += 1;
+            position.column = 1;
+        } else {
+            position.column += 1;
+        }
+    }
+}
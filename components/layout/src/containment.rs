@@ -0,0 +1,48 @@
+/// The containment of the boxes by their `contain` property. The size of
+/// a box with size containment doesn't depend on its contents, and the
+/// contents of a box with size & layout containment are laid out again
+/// on their own when they change, without the rest of the page.
+/// https://www.w3.org/TR/css-contain-2/#containment-types
+use super::formatting_context::layout_children;
+use super::layout_box::{BoxType, LayoutBox};
+
+/// Whether a box can be contained, unlike the internal table boxes & the
+/// inline boxes split into lines
+pub fn applies_to(layout_box: &LayoutBox) -> bool {
+    match layout_box.box_type {
+        BoxType::Block | BoxType::Table => true,
+        BoxType::Inline => layout_box.is_inline_block(),
+        _ => false,
+    }
+}
+
+/// Whether a box is sized as if it had no content
+pub fn contains_size(layout_box: &LayoutBox) -> bool {
+    match &layout_box.render_node {
+        Some(node) if applies_to(layout_box) => node.borrow().style().contain().size,
+        _ => false,
+    }
+}
+
+/// Whether the contents of a box are laid out again on their own
+pub fn is_layout_root(layout_box: &LayoutBox) -> bool {
+    match &layout_box.render_node {
+        Some(node) if applies_to(layout_box) => node.borrow().is_layout_root(),
+        _ => false,
+    }
+}
+
+/// Lay out the contents of the layout roots rebuilt inside the boxes
+/// whose layout was reused. The roots keep the position & the size of
+/// their previous layout.
+pub fn layout_dirty_roots(layout_box: &mut LayoutBox) {
+    let was_laid_out = layout_box.containing_size.is_some();
+    if layout_box.needs_layout && was_laid_out && is_layout_root(layout_box) {
+        layout_children(layout_box);
+        layout_box.needs_layout = false;
+        return;
+    }
+    for child in &mut layout_box.children {
+        layout_dirty_roots(child);
+    }
+}
@@ -0,0 +1,61 @@
+/// Skipping the layout of the contents of the boxes by their
+/// `content-visibility`. The contents of a `hidden` box are never laid
+/// out, the contents of an `auto` box are skipped while they're outside
+/// the area visible to the user, e.g. below the fold of a screenshot.
+/// A box skipping its contents is sized as if it had no content.
+/// https://www.w3.org/TR/css-contain-2/#content-visibility
+use super::box_model::Rect;
+use super::containment;
+use super::layout_box::LayoutBox;
+use std::cell::Cell;
+use style::values::content_visibility::ContentVisibility;
+
+thread_local! {
+    /// The top & bottom edges of the area visible to the user during a
+    /// lazy layout. Every content is relevant outside of a lazy layout.
+    static VISIBLE_AREA: Cell<Option<(f32, f32)>> = Cell::new(None);
+}
+
+/// Run a layout in which only the content inside an area is relevant
+pub fn with_visible_area<R>(area: &Rect, layout: impl FnOnce() -> R) -> R {
+    let edges = Some((area.y, area.y + area.height));
+    let previous = VISIBLE_AREA.with(|visible_area| visible_area.replace(edges));
+    let result = layout();
+    VISIBLE_AREA.with(|visible_area| visible_area.set(previous));
+    result
+}
+
+/// Whether the layout of the children of a box is skipped, once the box
+/// is positioned & its definite height is known
+pub fn skips_contents(layout_box: &LayoutBox) -> bool {
+    let node = match &layout_box.render_node {
+        Some(node) if containment::applies_to(layout_box) => node.clone(),
+        _ => return false,
+    };
+    let content_visibility = node.borrow().style().content_visibility();
+
+    match content_visibility {
+        ContentVisibility::Visible => false,
+        ContentVisibility::Hidden => true,
+        // the inline-level boxes are placed on the lines after their
+        // contents are laid out
+        ContentVisibility::Auto if layout_box.is_inline() => false,
+        ContentVisibility::Auto => match VISIBLE_AREA.with(Cell::get) {
+            Some((top, bottom)) => {
+                let border_box = layout_box.dimensions.border_box();
+                // the bottom edge of a box with an auto height is unknown
+                let is_above =
+                    layout_box.definite_height.is_some() && border_box.y + border_box.height <= top;
+                border_box.y >= bottom || is_above
+            }
+            None => false,
+        },
+    }
+}
+
+/// Whether one of the boxes or their descendants skipped its contents
+pub fn has_skipped_contents(boxes: &[LayoutBox]) -> bool {
+    boxes
+        .iter()
+        .any(|layout_box| layout_box.skipped_descendants)
+}
@@ -1,6 +1,12 @@
 use crate::box_model::{BoxComponent, Edge};
-use crate::formatting_context::{apply_explicit_sizes, layout_children, FormattingContext};
+use crate::formatting_context::{
+    apply_explicit_sizes, apply_specified_height, layout_children, FormattingContext,
+};
 use crate::layout_box::LayoutBox;
+use crate::list_marker::layout_marker;
+use crate::percentage::PercentageBases;
+use crate::replaced;
+use crate::sizing;
 use style::value_processing::Property;
 
 #[derive(Debug)]
@@ -33,95 +39,61 @@ impl BlockFormattingContext {
             None => return,
         };
 
-        let containing_block = &self.get_containing_block().dimensions.content;
+        let bases = PercentageBases::of(self.get_containing_block());
 
         let render_node = render_node.borrow();
-        let computed_width = render_node.get_style(&Property::Width);
-        let computed_margin_left = render_node.get_style(&Property::MarginLeft);
-        let computed_margin_right = render_node.get_style(&Property::MarginRight);
-        let computed_border_left = render_node.get_style(&Property::BorderLeftWidth);
-        let computed_border_right = render_node.get_style(&Property::BorderRightWidth);
-        let computed_padding_left = render_node.get_style(&Property::PaddingLeft);
-        let computed_padding_right = render_node.get_style(&Property::PaddingRight);
-        let containing_width = containing_block.width;
-
-        let box_width = computed_margin_left.to_px(containing_width)
-            + computed_border_left.to_px(containing_width)
-            + computed_padding_left.to_px(containing_width)
-            + computed_width.to_px(containing_width)
-            + computed_padding_right.to_px(containing_width)
-            + computed_border_right.to_px(containing_width)
-            + computed_margin_right.to_px(containing_width);
-
-        let mut used_width = computed_width.to_px(containing_width);
-        let mut used_margin_left = computed_margin_left.to_px(containing_width);
-        let mut used_margin_right = computed_margin_right.to_px(containing_width);
+        let style = render_node.style();
+        let computed_width = style.width();
+        let computed_border_left = style.border_left_width();
+        let computed_border_right = style.border_right_width();
+        let used_padding_left = bases.resolve(&Property::PaddingLeft, &style.padding_left());
+        let used_padding_right = bases.resolve(&Property::PaddingRight, &style.padding_right());
+        let margins = HorizontalMargins {
+            left: bases.resolve_auto(&Property::MarginLeft, &style.margin_left()),
+            right: bases.resolve_auto(&Property::MarginRight, &style.margin_right()),
+        };
+
+        let specified_width = sizing::specified_width(style, &bases);
+        let mut used_width = specified_width.unwrap_or(0.);
+        let mut used_margin_left = margins.left.unwrap_or(0.);
+        let mut used_margin_right = margins.right.unwrap_or(0.);
 
         // 3. block-level, non-replaced elements in normal flow
         if layout_box.is_non_replaced() {
-            if !computed_width.is_auto() && box_width > containing_width {
-                if computed_margin_left.is_auto() {
-                    used_margin_left = 0.0;
-                }
-                if computed_margin_right.is_auto() {
-                    used_margin_right = 0.0;
+            let edges = computed_border_left
+                + used_padding_left
+                + used_padding_right
+                + computed_border_right;
+            let solve = |width| solve_width(width, &margins, edges, bases.width);
+
+            // 10.4 the tentative used width is computed again with the
+            // maximum, then the minimum width in place of 'width' when it
+            // is out of their range
+            let (max_width, min_width) = sizing::width_constraints(style, &bases);
+            let mut used = solve(specified_width);
+            if let Some(max_width) = max_width {
+                if used.0 > max_width {
+                    used = solve(Some(max_width));
                 }
             }
+            if used.0 < min_width {
+                used = solve(Some(min_width));
+            }
+            let (width, margin_left, margin_right) = used;
+            used_width = width;
+            used_margin_left = margin_left;
+            used_margin_right = margin_right;
+        }
 
-            let underflow = containing_width - box_width;
-
-            match (
-                computed_width.is_auto(),
-                computed_margin_left.is_auto(),
-                computed_margin_right.is_auto(),
-            ) {
-                // If all of the above have a computed value other than 'auto',
-                // the values are said to be "over-constrained" and one of the
-                // used values will have to be different from its computed value.
-                // If the 'direction' property of the containing block has the
-                // value 'ltr', the specified value of 'margin-right' is ignored
-                // and the value is calculated so as to make the equality true.
-                // If the value of 'direction' is 'rtl', this happens to
-                // 'margin-left' instead.
-                (false, false, false) => {
-                    // TODO: support direction rtl
-                    used_margin_right = computed_margin_right.to_px(containing_width) + underflow;
-                }
-                // If there is exactly one value specified as 'auto',
-                // its used value follows from the equality.
-                (false, true, false) => {
-                    used_margin_left = underflow;
-                }
-                (false, false, true) => {
-                    used_margin_right = underflow;
-                }
-                // If 'width' is set to 'auto', any other 'auto' values become '0'
-                // and 'width' follows from the resulting equality.
-                (true, _, _) => {
-                    if computed_margin_left.is_auto() {
-                        used_margin_left = 0.0;
-                    }
-                    if computed_margin_right.is_auto() {
-                        used_margin_right = 0.0;
-                    }
-
-                    if underflow >= 0. {
-                        used_width = underflow;
-                    } else {
-                        used_width = 0.;
-                        used_margin_right =
-                            computed_margin_right.to_px(containing_width) + underflow;
-                    }
-                }
-                // If both 'margin-left' and 'margin-right' are 'auto', their
-                // used values are equal. This horizontally centers the element
-                // with respect to the edges of the containing block.
-                (false, true, true) => {
-                    let half_underflow = underflow / 2.;
-                    used_margin_left = half_underflow;
-                    used_margin_right = half_underflow;
+        // 10.3.4 block-level, replaced elements in normal flow
+        // The used value of 'width' is determined as for inline replaced elements.
+        if !layout_box.is_non_replaced() {
+            if computed_width.is_auto() {
+                if let Some(width) = replaced::intrinsic_width(layout_box, &bases) {
+                    used_width = width;
                 }
             }
+            used_width = sizing::clamp_width(style, used_width, &bases);
         }
 
         // apply all calculated used values
@@ -129,26 +101,10 @@ impl BlockFormattingContext {
         box_model.set_width(used_width);
         box_model.set(BoxComponent::Margin, Edge::Left, used_margin_left);
         box_model.set(BoxComponent::Margin, Edge::Right, used_margin_right);
-        box_model.set(
-            BoxComponent::Padding,
-            Edge::Left,
-            computed_padding_left.to_px(containing_width),
-        );
-        box_model.set(
-            BoxComponent::Padding,
-            Edge::Right,
-            computed_padding_right.to_px(containing_width),
-        );
-        box_model.set(
-            BoxComponent::Border,
-            Edge::Left,
-            computed_border_left.to_px(containing_width),
-        );
-        box_model.set(
-            BoxComponent::Border,
-            Edge::Right,
-            computed_border_right.to_px(containing_width),
-        );
+        box_model.set(BoxComponent::Padding, Edge::Left, used_padding_left);
+        box_model.set(BoxComponent::Padding, Edge::Right, used_padding_right);
+        box_model.set(BoxComponent::Border, Edge::Left, computed_border_left);
+        box_model.set(BoxComponent::Border, Edge::Right, computed_border_right);
     }
 
     fn update_new_data(&mut self, layout_box: &LayoutBox) {
@@ -159,6 +115,7 @@ impl BlockFormattingContext {
 
     fn calculate_position(&mut self, layout_box: &mut LayoutBox) {
         let containing_block = self.get_containing_block();
+        let bases = PercentageBases::of(containing_block);
         let containing_block = &containing_block.dimensions.content.clone();
 
         let render_node = layout_box.render_node.clone();
@@ -166,27 +123,20 @@ impl BlockFormattingContext {
 
         if let Some(render_node) = render_node {
             let render_node = render_node.borrow();
+            let style = render_node.style();
+
+            let margin_top = bases
+                .resolve_auto(&Property::MarginTop, &style.margin_top())
+                .unwrap_or(0.);
+            let margin_bottom = bases
+                .resolve_auto(&Property::MarginBottom, &style.margin_bottom())
+                .unwrap_or(0.);
+
+            let border_top = style.border_top_width();
+            let border_bottom = style.border_bottom_width();
 
-            let margin_top = render_node
-                .get_style(&Property::MarginTop)
-                .to_px(containing_block.width);
-            let margin_bottom = render_node
-                .get_style(&Property::MarginBottom)
-                .to_px(containing_block.width);
-
-            let border_top = render_node
-                .get_style(&Property::BorderTopWidth)
-                .to_px(containing_block.width);
-            let border_bottom = render_node
-                .get_style(&Property::BorderBottomWidth)
-                .to_px(containing_block.width);
-
-            let padding_top = render_node
-                .get_style(&Property::PaddingTop)
-                .to_px(containing_block.width);
-            let padding_bottom = render_node
-                .get_style(&Property::PaddingBottom)
-                .to_px(containing_block.width);
+            let padding_top = bases.resolve(&Property::PaddingTop, &style.padding_top());
+            let padding_bottom = bases.resolve(&Property::PaddingBottom, &style.padding_bottom());
 
             box_model.set(BoxComponent::Margin, Edge::Top, margin_top);
             box_model.set(BoxComponent::Margin, Edge::Bottom, margin_bottom);
@@ -214,16 +164,82 @@ impl BlockFormattingContext {
     }
 }
 
+/// The horizontal margins of a box, `None` being `auto`
+struct HorizontalMargins {
+    left: Option<f32>,
+    right: Option<f32>,
+}
+
+/// The used width & horizontal margins of a block-level, non-replaced
+/// box in normal flow, for a width of the content box or `auto`
+/// https://www.w3.org/TR/CSS2/visudet.html#blockwidth
+fn solve_width(
+    width: Option<f32>,
+    margins: &HorizontalMargins,
+    edges: f32,
+    containing_width: f32,
+) -> (f32, f32, f32) {
+    let box_width =
+        margins.left.unwrap_or(0.) + edges + width.unwrap_or(0.) + margins.right.unwrap_or(0.);
+
+    let underflow = containing_width - box_width;
+
+    match (width, margins.left, margins.right) {
+        // If all of the above have a computed value other than 'auto',
+        // the values are said to be "over-constrained" and one of the
+        // used values will have to be different from its computed value.
+        // If the 'direction' property of the containing block has the
+        // value 'ltr', the specified value of 'margin-right' is ignored
+        // and the value is calculated so as to make the equality true.
+        // If the value of 'direction' is 'rtl', this happens to
+        // 'margin-left' instead.
+        // TODO: support direction rtl
+        (Some(width), Some(left), Some(right)) => (width, left, right + underflow),
+        // If there is exactly one value specified as 'auto',
+        // its used value follows from the equality.
+        (Some(width), None, Some(right)) => (width, underflow, right),
+        (Some(width), Some(left), None) => (width, left, underflow),
+        // If both 'margin-left' and 'margin-right' are 'auto', their
+        // used values are equal. This horizontally centers the element
+        // with respect to the edges of the containing block.
+        (Some(width), None, None) => (width, underflow / 2., underflow / 2.),
+        // If 'width' is set to 'auto', any other 'auto' values become '0'
+        // and 'width' follows from the resulting equality.
+        (None, left, right) => {
+            let (left, right) = (left.unwrap_or(0.), right.unwrap_or(0.));
+            if underflow >= 0. {
+                (underflow, left, right)
+            } else {
+                (0., left, right + underflow)
+            }
+        }
+    }
+}
+
 impl FormattingContext for BlockFormattingContext {
     fn layout(&mut self, boxes: Vec<&mut LayoutBox>) -> f32 {
         let containing_block = self.get_containing_block();
+        let bases = PercentageBases::of(containing_block);
         let containing_block = &containing_block.dimensions.content.clone();
 
         for layout_box in boxes {
-            self.calculate_width(layout_box);
-            self.calculate_position(layout_box);
-            layout_children(layout_box);
-            apply_explicit_sizes(layout_box, containing_block);
+            if layout_box.can_reuse_layout(containing_block) {
+                // only the position of a clean box can change
+                let previous = layout_box.dimensions.content.clone();
+                self.calculate_position(layout_box);
+                let current = &layout_box.dimensions.content;
+                let (dx, dy) = (current.x - previous.x, current.y - previous.y);
+                layout_box.translate_children(dx, dy);
+                layout_marker(layout_box);
+            } else {
+                self.calculate_width(layout_box);
+                self.calculate_position(layout_box);
+                apply_specified_height(layout_box, &bases);
+                layout_marker(layout_box);
+                layout_children(layout_box);
+                apply_explicit_sizes(layout_box, &bases);
+                layout_box.finish_layout(containing_block);
+            }
             self.update_new_data(layout_box);
         }
 
@@ -238,10 +254,13 @@ impl FormattingContext for BlockFormattingContext {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::box_model::Rect;
     use crate::layout_box::BoxType;
     use crate::tree_builder::*;
     use css::cssom::css_rule::CSSRule;
+    use dom::node::Node;
     use style::build_render_tree;
+    use style::restyle::restyle_render_tree;
     use style::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
     use test_utils::css::parse_stylesheet;
     use test_utils::dom_creator::*;
@@ -272,12 +291,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -300,4 +320,464 @@ mod tests {
         assert_eq!(formatting_context.base.height, 40.);
         assert_eq!(formatting_context.base.offset_y, 40.);
     }
+
+    #[test]
+    fn test_relayout_dirty_boxes_only() {
+        let document = document();
+        let first = element("div.box", document.clone(), vec![]);
+        let second = element(
+            "div.box",
+            document.clone(),
+            vec![element("div.box", document.clone(), vec![])],
+        );
+        let dom = element("div", document.clone(), vec![first.clone(), second]);
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        .box {
+            height: 10px;
+        }
+        .tall {
+            height: 30px;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 100.,
+            height: 100.,
+        };
+
+        let mut render_tree = build_render_tree(dom.clone(), &rules);
+        let mut layout_box = crate::build_layout_tree(&render_tree).unwrap();
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        assert_eq!(layout_box.children[1].dimensions.content.y, 10.);
+        assert_eq!(layout_box.children[1].children[0].dimensions.content.y, 10.);
+
+        Node::set_attribute(&first, "class", "tall");
+        restyle_render_tree(&mut render_tree, &rules);
+
+        let mut layout_box = crate::rebuild_layout_tree(&render_tree, layout_box).unwrap();
+        assert!(layout_box.needs_layout);
+        assert!(layout_box.children[0].needs_layout);
+        assert!(!layout_box.children[1].needs_layout);
+
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        // the clean box is moved with its children instead of being laid out again
+        assert_eq!(layout_box.children[0].dimensions.content.height, 30.);
+        assert_eq!(layout_box.children[1].dimensions.content.y, 30.);
+        assert_eq!(layout_box.children[1].children[0].dimensions.content.y, 30.);
+        assert_eq!(layout_box.dimensions.content.height, 40.);
+    }
+
+    #[test]
+    fn measure_overflowing_content() {
+        let document = document();
+        let dom = element(
+            "div.short",
+            document.clone(),
+            vec![
+                element("div.box", document.clone(), vec![]),
+                element("div.tall", document.clone(), vec![]),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        .short {
+            height: 20px;
+            margin-bottom: 5px;
+        }
+        .box {
+            height: 10px;
+        }
+        .tall {
+            height: 300px;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 100.,
+            height: 100.,
+        };
+
+        let render_tree = build_render_tree(dom.clone(), &rules);
+        let mut layout_box = crate::build_layout_tree(&render_tree).unwrap();
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        assert_eq!(layout_box.dimensions.margin_box().height, 25.);
+        // the children overflowing the box are part of the content
+        assert_eq!(layout_box.content_bottom(), 310.);
+        assert_eq!(layout_box.children[0].content_bottom(), 10.);
+    }
+
+    #[test]
+    fn skip_contents_by_content_visibility() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element("div.box", document.clone(), vec![]),
+                element(
+                    "div.auto",
+                    document.clone(),
+                    vec![element("div.box", document.clone(), vec![])],
+                ),
+                element(
+                    "div.hidden",
+                    document.clone(),
+                    vec![element("div.box", document.clone(), vec![])],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        .box {
+            height: 40px;
+        }
+        .auto {
+            content-visibility: auto;
+        }
+        .hidden {
+            content-visibility: hidden;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 100.,
+            height: 100.,
+        };
+        let visible_area = Rect {
+            height: 30.,
+            ..viewport.clone()
+        };
+
+        let render_tree = build_render_tree(dom.clone(), &rules);
+        let mut layout_box = crate::build_layout_tree(&render_tree).unwrap();
+        crate::compute_lazy_layout(&mut layout_box, &viewport, &visible_area);
+
+        // the boxes skipping their contents are sized as if they were empty
+        assert!(layout_box.children[1].contents_skipped);
+        assert!(layout_box.children[2].contents_skipped);
+        assert_eq!(layout_box.children[1].dimensions.content.height, 0.);
+        assert_eq!(layout_box.children[2].dimensions.content.y, 40.);
+        assert_eq!(layout_box.dimensions.content.height, 40.);
+        assert!(layout_box.skipped_descendants);
+
+        // the skipped contents are laid out once they're relevant
+        crate::compute_layout(&mut layout_box, &viewport);
+        assert!(!layout_box.children[1].contents_skipped);
+        assert!(layout_box.children[2].contents_skipped);
+        assert_eq!(layout_box.children[1].dimensions.content.height, 40.);
+        assert_eq!(layout_box.children[1].children[0].dimensions.content.y, 40.);
+        assert_eq!(layout_box.children[2].dimensions.content.y, 80.);
+        assert_eq!(layout_box.dimensions.content.height, 80.);
+    }
+
+    #[test]
+    fn relayout_contained_subtrees_only() {
+        let document = document();
+        let inner = element("div.box", document.clone(), vec![]);
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element("div.root", document.clone(), vec![inner.clone()]),
+                element(
+                    "div.sized",
+                    document.clone(),
+                    vec![element("div.box", document.clone(), vec![])],
+                ),
+                element("div.box", document.clone(), vec![]),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        .root {
+            contain: strict;
+            height: 50px;
+        }
+        .sized {
+            contain: size;
+        }
+        .box {
+            height: 10px;
+        }
+        .tall {
+            height: 30px;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 100.,
+            height: 100.,
+        };
+
+        let mut render_tree = build_render_tree(dom.clone(), &rules);
+        let mut layout_box = crate::build_layout_tree(&render_tree).unwrap();
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        // the size contained box is sized as if it was empty
+        assert_eq!(layout_box.children[1].dimensions.content.height, 0.);
+        assert_eq!(layout_box.children[1].content_bottom(), 60.);
+        assert_eq!(layout_box.children[2].dimensions.content.y, 50.);
+
+        Node::set_attribute(&inner, "class", "tall");
+        restyle_render_tree(&mut render_tree, &rules);
+
+        // the change doesn't leave the layout root
+        let mut layout_box = crate::rebuild_layout_tree(&render_tree, layout_box).unwrap();
+        assert!(!layout_box.needs_layout);
+        assert!(layout_box.children[0].needs_layout);
+        assert!(!layout_box.children[2].needs_layout);
+
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        assert!(!layout_box.children[0].needs_layout);
+        assert_eq!(
+            layout_box.children[0].children[0].dimensions.content.height,
+            30.
+        );
+        assert_eq!(layout_box.children[0].dimensions.content.height, 50.);
+        assert_eq!(layout_box.children[2].dimensions.content.y, 50.);
+        assert_eq!(layout_box.dimensions.content.height, 60.);
+    }
+
+    #[test]
+    fn clamp_sizes_with_box_sizing() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element("div.max", document.clone(), vec![]),
+                element("div.min", document.clone(), vec![]),
+                element("div.border-box", document.clone(), vec![]),
+                element(
+                    "div.max-height",
+                    document.clone(),
+                    vec![element("div.tall", document.clone(), vec![])],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        .max {
+            max-width: 50%;
+            margin-left: auto;
+            margin-right: auto;
+            min-height: 15px;
+        }
+        .min {
+            width: 10px;
+            min-width: 30px;
+            max-width: 20px;
+        }
+        .border-box {
+            box-sizing: border-box;
+            width: 60px;
+            height: 30px;
+            padding: 5px;
+            border-width: 0 0 0 2px;
+        }
+        .max-height {
+            max-height: 20px;
+        }
+        .tall {
+            height: 100px;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 200.,
+            height: 100.,
+        };
+
+        let render_tree = build_render_tree(dom.clone(), &rules);
+        let mut layout_box = crate::build_layout_tree(&render_tree).unwrap();
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        // the auto width is limited & the box is centered with the margins
+        let max = &layout_box.children[0].dimensions;
+        assert_eq!(max.content.width, 100.);
+        assert_eq!(max.margin.left, 50.);
+        assert_eq!(max.content.height, 15.);
+
+        // the minimum width wins over the maximum width
+        assert_eq!(layout_box.children[1].dimensions.content.width, 30.);
+
+        let border_box = &layout_box.children[2].dimensions;
+        assert_eq!(border_box.content.width, 60. - 2. - 10.);
+        assert_eq!(border_box.content.height, 20.);
+        assert_eq!(border_box.border_box().width, 60.);
+
+        // the content overflows the height
+        assert_eq!(layout_box.children[3].dimensions.content.height, 20.);
+        assert_eq!(layout_box.children[3].content_bottom(), 45. + 100.);
+    }
+
+    #[test]
+    fn resolve_percentage_heights() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element(
+                    "div.fixed",
+                    document.clone(),
+                    vec![element("div.half", document.clone(), vec![])],
+                ),
+                element(
+                    "div.auto",
+                    document.clone(),
+                    vec![element(
+                        "div.half",
+                        document.clone(),
+                        vec![element("div.box", document.clone(), vec![])],
+                    )],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        .fixed {
+            height: 40px;
+        }
+        .half {
+            height: 50%;
+            padding-top: 10%;
+        }
+        .box {
+            height: 10px;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 200.,
+            height: 100.,
+        };
+
+        let render_tree = build_render_tree(dom.clone(), &rules);
+        let mut layout_box = crate::build_layout_tree(&render_tree).unwrap();
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        let fixed_half = &layout_box.children[0].children[0].dimensions;
+        assert_eq!(fixed_half.content.height, 20.);
+        // the vertical paddings are relative to the width
+        assert_eq!(fixed_half.padding.top, 20.);
+
+        // the height of the parent depends on its content, so the
+        // percentage behaves as auto
+        let auto_half = &layout_box.children[1].children[0].dimensions;
+        assert_eq!(auto_half.content.height, 10.);
+    }
 }
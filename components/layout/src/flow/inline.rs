@@ -1,12 +1,33 @@
-use crate::box_model::{BoxComponent, Edge};
-use crate::formatting_context::{apply_explicit_sizes, layout_children, FormattingContext};
+use crate::box_model::{BoxComponent, Edge, Rect};
+use crate::content_visibility::has_skipped_contents;
+use crate::form_control;
+use crate::formatting_context::{
+    apply_explicit_sizes, apply_specified_height, layout_children, FormattingContext,
+};
+use crate::hyphenation::{self, HYPHEN, SOFT_HYPHEN};
 use crate::layout_box::LayoutBox;
-use crate::line_box::LineBox;
+use crate::line_box::{LineBox, LineFragment};
+use crate::percentage::PercentageBases;
+use crate::replaced;
+use crate::sizing;
+use crate::text::{self, FontMetrics, TextFragment};
+use style::render_tree::RenderNodeRef;
 use style::value_processing::Property;
+use style::values::hyphens::Hyphens;
+use style::values::overflow_wrap::OverflowWrap;
+use style::values::text_align::TextAlign;
+use style::values::vertical_align::VerticalAlign;
+use style::values::white_space::WhiteSpace;
 
 pub struct InlineFormattingContext {
     line_boxes: Vec<LineBox>,
     containing_block: *mut LayoutBox,
+    /// Whether the next content starts on a new line, after the forced
+    /// line break ending the previous text
+    force_new_line: bool,
+    /// Whether the content placed last ends with a collapsible space,
+    /// which removes a collapsible space starting the next text
+    after_collapsible_space: bool,
 }
 
 impl InlineFormattingContext {
@@ -14,6 +35,8 @@ impl InlineFormattingContext {
         Self {
             line_boxes: Vec::new(),
             containing_block: layout_box,
+            force_new_line: false,
+            after_collapsible_space: false,
         }
     }
 
@@ -23,17 +46,21 @@ impl InlineFormattingContext {
             None => return,
         };
 
-        let containing_block = &self.get_containing_block().dimensions.content;
+        let bases = PercentageBases::of(self.get_containing_block());
 
         let render_node = render_node.borrow();
-        let computed_width = render_node.get_style(&Property::Width);
-        let computed_margin_left = render_node.get_style(&Property::MarginLeft);
-        let computed_margin_right = render_node.get_style(&Property::MarginRight);
-        let containing_width = containing_block.width;
+        let style = render_node.style();
+        let computed_width = style.width();
+        let computed_margin_left = style.margin_left();
+        let computed_margin_right = style.margin_right();
 
-        let mut used_width = computed_width.to_px(containing_width);
-        let mut used_margin_left = computed_margin_left.to_px(containing_width);
-        let mut used_margin_right = computed_margin_right.to_px(containing_width);
+        let mut used_width = sizing::specified_width(style, &bases).unwrap_or(0.);
+        let mut used_margin_left = bases
+            .resolve_auto(&Property::MarginLeft, &computed_margin_left)
+            .unwrap_or(0.);
+        let mut used_margin_right = bases
+            .resolve_auto(&Property::MarginRight, &computed_margin_right)
+            .unwrap_or(0.);
 
         if layout_box.is_non_replaced() && !layout_box.is_inline_block() {
             used_width = 0.0;
@@ -50,9 +77,22 @@ impl InlineFormattingContext {
             }
             if computed_width.is_auto() {
                 // TODO: Support auto width when we have shrink-to-fit width
+                if let Some(width) = form_control::intrinsic_width(layout_box) {
+                    used_width = width;
+                }
             }
         }
 
+        if !layout_box.is_non_replaced() && computed_width.is_auto() {
+            if let Some(width) = replaced::intrinsic_width(layout_box, &bases) {
+                used_width = width;
+            }
+        }
+
+        if layout_box.is_inline_block() || !layout_box.is_non_replaced() {
+            used_width = sizing::clamp_width(style, used_width, &bases);
+        }
+
         // apply all calculated used values
         let box_model = layout_box.box_model();
         box_model.set_width(used_width);
@@ -61,34 +101,26 @@ impl InlineFormattingContext {
     }
 
     fn apply_vertical_spacing(&mut self, layout_box: &mut LayoutBox) {
-        let containing_block = self.get_containing_block();
-        let containing_block = &containing_block.dimensions.content.clone();
+        let bases = PercentageBases::of(self.get_containing_block());
         let render_node = layout_box.render_node.clone();
         let box_model = layout_box.box_model();
 
         if let Some(render_node) = render_node {
             let render_node = render_node.borrow();
+            let style = render_node.style();
+
+            let margin_top = bases
+                .resolve_auto(&Property::MarginTop, &style.margin_top())
+                .unwrap_or(0.);
+            let margin_bottom = bases
+                .resolve_auto(&Property::MarginBottom, &style.margin_bottom())
+                .unwrap_or(0.);
 
-            let margin_top = render_node
-                .get_style(&Property::MarginTop)
-                .to_px(containing_block.width);
-            let margin_bottom = render_node
-                .get_style(&Property::MarginBottom)
-                .to_px(containing_block.width);
-
-            let border_top = render_node
-                .get_style(&Property::BorderTopWidth)
-                .to_px(containing_block.width);
-            let border_bottom = render_node
-                .get_style(&Property::BorderBottomWidth)
-                .to_px(containing_block.width);
-
-            let padding_top = render_node
-                .get_style(&Property::PaddingTop)
-                .to_px(containing_block.width);
-            let padding_bottom = render_node
-                .get_style(&Property::PaddingBottom)
-                .to_px(containing_block.width);
+            let border_top = style.border_top_width();
+            let border_bottom = style.border_bottom_width();
+
+            let padding_top = bases.resolve(&Property::PaddingTop, &style.padding_top());
+            let padding_bottom = bases.resolve(&Property::PaddingBottom, &style.padding_bottom());
 
             box_model.set(BoxComponent::Margin, Edge::Top, margin_top);
             box_model.set(BoxComponent::Margin, Edge::Bottom, margin_bottom);
@@ -106,53 +138,1115 @@ impl InlineFormattingContext {
             self.line_boxes.push(LineBox::new());
         }
     }
-}
 
-impl FormattingContext for InlineFormattingContext {
-    fn layout(&mut self, boxes: Vec<&mut LayoutBox>) -> f32 {
-        let containing_block = self.get_containing_block();
-        let containing_block = &containing_block.dimensions.content.clone();
+    /// Continue on a new line after a forced line break
+    fn break_line(&mut self) {
+        self.line_boxes.last_mut().unwrap().set_forced_break();
+        self.line_boxes.push(LineBox::new());
+    }
 
-        self.ensure_last_line_box();
+    fn first_line_indent(&self, first_line_indent: f32) -> f32 {
+        if self.line_boxes.len() == 1 {
+            first_line_indent
+        } else {
+            0.
+        }
+    }
 
-        for layout_box in boxes {
-            self.calculate_width(layout_box);
-            layout_children(layout_box);
+    /// The width left on the last line box
+    fn available_width(&self, containing_width: f32, first_line_indent: f32) -> f32 {
+        containing_width
+            - self.first_line_indent(first_line_indent)
+            - self.line_boxes.last().unwrap().width()
+    }
+
+    fn push_text_fragment(&mut self, text_box: &mut LayoutBox, text: String, style: &TextStyle) {
+        text_box
+            .text_fragments
+            .push(TextFragment::new(text, &style.metrics, style.line_height));
+        let fragment_index = text_box.text_fragments.len() - 1;
+        self.line_boxes
+            .last_mut()
+            .unwrap()
+            .push_text(text_box, fragment_index);
+    }
+
+    /// Place the text of a fragment on the current line and continue
+    /// on a new line. Collapsible spaces at the end of the line are removed.
+    fn wrap_line(&mut self, text_box: &mut LayoutBox, fragment: &mut String, style: &TextStyle) {
+        if !style.white_space.preserves_spaces() {
+            let trimmed_len = fragment.trim_end_matches(|c| c == ' ' || c == '\t').len();
+            fragment.truncate(trimmed_len);
+        }
+        if !fragment.is_empty() {
+            self.push_text_fragment(text_box, std::mem::take(fragment), style);
+        }
+        self.line_boxes.push(LineBox::new());
+    }
+
+    /// Split the text of a text box into fragments at the forced line
+    /// breaks and at the soft wrap opportunities when the text overflows
+    /// the line, and place them in line boxes. Returns whether the text
+    /// ends with a forced line break.
+    fn layout_text(&mut self, text_box: &mut LayoutBox, first_line_indent: f32) -> bool {
+        let render_node = match &text_box.render_node {
+            Some(node) => node.clone(),
+            None => return false,
+        };
+        let containing_width = self.get_containing_block().dimensions.content.width;
+        let style = TextStyle::new(&render_node);
+        let metrics = &style.metrics;
+        let content = text::text_content(&render_node).unwrap_or_default();
+        let content = text::collapse_white_space(&content, &style.white_space);
+
+        let mut lines = text::split_forced_breaks(&content, &style.white_space);
+        // the trailing forced break is taken into account by the
+        // next content placed in this formatting context
+        let ends_with_break = lines.len() > 1 && lines.last().unwrap().is_empty();
+        if ends_with_break {
+            lines.pop();
+        }
+
+        text_box.text_fragments.clear();
+        for (index, line) in lines.into_iter().enumerate() {
+            if index > 0 {
+                self.break_line();
+            }
+
+            let mut line = line;
+            if !style.white_space.preserves_spaces() && line.starts_with(' ') {
+                let is_line_empty = self.line_boxes.last().unwrap().is_empty();
+                if is_line_empty || self.after_collapsible_space {
+                    line.remove(0);
+                }
+            }
+            if !line.is_empty() {
+                self.after_collapsible_space =
+                    !style.white_space.preserves_spaces() && line.ends_with(' ');
+            }
+
+            if !style.white_space.wraps() {
+                let line = line.replace(SOFT_HYPHEN, "");
+                if !line.is_empty() || style.white_space.preserves_newlines() {
+                    self.push_text_fragment(text_box, line, &style);
+                }
+                continue;
+            }
+
+            // fill the lines with as many words as they can fit
+            let mut fragment = String::new();
+            let hyphen_width = metrics.measure(HYPHEN);
+            for word in text::split_words(&line) {
+                let (word, hyphenation_points) =
+                    hyphenation::hyphenate(word, &style.hyphens, style.language.as_deref());
+                let mut start = 0;
+                loop {
+                    // trailing spaces hang at the end of the line
+                    let word_width =
+                        metrics.measure(word[start..].trim_end_matches(|c| c == ' ' || c == '\t'));
+                    let available_width = self.available_width(containing_width, first_line_indent)
+                        - metrics.measure(&fragment);
+                    let is_line_empty =
+                        fragment.is_empty() && self.line_boxes.last().unwrap().is_empty();
+                    if word_width <= available_width {
+                        break;
+                    }
+
+                    // end the line with the longest start of the word that
+                    // fits with a hyphen, or continue the word on the next line
+                    let hyphenation_point = hyphenation_points
+                        .iter()
+                        .rev()
+                        .filter(|point| **point > start)
+                        .find(|point| {
+                            metrics.measure(&word[start..**point]) + hyphen_width <= available_width
+                        });
+                    if let Some(&point) = hyphenation_point {
+                        fragment.push_str(&word[start..point]);
+                        fragment.push_str(HYPHEN);
+                        start = point;
+                    } else if is_line_empty {
+                        break;
+                    }
+                    self.wrap_line(text_box, &mut fragment, &style);
+                }
+                let word = &word[start..];
+                let word_width = metrics.measure(word.trim_end_matches(|c| c == ' ' || c == '\t'));
+
+                let available_width = self.available_width(containing_width, first_line_indent);
+                if word_width > available_width && style.overflow_wrap.breaks_words() {
+                    for grapheme in text::graphemes(word) {
+                        let available_width =
+                            self.available_width(containing_width, first_line_indent);
+                        let new_width = metrics.measure(&fragment) + metrics.measure(grapheme);
+                        if new_width > available_width && !fragment.is_empty() {
+                            self.wrap_line(text_box, &mut fragment, &style);
+                        }
+                        fragment.push_str(grapheme);
+                    }
+                } else {
+                    fragment.push_str(word);
+                }
+            }
+
+            // the empty lines between forced line breaks keep their height
+            if !fragment.is_empty() || (line.is_empty() && style.white_space.preserves_newlines()) {
+                self.push_text_fragment(text_box, fragment, &style);
+            }
+        }
+
+        ends_with_break
+    }
+
+    /// Place an inline-level box in the line boxes. The content of inline
+    /// boxes is placed in the line boxes of this formatting context too,
+    /// while atomic inlines (e.g. inline blocks) are laid out on their own.
+    fn place_box(&mut self, layout_box: &mut LayoutBox, first_line_indent: f32) {
+        if self.force_new_line {
+            self.break_line();
+            self.force_new_line = false;
+        }
+
+        if layout_box.is_text() {
+            self.force_new_line = self.layout_text(layout_box, first_line_indent);
+            return;
+        }
+
+        let containing_block = self.get_containing_block().dimensions.content.clone();
+
+        if is_inline_container(layout_box) {
             self.apply_vertical_spacing(layout_box);
-            apply_explicit_sizes(layout_box, containing_block);
+            for child in layout_box.children.iter_mut() {
+                self.place_box(child, first_line_indent);
+            }
+            layout_box.skipped_descendants = has_skipped_contents(&layout_box.children);
+            layout_box.finish_layout(&containing_block);
+            return;
+        }
+
+        let bases = PercentageBases::of(self.get_containing_block());
+        self.calculate_width(layout_box);
+        apply_specified_height(layout_box, &bases);
+        layout_children(layout_box);
+        self.apply_vertical_spacing(layout_box);
+        apply_explicit_sizes(layout_box, &bases);
+        layout_box.finish_layout(&containing_block);
 
-            let new_width =
-                self.line_boxes.last().unwrap().width() + layout_box.dimensions.content.width;
+        let line_box = self.line_boxes.last().unwrap();
+        let new_width = self.first_line_indent(first_line_indent)
+            + line_box.width()
+            + layout_box.dimensions.content.width;
 
-            if new_width > containing_block.width {
-                self.line_boxes.push(LineBox::new());
+        if new_width > containing_block.width && !line_box.is_empty() {
+            self.line_boxes.push(LineBox::new());
+        }
+
+        let line_box = self.line_boxes.last_mut().unwrap();
+        line_box.push(layout_box);
+        self.after_collapsible_space = false;
+    }
+
+    /// Remove the collapsible spaces at the end of the lines, which may
+    /// come from the texts before the last one of the line
+    /// https://drafts.csswg.org/css-text-3/#white-space-phase-2
+    fn trim_line_ends(&mut self) {
+        for line in self.line_boxes.iter_mut() {
+            for fragment in line.fragments().to_vec().into_iter().rev() {
+                let (text_box, index) = match fragment {
+                    LineFragment::Text(text_box, index) => (text_box, index),
+                    LineFragment::Box(_) => break,
+                };
+                let text_box = unsafe { text_box.as_mut().unwrap() };
+                let render_node = match &text_box.render_node {
+                    Some(node) => node.clone(),
+                    None => break,
+                };
+                let style = TextStyle::new(&render_node);
+                if style.white_space.preserves_spaces() {
+                    break;
+                }
+                let fragment = &mut text_box.text_fragments[index];
+                let trimmed_len = fragment.text.trim_end_matches(' ').len();
+                fragment.text.truncate(trimmed_len);
+                let width = style.metrics.measure(&fragment.text);
+                line.shrink(fragment.rect.width - width);
+                fragment.rect.width = width;
+                if !fragment.text.is_empty() {
+                    break;
+                }
             }
+        }
+    }
 
-            let line_box = self.line_boxes.last_mut().unwrap();
-            line_box.push(layout_box);
+    /// The render node holding the inherited styles of the lines,
+    /// anonymous blocks use the styles of their parent
+    fn line_style_node(&mut self) -> Option<RenderNodeRef> {
+        let containing_block = self.get_containing_block();
+        if let Some(node) = &containing_block.render_node {
+            return Some(node.clone());
         }
+        containing_block
+            .children
+            .iter()
+            .filter_map(|child| child.render_node.as_ref())
+            .filter_map(|node| node.borrow().parent_render_node.clone())
+            .filter_map(|parent| parent.upgrade())
+            .next()
+    }
+
+    fn text_align(&mut self) -> TextAlign {
+        let node = match self.line_style_node() {
+            Some(node) => node,
+            None => return TextAlign::Left,
+        };
+        let node = node.borrow();
+        let style = node.style();
+        style.text_align().resolve(&style.direction())
+    }
+
+    /// Every line starts with a zero width inline box with the font and
+    /// line height of the block
+    /// https://www.w3.org/TR/CSS2/visudet.html#strut
+    fn strut(&mut self) -> Placement {
+        let (metrics, height) = match self.line_style_node() {
+            Some(node) => {
+                let metrics = text::font_metrics(&node);
+                let height = text::line_height(&node, &metrics);
+                (metrics, height)
+            }
+            None => {
+                let metrics = FontMetrics::default();
+                let height = metrics.normal_line_height();
+                (metrics, height)
+            }
+        };
+        Placement {
+            height,
+            ascent: metrics.baseline(height),
+            vertical_align: VerticalAlign::Baseline,
+        }
+    }
+
+    /// Position the fragments of the lines, returns the total height of the lines
+    fn position_lines(&mut self, first_line_indent: f32) -> f32 {
+        let containing_block = self.get_containing_block().dimensions.content.clone();
+        let containing_node = self.get_containing_block().render_node.clone();
+        let text_align = self.text_align();
+        let strut = self.strut();
+        let line_count = self.line_boxes.len();
 
         let mut offset_y = 0.;
 
-        for line in &self.line_boxes {
-            let mut offset_x = 0.;
+        for (index, line) in self.line_boxes.iter().enumerate() {
+            let indent = if index == 0 { first_line_indent } else { 0. };
+            let free_space = (containing_block.width - indent - line.width()).max(0.);
+
+            let is_last_line = index == line_count - 1 || line.ends_with_forced_break();
+            let word_spacing = match text_align {
+                TextAlign::Justify if !is_last_line => {
+                    let spaces = line
+                        .fragments()
+                        .iter()
+                        .map(|fragment| match fragment {
+                            LineFragment::Text(text_box, fragment_index) => {
+                                let text_box = unsafe { text_box.as_ref().unwrap() };
+                                text_box.text_fragments[*fragment_index].space_count()
+                            }
+                            LineFragment::Box(_) => 0,
+                        })
+                        .sum::<usize>();
+                    if spaces > 0 {
+                        free_space / spaces as f32
+                    } else {
+                        0.
+                    }
+                }
+                _ => 0.,
+            };
+            let mut offset_x = indent
+                + match text_align {
+                    TextAlign::Right => free_space,
+                    TextAlign::Center => free_space / 2.,
+                    _ => 0.,
+                };
+
+            let placements = line
+                .fragments()
+                .iter()
+                .map(|fragment| place_fragment(fragment, &containing_node))
+                .collect::<Vec<Placement>>();
+            let (line_height, baseline) = if line.is_empty() {
+                (0., 0.)
+            } else {
+                line_metrics(&strut, &placements)
+            };
 
-            for fragment in line.fragments() {
-                let x = containing_block.x + offset_x + fragment.dimensions.margin.left;
+            for (fragment, placement) in line.fragments().iter().zip(placements.iter()) {
+                let top = containing_block.y
+                    + offset_y
+                    + match placement.vertical_align {
+                        VerticalAlign::Top => 0.,
+                        VerticalAlign::Bottom => line_height - placement.height,
+                        _ => baseline - placement.ascent,
+                    };
 
-                let y = containing_block.y + offset_y + fragment.dimensions.margin.top;
+                match fragment {
+                    LineFragment::Box(layout_box) => {
+                        let layout_box = unsafe { layout_box.as_mut().unwrap() };
+                        let x = containing_block.x + offset_x + layout_box.dimensions.margin.left;
+                        let y = top + layout_box.dimensions.margin.top;
 
-                fragment.box_model().set_position(x, y);
-                offset_x += fragment.dimensions.margin_box().width;
+                        layout_box.box_model().set_position(x, y);
+                        offset_x += layout_box.dimensions.margin_box().width;
+                    }
+                    LineFragment::Text(text_box, fragment_index) => {
+                        let text_box = unsafe { text_box.as_mut().unwrap() };
+                        let fragment = &mut text_box.text_fragments[*fragment_index];
+
+                        fragment.word_spacing = word_spacing;
+                        fragment.rect.width += word_spacing * fragment.space_count() as f32;
+                        fragment.rect.x = containing_block.x + offset_x;
+                        fragment.rect.y = top;
+                        offset_x += fragment.rect.width;
+                    }
+                }
             }
 
-            offset_y += line.height();
+            offset_y += line_height;
         }
 
         offset_y
     }
+}
+
+/// The styles of a text box used to place its text in lines
+struct TextStyle {
+    metrics: FontMetrics,
+    line_height: f32,
+    white_space: WhiteSpace,
+    overflow_wrap: OverflowWrap,
+    hyphens: Hyphens,
+    language: Option<String>,
+}
+
+impl TextStyle {
+    fn new(node: &RenderNodeRef) -> Self {
+        let metrics = text::font_metrics(node);
+        Self {
+            line_height: text::line_height(node, &metrics),
+            white_space: text::white_space(node),
+            overflow_wrap: text::overflow_wrap(node),
+            hyphens: text::hyphens(node),
+            language: text::language(node),
+            metrics,
+        }
+    }
+}
+
+/// Inline boxes whose content is placed in the lines of their formatting context
+fn is_inline_container(layout_box: &LayoutBox) -> bool {
+    layout_box.is_inline()
+        && !layout_box.is_inline_block()
+        && !layout_box.is_text()
+        && layout_box.is_non_replaced()
+        && layout_box.children_are_inline()
+}
+
+/// The vertical metrics of a fragment placed on a line
+struct Placement {
+    height: f32,
+    /// The distance from the top of the fragment to the baseline of the line
+    ascent: f32,
+    vertical_align: VerticalAlign,
+}
+
+fn place_fragment(fragment: &LineFragment, containing_node: &Option<RenderNodeRef>) -> Placement {
+    let metrics = match containing_node {
+        Some(node) => text::font_metrics(node),
+        None => FontMetrics::default(),
+    };
+
+    let (height, baseline, vertical_align) = match fragment {
+        LineFragment::Box(layout_box) => {
+            let layout_box = unsafe { layout_box.as_ref().unwrap() };
+            let height = layout_box.dimensions.margin_box().height;
+            let vertical_align = match &layout_box.render_node {
+                Some(node) => vertical_align(node),
+                None => VerticalAlign::Baseline,
+            };
+            // TODO: use the baseline of the last line of inline blocks
+            (height, height, vertical_align)
+        }
+        LineFragment::Text(text_box, fragment_index) => {
+            let text_box = unsafe { text_box.as_ref().unwrap() };
+            let height = text_box.text_fragments[*fragment_index].rect.height;
+            let vertical_align = match &text_box.render_node {
+                Some(node) => text_vertical_align(node, containing_node),
+                None => VerticalAlign::Baseline,
+            };
+            (height, metrics.baseline(height), vertical_align)
+        }
+    };
+
+    let ascent = match &vertical_align {
+        VerticalAlign::Baseline | VerticalAlign::Top | VerticalAlign::Bottom => baseline,
+        VerticalAlign::Sub => baseline - metrics.subscript_offset(),
+        VerticalAlign::Super => baseline + metrics.superscript_offset(),
+        VerticalAlign::Length(length) => baseline + length.to_px(),
+        VerticalAlign::TextTop => metrics.ascent(),
+        VerticalAlign::TextBottom => height - metrics.descent(),
+        // align the middle of the fragment with the middle of the
+        // lowercase letters of the parent
+        VerticalAlign::Middle => height / 2. + metrics.x_height() / 2.,
+    };
+
+    Placement {
+        height,
+        ascent,
+        vertical_align,
+    }
+}
+
+/// The height of a line and the position of its baseline from the top
+/// of the line
+/// https://www.w3.org/TR/CSS2/visudet.html#line-height
+fn line_metrics(strut: &Placement, placements: &[Placement]) -> (f32, f32) {
+    let mut ascent = strut.ascent;
+    let mut descent = strut.height - strut.ascent;
+    for placement in placements {
+        if !placement.vertical_align.is_line_relative() {
+            ascent = ascent.max(placement.ascent);
+            descent = descent.max(placement.height - placement.ascent);
+        }
+    }
+
+    // the boxes aligned with the top or bottom of the line can make it taller
+    for placement in placements {
+        let overflow = placement.height - (ascent + descent);
+        if overflow > 0. {
+            match placement.vertical_align {
+                VerticalAlign::Top => descent += overflow,
+                VerticalAlign::Bottom => ascent += overflow,
+                _ => {}
+            }
+        }
+    }
+
+    (ascent + descent, ascent)
+}
+
+fn vertical_align(node: &RenderNodeRef) -> VerticalAlign {
+    node.borrow().style().vertical_align()
+}
+
+/// Text is aligned by the closest inline box ancestor that isn't aligned
+/// with the baseline
+fn text_vertical_align(
+    text_node: &RenderNodeRef,
+    containing_node: &Option<RenderNodeRef>,
+) -> VerticalAlign {
+    let mut current = text_node.borrow().parent_render_node.clone();
+
+    while let Some(node) = current.and_then(|node| node.upgrade()) {
+        if let Some(containing_node) = containing_node {
+            if node.as_ptr() == containing_node.as_ptr() {
+                break;
+            }
+        }
+        let alignment = vertical_align(&node);
+        if alignment != VerticalAlign::Baseline {
+            return alignment;
+        }
+        current = node.borrow().parent_render_node.clone();
+    }
+    VerticalAlign::Baseline
+}
+
+/// The area covered by the content of an inline-level box placed in
+/// line boxes. The content boxes of text boxes & inline boxes are set
+/// to the area covered by their fragments & children.
+fn finish_inline_layout(layout_box: &mut LayoutBox) -> Option<Rect> {
+    let bounds = if layout_box.is_text() {
+        layout_box
+            .text_fragments
+            .iter()
+            .map(|fragment| fragment.rect.clone())
+            .fold(None, union)
+    } else if is_inline_container(layout_box) {
+        layout_box
+            .children
+            .iter_mut()
+            .filter_map(finish_inline_layout)
+            .fold(None, union)
+    } else {
+        return Some(layout_box.dimensions.margin_box());
+    };
+
+    if let Some(bounds) = &bounds {
+        let box_model = layout_box.box_model();
+        box_model.set_position(bounds.x, bounds.y);
+        box_model.set_width(bounds.width);
+        box_model.set_height(bounds.height);
+    }
+    bounds
+}
+
+fn union(bounds: Option<Rect>, rect: Rect) -> Option<Rect> {
+    let bounds = match bounds {
+        Some(bounds) => bounds,
+        None => return Some(rect),
+    };
+    let x = bounds.x.min(rect.x);
+    let y = bounds.y.min(rect.y);
+    Some(Rect {
+        x,
+        y,
+        width: (bounds.x + bounds.width).max(rect.x + rect.width) - x,
+        height: (bounds.y + bounds.height).max(rect.y + rect.height) - y,
+    })
+}
+
+impl FormattingContext for InlineFormattingContext {
+    fn layout(&mut self, boxes: Vec<&mut LayoutBox>) -> f32 {
+        let first_line_indent = self.get_containing_block().inside_marker_width();
+
+        self.ensure_last_line_box();
+
+        let mut boxes = boxes;
+        for layout_box in boxes.iter_mut() {
+            self.place_box(layout_box, first_line_indent);
+        }
+
+        self.trim_line_ends();
+        let height = self.position_lines(first_line_indent);
+
+        for layout_box in boxes {
+            finish_inline_layout(layout_box);
+        }
+
+        height
+    }
 
     fn get_containing_block(&mut self) -> &mut LayoutBox {
         unsafe { self.containing_block.as_mut().unwrap() }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::box_model::Rect;
+    use crate::layout_box::LayoutBox;
+    use crate::text::FontMetrics;
+    use crate::tree_builder::TreeBuilder;
+    use css::cssom::css_rule::CSSRule;
+    use dom::dom_ref::NodeRef;
+    use dom::images::{Image, ImageState};
+    use dom::node::Node;
+    use std::rc::Rc;
+    use style::build_render_tree;
+    use style::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+
+    fn layout(dom: NodeRef, css: &str, width: f32) -> LayoutBox {
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom, &rules);
+        let mut layout_box = TreeBuilder::new(render_tree.root.unwrap()).build().unwrap();
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width,
+            height: 200.,
+        };
+        crate::compute_layout(&mut layout_box, &viewport);
+        layout_box
+    }
+
+    /// The text of the fragments of a text box with the index of their line
+    fn lines(text_box: &LayoutBox) -> Vec<(&str, usize)> {
+        let line_height = FontMetrics::default().normal_line_height();
+        text_box
+            .text_fragments
+            .iter()
+            .map(|fragment| {
+                let line = (fragment.rect.y / line_height).round() as usize;
+                (fragment.text.as_str(), line)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn collapse_white_spaces() {
+        let document = document();
+        let dom = element(
+            "p",
+            document.clone(),
+            vec![
+                text("\n    Hello   ", document.clone()),
+                element(
+                    "b",
+                    document.clone(),
+                    vec![text(" world ", document.clone())],
+                ),
+                text("\n", document.clone()),
+                element("i", document.clone(), vec![text("!", document.clone())]),
+                text("  \n", document.clone()),
+            ],
+        );
+
+        let css = r#"
+        p {
+            display: block;
+        }"#;
+
+        let layout_box = layout(dom, css, 200.);
+
+        // the spaces collapse across the texts & are removed at the
+        // start & the end of the line
+        let texts = |layout_box: &LayoutBox| {
+            layout_box
+                .text_fragments
+                .iter()
+                .map(|fragment| fragment.text.clone())
+                .collect::<Vec<String>>()
+        };
+        assert_eq!(texts(&layout_box.children[0]), vec!["Hello "]);
+        assert_eq!(texts(&layout_box.children[1].children[0]), vec!["world "]);
+        assert!(texts(&layout_box.children[2]).is_empty());
+        assert_eq!(texts(&layout_box.children[3].children[0]), vec!["!"]);
+        assert_eq!(texts(&layout_box.children[4]), vec![""]);
+        assert_eq!(layout_box.dimensions.content.height, 19.2);
+    }
+
+    #[test]
+    fn layout_preserved_line_breaks() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element(
+                    "pre",
+                    document.clone(),
+                    vec![text("fn main() {\n\n}\n", document.clone())],
+                ),
+                element(
+                    "p",
+                    document.clone(),
+                    vec![text("fn main() {\n\n}\n", document.clone())],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div, p {
+            display: block;
+        }
+        pre {
+            display: block;
+            white-space: pre;
+        }"#;
+
+        let layout_box = layout(dom, css, 200.);
+
+        // every forced line break starts a new line, the empty
+        // line still takes up the height of a line
+        let pre = &layout_box.children[0];
+        let text_box = &pre.children[0];
+        assert_eq!(lines(text_box), vec![("fn main() {", 0), ("", 1), ("}", 2)]);
+        assert_eq!(pre.dimensions.content.height, 19.2 * 3.);
+        assert_eq!(text_box.dimensions.content.width, 88.);
+
+        // newlines are rendered as spaces otherwise
+        let paragraph = &layout_box.children[1];
+        let text_box = &paragraph.children[0];
+        assert_eq!(text_box.text_fragments.len(), 1);
+        assert_eq!(paragraph.dimensions.content.y, 19.2 * 3.);
+        assert_eq!(paragraph.dimensions.content.height, 19.2);
+    }
+
+    #[test]
+    fn wrap_text_at_break_opportunities() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element(
+                    "p",
+                    document.clone(),
+                    vec![text("lorem ipsum dolor sit", document.clone())],
+                ),
+                element(
+                    "p",
+                    document.clone(),
+                    vec![text("supercalifragilistic", document.clone())],
+                ),
+                element(
+                    "p",
+                    document.clone(),
+                    vec![text("supercalifragilistic", document.clone())],
+                ),
+                element(
+                    "p",
+                    document.clone(),
+                    vec![text("東京都と大阪府", document.clone())],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div, p {
+            display: block;
+        }
+        p + p + p {
+            overflow-wrap: break-word;
+        }"#;
+
+        // 12 narrow characters fit on a line
+        let layout_box = layout(dom, css, 100.);
+        let paragraphs = &layout_box.children;
+
+        // the spaces at the end of the wrapped lines are removed
+        assert_eq!(
+            lines(&paragraphs[0].children[0]),
+            vec![("lorem ipsum", 0), ("dolor sit", 1)]
+        );
+
+        // long words overflow the line unless they can be broken
+        assert_eq!(
+            lines(&paragraphs[1].children[0]),
+            vec![("supercalifragilistic", 2)]
+        );
+        assert_eq!(
+            lines(&paragraphs[2].children[0]),
+            vec![("supercalifra", 3), ("gilistic", 4)]
+        );
+
+        assert_eq!(
+            lines(&paragraphs[3].children[0]),
+            vec![("東京都と大阪", 5), ("府", 6)]
+        );
+    }
+
+    #[test]
+    fn hyphenate_wrapped_words() {
+        let document = document();
+        let paragraph = |class: &str, content: &str| {
+            element(
+                &format!("p.{}", class),
+                document.clone(),
+                vec![text(content, document.clone())],
+            )
+        };
+        // the patterns of the language are needed to hyphenate words
+        // without soft hyphens
+        let unknown_language = paragraph("auto", "the hyphenation of words");
+        Node::set_attribute(&unknown_language, "lang", "");
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                paragraph("auto", "the hyphenation of words"),
+                paragraph("manual", "the hyphen\u{AD}ation of words"),
+                paragraph("none", "the hyphen\u{AD}ation of words"),
+                unknown_language,
+            ],
+        );
+        Node::set_attribute(&dom, "lang", "en");
+
+        let css = r#"
+        div, p {
+            display: block;
+        }
+        .auto {
+            hyphens: auto;
+        }
+        .none {
+            hyphens: none;
+        }"#;
+
+        // 12 narrow characters fit on a line
+        let layout_box = layout(dom, css, 100.);
+        let paragraphs = &layout_box.children;
+
+        assert_eq!(
+            lines(&paragraphs[0].children[0]),
+            vec![("the hyphen-", 0), ("ation of", 1), ("words", 2)]
+        );
+        // the soft hyphens are only shown where the words are hyphenated
+        assert_eq!(
+            lines(&paragraphs[1].children[0]),
+            vec![("the hyphen-", 3), ("ation of", 4), ("words", 5)]
+        );
+        assert_eq!(
+            lines(&paragraphs[2].children[0]),
+            vec![("the", 6), ("hyphenation", 7), ("of words", 8)]
+        );
+        assert_eq!(
+            lines(&paragraphs[3].children[0]),
+            vec![("the", 9), ("hyphenation", 10), ("of words", 11)]
+        );
+    }
+
+    #[test]
+    fn align_text_horizontally() {
+        let document = document();
+        let paragraph = |class: &str, content: &str| {
+            element(
+                &format!("p.{}", class),
+                document.clone(),
+                vec![text(content, document.clone())],
+            )
+        };
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                paragraph("right", "abc"),
+                paragraph("center", "abc"),
+                paragraph("justify", "aaa bbb ccc ddd eee"),
+            ],
+        );
+
+        let css = r#"
+        div, p {
+            display: block;
+        }
+        .right {
+            text-align: right;
+        }
+        .center {
+            text-align: center;
+        }
+        .justify {
+            text-align: justify;
+        }"#;
+
+        let layout_box = layout(dom, css, 100.);
+        let fragments = |index: usize| &layout_box.children[index].children[0].text_fragments;
+
+        assert_eq!(fragments(0)[0].rect.x, 76.);
+        assert_eq!(fragments(1)[0].rect.x, 38.);
+
+        // the spaces of all lines but the last are expanded to fill the line
+        let justified = fragments(2);
+        assert_eq!(justified[0].text, "aaa bbb ccc");
+        assert_eq!(justified[0].word_spacing, 6.);
+        assert_eq!(justified[0].rect.width, 100.);
+        assert_eq!(justified[1].word_spacing, 0.);
+        assert_eq!(justified[1].rect.width, 56.);
+    }
+
+    #[test]
+    fn align_boxes_vertically() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element("span#tall", document.clone(), vec![]),
+                element("span#middle", document.clone(), vec![]),
+                text("x", document.clone()),
+                element(
+                    "span#super",
+                    document.clone(),
+                    vec![text("y", document.clone())],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        #tall, #middle {
+            display: inline-block;
+            width: 10px;
+        }
+        #tall {
+            height: 40px;
+        }
+        #middle {
+            height: 10px;
+            vertical-align: middle;
+        }
+        #super {
+            vertical-align: super;
+        }"#;
+
+        let layout_box = layout(dom, css, 100.);
+        let children = &layout_box.children;
+        let assert_near = |actual: f32, expected: f32| {
+            assert!(
+                (actual - expected).abs() < 0.01,
+                "{} is not {}",
+                actual,
+                expected
+            )
+        };
+
+        // the baseline of the line is at the bottom of the tall box
+        assert_near(children[0].dimensions.content.y, 0.);
+        assert_near(children[1].dimensions.content.y, 31.);
+        assert_near(children[2].text_fragments[0].rect.y, 40. - 14.4);
+        assert_near(layout_box.dimensions.content.height, 40. + 4.8);
+
+        // the text of inline boxes is placed in the lines of the block
+        let span = &children[3];
+        assert_near(
+            span.children[0].text_fragments[0].rect.y,
+            40. - 14.4 - 16. / 3.,
+        );
+        assert_near(span.dimensions.content.x, 28.);
+        assert_near(span.dimensions.content.width, 8.);
+    }
+
+    #[test]
+    fn size_lines_with_line_height() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element("p", document.clone(), vec![text("a", document.clone())]),
+                element(
+                    "p.number",
+                    document.clone(),
+                    vec![text("a", document.clone())],
+                ),
+                element(
+                    "p.percentage",
+                    document.clone(),
+                    vec![text("a", document.clone())],
+                ),
+                element(
+                    "p.length",
+                    document.clone(),
+                    vec![element("span", document.clone(), vec![])],
+                ),
+                element(
+                    "p",
+                    document.clone(),
+                    vec![element(
+                        "span.tall",
+                        document.clone(),
+                        vec![text("a", document.clone())],
+                    )],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div, p {
+            display: block;
+        }
+        span {
+            display: inline-block;
+            width: 10px;
+            height: 10px;
+        }
+        .tall {
+            display: inline;
+            line-height: 40px;
+        }
+        .number {
+            line-height: 2;
+        }
+        .percentage {
+            line-height: 150%;
+        }
+        .length {
+            line-height: 30px;
+        }"#;
+
+        let layout_box = layout(dom, css, 100.);
+        let heights = layout_box
+            .children
+            .iter()
+            .map(|paragraph| paragraph.dimensions.content.height)
+            .collect::<Vec<f32>>();
+
+        // the strut of the line makes it as tall as the line height of
+        // the block, even if the content of the line is shorter
+        assert_eq!(heights, vec![19.2, 32., 24., 30., 40.]);
+
+        // the leading is split evenly above & below the glyphs
+        let length = &layout_box.children[3];
+        let inline_block = &length.children[0];
+        let offset = inline_block.dimensions.content.y - length.dimensions.content.y;
+        assert!((offset - ((30. - 16.) / 2. + 12.8 - 10.)).abs() < 0.01);
+    }
+
+    #[test]
+    fn size_images() {
+        let document = document();
+        let image = Image::decode(br#"<svg width="40" height="20"></svg>"#).unwrap();
+        document
+            .borrow_mut()
+            .as_document_mut()
+            .set_image_state("file:///moon.svg", ImageState::Loaded(Rc::new(image)));
+
+        let img = |selector: &str, attributes: &[(&str, &str)]| {
+            let node = element(selector, document.clone(), vec![]);
+            for (name, value) in attributes {
+                node.borrow_mut()
+                    .as_element_mut()
+                    .set_attribute(name, value);
+            }
+            node
+        };
+        let src = ("src", "file:///moon.svg");
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                img("img", &[src]),
+                img("img.wide", &[src]),
+                img("img", &[src, ("width", "80")]),
+                img("img", &[("height", "10")]),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        img {
+            display: inline-block;
+        }
+        .wide {
+            width: 100px;
+        }"#;
+
+        let layout_box = layout(dom, css, 1000.);
+        let sizes = layout_box
+            .children
+            .iter()
+            .map(|child| {
+                let content = &child.dimensions.content;
+                (content.width, content.height)
+            })
+            .collect::<Vec<(f32, f32)>>();
+
+        // the sizes keep the aspect ratio of the image, the size of
+        // images that aren't loaded being the default object size
+        assert_eq!(
+            sizes,
+            vec![(40., 20.), (100., 50.), (80., 40.), (300., 10.)]
+        );
+    }
+}
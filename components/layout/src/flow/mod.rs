@@ -1,2 +1,3 @@
 pub mod block;
 pub mod inline;
+pub mod table;
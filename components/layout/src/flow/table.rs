@@ -0,0 +1,680 @@
+/// This module contains the table formatting context, which
+/// places the rows & cells of a table on a grid. The width of
+/// the columns is computed with either the automatic or the
+/// fixed table layout algorithm.
+/// https://www.w3.org/TR/CSS22/tables.html#width-layout
+use crate::box_model::{BoxComponent, Edge, Rect};
+use crate::formatting_context::{layout_children, FormattingContext};
+use crate::layout_box::{BoxType, LayoutBox};
+use crate::percentage::PercentageBases;
+use style::value_processing::Property;
+use style::values::border_collapse::BorderCollapse;
+use style::values::display::{Display, InternalDisplayType};
+use style::values::length_percentage::{LengthPercentage, LengthPercentageAuto};
+use style::values::table_layout::TableLayout;
+
+const MAX_COLSPAN: usize = 1000;
+
+pub struct TableFormattingContext {
+    containing_block: *mut LayoutBox,
+}
+
+/// The intrinsic widths of a cell spanning one or more columns
+struct CellWidths {
+    column: usize,
+    span: usize,
+    min: f32,
+    max: f32,
+    specified: Option<f32>,
+}
+
+impl TableFormattingContext {
+    pub fn new(layout_box: &mut LayoutBox) -> Self {
+        Self {
+            containing_block: layout_box,
+        }
+    }
+
+    /// The horizontal & vertical spacing between the cells of the table
+    fn border_spacing(&mut self) -> (f32, f32) {
+        let table = self.get_containing_block();
+        let node = match &table.render_node {
+            Some(node) => node.clone(),
+            None => return (0., 0.),
+        };
+        let node = node.borrow();
+        let style = node.style();
+
+        // TODO: collapse the borders of adjacent cells
+        if style.border_collapse() == BorderCollapse::Collapse {
+            return (0., 0.);
+        }
+
+        let spacing = style.border_spacing();
+        (spacing.0.to_px(), spacing.1.to_px())
+    }
+
+    fn is_fixed_layout(&mut self) -> bool {
+        let table = self.get_containing_block();
+        if table.is_width_auto() {
+            return false;
+        }
+        match &table.render_node {
+            Some(node) => node.borrow().style().table_layout() == TableLayout::Fixed,
+            None => false,
+        }
+    }
+}
+
+impl FormattingContext for TableFormattingContext {
+    fn layout(&mut self, boxes: Vec<&mut LayoutBox>) -> f32 {
+        let (h_spacing, v_spacing) = self.border_spacing();
+        let is_fixed_layout = self.is_fixed_layout();
+
+        // header groups are placed before the other rows & footer groups after them
+        let mut boxes = boxes;
+        boxes.sort_by_key(|child| row_group_order(child));
+
+        let rows = boxes
+            .iter()
+            .flat_map(|child| match child.box_type {
+                BoxType::TableRowGroup => child.children.iter().filter(|c| is_row(c)).collect(),
+                BoxType::TableRow => vec![&**child],
+                _ => Vec::new(),
+            })
+            .collect::<Vec<&LayoutBox>>()
+            .into_iter()
+            .map(row_cell_widths)
+            .collect::<Vec<Vec<CellWidths>>>();
+
+        let column_count = rows
+            .iter()
+            .map(|cells| cells.last().map_or(0, |cell| cell.column + cell.span))
+            .max()
+            .unwrap_or(0);
+
+        let table = self.get_containing_block();
+        let spacing = h_spacing * (column_count + 1) as f32;
+        let assigned_width = table.dimensions.content.width;
+
+        let (columns, table_width) = if is_fixed_layout {
+            fixed_column_widths(&rows, column_count, assigned_width - spacing)
+        } else {
+            auto_column_widths(
+                &rows,
+                column_count,
+                assigned_width - spacing,
+                table.is_width_auto(),
+                h_spacing,
+            )
+        };
+
+        let table_width = table_width + spacing;
+        table.box_model().set_width(table_width);
+        let table_rect = table.dimensions.content.clone();
+        let bases = PercentageBases::of(table);
+
+        let mut offset_y = table_rect.y + v_spacing;
+        let mut has_rows = false;
+
+        for child in boxes {
+            match child.box_type {
+                BoxType::TableRowGroup => {
+                    let group_y = offset_y;
+                    for row in child.children.iter_mut().filter(|c| is_row(c)) {
+                        let height =
+                            layout_row(row, &columns, &table_rect, &bases, offset_y, h_spacing);
+                        offset_y += height + v_spacing;
+                        has_rows = true;
+                    }
+                    let height = (offset_y - v_spacing - group_y).max(0.);
+                    place_box(child, &table_rect, group_y, h_spacing, height);
+                }
+                BoxType::TableRow => {
+                    let height =
+                        layout_row(child, &columns, &table_rect, &bases, offset_y, h_spacing);
+                    offset_y += height + v_spacing;
+                    has_rows = true;
+                }
+                // TODO: wrap the other boxes of the table in anonymous cells
+                _ => {}
+            }
+        }
+
+        if !has_rows {
+            return 0.;
+        }
+        offset_y - table_rect.y
+    }
+
+    fn get_containing_block(&mut self) -> &mut LayoutBox {
+        unsafe { self.containing_block.as_mut().unwrap() }
+    }
+}
+
+fn is_row(layout_box: &LayoutBox) -> bool {
+    layout_box.box_type == BoxType::TableRow
+}
+
+fn row_group_order(layout_box: &LayoutBox) -> u8 {
+    let node = match &layout_box.render_node {
+        Some(node) => node,
+        None => return 1,
+    };
+    match node.borrow().style().display() {
+        Display::Internal(InternalDisplayType::TableHeaderGroup) => 0,
+        Display::Internal(InternalDisplayType::TableFooterGroup) => 2,
+        _ => 1,
+    }
+}
+
+/// Position a row or a row group across the table
+fn place_box(layout_box: &mut LayoutBox, table_rect: &Rect, y: f32, h_spacing: f32, height: f32) {
+    let box_model = layout_box.box_model();
+    box_model.set_position(table_rect.x + h_spacing, y);
+    box_model.set_width((table_rect.width - 2. * h_spacing).max(0.));
+    box_model.set_height(height);
+    layout_box.finish_layout(table_rect);
+}
+
+/// Lay out the cells of a row and return the height of the row
+fn layout_row(
+    row: &mut LayoutBox,
+    columns: &[f32],
+    table_rect: &Rect,
+    bases: &PercentageBases,
+    y: f32,
+    h_spacing: f32,
+) -> f32 {
+    let mut row_height = 0f32;
+    let mut column = 0;
+
+    for cell in row
+        .children
+        .iter_mut()
+        .filter(|c| c.box_type == BoxType::TableCell)
+    {
+        if column >= columns.len() {
+            break;
+        }
+        let span = colspan(cell).min(columns.len() - column);
+        let x = table_rect.x
+            + h_spacing
+            + columns[..column].iter().sum::<f32>()
+            + h_spacing * column as f32;
+        let width =
+            columns[column..column + span].iter().sum::<f32>() + h_spacing * (span - 1) as f32;
+        column += span;
+
+        set_cell_edges(cell, bases);
+        let dimensions = &cell.dimensions;
+        let horizontal_edges = dimensions.border.left
+            + dimensions.padding.left
+            + dimensions.padding.right
+            + dimensions.border.right;
+        let content_x = x + dimensions.border.left + dimensions.padding.left;
+        let content_y = y + dimensions.border.top + dimensions.padding.top;
+
+        let box_model = cell.box_model();
+        box_model.set_width((width - horizontal_edges).max(0.));
+        box_model.set_position(content_x, content_y);
+
+        layout_children(cell);
+        if let Some(height) = specified_height(cell, bases) {
+            let height = height.max(cell.dimensions.content.height);
+            cell.box_model().set_height(height);
+        }
+        cell.finish_layout(table_rect);
+
+        row_height = row_height.max(cell.dimensions.border_box().height);
+    }
+
+    if let Some(height) = specified_height(row, bases) {
+        row_height = row_height.max(height);
+    }
+
+    // cells are stretched to the height of their row
+    for cell in row
+        .children
+        .iter_mut()
+        .filter(|c| c.box_type == BoxType::TableCell)
+    {
+        let dimensions = &cell.dimensions;
+        let vertical_edges = dimensions.border.top
+            + dimensions.padding.top
+            + dimensions.padding.bottom
+            + dimensions.border.bottom;
+        cell.box_model()
+            .set_height((row_height - vertical_edges).max(0.));
+    }
+
+    place_box(row, table_rect, y, h_spacing, row_height);
+    row_height
+}
+
+fn set_cell_edges(cell: &mut LayoutBox, bases: &PercentageBases) {
+    let node = match &cell.render_node {
+        Some(node) => node.clone(),
+        None => return,
+    };
+    let node = node.borrow();
+    let style = node.style();
+
+    let box_model = cell.box_model();
+    box_model.set(
+        BoxComponent::Padding,
+        Edge::Top,
+        bases.resolve(&Property::PaddingTop, &style.padding_top()),
+    );
+    box_model.set(
+        BoxComponent::Padding,
+        Edge::Right,
+        bases.resolve(&Property::PaddingRight, &style.padding_right()),
+    );
+    box_model.set(
+        BoxComponent::Padding,
+        Edge::Bottom,
+        bases.resolve(&Property::PaddingBottom, &style.padding_bottom()),
+    );
+    box_model.set(
+        BoxComponent::Padding,
+        Edge::Left,
+        bases.resolve(&Property::PaddingLeft, &style.padding_left()),
+    );
+    box_model.set(BoxComponent::Border, Edge::Top, style.border_top_width());
+    box_model.set(
+        BoxComponent::Border,
+        Edge::Right,
+        style.border_right_width(),
+    );
+    box_model.set(
+        BoxComponent::Border,
+        Edge::Bottom,
+        style.border_bottom_width(),
+    );
+    box_model.set(BoxComponent::Border, Edge::Left, style.border_left_width());
+    // cells don't have margins
+    box_model.set(BoxComponent::Margin, Edge::Top, 0.);
+    box_model.set(BoxComponent::Margin, Edge::Right, 0.);
+    box_model.set(BoxComponent::Margin, Edge::Bottom, 0.);
+    box_model.set(BoxComponent::Margin, Edge::Left, 0.);
+}
+
+fn specified_height(layout_box: &LayoutBox, bases: &PercentageBases) -> Option<f32> {
+    let node = layout_box.render_node.as_ref()?;
+    let height = node.borrow().style().height();
+    bases.resolve_auto(&Property::Height, &height)
+}
+
+/// The number of columns spanned by a cell
+fn colspan(cell: &LayoutBox) -> usize {
+    // TODO: support rowspan
+    let node = match &cell.render_node {
+        Some(node) => node,
+        None => return 1,
+    };
+    let node = node.borrow();
+    let dom_node = node.node.borrow();
+    dom_node
+        .as_element_opt()
+        .and_then(|element| element.get_attribute("colspan").cloned())
+        .and_then(|colspan| colspan.trim().parse::<usize>().ok())
+        .map_or(1, |colspan| colspan.max(1).min(MAX_COLSPAN))
+}
+
+fn row_cell_widths(row: &LayoutBox) -> Vec<CellWidths> {
+    let mut column = 0;
+    row.children
+        .iter()
+        .filter(|c| c.box_type == BoxType::TableCell)
+        .map(|cell| {
+            let span = colspan(cell);
+            let (min, max) = intrinsic_widths(cell);
+            let specified = specified_width(cell).map(|width| width + horizontal_edges(cell));
+            let widths = CellWidths {
+                column,
+                span,
+                min: specified.map_or(min, |width| min.max(width)),
+                max: specified.map_or(max, |width| max.max(width)),
+                specified,
+            };
+            column += span;
+            widths
+        })
+        .collect()
+}
+
+/// Automatic table layout: the columns are sized to fit the content of
+/// their cells, within the width available to the table.
+/// Return the width of the columns & the total width of the columns.
+fn auto_column_widths(
+    rows: &[Vec<CellWidths>],
+    column_count: usize,
+    available_width: f32,
+    is_width_auto: bool,
+    h_spacing: f32,
+) -> (Vec<f32>, f32) {
+    let mut min_widths = vec![0f32; column_count];
+    let mut max_widths = vec![0f32; column_count];
+
+    let cells = rows.iter().flatten();
+    for cell in cells.clone().filter(|cell| cell.span == 1) {
+        min_widths[cell.column] = min_widths[cell.column].max(cell.min);
+        max_widths[cell.column] = max_widths[cell.column].max(cell.max);
+    }
+
+    // spanning cells distribute the width the spanned columns lack evenly
+    for cell in cells.filter(|cell| cell.span > 1) {
+        let end = (cell.column + cell.span).min(column_count);
+        let spanned = cell.column..end;
+        let inner_spacing = h_spacing * (spanned.len() - 1) as f32;
+        let spanned_min = min_widths[spanned.clone()].iter().sum::<f32>() + inner_spacing;
+        let spanned_max = max_widths[spanned.clone()].iter().sum::<f32>() + inner_spacing;
+
+        for column in spanned.clone() {
+            if cell.min > spanned_min {
+                min_widths[column] += (cell.min - spanned_min) / spanned.len() as f32;
+            }
+            if cell.max > spanned_max {
+                max_widths[column] += (cell.max - spanned_max) / spanned.len() as f32;
+            }
+        }
+    }
+
+    for column in 0..column_count {
+        max_widths[column] = max_widths[column].max(min_widths[column]);
+    }
+
+    let total_min = min_widths.iter().sum::<f32>();
+    let total_max = max_widths.iter().sum::<f32>();
+
+    let table_width = if is_width_auto {
+        available_width.min(total_max).max(total_min)
+    } else {
+        available_width.max(total_min)
+    };
+
+    let columns = if table_width >= total_max {
+        let extra = table_width - total_max;
+        max_widths
+            .iter()
+            .map(|max| {
+                if total_max > 0. {
+                    max + extra * max / total_max
+                } else {
+                    extra / column_count as f32
+                }
+            })
+            .collect()
+    } else {
+        let ratio = (table_width - total_min) / (total_max - total_min);
+        min_widths
+            .iter()
+            .zip(max_widths.iter())
+            .map(|(min, max)| min + (max - min) * ratio)
+            .collect()
+    };
+
+    (columns, table_width)
+}
+
+/// Fixed table layout: the width of the columns only depends on the
+/// width of the table & the cells of the first row.
+/// Return the width of the columns & the total width of the columns.
+fn fixed_column_widths(
+    rows: &[Vec<CellWidths>],
+    column_count: usize,
+    table_width: f32,
+) -> (Vec<f32>, f32) {
+    let mut columns: Vec<Option<f32>> = vec![None; column_count];
+
+    if let Some(first_row) = rows.first() {
+        for cell in first_row {
+            if let Some(width) = cell.specified {
+                for column in cell.column..cell.column + cell.span {
+                    columns[column] = Some(width / cell.span as f32);
+                }
+            }
+        }
+    }
+
+    let fixed_width = columns.iter().flatten().sum::<f32>();
+    let auto_columns = columns.iter().filter(|c| c.is_none()).count();
+    let auto_width = if auto_columns > 0 {
+        (table_width - fixed_width).max(0.) / auto_columns as f32
+    } else {
+        0.
+    };
+
+    let mut columns = columns
+        .iter()
+        .map(|column| column.unwrap_or(auto_width))
+        .collect::<Vec<f32>>();
+
+    // the extra width is distributed over the columns if they all have a width
+    if auto_columns == 0 && column_count > 0 && fixed_width < table_width {
+        let extra = (table_width - fixed_width) / column_count as f32;
+        columns.iter_mut().for_each(|column| *column += extra);
+    }
+
+    let total = columns.iter().sum::<f32>();
+    (columns, total.max(table_width))
+}
+
+fn specified_width(layout_box: &LayoutBox) -> Option<f32> {
+    let node = layout_box.render_node.as_ref()?;
+    // TODO: support percentage widths
+    match node.borrow().style().width() {
+        LengthPercentageAuto::LengthPercentage(LengthPercentage::Length(length)) => {
+            Some(length.to_px())
+        }
+        _ => None,
+    }
+}
+
+fn horizontal_edges(layout_box: &LayoutBox) -> f32 {
+    let node = match &layout_box.render_node {
+        Some(node) => node,
+        None => return 0.,
+    };
+    let node = node.borrow();
+    let style = node.style();
+
+    let margins = if layout_box.box_type == BoxType::TableCell {
+        0.
+    } else {
+        style.margin_left().to_px(0.) + style.margin_right().to_px(0.)
+    };
+
+    margins
+        + style.border_left_width()
+        + style.padding_left().to_px(0.)
+        + style.padding_right().to_px(0.)
+        + style.border_right_width()
+}
+
+/// The minimum & maximum width of the content of a box, including the
+/// horizontal edges of the box
+fn intrinsic_widths(layout_box: &LayoutBox) -> (f32, f32) {
+    let edges = horizontal_edges(layout_box);
+
+    if layout_box.box_type != BoxType::TableCell {
+        if let Some(width) = specified_width(layout_box) {
+            return (width + edges, width + edges);
+        }
+    }
+
+    // TODO: measure text when we support text
+    let children = layout_box.children.iter().map(intrinsic_widths);
+    let (min, max) = if layout_box.children_are_inline() {
+        children.fold((0f32, 0f32), |(min, max), (child_min, child_max)| {
+            (min.max(child_min), max + child_max)
+        })
+    } else {
+        children.fold((0f32, 0f32), |(min, max), (child_min, child_max)| {
+            (min.max(child_min), max.max(child_max))
+        })
+    };
+
+    (min + edges, max + edges)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tree_builder::TreeBuilder;
+    use css::cssom::css_rule::CSSRule;
+    use dom::dom_ref::NodeRef;
+    use dom::node::Node;
+    use style::build_render_tree;
+    use style::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+
+    fn layout(dom: NodeRef, css: &str) -> LayoutBox {
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom, &rules);
+        let mut layout_box = TreeBuilder::new(render_tree.root.unwrap()).build().unwrap();
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 500.,
+            height: 500.,
+        };
+        crate::compute_layout(&mut layout_box, &viewport);
+        layout_box
+    }
+
+    #[test]
+    fn auto_table_layout() {
+        let document = document();
+        let spanning_cell = element(
+            "td",
+            document.clone(),
+            vec![element("div.wide", document.clone(), vec![])],
+        );
+        Node::set_attribute(&spanning_cell, "colspan", "2");
+        let dom = element(
+            "table",
+            document.clone(),
+            vec![
+                element(
+                    "tr",
+                    document.clone(),
+                    vec![
+                        element(
+                            "td",
+                            document.clone(),
+                            vec![element("div.small", document.clone(), vec![])],
+                        ),
+                        element(
+                            "td",
+                            document.clone(),
+                            vec![element("div.medium", document.clone(), vec![])],
+                        ),
+                    ],
+                ),
+                element("tr", document.clone(), vec![spanning_cell]),
+            ],
+        );
+
+        let css = r#"
+        table {
+            display: table;
+            border-spacing: 2px;
+        }
+        tr {
+            display: table-row;
+        }
+        td {
+            display: table-cell;
+        }
+        div {
+            display: block;
+            height: 10px;
+        }
+        .small {
+            width: 50px;
+        }
+        .medium {
+            width: 100px;
+        }
+        .wide {
+            width: 200px;
+        }"#;
+
+        let table = layout(dom, css);
+
+        // the spanning cell is wider than the two columns, the lacking
+        // width is distributed evenly between them
+        assert_eq!(table.dimensions.content.width, 204.);
+        assert_eq!(table.dimensions.content.height, 26.);
+
+        let first_row = &table.children[0];
+        assert_eq!(first_row.dimensions.content.y, 2.);
+        assert_eq!(first_row.children[0].dimensions.content.x, 2.);
+        assert_eq!(first_row.children[0].dimensions.content.width, 74.);
+        assert_eq!(first_row.children[1].dimensions.content.x, 78.);
+        assert_eq!(first_row.children[1].dimensions.content.width, 124.);
+
+        let second_row = &table.children[1];
+        assert_eq!(second_row.dimensions.content.y, 14.);
+        assert_eq!(second_row.children[0].dimensions.content.width, 200.);
+    }
+
+    #[test]
+    fn fixed_table_layout() {
+        let document = document();
+        let dom = element(
+            "table",
+            document.clone(),
+            vec![
+                element("td.fixed", document.clone(), vec![]),
+                element("td", document.clone(), vec![]),
+                element("td", document.clone(), vec![]),
+            ],
+        );
+
+        let css = r#"
+        table {
+            display: table;
+            table-layout: fixed;
+            width: 300px;
+        }
+        td {
+            display: table-cell;
+            height: 20px;
+        }
+        .fixed {
+            width: 60px;
+        }"#;
+
+        let table = layout(dom, css);
+
+        // the cells are wrapped in an anonymous row
+        let row = &table.children[0];
+        assert!(row.is_anonymous());
+        assert!(row.box_type == BoxType::TableRow);
+
+        let widths = row
+            .children
+            .iter()
+            .map(|cell| cell.dimensions.content.width)
+            .collect::<Vec<f32>>();
+        assert_eq!(widths, vec![60., 120., 120.]);
+        assert_eq!(table.dimensions.content.height, 20.);
+    }
+}
@@ -0,0 +1,49 @@
+/// This module gives form controls their size when their width or
+/// height is auto. Like replaced elements, the controls have an
+/// intrinsic size: text inputs are as wide as their `size` in
+/// characters & as tall as a line, checkboxes & radio buttons are
+/// small squares and buttons are as wide as their label.
+use super::layout_box::LayoutBox;
+use super::text;
+use dom::elements::{ElementData, InputType};
+
+/// The width & height of checkboxes & radio buttons
+pub const CHECKBOX_SIZE: f32 = 13.;
+
+pub fn intrinsic_width(layout_box: &LayoutBox) -> Option<f32> {
+    let render_node = layout_box.render_node.as_ref()?;
+    let node = render_node.borrow().node.clone();
+    let node = node.borrow();
+    let metrics = text::font_metrics(render_node);
+
+    match node.as_element_opt()?.data() {
+        ElementData::Input(input) => match input.input_type() {
+            InputType::Text => Some(input.size() as f32 * metrics.char_advance('0')),
+            InputType::Checkbox | InputType::Radio => Some(CHECKBOX_SIZE),
+        },
+        ElementData::Button(_) => {
+            let label = node.descendant_text_content();
+            let label = label.split_whitespace().collect::<Vec<&str>>().join(" ");
+            Some(metrics.measure(&label))
+        }
+        _ => None,
+    }
+}
+
+pub fn intrinsic_height(layout_box: &LayoutBox) -> Option<f32> {
+    let render_node = layout_box.render_node.as_ref()?;
+    let node = render_node.borrow().node.clone();
+    let node = node.borrow();
+
+    match node.as_element_opt()?.data() {
+        ElementData::Input(input) => match input.input_type() {
+            InputType::Text => Some(text::line_height(
+                render_node,
+                &text::font_metrics(render_node),
+            )),
+            InputType::Checkbox | InputType::Radio => Some(CHECKBOX_SIZE),
+        },
+        // buttons are as tall as their label is laid out
+        _ => None,
+    }
+}
@@ -1,10 +1,16 @@
-use super::box_model::Rect;
+use super::containment;
+use super::content_visibility;
+use super::form_control;
 use super::layout_box::LayoutBox;
-use style::value_processing::{Property, Value};
+use super::percentage::PercentageBases;
+use super::replaced;
+use super::sizing;
+use io::cancellation;
 use style::values::display::{Display, InnerDisplayType};
 
 use super::flow::block::BlockFormattingContext;
 use super::flow::inline::InlineFormattingContext;
+use super::flow::table::TableFormattingContext;
 
 pub trait FormattingContext {
     fn layout(&mut self, boxes: Vec<&mut LayoutBox>) -> f32;
@@ -13,13 +19,33 @@ pub trait FormattingContext {
 }
 
 pub fn layout_children(layout_box: &mut LayoutBox) {
+    // the boxes left once cancelled keep the size of their last layout
+    if cancellation::is_cancelled() {
+        return;
+    }
+    layout_box.contents_skipped = content_visibility::skips_contents(layout_box);
+    if layout_box.contents_skipped {
+        layout_box.skipped_descendants = true;
+        if layout_box.definite_height.is_none() {
+            layout_box.dimensions.set_height(0.);
+        }
+        return;
+    }
+
     let mut context = get_formatting_context(layout_box);
 
     let height = context.layout(layout_box.children.iter_mut().collect());
 
-    if layout_box.is_height_auto() {
+    if layout_box.definite_height.is_none() {
+        // a box with size containment is sized as if it had no content
+        let height = if containment::contains_size(layout_box) {
+            0.
+        } else {
+            height
+        };
         layout_box.dimensions.set_height(height);
     }
+    layout_box.skipped_descendants = content_visibility::has_skipped_contents(&layout_box.children);
 }
 
 fn get_formatting_context(layout_box: &mut LayoutBox) -> Box<dyn FormattingContext> {
@@ -33,42 +59,65 @@ fn get_formatting_context(layout_box: &mut LayoutBox) -> Box<dyn FormattingConte
     let node = layout_box.render_node.clone().unwrap();
     let node = node.borrow();
 
-    let display = node.get_style(&Property::Display);
-    let inner_display = match display.inner() {
-        Value::Display(Display::Full(_, inner)) => inner,
+    let display = node.style().display();
+    let inner_display = match &display {
+        Display::Full(_, inner) | Display::ListItem(_, inner) => *inner,
+        // the internal table boxes laid out on their own, e.g. cells, contain flow content
+        Display::Internal(_) => InnerDisplayType::Flow,
         _ => unreachable!(),
     };
 
     match inner_display {
-        InnerDisplayType::Flow => {
+        // a flow root only contains the floats of its children, its
+        // inline content is laid out in lines like other blocks
+        InnerDisplayType::Flow | InnerDisplayType::FlowRoot => {
             if layout_box.children_are_inline() {
                 Box::new(InlineFormattingContext::new(layout_box))
             } else {
                 Box::new(BlockFormattingContext::new(layout_box))
             }
         }
-        InnerDisplayType::FlowRoot => Box::new(BlockFormattingContext::new(layout_box)),
+        InnerDisplayType::Table => Box::new(TableFormattingContext::new(layout_box)),
         _ => unimplemented!("Unsupported display type: {:#?}", display),
     }
 }
 
-pub fn apply_explicit_sizes(layout_box: &mut LayoutBox, containing_block: &Rect) {
+/// Give a box the height set by its styles before its children are laid
+/// out, so the percentages of their heights can be resolved
+pub fn apply_specified_height(layout_box: &mut LayoutBox, bases: &PercentageBases) {
+    layout_box.definite_height = layout_box.render_node.clone().and_then(|node| {
+        let node = node.borrow();
+        let height = sizing::specified_height(node.style(), bases)?;
+        Some(sizing::clamp_height(node.style(), height, bases))
+    });
+    if let Some(height) = layout_box.definite_height {
+        layout_box.box_model().set_height(height);
+    }
+}
+
+pub fn apply_explicit_sizes(layout_box: &mut LayoutBox, bases: &PercentageBases) {
     if layout_box.is_inline() && !layout_box.is_inline_block() {
         return;
     }
 
-    if let Some(render_node) = &layout_box.render_node {
-        let computed_width = render_node.borrow().get_style(&Property::Width);
-        let computed_height = render_node.borrow().get_style(&Property::Height);
+    let render_node = match layout_box.render_node.clone() {
+        Some(node) => node,
+        None => return,
+    };
+    let render_node = render_node.borrow();
+    let style = render_node.style();
 
-        if !computed_width.is_auto() {
-            let used_width = computed_width.to_px(containing_block.width);
-            layout_box.box_model().set_width(used_width);
-        }
+    let width =
+        sizing::specified_width(style, bases).unwrap_or(layout_box.dimensions.content.width);
+    layout_box
+        .box_model()
+        .set_width(sizing::clamp_width(style, width, bases));
 
-        if !computed_height.is_auto() {
-            let used_height = computed_height.to_px(containing_block.height);
-            layout_box.box_model().set_height(used_height);
-        }
-    }
+    let height = sizing::specified_height(style, bases)
+        .or_else(|| form_control::intrinsic_height(layout_box))
+        .or_else(|| replaced::intrinsic_height(layout_box))
+        .unwrap_or(layout_box.dimensions.content.height);
+    layout_box
+        .box_model()
+        .set_height(sizing::clamp_height(style, height, bases));
 }
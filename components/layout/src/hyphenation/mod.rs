@@ -0,0 +1,223 @@
+/// The hyphenation of the words wrapped at the end of the lines, at the
+/// soft hyphens of the text or at the hyphenation points found with the
+/// Knuth-Liang patterns of its language. The patterns of a few languages
+/// are embedded, the words of other languages are only hyphenated at
+/// their soft hyphens.
+/// https://www.w3.org/TR/css-text-3/#hyphenation
+/// https://tug.org/docs/liang/
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::rc::Rc;
+use style::values::hyphens::Hyphens;
+
+pub const SOFT_HYPHEN: char = '\u{AD}';
+
+/// The hyphen shown at the end of a line ending with a hyphenated word
+pub const HYPHEN: &str = "-";
+
+/// The languages with embedded patterns, as (language range, name,
+/// minimum number of chars before & after a hyphenation point, patterns)
+const LANGUAGES: &[(&str, &str, usize, usize, &str)] = &[
+    ("en", "en-us", 2, 3, include_str!("en-us.txt")),
+    ("fr", "fr", 2, 3, include_str!("fr.txt")),
+    ("es", "es", 2, 2, include_str!("es.txt")),
+];
+
+thread_local! {
+    /// The patterns of the languages, parsed the first time a word of
+    /// the language is hyphenated
+    static PATTERNS: RefCell<HashMap<&'static str, Rc<Patterns>>> = RefCell::new(HashMap::new());
+}
+
+/// The hyphenation patterns of a language. A pattern is a piece of a
+/// word with the levels between its letters, e.g. `a1bc`. The levels of
+/// all the patterns found in a word are merged, the word can be
+/// hyphenated where the highest level is odd.
+pub struct Patterns {
+    /// The levels between the chars of the patterns, by their letters
+    levels: HashMap<String, Vec<u8>>,
+    /// The number of chars of the longest pattern
+    max_len: usize,
+    left_min: usize,
+    right_min: usize,
+}
+
+impl Patterns {
+    /// Parse a list of patterns, one per line, `%` starting a comment
+    pub fn parse(text: &str, left_min: usize, right_min: usize) -> Self {
+        let mut levels = HashMap::new();
+        let mut max_len = 0;
+        for pattern in text.lines().filter_map(|line| line.split('%').next()) {
+            let pattern = pattern.trim();
+            if pattern.is_empty() {
+                continue;
+            }
+            let mut letters = String::new();
+            let mut pattern_levels = vec![0];
+            for c in pattern.chars() {
+                match c.to_digit(10) {
+                    Some(level) => *pattern_levels.last_mut().unwrap() = level as u8,
+                    None => {
+                        letters.push(c);
+                        pattern_levels.push(0);
+                    }
+                }
+            }
+            max_len = max_len.max(pattern_levels.len() - 1);
+            levels.insert(letters, pattern_levels);
+        }
+        Self {
+            levels,
+            max_len,
+            left_min,
+            right_min,
+        }
+    }
+
+    /// The char indices a word can be hyphenated at, the word only made
+    /// of letters
+    pub fn hyphenation_points(&self, word: &str) -> Vec<usize> {
+        let len = word.chars().count();
+        if len < self.left_min + self.right_min {
+            return Vec::new();
+        }
+        // the dots match the patterns at the start or the end of a word
+        let dotted = format!(".{}.", word.to_lowercase())
+            .chars()
+            .collect::<Vec<char>>();
+        if dotted.len() != len + 2 {
+            return Vec::new();
+        }
+
+        // levels[i] is the level before the char i of the dotted word
+        let mut levels = vec![0; dotted.len() + 1];
+        for start in 0..dotted.len() {
+            let end = dotted.len().min(start + self.max_len);
+            let mut letters = String::new();
+            for &c in &dotted[start..end] {
+                letters.push(c);
+                if let Some(pattern_levels) = self.levels.get(&letters) {
+                    for (offset, &level) in pattern_levels.iter().enumerate() {
+                        let merged = &mut levels[start + offset];
+                        *merged = level.max(*merged);
+                    }
+                }
+            }
+        }
+
+        (self.left_min..=len - self.right_min)
+            .filter(|index| levels[index + 1] % 2 == 1)
+            .collect()
+    }
+}
+
+/// The patterns of a language, e.g. `en-GB`, if they're embedded
+pub fn patterns(language: &str) -> Option<Rc<Patterns>> {
+    let &(_, name, left_min, right_min, text) = LANGUAGES
+        .iter()
+        .find(|(range, ..)| dom::lang::matches_language_range(language, range))?;
+    PATTERNS.with(|patterns| {
+        let patterns = patterns
+            .borrow_mut()
+            .entry(name)
+            .or_insert_with(|| Rc::new(Patterns::parse(text, left_min, right_min)))
+            .clone();
+        Some(patterns)
+    })
+}
+
+/// Remove the soft hyphens of a word & find where it can be hyphenated.
+/// Returns the word without soft hyphens & the byte offsets in it of the
+/// hyphenation points.
+pub fn hyphenate(word: &str, hyphens: &Hyphens, language: Option<&str>) -> (String, Vec<usize>) {
+    let mut text = String::with_capacity(word.len());
+    let mut soft_hyphens = Vec::new();
+    for c in word.chars() {
+        if c == SOFT_HYPHEN {
+            soft_hyphens.push(text.len());
+        } else {
+            text.push(c);
+        }
+    }
+    soft_hyphens.retain(|&offset| offset > 0 && offset < text.len());
+    soft_hyphens.dedup();
+
+    let points = match hyphens {
+        Hyphens::None => Vec::new(),
+        Hyphens::Manual => soft_hyphens,
+        Hyphens::Auto if !soft_hyphens.is_empty() => soft_hyphens,
+        Hyphens::Auto => match language.and_then(patterns) {
+            Some(patterns) => pattern_points(&text, &patterns),
+            None => Vec::new(),
+        },
+    };
+    (text, points)
+}
+
+/// The hyphenation points of the runs of letters of a word, e.g. the
+/// word of `(wonderful),`
+fn pattern_points(word: &str, patterns: &Patterns) -> Vec<usize> {
+    let mut points = Vec::new();
+    let mut runs = word.char_indices().peekable();
+    while let Some((start, c)) = runs.next() {
+        if !c.is_alphabetic() {
+            continue;
+        }
+        let mut end = start + c.len_utf8();
+        while let Some(&(index, c)) = runs.peek() {
+            if !c.is_alphabetic() {
+                break;
+            }
+            end = index + c.len_utf8();
+            runs.next();
+        }
+        let run = &word[start..end];
+        let offsets = run
+            .char_indices()
+            .map(|(offset, _)| offset)
+            .collect::<Vec<_>>();
+        for index in patterns.hyphenation_points(run) {
+            points.push(start + offsets[index]);
+        }
+    }
+    points
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn hyphenated(word: &str, hyphens: &Hyphens, language: &str) -> String {
+        let (mut text, points) = hyphenate(word, hyphens, Some(language));
+        for point in points.into_iter().rev() {
+            text.insert(point, '-');
+        }
+        text
+    }
+
+    #[test]
+    fn hyphenate_with_patterns() {
+        let auto = Hyphens::Auto;
+        assert_eq!(hyphenated("extensive", &auto, "en"), "ex-ten-sive");
+        assert_eq!(hyphenated("Probability", &auto, "en-US"), "Prob-a-bil-ity");
+        assert_eq!(hyphenated("recognize", &auto, "en-GB"), "rec-og-nize");
+        assert_eq!(
+            hyphenated("(hyphenation),", &auto, "en"),
+            "(hy-phen-ation),"
+        );
+        assert_eq!(hyphenated("hi", &auto, "en"), "hi");
+        assert_eq!(hyphenated("typographie", &auto, "fr"), "ty-po-gra-phie");
+        assert_eq!(hyphenated("extensive", &auto, "xx"), "extensive");
+    }
+
+    #[test]
+    fn hyphenate_at_soft_hyphens() {
+        let word = "ex\u{AD}tensive";
+        assert_eq!(hyphenated(word, &Hyphens::None, "en"), "extensive");
+        assert_eq!(hyphenated(word, &Hyphens::Manual, "en"), "ex-tensive");
+        // the soft hyphens replace the patterns
+        assert_eq!(hyphenated(word, &Hyphens::Auto, "en"), "ex-tensive");
+        assert_eq!(hyphenated("extensive", &Hyphens::Manual, "en"), "extensive");
+        assert_eq!(hyphenated("\u{AD}a\u{AD}", &Hyphens::Manual, "en"), "a");
+    }
+}
@@ -1,10 +1,11 @@
 /// This module contains the definition of
 /// the layout box, which is the component
 /// that made up the layout tree.
-use super::box_model::Dimensions;
+use super::box_model::{Dimensions, Rect};
+use super::list_marker::Marker;
+use super::text::TextFragment;
 use style::render_tree::RenderNodeRef;
-use style::value_processing::{Property, Value};
-use style::values::display::{Display, InnerDisplayType};
+use style::values::display::InnerDisplayType;
 use style::values::float::Float;
 use style::values::position::Position;
 
@@ -26,6 +27,35 @@ pub struct LayoutBox {
 
     /// The children of this box
     pub children: Vec<LayoutBox>,
+
+    /// Indicate if the box or one of its descendants changed since the
+    /// last layout. A clean box keeps its size as long as its containing
+    /// block size stays the same.
+    pub needs_layout: bool,
+
+    /// Size of the containing block the box was last laid out in
+    pub containing_size: Option<(f32, f32)>,
+
+    /// The height of the content box when it doesn't depend on the content
+    /// of the box, which the percentages of the heights of its children
+    /// are relative to
+    pub definite_height: Option<f32>,
+
+    /// The marker of the box if the box is a list item
+    pub marker: Option<Marker>,
+
+    /// The pieces of text placed on each line, if the box is a text box
+    pub text_fragments: Vec<TextFragment>,
+
+    /// Indicate if the layout of the children of the box was skipped by
+    /// its `content-visibility`. The children keep the layout they had
+    /// and aren't painted.
+    pub contents_skipped: bool,
+
+    /// Indicate if the layout of the children of the box or of one of its
+    /// descendants was skipped. Such a box is laid out again on the next
+    /// layout, in case the skipped contents became relevant.
+    pub skipped_descendants: bool,
 }
 
 /// Different box types for each layout box
@@ -36,6 +66,18 @@ pub enum BoxType {
 
     /// Inline-level box
     Inline,
+
+    /// Block-level box establishing a table formatting context
+    Table,
+
+    /// Box grouping the rows of a table
+    TableRowGroup,
+
+    /// Row of a table
+    TableRow,
+
+    /// Cell of a table row
+    TableCell,
 }
 
 impl LayoutBox {
@@ -46,6 +88,13 @@ impl LayoutBox {
             dimensions: Dimensions::default(),
             children_are_inline: false,
             children: Vec::new(),
+            needs_layout: true,
+            containing_size: None,
+            definite_height: None,
+            marker: None,
+            text_fragments: Vec::new(),
+            contents_skipped: false,
+            skipped_descendants: false,
         }
     }
 
@@ -56,6 +105,13 @@ impl LayoutBox {
             dimensions: Dimensions::default(),
             children_are_inline: false,
             children: Vec::new(),
+            needs_layout: true,
+            containing_size: None,
+            definite_height: None,
+            marker: None,
+            text_fragments: Vec::new(),
+            contents_skipped: false,
+            skipped_descendants: false,
         }
     }
 
@@ -71,12 +127,16 @@ impl LayoutBox {
         self.box_type == BoxType::Block
     }
 
+    pub fn is_text(&self) -> bool {
+        match &self.render_node {
+            Some(node) => node.borrow().node.is_text(),
+            None => false,
+        }
+    }
+
     pub fn is_float(&self) -> bool {
         match &self.render_node {
-            Some(node) => match node.borrow().get_style(&Property::Float).inner() {
-                Value::Float(Float::None) => false,
-                _ => true,
-            },
+            Some(node) => node.borrow().style().float() != Float::None,
             _ => false,
         }
     }
@@ -85,7 +145,7 @@ impl LayoutBox {
         match &self.render_node {
             Some(node) => match node.borrow().node.borrow().as_element_opt() {
                 Some(e) => match e.tag_name().as_str() {
-                    "video" | "image" | "img" | "canvas" => false,
+                    "video" | "image" | "img" | "canvas" | "svg" => false,
                     _ => true,
                 },
                 _ => true,
@@ -96,10 +156,10 @@ impl LayoutBox {
 
     pub fn is_inline_block(&self) -> bool {
         match &self.render_node {
-            Some(node) => match node.borrow().get_style(&Property::Display).inner() {
-                Value::Display(Display::Full(_, InnerDisplayType::FlowRoot)) => self.is_inline(),
-                _ => false,
-            },
+            Some(node) => {
+                node.borrow().style().display().inside() == Some(InnerDisplayType::FlowRoot)
+                    && self.is_inline()
+            }
             _ => false,
         }
     }
@@ -111,10 +171,7 @@ impl LayoutBox {
 
     pub fn is_absolutely_positioned(&self) -> bool {
         match &self.render_node {
-            Some(node) => match node.borrow().get_style(&Property::Position).inner() {
-                Value::Position(Position::Absolute) => true,
-                _ => false,
-            },
+            Some(node) => node.borrow().style().position() == Position::Absolute,
             _ => false,
         }
     }
@@ -135,9 +192,74 @@ impl LayoutBox {
         self.children_are_inline
     }
 
+    /// Whether the previous layout of the box is still valid in a containing block
+    pub fn can_reuse_layout(&self, containing_block: &Rect) -> bool {
+        !self.needs_layout
+            && !self.skipped_descendants
+            && self.containing_size == Some((containing_block.width, containing_block.height))
+    }
+
+    /// Mark the box as laid out in a containing block
+    pub fn finish_layout(&mut self, containing_block: &Rect) {
+        self.needs_layout = false;
+        self.containing_size = Some((containing_block.width, containing_block.height));
+    }
+
+    /// Move the descendants of the box, e.g. after the box itself was moved
+    /// without being laid out again
+    pub fn translate_children(&mut self, dx: f32, dy: f32) {
+        for child in &mut self.children {
+            child.dimensions.content.x += dx;
+            child.dimensions.content.y += dy;
+            if let Some(marker) = &mut child.marker {
+                marker.rect.x += dx;
+                marker.rect.y += dy;
+            }
+            for fragment in &mut child.text_fragments {
+                fragment.rect.x += dx;
+                fragment.rect.y += dy;
+            }
+            child.translate_children(dx, dy);
+        }
+    }
+
+    /// The bottom edge of the content painted by the box and its descendants,
+    /// including the content overflowing the box
+    pub fn content_bottom(&self) -> f32 {
+        let margin_box = self.dimensions.margin_box();
+        let mut bottom = margin_box.y + margin_box.height;
+        if let Some(marker) = &self.marker {
+            bottom = bottom.max(marker.rect.y + marker.rect.height);
+        }
+        for fragment in &self.text_fragments {
+            bottom = bottom.max(fragment.rect.y + fragment.rect.height);
+        }
+        self.children
+            .iter()
+            .map(|child| child.content_bottom())
+            .fold(bottom, f32::max)
+    }
+
+    /// The width of the marker placed at the start of the first line of the box
+    pub fn inside_marker_width(&self) -> f32 {
+        match &self.marker {
+            Some(marker) if marker.is_inside() => marker.rect.width,
+            _ => 0.,
+        }
+    }
+
+    pub fn is_width_auto(&self) -> bool {
+        if let Some(node) = &self.render_node {
+            let computed_width = node.borrow().style().width();
+
+            return computed_width.is_auto();
+        }
+        return true;
+    }
+
     pub fn is_height_auto(&self) -> bool {
         if let Some(node) = &self.render_node {
-            let computed_height = node.borrow().get_style(&Property::Height);
+            let computed_height = node.borrow().style().height();
 
             return computed_height.is_auto();
         }
@@ -1,9 +1,19 @@
 pub mod box_model;
+pub mod containment;
+pub mod content_visibility;
 pub mod flow;
+pub mod form_control;
 pub mod formatting_context;
+pub mod hyphenation;
 pub mod layout_box;
 pub mod layout_printer;
 pub mod line_box;
+pub mod list_marker;
+pub mod pagination;
+pub mod percentage;
+pub mod replaced;
+pub mod sizing;
+pub mod text;
 pub mod tree_builder;
 
 use box_model::Rect;
@@ -17,12 +27,29 @@ pub fn compute_layout(root: &mut LayoutBox, viewport: &Rect) {
     let mut viewport_box = LayoutBox::new_anonymous(layout_box::BoxType::Block);
     viewport_box.box_model().set_width(viewport.width);
     viewport_box.box_model().set_height(viewport.height);
+    viewport_box.definite_height = Some(viewport.height);
     let mut context = BlockFormattingContext::new(&mut viewport_box);
-    context.layout(vec![root]);
+    context.layout(vec![&mut *root]);
+    containment::layout_dirty_roots(root);
+}
+
+/// Lay out a tree, skipping the contents of the `content-visibility: auto`
+/// boxes outside of the area of the page visible to the user
+pub fn compute_lazy_layout(root: &mut LayoutBox, viewport: &Rect, visible_area: &Rect) {
+    content_visibility::with_visible_area(visible_area, || compute_layout(root, viewport));
 }
 
 pub fn build_layout_tree(tree: &RenderTree) -> Option<LayoutBox> {
-    let layout_tree_builder = TreeBuilder::new(tree.root.clone().unwrap());
+    // a document without rendered elements, e.g. parsed until cancelled
+    let layout_tree_builder = TreeBuilder::new(tree.root.clone()?);
+
+    layout_tree_builder.build()
+}
+
+/// Rebuild the layout tree, keeping the boxes of the previous layout
+/// tree for the render nodes that didn't change since it was built
+pub fn rebuild_layout_tree(tree: &RenderTree, previous: LayoutBox) -> Option<LayoutBox> {
+    let layout_tree_builder = TreeBuilder::new(tree.root.clone()?).reuse(previous);
 
     layout_tree_builder.build()
 }
@@ -1,10 +1,21 @@
 use super::layout_box::LayoutBox;
 
+/// A piece of content placed on a line
+#[derive(Debug, Clone)]
+pub enum LineFragment {
+    /// An inline-level box
+    Box(*mut LayoutBox),
+    /// A text fragment of a text box, by index
+    Text(*mut LayoutBox, usize),
+}
+
 #[derive(Debug, Clone)]
 pub struct LineBox {
-    fragments: Vec<*mut LayoutBox>,
+    fragments: Vec<LineFragment>,
     width: f32,
     height: f32,
+    /// Whether the line ends with a forced line break
+    ends_with_forced_break: bool,
 }
 
 impl LineBox {
@@ -13,29 +24,56 @@ impl LineBox {
             fragments: Vec::new(),
             width: 0.,
             height: 0.,
+            ends_with_forced_break: false,
         }
     }
 
-    pub fn fragments(&self) -> Vec<&mut LayoutBox> {
-        unsafe {
-            self.fragments
-                .iter()
-                .map(|layout_box| layout_box.as_mut().unwrap())
-                .collect()
-        }
+    pub fn fragments(&self) -> &[LineFragment] {
+        &self.fragments
     }
 
     pub fn push(&mut self, layout_box: &mut LayoutBox) {
         let fragment_height = layout_box.dimensions.margin_box().height;
         let fragment_width = layout_box.dimensions.margin_box().width;
 
-        if fragment_height > self.height {
-            self.height = fragment_height;
+        self.grow(fragment_width, fragment_height);
+
+        self.fragments.push(LineFragment::Box(layout_box));
+    }
+
+    /// Place a text fragment of a text box on the line
+    pub fn push_text(&mut self, text_box: &mut LayoutBox, index: usize) {
+        let rect = &text_box.text_fragments[index].rect;
+        let (width, height) = (rect.width, rect.height);
+
+        self.grow(width, height);
+
+        self.fragments.push(LineFragment::Text(text_box, index));
+    }
+
+    fn grow(&mut self, width: f32, height: f32) {
+        if height > self.height {
+            self.height = height;
         }
 
-        self.width += fragment_width;
+        self.width += width;
+    }
+
+    /// Narrow the line, e.g. when the spaces at its end are removed
+    pub fn shrink(&mut self, width: f32) {
+        self.width -= width;
+    }
+
+    pub fn set_forced_break(&mut self) {
+        self.ends_with_forced_break = true;
+    }
+
+    pub fn ends_with_forced_break(&self) -> bool {
+        self.ends_with_forced_break
+    }
 
-        self.fragments.push(layout_box);
+    pub fn is_empty(&self) -> bool {
+        self.fragments.is_empty()
     }
 
     pub fn width(&self) -> f32 {
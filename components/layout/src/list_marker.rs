@@ -0,0 +1,376 @@
+/// This module is responsible for the generation of the
+/// `::marker` boxes of list items. A marker is laid out
+/// next to the principal box of its list item, either
+/// outside of it (in the padding of the list) or inside
+/// at the start of its first line.
+use super::box_model::Rect;
+use super::layout_box::LayoutBox;
+use super::text::{self, FontMetrics};
+use style::render_tree::RenderNodeRef;
+use style::values::list_style_position::ListStylePosition;
+use style::values::list_style_type::ListStyleType;
+
+/// The marker box of a list item
+#[derive(Debug, Clone, PartialEq)]
+pub struct Marker {
+    pub content: MarkerContent,
+    pub position: ListStylePosition,
+    /// The area of the marker, including the space separating it
+    /// from the content of the list item
+    pub rect: Rect,
+    /// The font size of the list item
+    pub font_size: f32,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum MarkerContent {
+    Disc,
+    Circle,
+    Square,
+    /// A counter representation, e.g. `3.` or `iv.`
+    Text(String),
+}
+
+impl Marker {
+    /// The area of the bullet or the text of the marker
+    pub fn glyph_rect(&self) -> Rect {
+        let metrics = FontMetrics::new(self.font_size);
+        let size = match self.content {
+            MarkerContent::Text(_) => {
+                return Rect {
+                    x: self.rect.x,
+                    y: self.rect.y,
+                    width: self.rect.width - metrics.font_size / 4.,
+                    height: self.rect.height,
+                }
+            }
+            _ => bullet_size(&metrics),
+        };
+
+        Rect {
+            x: self.rect.x,
+            y: self.rect.y + (self.rect.height - size) / 2.,
+            width: size,
+            height: size,
+        }
+    }
+
+    pub fn is_inside(&self) -> bool {
+        self.position == ListStylePosition::Inside
+    }
+}
+
+impl MarkerContent {
+    fn width(&self, metrics: &FontMetrics) -> f32 {
+        match self {
+            MarkerContent::Text(text) => metrics.measure(text) + metrics.font_size / 4.,
+            _ => bullet_size(metrics) + metrics.font_size / 2.,
+        }
+    }
+}
+
+fn bullet_size(metrics: &FontMetrics) -> f32 {
+    metrics.font_size / 3.
+}
+
+pub fn is_list_item(node: &RenderNodeRef) -> bool {
+    node.borrow().style().display().is_list_item()
+}
+
+/// Generate & position the marker of a list item box, after the
+/// position of the box has been computed
+pub fn layout_marker(layout_box: &mut LayoutBox) {
+    let node = match &layout_box.render_node {
+        Some(node) if is_list_item(node) => node.clone(),
+        _ => {
+            layout_box.marker = None;
+            return;
+        }
+    };
+
+    let (list_style_type, position) = {
+        let node = node.borrow();
+        let style = node.style();
+        (style.list_style_type(), style.list_style_position())
+    };
+
+    let content = match marker_content(&list_style_type, list_item_ordinal(&node)) {
+        Some(content) => content,
+        None => {
+            layout_box.marker = None;
+            return;
+        }
+    };
+
+    let metrics = text::font_metrics(&node);
+    let width = content.width(&metrics);
+    let content_box = &layout_box.dimensions.content;
+    let x = match position {
+        ListStylePosition::Outside => layout_box.dimensions.border_box().x - width,
+        ListStylePosition::Inside => content_box.x,
+    };
+
+    layout_box.marker = Some(Marker {
+        rect: Rect {
+            x,
+            y: content_box.y,
+            width,
+            height: text::line_height(&node, &metrics),
+        },
+        font_size: metrics.font_size,
+        content,
+        position,
+    });
+}
+
+/// The ordinal value of a list item, counted from the list items that
+/// are siblings of the item.
+/// https://html.spec.whatwg.org/multipage/grouping-content.html#ordinal-value
+pub fn list_item_ordinal(node: &RenderNodeRef) -> i32 {
+    let parent = match node.borrow().parent_render_node.clone() {
+        Some(parent) => parent.upgrade(),
+        None => None,
+    };
+    let parent = match parent {
+        Some(parent) => parent,
+        None => return integer_attribute(node, "value").unwrap_or(1),
+    };
+
+    let items = parent
+        .borrow()
+        .children
+        .iter()
+        .filter(|child| is_list_item(child))
+        .cloned()
+        .collect::<Vec<RenderNodeRef>>();
+
+    let is_reversed = match parent.borrow().node.borrow().as_element_opt() {
+        Some(element) => element.has_tag_name("ol") && element.attributes().get_bool("reversed"),
+        None => false,
+    };
+    let increment = if is_reversed { -1 } else { 1 };
+
+    let start = integer_attribute(&parent, "start").unwrap_or(if is_reversed {
+        items.len() as i32
+    } else {
+        1
+    });
+
+    let mut ordinal = start - increment;
+    for item in items {
+        ordinal = integer_attribute(&item, "value").unwrap_or(ordinal + increment);
+        if item.as_ptr() == node.as_ptr() {
+            break;
+        }
+    }
+    ordinal
+}
+
+fn integer_attribute(node: &RenderNodeRef, name: &str) -> Option<i32> {
+    let node = node.borrow();
+    let dom_node = node.node.borrow();
+    let element = dom_node.as_element_opt()?;
+    element.get_attribute(name)?.trim().parse().ok()
+}
+
+/// The content of the marker of a list item at an ordinal
+pub fn marker_content(list_style_type: &ListStyleType, ordinal: i32) -> Option<MarkerContent> {
+    let text = match list_style_type {
+        ListStyleType::None => return None,
+        ListStyleType::Disc => return Some(MarkerContent::Disc),
+        ListStyleType::Circle => return Some(MarkerContent::Circle),
+        ListStyleType::Square => return Some(MarkerContent::Square),
+        ListStyleType::Decimal => ordinal.to_string(),
+        ListStyleType::DecimalLeadingZero => match ordinal {
+            0..=9 => format!("0{}", ordinal),
+            -9..=-1 => format!("-0{}", -ordinal),
+            _ => ordinal.to_string(),
+        },
+        ListStyleType::LowerRoman => to_roman(ordinal).to_lowercase(),
+        ListStyleType::UpperRoman => to_roman(ordinal),
+        ListStyleType::LowerAlpha => to_alphabetic(ordinal).to_lowercase(),
+        ListStyleType::UpperAlpha => to_alphabetic(ordinal),
+    };
+
+    Some(MarkerContent::Text(format!("{}.", text)))
+}
+
+/// Roman numerals are defined for 1 to 3999, other values
+/// fall back to decimal
+fn to_roman(value: i32) -> String {
+    if !(1..=3999).contains(&value) {
+        return value.to_string();
+    }
+
+    let numerals = [
+        (1000, "M"),
+        (900, "CM"),
+        (500, "D"),
+        (400, "CD"),
+        (100, "C"),
+        (90, "XC"),
+        (50, "L"),
+        (40, "XL"),
+        (10, "X"),
+        (9, "IX"),
+        (5, "V"),
+        (4, "IV"),
+        (1, "I"),
+    ];
+
+    let mut value = value;
+    let mut result = String::new();
+    for (numeral_value, numeral) in numerals.iter() {
+        while value >= *numeral_value {
+            result.push_str(numeral);
+            value -= numeral_value;
+        }
+    }
+    result
+}
+
+/// Alphabetic counters go A, B, ..., Z, AA, AB, ... and are only
+/// defined for positive values
+fn to_alphabetic(value: i32) -> String {
+    if value < 1 {
+        return value.to_string();
+    }
+
+    let mut value = value;
+    let mut result = Vec::new();
+    while value > 0 {
+        value -= 1;
+        result.push((b'A' + (value % 26) as u8) as char);
+        value /= 26;
+    }
+    result.iter().rev().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout_box::BoxType;
+    use crate::tree_builder::TreeBuilder;
+    use css::cssom::css_rule::CSSRule;
+    use dom::node::Node;
+    use style::build_render_tree;
+    use style::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+
+    #[test]
+    fn generate_list_markers() {
+        let document = document();
+        let numbered_item = element("li", document.clone(), vec![]);
+        Node::set_attribute(&numbered_item, "value", "10");
+        let ordered_list = element(
+            "ol",
+            document.clone(),
+            vec![
+                element("li", document.clone(), vec![]),
+                numbered_item,
+                element("li", document.clone(), vec![]),
+            ],
+        );
+        Node::set_attribute(&ordered_list, "start", "3");
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                ordered_list,
+                element(
+                    "ul",
+                    document.clone(),
+                    vec![element("li", document.clone(), vec![])],
+                ),
+            ],
+        );
+
+        let css = r#"
+        div, ol, ul {
+            display: block;
+            padding-left: 40px;
+        }
+        ol {
+            list-style-type: decimal;
+        }
+        ul {
+            list-style: square inside;
+        }
+        li {
+            display: list-item;
+            height: 10px;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom.clone(), &rules);
+        let mut layout_box = TreeBuilder::new(render_tree.root.unwrap()).build().unwrap();
+        let viewport = Rect {
+            x: 0.,
+            y: 0.,
+            width: 200.,
+            height: 200.,
+        };
+        crate::compute_layout(&mut layout_box, &viewport);
+
+        let ordered_items = &layout_box.children[0].children;
+        let texts = ordered_items
+            .iter()
+            .map(|item| item.marker.as_ref().unwrap().content.clone())
+            .collect::<Vec<MarkerContent>>();
+        assert_eq!(
+            texts,
+            vec![
+                MarkerContent::Text("3.".to_string()),
+                MarkerContent::Text("10.".to_string()),
+                MarkerContent::Text("11.".to_string()),
+            ]
+        );
+
+        // outside markers are placed in the padding of the list
+        let item = &ordered_items[1];
+        let marker = item.marker.as_ref().unwrap();
+        assert_eq!(marker.rect.x + marker.rect.width, item.dimensions.content.x);
+        assert_eq!(marker.rect.y, item.dimensions.content.y);
+
+        let unordered_item = &layout_box.children[1].children[0];
+        assert!(unordered_item.box_type == BoxType::Block);
+        let marker = unordered_item.marker.as_ref().unwrap();
+        assert_eq!(marker.content, MarkerContent::Square);
+        assert!(marker.is_inside());
+        assert_eq!(marker.rect.x, unordered_item.dimensions.content.x);
+    }
+
+    #[test]
+    fn format_counters() {
+        let text = |list_style_type, ordinal| match marker_content(&list_style_type, ordinal) {
+            Some(MarkerContent::Text(text)) => text,
+            content => panic!("Unexpected marker content: {:?}", content),
+        };
+
+        assert_eq!(text(ListStyleType::Decimal, 3), "3.");
+        assert_eq!(text(ListStyleType::DecimalLeadingZero, 7), "07.");
+        assert_eq!(text(ListStyleType::LowerRoman, 1994), "mcmxciv.");
+        assert_eq!(text(ListStyleType::UpperRoman, 0), "0.");
+        assert_eq!(text(ListStyleType::LowerAlpha, 28), "ab.");
+        assert_eq!(text(ListStyleType::UpperAlpha, 26), "Z.");
+        assert_eq!(marker_content(&ListStyleType::None, 1), None);
+        assert_eq!(
+            marker_content(&ListStyleType::Square, 1),
+            Some(MarkerContent::Square)
+        );
+    }
+}
@@ -0,0 +1,151 @@
+/// Fragmentation of a laid out tree into pages of a height. The lines,
+/// the markers & the boxes without children can't be split, so the ones
+/// straddling the end of a page are pushed to the start of the next page
+/// with the content after them, and their ancestors grow by the gap.
+/// https://www.w3.org/TR/css-break-3/#breaking-rules
+use super::box_model::Rect;
+use super::layout_box::LayoutBox;
+
+/// Push the content of a tree straddling the end of a page to the next
+/// page. Returns the number of pages of the content.
+pub fn paginate(root: &mut LayoutBox, page_height: f32) -> usize {
+    let mut units = Vec::new();
+    collect_unbreakable(root, &mut units);
+    units.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap());
+
+    // the content at or below a break is moved down by its distance
+    let mut breaks: Vec<(f32, f32)> = Vec::new();
+    let mut shift = 0.;
+    for (top, bottom) in units {
+        let (top, bottom) = (top + shift, bottom + shift);
+        // content taller than a page is cut wherever it ends up
+        if bottom - top > page_height {
+            continue;
+        }
+        let page_end = ((top / page_height).floor() + 1.) * page_height;
+        if bottom > page_end + 0.01 {
+            let distance = page_end - top;
+            breaks.push((top - shift, distance));
+            shift += distance;
+        }
+    }
+
+    if !breaks.is_empty() {
+        move_content(root, &breaks);
+    }
+    ((root.content_bottom() / page_height).ceil() as usize).max(1)
+}
+
+/// The vertical extents of the pieces of content that can't be split
+fn collect_unbreakable(layout_box: &LayoutBox, units: &mut Vec<(f32, f32)>) {
+    if let Some(marker) = &layout_box.marker {
+        units.push((marker.rect.y, marker.rect.y + marker.rect.height));
+    }
+    for fragment in &layout_box.text_fragments {
+        units.push((fragment.rect.y, fragment.rect.y + fragment.rect.height));
+    }
+    if is_unbreakable(layout_box) {
+        let border_box = layout_box.dimensions.border_box();
+        if border_box.height > 0. {
+            units.push((border_box.y, border_box.y + border_box.height));
+        }
+        return;
+    }
+    for child in &layout_box.children {
+        collect_unbreakable(child, units);
+    }
+}
+
+/// The boxes without laid out children are moved as a whole
+fn is_unbreakable(layout_box: &LayoutBox) -> bool {
+    layout_box.contents_skipped
+        || layout_box.children.is_empty() && layout_box.text_fragments.is_empty()
+}
+
+fn move_content(layout_box: &mut LayoutBox, breaks: &[(f32, f32)]) {
+    // the top edges at a break are moved, the bottom edges at a break
+    // belong to the content before it
+    let moved_top = |y: f32| {
+        y + breaks
+            .iter()
+            .filter(|(at, _)| *at <= y)
+            .map(|(_, distance)| distance)
+            .sum::<f32>()
+    };
+    let moved_bottom = |y: f32| {
+        y + breaks
+            .iter()
+            .filter(|(at, _)| *at < y)
+            .map(|(_, distance)| distance)
+            .sum::<f32>()
+    };
+    let move_rect = |rect: &mut Rect| rect.y = moved_top(rect.y);
+
+    let is_unbreakable = is_unbreakable(layout_box);
+    let content = &mut layout_box.dimensions.content;
+    if is_unbreakable {
+        move_rect(content);
+    } else {
+        let bottom = moved_bottom(content.y + content.height);
+        content.y = moved_top(content.y);
+        content.height = (bottom - content.y).max(0.);
+    }
+    if let Some(marker) = &mut layout_box.marker {
+        move_rect(&mut marker.rect);
+    }
+    for fragment in &mut layout_box.text_fragments {
+        move_rect(&mut fragment.rect);
+    }
+    for child in &mut layout_box.children {
+        move_content(child, breaks);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::layout_box::BoxType;
+
+    fn block(y: f32, height: f32, children: Vec<LayoutBox>) -> LayoutBox {
+        let mut layout_box = LayoutBox::new_anonymous(BoxType::Block);
+        layout_box.box_model().set_position(0., y);
+        layout_box.box_model().set_height(height);
+        layout_box.children = children;
+        layout_box
+    }
+
+    fn tops(layout_box: &LayoutBox) -> Vec<f32> {
+        layout_box
+            .children
+            .iter()
+            .map(|child| child.dimensions.content.y)
+            .collect()
+    }
+
+    #[test]
+    fn push_content_to_next_page() {
+        let mut root = block(
+            0.,
+            270.,
+            vec![
+                block(0., 40., vec![]),
+                block(40., 40., vec![]),
+                block(80., 40., vec![]),
+                // taller than a page
+                block(120., 150., vec![]),
+            ],
+        );
+        assert_eq!(paginate(&mut root, 100.), 3);
+        assert_eq!(tops(&root), vec![0., 40., 100., 140.]);
+        assert_eq!(root.children[2].dimensions.content.height, 40.);
+        assert_eq!(root.dimensions.content.height, 290.);
+
+        let mut root = block(
+            0.,
+            80.,
+            vec![block(0., 40., vec![]), block(40., 40., vec![])],
+        );
+        assert_eq!(paginate(&mut root, 100.), 1);
+        assert_eq!(tops(&root), vec![0., 40.]);
+    }
+}
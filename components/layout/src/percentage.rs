@@ -0,0 +1,107 @@
+/// This module resolves the percentages kept in the computed values of
+/// the sizes, margins, paddings & offsets of a box. A percentage is
+/// relative to the width or the height of the containing block, known
+/// once the containing block is laid out. A percentage of a height that
+/// depends on the content of the containing block can't be resolved, and
+/// the property behaves as if it wasn't set.
+/// https://www.w3.org/TR/CSS2/visudet.html#the-height-property
+use super::layout_box::LayoutBox;
+use style::value_processing::{PercentageBasis, Property};
+use style::values::prelude::{LengthPercentage, LengthPercentageAuto};
+
+/// The sizes the percentages of the boxes in a containing block are
+/// relative to
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PercentageBases {
+    pub width: f32,
+    /// The height of the containing block, if it doesn't depend on its content
+    pub height: Option<f32>,
+}
+
+impl PercentageBases {
+    pub fn new(width: f32, height: Option<f32>) -> Self {
+        Self { width, height }
+    }
+
+    /// The bases of the boxes laid out in a containing block
+    pub fn of(containing_block: &LayoutBox) -> Self {
+        Self::new(
+            containing_block.dimensions.content.width,
+            containing_block.definite_height,
+        )
+    }
+
+    fn basis(&self, property: &Property) -> Option<f32> {
+        match property.percentage_basis() {
+            Some(PercentageBasis::ContainingBlockWidth) => Some(self.width),
+            Some(PercentageBasis::ContainingBlockHeight) => self.height,
+            None => unreachable!("{:?} isn't resolved during layout", property),
+        }
+    }
+
+    /// The value of a property in px, or `None` for a percentage of an
+    /// unknown height
+    pub fn resolve_definite(&self, property: &Property, value: &LengthPercentage) -> Option<f32> {
+        match value {
+            LengthPercentage::Length(length) => Some(length.to_px()),
+            LengthPercentage::Percentage(percentage) => {
+                self.basis(property).map(|basis| percentage.to_px(basis))
+            }
+        }
+    }
+
+    /// The value of a property in px, a percentage of an unknown height
+    /// being 0
+    pub fn resolve(&self, property: &Property, value: &LengthPercentage) -> f32 {
+        self.resolve_definite(property, value).unwrap_or(0.)
+    }
+
+    /// The value of a property in px, or `None` when it's `auto` or
+    /// behaves as `auto`
+    pub fn resolve_auto(&self, property: &Property, value: &LengthPercentageAuto) -> Option<f32> {
+        match value {
+            LengthPercentageAuto::LengthPercentage(value) => self.resolve_definite(property, value),
+            LengthPercentageAuto::Auto => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use style::values::prelude::{Length, Percentage};
+
+    fn percent(value: f32) -> LengthPercentage {
+        LengthPercentage::Percentage(Percentage(value.into()))
+    }
+
+    #[test]
+    fn resolve_percentages() {
+        let bases = PercentageBases::new(200., None);
+        assert_eq!(bases.resolve(&Property::PaddingTop, &percent(10.)), 20.);
+        assert_eq!(bases.resolve(&Property::MinHeight, &percent(10.)), 0.);
+        assert_eq!(
+            bases.resolve_auto(
+                &Property::Height,
+                &LengthPercentageAuto::LengthPercentage(percent(50.))
+            ),
+            None
+        );
+        assert_eq!(
+            bases.resolve_definite(
+                &Property::MaxHeight,
+                &LengthPercentage::Length(Length::new_px(5.))
+            ),
+            Some(5.)
+        );
+
+        let bases = PercentageBases::new(200., Some(50.));
+        assert_eq!(
+            bases.resolve_auto(
+                &Property::Height,
+                &LengthPercentageAuto::LengthPercentage(percent(50.))
+            ),
+            Some(25.)
+        );
+    }
+}
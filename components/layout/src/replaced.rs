@@ -0,0 +1,124 @@
+/// This module gives replaced elements their size when their width or
+/// height is auto. The size comes from the `width` & `height` attributes
+/// of an image or of an `<svg>` element, then from the image once it's
+/// loaded or from the bitmap of a canvas, keeping its aspect ratio when
+/// only one dimension is known.
+/// Without any of them, the element is as large as the default object
+/// size.
+/// https://www.w3.org/TR/CSS2/visudet.html#inline-replaced-width
+use super::layout_box::LayoutBox;
+use super::percentage::PercentageBases;
+use dom::elements::ElementData;
+use dom::images::Image;
+use style::value_processing::Property;
+
+/// https://www.w3.org/TR/css-images-3/#default-object-size
+pub const DEFAULT_WIDTH: f32 = 300.;
+pub const DEFAULT_HEIGHT: f32 = 150.;
+
+/// The size of a replaced element as far as it's known before layout
+struct IntrinsicSize {
+    /// The size set by the attributes of the element, which behave like
+    /// the `width` & `height` properties
+    width: Option<f32>,
+    height: Option<f32>,
+    /// The natural size of the content, e.g. of the loaded image
+    natural_width: Option<f32>,
+    natural_height: Option<f32>,
+    aspect_ratio: Option<f32>,
+}
+
+fn intrinsic_size(layout_box: &LayoutBox) -> Option<IntrinsicSize> {
+    let render_node = layout_box.render_node.as_ref()?;
+    let node_ref = render_node.borrow().node.clone();
+    let node = node_ref.borrow();
+
+    match node.as_element_opt()?.data() {
+        ElementData::Image(element) => {
+            let image = element.src().and_then(|src| {
+                let document = node.owner_document()?;
+                let document = document.borrow();
+                document.as_document().image(src.raw())
+            });
+            let width = element.width().map(|width| width as f32);
+            let height = element.height().map(|height| height as f32);
+            let attributes_ratio = match (width, height) {
+                (Some(width), Some(height)) if height > 0. => Some(width / height),
+                _ => None,
+            };
+            Some(IntrinsicSize {
+                width,
+                height,
+                natural_width: image.as_ref().and_then(|image| image.intrinsic_width()),
+                natural_height: image.as_ref().and_then(|image| image.intrinsic_height()),
+                aspect_ratio: image
+                    .as_ref()
+                    .and_then(|image| image.aspect_ratio())
+                    .or(attributes_ratio),
+            })
+        }
+        // the bitmap of a canvas is its natural size
+        ElementData::Canvas(canvas) => {
+            let (width, height) = (canvas.width() as f32, canvas.height() as f32);
+            Some(IntrinsicSize {
+                width: None,
+                height: None,
+                natural_width: Some(width),
+                natural_height: Some(height),
+                aspect_ratio: if height > 0. {
+                    Some(width / height)
+                } else {
+                    None
+                },
+            })
+        }
+        // the size of an inline SVG image is set by the attributes of
+        // its `<svg>` element, it has no natural size
+        ElementData::Svg(_) => {
+            let image = Image::from_svg_element(&node_ref).ok()?;
+            Some(IntrinsicSize {
+                width: image.intrinsic_width(),
+                height: image.intrinsic_height(),
+                natural_width: None,
+                natural_height: None,
+                aspect_ratio: image.aspect_ratio(),
+            })
+        }
+        _ => None,
+    }
+}
+
+/// The used width of a replaced element with an auto width
+pub fn intrinsic_width(layout_box: &LayoutBox, bases: &PercentageBases) -> Option<f32> {
+    let size = intrinsic_size(layout_box)?;
+    if let Some(width) = size.width {
+        return Some(width);
+    }
+    let height = used_height(layout_box, bases).or(size.height);
+    Some(match (height, size.aspect_ratio) {
+        (Some(height), Some(ratio)) => height * ratio,
+        _ => size.natural_width.unwrap_or(DEFAULT_WIDTH),
+    })
+}
+
+/// The used height of a replaced element with an auto height, once its
+/// width is laid out
+pub fn intrinsic_height(layout_box: &LayoutBox) -> Option<f32> {
+    let size = intrinsic_size(layout_box)?;
+    if let Some(height) = size.height {
+        return Some(height);
+    }
+    let width = layout_box.dimensions.content.width;
+    Some(match size.aspect_ratio {
+        Some(ratio) if ratio > 0. => width / ratio,
+        _ => size.natural_height.unwrap_or(DEFAULT_HEIGHT),
+    })
+}
+
+/// The height set by the styles, which decides the width of an image
+/// keeping its aspect ratio
+fn used_height(layout_box: &LayoutBox, bases: &PercentageBases) -> Option<f32> {
+    let render_node = layout_box.render_node.as_ref()?;
+    let height = render_node.borrow().style().height();
+    bases.resolve_auto(&Property::Height, &height)
+}
@@ -0,0 +1,92 @@
+/// This module resolves the sizes of the content box of a box set by its
+/// `width` & `height`, clamped by its `min-*` & `max-*` sizes. With
+/// `box-sizing: border-box`, the sizes set the border box instead, so the
+/// padding & the border are taken out of them.
+/// https://www.w3.org/TR/CSS2/visudet.html#min-max-widths
+use super::percentage::PercentageBases;
+use style::computed_style::ComputedStyle;
+use style::value_processing::Property;
+use style::values::prelude::BoxSizing;
+
+/// The width of the content box set by the `width` of a box, or `None`
+/// when the width is `auto`
+pub fn specified_width(style: &ComputedStyle, bases: &PercentageBases) -> Option<f32> {
+    let width = bases.resolve_auto(&Property::Width, &style.width())?;
+    Some(content_size(width, horizontal_edges(style, bases)))
+}
+
+/// The height of the content box set by the `height` of a box, or `None`
+/// when the height is `auto` or a percentage of an unknown height
+pub fn specified_height(style: &ComputedStyle, bases: &PercentageBases) -> Option<f32> {
+    let height = bases.resolve_auto(&Property::Height, &style.height())?;
+    Some(content_size(height, vertical_edges(style, bases)))
+}
+
+/// The largest & the smallest widths of the content box, `min-width`
+/// winning over `max-width` when they conflict
+pub fn width_constraints(style: &ComputedStyle, bases: &PercentageBases) -> (Option<f32>, f32) {
+    let edges = horizontal_edges(style, bases);
+    let max = style
+        .max_width()
+        .and_then(|max| bases.resolve_definite(&Property::MaxWidth, &max))
+        .map(|max| content_size(max, edges));
+    let min = bases
+        .resolve_auto(&Property::MinWidth, &style.min_width())
+        .map_or(0., |min| content_size(min, edges));
+    (max, min)
+}
+
+pub fn clamp_width(style: &ComputedStyle, width: f32, bases: &PercentageBases) -> f32 {
+    let (max, min) = width_constraints(style, bases);
+    clamp(width, max, min)
+}
+
+pub fn clamp_height(style: &ComputedStyle, height: f32, bases: &PercentageBases) -> f32 {
+    let edges = vertical_edges(style, bases);
+    let max = style
+        .max_height()
+        .and_then(|max| bases.resolve_definite(&Property::MaxHeight, &max))
+        .map(|max| content_size(max, edges));
+    let min = bases
+        .resolve_auto(&Property::MinHeight, &style.min_height())
+        .map_or(0., |min| content_size(min, edges));
+    clamp(height, max, min)
+}
+
+fn clamp(size: f32, max: Option<f32>, min: f32) -> f32 {
+    let size = match max {
+        Some(max) => size.min(max),
+        None => size,
+    };
+    size.max(min)
+}
+
+fn content_size(size: f32, edges: f32) -> f32 {
+    (size - edges).max(0.)
+}
+
+/// The padding & the border included in the widths of a box
+fn horizontal_edges(style: &ComputedStyle, bases: &PercentageBases) -> f32 {
+    match style.box_sizing() {
+        BoxSizing::ContentBox => 0.,
+        BoxSizing::BorderBox => {
+            bases.resolve(&Property::PaddingLeft, &style.padding_left())
+                + bases.resolve(&Property::PaddingRight, &style.padding_right())
+                + style.border_left_width()
+                + style.border_right_width()
+        }
+    }
+}
+
+/// The padding & the border included in the heights of a box
+fn vertical_edges(style: &ComputedStyle, bases: &PercentageBases) -> f32 {
+    match style.box_sizing() {
+        BoxSizing::ContentBox => 0.,
+        BoxSizing::BorderBox => {
+            bases.resolve(&Property::PaddingTop, &style.padding_top())
+                + bases.resolve(&Property::PaddingBottom, &style.padding_bottom())
+                + style.border_top_width()
+                + style.border_bottom_width()
+        }
+    }
+}
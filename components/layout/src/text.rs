@@ -0,0 +1,518 @@
+/// This module contains the processing of the text of
+/// text boxes before it's placed in line boxes, and the
+/// font metrics used to measure the text.
+use super::box_model::Rect;
+use style::render_tree::RenderNodeRef;
+use style::values::hyphens::Hyphens;
+use style::values::overflow_wrap::OverflowWrap;
+use style::values::white_space::WhiteSpace;
+use unicode_segmentation::UnicodeSegmentation;
+use unicode_width::UnicodeWidthChar;
+
+/// The font size of `FontMetrics::default()`, measuring the text of the
+/// boxes without a render node to take their font size from
+pub const DEFAULT_FONT_SIZE: f32 = 16.;
+
+const TAB_SIZE: usize = 8;
+
+/// Metrics of the font used to lay out text. Without font support,
+/// every glyph is assumed to be half an em wide (a full em for wide
+/// characters, e.g. CJK) and the vertical metrics are the ones of a
+/// typical sans-serif font.
+// TODO: match the faces of the `font-family` list when we support fonts,
+// falling back per character to the next family with a glyph for it,
+// then to the fonts of the system & of the `fonts_dir` of the config,
+// & split the fragments by face
+#[derive(Debug, Clone, PartialEq)]
+pub struct FontMetrics {
+    pub font_size: f32,
+    /// The vertical metrics of the font, in em
+    ascent: f32,
+    descent: f32,
+    line_gap: f32,
+    x_height: f32,
+}
+
+impl Default for FontMetrics {
+    fn default() -> Self {
+        Self::new(DEFAULT_FONT_SIZE)
+    }
+}
+
+impl FontMetrics {
+    pub fn new(font_size: f32) -> Self {
+        Self {
+            font_size,
+            ascent: 0.8,
+            descent: 0.2,
+            line_gap: 0.2,
+            x_height: 0.5,
+        }
+    }
+
+    /// The distance from the baseline to the top of the glyphs
+    pub fn ascent(&self) -> f32 {
+        self.font_size * self.ascent
+    }
+
+    /// The distance from the baseline to the bottom of the glyphs
+    pub fn descent(&self) -> f32 {
+        self.font_size * self.descent
+    }
+
+    pub fn x_height(&self) -> f32 {
+        self.font_size * self.x_height
+    }
+
+    /// The line height used for `line-height: normal`
+    pub fn normal_line_height(&self) -> f32 {
+        self.font_size * (self.ascent + self.descent + self.line_gap)
+    }
+
+    /// The distance from the top of a line to the baseline, the
+    /// leading is split evenly above & below the glyphs
+    /// https://www.w3.org/TR/CSS2/visudet.html#leading
+    pub fn baseline(&self, line_height: f32) -> f32 {
+        (line_height - self.ascent() - self.descent()) / 2. + self.ascent()
+    }
+
+    /// How much the baseline is lowered for subscripts
+    pub fn subscript_offset(&self) -> f32 {
+        self.font_size / 5.
+    }
+
+    /// How much the baseline is raised for superscripts
+    pub fn superscript_offset(&self) -> f32 {
+        self.font_size / 3.
+    }
+
+    /// The distance from the baseline to the underline
+    pub fn underline_offset(&self) -> f32 {
+        self.font_size * 0.1
+    }
+
+    /// The distance from the baseline to the line-through
+    pub fn strikeout_offset(&self) -> f32 {
+        self.font_size * 0.25
+    }
+
+    pub fn decoration_thickness(&self) -> f32 {
+        (self.font_size / 16.).max(1.)
+    }
+
+    pub fn char_advance(&self, c: char) -> f32 {
+        c.width().unwrap_or(0) as f32 * self.font_size / 2.
+    }
+
+    pub fn measure(&self, text: &str) -> f32 {
+        if text.is_ascii() {
+            return text.chars().map(|c| self.char_advance(c)).sum();
+        }
+        text.graphemes(true)
+            .map(|cluster| self.cluster_advance(cluster))
+            .sum()
+    }
+
+    /// The advance of a grapheme cluster, an emoji being a single glyph
+    /// a full em wide whatever the number of its chars. Only the advance
+    /// is known, the color glyphs of the emoji aren't painted.
+    // TODO: rasterize the color glyphs (CBDT, sbix, COLR) when we support fonts
+    pub fn cluster_advance(&self, cluster: &str) -> f32 {
+        if is_emoji_cluster(cluster) {
+            return self.font_size;
+        }
+        cluster.chars().map(|c| self.char_advance(c)).sum()
+    }
+}
+
+/// A piece of the text of a text box placed on a line
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextFragment {
+    pub text: String,
+    pub rect: Rect,
+    /// The space added to each space of the text when the line is justified
+    pub word_spacing: f32,
+}
+
+impl TextFragment {
+    pub fn new(text: String, metrics: &FontMetrics, line_height: f32) -> Self {
+        Self {
+            rect: Rect {
+                x: 0.,
+                y: 0.,
+                width: metrics.measure(&text),
+                height: line_height,
+            },
+            text,
+            word_spacing: 0.,
+        }
+    }
+
+    pub fn space_count(&self) -> usize {
+        self.text.chars().filter(|c| *c == ' ').count()
+    }
+}
+
+/// The metrics of the font of the text of a node
+pub fn font_metrics(node: &RenderNodeRef) -> FontMetrics {
+    FontMetrics::new(node.borrow().style().font_size().to_px())
+}
+
+/// The language of the text of a node, from the `lang` of its element or
+/// of an ancestor, e.g. to break its words or to pick its fonts
+pub fn language(node: &RenderNodeRef) -> Option<String> {
+    dom::lang::language(&node.borrow().node)
+}
+
+pub fn white_space(node: &RenderNodeRef) -> WhiteSpace {
+    node.borrow().style().white_space()
+}
+
+pub fn overflow_wrap(node: &RenderNodeRef) -> OverflowWrap {
+    node.borrow().style().overflow_wrap()
+}
+
+pub fn hyphens(node: &RenderNodeRef) -> Hyphens {
+    node.borrow().style().hyphens()
+}
+
+/// The used line height of the lines & inline boxes of a node
+pub fn line_height(node: &RenderNodeRef, metrics: &FontMetrics) -> f32 {
+    node.borrow()
+        .style()
+        .line_height()
+        .to_px(metrics.font_size)
+        .unwrap_or_else(|| metrics.normal_line_height())
+}
+
+/// The text of a text node
+pub fn text_content(node: &RenderNodeRef) -> Option<String> {
+    let node = node.borrow();
+    let dom_node = node.node.borrow();
+    dom_node.as_text_opt().map(|text| text.get_data())
+}
+
+/// Whether the text of a text box doesn't render anything, i.e. it only
+/// contains white spaces that are collapsed away
+pub fn is_collapsible(text: &str, white_space: &WhiteSpace) -> bool {
+    if white_space.preserves_spaces() {
+        return text.is_empty();
+    }
+    if white_space.preserves_newlines() {
+        return text.chars().all(|c| c == ' ' || c == '\t');
+    }
+    text.chars().all(|c| c.is_ascii_whitespace())
+}
+
+/// Collapse the white spaces of a text, the first phase of the white
+/// space processing: the spaces & tabs around newlines are removed, the
+/// newlines are rendered as spaces unless they're forced line breaks,
+/// and sequences of spaces & tabs become a single space. The spaces
+/// collapsing across texts & at the ends of lines are removed during
+/// the inline layout.
+/// https://drafts.csswg.org/css-text-3/#white-space-phase-1
+pub fn collapse_white_space(text: &str, white_space: &WhiteSpace) -> String {
+    if white_space.preserves_spaces() {
+        return text.to_string();
+    }
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    let mut result = String::with_capacity(text.len());
+    let mut after_newline = false;
+    for c in text.chars() {
+        match c {
+            ' ' | '\t' if after_newline => {}
+            '\n' => {
+                let trimmed_len = result.trim_end_matches(|c| c == ' ').len();
+                result.truncate(trimmed_len);
+                if white_space.preserves_newlines() {
+                    result.push('\n');
+                } else if !result.ends_with(' ') {
+                    result.push(' ');
+                }
+                after_newline = true;
+            }
+            ' ' | '\t' => {
+                if !result.ends_with(' ') {
+                    result.push(' ');
+                }
+            }
+            c => {
+                result.push(c);
+                after_newline = false;
+            }
+        }
+    }
+    result
+}
+
+/// Split a text into the lines separated by forced line breaks.
+/// Newlines are only forced line breaks if the white space
+/// is preserved, otherwise they are rendered as spaces.
+pub fn split_forced_breaks(text: &str, white_space: &WhiteSpace) -> Vec<String> {
+    let text = text.replace("\r\n", "\n").replace('\r', "\n");
+
+    if !white_space.preserves_newlines() {
+        return vec![text.replace('\n', " ")];
+    }
+
+    text.split('\n')
+        .map(|line| {
+            if white_space.preserves_spaces() {
+                expand_tabs(line)
+            } else {
+                line.to_string()
+            }
+        })
+        .collect()
+}
+
+/// The positions (in bytes) in a text where a line can be soft wrapped.
+/// This is an approximation of the line breaking algorithm of UAX #14:
+/// lines can be wrapped after spaces, after hyphens inside words and
+/// around ideographic characters, except before closing punctuation
+/// and after opening punctuation.
+/// https://www.unicode.org/reports/tr14/
+pub fn break_opportunities(text: &str) -> Vec<usize> {
+    let chars = text.char_indices().collect::<Vec<(usize, char)>>();
+    let mut result = Vec::new();
+
+    for index in 1..chars.len() {
+        let (position, after) = chars[index];
+        let before = chars[index - 1].1;
+        let previous = if index > 1 {
+            Some(chars[index - 2].1)
+        } else {
+            None
+        };
+
+        if can_break_between(previous, before, after) {
+            result.push(position);
+        }
+    }
+    result
+}
+
+fn can_break_between(previous: Option<char>, before: char, after: char) -> bool {
+    if is_space(after) {
+        return false;
+    }
+    // the chars of an emoji sequence stay together
+    if before == ZERO_WIDTH_JOINER || is_emoji_component(after) {
+        return false;
+    }
+    if is_space(before) || before == ZERO_WIDTH_SPACE {
+        return true;
+    }
+    if is_closing_punctuation(after) || is_opening_punctuation(before) {
+        return false;
+    }
+    if is_ideographic(before) || is_ideographic(after) {
+        return true;
+    }
+    is_hyphen(before)
+        && previous.map(char::is_alphanumeric).unwrap_or(false)
+        && after.is_alphabetic()
+}
+
+const ZERO_WIDTH_SPACE: char = '\u{200B}';
+const ZERO_WIDTH_JOINER: char = '\u{200D}';
+/// The variation selector showing the previous char as an emoji
+const EMOJI_PRESENTATION_SELECTOR: char = '\u{FE0F}';
+const COMBINING_KEYCAP: char = '\u{20E3}';
+
+/// Whether a grapheme cluster is shown as a color emoji: a pictograph
+/// with an emoji presentation, a sequence of pictographs joined by zero
+/// width joiners, a keycap or a flag made of two regional indicators.
+/// https://www.unicode.org/reports/tr51/#Definitions
+pub fn is_emoji_cluster(cluster: &str) -> bool {
+    let mut chars = cluster.chars();
+    let first = match chars.next() {
+        Some(first) => first,
+        None => return false,
+    };
+    if is_regional_indicator(first) {
+        return chars.next().map(is_regional_indicator).unwrap_or(false);
+    }
+    if cluster.contains(COMBINING_KEYCAP) {
+        return true;
+    }
+    is_pictograph(first)
+        && (first.width() == Some(2)
+            || cluster.contains(EMOJI_PRESENTATION_SELECTOR)
+            || cluster.contains(ZERO_WIDTH_JOINER))
+}
+
+/// The pictographic chars that can be shown as emoji, approximated by
+/// the blocks of symbols & pictographs
+fn is_pictograph(c: char) -> bool {
+    matches!(
+        c,
+        '\u{A9}' | '\u{AE}' | '\u{203C}' | '\u{2049}' | '\u{2122}' | '\u{2139}'
+            | '\u{2190}'..='\u{2BFF}'
+            | '\u{1F000}'..='\u{1FAFF}'
+    )
+}
+
+fn is_regional_indicator(c: char) -> bool {
+    ('\u{1F1E6}'..='\u{1F1FF}').contains(&c)
+}
+
+/// The chars modifying the emoji before them, e.g. a skin tone
+fn is_emoji_component(c: char) -> bool {
+    c == ZERO_WIDTH_JOINER
+        || c == EMOJI_PRESENTATION_SELECTOR
+        || c == COMBINING_KEYCAP
+        || ('\u{1F3FB}'..='\u{1F3FF}').contains(&c)
+}
+
+fn is_space(c: char) -> bool {
+    c == ' ' || c == '\t'
+}
+
+fn is_hyphen(c: char) -> bool {
+    c == '-' || c == '\u{2010}' || c == '\u{2013}'
+}
+
+/// Characters that occupy a full em, e.g. CJK characters
+fn is_ideographic(c: char) -> bool {
+    c.width() == Some(2)
+}
+
+/// Characters that can't start a line
+fn is_closing_punctuation(c: char) -> bool {
+    match c {
+        ')' | ']' | '}' | ',' | '.' | ':' | ';' | '!' | '?' => true,
+        '、' | '。' | '，' | '．' | '：' | '；' | '！' | '？' => true,
+        '）' | '」' | '』' | '】' | '〕' | '〉' | '》' | '〗' | '〙' | '〛' => true,
+        'ー' | '々' | '〻' => true,
+        _ => false,
+    }
+}
+
+/// Characters that can't end a line
+fn is_opening_punctuation(c: char) -> bool {
+    match c {
+        '(' | '[' | '{' => true,
+        '（' | '「' | '『' | '【' | '〔' | '〈' | '《' | '〖' | '〘' | '〚' => true,
+        _ => false,
+    }
+}
+
+/// Split a text into the words that can't be wrapped, each word
+/// keeps its trailing spaces
+pub fn split_words(text: &str) -> Vec<&str> {
+    let mut words = Vec::new();
+    let mut start = 0;
+    for position in break_opportunities(text) {
+        words.push(&text[start..position]);
+        start = position;
+    }
+    if start < text.len() {
+        words.push(&text[start..]);
+    }
+    words
+}
+
+/// Split a word that doesn't fit on a line at grapheme boundaries
+pub fn graphemes(word: &str) -> Vec<&str> {
+    word.graphemes(true).collect()
+}
+
+/// Replace the tabs of a line with spaces up to the next tab stop
+fn expand_tabs(line: &str) -> String {
+    let mut result = String::new();
+    let mut column = 0;
+    for c in line.chars() {
+        if c == '\t' {
+            let spaces = TAB_SIZE - column % TAB_SIZE;
+            result.push_str(&" ".repeat(spaces));
+            column += spaces;
+        } else {
+            result.push(c);
+            column += c.width().unwrap_or(0);
+        }
+    }
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn split_text_at_forced_breaks() {
+        let text = "let a = 1;\r\n\tlet b = 2;\n";
+
+        assert_eq!(
+            split_forced_breaks(text, &WhiteSpace::Pre),
+            vec!["let a = 1;", "        let b = 2;", ""]
+        );
+        assert_eq!(
+            split_forced_breaks(text, &WhiteSpace::PreLine),
+            vec!["let a = 1;", "\tlet b = 2;", ""]
+        );
+        assert_eq!(
+            split_forced_breaks(text, &WhiteSpace::Normal),
+            vec!["let a = 1; \tlet b = 2; "]
+        );
+
+        assert!(is_collapsible(" \n\t", &WhiteSpace::Normal));
+        assert!(!is_collapsible(" \n\t", &WhiteSpace::PreLine));
+        assert!(!is_collapsible(" ", &WhiteSpace::Pre));
+    }
+
+    #[test]
+    fn collapse_white_spaces() {
+        let text = "  Hello \t world \r\n\n  again\n";
+
+        assert_eq!(
+            collapse_white_space(text, &WhiteSpace::Normal),
+            " Hello world again "
+        );
+        assert_eq!(
+            collapse_white_space(text, &WhiteSpace::Nowrap),
+            " Hello world again "
+        );
+        assert_eq!(
+            collapse_white_space(text, &WhiteSpace::PreLine),
+            " Hello world\n\nagain\n"
+        );
+        assert_eq!(collapse_white_space(text, &WhiteSpace::PreWrap), text);
+    }
+
+    #[test]
+    fn find_break_opportunities() {
+        assert_eq!(split_words("Hello  world"), vec!["Hello  ", "world"]);
+        assert_eq!(split_words("well-known -1"), vec!["well-", "known ", "-1"]);
+        assert_eq!(split_words("日本語text"), vec!["日", "本", "語", "text"]);
+        assert_eq!(
+            split_words("東京。「大阪」"),
+            vec!["東", "京。", "「大", "阪」"]
+        );
+        assert_eq!(split_words("a\u{200B}b"), vec!["a\u{200B}", "b"]);
+        assert_eq!(break_opportunities(" "), Vec::<usize>::new());
+        assert_eq!(
+            split_words("\u{1F44D}\u{1F3FB}\u{1F468}\u{200D}\u{1F467}"),
+            vec!["\u{1F44D}\u{1F3FB}", "\u{1F468}\u{200D}\u{1F467}"]
+        );
+    }
+
+    #[test]
+    fn measure_emoji_clusters() {
+        let metrics = FontMetrics::new(16.);
+        // a family joined by zero width joiners
+        assert_eq!(
+            metrics.measure("\u{1F468}\u{200D}\u{1F469}\u{200D}\u{1F467}"),
+            16.
+        );
+        // a thumbs up with a skin tone, a red heart & a flag
+        assert_eq!(metrics.measure("\u{1F44D}\u{1F3FB}"), 16.);
+        assert_eq!(metrics.measure("\u{2764}\u{FE0F}"), 16.);
+        assert_eq!(metrics.measure("\u{1F1EB}\u{1F1F7}"), 16.);
+        assert_eq!(metrics.measure("1\u{FE0F}\u{20E3} ok"), 16. + 3. * 8.);
+        // without the emoji presentation, the heart is a narrow symbol
+        assert_eq!(metrics.measure("\u{2764}"), 8.);
+        assert!(!is_emoji_cluster("e\u{301}"));
+    }
+}
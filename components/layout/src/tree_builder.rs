@@ -3,69 +3,221 @@
 /// this module transforms render tree to layout tree
 /// to prepare for layouting process.
 use super::layout_box::{BoxType, LayoutBox};
+use super::text;
 use std::cell::RefCell;
+use std::collections::HashMap;
 use std::rc::Rc;
-use style::render_tree::RenderNodeRef;
-use style::value_processing::{Property, Value};
-use style::values::display::{Display, InnerDisplayType, OuterDisplayType};
+use style::render_tree::{RenderNode, RenderNodeRef};
+use style::values::display::{Display, InnerDisplayType, InternalDisplayType, OuterDisplayType};
 
 pub struct TreeBuilder {
     parent_stack: Rc<RefCell<Vec<*mut LayoutBox>>>,
+    split_inlines: SplitInlines,
     root: RenderNodeRef,
+    /// Boxes of the previous layout tree whose render nodes didn't change
+    reusable_boxes: HashMap<*const RenderNode, LayoutBox>,
 }
 
 impl TreeBuilder {
     pub fn new(root: RenderNodeRef) -> Self {
         Self {
             parent_stack: Rc::new(RefCell::new(Vec::new())),
+            split_inlines: SplitInlines::default(),
             root,
+            reusable_boxes: HashMap::new(),
         }
     }
 
+    /// Reuse the boxes, and their layout, of a previous layout tree
+    /// for the render nodes that didn't change since it was built
+    pub fn reuse(mut self, previous: LayoutBox) -> Self {
+        collect_reusable_boxes(previous, &mut self.reusable_boxes);
+        self
+    }
+
     /// Build the layout tree for the provided root render node
     pub fn build(mut self) -> Option<LayoutBox> {
         let root = self.root.clone();
+        if let Some(root_box) = self.take_reusable_box(&root) {
+            return Some(root_box);
+        }
         let mut root_box = match build_box_by_display(&root) {
             Some(b) => b,
             None => return None,
         };
+        root.borrow_mut().needs_layout = false;
+
+        // the root box is block-level, so every block box has a block
+        // container to be placed in
+        // https://drafts.csswg.org/css-display-3/#transformations
+        if root_box.is_inline() && !root_box.is_inline_block() {
+            root_box.box_type = BoxType::Block;
+            root_box.set_children_inline(all_inline_children(&root));
+        }
 
         self.parent_stack.borrow_mut().push(&mut root_box);
         for child in &root.borrow().children {
             self.build_layout_tree(child.clone());
         }
-        self.parent_stack.borrow_mut().pop();
+        self.pop_parent();
+
+        for node in self.split_inlines.nodes {
+            node.borrow_mut().needs_layout = true;
+        }
 
         return Some(root_box);
     }
 
     /// Recursively building the layout tree for a node
     fn build_layout_tree(&mut self, node: RenderNodeRef) -> Option<&LayoutBox> {
-        let layout_box = match build_box_by_display(&node) {
+        if is_contents(&node) {
+            // the children of the node are laid out as children of its parent
+            node.borrow_mut().needs_layout = false;
+            for child in &node.borrow().children {
+                self.build_layout_tree(child.clone());
+            }
+            return None;
+        }
+
+        let reused_box = self.take_reusable_box(&node);
+        let is_reused = reused_box.is_some();
+        let layout_box = match reused_box.or_else(|| build_box_by_display(&node)) {
             Some(b) => b,
             None => return None,
         };
+        node.borrow_mut().needs_layout = false;
 
         let parent = unsafe {
             if layout_box.is_inline() {
-                get_parent_for_inline(self.parent_stack.clone())
+                get_parent_for_inline(self.parent_stack.clone(), &mut self.split_inlines)
+            } else if layout_box.box_type == BoxType::TableCell {
+                get_parent_for_table_cell(self.parent_stack.clone(), &mut self.split_inlines)
             } else {
-                get_parent_for_block(self.parent_stack.clone())
+                get_parent_for_block(self.parent_stack.clone(), &mut self.split_inlines)
+            }
+        };
+        let parent = match parent {
+            Some(parent) => parent,
+            None => {
+                log::warn!("No parent for a {:?} box, skipping it", layout_box.box_type);
+                return None;
             }
         };
 
         parent.add_child(layout_box);
 
+        if is_reused {
+            return parent.children.last();
+        }
+
         let box_ref = parent.children.last_mut().unwrap();
 
         self.parent_stack.borrow_mut().push(box_ref);
         for child in &node.borrow().children {
             self.build_layout_tree(child.clone());
         }
-        self.parent_stack.borrow_mut().pop();
+        self.pop_parent();
 
         parent.children.last()
     }
+
+    /// Pop the box whose children were built off the parent stack
+    fn pop_parent(&mut self) {
+        let len = {
+            let mut parent_stack = self.parent_stack.borrow_mut();
+            parent_stack.pop();
+            parent_stack.len()
+        };
+        // once the split inline boxes are closed, their parents are the
+        // ones continuing after the block boxes
+        let splits = &mut self.split_inlines.splits;
+        while let Some((index, split_len)) = splits.last_mut() {
+            if *split_len <= len {
+                break;
+            }
+            if len > *index + 1 {
+                *split_len = len;
+                break;
+            }
+            splits.pop();
+        }
+    }
+
+    fn take_reusable_box(&mut self, node: &RenderNodeRef) -> Option<LayoutBox> {
+        if node.borrow().needs_layout {
+            return None;
+        }
+        let mut layout_box = self
+            .reusable_boxes
+            .remove(&(node.as_ptr() as *const RenderNode))?;
+        rebuild_layout_roots(&mut layout_box);
+        Some(layout_box)
+    }
+}
+
+/// Rebuild the boxes of the layout roots inside a reused box whose
+/// contents changed. The ancestors of a layout root aren't marked when
+/// its contents change, so the rebuilt root keeps its previous layout
+/// until its contents are laid out again.
+fn rebuild_layout_roots(layout_box: &mut LayoutBox) {
+    for child in &mut layout_box.children {
+        let is_dirty_root = match &child.render_node {
+            Some(node) => {
+                let node = node.borrow();
+                node.needs_layout && node.is_layout_root()
+            }
+            None => false,
+        };
+        if !is_dirty_root {
+            rebuild_layout_roots(child);
+            continue;
+        }
+
+        let node = child.render_node.clone().unwrap();
+        let dimensions = child.dimensions.clone();
+        let containing_size = child.containing_size;
+        let definite_height = child.definite_height;
+        let marker = child.marker.take();
+
+        // the style of the root didn't change, so it still generates a box
+        let previous = std::mem::replace(child, LayoutBox::new_anonymous(BoxType::Block));
+        if let Some(rebuilt) = TreeBuilder::new(node).reuse(previous).build() {
+            *child = rebuilt;
+        }
+        child.dimensions = dimensions;
+        child.containing_size = containing_size;
+        child.definite_height = definite_height;
+        child.marker = marker;
+    }
+}
+
+/// Collect the topmost boxes of a layout tree whose render nodes didn't change
+fn collect_reusable_boxes(
+    layout_box: LayoutBox,
+    boxes: &mut HashMap<*const RenderNode, LayoutBox>,
+) {
+    if let Some(node) = &layout_box.render_node {
+        if !node.borrow().needs_layout {
+            boxes.insert(node.as_ptr() as *const RenderNode, layout_box);
+            return;
+        }
+    }
+    for child in layout_box.children {
+        collect_reusable_boxes(child, boxes);
+    }
+}
+
+/// The inline boxes split by the block boxes placed in them
+#[derive(Default)]
+struct SplitInlines {
+    /// The index in the parent stack of the block container the block
+    /// boxes were placed in & the length of the stack down to the split
+    /// inline boxes. The content after the block boxes continues in new
+    /// boxes.
+    splits: Vec<(usize, usize)>,
+    /// The render nodes of the split boxes, whose boxes can't be reused
+    /// on their own since their content isn't in them only
+    nodes: Vec<RenderNodeRef>,
 }
 
 /// Get a parent for an inline-level box
@@ -78,18 +230,26 @@ impl TreeBuilder {
 /// Otherwise, if the nearest parent established a block formatting context
 /// then create an anonymous block-level box to wrap the inline-box in before
 /// inserting into the parent.
+///
+/// If the nearest parent was split by a block-level box, the box is inserted
+/// into the continuation of the parent after the block-level box.
 unsafe fn get_parent_for_inline<'a>(
     parent_stack: Rc<RefCell<Vec<*mut LayoutBox>>>,
-) -> &'a mut LayoutBox {
-    let parent_stack = parent_stack.borrow();
-    let parent = parent_stack.last().expect("No parent in stack");
+    split_inlines: &mut SplitInlines,
+) -> Option<&'a mut LayoutBox> {
+    let mut parent_stack = parent_stack.borrow_mut();
 
-    let parent_mut = parent
-        .as_mut()
-        .expect("Can't get mutable reference to parent");
+    if let Some(&(index, len)) = split_inlines.splits.last() {
+        if len == parent_stack.len() {
+            split_inlines.splits.pop();
+            continue_inline_boxes(&mut parent_stack, index);
+        }
+    }
+
+    let parent_mut = parent_stack.last()?.as_mut()?;
 
     if parent_mut.children_are_inline() {
-        return parent_mut;
+        return Some(parent_mut);
     }
 
     if let Some(last) = parent_mut.children.last() {
@@ -104,7 +264,7 @@ unsafe fn get_parent_for_inline<'a>(
         parent_mut.add_child(anonymous);
     }
 
-    parent_mut.children.last_mut().unwrap()
+    parent_mut.children.last_mut()
 }
 
 /// Get a parent for an block-level box
@@ -119,29 +279,22 @@ unsafe fn get_parent_for_inline<'a>(
 /// inline-level boxes currently in the parent. After that, set the
 /// formatting context of parent to block and insert the box as a direct
 /// children of the parent.
+///
+/// The inline boxes between the box & its parent stay in the stack, split
+/// by the box: their content after the box continues in new inline boxes
+/// inserted after it.
+/// https://www.w3.org/TR/CSS2/visuren.html#anonymous-block-level
 unsafe fn get_parent_for_block<'a>(
     parent_stack: Rc<RefCell<Vec<*mut LayoutBox>>>,
-) -> &'a mut LayoutBox {
+    split_inlines: &mut SplitInlines,
+) -> Option<&'a mut LayoutBox> {
     let mut parent_stack = parent_stack.borrow_mut();
 
-    while let Some(parent_box) = parent_stack.last() {
-        let current_box = parent_box.as_ref().unwrap();
-        if !current_box.is_inline_block() && current_box.is_inline() {
-            parent_stack.pop();
-        } else {
-            break;
-        }
-    }
-
-    if parent_stack.last().is_none() {
-        panic!("Can't find block parent for block box");
-    }
-
-    let parent_mut = parent_stack
-        .last()
-        .unwrap()
-        .as_mut()
-        .expect("Can't get mutable reference to parent");
+    let index = parent_stack.iter().rposition(|parent| {
+        let parent = &**parent;
+        parent.is_inline_block() || !parent.is_inline()
+    })?;
+    let parent_mut = parent_stack[index].as_mut()?;
 
     if parent_mut.children_are_inline() {
         let children = parent_mut.children.drain(..).collect::<Vec<_>>();
@@ -150,48 +303,136 @@ unsafe fn get_parent_for_block<'a>(
         anonymous.set_children_inline(true);
         parent_mut.add_child(anonymous);
         parent_mut.set_children_inline(false);
+
+        // the inline boxes being built were moved into the anonymous box,
+        // each being the last child of the box before it in the stack
+        let mut last = parent_mut.children.last_mut()? as *mut LayoutBox;
+        for parent in parent_stack[index + 1..].iter_mut() {
+            last = (*last).children.last_mut()?;
+            *parent = last;
+        }
     }
 
-    return parent_mut;
+    let len = parent_stack.len();
+    if len > index + 1 {
+        for parent in &parent_stack[index + 1..] {
+            if let Some(node) = &(**parent).render_node {
+                split_inlines.nodes.push(node.clone());
+            }
+        }
+        if split_inlines.splits.last() != Some(&(index, len)) {
+            split_inlines.splits.push((index, len));
+        }
+    }
+
+    Some(parent_mut)
+}
+
+/// Continue the inline boxes split by block boxes after the last block
+/// box of their block container, in an anonymous block box
+unsafe fn continue_inline_boxes(parent_stack: &mut [*mut LayoutBox], index: usize) {
+    let container = &mut *parent_stack[index];
+    let mut anonymous = LayoutBox::new_anonymous(BoxType::Block);
+    anonymous.set_children_inline(true);
+    container.add_child(anonymous);
+
+    let mut last = container.children.last_mut().unwrap() as *mut LayoutBox;
+    for parent in parent_stack[index + 1..].iter_mut() {
+        let mut continuation = match &(**parent).render_node {
+            Some(node) => LayoutBox::new(node.clone(), BoxType::Inline),
+            None => LayoutBox::new_anonymous(BoxType::Inline),
+        };
+        continuation.set_children_inline(true);
+        (*last).add_child(continuation);
+        last = (*last).children.last_mut().unwrap();
+        *parent = last;
+    }
+}
+
+/// Get a parent for a table cell
+///
+/// A table cell can only be inserted into a table row. If the nearest
+/// non-inline parent isn't a row, the cell is inserted into an anonymous
+/// row wrapping the consecutive cells of the parent.
+unsafe fn get_parent_for_table_cell<'a>(
+    parent_stack: Rc<RefCell<Vec<*mut LayoutBox>>>,
+    split_inlines: &mut SplitInlines,
+) -> Option<&'a mut LayoutBox> {
+    let parent_mut = get_parent_for_block(parent_stack, split_inlines)?;
+
+    if parent_mut.box_type == BoxType::TableRow {
+        return Some(parent_mut);
+    }
+
+    let needs_anonymous_row = match parent_mut.children.last() {
+        Some(last) => !last.is_anonymous() || last.box_type != BoxType::TableRow,
+        None => true,
+    };
+
+    if needs_anonymous_row {
+        parent_mut.add_child(LayoutBox::new_anonymous(BoxType::TableRow));
+    }
+
+    parent_mut.children.last_mut()
+}
+
+fn is_contents(node: &RenderNodeRef) -> bool {
+    let node = node.borrow();
+    !node.node.is_text() && node.style().display().is_contents()
 }
 
 fn all_inline_children(node: &RenderNodeRef) -> bool {
-    for child in &node.borrow().children {
-        match child.borrow().get_style(&Property::Display).inner() {
-            Value::Display(Display::Full(OuterDisplayType::Block, _)) => return false,
-            _ => {}
+    node.borrow().children.iter().all(|child| {
+        if is_contents(child) {
+            return all_inline_children(child);
         }
-    }
-    true
+        !child.borrow().style().display().is_block_level()
+    })
 }
 
 fn build_box_by_display(node: &RenderNodeRef) -> Option<LayoutBox> {
-    // TODO: support text
     if node.borrow().node.is_text() {
-        return None;
+        return build_text_box(node);
     }
 
-    let display = node.borrow().get_style(&Property::Display);
-
-    let box_type = match display.inner() {
-        Value::Display(d) => match d {
-            Display::Full(outer, inner) => match (outer, inner) {
-                (OuterDisplayType::Block, InnerDisplayType::Flow) => BoxType::Block,
-                (OuterDisplayType::Inline, InnerDisplayType::Flow)
-                | (OuterDisplayType::Inline, InnerDisplayType::FlowRoot) => BoxType::Inline,
-                _ => return None,
-            },
-            _ => {
-                log::warn!("Unsupport display type: {:#?}", d);
-                return None;
-            }
+    let display = node.borrow().style().display();
+
+    let box_type = match display {
+        Display::Full(outer, inner) | Display::ListItem(outer, inner) => match (outer, inner) {
+            // TODO: lay out run-in boxes in the block following them
+            (OuterDisplayType::Block, InnerDisplayType::Flow)
+            | (OuterDisplayType::Block, InnerDisplayType::FlowRoot)
+            | (OuterDisplayType::RunIn, InnerDisplayType::Flow)
+            | (OuterDisplayType::RunIn, InnerDisplayType::FlowRoot) => BoxType::Block,
+            (OuterDisplayType::Inline, InnerDisplayType::Flow)
+            | (OuterDisplayType::Inline, InnerDisplayType::FlowRoot) => BoxType::Inline,
+            // TODO: lay out inline tables as inline-level boxes
+            (_, InnerDisplayType::Table) => BoxType::Table,
+            _ => return None,
         },
-        _ => unreachable!(),
+        Display::Internal(internal) => match internal {
+            InternalDisplayType::TableRowGroup
+            | InternalDisplayType::TableHeaderGroup
+            | InternalDisplayType::TableFooterGroup => BoxType::TableRowGroup,
+            InternalDisplayType::TableRow => BoxType::TableRow,
+            InternalDisplayType::TableCell => BoxType::TableCell,
+            // TODO: support table captions & column widths
+            _ => return None,
+        },
+        _ => {
+            log::warn!("Unsupport display type: {:#?}", display);
+            return None;
+        }
     };
 
     let mut layout_box = LayoutBox::new(node.clone(), box_type.clone());
 
-    if all_inline_children(node) {
+    let contains_table_boxes = match box_type {
+        BoxType::Table | BoxType::TableRowGroup | BoxType::TableRow => true,
+        _ => false,
+    };
+
+    if all_inline_children(node) && !contains_table_boxes {
         layout_box.set_children_inline(true);
     }
 
@@ -204,10 +445,32 @@ fn build_box_by_display(node: &RenderNodeRef) -> Option<LayoutBox> {
     Some(layout_box)
 }
 
+/// Text nodes generate inline-level text boxes, unless their
+/// text only contains collapsible white spaces between block-level
+/// boxes. Between inline-level boxes, the white spaces collapse to
+/// a space during the inline layout.
+fn build_text_box(node: &RenderNodeRef) -> Option<LayoutBox> {
+    let text = text::text_content(node)?;
+    let in_inline_content = node
+        .borrow()
+        .parent_render_node
+        .as_ref()
+        .and_then(|parent| parent.upgrade())
+        .map_or(false, |parent| all_inline_children(&parent));
+    if text::is_collapsible(&text, &text::white_space(node)) && !in_inline_content {
+        return None;
+    }
+
+    let mut layout_box = LayoutBox::new(node.clone(), BoxType::Inline);
+    layout_box.set_children_inline(true);
+    Some(layout_box)
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use css::cssom::css_rule::CSSRule;
+    use dom::dom_ref::NodeRef;
     use style::build_render_tree;
     use style::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
     use test_utils::css::parse_stylesheet;
@@ -245,12 +508,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -306,12 +570,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -346,4 +611,243 @@ mod tests {
         assert!(layout_box.children[2].box_type == BoxType::Block);
         assert!(layout_box.children[2].is_anonymous());
     }
+
+    #[test]
+    fn test_display_contents_and_flow_root() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element(
+                    "section",
+                    document.clone(),
+                    vec![
+                        element("span", document.clone(), vec![]),
+                        element("span", document.clone(), vec![]),
+                    ],
+                ),
+                element("p", document.clone(), vec![]),
+            ],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        section {
+            display: contents;
+        }
+        span {
+            display: inline;
+        }
+        p {
+            display: flow-root;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom.clone(), &rules);
+
+        let layout_tree_builder = TreeBuilder::new(render_tree.root.unwrap());
+
+        let layout_box = layout_tree_builder.build().unwrap();
+
+        // The result box tree should look like this
+        // [Block] - Div
+        //   |- [Block Anonymous]
+        //        |- [Inline] - Span
+        //        |- [Inline] - Span
+        //   |- [Block] - P
+
+        assert_eq!(layout_box.children.len(), 2);
+
+        assert!(layout_box.children[0].is_anonymous());
+        assert_eq!(layout_box.children[0].children.len(), 2);
+        assert!(layout_box.children[0].children[0].box_type == BoxType::Inline);
+
+        assert!(layout_box.children[1].box_type == BoxType::Block);
+        assert!(!layout_box.children[1].is_anonymous());
+    }
+
+    #[test]
+    fn test_pseudo_element_boxes() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![text("item", document.clone())],
+        );
+
+        let css = r#"
+        div {
+            display: block;
+        }
+        div::before {
+            content: "\2022  ";
+        }
+        div::after {
+            content: "";
+            display: block;
+        }"#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom.clone(), &rules);
+
+        let layout_tree_builder = TreeBuilder::new(render_tree.root.unwrap());
+
+        let layout_box = layout_tree_builder.build().unwrap();
+
+        // The result box tree should look like this
+        // [Block] - Div
+        //   |- [Block Anonymous]
+        //        |- [Inline] - ::before
+        //             |- [Inline] - "• "
+        //        |- [Inline] - "item"
+        //   |- [Block] - ::after
+
+        assert_eq!(layout_box.children.len(), 2);
+
+        let line = &layout_box.children[0];
+        assert!(line.is_anonymous());
+        assert_eq!(line.children.len(), 2);
+        assert!(line.children[0].box_type == BoxType::Inline);
+        let content = line.children[0].children[0].render_node.clone().unwrap();
+        assert_eq!(
+            crate::text::text_content(&content),
+            Some("\u{2022} ".to_string())
+        );
+
+        assert!(layout_box.children[1].box_type == BoxType::Block);
+        assert!(!layout_box.children[1].is_anonymous());
+    }
+    fn build(dom: NodeRef, css: &str) -> LayoutBox {
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom, &rules);
+        TreeBuilder::new(render_tree.root.unwrap()).build().unwrap()
+    }
+
+    #[test]
+    fn test_block_in_inline() {
+        let document = document();
+        let dom = element(
+            "div",
+            document.clone(),
+            vec![
+                element(
+                    "span",
+                    document.clone(),
+                    vec![element(
+                        "div",
+                        document.clone(),
+                        vec![text("x", document.clone())],
+                    )],
+                ),
+                element("b", document.clone(), vec![text("y", document.clone())]),
+            ],
+        );
+
+        let layout_box = build(dom, "div { display: block; } span, b { display: inline; }");
+
+        // The result box tree should look like this
+        // [Block] - Div
+        //   |- [Block Anonymous]
+        //        |- [Inline] - Span
+        //   |- [Block] - Div
+        //        |- [Inline] - "x"
+        //   |- [Block Anonymous]
+        //        |- [Inline] - B
+        //             |- [Inline] - "y"
+
+        assert_eq!(layout_box.children.len(), 3);
+        assert!(layout_box.children[0].is_anonymous());
+        assert!(layout_box.children[0].children[0].is_inline());
+        assert!(layout_box.children[1].box_type == BoxType::Block);
+        assert!(!layout_box.children[1].is_anonymous());
+        assert_eq!(layout_box.children[1].children.len(), 1);
+        assert!(layout_box.children[2].is_anonymous());
+        assert_eq!(layout_box.children[2].children.len(), 1);
+    }
+
+    #[test]
+    fn test_split_inline_around_block() {
+        let document = document();
+        let span = element(
+            "span",
+            document.clone(),
+            vec![
+                text("a", document.clone()),
+                element("div", document.clone(), vec![text("x", document.clone())]),
+                text("b", document.clone()),
+            ],
+        );
+        let dom = element("span", document.clone(), vec![span]);
+
+        // the root box is block-level even when its element is inline
+        let layout_box = build(dom, "div { display: block; } span { display: inline; }");
+
+        // The result box tree should look like this
+        // [Block] - Span
+        //   |- [Block Anonymous]
+        //        |- [Inline] - Span
+        //             |- [Inline] - "a"
+        //   |- [Block] - Div
+        //        |- [Inline] - "x"
+        //   |- [Block Anonymous]
+        //        |- [Inline] - Span
+        //             |- [Inline] - "b"
+
+        assert!(layout_box.box_type == BoxType::Block);
+        assert_eq!(layout_box.children.len(), 3);
+
+        let before = &layout_box.children[0].children[0];
+        let after = &layout_box.children[2].children[0];
+        assert!(before.is_inline() && after.is_inline());
+        assert_eq!(before.children.len(), 1);
+        assert_eq!(after.children.len(), 1);
+        // both halves are boxes of the same element
+        assert!(before.render_node.is_some());
+        assert_eq!(before.render_node, after.render_node);
+
+        assert!(layout_box.children[1].box_type == BoxType::Block);
+        assert_eq!(layout_box.children[1].children.len(), 1);
+    }
 }
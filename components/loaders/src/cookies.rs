@@ -0,0 +1,510 @@
+/// The cookie jar of the network layer. Cookies are stored from the
+/// `Set-Cookie` headers of the responses and sent back in the `Cookie`
+/// header of the requests to matching URLs. Cookies with an expiry
+/// outlive the session when the jar is saved to the profile directory.
+/// https://datatracker.ietf.org/doc/html/rfc6265
+use std::fs;
+use std::io;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+use std::time::{SystemTime, UNIX_EPOCH};
+use url::Url;
+
+/// The public suffixes of more than one label, one per line
+const PUBLIC_SUFFIXES: &str = include_str!("public_suffixes.txt");
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Cookie {
+    pub name: String,
+    pub value: String,
+    pub domain: String,
+    /// Whether the cookie is only sent to the host that set it, not to
+    /// its subdomains
+    pub host_only: bool,
+    pub path: String,
+    /// When the cookie expires, in seconds since the unix epoch. Session
+    /// cookies don't expire but aren't persisted.
+    pub expires: Option<u64>,
+    pub secure: bool,
+    pub http_only: bool,
+}
+
+/// The jar shared by the loaders of the documents of a renderer, used
+/// on the threads loading the resources
+pub type CookieJarRef = Arc<Mutex<CookieJar>>;
+
+#[derive(Debug, Default)]
+pub struct CookieJar {
+    cookies: Vec<Cookie>,
+}
+
+impl Cookie {
+    fn is_expired(&self, now: u64) -> bool {
+        matches!(self.expires, Some(expires) if expires <= now)
+    }
+
+    fn matches(&self, url: &Url) -> bool {
+        let host = url.host().to_ascii_lowercase();
+        let domain_matches = if self.host_only {
+            host == self.domain
+        } else {
+            domain_match(&host, &self.domain)
+        };
+        domain_matches
+            && path_match(&request_path(url), &self.path)
+            && (!self.secure || url.protocol() == "https")
+    }
+}
+
+impl CookieJar {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn cookies(&self) -> &[Cookie] {
+        &self.cookies
+    }
+
+    /// Store the cookie of a `Set-Cookie` header received from a URL.
+    /// A cookie replaces the one with the same name, domain & path, and
+    /// an expired cookie removes it.
+    /// https://datatracker.ietf.org/doc/html/rfc6265#section-5.3
+    pub fn set_cookie(&mut self, url: &Url, header: &str, now: SystemTime) {
+        let now = unix_time(now);
+        let cookie = match parse_set_cookie(url, header, now) {
+            Some(cookie) => cookie,
+            None => return,
+        };
+
+        self.cookies.retain(|existing| {
+            existing.name != cookie.name
+                || existing.domain != cookie.domain
+                || existing.path != cookie.path
+        });
+        if !cookie.is_expired(now) {
+            self.cookies.push(cookie);
+        }
+    }
+
+    /// The value of the `Cookie` header of a request to a URL, the
+    /// cookies with longer paths first
+    /// https://datatracker.ietf.org/doc/html/rfc6265#section-5.4
+    pub fn cookie_header(&self, url: &Url, now: SystemTime) -> Option<String> {
+        if !is_http(url) {
+            return None;
+        }
+        let now = unix_time(now);
+        let mut cookies = self
+            .cookies
+            .iter()
+            .filter(|cookie| !cookie.is_expired(now) && cookie.matches(url))
+            .collect::<Vec<&Cookie>>();
+        if cookies.is_empty() {
+            return None;
+        }
+
+        // the sort is stable so the older cookies of a path come first
+        cookies.sort_by(|a, b| b.path.len().cmp(&a.path.len()));
+        let pairs = cookies
+            .iter()
+            .map(|cookie| format!("{}={}", cookie.name, cookie.value))
+            .collect::<Vec<String>>();
+        Some(pairs.join("; "))
+    }
+
+    /// Load the cookies saved in a file, in the `cookies.txt` format.
+    /// A missing file is an empty jar.
+    pub fn load(path: &Path) -> io::Result<Self> {
+        let content = match fs::read_to_string(path) {
+            Ok(content) => content,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Self::new()),
+            Err(e) => return Err(e),
+        };
+        let cookies = content.lines().filter_map(parse_cookie_line).collect();
+        Ok(Self { cookies })
+    }
+
+    /// Save the persistent cookies that aren't expired, the session
+    /// cookies end with the session
+    pub fn save(&self, path: &Path, now: SystemTime) -> io::Result<()> {
+        let now = unix_time(now);
+        let mut content = String::from("# Netscape HTTP Cookie File\n");
+        for cookie in &self.cookies {
+            let expires = match cookie.expires {
+                Some(expires) if expires > now => expires,
+                _ => continue,
+            };
+            let domain = if cookie.host_only {
+                cookie.domain.clone()
+            } else {
+                format!(".{}", cookie.domain)
+            };
+            content.push_str(&format!(
+                "{}{}\t{}\t{}\t{}\t{}\t{}\t{}\n",
+                if cookie.http_only {
+                    HTTP_ONLY_PREFIX
+                } else {
+                    ""
+                },
+                domain,
+                bool_field(!cookie.host_only),
+                cookie.path,
+                bool_field(cookie.secure),
+                expires,
+                cookie.name,
+                cookie.value
+            ));
+        }
+        fs::write(path, content)
+    }
+}
+
+const HTTP_ONLY_PREFIX: &str = "#HttpOnly_";
+
+fn bool_field(value: bool) -> &'static str {
+    if value {
+        "TRUE"
+    } else {
+        "FALSE"
+    }
+}
+
+fn parse_cookie_line(line: &str) -> Option<Cookie> {
+    let (line, http_only) = match line.strip_prefix(HTTP_ONLY_PREFIX) {
+        Some(line) => (line, true),
+        None if line.starts_with('#') => return None,
+        None => (line, false),
+    };
+    let fields = line.split('\t').collect::<Vec<&str>>();
+    if fields.len() != 7 {
+        return None;
+    }
+    Some(Cookie {
+        name: fields[5].to_string(),
+        value: fields[6].to_string(),
+        domain: fields[0].trim_start_matches('.').to_string(),
+        host_only: fields[1] != "TRUE",
+        path: fields[2].to_string(),
+        expires: Some(fields[4].parse().ok()?),
+        secure: fields[3] == "TRUE",
+        http_only,
+    })
+}
+
+fn unix_time(time: SystemTime) -> u64 {
+    time.duration_since(UNIX_EPOCH)
+        .map(|duration| duration.as_secs())
+        .unwrap_or(0)
+}
+
+fn is_http(url: &Url) -> bool {
+    matches!(url.protocol(), "http" | "https")
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.2
+fn parse_set_cookie(url: &Url, header: &str, now: u64) -> Option<Cookie> {
+    if !is_http(url) {
+        return None;
+    }
+    let mut parts = header.split(';');
+    let (name, value) = parts.next()?.split_once('=')?;
+    let name = name.trim();
+    if name.is_empty() {
+        return None;
+    }
+
+    let host = url.host().to_ascii_lowercase();
+    let mut cookie = Cookie {
+        name: name.to_string(),
+        value: value.trim().to_string(),
+        domain: host.clone(),
+        host_only: true,
+        path: default_path(url),
+        expires: None,
+        secure: false,
+        http_only: false,
+    };
+    let mut max_age = None;
+
+    for attribute in parts {
+        let (key, value) = match attribute.split_once('=') {
+            Some((key, value)) => (key.trim(), value.trim()),
+            None => (attribute.trim(), ""),
+        };
+        match key.to_ascii_lowercase().as_str() {
+            "expires" => {
+                if let Some(expires) = parse_cookie_date(value) {
+                    cookie.expires = Some(expires);
+                }
+            }
+            "max-age" => {
+                if let Ok(seconds) = value.parse::<i64>() {
+                    max_age = Some(seconds);
+                }
+            }
+            "domain" => {
+                let domain = value.trim_start_matches('.').to_ascii_lowercase();
+                // a public suffix is only the domain of the host itself,
+                // the cookie isn't shared with the other sites of the suffix
+                if !domain.is_empty() && is_public_suffix(&domain) {
+                    if domain != host {
+                        return None;
+                    }
+                } else if !domain.is_empty() {
+                    if !domain_match(&host, &domain) {
+                        return None;
+                    }
+                    cookie.domain = domain;
+                    cookie.host_only = false;
+                }
+            }
+            "path" if value.starts_with('/') => cookie.path = value.to_string(),
+            "secure" => cookie.secure = true,
+            "httponly" => cookie.http_only = true,
+            _ => {}
+        }
+    }
+
+    // the max-age attribute has precedence over the expires attribute
+    if let Some(seconds) = max_age {
+        cookie.expires = Some(if seconds <= 0 {
+            0
+        } else {
+            now.saturating_add(seconds as u64)
+        });
+    }
+    if cookie.secure && url.protocol() != "https" {
+        return None;
+    }
+    Some(cookie)
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.3
+fn domain_match(host: &str, domain: &str) -> bool {
+    if host == domain {
+        return true;
+    }
+    let is_ip_address = host.parse::<std::net::IpAddr>().is_ok();
+    !is_ip_address && host.ends_with(domain) && host[..host.len() - domain.len()].ends_with('.')
+}
+
+/// Whether a domain is shared by the sites of different owners, e.g.
+/// `com` or `co.uk`, which the cookies can't be set on
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.3
+fn is_public_suffix(domain: &str) -> bool {
+    !domain.contains('.')
+        || PUBLIC_SUFFIXES
+            .lines()
+            .any(|line| !line.starts_with("//") && line.trim() == domain)
+}
+
+fn request_path(url: &Url) -> String {
+    match url.path() {
+        path if path.starts_with('/') => path.to_string(),
+        _ => "/".to_string(),
+    }
+}
+
+/// The path of a cookie without a `Path` attribute, the directory of
+/// the URL path
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.4
+fn default_path(url: &Url) -> String {
+    let path = request_path(url);
+    match path.rfind('/') {
+        Some(0) | None => "/".to_string(),
+        Some(index) => path[..index].to_string(),
+    }
+}
+
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.4
+fn path_match(request_path: &str, cookie_path: &str) -> bool {
+    if request_path == cookie_path {
+        return true;
+    }
+    request_path.starts_with(cookie_path)
+        && (cookie_path.ends_with('/') || request_path[cookie_path.len()..].starts_with('/'))
+}
+
+/// Parse the date of an `Expires` attribute, e.g.
+/// `Wed, 21 Oct 2015 07:28:00 GMT`, into seconds since the unix epoch
+/// https://datatracker.ietf.org/doc/html/rfc6265#section-5.1.1
+fn parse_cookie_date(input: &str) -> Option<u64> {
+    const MONTHS: [&str; 12] = [
+        "jan", "feb", "mar", "apr", "may", "jun", "jul", "aug", "sep", "oct", "nov", "dec",
+    ];
+    let mut time = None;
+    let mut day = None;
+    let mut month = None;
+    let mut year = None;
+
+    let tokens = input
+        .split(|c: char| !c.is_ascii_alphanumeric() && c != ':')
+        .filter(|token| !token.is_empty());
+    for token in tokens {
+        if time.is_none() && token.contains(':') {
+            let parts = token
+                .split(':')
+                .map(|part| part.parse::<u64>().ok())
+                .collect::<Option<Vec<u64>>>();
+            if let Some(&[hour, minute, second]) = parts.as_deref() {
+                time = Some((hour, minute, second));
+                continue;
+            }
+        }
+        let is_number = token.chars().all(|c| c.is_ascii_digit());
+        if day.is_none() && is_number && token.len() <= 2 {
+            day = token.parse::<u64>().ok();
+        } else if month.is_none() && token.len() >= 3 {
+            let prefix = token[..3].to_ascii_lowercase();
+            if let Some(index) = MONTHS.iter().position(|m| *m == prefix) {
+                month = Some(index as u64 + 1);
+            } else if year.is_none() && is_number {
+                year = token.parse::<u64>().ok();
+            }
+        } else if year.is_none() && is_number {
+            year = token.parse::<u64>().ok();
+        }
+    }
+
+    let (hour, minute, second) = time?;
+    let (day, month) = (day?, month?);
+    let year = match year? {
+        year @ 70..=99 => year + 1900,
+        year @ 0..=69 => year + 2000,
+        year => year,
+    };
+    if year < 1970 || !(1..=31).contains(&day) || hour > 23 || minute > 59 || second > 59 {
+        return None;
+    }
+    let days = days_from_civil(year, month, day);
+    Some(days * 86400 + hour * 3600 + minute * 60 + second)
+}
+
+/// The number of days since the unix epoch of a date of the
+/// proleptic Gregorian calendar
+fn days_from_civil(year: u64, month: u64, day: u64) -> u64 {
+    let year = if month <= 2 { year - 1 } else { year };
+    let era = year / 400;
+    let year_of_era = year - era * 400;
+    let month_index = (month + 9) % 12;
+    let day_of_year = (153 * month_index + 2) / 5 + day - 1;
+    let day_of_era = year_of_era * 365 + year_of_era / 4 - year_of_era / 100 + day_of_year;
+    era * 146097 + day_of_era - 719468
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::time::Duration;
+
+    fn url(url: &str) -> Url {
+        Url::parse(url).unwrap()
+    }
+
+    fn at(seconds: u64) -> SystemTime {
+        UNIX_EPOCH + Duration::from_secs(seconds)
+    }
+
+    #[test]
+    fn parse_dates() {
+        assert_eq!(
+            parse_cookie_date("Wed, 21 Oct 2015 07:28:00 GMT"),
+            Some(1445412480)
+        );
+        assert_eq!(
+            parse_cookie_date("Thursday, 01-Jan-70 00:00:01 GMT"),
+            Some(1)
+        );
+        assert_eq!(parse_cookie_date("not a date"), None);
+    }
+
+    #[test]
+    fn send_matching_cookies() {
+        let mut jar = CookieJar::new();
+        let now = at(1000);
+        let page = url("http://www.example.com/shop/cart");
+
+        jar.set_cookie(&page, "id=1", now);
+        jar.set_cookie(&page, "theme=dark; Domain=example.com; Path=/", now);
+        jar.set_cookie(&page, "token=x; Secure", now);
+        jar.set_cookie(&page, "evil=1; Domain=other.com", now);
+        jar.set_cookie(&page, "short=1; Max-Age=10", now);
+        assert_eq!(jar.cookies().len(), 3);
+
+        assert_eq!(
+            jar.cookie_header(&page, now),
+            Some("id=1; short=1; theme=dark".to_string())
+        );
+        // host-only cookies aren't sent to other subdomains, and no
+        // cookies are sent outside of their path
+        assert_eq!(
+            jar.cookie_header(&url("http://api.example.com/shop/cart"), now),
+            Some("theme=dark".to_string())
+        );
+        assert_eq!(
+            jar.cookie_header(&url("http://www.example.com/shopping"), now),
+            Some("theme=dark".to_string())
+        );
+        assert_eq!(
+            jar.cookie_header(&page, at(1010)),
+            Some("id=1; theme=dark".to_string())
+        );
+
+        // a cookie is replaced, or removed when it's expired
+        jar.set_cookie(&page, "id=2", now);
+        jar.set_cookie(&page, "theme=; Domain=example.com; Path=/; Max-Age=0", now);
+        assert_eq!(
+            jar.cookie_header(&page, now),
+            Some("short=1; id=2".to_string())
+        );
+    }
+
+    #[test]
+    fn ignore_public_suffix_domains() {
+        let mut jar = CookieJar::new();
+        let now = at(1000);
+
+        jar.set_cookie(&url("http://example.com/"), "a=1; Domain=com", now);
+        jar.set_cookie(&url("http://shop.example.co.uk/"), "b=1; Domain=co.uk", now);
+        jar.set_cookie(
+            &url("http://shop.example.co.uk/"),
+            "c=1; Domain=.CO.UK",
+            now,
+        );
+        assert!(jar.cookies().is_empty());
+
+        // the domain of the host itself is a host-only cookie
+        jar.set_cookie(&url("http://localhost/"), "d=1; Domain=localhost", now);
+        jar.set_cookie(
+            &url("http://shop.example.co.uk/"),
+            "e=1; Domain=example.co.uk",
+            now,
+        );
+        assert_eq!(jar.cookies().len(), 2);
+        assert!(jar.cookies()[0].host_only);
+        assert!(!jar.cookies()[1].host_only);
+        assert_eq!(
+            jar.cookie_header(&url("http://www.example.co.uk/"), now),
+            Some("e=1".to_string())
+        );
+    }
+
+    #[test]
+    fn persist_cookies() {
+        let mut jar = CookieJar::new();
+        let now = at(1000);
+        let page = url("https://example.com/");
+        jar.set_cookie(&page, "session=1", now);
+        jar.set_cookie(&page, "kept=1; Max-Age=100; Secure; HttpOnly", now);
+        jar.set_cookie(&page, "wide=1; Domain=example.com; Max-Age=100", now);
+
+        let path = std::env::temp_dir().join("moon_cookies.txt");
+        jar.save(&path, now).unwrap();
+        let loaded = CookieJar::load(&path).unwrap();
+        assert_eq!(loaded.cookies(), &jar.cookies()[1..]);
+        assert_eq!(
+            loaded.cookie_header(&url("https://sub.example.com/"), now),
+            Some("wide=1".to_string())
+        );
+    }
+}
@@ -0,0 +1,292 @@
+use super::cookies::CookieJarRef;
+use super::scheme::{Request, SchemeHandler};
+use dom::document_loader::Method;
+use rustls::client::{ServerCertVerified, ServerCertVerifier, ServerName};
+use rustls::{Certificate, ClientConfig};
+use std::io::Read;
+use std::sync::Arc;
+use std::time::{Duration, SystemTime};
+use url::Url;
+
+pub const DEFAULT_USER_AGENT: &str = concat!("Moon/", env!("CARGO_PKG_VERSION"));
+
+/// The number of redirects followed before giving up on a request
+pub const MAX_REDIRECTS: u32 = 20;
+
+/// How the HTTP client connects to the servers
+#[derive(Debug, Clone)]
+pub struct HttpConfig {
+    pub user_agent: String,
+    /// How long a request to a server may take, each redirect being
+    /// another request
+    pub timeout: Option<Duration>,
+    /// The URL of the proxy the requests go through, e.g. `http://localhost:8080`
+    pub proxy: Option<String>,
+    /// Accept any certificate, e.g. the self-signed certificate of a local server
+    pub insecure: bool,
+    pub max_redirects: u32,
+}
+
+impl Default for HttpConfig {
+    fn default() -> Self {
+        Self {
+            user_agent: DEFAULT_USER_AGENT.to_string(),
+            timeout: None,
+            proxy: None,
+            insecure: false,
+            max_redirects: MAX_REDIRECTS,
+        }
+    }
+}
+
+/// Load `http` & `https` URLs. The redirects are followed by the handler
+/// so the cookies of every response are stored & sent to the next URL.
+pub struct HttpHandler {
+    agent: ureq::Agent,
+    max_redirects: u32,
+    cookie_jar: Option<CookieJarRef>,
+}
+
+impl HttpHandler {
+    pub fn new(config: &HttpConfig) -> Result<Self, String> {
+        let mut builder = ureq::AgentBuilder::new()
+            .user_agent(&config.user_agent)
+            .redirects(0);
+        if let Some(timeout) = config.timeout {
+            builder = builder.timeout(timeout);
+        }
+        if let Some(proxy) = &config.proxy {
+            let proxy = ureq::Proxy::new(proxy).map_err(|e| format!("Invalid proxy: {}", e))?;
+            builder = builder.proxy(proxy);
+        }
+        if config.insecure {
+            let tls_config = ClientConfig::builder()
+                .with_safe_defaults()
+                .with_custom_certificate_verifier(Arc::new(AcceptAnyCertificate))
+                .with_no_client_auth();
+            builder = builder.tls_config(Arc::new(tls_config));
+        }
+
+        Ok(Self {
+            agent: builder.build(),
+            max_redirects: config.max_redirects,
+            cookie_jar: None,
+        })
+    }
+
+    /// Store the cookies of the responses in a jar & send them back
+    pub fn with_cookie_jar(mut self, cookie_jar: CookieJarRef) -> Self {
+        self.cookie_jar = Some(cookie_jar);
+        self
+    }
+
+    fn send(
+        &self,
+        url: &Url,
+        method: Method,
+        request: &Request,
+        with_body: bool,
+    ) -> Result<ureq::Response, String> {
+        let method_name = match method {
+            Method::Get => "GET",
+            Method::Post => "POST",
+        };
+        // the fragment is never sent to the server
+        let mut http_request = self.agent.request(method_name, url.without_fragment());
+        for (name, value) in &request.headers {
+            http_request = http_request.set(name, value);
+        }
+        if let Some(jar) = &self.cookie_jar {
+            let cookies = jar.lock().unwrap().cookie_header(url, SystemTime::now());
+            if let Some(cookies) = cookies {
+                http_request = http_request.set("Cookie", &cookies);
+            }
+        }
+
+        let result = match &request.body {
+            Some(body) if with_body => http_request
+                .set("Content-Type", &body.content_type)
+                .send_bytes(&body.bytes),
+            _ => http_request.call(),
+        };
+        match result {
+            Ok(response) => Ok(response),
+            Err(ureq::Error::Status(status, response)) => {
+                self.store_cookies(url, &response);
+                Err(format!("HTTP {} {}", status, response.status_text()))
+            }
+            Err(ureq::Error::Transport(e)) => Err(e.to_string()),
+        }
+    }
+
+    fn store_cookies(&self, url: &Url, response: &ureq::Response) {
+        if let Some(jar) = &self.cookie_jar {
+            let mut jar = jar.lock().unwrap();
+            for header in response.all("Set-Cookie") {
+                jar.set_cookie(url, header, SystemTime::now());
+            }
+        }
+    }
+}
+
+impl SchemeHandler for HttpHandler {
+    fn load(&self, request: &Request) -> Result<Vec<u8>, String> {
+        let mut url = request.url.clone();
+        let mut method = request.method;
+        let mut with_body = request.body.is_some();
+        let mut visited = Vec::new();
+
+        loop {
+            let response = self.send(&url, method, request, with_body)?;
+            self.store_cookies(&url, &response);
+
+            let status = response.status();
+            if !matches!(status, 301 | 302 | 303 | 307 | 308) {
+                let mut bytes = Vec::new();
+                response
+                    .into_reader()
+                    .read_to_end(&mut bytes)
+                    .map_err(|e| e.to_string())?;
+                return Ok(bytes);
+            }
+
+            visited.push(url.raw().to_string());
+            if visited.len() > self.max_redirects as usize {
+                return Err(format!("Too many redirects from {}", request.url.raw()));
+            }
+            let location = response
+                .header("Location")
+                .ok_or_else(|| format!("Redirect without a location from {}", url.raw()))?;
+            let next = resolve_location(&url, location)?;
+            if visited.iter().any(|visited| visited == next.raw()) {
+                return Err(format!("Redirect loop at {}", next.raw()));
+            }
+
+            // https://fetch.spec.whatwg.org/#http-redirect-fetch
+            let is_post_redirect = matches!(status, 301 | 302) && method == Method::Post;
+            if status == 303 || is_post_redirect {
+                method = Method::Get;
+                with_body = false;
+            }
+            log::info!("Redirecting from {} to {}", url.raw(), next.raw());
+            url = next;
+        }
+    }
+}
+
+/// The URL a redirect goes to, resolving locations relative to the
+/// redirected URL
+fn resolve_location(base: &Url, location: &str) -> Result<Url, String> {
+    base.join(location)
+        .map_err(|e| format!("Invalid redirect location {}: {:?}", location, e))
+}
+
+/// Trust the certificate of any server, for `--insecure`
+struct AcceptAnyCertificate;
+
+impl ServerCertVerifier for AcceptAnyCertificate {
+    fn verify_server_cert(
+        &self,
+        _end_entity: &Certificate,
+        _intermediates: &[Certificate],
+        _server_name: &ServerName,
+        _scts: &mut dyn Iterator<Item = &[u8]>,
+        _ocsp_response: &[u8],
+        _now: SystemTime,
+    ) -> Result<ServerCertVerified, rustls::Error> {
+        Ok(ServerCertVerified::assertion())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::cookies::CookieJar;
+    use std::io::{BufRead, BufReader, Write};
+    use std::net::TcpListener;
+    use std::sync::Mutex;
+    use std::thread;
+
+    /// Serve a canned response to each connection, returning the request
+    /// lines & cookies the server received
+    fn serve(responses: Vec<&'static str>) -> (u16, thread::JoinHandle<Vec<String>>) {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let port = listener.local_addr().unwrap().port();
+        let handle = thread::spawn(move || {
+            let mut received = Vec::new();
+            for response in responses {
+                let (stream, _) = listener.accept().unwrap();
+                let mut reader = BufReader::new(stream);
+                loop {
+                    let mut line = String::new();
+                    reader.read_line(&mut line).unwrap();
+                    let line = line.trim_end().to_string();
+                    if line.is_empty() {
+                        break;
+                    }
+                    if !line.contains(':') || line.starts_with("Cookie:") {
+                        received.push(line);
+                    }
+                }
+                let mut stream = reader.into_inner();
+                stream.write_all(response.as_bytes()).unwrap();
+            }
+            received
+        });
+        (port, handle)
+    }
+
+    #[test]
+    fn follow_redirects_with_cookies() {
+        let (port, server) = serve(vec![
+            "HTTP/1.1 303 See Other\r\nLocation: /next\r\nSet-Cookie: id=1\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 200 OK\r\nContent-Length: 4\r\nConnection: close\r\n\r\nmoon",
+        ]);
+        let jar = Arc::new(Mutex::new(CookieJar::new()));
+        let handler = HttpHandler::new(&HttpConfig::default())
+            .unwrap()
+            .with_cookie_jar(jar.clone());
+
+        let url = Url::parse(&format!("http://127.0.0.1:{}/form", port)).unwrap();
+        let mut request = Request::new(url);
+        request.method = Method::Post;
+        assert_eq!(handler.load(&request), Ok(b"moon".to_vec()));
+
+        assert_eq!(
+            server.join().unwrap(),
+            vec!["POST /form HTTP/1.1", "GET /next HTTP/1.1", "Cookie: id=1"]
+        );
+        assert_eq!(jar.lock().unwrap().cookies().len(), 1);
+    }
+
+    #[test]
+    fn stop_redirect_loops() {
+        let (port, server) = serve(vec![
+            "HTTP/1.1 302 Found\r\nLocation: b\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+            "HTTP/1.1 302 Found\r\nLocation: /a\r\nContent-Length: 0\r\nConnection: close\r\n\r\n",
+        ]);
+        let handler = HttpHandler::new(&HttpConfig::default()).unwrap();
+
+        let url = Url::parse(&format!("http://127.0.0.1:{}/a", port)).unwrap();
+        let result = handler.load(&Request::new(url));
+        assert_eq!(
+            result,
+            Err(format!("Redirect loop at http://127.0.0.1:{}/a", port))
+        );
+        server.join().unwrap();
+    }
+
+    #[test]
+    fn resolve_locations() {
+        let base = Url::parse("https://example.com:8443/a/b.html").unwrap();
+        let resolve = |location| resolve_location(&base, location).unwrap().raw().to_string();
+        assert_eq!(resolve("c.html"), "https://example.com:8443/a/c.html");
+        assert_eq!(resolve("/c.html"), "https://example.com:8443/c.html");
+        assert_eq!(resolve("//moon.dev/"), "https://moon.dev/");
+        assert_eq!(resolve("http://moon.dev"), "http://moon.dev");
+        assert_eq!(
+            resolve("../c.html?x#y"),
+            "https://example.com:8443/c.html?x#y"
+        );
+    }
+}
@@ -1 +1,5 @@
+pub mod cookies;
+pub mod http;
 pub mod inprocess;
+pub mod resource;
+pub mod scheme;
@@ -0,0 +1,251 @@
+use super::scheme::{Request, SchemeHandlers};
+use dom::document_loader::{Destination, DocumentLoader, LoadRequest, ResponseTask};
+use std::sync::mpsc::{channel, Receiver, Sender};
+use std::thread;
+
+/// The number of requests loaded at the same time
+pub const MAX_CONNECTIONS: usize = 6;
+
+type Response = (usize, Result<Vec<u8>, String>);
+
+struct PendingRequest {
+    id: usize,
+    request: LoadRequest,
+}
+
+/// Load the resources of a document concurrently. The requests wait in a
+/// queue ordered by the priority of their destination & are fetched on
+/// worker threads, a limited number at a time. The callbacks of the
+/// finished requests are taken by the owner of the document, so the
+/// document is rendered while the rest of its resources are loading.
+pub struct ResourceLoader {
+    schemes: SchemeHandlers,
+    max_connections: usize,
+    /// The requests waiting for a connection, in the order they were made
+    queue: Vec<PendingRequest>,
+    in_flight: Vec<PendingRequest>,
+    next_id: usize,
+    sender: Sender<Response>,
+    receiver: Receiver<Response>,
+    finished: Vec<ResponseTask>,
+}
+
+impl ResourceLoader {
+    pub fn new() -> Self {
+        let (sender, receiver) = channel();
+        Self {
+            schemes: SchemeHandlers::new(),
+            max_connections: MAX_CONNECTIONS,
+            queue: Vec::new(),
+            in_flight: Vec::new(),
+            next_id: 0,
+            sender,
+            receiver,
+            finished: Vec::new(),
+        }
+    }
+
+    pub fn with_scheme_handlers(mut self, schemes: SchemeHandlers) -> Self {
+        self.schemes = schemes;
+        self
+    }
+
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections.max(1);
+        self
+    }
+
+    /// Whether requests for a destination are waiting or being fetched
+    pub fn is_loading(&self, destination: Destination) -> bool {
+        self.queue
+            .iter()
+            .chain(self.in_flight.iter())
+            .any(|pending| pending.request.destination == destination)
+    }
+
+    /// Start fetching the queued requests with the highest priority while
+    /// there are connections left
+    fn dispatch(&mut self) {
+        while self.in_flight.len() < self.max_connections {
+            let in_flight = &self.in_flight;
+            let next = self
+                .queue
+                .iter()
+                .enumerate()
+                .filter(|(_, pending)| {
+                    let destination = pending.request.destination;
+                    let loading = in_flight
+                        .iter()
+                        .filter(|p| p.request.destination == destination)
+                        .count();
+                    loading < connection_limit(destination)
+                })
+                // the earliest request wins among the same priority
+                .min_by_key(|(index, pending)| {
+                    (
+                        std::cmp::Reverse(priority(pending.request.destination)),
+                        *index,
+                    )
+                })
+                .map(|(index, _)| index);
+
+            match next {
+                Some(index) => {
+                    let pending = self.queue.remove(index);
+                    self.start(pending);
+                }
+                None => break,
+            }
+        }
+    }
+
+    fn start(&mut self, pending: PendingRequest) {
+        let id = pending.id;
+        let request = Request::from(&pending.request);
+        let handler = self.schemes.get(request.url.protocol());
+        let sender = self.sender.clone();
+        thread::spawn(move || {
+            let response = match handler {
+                Some(handler) => handler.load(&request),
+                None => Err(format!("Unsupported protocol: {}", request.url.protocol())),
+            };
+            // the loader may be gone when the response arrives
+            let _ = sender.send((id, response));
+        });
+        self.in_flight.push(pending);
+    }
+
+    fn finish(&mut self, (id, result): Response) {
+        let index = match self.in_flight.iter().position(|pending| pending.id == id) {
+            Some(index) => index,
+            None => return,
+        };
+        let request = self.in_flight.remove(index).request;
+
+        let task: ResponseTask = match result {
+            Ok(bytes) => {
+                let callback = request.success_callback;
+                Box::new(move || {
+                    if let Some(cb) = callback {
+                        cb(bytes);
+                    }
+                })
+            }
+            Err(e) => {
+                let callback = request.error_callback;
+                Box::new(move || {
+                    if let Some(cb) = callback {
+                        cb(e);
+                    }
+                })
+            }
+        };
+        self.finished.push(task);
+        self.dispatch();
+    }
+}
+
+impl DocumentLoader for ResourceLoader {
+    fn load(&mut self, request: LoadRequest) {
+        let id = self.next_id;
+        self.next_id += 1;
+        self.queue.push(PendingRequest { id, request });
+        self.dispatch();
+    }
+
+    fn take_responses(&mut self) -> Vec<ResponseTask> {
+        while let Ok(response) = self.receiver.try_recv() {
+            self.finish(response);
+        }
+        std::mem::take(&mut self.finished)
+    }
+
+    fn wait_for(&mut self, destination: Destination) -> Vec<ResponseTask> {
+        while self.is_loading(destination) {
+            match self.receiver.recv() {
+                Ok(response) => self.finish(response),
+                Err(_) => break,
+            }
+        }
+        self.take_responses()
+    }
+}
+
+/// Higher priorities are loaded first. Stylesheets block the rendering
+/// of the document while fonts & images only change it when they arrive.
+fn priority(destination: Destination) -> u8 {
+    match destination {
+        Destination::Document => 4,
+        Destination::Style => 3,
+        Destination::Font => 2,
+        Destination::Image => 1,
+        Destination::Other => 0,
+    }
+}
+
+/// The number of requests for a destination loaded at the same time, so
+/// the images of a page don't take every connection
+fn connection_limit(destination: Destination) -> usize {
+    match destination {
+        Destination::Image => 4,
+        Destination::Font => 2,
+        _ => MAX_CONNECTIONS,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use url::Url;
+
+    #[test]
+    fn load_by_priority() {
+        let dir = std::env::temp_dir().join(format!("moon-resources-{}", std::process::id()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let loaded = Rc::new(RefCell::new(Vec::new()));
+        let mut loader = ResourceLoader::new().with_max_connections(1);
+
+        let requests = [
+            ("a.png", Destination::Image),
+            ("b.woff", Destination::Font),
+            ("c.css", Destination::Style),
+            ("d.png", Destination::Image),
+        ];
+        for (name, destination) in requests.iter() {
+            let path = dir.join(name);
+            std::fs::write(&path, name).unwrap();
+            let url = Url::parse(&format!("file://{}", path.display())).unwrap();
+            let loaded = loaded.clone();
+            loader.load(
+                LoadRequest::new(url)
+                    .with_destination(*destination)
+                    .on_success(Box::new(move |bytes| {
+                        loaded.borrow_mut().push(String::from_utf8(bytes).unwrap())
+                    })),
+            );
+        }
+        let failed = Rc::new(RefCell::new(None));
+        let error = failed.clone();
+        loader.load(
+            LoadRequest::new(Url::parse("ftp://example.com/e.css").unwrap())
+                .with_destination(Destination::Style)
+                .on_error(Box::new(move |e| *error.borrow_mut() = Some(e))),
+        );
+        assert!(loader.is_loading(Destination::Font));
+
+        for task in loader.wait_for(Destination::Image) {
+            task();
+        }
+        std::fs::remove_dir_all(&dir).unwrap();
+
+        // the first request started before the others were made
+        assert_eq!(*loaded.borrow(), vec!["a.png", "c.css", "b.woff", "d.png"]);
+        assert_eq!(
+            failed.borrow().as_deref(),
+            Some("Unsupported protocol: ftp")
+        );
+        assert!(!loader.is_loading(Destination::Style));
+    }
+}
@@ -0,0 +1,265 @@
+use super::http::{HttpConfig, HttpHandler};
+use dom::document_loader::{LoadRequest, Method, RequestBody};
+use relative_path::RelativePath;
+use std::collections::HashMap;
+use std::sync::Arc;
+use url::Url;
+
+/// Loads the URLs of a protocol. Handlers are called on the worker
+/// threads of the loaders, so embedders registering a custom protocol,
+/// e.g. `app://`, can serve their resources without network access.
+pub trait SchemeHandler: Send + Sync {
+    fn load(&self, request: &Request) -> Result<Vec<u8>, String>;
+}
+
+/// What a scheme handler loads: a load request without its callbacks,
+/// which stay on the thread of the document
+#[derive(Debug, Clone)]
+pub struct Request {
+    pub url: Url,
+    pub method: Method,
+    pub headers: Vec<(String, String)>,
+    pub body: Option<RequestBody>,
+}
+
+impl Request {
+    pub fn new(url: Url) -> Self {
+        Self {
+            url,
+            method: Method::Get,
+            headers: Vec::new(),
+            body: None,
+        }
+    }
+}
+
+impl From<&LoadRequest> for Request {
+    fn from(request: &LoadRequest) -> Self {
+        Self {
+            url: request.url.clone(),
+            method: request.method,
+            headers: request.headers.clone(),
+            body: request.body.clone(),
+        }
+    }
+}
+
+/// The scheme handlers of a loader by protocol
+#[derive(Clone)]
+pub struct SchemeHandlers {
+    handlers: HashMap<String, Arc<dyn SchemeHandler>>,
+}
+
+impl SchemeHandlers {
+    /// The handlers of the protocols supported out of the box
+    pub fn new() -> Self {
+        let mut handlers = Self {
+            handlers: HashMap::new(),
+        };
+        handlers.register("file", FileHandler);
+        handlers.register("relative", RelativeFileHandler);
+        handlers.register("data", DataHandler);
+        handlers.register_http(HttpHandler::new(&HttpConfig::default()).unwrap());
+        handlers
+    }
+
+    /// Handle the URLs of a protocol, replacing its current handler
+    pub fn register<H: SchemeHandler + 'static>(&mut self, protocol: &str, handler: H) {
+        self.handlers
+            .insert(protocol.to_ascii_lowercase(), Arc::new(handler));
+    }
+
+    /// Load `http` & `https` URLs with a handler
+    pub fn register_http(&mut self, handler: HttpHandler) {
+        let handler = Arc::new(handler);
+        self.handlers.insert("http".to_string(), handler.clone());
+        self.handlers.insert("https".to_string(), handler);
+    }
+
+    pub fn get(&self, protocol: &str) -> Option<Arc<dyn SchemeHandler>> {
+        self.handlers.get(protocol).cloned()
+    }
+
+    pub fn load(&self, request: &Request) -> Result<Vec<u8>, String> {
+        match self.get(request.url.protocol()) {
+            Some(handler) => handler.load(request),
+            None => Err(format!("Unsupported protocol: {}", request.url.protocol())),
+        }
+    }
+}
+
+impl Default for SchemeHandlers {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+/// Read local files. Files are read whatever the method of the request,
+/// the body of a request is only meaningful to a server.
+pub struct FileHandler;
+
+impl SchemeHandler for FileHandler {
+    fn load(&self, request: &Request) -> Result<Vec<u8>, String> {
+        std::fs::read(request.url.path()).map_err(|e| e.to_string())
+    }
+}
+
+/// Read files relative to the working directory
+pub struct RelativeFileHandler;
+
+impl SchemeHandler for RelativeFileHandler {
+    fn load(&self, request: &Request) -> Result<Vec<u8>, String> {
+        let current_dir = std::env::current_dir().map_err(|e| e.to_string())?;
+        let path = RelativePath::new(request.url.path()).to_logical_path(current_dir);
+        std::fs::read(path).map_err(|e| e.to_string())
+    }
+}
+
+/// Decode the content embedded in `data:` URLs
+/// https://fetch.spec.whatwg.org/#data-urls
+pub struct DataHandler;
+
+impl SchemeHandler for DataHandler {
+    fn load(&self, request: &Request) -> Result<Vec<u8>, String> {
+        let path = request.url.path();
+        let path = match path.find('#') {
+            Some(index) => &path[..index],
+            None => path,
+        };
+        let comma = path
+            .find(',')
+            .ok_or_else(|| "Invalid data URL: missing comma".to_string())?;
+        let (mime_type, body) = (&path[..comma], &path[comma + 1..]);
+        let body = percent_decode(body);
+
+        if is_base64(mime_type) {
+            base64_decode(&body).ok_or_else(|| "Invalid data URL: bad base64".to_string())
+        } else {
+            Ok(body)
+        }
+    }
+}
+
+/// Whether the MIME type of a data URL ends with `;base64`
+fn is_base64(mime_type: &str) -> bool {
+    let mime_type = mime_type.trim_end();
+    let split = mime_type.len().saturating_sub(6);
+    match (mime_type.get(..split), mime_type.get(split..)) {
+        (Some(rest), Some(suffix)) => {
+            suffix.eq_ignore_ascii_case("base64") && rest.trim_end_matches(' ').ends_with(';')
+        }
+        _ => false,
+    }
+}
+
+fn percent_decode(input: &str) -> Vec<u8> {
+    let bytes = input.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = match (bytes[index], bytes.get(index + 1), bytes.get(index + 2)) {
+            (b'%', Some(high), Some(low)) => hex_value(*high)
+                .zip(hex_value(*low))
+                .map(|(high, low)| high * 16 + low),
+            _ => None,
+        };
+        match escaped {
+            Some(byte) => {
+                result.push(byte);
+                index += 3;
+            }
+            None => {
+                result.push(bytes[index]);
+                index += 1;
+            }
+        }
+    }
+    result
+}
+
+fn hex_value(byte: u8) -> Option<u8> {
+    (byte as char).to_digit(16).map(|value| value as u8)
+}
+
+/// https://infra.spec.whatwg.org/#forgiving-base64-decode
+fn base64_decode(input: &[u8]) -> Option<Vec<u8>> {
+    let mut data = input
+        .iter()
+        .copied()
+        .filter(|byte| !byte.is_ascii_whitespace())
+        .collect::<Vec<u8>>();
+    if data.len() % 4 == 0 {
+        for _ in 0..2 {
+            if data.last() == Some(&b'=') {
+                data.pop();
+            }
+        }
+    }
+    if data.len() % 4 == 1 {
+        return None;
+    }
+
+    let mut result = Vec::with_capacity(data.len() * 3 / 4);
+    let mut buffer: u32 = 0;
+    let mut bits = 0;
+    for byte in data {
+        let value = match byte {
+            b'A'..=b'Z' => byte - b'A',
+            b'a'..=b'z' => byte - b'a' + 26,
+            b'0'..=b'9' => byte - b'0' + 52,
+            b'+' => 62,
+            b'/' => 63,
+            _ => return None,
+        };
+        buffer = (buffer << 6) | value as u32;
+        bits += 6;
+        if bits >= 8 {
+            bits -= 8;
+            result.push((buffer >> bits) as u8);
+            buffer &= (1 << bits) - 1;
+        }
+    }
+    Some(result)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn load(url: &str) -> Result<Vec<u8>, String> {
+        SchemeHandlers::new().load(&Request::new(Url::parse(url).unwrap()))
+    }
+
+    #[test]
+    fn load_data_urls() {
+        assert_eq!(load("data:,a%20b#c"), Ok(b"a b".to_vec()));
+        assert_eq!(
+            load("data:text/css;base64,Ym9keSB7IH0="),
+            Ok(b"body { }".to_vec())
+        );
+        assert_eq!(load("data:;BASE64,bW9v bg"), Ok(b"moon".to_vec()));
+        assert!(load("data:;base64,bW9vb").is_err());
+        assert!(load("data:text/plain").is_err());
+    }
+
+    #[test]
+    fn register_custom_protocol() {
+        struct AppHandler;
+
+        impl SchemeHandler for AppHandler {
+            fn load(&self, request: &Request) -> Result<Vec<u8>, String> {
+                Ok(request.url.path().as_bytes().to_vec())
+            }
+        }
+
+        let request = Request::new(Url::parse("app://moon/index.html").unwrap());
+        let mut handlers = SchemeHandlers::new();
+        assert_eq!(
+            handlers.load(&request),
+            Err("Unsupported protocol: app".to_string())
+        );
+
+        handlers.register("app", AppHandler);
+        assert_eq!(handlers.load(&request), Ok(b"/index.html".to_vec()));
+    }
+}
@@ -1,8 +1,76 @@
 use serde::{Deserialize, Serialize};
 
+/// The pixels of a frame that changed since the previous frame. The
+/// receiver keeps the previous frame & copies the damaged regions into it.
 #[derive(Debug, Serialize, Deserialize)]
 pub struct RenderedBitmap {
-    pub data: Vec<u8>,
+    pub width: u32,
+    pub height: u32,
+    /// The first frame & the frames of a new size damage the whole frame
+    pub damage: Vec<DamagedRegion>,
+}
+
+/// A rectangle of a frame with its RGBA pixels, row by row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct DamagedRegion {
+    pub x: u32,
+    pub y: u32,
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+}
+
+impl RenderedBitmap {
+    /// Cut the damaged regions, as (x, y, width, height), out of the RGBA
+    /// pixels of a frame. The regions are clamped to the frame.
+    pub fn from_frame(
+        width: u32,
+        height: u32,
+        frame: &[u8],
+        regions: &[(u32, u32, u32, u32)],
+    ) -> Self {
+        let damage = regions
+            .iter()
+            .filter_map(|(x, y, region_width, region_height)| {
+                let (x, y) = ((*x).min(width), (*y).min(height));
+                let region_width = (*region_width).min(width - x);
+                let region_height = (*region_height).min(height - y);
+                if region_width == 0 || region_height == 0 {
+                    return None;
+                }
+                let mut pixels = Vec::with_capacity((region_width * region_height * 4) as usize);
+                for row in y..y + region_height {
+                    let start = ((row * width + x) * 4) as usize;
+                    pixels.extend_from_slice(&frame[start..start + (region_width * 4) as usize]);
+                }
+                Some(DamagedRegion {
+                    x,
+                    y,
+                    width: region_width,
+                    height: region_height,
+                    pixels,
+                })
+            })
+            .collect();
+        Self {
+            width,
+            height,
+            damage,
+        }
+    }
+
+    /// Copy the damaged regions into the RGBA pixels of the previous frame,
+    /// which is resized when the size of the frame changed
+    pub fn blit(&self, frame: &mut Vec<u8>) {
+        frame.resize((self.width * self.height * 4) as usize, 255);
+        for region in &self.damage {
+            let row_size = (region.width * 4) as usize;
+            for (index, row) in region.pixels.chunks(row_size).enumerate() {
+                let start = (((region.y + index as u32) * self.width + region.x) * 4) as usize;
+                frame[start..start + row_size].copy_from_slice(row);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize)]
@@ -15,3 +83,69 @@ pub struct LoadFileContentParams {
     pub content: String,
     pub content_type: String,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn blit_damaged_regions() {
+        // 3x2 frame whose pixels are their index
+        let frame = (0..6).flat_map(|pixel| vec![pixel; 4]).collect::<Vec<u8>>();
+        let bitmap = RenderedBitmap::from_frame(3, 2, &frame, &[(1, 0, 5, 2)]);
+        assert_eq!(bitmap.damage.len(), 1);
+        assert_eq!(bitmap.damage[0].width, 2);
+
+        let mut previous = vec![9; 24];
+        bitmap.blit(&mut previous);
+        let pixels = previous.chunks(4).map(|pixel| pixel[0]).collect::<Vec<_>>();
+        assert_eq!(pixels, vec![9, 1, 2, 9, 4, 5]);
+    }
+}
+
+/// A point of the viewport the mouse is at, in CSS pixels
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct MousePosition {
+    pub x: f32,
+    pub y: f32,
+}
+
+/// The OS cursor the UI shows over the page. The names are the ones of
+/// the cursor icons of winit, so the UI maps them one to one.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum CursorIcon {
+    Default,
+    Crosshair,
+    Hand,
+    Move,
+    Text,
+    Wait,
+    Help,
+    Progress,
+    NotAllowed,
+    ContextMenu,
+    Cell,
+    VerticalText,
+    Alias,
+    Copy,
+    NoDrop,
+    Grab,
+    Grabbing,
+    AllScroll,
+    ZoomIn,
+    ZoomOut,
+    EResize,
+    NResize,
+    NeResize,
+    NwResize,
+    SResize,
+    SeResize,
+    SwResize,
+    WResize,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ColResize,
+    RowResize,
+}
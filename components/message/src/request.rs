@@ -14,3 +14,13 @@ impl Request for GetRenderedBitmap {
     type Result = RenderedBitmap;
     const METHOD: &'static str = "getRenderedBitmap";
 }
+
+/// The cursor to show while the mouse is over a point of the page. There's
+/// no cursor when the page hides it with `cursor: none`.
+pub enum GetCursor {}
+
+impl Request for GetCursor {
+    type Params = MousePosition;
+    type Result = Option<CursorIcon>;
+    const METHOD: &'static str = "getCursor";
+}
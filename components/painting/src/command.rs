@@ -1,14 +1,23 @@
-use super::primitive::{Color, RRect, Rect};
+use super::primitive::{Color, Image, RRect, Rect, Transform};
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DrawCommand {
     FillRect(Rect, Color),
     FillRRect(RRect, Color),
+    /// The outline of a rounded rect, with a line width
+    StrokeRRect(RRect, Color, f32),
+    /// An image stretched over a rect
+    DrawImage(Rect, Image),
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub enum DisplayCommand {
     Draw(DrawCommand),
     GroupDraw(Vec<DrawCommand>),
+    /// A command painted with a transform
+    Transformed(Transform, Box<DisplayCommand>),
+    /// Commands painted inside a rect only, e.g. the contents of a box
+    /// with paint containment
+    Clipped(Rect, Vec<DisplayCommand>),
 }
@@ -0,0 +1,165 @@
+/// Culling of the display commands outside of the area of the page that
+/// is painted. The commands are sorted into square tiles of the page by
+/// their bounds, so the commands of an area, e.g. the viewport scrolled
+/// down the page, are found without going through the whole list.
+use crate::command::DisplayCommand;
+use crate::damage::command_bounds;
+use crate::primitive::Rect;
+use crate::render::DisplayList;
+use std::collections::HashMap;
+
+/// The size of the tiles of the index
+const TILE_SIZE: f32 = 256.;
+
+/// The distance around the viewport whose commands are kept, so the
+/// content about to be scrolled in is ready
+pub const CULL_MARGIN: f32 = 256.;
+
+/// A display list with its commands indexed by the tiles they paint
+pub struct TileIndex {
+    commands: DisplayList,
+    bounds: Vec<Rect>,
+    /// The indices of the commands painting each tile, as (column, row)
+    tiles: HashMap<(i32, i32), Vec<usize>>,
+}
+
+impl TileIndex {
+    pub fn new(display_list: DisplayList) -> Self {
+        let mut commands = Vec::with_capacity(display_list.len());
+        let mut bounds = Vec::with_capacity(display_list.len());
+        let mut tiles: HashMap<(i32, i32), Vec<usize>> = HashMap::new();
+
+        for command in display_list {
+            // the commands painting nothing are dropped
+            let command_bounds = match union_bounds(&command) {
+                Some(command_bounds) => command_bounds,
+                None => continue,
+            };
+            let index = commands.len();
+            for tile in tiles_of(&command_bounds) {
+                tiles.entry(tile).or_default().push(index);
+            }
+            commands.push(command);
+            bounds.push(command_bounds);
+        }
+
+        Self {
+            commands,
+            bounds,
+            tiles,
+        }
+    }
+
+    /// The commands painting an area of the page, in the order they are
+    /// painted
+    pub fn visible(&self, area: &Rect) -> Vec<&DisplayCommand> {
+        let mut indices = tiles_of(area)
+            .filter_map(|tile| self.tiles.get(&tile))
+            .flatten()
+            .copied()
+            .filter(|index| self.bounds[*index].intersects(area))
+            .collect::<Vec<usize>>();
+        indices.sort_unstable();
+        indices.dedup();
+        indices
+            .into_iter()
+            .map(|index| &self.commands[index])
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.commands.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.commands.is_empty()
+    }
+}
+
+/// Drop the commands of a display list entirely outside of the viewport
+/// and its margin
+pub fn cull(display_list: DisplayList, viewport: &Rect) -> DisplayList {
+    let area = Rect::new(
+        viewport.x - CULL_MARGIN,
+        viewport.y - CULL_MARGIN,
+        viewport.width + 2. * CULL_MARGIN,
+        viewport.height + 2. * CULL_MARGIN,
+    );
+    display_list
+        .into_iter()
+        .filter(|command| union_bounds(command).map_or(false, |bounds| bounds.intersects(&area)))
+        .collect()
+}
+
+fn union_bounds(command: &DisplayCommand) -> Option<Rect> {
+    command_bounds(command)
+        .into_iter()
+        .filter(|bounds| bounds.width > 0. && bounds.height > 0.)
+        .reduce(|region, bounds| region.union(&bounds))
+}
+
+/// The tiles covered by a rect
+fn tiles_of(rect: &Rect) -> impl Iterator<Item = (i32, i32)> {
+    let first_column = (rect.x / TILE_SIZE).floor() as i32;
+    let last_column = ((rect.x + rect.width) / TILE_SIZE).floor() as i32;
+    let first_row = (rect.y / TILE_SIZE).floor() as i32;
+    let last_row = ((rect.y + rect.height) / TILE_SIZE).floor() as i32;
+    (first_row..=last_row)
+        .flat_map(move |row| (first_column..=last_column).map(move |column| (column, row)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::command::DrawCommand;
+    use crate::primitive::{Color, Transform};
+
+    fn fill(y: f32) -> DisplayCommand {
+        let black = Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        DisplayCommand::Draw(DrawCommand::FillRect(Rect::new(0., y, 100., 50.), black))
+    }
+
+    #[test]
+    fn find_visible_commands() {
+        let display_list = vec![
+            fill(0.),
+            fill(600.),
+            DisplayCommand::Transformed(Transform::translate(0., 1000.), Box::new(fill(0.))),
+            // spanning the tiles of the other commands
+            DisplayCommand::Draw(DrawCommand::FillRect(
+                Rect::new(0., 0., 10., 2000.),
+                Color {
+                    r: 255,
+                    g: 0,
+                    b: 0,
+                    a: 255,
+                },
+            )),
+            fill(5000.),
+        ];
+        let index = TileIndex::new(display_list.clone());
+        assert_eq!(index.len(), 5);
+
+        let visible = index.visible(&Rect::new(0., 550., 100., 500.));
+        assert_eq!(
+            visible,
+            vec![&display_list[1], &display_list[2], &display_list[3]]
+        );
+        assert!(index.visible(&Rect::new(0., 3000., 100., 500.)).is_empty());
+
+        let culled = cull(display_list.clone(), &Rect::new(0., 0., 100., 500.));
+        assert_eq!(
+            culled,
+            vec![
+                display_list[0].clone(),
+                display_list[1].clone(),
+                display_list[3].clone()
+            ]
+        );
+    }
+}
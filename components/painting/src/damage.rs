@@ -0,0 +1,66 @@
+/// Damage tracking between two display lists. Only the region covered by
+/// the commands that changed has to be repainted, the rest of the previous
+/// frame can be kept as is.
+use crate::command::{DisplayCommand, DrawCommand};
+use crate::primitive::Rect;
+use crate::render::DisplayList;
+
+/// The region of the screen that changed between two display lists,
+/// or `None` if they paint the same thing
+pub fn damaged_region(previous: &DisplayList, current: &DisplayList) -> Option<Rect> {
+    let common_prefix = previous
+        .iter()
+        .zip(current.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let previous = &previous[common_prefix..];
+    let current = &current[common_prefix..];
+
+    let common_suffix = previous
+        .iter()
+        .rev()
+        .zip(current.iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let previous = &previous[..previous.len() - common_suffix];
+    let current = &current[..current.len() - common_suffix];
+
+    previous
+        .iter()
+        .chain(current.iter())
+        .flat_map(command_bounds)
+        .fold(None, |region: Option<Rect>, bounds| match region {
+            Some(region) => Some(region.union(&bounds)),
+            None => Some(bounds),
+        })
+}
+
+/// The bounds of the area painted by a display command
+pub fn command_bounds(command: &DisplayCommand) -> Vec<Rect> {
+    match command {
+        DisplayCommand::Draw(draw_command) => vec![draw_bounds(draw_command)],
+        DisplayCommand::GroupDraw(draw_commands) => draw_commands.iter().map(draw_bounds).collect(),
+        DisplayCommand::Transformed(transform, command) => command_bounds(command)
+            .iter()
+            .map(|bounds| transform.map_rect(bounds))
+            .collect(),
+        DisplayCommand::Clipped(rect, commands) => commands
+            .iter()
+            .flat_map(command_bounds)
+            .map(|bounds| bounds.intersection(rect))
+            .collect(),
+    }
+}
+
+fn draw_bounds(draw_command: &DrawCommand) -> Rect {
+    match draw_command {
+        DrawCommand::FillRect(rect, _) | DrawCommand::DrawImage(rect, _) => rect.clone(),
+        DrawCommand::FillRRect(rect, _) => Rect::new(rect.x, rect.y, rect.width, rect.height),
+        DrawCommand::StrokeRRect(rect, _, width) => Rect::new(
+            rect.x - width / 2.,
+            rect.y - width / 2.,
+            rect.width + width,
+            rect.height + width,
+        ),
+    }
+}
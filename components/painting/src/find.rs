@@ -0,0 +1,139 @@
+/// This module contains the search of a text in the rendered text of the
+/// page, e.g. for the find bar of the browser, and the highlights of the
+/// matches.
+use crate::command::{DisplayCommand, DrawCommand};
+use crate::primitive::{Color, Rect};
+use crate::selection::{rects, separator, text_runs, TextPosition, TextSelection};
+use layout::layout_box::LayoutBox;
+
+const MATCH_COLOR: Color = Color {
+    r: 255,
+    g: 235,
+    b: 59,
+    a: 160,
+};
+
+/// The match the find bar is at, e.g. after moving to the next match
+const ACTIVE_MATCH_COLOR: Color = Color {
+    r: 255,
+    g: 150,
+    b: 50,
+    a: 200,
+};
+
+/// A match of the searched text, from its first character to the end of
+/// its last one
+#[derive(Debug, Clone, PartialEq)]
+pub struct MatchRange {
+    pub start: TextPosition,
+    pub end: TextPosition,
+    /// The rectangles of the matched text in the page, one per fragment
+    pub rects: Vec<Rect>,
+}
+
+impl MatchRange {
+    /// The smallest rectangle containing the matched text, e.g. to scroll
+    /// the match into view
+    pub fn bounds(&self) -> Option<Rect> {
+        let first = self.rects.first()?.clone();
+        Some(self.rects.iter().skip(1).fold(first, |bounds, rect| {
+            let x = bounds.x.min(rect.x);
+            let y = bounds.y.min(rect.y);
+            let right = (bounds.x + bounds.width).max(rect.x + rect.width);
+            let bottom = (bounds.y + bounds.height).max(rect.y + rect.height);
+            Rect::new(x, y, right - x, bottom - y)
+        }))
+    }
+}
+
+/// Search the rendered text of the page for a text, ignoring the case.
+/// The text is searched as it's selected: the lines are joined by spaces
+/// & the blocks by newlines, so a match can span several lines. The
+/// matches don't overlap.
+pub fn find(root: &LayoutBox, text: &str) -> Vec<MatchRange> {
+    let needle = text.chars().map(fold_case).collect::<Vec<_>>();
+    if needle.is_empty() {
+        return Vec::new();
+    }
+
+    // the characters of the page with their position & the position after them
+    let runs = text_runs(root);
+    let mut haystack: Vec<(char, TextPosition, TextPosition)> = Vec::new();
+    for (index, run) in runs.iter().enumerate() {
+        let start = TextPosition {
+            fragment: index,
+            offset: 0,
+        };
+        match (index.checked_sub(1), haystack.last()) {
+            (Some(previous), Some((last, ..))) => match separator(&runs[previous], run) {
+                Some(' ') if *last == ' ' => {}
+                Some(separator) => haystack.push((separator, start, start)),
+                None => {}
+            },
+            _ => {}
+        }
+        for (offset, c) in run.fragment.text.chars().enumerate() {
+            let position = |offset| TextPosition {
+                fragment: index,
+                offset,
+            };
+            haystack.push((fold_case(c), position(offset), position(offset + 1)));
+        }
+    }
+
+    let mut matches = Vec::new();
+    let mut index = 0;
+    while index + needle.len() <= haystack.len() {
+        let is_match = haystack[index..index + needle.len()]
+            .iter()
+            .zip(&needle)
+            .all(|((c, ..), needle)| c == needle);
+        if !is_match {
+            index += 1;
+            continue;
+        }
+        let start = haystack[index].1;
+        let end = haystack[index + needle.len() - 1].2;
+        matches.push(MatchRange {
+            start,
+            end,
+            rects: rects(
+                &runs,
+                &TextSelection {
+                    anchor: start,
+                    focus: end,
+                },
+            ),
+        });
+        index += needle.len();
+    }
+    matches
+}
+
+/// Highlight the matches over the rest of the page, the active match
+/// standing out from the others
+pub fn paint_matches(matches: &[MatchRange], active: Option<usize>) -> Option<DisplayCommand> {
+    let commands = matches
+        .iter()
+        .enumerate()
+        .flat_map(|(index, range)| {
+            let color = if Some(index) == active {
+                ACTIVE_MATCH_COLOR
+            } else {
+                MATCH_COLOR
+            };
+            range
+                .rects
+                .iter()
+                .map(move |rect| DrawCommand::FillRect(rect.clone(), color.clone()))
+        })
+        .collect::<Vec<_>>();
+    if commands.is_empty() {
+        return None;
+    }
+    Some(DisplayCommand::GroupDraw(commands))
+}
+
+fn fold_case(c: char) -> char {
+    c.to_lowercase().next().unwrap_or(c)
+}
@@ -0,0 +1,51 @@
+use crate::primitive::{Rect, Transform};
+use crate::utils::{box_transform, contains_paint, is_visible};
+use layout::layout_box::LayoutBox;
+
+/// Find the topmost box painted at a point of the page, taking the
+/// transforms of the boxes into account
+pub fn hit_test(layout_box: &LayoutBox, x: f32, y: f32) -> Option<&LayoutBox> {
+    hit_test_with_transform(layout_box, x, y, &Transform::identity())
+}
+
+fn hit_test_with_transform<'a>(
+    layout_box: &'a LayoutBox,
+    x: f32,
+    y: f32,
+    parent_transform: &Transform,
+) -> Option<&'a LayoutBox> {
+    let transform = parent_transform.multiply(&box_transform(layout_box));
+    // the point in the coordinates of the box before transformation
+    let local_point = transform.inverse().map(|inverse| inverse.map_point(x, y));
+
+    // the contents clipped by a box with paint containment aren't hit
+    // outside of its padding box
+    let is_clipped = contains_paint(layout_box)
+        && !local_point.map_or(false, |(x, y)| {
+            Rect::from(layout_box.dimensions.padding_box()).contains_point(x, y)
+        });
+
+    // boxes painted later are on top of the earlier ones
+    let children = if layout_box.contents_skipped || is_clipped {
+        &[]
+    } else {
+        layout_box.children.as_slice()
+    };
+    for child in children.iter().rev() {
+        if let Some(hit) = hit_test_with_transform(child, x, y, &transform) {
+            return Some(hit);
+        }
+    }
+
+    if !is_visible(layout_box) {
+        return None;
+    }
+
+    let (x, y) = local_point?;
+    let border_box: Rect = layout_box.dimensions.border_box().into();
+
+    if border_box.contains_point(x, y) {
+        return Some(layout_box);
+    }
+    None
+}
@@ -1,37 +1,170 @@
 mod command;
+mod culling;
+mod damage;
+mod find;
+mod hit_test;
 mod paint_functions;
 mod painter;
 mod primitive;
 mod render;
+mod selection;
 mod utils;
 
 use command::{DisplayCommand, DrawCommand};
 use layout::layout_box::LayoutBox;
 use render::PaintChainBuilder;
+use std::collections::BTreeMap;
 
+pub use culling::{cull, TileIndex, CULL_MARGIN};
+pub use damage::damaged_region;
+pub use find::{find, paint_matches, MatchRange};
+pub use hit_test::hit_test;
 pub use painter::Painter;
 pub use primitive::*;
 pub use render::DisplayList;
+pub use selection::{
+    paint_selection, selected_text, selection_rects, text_position_at, TextPosition, TextSelection,
+};
 
 use paint_functions::*;
 
-pub fn paint(display_list: DisplayList, painter: &mut dyn Painter) {
+pub fn paint(display_list: &DisplayList, painter: &mut dyn Painter) {
+    paint_with_transform(display_list, &Transform::identity(), painter);
+}
+
+/// Paint the display list with a transform applied on top of the
+/// transforms of the commands, e.g. to paint a part of the page
+/// scrolled into the painter
+pub fn paint_with_transform(
+    display_list: &DisplayList,
+    transform: &Transform,
+    painter: &mut dyn Painter,
+) {
+    painter.set_transform(transform.clone());
+    for command in display_list {
+        paint_command(command, transform, None, painter);
+    }
+}
+
+/// Apply a transform on top of the transforms of the commands of a
+/// display list, e.g. to move the page scrolled into the viewport
+pub fn transform_display_list(display_list: DisplayList, transform: &Transform) -> DisplayList {
+    if transform.is_identity() {
+        return display_list;
+    }
+    display_list
+        .into_iter()
+        .map(|command| DisplayCommand::Transformed(transform.clone(), Box::new(command)))
+        .collect()
+}
+
+/// Paint the commands of an indexed display list visible in an area of
+/// the page, with a transform placing the area in the painter
+pub fn paint_area(
+    index: &TileIndex,
+    area: &Rect,
+    transform: &Transform,
+    painter: &mut dyn Painter,
+) {
+    painter.set_transform(transform.clone());
+    for command in index.visible(area) {
+        paint_command(command, transform, None, painter);
+    }
+}
+
+/// Paint only the commands that intersect with a region
+pub fn paint_region(display_list: &DisplayList, region: &Rect, painter: &mut dyn Painter) {
     for command in display_list {
+        let is_damaged = damage::command_bounds(command)
+            .iter()
+            .any(|bounds| bounds.intersects(region));
+        if is_damaged {
+            paint_command(command, &Transform::identity(), None, painter);
+        }
+    }
+}
+
+fn paint_command(
+    command: &DisplayCommand,
+    transform: &Transform,
+    clip: Option<&Rect>,
+    painter: &mut dyn Painter,
+) {
+    match command {
+        DisplayCommand::Draw(draw_command) => draw(draw_command, painter),
+        DisplayCommand::GroupDraw(draw_commands) => {
+            for draw_command in draw_commands {
+                draw(draw_command, painter);
+            }
+        }
+        DisplayCommand::Transformed(command_transform, command) => {
+            let command_transform = transform.multiply(command_transform);
+            painter.set_transform(command_transform.clone());
+            paint_command(command, &command_transform, clip, painter);
+            painter.set_transform(transform.clone());
+        }
+        DisplayCommand::Clipped(rect, commands) => {
+            // the clips of the ancestors apply too
+            let bounds = transform.map_rect(rect);
+            let bounds = match clip {
+                Some(clip) => clip.intersection(&bounds),
+                None => bounds,
+            };
+            painter.set_clip(Some(bounds.clone()));
+            for command in commands {
+                paint_command(command, transform, Some(&bounds), painter);
+            }
+            painter.set_clip(clip.cloned());
+        }
+    }
+}
+
+fn draw(draw_command: &DrawCommand, painter: &mut dyn Painter) {
+    match draw_command {
+        DrawCommand::FillRect(rect, color) => painter.fill_rect(rect.clone(), color.clone()),
+        DrawCommand::FillRRect(rect, color) => painter.fill_rrect(rect.clone(), color.clone()),
+        DrawCommand::StrokeRRect(rect, color, width) => {
+            painter.stroke_rrect(rect.clone(), color.clone(), *width)
+        }
+        DrawCommand::DrawImage(rect, image) => painter.draw_image(rect.clone(), image),
+    }
+}
+
+/// The number of draw commands of each kind in a display list, e.g. to
+/// profile painting
+pub fn command_counts(display_list: &DisplayList) -> BTreeMap<&'static str, usize> {
+    fn count(command: &DisplayCommand, counts: &mut BTreeMap<&'static str, usize>) {
         match command {
-            DisplayCommand::Draw(draw_command) => draw(draw_command, painter),
+            DisplayCommand::Draw(draw_command) => {
+                *counts.entry(command_name(draw_command)).or_default() += 1;
+            }
             DisplayCommand::GroupDraw(draw_commands) => {
                 for draw_command in draw_commands {
-                    draw(draw_command, painter);
+                    *counts.entry(command_name(draw_command)).or_default() += 1;
+                }
+            }
+            DisplayCommand::Transformed(_, command) => count(command, counts),
+            DisplayCommand::Clipped(_, commands) => {
+                for command in commands {
+                    count(command, counts);
                 }
             }
         }
     }
+
+    let mut counts = BTreeMap::new();
+    for command in display_list {
+        count(command, &mut counts);
+    }
+    counts
 }
 
-fn draw(draw_command: DrawCommand, painter: &mut dyn Painter) {
+fn command_name(draw_command: &DrawCommand) -> &'static str {
     match draw_command {
-        DrawCommand::FillRect(rect, color) => painter.fill_rect(rect, color),
-        DrawCommand::FillRRect(rect, color) => painter.fill_rrect(rect, color),
+        DrawCommand::FillRect(..) => "fill_rect",
+        DrawCommand::FillRRect(..) => "fill_rrect",
+        DrawCommand::StrokeRRect(..) => "stroke_rrect",
+        DrawCommand::DrawImage(..) => "draw_image",
     }
 }
 
@@ -39,7 +172,42 @@ pub fn build_display_list(layout_box: &LayoutBox) -> DisplayList {
     let chain = PaintChainBuilder::new_chain()
         .with_function(&paint_border)
         .with_function(&paint_background)
+        .with_function(&paint_marker)
+        .with_function(&paint_text_decoration)
+        .with_function(&paint_form_control)
+        .with_function(&paint_image)
         .build();
 
     chain.paint(layout_box)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn count_commands() {
+        let red = Color {
+            r: 255,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        let fill = DrawCommand::FillRect(Rect::new(0., 0., 1., 1.), red);
+        let display_list = vec![
+            DisplayCommand::Draw(fill.clone()),
+            DisplayCommand::Transformed(
+                Transform::identity(),
+                Box::new(DisplayCommand::GroupDraw(vec![
+                    fill,
+                    DrawCommand::DrawImage(Rect::new(0., 0., 1., 1.), Image::new(0, 0, vec![])),
+                ])),
+            ),
+        ];
+
+        let counts = command_counts(&display_list);
+        assert_eq!(counts.get("fill_rect"), Some(&2));
+        assert_eq!(counts.get("draw_image"), Some(&1));
+        assert_eq!(counts.get("fill_rrect"), None);
+    }
+}
@@ -1,28 +1,33 @@
+use super::image::{document_image, document_url, rasterize};
 use crate::command::{DisplayCommand, DrawCommand};
 use crate::primitive::{Corners, RRect, Radii, Rect};
 use crate::LayoutBox;
 use crate::{primitive::style_color_to_paint_color, utils::is_zero};
-use style::value_processing::{Property, Value};
+use layout::replaced::{DEFAULT_HEIGHT, DEFAULT_WIDTH};
+use style::render_tree::RenderNode;
 use style::values::border_radius::BorderRadius;
 
 pub fn paint_background(layout_box: &LayoutBox) -> Option<DisplayCommand> {
     if let Some(render_node) = &layout_box.render_node {
         let render_node = render_node.borrow();
-        let background = render_node.get_style(&Property::BackgroundColor);
+        let style = render_node.style();
 
-        let border_top_left_radius = render_node.get_style(&Property::BorderTopLeftRadius);
-        let border_bottom_left_radius = render_node.get_style(&Property::BorderBottomLeftRadius);
-        let border_top_right_radius = render_node.get_style(&Property::BorderTopRightRadius);
-        let border_bottom_right_radius = render_node.get_style(&Property::BorderBottomRightRadius);
+        let border_top_left_radius = style.border_top_left_radius();
+        let border_bottom_left_radius = style.border_bottom_left_radius();
+        let border_top_right_radius = style.border_top_right_radius();
+        let border_bottom_right_radius = style.border_bottom_right_radius();
 
-        let color = style_color_to_paint_color(background.inner()).unwrap_or_default();
+        let color = style_color_to_paint_color(&style.background_color()).unwrap_or_default();
 
         let (x, y, width, height) = layout_box.dimensions.padding_box().into();
 
-        let has_no_border_radius = is_zero(border_top_left_radius.inner())
-            && is_zero(border_bottom_left_radius.inner())
-            && is_zero(border_top_right_radius.inner())
-            && is_zero(border_bottom_right_radius.inner());
+        let has_no_border_radius = is_zero(&border_top_left_radius)
+            && is_zero(&border_bottom_left_radius)
+            && is_zero(&border_top_right_radius)
+            && is_zero(&border_bottom_right_radius);
+
+        let padding_box = Rect::new(x, y, width, height);
+        let image_tiles = background_image_tiles(&render_node, &padding_box);
 
         if has_no_border_radius {
             let rect = Rect {
@@ -32,14 +37,15 @@ pub fn paint_background(layout_box: &LayoutBox) -> Option<DisplayCommand> {
                 height,
             };
 
-            return Some(DisplayCommand::Draw(DrawCommand::FillRect(rect, color)));
+            let command = DrawCommand::FillRect(rect, color);
+            return Some(with_image_tiles(command, image_tiles));
         } else {
             let border_box = layout_box.dimensions.border_box();
 
-            let tl = to_radii(border_top_left_radius.inner(), border_box.width);
-            let tr = to_radii(border_top_right_radius.inner(), border_box.width);
-            let bl = to_radii(border_bottom_left_radius.inner(), border_box.width);
-            let br = to_radii(border_bottom_right_radius.inner(), border_box.width);
+            let tl = to_radii(&border_top_left_radius, border_box.width);
+            let tr = to_radii(&border_top_right_radius, border_box.width);
+            let bl = to_radii(&border_bottom_left_radius, border_box.width);
+            let br = to_radii(&border_bottom_right_radius, border_box.width);
 
             let rect = RRect {
                 x,
@@ -49,15 +55,82 @@ pub fn paint_background(layout_box: &LayoutBox) -> Option<DisplayCommand> {
                 corners: Corners::new(tl, tr, bl, br),
             };
 
-            return Some(DisplayCommand::Draw(DrawCommand::FillRRect(rect, color)));
+            // TODO: clip the image to the rounded corners
+            let command = DrawCommand::FillRRect(rect, color);
+            return Some(with_image_tiles(command, image_tiles));
         }
     }
     None
 }
 
-fn to_radii(value: &Value, width: f32) -> Radii {
-    match value {
-        Value::BorderRadius(BorderRadius(hr, vr)) => Radii::new(hr.to_px(width), vr.to_px(width)),
-        _ => Radii::new(0.0, 0.0),
+fn with_image_tiles(command: DrawCommand, tiles: Vec<DrawCommand>) -> DisplayCommand {
+    if tiles.is_empty() {
+        return DisplayCommand::Draw(command);
+    }
+    let mut commands = vec![command];
+    commands.extend(tiles);
+    DisplayCommand::GroupDraw(commands)
+}
+
+/// The background image of a box repeated over its padding box from its
+/// top left corner, like the initial values of `background-repeat` &
+/// `background-position` do. The image is painted at its intrinsic
+/// size, missing dimensions following from its aspect ratio.
+fn background_image_tiles(render_node: &RenderNode, padding_box: &Rect) -> Vec<DrawCommand> {
+    let url = match render_node.style().background_image().url() {
+        Some(url) => url.to_string(),
+        None => return Vec::new(),
+    };
+    let image = document_url(&render_node.node, &url)
+        .and_then(|url| document_image(&render_node.node, &url));
+    let image = match image {
+        Some(image) => image,
+        None => return Vec::new(),
+    };
+
+    let ratio = image.aspect_ratio();
+    let (width, height) = match (image.intrinsic_width(), image.intrinsic_height(), ratio) {
+        (Some(width), Some(height), _) => (width, height),
+        (Some(width), None, Some(ratio)) => (width, width / ratio),
+        (None, Some(height), Some(ratio)) => (height * ratio, height),
+        // as large as possible inside the padding box
+        (None, None, Some(ratio)) => {
+            let width = padding_box.width.min(padding_box.height * ratio);
+            (width, width / ratio)
+        }
+        (width, height, _) => (
+            width.unwrap_or(DEFAULT_WIDTH),
+            height.unwrap_or(DEFAULT_HEIGHT),
+        ),
+    };
+    let tile = match rasterize(&image, width, height) {
+        Some(tile) => tile.with_key(&url),
+        None => return Vec::new(),
+    };
+
+    let mut tiles = Vec::new();
+    let mut y = 0;
+    while (y as f32) < padding_box.height {
+        let mut x = 0;
+        while (x as f32) < padding_box.width {
+            // the tiles at the edges are cut by the padding box
+            let visible_width = (padding_box.width - x as f32).ceil() as u32;
+            let visible_height = (padding_box.height - y as f32).ceil() as u32;
+            let image = tile.crop(0, 0, visible_width, visible_height);
+            let rect = Rect::new(
+                padding_box.x + x as f32,
+                padding_box.y + y as f32,
+                image.width as f32,
+                image.height as f32,
+            );
+            tiles.push(DrawCommand::DrawImage(rect, image));
+            x += tile.width;
+        }
+        y += tile.height;
     }
+    tiles
+}
+
+fn to_radii(BorderRadius(hr, vr): &BorderRadius, width: f32) -> Radii {
+    Radii::new(hr.to_px(width), vr.to_px(width))
 }
@@ -3,31 +3,20 @@ use crate::primitive::style_color_to_paint_color;
 use crate::primitive::Rect;
 use crate::LayoutBox;
 use layout::box_model::Edge;
-use style::value_processing::Property;
 
 pub fn paint_border(layout_box: &LayoutBox) -> Option<DisplayCommand> {
     if let Some(render_node) = &layout_box.render_node {
         let render_node = render_node.borrow();
+        let style = render_node.style();
 
-        let border_top_color = render_node
-            .get_style(&Property::BorderTopColor)
-            .map(style_color_to_paint_color)
-            .unwrap_or_default();
-
-        let border_bottom_color = render_node
-            .get_style(&Property::BorderBottomColor)
-            .map(style_color_to_paint_color)
-            .unwrap_or_default();
-
-        let border_left_color = render_node
-            .get_style(&Property::BorderLeftColor)
-            .map(style_color_to_paint_color)
-            .unwrap_or_default();
-
-        let border_right_color = render_node
-            .get_style(&Property::BorderRightColor)
-            .map(style_color_to_paint_color)
-            .unwrap_or_default();
+        let border_top_color =
+            style_color_to_paint_color(&style.border_top_color()).unwrap_or_default();
+        let border_bottom_color =
+            style_color_to_paint_color(&style.border_bottom_color()).unwrap_or_default();
+        let border_left_color =
+            style_color_to_paint_color(&style.border_left_color()).unwrap_or_default();
+        let border_right_color =
+            style_color_to_paint_color(&style.border_right_color()).unwrap_or_default();
 
         // TODO: support other border style other than solid
         let mut draw_commands = Vec::new();
@@ -0,0 +1,185 @@
+use crate::command::{DisplayCommand, DrawCommand};
+use crate::primitive::style_color_to_paint_color;
+use crate::primitive::{Color, Corners, RRect, Radii, Rect};
+use crate::selection::SELECTION_COLOR;
+use crate::LayoutBox;
+use dom::dom_ref::NodeRef;
+use dom::elements::{ElementData, HTMLInputElement, InputType};
+use layout::text::FontMetrics;
+
+const FRAME_COLOR: Color = Color {
+    r: 118,
+    g: 118,
+    b: 118,
+    a: 255,
+};
+
+const ACCENT_COLOR: Color = Color {
+    r: 0,
+    g: 117,
+    b: 255,
+    a: 255,
+};
+
+/// The mark of a checked checkbox, on top of the accent color
+const CHECK_COLOR: Color = Color {
+    r: 255,
+    g: 255,
+    b: 255,
+    a: 255,
+};
+
+/// Darkens a button while it's held down
+const PRESSED_COLOR: Color = Color {
+    r: 0,
+    g: 0,
+    b: 0,
+    a: 40,
+};
+
+/// Paint the widgets of the form controls: the frame of text inputs with
+/// their caret & selection when focused, checkboxes & radio buttons with
+/// their checked state, and the pressed state of buttons. The rest of a
+/// control, e.g. the border & background of a button, is painted from
+/// its styles like any other box.
+pub fn paint_form_control(layout_box: &LayoutBox) -> Option<DisplayCommand> {
+    let render_node = layout_box.render_node.as_ref()?;
+    let render_node = render_node.borrow();
+    let node = render_node.node.clone();
+    let color = style_color_to_paint_color(&render_node.style().color()).unwrap_or_default();
+
+    let dimensions = &layout_box.dimensions;
+    let (x, y, width, height) = dimensions.padding_box().into();
+    let padding_box = Rect::new(x, y, width, height);
+    let (x, y, width, height) = dimensions.content_box().into();
+    let content_box = Rect::new(x, y, width, height);
+
+    let node_ref = node.borrow();
+    let commands = match node_ref.as_element_opt()?.data() {
+        ElementData::Input(input) => match input.input_type() {
+            InputType::Text => {
+                let mut commands = vec![DrawCommand::StrokeRRect(
+                    rounded(&padding_box, 2.),
+                    FRAME_COLOR,
+                    1.,
+                )];
+                if is_focused(&node) {
+                    let (selection, caret) = {
+                        let metrics = FontMetrics::new(render_node.style().font_size().to_px());
+                        text_input_rects(&content_box, input, &metrics)
+                    };
+                    if let Some(selection) = selection {
+                        commands.push(DrawCommand::FillRect(selection, SELECTION_COLOR));
+                    }
+                    commands.push(DrawCommand::FillRect(caret, color));
+                }
+                commands
+            }
+            InputType::Checkbox => {
+                let frame = rounded(&content_box, 2.);
+                if input.checked() {
+                    vec![
+                        DrawCommand::FillRRect(frame, ACCENT_COLOR),
+                        DrawCommand::FillRect(inset(&content_box, 3.), CHECK_COLOR),
+                    ]
+                } else {
+                    vec![DrawCommand::StrokeRRect(frame, FRAME_COLOR, 1.)]
+                }
+            }
+            InputType::Radio => {
+                let radius = content_box.width / 2.;
+                let frame = rounded(&content_box, radius);
+                if input.checked() {
+                    let dot = inset(&content_box, 3.);
+                    let dot_radius = dot.width / 2.;
+                    vec![
+                        DrawCommand::StrokeRRect(frame, ACCENT_COLOR, 1.),
+                        DrawCommand::FillRRect(rounded(&dot, dot_radius), ACCENT_COLOR),
+                    ]
+                } else {
+                    vec![DrawCommand::StrokeRRect(frame, FRAME_COLOR, 1.)]
+                }
+            }
+        },
+        ElementData::Button(button) if button.pressed() => {
+            vec![DrawCommand::FillRect(padding_box, PRESSED_COLOR)]
+        }
+        _ => return None,
+    };
+
+    Some(DisplayCommand::GroupDraw(commands))
+}
+
+fn is_focused(node: &NodeRef) -> bool {
+    let document = node.borrow().owner_document();
+    match document {
+        Some(document) => document.borrow().as_document().focused_element() == Some(node.clone()),
+        None => false,
+    }
+}
+
+/// The selection highlight & the caret of a text input. The value is
+/// measured from the start of the content box.
+fn text_input_rects(
+    content_box: &Rect,
+    input: &HTMLInputElement,
+    metrics: &FontMetrics,
+) -> (Option<Rect>, Rect) {
+    let offset = |index: usize| {
+        let prefix = input.value().chars().take(index).collect::<String>();
+        content_box.x + metrics.measure(&prefix)
+    };
+
+    let selection = input.selection().map(|(start, end)| {
+        let start = offset(start);
+        Rect::new(
+            start,
+            content_box.y,
+            offset(end) - start,
+            content_box.height,
+        )
+    });
+    let caret = Rect::new(offset(input.caret()), content_box.y, 1., content_box.height);
+    (selection, caret)
+}
+
+fn rounded(rect: &Rect, radius: f32) -> RRect {
+    let radii = Radii::new(radius, radius);
+    RRect::new(
+        rect.x,
+        rect.y,
+        rect.width,
+        rect.height,
+        Corners::new(radii.clone(), radii.clone(), radii.clone(), radii),
+    )
+}
+
+fn inset(rect: &Rect, amount: f32) -> Rect {
+    Rect::new(
+        rect.x + amount,
+        rect.y + amount,
+        (rect.width - amount * 2.).max(0.),
+        (rect.height - amount * 2.).max(0.),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn caret_and_selection() {
+        let mut input = HTMLInputElement::empty();
+        input.set_value("abcd");
+        let content_box = Rect::new(10., 5., 100., 20.);
+
+        let (selection, caret) = text_input_rects(&content_box, &input, &FontMetrics::default());
+        assert_eq!(selection, None);
+        assert_eq!(caret, Rect::new(42., 5., 1., 20.));
+
+        input.select_all();
+        let (selection, caret) = text_input_rects(&content_box, &input, &FontMetrics::default());
+        assert_eq!(selection, Some(Rect::new(10., 5., 32., 20.)));
+        assert_eq!(caret.x, 42.);
+    }
+}
@@ -0,0 +1,67 @@
+use crate::command::{DisplayCommand, DrawCommand};
+use crate::primitive::{Image, Rect};
+use crate::LayoutBox;
+use dom::dom_ref::NodeRef;
+use dom::elements::ElementData;
+use dom::images::Image as DocumentImage;
+use std::rc::Rc;
+use url::Url;
+
+/// Paint the loaded image of an `<img>` element, the inline SVG image of
+/// an `<svg>` element or the bitmap of a `<canvas>` element over its
+/// content box. Images that aren't loaded yet or failed to load aren't
+/// painted.
+pub fn paint_image(layout_box: &LayoutBox) -> Option<DisplayCommand> {
+    let render_node = layout_box.render_node.as_ref()?;
+    let node = render_node.borrow().node.clone();
+
+    let (x, y, width, height) = layout_box.dimensions.content_box().into();
+    let rect = Rect::new(x, y, width, height);
+
+    let image = match node.borrow().as_element_opt()?.data() {
+        ElementData::Image(element) => {
+            let url = element.src()?;
+            let image = document_image(&node, url)?;
+            rasterize(&image, width, height)?.with_key(url.raw())
+        }
+        ElementData::Svg(_) => {
+            rasterize(&DocumentImage::from_svg_element(&node).ok()?, width, height)?
+        }
+        // the bitmap is stretched over the box, like an image
+        ElementData::Canvas(canvas) => {
+            let context = canvas.context_2d();
+            if context.width() == 0 || context.height() == 0 {
+                return None;
+            }
+            Image::new(context.width(), context.height(), context.pixels().to_vec())
+        }
+        _ => return None,
+    };
+    Some(DisplayCommand::Draw(DrawCommand::DrawImage(rect, image)))
+}
+
+/// A URL of a style resolved against the base URL of the document of a
+/// node
+pub fn document_url(node: &NodeRef, url: &str) -> Option<Url> {
+    let document = node.borrow().owner_document()?;
+    let document = document.borrow();
+    document.as_document().parse_url(url)
+}
+
+/// The image loaded from a URL by the document of a node
+pub fn document_image(node: &NodeRef, url: &Url) -> Option<Rc<DocumentImage>> {
+    let document = node.borrow().owner_document()?;
+    let document = document.borrow();
+    document.as_document().image(url.raw())
+}
+
+/// The pixels of an image painted at a size, one pixel per CSS pixel
+pub fn rasterize(image: &DocumentImage, width: f32, height: f32) -> Option<Image> {
+    let (width, height) = (width.round(), height.round());
+    if width < 1. || height < 1. {
+        return None;
+    }
+    let (width, height) = (width as u32, height as u32);
+    let pixels = image.rasterize(width, height);
+    Some(Image::new(width, height, pixels.to_vec()))
+}
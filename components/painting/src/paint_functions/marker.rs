@@ -0,0 +1,38 @@
+use crate::command::{DisplayCommand, DrawCommand};
+use crate::primitive::style_color_to_paint_color;
+use crate::primitive::{Corners, RRect, Radii, Rect};
+use crate::LayoutBox;
+use layout::list_marker::MarkerContent;
+
+pub fn paint_marker(layout_box: &LayoutBox) -> Option<DisplayCommand> {
+    let marker = layout_box.marker.as_ref()?;
+    let render_node = layout_box.render_node.as_ref()?;
+
+    let color =
+        style_color_to_paint_color(&render_node.borrow().style().color()).unwrap_or_default();
+
+    let (x, y, width, height) = marker.glyph_rect().into();
+    let radius = width / 2.;
+    let circle = RRect::new(
+        x,
+        y,
+        width,
+        height,
+        Corners::new(
+            Radii::new(radius, radius),
+            Radii::new(radius, radius),
+            Radii::new(radius, radius),
+            Radii::new(radius, radius),
+        ),
+    );
+
+    let command = match &marker.content {
+        MarkerContent::Disc => DrawCommand::FillRRect(circle, color),
+        MarkerContent::Circle => DrawCommand::StrokeRRect(circle, color, 1.),
+        MarkerContent::Square => DrawCommand::FillRect(Rect::new(x, y, width, height), color),
+        // TODO: paint the counter text when we support text
+        MarkerContent::Text(_) => return None,
+    };
+
+    Some(DisplayCommand::Draw(command))
+}
@@ -1,5 +1,13 @@
 mod background;
 mod border;
+mod form_control;
+mod image;
+mod marker;
+mod text_decoration;
 
 pub use background::paint_background;
 pub use border::paint_border;
+pub use form_control::paint_form_control;
+pub use image::paint_image;
+pub use marker::paint_marker;
+pub use text_decoration::paint_text_decoration;
@@ -0,0 +1,187 @@
+use crate::command::{DisplayCommand, DrawCommand};
+use crate::primitive::style_color_to_paint_color;
+use crate::primitive::{Color, Rect};
+use crate::LayoutBox;
+use layout::text;
+use style::render_tree::{RenderNode, RenderNodeRef};
+use style::values::display::{InnerDisplayType, OuterDisplayType};
+use style::values::float::Float;
+use style::values::position::Position;
+use style::values::text_decoration_line::TextDecorationLine;
+use style::values::text_decoration_style::TextDecorationStyle;
+
+/// A decoration applied to the text of a box, either specified on the
+/// box or propagated from one of its ancestors
+struct Decoration {
+    line: TextDecorationLine,
+    style: TextDecorationStyle,
+    color: Color,
+}
+
+pub fn paint_text_decoration(layout_box: &LayoutBox) -> Option<DisplayCommand> {
+    if !layout_box.is_text() {
+        return None;
+    }
+    let render_node = layout_box.render_node.as_ref()?;
+    let decorations = applied_decorations(render_node);
+    if decorations.is_empty() {
+        return None;
+    }
+
+    let metrics = text::font_metrics(render_node);
+    let thickness = metrics.decoration_thickness();
+
+    let mut commands = Vec::new();
+    for fragment in &layout_box.text_fragments {
+        let rect = &fragment.rect;
+        let baseline = rect.y + metrics.baseline(rect.height);
+
+        for decoration in &decorations {
+            let mut lines = Vec::new();
+            if decoration.line.underline {
+                lines.push(baseline + metrics.underline_offset());
+            }
+            if decoration.line.overline {
+                lines.push(baseline - metrics.ascent());
+            }
+            if decoration.line.line_through {
+                lines.push(baseline - metrics.strikeout_offset() - thickness / 2.);
+            }
+
+            for y in lines {
+                let line = Rect::new(rect.x, y, rect.width, thickness);
+                commands.extend(
+                    decoration_rects(&line, &decoration.style)
+                        .into_iter()
+                        .map(|rect| DrawCommand::FillRect(rect, decoration.color.clone())),
+                );
+            }
+        }
+    }
+
+    if commands.is_empty() {
+        return None;
+    }
+    Some(DisplayCommand::GroupDraw(commands))
+}
+
+/// Decorations are propagated to the in-flow inline content of the
+/// decorating box, but not into atomic inlines, floats and absolutely
+/// positioned descendants.
+/// https://www.w3.org/TR/css-text-decor-3/#line-decoration
+fn applied_decorations(render_node: &RenderNodeRef) -> Vec<Decoration> {
+    let mut result = Vec::new();
+    let mut current = Some(render_node.clone());
+
+    while let Some(node) = current {
+        let node = node.borrow();
+        let style = node.style();
+
+        let line = style.text_decoration_line();
+        if !line.is_none() {
+            result.push(Decoration {
+                line,
+                style: style.text_decoration_style(),
+                color: style_color_to_paint_color(&style.text_decoration_color())
+                    .unwrap_or_default(),
+            });
+        }
+
+        if stops_propagation(&node) {
+            break;
+        }
+        current = node
+            .parent_render_node
+            .as_ref()
+            .and_then(|parent| parent.upgrade());
+    }
+    result
+}
+
+fn stops_propagation(node: &RenderNode) -> bool {
+    let style = node.style();
+    let display = style.display();
+    let is_atomic_inline = display.outside() == Some(OuterDisplayType::Inline)
+        && display.inside() != Some(InnerDisplayType::Flow);
+    let is_float = style.float() != Float::None;
+    let is_absolutely_positioned = match style.position() {
+        Position::Absolute | Position::Fixed => true,
+        _ => false,
+    };
+    is_atomic_inline || is_float || is_absolutely_positioned
+}
+
+/// The rectangles painted for a decoration line of a style. The height
+/// of the line is the thickness of the decoration.
+fn decoration_rects(line: &Rect, style: &TextDecorationStyle) -> Vec<Rect> {
+    let thickness = line.height;
+    let end = line.x + line.width;
+
+    // segments of the line repeated along the line
+    let segments = |length: f32, gap: f32, offset: &dyn Fn(usize) -> f32| {
+        let mut result = Vec::new();
+        let mut x = line.x;
+        let mut index = 0;
+        while x < end {
+            let width = length.min(end - x);
+            result.push(Rect::new(x, line.y + offset(index), width, thickness));
+            x += length + gap;
+            index += 1;
+        }
+        result
+    };
+
+    match style {
+        TextDecorationStyle::Solid => vec![line.clone()],
+        TextDecorationStyle::Double => vec![
+            line.clone(),
+            Rect::new(line.x, line.y + thickness * 2., line.width, thickness),
+        ],
+        TextDecorationStyle::Dotted => segments(thickness, thickness, &|_| 0.),
+        TextDecorationStyle::Dashed => segments(thickness * 3., thickness * 3., &|_| 0.),
+        // TODO: paint a smooth wave when we can draw paths
+        TextDecorationStyle::Wavy => segments(thickness * 2., 0., &|index| {
+            if index % 2 == 0 {
+                -thickness / 2.
+            } else {
+                thickness / 2.
+            }
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decoration_line_styles() {
+        let line = Rect::new(0., 10., 10., 1.);
+
+        assert_eq!(
+            decoration_rects(&line, &TextDecorationStyle::Solid),
+            vec![line.clone()]
+        );
+        assert_eq!(
+            decoration_rects(&line, &TextDecorationStyle::Double),
+            vec![line.clone(), Rect::new(0., 12., 10., 1.)]
+        );
+
+        let dots = decoration_rects(&line, &TextDecorationStyle::Dotted);
+        assert_eq!(dots.len(), 5);
+        assert_eq!(dots[1], Rect::new(2., 10., 1., 1.));
+
+        // the last dash is clipped at the end of the line
+        let line = Rect::new(0., 10., 8., 1.);
+        let dashes = decoration_rects(&line, &TextDecorationStyle::Dashed);
+        assert_eq!(
+            dashes,
+            vec![Rect::new(0., 10., 3., 1.), Rect::new(6., 10., 2., 1.)]
+        );
+
+        let waves = decoration_rects(&line, &TextDecorationStyle::Wavy);
+        assert_eq!(waves.len(), 4);
+        assert_eq!(waves[0].y, 9.5);
+        assert_eq!(waves[1].y, 10.5);
+    }
+}
@@ -1,6 +1,42 @@
-use super::primitive::{Color, RRect, Rect};
+use super::primitive::{Color, Image, RRect, Rect, Transform};
 
 pub trait Painter {
     fn fill_rect(&mut self, rect: Rect, color: Color);
     fn fill_rrect(&mut self, rect: RRect, color: Color);
+    fn stroke_rrect(&mut self, rect: RRect, color: Color, width: f32);
+    /// Set the transform applied to the shapes filled after this call
+    fn set_transform(&mut self, transform: Transform);
+    /// Only paint the shapes filled after this call inside a rect of the
+    /// frame, whatever the transform, or everywhere without a rect
+    fn set_clip(&mut self, clip: Option<Rect>);
+
+    /// Paint an image stretched over a rect. Painters without textures
+    /// fill the runs of pixels of the same color of each row.
+    fn draw_image(&mut self, rect: Rect, image: &Image) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+        let pixel_width = rect.width / image.width as f32;
+        let pixel_height = rect.height / image.height as f32;
+        for y in 0..image.height {
+            let mut x = 0;
+            while x < image.width {
+                let color = image.pixel(x, y);
+                let start = x;
+                while x < image.width && image.pixel(x, y) == color {
+                    x += 1;
+                }
+                if color.a == 0 {
+                    continue;
+                }
+                let run = Rect::new(
+                    rect.x + start as f32 * pixel_width,
+                    rect.y + y as f32 * pixel_height,
+                    (x - start) as f32 * pixel_width,
+                    pixel_height,
+                );
+                self.fill_rect(run, color);
+            }
+        }
+    }
 }
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
-use style::value_processing::Value;
+use style::values::color::Color as StyleColor;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Color {
     pub r: u8,
     pub g: u8,
@@ -20,14 +20,9 @@ impl Default for Color {
     }
 }
 
-pub fn style_color_to_paint_color(style_color: &Value) -> Option<Color> {
-    let color = match style_color {
-        Value::Color(c) => c,
-        _ => return None,
-    };
-
-    match color {
-        style::values::color::Color::Rgba(r, g, b, a) => {
+pub fn style_color_to_paint_color(style_color: &StyleColor) -> Option<Color> {
+    match style_color {
+        StyleColor::Rgba(r, g, b, a) => {
             let alpha: u8 = a.as_u8();
             Some(Color {
                 r: r.as_u8(),
@@ -0,0 +1,59 @@
+use super::Color;
+use serde::{Deserialize, Serialize};
+
+/// The pixels of an image, as RGBA bytes row by row
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Image {
+    pub width: u32,
+    pub height: u32,
+    pub pixels: Vec<u8>,
+    /// The resource the pixels are decoded from, e.g. its URL. Painters
+    /// keep the images of the same resource & size across paints.
+    #[serde(default)]
+    pub key: Option<String>,
+}
+
+impl Image {
+    pub fn new(width: u32, height: u32, pixels: Vec<u8>) -> Self {
+        Self {
+            width,
+            height,
+            pixels,
+            key: None,
+        }
+    }
+
+    pub fn with_key(mut self, key: &str) -> Self {
+        self.key = Some(key.to_string());
+        self
+    }
+
+    pub fn pixel(&self, x: u32, y: u32) -> Color {
+        let index = ((y * self.width + x) * 4) as usize;
+        match self.pixels.get(index..index + 4) {
+            Some([r, g, b, a]) => Color {
+                r: *r,
+                g: *g,
+                b: *b,
+                a: *a,
+            },
+            _ => Color::default(),
+        }
+    }
+
+    /// The part of the image in a rectangle of pixels
+    pub fn crop(&self, x: u32, y: u32, width: u32, height: u32) -> Image {
+        let width = width.min(self.width.saturating_sub(x));
+        let height = height.min(self.height.saturating_sub(y));
+        let mut pixels = Vec::with_capacity((width * height * 4) as usize);
+        for row in y..y + height {
+            let start = ((row * self.width + x) * 4) as usize;
+            pixels.extend_from_slice(&self.pixels[start..start + (width * 4) as usize]);
+        }
+        // the parts at different offsets of an image are different images
+        Image {
+            key: self.key.as_ref().map(|key| format!("{}#{},{}", key, x, y)),
+            ..Image::new(width, height, pixels)
+        }
+    }
+}
@@ -1,7 +1,11 @@
 mod color;
+mod image;
 mod rect;
 mod rrect;
+mod transform;
 
 pub use color::*;
+pub use image::*;
 pub use rect::*;
 pub use rrect::*;
+pub use transform::*;
@@ -1,6 +1,6 @@
 use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct RRect {
     pub x: f32,
     pub y: f32,
@@ -9,7 +9,7 @@ pub struct RRect {
     pub corners: Corners,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Corners {
     pub top_left: Radii,
     pub top_right: Radii,
@@ -17,7 +17,7 @@ pub struct Corners {
     pub bottom_right: Radii,
 }
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
 pub struct Radii(f32, f32);
 
 impl RRect {
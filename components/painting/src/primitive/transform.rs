@@ -0,0 +1,196 @@
+use super::Rect;
+use serde::{Deserialize, Serialize};
+use style::values::transform::{Transform as StyleTransform, TransformFunction};
+
+/// 2D affine transformation matrix
+///
+/// | a c e |
+/// | b d f |
+/// | 0 0 1 |
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Default for Transform {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Transform {
+    pub fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub fn identity() -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, 0.0, 0.0)
+    }
+
+    pub fn translate(x: f32, y: f32) -> Self {
+        Self::new(1.0, 0.0, 0.0, 1.0, x, y)
+    }
+
+    pub fn scale(x: f32, y: f32) -> Self {
+        Self::new(x, 0.0, 0.0, y, 0.0, 0.0)
+    }
+
+    /// Clockwise rotation by an angle in degrees
+    pub fn rotate(degrees: f32) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Self::new(cos, sin, -sin, cos, 0.0, 0.0)
+    }
+
+    /// Skew by angles along the x & y axis in degrees
+    pub fn skew(x_degrees: f32, y_degrees: f32) -> Self {
+        Self::new(
+            1.0,
+            y_degrees.to_radians().tan(),
+            x_degrees.to_radians().tan(),
+            1.0,
+            0.0,
+            0.0,
+        )
+    }
+
+    pub fn is_identity(&self) -> bool {
+        *self == Self::identity()
+    }
+
+    /// The transform applying `other` first, then this transform
+    pub fn multiply(&self, other: &Transform) -> Transform {
+        Transform {
+            a: self.a * other.a + self.c * other.b,
+            b: self.b * other.a + self.d * other.b,
+            c: self.a * other.c + self.c * other.d,
+            d: self.b * other.c + self.d * other.d,
+            e: self.a * other.e + self.c * other.f + self.e,
+            f: self.b * other.e + self.d * other.f + self.f,
+        }
+    }
+
+    /// The inverse transform, or `None` if the transform isn't invertible,
+    /// e.g. `scale(0)`
+    pub fn inverse(&self) -> Option<Transform> {
+        let determinant = self.a * self.d - self.b * self.c;
+        if determinant == 0.0 || !determinant.is_finite() {
+            return None;
+        }
+
+        Some(Transform {
+            a: self.d / determinant,
+            b: -self.b / determinant,
+            c: -self.c / determinant,
+            d: self.a / determinant,
+            e: (self.c * self.f - self.d * self.e) / determinant,
+            f: (self.b * self.e - self.a * self.f) / determinant,
+        })
+    }
+
+    pub fn map_point(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// The bounding box of a transformed rect
+    pub fn map_rect(&self, rect: &Rect) -> Rect {
+        if self.is_identity() {
+            return rect.clone();
+        }
+
+        let corners = [
+            self.map_point(rect.x, rect.y),
+            self.map_point(rect.x + rect.width, rect.y),
+            self.map_point(rect.x + rect.width, rect.y + rect.height),
+            self.map_point(rect.x, rect.y + rect.height),
+        ];
+
+        let min_x = corners.iter().map(|c| c.0).fold(f32::INFINITY, f32::min);
+        let min_y = corners.iter().map(|c| c.1).fold(f32::INFINITY, f32::min);
+        let max_x = corners
+            .iter()
+            .map(|c| c.0)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let max_y = corners
+            .iter()
+            .map(|c| c.1)
+            .fold(f32::NEG_INFINITY, f32::max);
+
+        Rect::new(min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+}
+
+/// Convert the `transform` style of a box into a matrix. The transform
+/// origin is the center of the box border box.
+pub fn style_transform_to_paint_transform(
+    style_transform: &StyleTransform,
+    border_box: &Rect,
+) -> Transform {
+    let functions = match style_transform {
+        StyleTransform::Functions(functions) => functions,
+        _ => return Transform::identity(),
+    };
+
+    let origin_x = border_box.x + border_box.width / 2.0;
+    let origin_y = border_box.y + border_box.height / 2.0;
+
+    let matrix = functions
+        .iter()
+        .map(|function| match function {
+            TransformFunction::Matrix([a, b, c, d, e, f]) => {
+                Transform::new(**a, **b, **c, **d, **e, **f)
+            }
+            TransformFunction::Translate(x, y) => {
+                Transform::translate(x.to_px(border_box.width), y.to_px(border_box.height))
+            }
+            TransformFunction::Scale(x, y) => Transform::scale(**x, **y),
+            TransformFunction::Rotate(angle) => Transform::rotate(**angle),
+            TransformFunction::Skew(x, y) => Transform::skew(**x, **y),
+        })
+        .fold(Transform::identity(), |matrix, function| {
+            matrix.multiply(&function)
+        });
+
+    Transform::translate(origin_x, origin_y)
+        .multiply(&matrix)
+        .multiply(&Transform::translate(-origin_x, -origin_y))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_point_eq(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn transform_around_origin() {
+        let transform = Transform::translate(10.0, 10.0)
+            .multiply(&Transform::rotate(90.0))
+            .multiply(&Transform::translate(-10.0, -10.0));
+
+        assert_point_eq(transform.map_point(20.0, 10.0), (10.0, 20.0));
+
+        let inverse = transform.inverse().unwrap();
+        assert_point_eq(inverse.map_point(10.0, 20.0), (20.0, 10.0));
+
+        let bounds = transform.map_rect(&Rect::new(0.0, 5.0, 20.0, 10.0));
+        assert_point_eq((bounds.x, bounds.y), (5.0, 0.0));
+        assert_point_eq((bounds.width, bounds.height), (10.0, 20.0));
+
+        assert!(Transform::scale(0.0, 1.0).inverse().is_none());
+    }
+}
@@ -1,4 +1,6 @@
 use crate::command::DisplayCommand;
+use crate::primitive::Transform;
+use crate::utils::{box_transform, contains_paint, is_visible};
 use layout::layout_box::LayoutBox;
 
 pub type PaintFn = dyn Fn(&LayoutBox) -> Option<DisplayCommand>;
@@ -13,18 +15,63 @@ pub struct PaintChainBuilder<'a> {
 impl<'a> PaintChain<'a> {
     pub fn paint(&self, layout_box: &LayoutBox) -> DisplayList {
         let mut result = Vec::new();
+        self.paint_with_transform(layout_box, &Transform::identity(), &mut result);
+        result
+    }
+
+    /// Paint a box & its descendants, attaching the transform of the box
+    /// and its ancestors to each command
+    fn paint_with_transform(
+        &self,
+        layout_box: &LayoutBox,
+        parent_transform: &Transform,
+        result: &mut DisplayList,
+    ) {
+        let transform = parent_transform.multiply(&box_transform(layout_box));
+
+        // descendants of an invisible box can still be visible
+        let paint_fns: &[&PaintFn] = if is_visible(layout_box) { &self.0 } else { &[] };
 
-        for paint_fn in &self.0 {
+        for paint_fn in paint_fns {
             if let Some(command) = paint_fn(layout_box) {
+                if transform.is_identity() {
+                    result.push(command);
+                } else {
+                    result.push(DisplayCommand::Transformed(
+                        transform.clone(),
+                        Box::new(command),
+                    ));
+                }
+            }
+        }
+
+        // the children skipped by the layout have no position
+        if layout_box.contents_skipped {
+            return;
+        }
+
+        if contains_paint(layout_box) {
+            // the contents are clipped in the coordinates of the box
+            let mut contents = Vec::new();
+            for child in &layout_box.children {
+                self.paint_with_transform(child, &Transform::identity(), &mut contents);
+            }
+            if contents.is_empty() {
+                return;
+            }
+            let clip = layout_box.dimensions.padding_box().into();
+            let command = DisplayCommand::Clipped(clip, contents);
+            if transform.is_identity() {
                 result.push(command);
+            } else {
+                result.push(DisplayCommand::Transformed(transform, Box::new(command)));
             }
+            return;
         }
 
         for child in &layout_box.children {
-            result.extend(self.paint(child));
+            self.paint_with_transform(child, &transform, result);
         }
-
-        result
     }
 }
 
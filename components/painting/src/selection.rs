@@ -0,0 +1,302 @@
+/// This module contains the selection of the text of the page with
+/// the mouse: finding the text under the mouse, the highlight painted
+/// over the selected text & the selected string.
+use crate::command::{DisplayCommand, DrawCommand};
+use crate::primitive::{Color, Rect};
+use crate::utils::is_visible;
+use layout::box_model::Rect as LayoutRect;
+use layout::layout_box::LayoutBox;
+use layout::text::{font_metrics, white_space, FontMetrics, TextFragment};
+
+pub(crate) const SELECTION_COLOR: Color = Color {
+    r: 0,
+    g: 117,
+    b: 255,
+    a: 80,
+};
+
+/// A boundary between two characters of the text of the page: the index
+/// of a text fragment in tree order & the number of characters of the
+/// fragment before the boundary. The positions only hold for the layout
+/// they were found in.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub struct TextPosition {
+    pub fragment: usize,
+    pub offset: usize,
+}
+
+/// The text between the position the selection started at, e.g. where the
+/// mouse was pressed, and the position it was extended to
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct TextSelection {
+    pub anchor: TextPosition,
+    pub focus: TextPosition,
+}
+
+impl TextSelection {
+    pub fn collapsed(position: TextPosition) -> Self {
+        Self {
+            anchor: position,
+            focus: position,
+        }
+    }
+
+    pub fn is_collapsed(&self) -> bool {
+        self.anchor == self.focus
+    }
+
+    /// The start & the end of the selection in tree order
+    fn range(&self) -> (TextPosition, TextPosition) {
+        if self.anchor <= self.focus {
+            (self.anchor, self.focus)
+        } else {
+            (self.focus, self.anchor)
+        }
+    }
+}
+
+/// A text fragment with the block container it's laid out in, whose
+/// lines are separated by newlines in the selected text
+pub(crate) struct TextRun<'a> {
+    pub fragment: &'a TextFragment,
+    block: *const LayoutBox,
+    preserves_newlines: bool,
+    metrics: FontMetrics,
+}
+
+/// The position of the text closest to a point of the page. The point
+/// is placed in the fragment of its line closest to it; below the end of
+/// a line it's at the end of the last line above it.
+pub fn text_position_at(root: &LayoutBox, x: f32, y: f32) -> Option<TextPosition> {
+    let runs = text_runs(root);
+    let distance = |rect: &LayoutRect| {
+        if x < rect.x {
+            rect.x - x
+        } else {
+            (x - rect.x - rect.width).max(0.)
+        }
+    };
+
+    let on_line = runs
+        .iter()
+        .enumerate()
+        .filter(|(_, run)| {
+            y >= run.fragment.rect.y && y < run.fragment.rect.y + run.fragment.rect.height
+        })
+        .min_by(|(_, a), (_, b)| {
+            distance(&a.fragment.rect)
+                .partial_cmp(&distance(&b.fragment.rect))
+                .unwrap()
+        });
+    if let Some((index, run)) = on_line {
+        return Some(TextPosition {
+            fragment: index,
+            offset: offset_at(run.fragment, &run.metrics, x),
+        });
+    }
+
+    let above = runs
+        .iter()
+        .rposition(|run| run.fragment.rect.y + run.fragment.rect.height <= y);
+    match above {
+        Some(index) => Some(TextPosition {
+            fragment: index,
+            offset: runs[index].fragment.text.chars().count(),
+        }),
+        None if !runs.is_empty() => Some(TextPosition {
+            fragment: 0,
+            offset: 0,
+        }),
+        None => None,
+    }
+}
+
+/// The rectangles of the selected text, one per fragment
+pub fn selection_rects(root: &LayoutBox, selection: &TextSelection) -> Vec<Rect> {
+    rects(&text_runs(root), selection)
+}
+
+pub(crate) fn rects(runs: &[TextRun], selection: &TextSelection) -> Vec<Rect> {
+    selected_parts(runs, selection)
+        .into_iter()
+        .filter(|(_, start, end)| start < end)
+        .map(|(run, start, end)| {
+            let rect = &run.fragment.rect;
+            let left = offset_x(run.fragment, &run.metrics, start);
+            Rect::new(
+                left,
+                rect.y,
+                offset_x(run.fragment, &run.metrics, end) - left,
+                rect.height,
+            )
+        })
+        .collect()
+}
+
+/// The selected text. The lines of a block are joined by spaces, unless
+/// the block preserves its newlines, and blocks are separated by newlines.
+pub fn selected_text(root: &LayoutBox, selection: &TextSelection) -> String {
+    let runs = text_runs(root);
+    let mut result = String::new();
+    let mut previous: Option<&TextRun> = None;
+    for (run, start, end) in selected_parts(&runs, selection) {
+        match previous.and_then(|previous| separator(previous, run)) {
+            Some(' ') if result.ends_with(' ') => {}
+            Some(separator) => result.push(separator),
+            None => {}
+        }
+        result.extend(run.fragment.text.chars().skip(start).take(end - start));
+        previous = Some(run);
+    }
+    result
+}
+
+/// Highlight the selected text, over the rest of the page
+pub fn paint_selection(root: &LayoutBox, selection: &TextSelection) -> Option<DisplayCommand> {
+    let rects = selection_rects(root, selection);
+    if rects.is_empty() {
+        return None;
+    }
+    Some(DisplayCommand::GroupDraw(
+        rects
+            .into_iter()
+            .map(|rect| DrawCommand::FillRect(rect, SELECTION_COLOR))
+            .collect(),
+    ))
+}
+
+/// The character between the texts of two consecutive fragments: a
+/// newline between blocks & between the lines of a block preserving its
+/// newlines, otherwise a space between lines. A fragment starting below
+/// the previous one is on the next line, the fragments of a line can be
+/// shifted up & down by their vertical alignment.
+pub(crate) fn separator(previous: &TextRun, run: &TextRun) -> Option<char> {
+    let previous_rect = &previous.fragment.rect;
+    if previous.block != run.block {
+        Some('\n')
+    } else if run.fragment.rect.y >= previous_rect.y + previous_rect.height {
+        Some(if run.preserves_newlines { '\n' } else { ' ' })
+    } else {
+        None
+    }
+}
+
+/// The selected characters of each selected fragment, as a range of
+/// character indices
+fn selected_parts<'a, 'b>(
+    runs: &'b [TextRun<'a>],
+    selection: &TextSelection,
+) -> Vec<(&'b TextRun<'a>, usize, usize)> {
+    let (start, end) = selection.range();
+    runs.iter()
+        .enumerate()
+        .skip(start.fragment)
+        .take((end.fragment + 1).saturating_sub(start.fragment))
+        .map(|(index, run)| {
+            let length = run.fragment.text.chars().count();
+            let from = if index == start.fragment {
+                start.offset
+            } else {
+                0
+            };
+            let to = if index == end.fragment {
+                end.offset
+            } else {
+                length
+            };
+            (run, from.min(length), to.min(length))
+        })
+        .collect()
+}
+
+/// The visible text fragments of the page in tree order
+pub(crate) fn text_runs(root: &LayoutBox) -> Vec<TextRun> {
+    fn collect<'a>(
+        layout_box: &'a LayoutBox,
+        block: *const LayoutBox,
+        runs: &mut Vec<TextRun<'a>>,
+    ) {
+        let block = if layout_box.is_inline() {
+            block
+        } else {
+            layout_box as *const LayoutBox
+        };
+        if layout_box.is_text() && is_visible(layout_box) {
+            let (preserves_newlines, metrics) = match &layout_box.render_node {
+                Some(node) => (white_space(node).preserves_newlines(), font_metrics(node)),
+                None => (false, FontMetrics::default()),
+            };
+            runs.extend(layout_box.text_fragments.iter().map(|fragment| TextRun {
+                fragment,
+                block,
+                preserves_newlines,
+                metrics: metrics.clone(),
+            }));
+        }
+        // the children skipped by the layout have no position
+        if layout_box.contents_skipped {
+            return;
+        }
+        for child in &layout_box.children {
+            collect(child, block, runs);
+        }
+    }
+
+    let mut runs = Vec::new();
+    collect(root, root, &mut runs);
+    runs
+}
+
+/// The horizontal position of a character boundary of a fragment
+fn offset_x(fragment: &TextFragment, metrics: &FontMetrics, offset: usize) -> f32 {
+    fragment.rect.x
+        + fragment
+            .text
+            .chars()
+            .take(offset)
+            .map(|c| advance(metrics, fragment, c))
+            .sum::<f32>()
+}
+
+/// The character boundary of a fragment closest to a horizontal position
+fn offset_at(fragment: &TextFragment, metrics: &FontMetrics, x: f32) -> usize {
+    let mut left = fragment.rect.x;
+    for (index, c) in fragment.text.chars().enumerate() {
+        let advance = advance(metrics, fragment, c);
+        if x < left + advance / 2. {
+            return index;
+        }
+        left += advance;
+    }
+    fragment.text.chars().count()
+}
+
+fn advance(metrics: &FontMetrics, fragment: &TextFragment, c: char) -> f32 {
+    if c == ' ' {
+        metrics.char_advance(c) + fragment.word_spacing
+    } else {
+        metrics.char_advance(c)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn character_offsets() {
+        let metrics = FontMetrics::default();
+        let mut fragment = TextFragment::new("ab cd".to_string(), &metrics, 20.);
+        fragment.rect.x = 10.;
+        fragment.word_spacing = 4.;
+
+        assert_eq!(offset_x(&fragment, &metrics, 0), 10.);
+        assert_eq!(offset_x(&fragment, &metrics, 3), 10. + 3. * 8. + 4.);
+        assert_eq!(offset_at(&fragment, &metrics, 0.), 0);
+        assert_eq!(offset_at(&fragment, &metrics, 13.), 0);
+        assert_eq!(offset_at(&fragment, &metrics, 15.), 1);
+        // the space is wider on a justified line
+        assert_eq!(offset_at(&fragment, &metrics, 39.), 3);
+        assert_eq!(offset_at(&fragment, &metrics, 100.), 5);
+    }
+}
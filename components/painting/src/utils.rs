@@ -1,9 +1,38 @@
-use style::value_processing::Value;
+use crate::primitive::{style_transform_to_paint_transform, Transform};
+use layout::containment;
+use layout::layout_box::LayoutBox;
+use style::values::border_radius::BorderRadius;
 
-pub fn is_zero(value: &Value) -> bool {
-    match value {
-        Value::Length(l) => l.to_px() == 0.0,
-        Value::Percentage(p) => *p.0 == 0.0,
+pub fn is_zero(radius: &BorderRadius) -> bool {
+    radius.0.is_zero() && radius.1.is_zero()
+}
+
+/// The transform of a box relative to its parent
+pub fn box_transform(layout_box: &LayoutBox) -> Transform {
+    match &layout_box.render_node {
+        Some(render_node) => style_transform_to_paint_transform(
+            &render_node.borrow().style().transform(),
+            &layout_box.dimensions.border_box().into(),
+        ),
+        None => Transform::identity(),
+    }
+}
+
+/// Whether the contents of a box are clipped to its padding box and
+/// painted together, above the box & below the boxes after it
+pub fn contains_paint(layout_box: &LayoutBox) -> bool {
+    match &layout_box.render_node {
+        Some(render_node) if containment::applies_to(layout_box) => {
+            render_node.borrow().style().contain().paint
+        }
         _ => false,
     }
 }
+
+/// Invisible boxes take space in the layout but aren't painted
+pub fn is_visible(layout_box: &LayoutBox) -> bool {
+    match &layout_box.render_node {
+        Some(render_node) => render_node.borrow().is_visible(),
+        None => true,
+    }
+}
@@ -0,0 +1,115 @@
+/// The objects of a PDF file, numbered from 1 in the order they are
+/// added & written with the cross-reference table locating them.
+/// https://www.adobe.com/content/dam/acom/en/devnet/pdf/pdfs/PDF32000_2008.pdf
+pub struct Document {
+    objects: Vec<Vec<u8>>,
+}
+
+impl Document {
+    pub fn new() -> Self {
+        Self {
+            objects: Vec::new(),
+        }
+    }
+
+    /// Reserve the number of an object written later, so the objects
+    /// added before can refer to it
+    pub fn reserve(&mut self) -> usize {
+        self.objects.push(Vec::new());
+        self.objects.len()
+    }
+
+    pub fn set(&mut self, id: usize, object: Vec<u8>) {
+        self.objects[id - 1] = object;
+    }
+
+    pub fn add(&mut self, object: Vec<u8>) -> usize {
+        self.objects.push(object);
+        self.objects.len()
+    }
+
+    /// Write the file with an object as its catalog
+    pub fn finish(self, root: usize) -> Vec<u8> {
+        let mut output = b"%PDF-1.4\n%\xe2\xe3\xcf\xd3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (index, object) in self.objects.iter().enumerate() {
+            offsets.push(output.len());
+            output.extend_from_slice(format!("{} 0 obj\n", index + 1).as_bytes());
+            output.extend_from_slice(object);
+            output.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref = output.len();
+        output.extend_from_slice(format!("xref\n0 {}\n", self.objects.len() + 1).as_bytes());
+        // every entry is 20 bytes long, including its end of line
+        output.extend_from_slice(b"0000000000 65535 f \n");
+        for offset in offsets {
+            output.extend_from_slice(format!("{:010} 00000 n \n", offset).as_bytes());
+        }
+        output.extend_from_slice(
+            format!(
+                "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+                self.objects.len() + 1,
+                root,
+                xref
+            )
+            .as_bytes(),
+        );
+        output
+    }
+}
+
+/// A stream object with the entries of its dictionary
+pub fn stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+    let mut object = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+    object.extend_from_slice(data);
+    object.extend_from_slice(b"\nendstream");
+    object
+}
+
+/// A number as written in a PDF file, which has no exponent notation
+pub fn number(value: f32) -> String {
+    let value = format!("{:.3}", value);
+    let value = value.trim_end_matches('0').trim_end_matches('.');
+    match value {
+        "-0" | "" => "0".to_string(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn cross_reference_table() {
+        let mut document = Document::new();
+        let pages = document.reserve();
+        let catalog =
+            document.add(format!("<< /Type /Catalog /Pages {} 0 R >>", pages).into_bytes());
+        document.set(pages, b"<< /Type /Pages /Kids [] /Count 0 >>".to_vec());
+        let output = document.finish(catalog);
+
+        // the header has binary bytes, the offsets are in bytes
+        let text = |offset: usize| String::from_utf8_lossy(&output[offset..]).to_string();
+        let trailer = text(output.len() - 64);
+        let startxref = trailer.rsplit("startxref\n").next().unwrap();
+        let xref: usize = startxref.lines().next().unwrap().parse().unwrap();
+        assert!(text(xref).starts_with("xref\n0 3\n"));
+
+        for (index, entry) in text(xref).lines().skip(3).take(2).enumerate() {
+            let offset: usize = entry[..10].parse().unwrap();
+            assert!(text(offset).starts_with(&format!("{} 0 obj", index + 1)));
+        }
+        assert!(trailer.contains("/Root 2 0 R"));
+    }
+
+    #[test]
+    fn numbers() {
+        assert_eq!(number(1.), "1");
+        assert_eq!(number(0.75), "0.75");
+        assert_eq!(number(-0.0001), "0");
+        assert_eq!(number(1e-7), "0");
+        assert_eq!(number(12.3456), "12.346");
+    }
+}
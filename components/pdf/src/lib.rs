@@ -0,0 +1,6 @@
+/// Export of the display list into PDF documents, as vector shapes &
+/// images rather than the pixels of a rasterized page
+mod document;
+mod painter;
+
+pub use painter::PdfPainter;
@@ -0,0 +1,454 @@
+use super::document::{number, stream, Document};
+use painting::{Color, Image, Painter, RRect, Rect, Transform};
+use std::fmt::Write;
+
+/// The points of a CSS pixel, the unit of the PDF pages
+const POINTS_PER_PIXEL: f32 = 0.75;
+
+/// A painter writing the commands of a display list as the content of
+/// PDF pages of the same size. The shapes are written as vector paths &
+/// the images as image objects, so the pages aren't rasterized at any
+/// resolution.
+pub struct PdfPainter {
+    width: f32,
+    height: f32,
+    /// The content streams of the pages before the current page
+    pages: Vec<String>,
+    /// The content stream of the current page
+    content: String,
+    transform: Transform,
+    /// The rect of the page the shapes are clipped to
+    clip: Option<Rect>,
+    /// The images drawn, named `/Im<index>` in the content
+    images: Vec<Image>,
+    /// The opacities of the colors drawn, set by the graphics states
+    /// named `/GS<index>` in the content
+    opacities: Vec<u8>,
+}
+
+impl PdfPainter {
+    /// Create a painter for a page of a size in CSS pixels
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            pages: Vec::new(),
+            content: page_content(height),
+            transform: Transform::identity(),
+            clip: None,
+            images: Vec::new(),
+            opacities: Vec::new(),
+        }
+    }
+
+    /// Start painting a new page, after the pages painted so far
+    pub fn add_page(&mut self) {
+        let content = std::mem::replace(&mut self.content, page_content(self.height));
+        self.pages.push(content);
+        self.transform = Transform::identity();
+        self.clip = None;
+    }
+
+    /// Write the PDF file of the pages painted so far
+    pub fn finish(mut self) -> Vec<u8> {
+        let mut document = Document::new();
+        let catalog = document.reserve();
+        let pages = document.reserve();
+
+        let mut resources = String::new();
+        if !self.images.is_empty() {
+            resources.push_str("/XObject <<");
+            for (index, image) in self.images.iter().enumerate() {
+                let id = add_image(&mut document, image);
+                write!(resources, " /Im{} {} 0 R", index, id).unwrap();
+            }
+            resources.push_str(" >> ");
+        }
+        if !self.opacities.is_empty() {
+            resources.push_str("/ExtGState <<");
+            for (index, opacity) in self.opacities.iter().enumerate() {
+                let alpha = number(*opacity as f32 / 255.);
+                write!(resources, " /GS{} << /ca {1} /CA {1} >>", index, alpha).unwrap();
+            }
+            resources.push_str(" >> ");
+        }
+
+        self.pages.push(self.content);
+        let mut kids = Vec::new();
+        for page_content in &self.pages {
+            let content = document.add(stream("", page_content.as_bytes()));
+            let page = document.add(
+                format!(
+                    "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {} {}] /Resources << {}>> /Contents {} 0 R >>",
+                    pages,
+                    number(self.width * POINTS_PER_PIXEL),
+                    number(self.height * POINTS_PER_PIXEL),
+                    resources,
+                    content
+                )
+                .into_bytes(),
+            );
+            kids.push(format!("{} 0 R", page));
+        }
+        document.set(
+            pages,
+            format!(
+                "<< /Type /Pages /Kids [{}] /Count {} >>",
+                kids.join(" "),
+                kids.len()
+            )
+            .into_bytes(),
+        );
+        document.set(
+            catalog,
+            format!("<< /Type /Catalog /Pages {} 0 R >>", pages).into_bytes(),
+        );
+        document.finish(catalog)
+    }
+
+    /// Start drawing a shape in the current transform. Returns false if
+    /// the color is transparent & there's nothing to draw.
+    fn begin(&mut self, color: &Color, operator: &str) -> bool {
+        if color.a == 0 {
+            return false;
+        }
+        self.content.push_str("q\n");
+        self.apply_clip();
+        self.apply_transform();
+        if color.a < 255 {
+            let state = self.opacity_state(color.a);
+            writeln!(self.content, "/GS{} gs", state).unwrap();
+        }
+        writeln!(
+            self.content,
+            "{} {} {} {}",
+            number(color.r as f32 / 255.),
+            number(color.g as f32 / 255.),
+            number(color.b as f32 / 255.),
+            operator
+        )
+        .unwrap();
+        true
+    }
+
+    /// Clip the shape drawn next, before its transform since the clip is
+    /// in the coordinates of the page
+    fn apply_clip(&mut self) {
+        if let Some(clip) = &self.clip {
+            writeln!(
+                self.content,
+                "{} {} {} {} re W n",
+                number(clip.x),
+                number(clip.y),
+                number(clip.width),
+                number(clip.height)
+            )
+            .unwrap();
+        }
+    }
+
+    fn apply_transform(&mut self) {
+        if self.transform.is_identity() {
+            return;
+        }
+        let t = &self.transform;
+        writeln!(
+            self.content,
+            "{} {} {} {} {} {} cm",
+            number(t.a),
+            number(t.b),
+            number(t.c),
+            number(t.d),
+            number(t.e),
+            number(t.f)
+        )
+        .unwrap();
+    }
+
+    fn opacity_state(&mut self, opacity: u8) -> usize {
+        match self.opacities.iter().position(|o| *o == opacity) {
+            Some(index) => index,
+            None => {
+                self.opacities.push(opacity);
+                self.opacities.len() - 1
+            }
+        }
+    }
+
+    fn rrect_path(&mut self, rect: &RRect) {
+        let corners = &rect.corners;
+        let (left, top) = (rect.x, rect.y);
+        let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+
+        self.move_to(left + corners.top_left.horizontal_r(), top);
+        self.line_to(right - corners.top_right.horizontal_r(), top);
+        self.quad_to(
+            (right - corners.top_right.horizontal_r(), top),
+            (right, top),
+            (right, top + corners.top_right.vertical_r()),
+        );
+        self.line_to(right, bottom - corners.bottom_right.vertical_r());
+        self.quad_to(
+            (right, bottom - corners.bottom_right.vertical_r()),
+            (right, bottom),
+            (right - corners.bottom_right.horizontal_r(), bottom),
+        );
+        self.line_to(left + corners.bottom_left.horizontal_r(), bottom);
+        self.quad_to(
+            (left + corners.bottom_left.horizontal_r(), bottom),
+            (left, bottom),
+            (left, bottom - corners.bottom_left.vertical_r()),
+        );
+        self.line_to(left, top + corners.top_left.vertical_r());
+        self.quad_to(
+            (left, top + corners.top_left.vertical_r()),
+            (left, top),
+            (left + corners.top_left.horizontal_r(), top),
+        );
+        self.content.push_str("h\n");
+    }
+
+    fn move_to(&mut self, x: f32, y: f32) {
+        writeln!(self.content, "{} {} m", number(x), number(y)).unwrap();
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        writeln!(self.content, "{} {} l", number(x), number(y)).unwrap();
+    }
+
+    /// A quadratic curve, written as the cubic curve PDF supports
+    fn quad_to(&mut self, from: (f32, f32), control: (f32, f32), to: (f32, f32)) {
+        let control1 = (
+            from.0 + (control.0 - from.0) * 2. / 3.,
+            from.1 + (control.1 - from.1) * 2. / 3.,
+        );
+        let control2 = (
+            to.0 + (control.0 - to.0) * 2. / 3.,
+            to.1 + (control.1 - to.1) * 2. / 3.,
+        );
+        writeln!(
+            self.content,
+            "{} {} {} {} {} {} c",
+            number(control1.0),
+            number(control1.1),
+            number(control2.0),
+            number(control2.1),
+            number(to.0),
+            number(to.1)
+        )
+        .unwrap();
+    }
+}
+
+impl Painter for PdfPainter {
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        if !self.begin(&color, "rg") {
+            return;
+        }
+        writeln!(
+            self.content,
+            "{} {} {} {} re f\nQ",
+            number(rect.x),
+            number(rect.y),
+            number(rect.width),
+            number(rect.height)
+        )
+        .unwrap();
+    }
+
+    fn fill_rrect(&mut self, rect: RRect, color: Color) {
+        if !self.begin(&color, "rg") {
+            return;
+        }
+        self.rrect_path(&rect);
+        self.content.push_str("f\nQ\n");
+    }
+
+    fn stroke_rrect(&mut self, rect: RRect, color: Color, width: f32) {
+        if !self.begin(&color, "RG") {
+            return;
+        }
+        writeln!(self.content, "{} w", number(width)).unwrap();
+        self.rrect_path(&rect);
+        self.content.push_str("S\nQ\n");
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        self.clip = clip;
+    }
+
+    fn draw_image(&mut self, rect: Rect, image: &Image) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+        // the tiles of a background share their image
+        let index = match self.images.iter().position(|drawn| drawn == image) {
+            Some(index) => index,
+            None => {
+                self.images.push(image.clone());
+                self.images.len() - 1
+            }
+        };
+        self.content.push_str("q\n");
+        self.apply_clip();
+        self.apply_transform();
+        // the first row of an image is at the top of the unit square
+        writeln!(
+            self.content,
+            "{} 0 0 -{} {} {} cm\n/Im{} Do\nQ",
+            number(rect.width),
+            number(rect.height),
+            number(rect.x),
+            number(rect.y + rect.height),
+            index
+        )
+        .unwrap();
+    }
+}
+
+/// Add an image object with the RGB of the pixels & their alpha as
+/// a soft mask when the image isn't opaque
+fn add_image(document: &mut Document, image: &Image) -> usize {
+    let pixel_count = (image.width * image.height) as usize;
+    let mut rgb = Vec::with_capacity(pixel_count * 3);
+    let mut alpha = Vec::with_capacity(pixel_count);
+    for pixel in image.pixels.chunks_exact(4).take(pixel_count) {
+        rgb.extend_from_slice(&pixel[..3]);
+        alpha.push(pixel[3]);
+    }
+
+    let dictionary = format!(
+        "/Type /XObject /Subtype /Image /Width {} /Height {} /BitsPerComponent 8",
+        image.width, image.height
+    );
+    let mask = if alpha.iter().all(|alpha| *alpha == 255) {
+        String::new()
+    } else {
+        let mask = document.add(stream(
+            &format!("{} /ColorSpace /DeviceGray", dictionary),
+            &alpha,
+        ));
+        format!(" /SMask {} 0 R", mask)
+    };
+    document.add(stream(
+        &format!("{} /ColorSpace /DeviceRGB{}", dictionary, mask),
+        &rgb,
+    ))
+}
+
+/// The start of the content stream of a page, flipping the y axis of
+/// PDF going up from the bottom of the page
+fn page_content(height: f32) -> String {
+    format!(
+        "{0} 0 0 -{0} 0 {1} cm\n",
+        number(POINTS_PER_PIXEL),
+        number(height * POINTS_PER_PIXEL)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use painting::{Corners, Radii};
+
+    fn content(painter: &PdfPainter) -> Vec<&str> {
+        painter.content.lines().skip(1).collect()
+    }
+
+    #[test]
+    fn paint_shapes() {
+        let mut painter = PdfPainter::new(100., 50.);
+        painter.fill_rect(
+            Rect::new(0., 0., 10., 5.),
+            Color {
+                r: 255,
+                g: 0,
+                b: 0,
+                a: 255,
+            },
+        );
+        painter.fill_rect(Rect::new(0., 0., 10., 5.), Color::default());
+        painter.set_transform(Transform::translate(5., 0.));
+        let radius = Radii::new(2., 2.);
+        painter.stroke_rrect(
+            RRect::new(
+                0.,
+                0.,
+                10.,
+                10.,
+                Corners::new(radius.clone(), radius.clone(), radius.clone(), radius),
+            ),
+            Color {
+                r: 0,
+                g: 0,
+                b: 255,
+                a: 51,
+            },
+            2.,
+        );
+
+        let content = content(&painter);
+        assert_eq!(content[..3], ["q", "1 0 0 rg", "0 0 10 5 re f"]);
+        assert_eq!(
+            content[4..9],
+            ["q", "1 0 0 1 5 0 cm", "/GS0 gs", "0 0 1 RG", "2 w"]
+        );
+        assert_eq!(content[10..12], ["8 0 l", "9.333 0 10 0.667 10 2 c"]);
+        assert_eq!(content.iter().filter(|line| **line == "q").count(), 2);
+
+        let output = String::from_utf8_lossy(&painter.finish()).to_string();
+        assert!(output.contains("/MediaBox [0 0 75 37.5]"));
+        assert!(output.contains("/ExtGState << /GS0 << /ca 0.2 /CA 0.2 >> >>"));
+    }
+
+    #[test]
+    fn embed_images() {
+        let mut painter = PdfPainter::new(100., 50.);
+        let image = Image::new(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]);
+        painter.draw_image(Rect::new(0., 0., 20., 10.), &image);
+        painter.draw_image(Rect::new(20., 0., 20., 10.), &image);
+
+        assert_eq!(
+            content(&painter)[4..],
+            ["q", "20 0 0 -10 20 10 cm", "/Im0 Do", "Q"]
+        );
+
+        let output = String::from_utf8_lossy(&painter.finish()).to_string();
+        assert_eq!(output.matches("/Subtype /Image").count(), 2);
+        assert!(output.contains("/ColorSpace /DeviceGray /Length 2 >>"));
+        assert!(output.contains("/ColorSpace /DeviceRGB /SMask 3 0 R /Length 6 >>"));
+        assert!(output.contains("/XObject << /Im0 4 0 R >>"));
+    }
+
+    #[test]
+    fn write_pages() {
+        let mut painter = PdfPainter::new(100., 50.);
+        painter.set_transform(Transform::translate(5., 0.));
+        let black = Color {
+            r: 0,
+            g: 0,
+            b: 0,
+            a: 255,
+        };
+        painter.fill_rect(Rect::new(0., 0., 10., 5.), black.clone());
+        painter.add_page();
+        painter.fill_rect(Rect::new(0., 0., 10., 5.), black.clone());
+
+        assert_eq!(content(&painter), ["q", "0 0 0 rg", "0 0 10 5 re f", "Q"]);
+
+        painter.set_clip(Some(Rect::new(0., 0., 5., 5.)));
+        painter.set_transform(Transform::translate(5., 0.));
+        painter.fill_rect(Rect::new(0., 0., 10., 5.), black);
+        assert_eq!(
+            content(&painter)[4..7],
+            ["q", "0 0 5 5 re W n", "1 0 0 1 5 0 cm"]
+        );
+        let output = String::from_utf8_lossy(&painter.finish()).to_string();
+        assert!(output.contains("/Kids [4 0 R 6 0 R] /Count 2"));
+        assert_eq!(output.matches("/Type /Page ").count(), 2);
+    }
+}
@@ -0,0 +1,785 @@
+/// CSS animations. The `@keyframes` rules named by `animation-name` animate
+/// the properties of an element on the clock of the document. The animated
+/// values are cascaded at the animation origin, above the normal declarations
+/// and below the important ones.
+/// https://www.w3.org/TR/css-animations-1/
+use super::interpolate::interpolate;
+use super::render_tree::{compute_styles, RenderNodeRef, RenderNodeWeak, RenderTree};
+use super::transition::{affects_layout, apply_value, as_millis, mark_needs_layout};
+use super::value_processing::{
+    apply_animated_styles, apply_styles, parse_declaration, ContextualRule, Property, Value,
+    ValueRef,
+};
+use super::values::animation_direction::AnimationDirection;
+use super::values::animation_fill_mode::AnimationFillMode;
+use super::values::animation_play_state::AnimationPlayState;
+use super::values::length::LengthContext;
+use super::values::timing_function::TimingFunction;
+use css::cssom::keyframes_rule::KeyframesRule;
+use dom::dom_ref::NodeRef;
+use std::collections::{HashMap, HashSet};
+use std::rc::Rc;
+use std::time::Duration;
+
+/// The keyframes of a `@keyframes` rule, sorted by offset
+#[derive(Debug)]
+struct Keyframes(Vec<Keyframe>);
+
+#[derive(Debug)]
+struct Keyframe {
+    offset: f32,
+    values: HashMap<Property, Value>,
+    /// The easing from this keyframe to the next one, replacing
+    /// the timing function of the animation
+    timing_function: Option<TimingFunction>,
+}
+
+/// An animation of an element, from the time its name is set
+/// in `animation-name` until it's removed
+#[derive(Debug)]
+struct Animation {
+    /// The animated element. Its render node changes when it's restyled.
+    node: NodeRef,
+    render_node: RenderNodeWeak,
+    name: String,
+    keyframes: Rc<Keyframes>,
+    /// The time the animation started, before its delay, in milliseconds
+    started_at: f64,
+    delay: f64,
+    duration: f64,
+    iteration_count: f32,
+    direction: AnimationDirection,
+    fill_mode: AnimationFillMode,
+    timing_function: TimingFunction,
+    /// The time the animation was paused at by `animation-play-state`
+    paused_at: Option<f64>,
+    /// The time the values of the animation were last applied to its
+    /// render node, if they were
+    sampled_at: Option<f64>,
+}
+
+/// The animations of a render tree, the keyframes they use
+/// and the time of its clock
+#[derive(Debug, Default)]
+pub struct Animations {
+    keyframes: HashMap<String, Rc<Keyframes>>,
+    animations: Vec<Animation>,
+    current_time: Duration,
+    /// Whether the user prefers reduced motion. The animations then skip
+    /// to their end, where they leave the values they fill forwards with.
+    reduced_motion: bool,
+}
+
+impl Keyframes {
+    fn new(rule: &KeyframesRule) -> Self {
+        let mut keyframes: Vec<Keyframe> = Vec::new();
+
+        for block in &rule.keyframes {
+            let mut values = HashMap::new();
+            let mut timing_function = None;
+            for declaration in &block.declarations {
+                // important declarations are ignored in keyframes
+                if declaration.important {
+                    continue;
+                }
+                for (property, value) in parse_declaration(declaration) {
+                    match (property, value) {
+                        (Property::AnimationTimingFunction, Value::TimingFunctionList(list)) => {
+                            timing_function = Some(list.get(0));
+                        }
+                        (property, value) if is_animatable(&property) => {
+                            values.insert(property, value);
+                        }
+                        _ => {}
+                    }
+                }
+            }
+
+            for offset in &block.offsets {
+                // the keyframes with the same offset cascade. The offsets
+                // are parsed from the same percentages, so equal offsets
+                // are exactly equal.
+                #[allow(clippy::float_cmp)]
+                let keyframe = keyframes
+                    .iter_mut()
+                    .find(|keyframe| keyframe.offset == *offset);
+                match keyframe {
+                    Some(keyframe) => {
+                        keyframe.values.extend(values.clone());
+                        if timing_function.is_some() {
+                            keyframe.timing_function = timing_function.clone();
+                        }
+                    }
+                    None => keyframes.push(Keyframe {
+                        offset: *offset,
+                        values: values.clone(),
+                        timing_function: timing_function.clone(),
+                    }),
+                }
+            }
+        }
+
+        keyframes.sort_by(|a, b| a.offset.partial_cmp(&b.offset).unwrap());
+        Self(keyframes)
+    }
+
+    fn properties(&self) -> HashSet<Property> {
+        self.0
+            .iter()
+            .flat_map(|keyframe| keyframe.values.keys().cloned())
+            .collect()
+    }
+
+    /// The value of a property at a progress of an iteration. The element's
+    /// own value is used when no keyframe sets the property at 0% or 100%.
+    fn value_at(
+        &self,
+        property: &Property,
+        progress: f32,
+        base: &Value,
+        timing_function: &TimingFunction,
+    ) -> Value {
+        let mut frames = self
+            .0
+            .iter()
+            .filter_map(|keyframe| {
+                let value = keyframe.values.get(property)?;
+                Some((keyframe.offset, value, keyframe.timing_function.as_ref()))
+            })
+            .collect::<Vec<_>>();
+        if frames.first().map(|(offset, ..)| *offset) != Some(0.) {
+            frames.insert(0, (0., base, None));
+        }
+        if frames.last().map(|(offset, ..)| *offset) != Some(1.) {
+            frames.push((1., base, None));
+        }
+
+        let next = frames
+            .iter()
+            .position(|(offset, ..)| *offset > progress)
+            .unwrap_or(frames.len() - 1)
+            .max(1);
+        let (from_offset, from, easing) = frames[next - 1];
+        let (to_offset, to, _) = frames[next];
+
+        let progress = (progress - from_offset) / (to_offset - from_offset);
+        let progress = easing.unwrap_or(timing_function).output(progress);
+        // values that can't be interpolated flip half way through
+        interpolate(from, to, progress).unwrap_or_else(|| {
+            if progress < 0.5 {
+                from.clone()
+            } else {
+                to.clone()
+            }
+        })
+    }
+}
+
+impl Animation {
+    fn targets(&self, render_node: &RenderNodeRef) -> bool {
+        self.render_node.upgrade().as_ref() == Some(render_node)
+    }
+
+    fn active_duration(&self) -> f64 {
+        if self.duration == 0. || self.iteration_count == 0. {
+            return 0.;
+        }
+        self.duration * self.iteration_count as f64
+    }
+
+    /// The time the animation ends, after all its iterations
+    fn end(&self) -> f64 {
+        self.started_at + self.delay + self.active_duration()
+    }
+
+    /// Whether the values of the animation change as the time advances
+    fn is_running(&self, time: f64) -> bool {
+        self.paused_at.is_none() && time < self.end()
+    }
+
+    fn needs_sample(&self, time: f64) -> bool {
+        match self.sampled_at {
+            None => true,
+            Some(_) if self.paused_at.is_some() => false,
+            // the values change until the end & once more when it ends
+            Some(sampled_at) => sampled_at < self.end() || time < self.end(),
+        }
+    }
+
+    /// The progress of the current iteration at a time, in the direction it
+    /// plays, or `None` if the animation doesn't apply any value at the time
+    fn progress_at(&self, time: f64) -> Option<f32> {
+        let local_time = self.paused_at.unwrap_or(time) - self.started_at - self.delay;
+        let active_duration = self.active_duration();
+
+        let (iteration, progress) = if local_time < 0. {
+            if !self.fill_mode.fills_backwards() {
+                return None;
+            }
+            (0., 0.)
+        } else if local_time >= active_duration {
+            if !self.fill_mode.fills_forwards() {
+                return None;
+            }
+            let iterations = self.iteration_count as f64;
+            if iterations == 0. {
+                (0., 0.)
+            } else if iterations.is_infinite() || iterations.fract() == 0. {
+                // the animation ends at the end of its last iteration
+                ((iterations - 1.).min(f64::MAX), 1.)
+            } else {
+                (iterations.floor(), iterations.fract())
+            }
+        } else {
+            let iterations = local_time / self.duration;
+            (iterations.floor(), iterations.fract())
+        };
+
+        if self.direction.is_reversed(iteration as u64) {
+            Some(1. - progress as f32)
+        } else {
+            Some(progress as f32)
+        }
+    }
+}
+
+impl Animations {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Set the `@keyframes` rules of the stylesheets. The last rule
+    /// with a name is used by the animations with the name.
+    pub fn set_keyframes(&mut self, rules: &[&KeyframesRule]) {
+        self.keyframes = rules
+            .iter()
+            .map(|rule| (rule.name.clone(), Rc::new(Keyframes::new(rule))))
+            .collect();
+    }
+
+    /// Skip the motion of the animations started from now on or not, for
+    /// the users who prefer reduced motion
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        self.reduced_motion = reduced_motion;
+    }
+
+    /// Whether some animations are still running, i.e. the styles of
+    /// the render tree change when the clock advances
+    pub fn is_running(&self) -> bool {
+        let now = as_millis(self.current_time);
+        self.animations
+            .iter()
+            .any(|animation| animation.is_running(now))
+    }
+
+    /// Move the animations of a render node replaced by a restyle and its
+    /// descendants to the new render nodes, which get the animated values
+    /// before they are compared with the old ones to start transitions.
+    pub(crate) fn restyled(
+        &mut self,
+        old: &RenderNodeRef,
+        new: &RenderNodeRef,
+        rules: &[ContextualRule],
+        cache: &mut HashSet<ValueRef>,
+        lengths: &LengthContext,
+    ) {
+        if self.animations.is_empty() {
+            return;
+        }
+
+        let mut properties = HashSet::new();
+        for animation in &mut self.animations {
+            if animation.targets(old) {
+                animation.render_node = new.downgrade();
+                properties.extend(animation.keyframes.properties());
+            }
+        }
+        if !properties.is_empty() {
+            self.apply(new, &properties, rules, cache, lengths);
+        }
+
+        let old_children = old.borrow().children.clone();
+        for new_child in &new.borrow().children {
+            let old_child = old_children
+                .iter()
+                .find(|old_child| old_child.borrow().node == new_child.borrow().node);
+            if let Some(old_child) = old_child {
+                self.restyled(old_child, new_child, rules, cache, lengths);
+            }
+        }
+    }
+
+    /// Start the animations named by the elements of the tree that are not
+    /// running yet and stop the ones that aren't named anymore
+    fn update(&mut self, root: &RenderNodeRef) -> Vec<(RenderNodeRef, HashSet<Property>)> {
+        let mut previous = std::mem::take(&mut self.animations);
+        self.update_node(root, &mut previous);
+
+        // the animated values of the stopped animations are removed
+        previous
+            .into_iter()
+            .filter_map(|animation| {
+                let render_node = animation.render_node.upgrade()?;
+                Some((render_node, animation.keyframes.properties()))
+            })
+            .collect()
+    }
+
+    fn update_node(&mut self, render_node: &RenderNodeRef, previous: &mut Vec<Animation>) {
+        let now = as_millis(self.current_time);
+        let node = render_node.borrow().node.clone();
+
+        if node.is_element() {
+            let style = |property: &Property| render_node.borrow().get_style(property);
+            let names = match style(&Property::AnimationName).inner() {
+                Value::AnimationName(names) => names.0.clone(),
+                _ => Vec::new(),
+            };
+
+            for (index, name) in names.into_iter().enumerate() {
+                let name = match name {
+                    Some(name) => name,
+                    None => continue,
+                };
+                // an animation without keyframes doesn't run
+                let keyframes = match self.keyframes.get(&name) {
+                    Some(keyframes) => keyframes.clone(),
+                    None => continue,
+                };
+                let time_at = |property: &Property| match style(property).inner() {
+                    Value::TimeList(times) => *times.get(index).milliseconds as f64,
+                    _ => 0.,
+                };
+                let play_state = match style(&Property::AnimationPlayState).inner() {
+                    Value::AnimationPlayStateList(states) => states.get(index),
+                    _ => AnimationPlayState::Running,
+                };
+
+                let existing = previous
+                    .iter()
+                    .position(|animation| animation.node == node && animation.name == name);
+                let mut animation = match existing {
+                    Some(existing) => previous.remove(existing),
+                    None => Animation {
+                        node: node.clone(),
+                        render_node: render_node.downgrade(),
+                        name,
+                        keyframes: keyframes.clone(),
+                        started_at: now,
+                        delay: 0.,
+                        duration: 0.,
+                        iteration_count: 1.,
+                        direction: AnimationDirection::Normal,
+                        fill_mode: AnimationFillMode::None,
+                        timing_function: TimingFunction::ease(),
+                        paused_at: None,
+                        sampled_at: None,
+                    },
+                };
+
+                // the animation keeps its progress when its render node or its
+                // keyframes change but they have to be applied again
+                if !animation.targets(render_node) || !Rc::ptr_eq(&animation.keyframes, &keyframes)
+                {
+                    animation.render_node = render_node.downgrade();
+                    animation.keyframes = keyframes;
+                    animation.sampled_at = None;
+                }
+                animation.delay = time_at(&Property::AnimationDelay);
+                animation.duration = time_at(&Property::AnimationDuration).max(0.);
+                if self.reduced_motion {
+                    animation.delay = 0.;
+                    animation.duration = 0.;
+                }
+                animation.iteration_count = match style(&Property::AnimationIterationCount).inner()
+                {
+                    Value::IterationCountList(counts) => counts.get(index).to_f32(),
+                    _ => 1.,
+                };
+                animation.direction = match style(&Property::AnimationDirection).inner() {
+                    Value::AnimationDirectionList(directions) => directions.get(index),
+                    _ => AnimationDirection::Normal,
+                };
+                animation.fill_mode = match style(&Property::AnimationFillMode).inner() {
+                    Value::AnimationFillModeList(fill_modes) => fill_modes.get(index),
+                    _ => AnimationFillMode::None,
+                };
+                animation.timing_function = match style(&Property::AnimationTimingFunction).inner()
+                {
+                    Value::TimingFunctionList(functions) => functions.get(index),
+                    _ => TimingFunction::ease(),
+                };
+                match (play_state, animation.paused_at) {
+                    (AnimationPlayState::Paused, None) => animation.paused_at = Some(now),
+                    (AnimationPlayState::Running, Some(paused_at)) => {
+                        // the animation resumes where it was paused
+                        animation.started_at += now - paused_at;
+                        animation.paused_at = None;
+                        animation.sampled_at = None;
+                    }
+                    _ => {}
+                }
+
+                self.animations.push(animation);
+            }
+        }
+
+        for child in &render_node.borrow().children {
+            self.update_node(child, previous);
+        }
+    }
+
+    /// Apply the values of the animations at the current time to
+    /// the render nodes whose animated values may have changed
+    fn sample(
+        &mut self,
+        mut targets: Vec<(RenderNodeRef, HashSet<Property>)>,
+        rules: &[ContextualRule],
+        cache: &mut HashSet<ValueRef>,
+        lengths: &LengthContext,
+    ) -> bool {
+        let now = as_millis(self.current_time);
+
+        for animation in &mut self.animations {
+            if !animation.needs_sample(now) {
+                continue;
+            }
+            animation.sampled_at = Some(now);
+            let render_node = match animation.render_node.upgrade() {
+                Some(render_node) => render_node,
+                None => continue,
+            };
+            let properties = animation.keyframes.properties();
+            match targets
+                .iter_mut()
+                .find(|(target, _)| *target == render_node)
+            {
+                Some((_, target_properties)) => target_properties.extend(properties),
+                None => targets.push((render_node, properties)),
+            }
+        }
+
+        let mut changed = false;
+        for (render_node, properties) in targets {
+            changed |= self.apply(&render_node, &properties, rules, cache, lengths);
+        }
+        changed
+    }
+
+    /// Cascade the values of the animations of a render node at the current
+    /// time with its declared values & set the animated properties to the
+    /// result. Returns whether any style changed.
+    fn apply(
+        &self,
+        render_node: &RenderNodeRef,
+        properties: &HashSet<Property>,
+        rules: &[ContextualRule],
+        cache: &mut HashSet<ValueRef>,
+        lengths: &LengthContext,
+    ) -> bool {
+        let now = as_millis(self.current_time);
+        let node = render_node.borrow().node.clone();
+        let parent = render_node.borrow().parent_render_node.clone();
+        let matched_rules = render_node
+            .borrow()
+            .matched_rules
+            .iter()
+            .filter_map(|rule_index| rules.get(*rule_index))
+            .collect::<Vec<_>>();
+
+        // the values of the element without its animations
+        let base = compute_styles(
+            apply_styles(&node, &matched_rules),
+            parent.clone(),
+            cache,
+            lengths,
+        );
+
+        // the later animations in animation-name win
+        let mut animated_values = HashMap::new();
+        for animation in &self.animations {
+            if animation.node != node {
+                continue;
+            }
+            if let Some(progress) = animation.progress_at(now) {
+                for property in animation.keyframes.properties() {
+                    let value = animation.keyframes.value_at(
+                        &property,
+                        progress,
+                        &base[&property],
+                        &animation.timing_function,
+                    );
+                    animated_values.insert(property, value);
+                }
+            }
+        }
+
+        let computed = compute_styles(
+            apply_animated_styles(&node, &matched_rules, &animated_values),
+            parent,
+            cache,
+            lengths,
+        );
+
+        let mut changed = false;
+        for property in properties {
+            let value = &computed[property];
+            if render_node.borrow().properties.get(property) != Some(value) {
+                apply_value(render_node, property, value);
+                if affects_layout(value) {
+                    mark_needs_layout(render_node);
+                }
+                changed = true;
+            }
+        }
+        changed
+    }
+}
+
+/// Start & stop the animations of a render tree after it's built or
+/// restyled, and apply their values at a time of the document clock
+pub fn update_animations(tree: &mut RenderTree, rules: &[ContextualRule], time: Duration) {
+    let animations = &mut tree.animations;
+    animations.current_time = time;
+    if animations.keyframes.is_empty() && animations.animations.is_empty() {
+        return;
+    }
+    let root = match &tree.root {
+        Some(root) => root.clone(),
+        None => {
+            animations.animations.clear();
+            return;
+        }
+    };
+
+    let stopped = animations.update(&root);
+    animations.sample(stopped, rules, &mut tree.style_cache, &tree.lengths);
+}
+
+/// Advance the clock of the animations of a render tree & apply their
+/// values at the new time. Returns whether any style changed.
+pub fn sample_animations(tree: &mut RenderTree, rules: &[ContextualRule], time: Duration) -> bool {
+    tree.animations.current_time = time;
+    if tree.animations.animations.is_empty() {
+        return false;
+    }
+    tree.animations
+        .sample(Vec::new(), rules, &mut tree.style_cache, &tree.lengths)
+}
+
+/// The animation & transition properties can't be animated
+fn is_animatable(property: &Property) -> bool {
+    match property {
+        Property::AnimationName
+        | Property::AnimationDuration
+        | Property::AnimationTimingFunction
+        | Property::AnimationIterationCount
+        | Property::AnimationDirection
+        | Property::AnimationPlayState
+        | Property::AnimationDelay
+        | Property::AnimationFillMode
+        | Property::TransitionProperty
+        | Property::TransitionDuration
+        | Property::TransitionTimingFunction
+        | Property::TransitionDelay => false,
+        _ => true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_tree::build_render_tree;
+    use crate::restyle::restyle_render_tree;
+    use crate::value_processing::{CSSLocation, CascadeOrigin};
+    use crate::values::color::Color;
+    use crate::values::length::Length;
+    use css::cssom::css_rule::CSSRule;
+    use css::cssom::stylesheet::StyleSheet;
+    use dom::node::Node;
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+
+    fn style(render_node: &RenderNodeRef, property: Property) -> Value {
+        (**render_node.borrow().get_style(&property)).clone()
+    }
+
+    fn box_node(tree: &RenderTree) -> RenderNodeRef {
+        tree.root.clone().unwrap().borrow().children[0].clone()
+    }
+
+    fn px(value: f32) -> Value {
+        Value::Length(Length::new_px(value))
+    }
+
+    fn rgb(r: f32) -> Value {
+        Value::Color(Color::Rgba(r.into(), 0.0.into(), 0.0.into(), 255.0.into()))
+    }
+
+    fn rules(stylesheet: &StyleSheet) -> Vec<ContextualRule> {
+        stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect()
+    }
+
+    fn keyframes(stylesheet: &StyleSheet) -> Vec<&KeyframesRule> {
+        stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Keyframes(keyframes) => Some(keyframes),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn animate_keyframes() {
+        let doc = document();
+        let inner = element("span", doc.clone(), vec![]);
+        let target = element("div.box", doc.clone(), vec![inner.clone()]);
+        let root = element("div", doc.clone(), vec![target.clone()]);
+
+        let css = r#"
+        @keyframes grow {
+            from { width: 10px; }
+            to { width: 110px; }
+        }
+        @keyframes fade {
+            50% { color: rgb(100, 0, 0); }
+        }
+        .box {
+            width: 0px;
+            color: rgb(0, 0, 0);
+            animation: grow 100ms linear 2 alternate, fade 200ms linear 50ms backwards;
+        }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = rules(&stylesheet);
+        let mut tree = build_render_tree(root.clone(), &rules);
+        tree.animations.set_keyframes(&keyframes(&stylesheet));
+        update_animations(&mut tree, &rules, Duration::from_millis(1000));
+        assert!(tree.animations.is_running());
+
+        // the delayed animation fills backwards with its first keyframe
+        assert_eq!(style(&box_node(&tree), Property::Width), px(10.));
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(0.));
+
+        box_node(&tree).borrow_mut().needs_layout = false;
+        assert!(sample_animations(
+            &mut tree,
+            &rules,
+            Duration::from_millis(1050)
+        ));
+        assert_eq!(style(&box_node(&tree), Property::Width), px(60.));
+        assert!(box_node(&tree).borrow().needs_layout);
+
+        // the second iteration plays backwards
+        sample_animations(&mut tree, &rules, Duration::from_millis(1125));
+        assert_eq!(style(&box_node(&tree), Property::Width), px(85.));
+        // the keyframes without the property use the value of the element
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(75.));
+        // the descendants inherit the animated color
+        let inner_node = box_node(&tree).borrow().children[0].clone();
+        assert_eq!(style(&inner_node, Property::Color), rgb(75.));
+
+        // the values are removed when the animations end
+        sample_animations(&mut tree, &rules, Duration::from_millis(1300));
+        assert_eq!(style(&box_node(&tree), Property::Width), px(0.));
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(0.));
+        assert!(!tree.animations.is_running());
+    }
+
+    #[test]
+    fn animations_follow_restyles() {
+        let doc = document();
+        let target = element("div.box", doc.clone(), vec![]);
+        let root = element("div", doc.clone(), vec![target.clone()]);
+
+        let css = r#"
+        @keyframes slide {
+            from { width: 0px; height: 0px; }
+            to { width: 100px; height: 100px; }
+        }
+        .box {
+            height: 10px !important;
+            animation: slide 100ms linear infinite;
+        }
+        .box.wide {
+            width: 50px;
+        }
+        .box.still {
+            animation-name: none;
+        }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = rules(&stylesheet);
+        let mut tree = build_render_tree(root.clone(), &rules);
+        tree.animations.set_keyframes(&keyframes(&stylesheet));
+        update_animations(&mut tree, &rules, Duration::ZERO);
+
+        sample_animations(&mut tree, &rules, Duration::from_millis(20));
+        assert_eq!(style(&box_node(&tree), Property::Width), px(20.));
+        // important declarations win over the animations
+        assert_eq!(style(&box_node(&tree), Property::Height), px(10.));
+
+        // the animation keeps running when the element is restyled
+        Node::set_attribute(&target, "class", "box wide");
+        restyle_render_tree(&mut tree, &rules);
+        update_animations(&mut tree, &rules, Duration::from_millis(20));
+        assert_eq!(style(&box_node(&tree), Property::Width), px(20.));
+        sample_animations(&mut tree, &rules, Duration::from_millis(125));
+        assert_eq!(style(&box_node(&tree), Property::Width), px(25.));
+        assert!(tree.animations.is_running());
+
+        // removing the name stops the animation
+        Node::set_attribute(&target, "class", "box wide still");
+        restyle_render_tree(&mut tree, &rules);
+        update_animations(&mut tree, &rules, Duration::from_millis(125));
+        assert_eq!(style(&box_node(&tree), Property::Width), px(50.));
+        assert!(!tree.animations.is_running());
+    }
+
+    #[test]
+    fn skip_animations_with_reduced_motion() {
+        let doc = document();
+        let target = element("div.box", doc.clone(), vec![]);
+        let root = element("div", doc.clone(), vec![target.clone()]);
+
+        let css = r#"
+        @keyframes grow {
+            from { width: 10px; }
+            to { width: 110px; }
+        }
+        @keyframes spin {
+            to { height: 100px; }
+        }
+        .box {
+            width: 0px;
+            height: 0px;
+            animation: grow 100ms linear 50ms forwards, spin 1s linear infinite;
+        }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = rules(&stylesheet);
+        let mut tree = build_render_tree(root.clone(), &rules);
+        tree.animations.set_keyframes(&keyframes(&stylesheet));
+        tree.animations.set_reduced_motion(true);
+        update_animations(&mut tree, &rules, Duration::ZERO);
+
+        // the animations end as they start, filling forwards if they do
+        assert!(!tree.animations.is_running());
+        assert_eq!(style(&box_node(&tree), Property::Width), px(110.));
+        assert_eq!(style(&box_node(&tree), Property::Height), px(0.));
+    }
+}
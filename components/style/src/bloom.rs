@@ -0,0 +1,146 @@
+/// Counting bloom filter of the ancestors of the element being styled.
+///
+/// Each ancestor adds the hashes of its tag name, id and classes. A selector
+/// that requires an ancestor whose hash is not in the filter can't match, so
+/// it can be rejected without walking up the DOM tree. False positives are
+/// possible, false negatives are not.
+use dom::dom_ref::NodeRef;
+use dom::element::Element;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+
+const KEY_BITS: u32 = 12;
+const KEY_MASK: u32 = (1 << KEY_BITS) - 1;
+const FILTER_SIZE: usize = 1 << KEY_BITS;
+
+/// The kind of a hashed selector component, so that `#a`, `.a` and `a`
+/// don't share the same hash
+#[derive(Hash)]
+pub enum HashKind {
+    Tag,
+    Id,
+    Class,
+}
+
+pub fn selector_hash(kind: HashKind, value: &str) -> u32 {
+    let mut hasher = DefaultHasher::new();
+    kind.hash(&mut hasher);
+    match kind {
+        // the tag names of the HTML elements are case-insensitive, the
+        // other tag names share their hashes with a false positive
+        HashKind::Tag => value.to_ascii_lowercase().hash(&mut hasher),
+        _ => value.hash(&mut hasher),
+    }
+    hasher.finish() as u32
+}
+
+/// Hashes of the tag name, id and classes of an element
+pub fn element_hashes(element: &Element) -> Vec<u32> {
+    let mut hashes = vec![selector_hash(HashKind::Tag, &element.tag_name())];
+    if !element.id().is_empty() {
+        hashes.push(selector_hash(HashKind::Id, element.id()));
+    }
+    for class in element.class_list().iter() {
+        hashes.push(selector_hash(HashKind::Class, class));
+    }
+    hashes
+}
+
+pub struct AncestorFilter {
+    counters: Vec<u8>,
+    /// Hashes added for each pushed element, to be removed when it's popped
+    pushed: Vec<Vec<u32>>,
+    /// The shadow root of the tree of the ancestors, if they're in a shadow tree
+    scope: Option<NodeRef>,
+}
+
+impl AncestorFilter {
+    pub fn new() -> Self {
+        Self {
+            counters: vec![0; FILTER_SIZE],
+            pushed: Vec::new(),
+            scope: None,
+        }
+    }
+
+    /// A filter for the nodes of a shadow tree, whose ancestors outside of
+    /// the shadow root are not matched by the selectors
+    pub fn for_shadow_tree(shadow_root: &NodeRef) -> Self {
+        let mut filter = Self::new();
+        filter.scope = Some(shadow_root.clone());
+        filter
+    }
+
+    pub fn scope(&self) -> Option<&NodeRef> {
+        self.scope.as_ref()
+    }
+
+    /// Add an element as the innermost ancestor
+    pub fn push_element(&mut self, element: &Element) {
+        let hashes = element_hashes(element);
+        for hash in &hashes {
+            for slot in slots(*hash).iter() {
+                let counter = &mut self.counters[*slot];
+                // a saturated counter is never decremented again
+                *counter = counter.saturating_add(1);
+            }
+        }
+        self.pushed.push(hashes);
+    }
+
+    /// Remove the innermost ancestor
+    pub fn pop_element(&mut self) {
+        if let Some(hashes) = self.pushed.pop() {
+            for hash in hashes {
+                for slot in slots(hash).iter() {
+                    let counter = &mut self.counters[*slot];
+                    if *counter != u8::MAX {
+                        *counter -= 1;
+                    }
+                }
+            }
+        }
+    }
+
+    pub fn might_contain(&self, hash: u32) -> bool {
+        slots(hash).iter().all(|slot| self.counters[*slot] > 0)
+    }
+
+    pub fn might_contain_all(&self, hashes: &[u32]) -> bool {
+        hashes.iter().all(|hash| self.might_contain(*hash))
+    }
+}
+
+fn slots(hash: u32) -> [usize; 2] {
+    [
+        (hash & KEY_MASK) as usize,
+        ((hash >> KEY_BITS) & KEY_MASK) as usize,
+    ]
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dom::create_element;
+    use test_utils::dom_creator::document;
+
+    #[test]
+    fn push_and_pop_ancestors() {
+        let doc = document();
+        let div = create_element(doc.clone().downgrade(), "div");
+        div.borrow_mut()
+            .as_element_mut()
+            .set_attribute("class", "menu");
+
+        let mut filter = AncestorFilter::new();
+        filter.push_element(div.borrow().as_element());
+
+        assert!(filter.might_contain(selector_hash(HashKind::Tag, "div")));
+        assert!(filter.might_contain(selector_hash(HashKind::Class, "menu")));
+
+        filter.pop_element();
+
+        assert!(!filter.might_contain(selector_hash(HashKind::Tag, "div")));
+        assert!(!filter.might_contain(selector_hash(HashKind::Class, "menu")));
+    }
+}
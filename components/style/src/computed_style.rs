@@ -0,0 +1,268 @@
+/// The computed styles of a render node with a typed getter for each
+/// property, so the layout & painting don't have to match on `Value`.
+/// The values are computed once when the node is styled, a getter only
+/// reads the value of its property.
+use super::property_map::PropertyMap;
+use super::value_processing::{Property, Value, ValueRef};
+use super::values::prelude::*;
+use std::ops::{Deref, DerefMut};
+
+#[derive(Debug, Clone, PartialEq, Default)]
+pub struct ComputedStyle(PropertyMap<ValueRef>);
+
+impl ComputedStyle {
+    pub fn new(values: PropertyMap<ValueRef>) -> Self {
+        Self(values)
+    }
+
+    /// The computed value of a property. Every property has a computed
+    /// value once the node is styled.
+    pub fn value(&self, property: &Property) -> &Value {
+        self.0[property].inner()
+    }
+}
+
+impl Deref for ComputedStyle {
+    type Target = PropertyMap<ValueRef>;
+
+    fn deref(&self) -> &Self::Target {
+        &self.0
+    }
+}
+
+impl DerefMut for ComputedStyle {
+    fn deref_mut(&mut self) -> &mut Self::Target {
+        &mut self.0
+    }
+}
+
+/// Getters of the properties whose computed value is a single type
+macro_rules! typed_getters {
+    ($($getter:ident: $property:ident => $type:ident;)*) => {
+        impl ComputedStyle {
+            $(
+                pub fn $getter(&self) -> $type {
+                    match self.value(&Property::$property) {
+                        Value::$type(value) => value.clone(),
+                        value => unreachable!(
+                            "Unexpected computed value for {:?}: {:?}",
+                            Property::$property,
+                            value
+                        ),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+/// Getters of the properties computed to a length, a percentage or `auto`
+macro_rules! length_percentage_auto_getters {
+    ($($getter:ident: $property:ident;)*) => {
+        impl ComputedStyle {
+            $(
+                pub fn $getter(&self) -> LengthPercentageAuto {
+                    match self.value(&Property::$property) {
+                        Value::Auto => LengthPercentageAuto::Auto,
+                        value => LengthPercentageAuto::LengthPercentage(
+                            length_percentage(&Property::$property, value),
+                        ),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+/// Getters of the properties computed to a length, a percentage or
+/// `none`, `none` being `None`
+macro_rules! length_percentage_none_getters {
+    ($($getter:ident: $property:ident;)*) => {
+        impl ComputedStyle {
+            $(
+                pub fn $getter(&self) -> Option<LengthPercentage> {
+                    match self.value(&Property::$property) {
+                        Value::None => None,
+                        value => Some(length_percentage(&Property::$property, value)),
+                    }
+                }
+            )*
+        }
+    };
+}
+
+/// Getters of the properties computed to a length or a percentage
+macro_rules! length_percentage_getters {
+    ($($getter:ident: $property:ident;)*) => {
+        impl ComputedStyle {
+            $(
+                pub fn $getter(&self) -> LengthPercentage {
+                    length_percentage(&Property::$property, self.value(&Property::$property))
+                }
+            )*
+        }
+    };
+}
+
+/// Getters of the widths of the borders in px
+macro_rules! border_width_getters {
+    ($($getter:ident: $property:ident;)*) => {
+        impl ComputedStyle {
+            $(
+                /// The `thin`, `medium` & `thick` keywords count as 0 as long
+                /// as the layout doesn't take the border styles into account
+                pub fn $getter(&self) -> f32 {
+                    match self.value(&Property::$property) {
+                        Value::Length(length) => length.to_px(),
+                        _ => 0.,
+                    }
+                }
+            )*
+        }
+    };
+}
+
+fn length_percentage(property: &Property, value: &Value) -> LengthPercentage {
+    match value {
+        Value::Length(length) => LengthPercentage::Length(length.clone()),
+        Value::Percentage(percentage) => LengthPercentage::Percentage(percentage.clone()),
+        value => unreachable!("Unexpected computed value for {:?}: {:?}", property, value),
+    }
+}
+
+typed_getters! {
+    display: Display => Display;
+    float: Float => Float;
+    position: Position => Position;
+    direction: Direction => Direction;
+    visibility: Visibility => Visibility;
+    color: Color => Color;
+    background_color: BackgroundColor => Color;
+    background_image: BackgroundImage => BackgroundImage;
+    border_top_color: BorderTopColor => Color;
+    border_right_color: BorderRightColor => Color;
+    border_bottom_color: BorderBottomColor => Color;
+    border_left_color: BorderLeftColor => Color;
+    border_top_style: BorderTopStyle => BorderStyle;
+    border_right_style: BorderRightStyle => BorderStyle;
+    border_bottom_style: BorderBottomStyle => BorderStyle;
+    border_left_style: BorderLeftStyle => BorderStyle;
+    border_top_left_radius: BorderTopLeftRadius => BorderRadius;
+    border_top_right_radius: BorderTopRightRadius => BorderRadius;
+    border_bottom_left_radius: BorderBottomLeftRadius => BorderRadius;
+    border_bottom_right_radius: BorderBottomRightRadius => BorderRadius;
+    border_collapse: BorderCollapse => BorderCollapse;
+    border_spacing: BorderSpacing => BorderSpacing;
+    table_layout: TableLayout => TableLayout;
+    list_style_type: ListStyleType => ListStyleType;
+    list_style_position: ListStylePosition => ListStylePosition;
+    white_space: WhiteSpace => WhiteSpace;
+    overflow_wrap: OverflowWrap => OverflowWrap;
+    hyphens: Hyphens => Hyphens;
+    text_align: TextAlign => TextAlign;
+    vertical_align: VerticalAlign => VerticalAlign;
+    line_height: LineHeight => LineHeight;
+    text_decoration_line: TextDecorationLine => TextDecorationLine;
+    text_decoration_style: TextDecorationStyle => TextDecorationStyle;
+    text_decoration_color: TextDecorationColor => Color;
+    transform: Transform => Transform;
+    box_sizing: BoxSizing => BoxSizing;
+    content: Content => Content;
+    quotes: Quotes => Quotes;
+    content_visibility: ContentVisibility => ContentVisibility;
+    contain: Contain => Contain;
+    cursor: Cursor => Cursor;
+    font_size: FontSize => FontSize;
+    font_family: FontFamily => FontFamily;
+}
+
+length_percentage_auto_getters! {
+    width: Width;
+    height: Height;
+    min_width: MinWidth;
+    min_height: MinHeight;
+    margin_top: MarginTop;
+    margin_right: MarginRight;
+    margin_bottom: MarginBottom;
+    margin_left: MarginLeft;
+    top: Top;
+    right: Right;
+    bottom: Bottom;
+    left: Left;
+}
+
+length_percentage_none_getters! {
+    max_width: MaxWidth;
+    max_height: MaxHeight;
+}
+
+length_percentage_getters! {
+    padding_top: PaddingTop;
+    padding_right: PaddingRight;
+    padding_bottom: PaddingBottom;
+    padding_left: PaddingLeft;
+}
+
+border_width_getters! {
+    border_top_width: BorderTopWidth;
+    border_right_width: BorderRightWidth;
+    border_bottom_width: BorderBottomWidth;
+    border_left_width: BorderLeftWidth;
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_tree::build_render_tree;
+    use crate::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
+    use css::cssom::css_rule::CSSRule;
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+
+    #[test]
+    fn typed_getters() {
+        let doc = document();
+        let root = element("div.box", doc.clone(), vec![]);
+
+        let css = r#"
+        .box {
+            display: block;
+            width: 50%;
+            margin-left: auto;
+            padding-top: 4px;
+            border-width: 2px medium;
+            max-width: 80px;
+            box-sizing: border-box;
+        }
+        "#;
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let tree = build_render_tree(root, &rules);
+        let root = tree.root.unwrap();
+        let style = &root.borrow().properties;
+
+        assert_eq!(style.display(), Display::new_block());
+        assert_eq!(style.width().to_px(200.), 100.);
+        assert!(style.margin_left().is_auto());
+        assert_eq!(style.height(), LengthPercentageAuto::Auto);
+        assert_eq!(style.padding_top().to_px(200.), 4.);
+        assert_eq!(style.border_top_width(), 2.);
+        assert_eq!(style.border_right_width(), 0.);
+        assert_eq!(style.visibility(), Visibility::Visible);
+        assert_eq!(style.min_width(), LengthPercentageAuto::Auto);
+        assert_eq!(style.max_width().map(|width| width.to_px(200.)), Some(80.));
+        assert_eq!(style.max_height(), None);
+        assert_eq!(style.box_sizing(), BoxSizing::BorderBox);
+    }
+}
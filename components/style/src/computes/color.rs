@@ -20,8 +20,8 @@ pub fn compute_color(value: &Value, property: &Property, context: &mut ComputeCo
             }
             _ => {
                 // It's guarentee that all properties have a vlue
-                let color = context.properties.get(&Property::Color).unwrap().clone();
-                compute(&Property::Color, &color, context)
+                let color = &context.properties[&Property::Color];
+                compute(&Property::Color, color, context)
             }
         },
         _ => {
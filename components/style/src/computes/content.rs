@@ -0,0 +1,22 @@
+use crate::value_processing::ValueRef;
+use crate::value_processing::{ComputeContext, Value};
+
+/// Compute the content of a pseudo-element, resolving the attributes of
+/// its originating element, the parent of its box
+pub fn compute_content(value: &Value, context: &mut ComputeContext) -> ValueRef {
+    let value = match value {
+        Value::Content(content) => {
+            let originating = context.parent.as_ref().and_then(|parent| parent.upgrade());
+            Value::Content(content.resolve_attributes(|name| {
+                let originating = originating.as_ref()?.borrow();
+                let node = originating.node.borrow();
+                node.as_element_opt()?.get_attribute(name).cloned()
+            }))
+        }
+        _ => value.clone(),
+    };
+    if !context.style_cache.contains(&value) {
+        context.style_cache.insert(ValueRef::new(value.clone()));
+    }
+    context.style_cache.get(&value).unwrap().clone()
+}
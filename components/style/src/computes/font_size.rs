@@ -0,0 +1,21 @@
+use crate::value_processing::ValueRef;
+use crate::value_processing::{ComputeContext, Value};
+
+/// Compute the font size of an element from the font size of its parent
+pub fn compute_font_size(value: &Value, context: &mut ComputeContext) -> ValueRef {
+    let value = match value {
+        Value::FontSize(size) => {
+            let parent_size = context
+                .parent
+                .as_ref()
+                .and_then(|parent| parent.upgrade())
+                .map(|parent| parent.borrow().style().font_size().to_px());
+            Value::FontSize(size.compute(parent_size, context.lengths))
+        }
+        _ => value.clone(),
+    };
+    if !context.style_cache.contains(&value) {
+        context.style_cache.insert(ValueRef::new(value.clone()));
+    }
+    context.style_cache.get(&value).unwrap().clone()
+}
@@ -0,0 +1,21 @@
+use crate::value_processing::ValueRef;
+use crate::value_processing::{ComputeContext, Value};
+
+/// Compute the lengths of a value, resolving the root font & viewport
+/// relative lengths to px
+pub fn compute_length(value: &Value, context: &mut ComputeContext) -> ValueRef {
+    let lengths = context.lengths;
+    let value = match value {
+        Value::Length(length) => Value::Length(length.resolve(lengths)),
+        Value::BorderRadius(radius) => Value::BorderRadius(radius.resolve(lengths)),
+        Value::BorderSpacing(spacing) => Value::BorderSpacing(spacing.resolve(lengths)),
+        Value::Transform(transform) => Value::Transform(transform.resolve(lengths)),
+        Value::VerticalAlign(align) => Value::VerticalAlign(align.resolve(lengths)),
+        Value::LineHeight(height) => Value::LineHeight(height.resolve(lengths)),
+        _ => value.clone(),
+    };
+    if !context.style_cache.contains(&value) {
+        context.style_cache.insert(ValueRef::new(value.clone()));
+    }
+    context.style_cache.get(&value).unwrap().clone()
+}
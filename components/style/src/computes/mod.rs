@@ -1 +1,4 @@
 pub mod color;
+pub mod content;
+pub mod font_size;
+pub mod length;
@@ -0,0 +1,168 @@
+use super::{split_comma_separated, ExpandOutput};
+use crate::value_processing::{Property, Value};
+use crate::values::animation_direction::{AnimationDirection, AnimationDirectionList};
+use crate::values::animation_fill_mode::{AnimationFillMode, AnimationFillModeList};
+use crate::values::animation_name::{parse_keyframes_name, AnimationName};
+use crate::values::animation_play_state::{AnimationPlayState, AnimationPlayStateList};
+use crate::values::iteration_count::{IterationCount, IterationCountList};
+use crate::values::time::{Time, TimeList};
+use crate::values::timing_function::{TimingFunction, TimingFunctionList};
+use css::parser::structs::ComponentValue;
+
+const LONGHANDS: [Property; 8] = [
+    Property::AnimationName,
+    Property::AnimationDuration,
+    Property::AnimationTimingFunction,
+    Property::AnimationIterationCount,
+    Property::AnimationDirection,
+    Property::AnimationPlayState,
+    Property::AnimationDelay,
+    Property::AnimationFillMode,
+];
+
+/// Expand `animation: <single-animation>#` where a single animation is
+/// `<duration> || <easing-function> || <delay> || <iteration-count> ||
+/// <direction> || <fill-mode> || <play-state> || [ none | <keyframes-name> ]`.
+/// The first time of an animation is its duration, the second its delay.
+/// A word is only used as the name when it isn't a keyword of another property.
+pub fn expand_animation(values: &[&[ComponentValue]]) -> ExpandOutput {
+    if let [tokens] = values {
+        match Value::parse(&Property::AnimationName, tokens) {
+            Some(keyword @ Value::Inherit)
+            | Some(keyword @ Value::Initial)
+            | Some(keyword @ Value::Unset) => {
+                return Some(
+                    LONGHANDS
+                        .iter()
+                        .map(|property| (property.clone(), Some(keyword.clone())))
+                        .collect(),
+                );
+            }
+            _ => {}
+        }
+    }
+
+    let mut names = Vec::new();
+    let mut durations = Vec::new();
+    let mut timing_functions = Vec::new();
+    let mut iteration_counts = Vec::new();
+    let mut directions = Vec::new();
+    let mut play_states = Vec::new();
+    let mut delays = Vec::new();
+    let mut fill_modes = Vec::new();
+
+    for words in split_comma_separated(values) {
+        if words.is_empty() {
+            return None;
+        }
+        let mut name = None;
+        let mut duration = None;
+        let mut timing_function = None;
+        let mut iteration_count = None;
+        let mut direction = None;
+        let mut play_state = None;
+        let mut delay = None;
+        let mut fill_mode = None;
+
+        for word in words {
+            if let Some(time) = Time::parse(word) {
+                if duration.is_none() {
+                    duration = Some(time);
+                } else if delay.is_none() {
+                    delay = Some(time);
+                } else {
+                    return None;
+                }
+                continue;
+            }
+            if timing_function.is_none() {
+                if let Some(value) = TimingFunction::parse(word) {
+                    timing_function = Some(value);
+                    continue;
+                }
+            }
+            if iteration_count.is_none() {
+                if let Some(value) = IterationCount::parse(word) {
+                    iteration_count = Some(value);
+                    continue;
+                }
+            }
+            if direction.is_none() {
+                if let Some(value) = AnimationDirection::parse(word) {
+                    direction = Some(value);
+                    continue;
+                }
+            }
+            if fill_mode.is_none() {
+                if let Some(value) = AnimationFillMode::parse(word) {
+                    fill_mode = Some(value);
+                    continue;
+                }
+            }
+            if play_state.is_none() {
+                if let Some(value) = AnimationPlayState::parse(word) {
+                    play_state = Some(value);
+                    continue;
+                }
+            }
+            if name.is_some() {
+                return None;
+            }
+            name = Some(parse_keyframes_name(word)?);
+        }
+
+        names.push(name.unwrap_or(None));
+        durations.push(duration.unwrap_or_else(Time::zero));
+        timing_functions.push(timing_function.unwrap_or_else(TimingFunction::ease));
+        iteration_counts.push(iteration_count.unwrap_or(IterationCount::Number(1.0.into())));
+        directions.push(direction.unwrap_or(AnimationDirection::Normal));
+        play_states.push(play_state.unwrap_or(AnimationPlayState::Running));
+        delays.push(delay.unwrap_or_else(Time::zero));
+        fill_modes.push(fill_mode.unwrap_or(AnimationFillMode::None));
+    }
+
+    Some(vec![
+        (
+            Property::AnimationName,
+            Some(Value::AnimationName(AnimationName(names))),
+        ),
+        (
+            Property::AnimationDuration,
+            Some(Value::TimeList(TimeList(durations))),
+        ),
+        (
+            Property::AnimationTimingFunction,
+            Some(Value::TimingFunctionList(TimingFunctionList(
+                timing_functions,
+            ))),
+        ),
+        (
+            Property::AnimationIterationCount,
+            Some(Value::IterationCountList(IterationCountList(
+                iteration_counts,
+            ))),
+        ),
+        (
+            Property::AnimationDirection,
+            Some(Value::AnimationDirectionList(AnimationDirectionList(
+                directions,
+            ))),
+        ),
+        (
+            Property::AnimationPlayState,
+            Some(Value::AnimationPlayStateList(AnimationPlayStateList(
+                play_states,
+            ))),
+        ),
+        (
+            Property::AnimationDelay,
+            Some(Value::TimeList(TimeList(delays))),
+        ),
+        (
+            Property::AnimationFillMode,
+            Some(Value::AnimationFillModeList(AnimationFillModeList(
+                fill_modes,
+            ))),
+        ),
+    ])
+}
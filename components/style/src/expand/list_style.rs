@@ -0,0 +1,59 @@
+use super::ExpandOutput;
+use crate::value_processing::{Property, Value};
+use css::parser::structs::ComponentValue;
+
+/// Expand `list-style: <type> || <position>`. The omitted longhands are
+/// reset to their initial value.
+pub fn expand_list_style(values: &[&[ComponentValue]]) -> ExpandOutput {
+    let mut list_style_type = None;
+    let mut list_style_position = None;
+
+    if let [tokens] = values {
+        match Value::parse(&Property::ListStylePosition, tokens) {
+            Some(keyword @ Value::Inherit)
+            | Some(keyword @ Value::Initial)
+            | Some(keyword @ Value::Unset) => {
+                return Some(vec![
+                    (Property::ListStyleType, Some(keyword.clone())),
+                    (Property::ListStylePosition, Some(keyword)),
+                ]);
+            }
+            _ => {}
+        }
+    }
+
+    for tokens in values {
+        if let Some(position) = Value::parse(&Property::ListStylePosition, tokens) {
+            if list_style_position.is_none() {
+                list_style_position = Some(position);
+                continue;
+            } else {
+                return None;
+            }
+        }
+        if let Some(style_type) = Value::parse(&Property::ListStyleType, tokens) {
+            if list_style_type.is_none() {
+                list_style_type = Some(style_type);
+                continue;
+            } else {
+                return None;
+            }
+        }
+        return None;
+    }
+
+    if list_style_type.is_none() && list_style_position.is_none() {
+        return None;
+    }
+
+    Some(vec![
+        (
+            Property::ListStyleType,
+            list_style_type.or(Some(Value::Initial)),
+        ),
+        (
+            Property::ListStylePosition,
+            list_style_position.or(Some(Value::Initial)),
+        ),
+    ])
+}
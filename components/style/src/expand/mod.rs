@@ -1,21 +1,55 @@
 use crate::value_processing::{Property, Value};
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
 pub type ExpandOutput = Option<Vec<(Property, Option<Value>)>>;
 
+mod animation;
 mod border;
 mod border_color;
 mod border_radius;
 mod border_style;
 mod border_width;
+mod list_style;
 mod margin;
 mod padding;
+mod text_decoration;
+mod transition;
 
 pub(crate) mod prelude {
+    pub use super::animation::expand_animation;
     pub use super::border::expand_border;
     pub use super::border_color::expand_border_color;
     pub use super::border_radius::expand_border_radius;
     pub use super::border_style::expand_border_style;
     pub use super::border_width::expand_border_width;
+    pub use super::list_style::expand_list_style;
     pub use super::margin::expand_margin;
     pub use super::padding::expand_padding;
+    pub use super::text_decoration::expand_text_decoration;
+    pub use super::transition::expand_transition;
     pub use super::ExpandOutput;
 }
+
+/// Group the words of a comma separated list of values, e.g. the
+/// transitions of `transition`, by the item of the list they belong to
+fn split_comma_separated<'a>(values: &[&'a [ComponentValue]]) -> Vec<Vec<&'a [ComponentValue]>> {
+    let mut items = vec![Vec::new()];
+
+    for tokens in values {
+        let mut parts = tokens
+            .split(|token| *token == ComponentValue::PerservedToken(Token::Comma))
+            .peekable();
+        while let Some(part) = parts.next() {
+            if !part.is_empty() {
+                items.last_mut().unwrap().push(part);
+            }
+            // every part but the last one is followed by a comma
+            if parts.peek().is_some() {
+                items.push(Vec::new());
+            }
+        }
+    }
+
+    items
+}
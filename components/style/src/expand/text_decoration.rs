@@ -0,0 +1,73 @@
+use super::ExpandOutput;
+use crate::value_processing::{Property, Value};
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Expand `text-decoration: <line> || <style> || <color>`. The omitted
+/// longhands are reset to their initial value.
+pub fn expand_text_decoration(values: &[&[ComponentValue]]) -> ExpandOutput {
+    let mut line_tokens: Vec<ComponentValue> = Vec::new();
+    let mut style = None;
+    let mut color = None;
+
+    if let [tokens] = values {
+        match Value::parse(&Property::TextDecorationStyle, tokens) {
+            Some(keyword @ Value::Inherit)
+            | Some(keyword @ Value::Initial)
+            | Some(keyword @ Value::Unset) => {
+                return Some(vec![
+                    (Property::TextDecorationLine, Some(keyword.clone())),
+                    (Property::TextDecorationStyle, Some(keyword.clone())),
+                    (Property::TextDecorationColor, Some(keyword)),
+                ]);
+            }
+            _ => {}
+        }
+    }
+
+    for tokens in values {
+        if let Some(value) = Value::parse(&Property::TextDecorationStyle, tokens) {
+            if style.is_none() {
+                style = Some(value);
+                continue;
+            } else {
+                return None;
+            }
+        }
+        if let Some(value) = Value::parse(&Property::TextDecorationColor, tokens) {
+            if color.is_none() {
+                color = Some(value);
+                continue;
+            } else {
+                return None;
+            }
+        }
+        // the line keywords are parsed together
+        if !line_tokens.is_empty() {
+            line_tokens.push(ComponentValue::PerservedToken(Token::Whitespace));
+        }
+        line_tokens.extend(tokens.iter().cloned());
+    }
+
+    let line = if line_tokens.is_empty() {
+        None
+    } else {
+        Some(Value::parse(&Property::TextDecorationLine, &line_tokens)?)
+    };
+
+    if line.is_none() && style.is_none() && color.is_none() {
+        return None;
+    }
+
+    Some(vec![
+        (Property::TextDecorationLine, line.or(Some(Value::Initial))),
+        (
+            Property::TextDecorationStyle,
+            style.or(Some(Value::Initial)),
+        ),
+        (
+            Property::TextDecorationColor,
+            color.or(Some(Value::Initial)),
+        ),
+    ])
+}
@@ -0,0 +1,111 @@
+use super::{split_comma_separated, ExpandOutput};
+use crate::value_processing::{Property, Value};
+use crate::values::time::{Time, TimeList};
+use crate::values::timing_function::{TimingFunction, TimingFunctionList};
+use crate::values::transition_property::{SingleTransitionProperty, TransitionProperty};
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Expand `transition: <single-transition>#` where a single transition is
+/// `[ none | <property> ] || <duration> || <easing-function> || <delay>`.
+/// The first time of a transition is its duration, the second its delay.
+pub fn expand_transition(values: &[&[ComponentValue]]) -> ExpandOutput {
+    if let [tokens] = values {
+        match Value::parse(&Property::TransitionProperty, tokens) {
+            Some(keyword @ Value::Inherit)
+            | Some(keyword @ Value::Initial)
+            | Some(keyword @ Value::Unset) => {
+                return Some(vec![
+                    (Property::TransitionProperty, Some(keyword.clone())),
+                    (Property::TransitionDuration, Some(keyword.clone())),
+                    (Property::TransitionTimingFunction, Some(keyword.clone())),
+                    (Property::TransitionDelay, Some(keyword)),
+                ]);
+            }
+            _ => {}
+        }
+    }
+
+    let transitions = split_comma_separated(values);
+
+    let mut properties = Vec::new();
+    let mut durations = Vec::new();
+    let mut timing_functions = Vec::new();
+    let mut delays = Vec::new();
+    let mut has_none = false;
+
+    for words in &transitions {
+        if words.is_empty() {
+            return None;
+        }
+        let mut property = None;
+        let mut duration = None;
+        let mut timing_function = None;
+        let mut delay = None;
+
+        for word in words {
+            if let Some(time) = Time::parse(word) {
+                if duration.is_none() {
+                    duration = Some(time);
+                } else if delay.is_none() {
+                    delay = Some(time);
+                } else {
+                    return None;
+                }
+                continue;
+            }
+            if let Some(function) = TimingFunction::parse(word) {
+                if timing_function.is_some() {
+                    return None;
+                }
+                timing_function = Some(function);
+                continue;
+            }
+            if property.is_some() {
+                return None;
+            }
+            property = match word {
+                [ComponentValue::PerservedToken(Token::Ident(name))]
+                    if name.eq_ignore_ascii_case("none") =>
+                {
+                    has_none = true;
+                    Some(SingleTransitionProperty::All)
+                }
+                _ => Some(SingleTransitionProperty::parse(word)?),
+            };
+        }
+
+        properties.push(property.unwrap_or(SingleTransitionProperty::All));
+        durations.push(duration.unwrap_or_else(Time::zero));
+        timing_functions.push(timing_function.unwrap_or_else(TimingFunction::ease));
+        delays.push(delay.unwrap_or_else(Time::zero));
+    }
+
+    // none is only valid for a single transition
+    let property = match (has_none, properties.len()) {
+        (true, 1) => TransitionProperty::None,
+        (true, _) => return None,
+        _ => TransitionProperty::List(properties),
+    };
+
+    Some(vec![
+        (
+            Property::TransitionProperty,
+            Some(Value::TransitionProperty(property)),
+        ),
+        (
+            Property::TransitionDuration,
+            Some(Value::TimeList(TimeList(durations))),
+        ),
+        (
+            Property::TransitionTimingFunction,
+            Some(Value::TimingFunctionList(TimingFunctionList(
+                timing_functions,
+            ))),
+        ),
+        (
+            Property::TransitionDelay,
+            Some(Value::TimeList(TimeList(delays))),
+        ),
+    ])
+}
@@ -5,6 +5,20 @@ lazy_static! {
     pub static ref INHERITABLES: HashSet<Property> = {
         let mut set = HashSet::new();
         set.insert(Property::Color);
+        set.insert(Property::Visibility);
+        set.insert(Property::ListStyleType);
+        set.insert(Property::ListStylePosition);
+        set.insert(Property::BorderCollapse);
+        set.insert(Property::BorderSpacing);
+        set.insert(Property::WhiteSpace);
+        set.insert(Property::OverflowWrap);
+        set.insert(Property::Hyphens);
+        set.insert(Property::TextAlign);
+        set.insert(Property::LineHeight);
+        set.insert(Property::Quotes);
+        set.insert(Property::Cursor);
+        set.insert(Property::FontSize);
+        set.insert(Property::FontFamily);
         set
     };
 }
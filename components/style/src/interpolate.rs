@@ -0,0 +1,128 @@
+/// Interpolation of computed values, used to animate the values
+/// between two styles.
+/// https://www.w3.org/TR/css-values-4/#combining-values
+use super::value_processing::Value;
+use super::values::color::Color;
+use super::values::font_size::FontSize;
+use super::values::length::Length;
+use super::values::percentage::Percentage;
+
+/// The value at a progress between two computed values, or `None` if
+/// the values can't be interpolated & change discretely instead.
+/// The progress can be outside of [0, 1] for some easing functions.
+pub fn interpolate(from: &Value, to: &Value, progress: f32) -> Option<Value> {
+    match (from, to) {
+        (Value::Color(from), Value::Color(to)) => {
+            interpolate_color(from, to, progress).map(Value::Color)
+        }
+        (Value::Length(from), Value::Length(to)) if from.unit == to.unit => Some(Value::Length(
+            Length::new(lerp(*from.value, *to.value, progress), from.unit.clone()),
+        )),
+        (Value::Percentage(from), Value::Percentage(to)) => Some(Value::Percentage(Percentage(
+            lerp(*from.0, *to.0, progress).into(),
+        ))),
+        (Value::FontSize(FontSize::Computed(from)), Value::FontSize(FontSize::Computed(to))) => {
+            Some(Value::FontSize(FontSize::Computed(
+                lerp(**from, **to, progress).into(),
+            )))
+        }
+        _ => None,
+    }
+}
+
+/// Whether a property with these values can be animated smoothly
+pub fn can_interpolate(from: &Value, to: &Value) -> bool {
+    interpolate(from, to, 0.).is_some()
+}
+
+fn lerp(from: f32, to: f32, progress: f32) -> f32 {
+    from + (to - from) * progress
+}
+
+/// Colors are interpolated with premultiplied alpha, so a transparent
+/// color doesn't tint the other color during the transition
+fn interpolate_color(from: &Color, to: &Color, progress: f32) -> Option<Color> {
+    match (from, to) {
+        (Color::Rgba(r1, g1, b1, a1), Color::Rgba(r2, g2, b2, a2)) => {
+            let alpha = lerp(**a1, **a2, progress).max(0.).min(255.);
+            if alpha == 0. {
+                return Some(Color::transparent());
+            }
+            let channel = |from: f32, to: f32| {
+                let premultiplied = lerp(from * **a1, to * **a2, progress);
+                (premultiplied / alpha).max(0.).min(255.)
+            };
+            Some(Color::Rgba(
+                channel(**r1, **r2).into(),
+                channel(**g1, **g2).into(),
+                channel(**b1, **b2).into(),
+                alpha.into(),
+            ))
+        }
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::length::LengthUnit;
+
+    #[test]
+    fn interpolate_values() {
+        assert_eq!(
+            interpolate(
+                &Value::Length(Length::new_px(10.)),
+                &Value::Length(Length::new_px(30.)),
+                0.25
+            ),
+            Some(Value::Length(Length::new_px(15.)))
+        );
+        assert_eq!(
+            interpolate(
+                &Value::Length(Length::new_px(10.)),
+                &Value::Length(Length::new(2., LengthUnit::Em)),
+                0.5
+            ),
+            None
+        );
+        assert_eq!(
+            interpolate(&Value::Auto, &Value::Length(Length::new_px(30.)), 0.5),
+            None
+        );
+
+        let red = Value::Color(Color::Rgba(
+            255.0.into(),
+            0.0.into(),
+            0.0.into(),
+            255.0.into(),
+        ));
+        let blue = Value::Color(Color::Rgba(
+            0.0.into(),
+            0.0.into(),
+            255.0.into(),
+            255.0.into(),
+        ));
+        assert_eq!(
+            interpolate(&red, &blue, 0.5),
+            Some(Value::Color(Color::Rgba(
+                127.5.into(),
+                0.0.into(),
+                127.5.into(),
+                255.0.into()
+            )))
+        );
+
+        // the color of a transparent color doesn't show
+        let transparent = Value::Color(Color::transparent());
+        assert_eq!(
+            interpolate(&transparent, &red, 0.5),
+            Some(Value::Color(Color::Rgba(
+                255.0.into(),
+                0.0.into(),
+                0.0.into(),
+                127.5.into()
+            )))
+        );
+    }
+}
@@ -1,8 +1,19 @@
+pub mod animation;
+pub mod bloom;
+pub mod computed_style;
 pub mod computes;
 pub mod expand;
 pub mod inheritable;
+pub mod interpolate;
+pub mod media;
+pub mod page;
+pub mod property_map;
 pub mod render_tree;
+pub mod restyle;
+pub mod rule_index;
 pub mod selector_matching;
+pub mod supports;
+pub mod transition;
 pub mod value_processing;
 pub mod values;
 
@@ -0,0 +1,229 @@
+/// The evaluation of the media queries of the `@media` rules against the
+/// environment the documents are rendered in.
+/// https://www.w3.org/TR/mediaqueries-5/
+use super::values::length::{Length, LengthContext, LengthUnit, DEFAULT_FONT_SIZE};
+use css::cssom::media_rule::{MediaFeature, MediaQueryList};
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+use std::str::FromStr;
+
+/// The color scheme the user prefers the pages in
+/// https://www.w3.org/TR/mediaqueries-5/#prefers-color-scheme
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColorScheme {
+    Light,
+    Dark,
+}
+
+/// The values of the media features of the environment
+#[derive(Debug, Clone, PartialEq)]
+pub struct MediaFeatures {
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+    pub color_scheme: ColorScheme,
+    /// Whether the user prefers pages with less motion
+    /// https://www.w3.org/TR/mediaqueries-5/#prefers-reduced-motion
+    pub reduced_motion: bool,
+    /// Whether the colors of the pages are forced to a limited palette
+    /// https://www.w3.org/TR/mediaqueries-5/#forced-colors
+    pub forced_colors: bool,
+}
+
+impl Default for ColorScheme {
+    fn default() -> Self {
+        ColorScheme::Light
+    }
+}
+
+impl FromStr for ColorScheme {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        match value.trim().to_ascii_lowercase().as_str() {
+            "light" => Ok(ColorScheme::Light),
+            "dark" => Ok(ColorScheme::Dark),
+            _ => Err(format!(
+                "Invalid color scheme {:?}, expected light or dark",
+                value
+            )),
+        }
+    }
+}
+
+impl MediaFeatures {
+    /// The features of a viewport, with the default preferences
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        Self {
+            viewport_width,
+            viewport_height,
+            color_scheme: ColorScheme::default(),
+            reduced_motion: false,
+            forced_colors: false,
+        }
+    }
+
+    /// Whether the media feature of a query matches the environment. The
+    /// unknown features & the invalid values never match.
+    pub fn feature_matches(&self, feature: &MediaFeature) -> bool {
+        let (prefix, name) = match feature.name.split_once('-') {
+            Some((prefix, name)) if prefix == "min" || prefix == "max" => (Some(prefix), name),
+            _ => (None, feature.name.as_str()),
+        };
+        let value = feature.value.as_deref();
+
+        match name {
+            "width" | "height" => {
+                let size = if name == "width" {
+                    self.viewport_width
+                } else {
+                    self.viewport_height
+                };
+                let length = match value.and_then(|value| self.length_px(value)) {
+                    Some(length) => length,
+                    // `(width)` is true when the viewport isn't empty
+                    None => return value.is_none() && prefix.is_none() && size > 0.,
+                };
+                // like the browsers, `(width: 800px)` only matches exactly
+                #[allow(clippy::float_cmp)]
+                let matches = match prefix {
+                    Some("min") => size >= length,
+                    Some("max") => size <= length,
+                    _ => size == length,
+                };
+                matches
+            }
+            _ if prefix.is_some() => false,
+            "orientation" => {
+                let landscape = self.viewport_width > self.viewport_height;
+                match keyword(value) {
+                    Some(keyword) if keyword == "portrait" => !landscape,
+                    Some(keyword) if keyword == "landscape" => landscape,
+                    Some(_) => false,
+                    None => value.is_none(),
+                }
+            }
+            "prefers-color-scheme" => match keyword(value) {
+                Some(keyword) => keyword.parse() == Ok(self.color_scheme),
+                // the preference is always known
+                None => value.is_none(),
+            },
+            "prefers-reduced-motion" => match keyword(value).as_deref() {
+                Some("reduce") => self.reduced_motion,
+                Some("no-preference") => !self.reduced_motion,
+                Some(_) => false,
+                None => value.is_none() && self.reduced_motion,
+            },
+            "forced-colors" => match keyword(value).as_deref() {
+                Some("active") => self.forced_colors,
+                Some("none") => !self.forced_colors,
+                Some(_) => false,
+                None => value.is_none() && self.forced_colors,
+            },
+            _ => false,
+        }
+    }
+
+    /// A length of a media feature in px. The font relative lengths are
+    /// relative to the initial font size.
+    fn length_px(&self, values: &[ComponentValue]) -> Option<f32> {
+        if values.len() != 1 {
+            return None;
+        }
+        let length = Length::parse(values)?;
+        match length.unit {
+            LengthUnit::Em | LengthUnit::Rem => Some(*length.value * DEFAULT_FONT_SIZE),
+            LengthUnit::Ex => Some(*length.value * DEFAULT_FONT_SIZE / 2.),
+            _ => {
+                let context = LengthContext::new(self.viewport_width, self.viewport_height);
+                length
+                    .absolute_px()
+                    .or_else(|| length.resolve(&context).absolute_px())
+            }
+        }
+    }
+}
+
+/// The keyword value of a media feature
+fn keyword(value: Option<&[ComponentValue]>) -> Option<String> {
+    match value {
+        Some([ComponentValue::PerservedToken(Token::Ident(keyword))]) => {
+            Some(keyword.to_ascii_lowercase())
+        }
+        _ => None,
+    }
+}
+
+/// Whether the media queries of a `@media` rule match the environment
+pub fn matches(queries: &MediaQueryList, features: &MediaFeatures) -> bool {
+    queries.evaluate(&|feature| features.feature_matches(feature))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::cssom::css_rule::CSSRule;
+    use test_utils::css::parse_stylesheet;
+
+    fn evaluate(queries: &str, features: &MediaFeatures) -> bool {
+        let stylesheet = parse_stylesheet(&format!("@media {} {{}}", queries));
+        match &stylesheet[0] {
+            CSSRule::Media(media) => matches(&media.queries, features),
+            rule => panic!("Expected a @media rule, got {:?}", rule),
+        }
+    }
+
+    #[test]
+    fn match_viewport_size() {
+        let features = MediaFeatures::new(800., 600.);
+        assert!(evaluate("screen and (min-width: 600px)", &features));
+        assert!(evaluate("(max-width: 50em)", &features));
+        assert!(!evaluate("(min-width: 1000px)", &features));
+        assert!(evaluate(
+            "(height: 600px) and (orientation: landscape)",
+            &features
+        ));
+        assert!(!evaluate("print", &features));
+        assert!(evaluate("not print", &features));
+        assert!(evaluate("print, (width)", &features));
+        assert!(!evaluate(
+            "(min-width: 600px) and (width >= 600px)",
+            &features
+        ));
+        assert!(!evaluate("(max-orientation: portrait)", &features));
+    }
+
+    #[test]
+    fn match_color_scheme() {
+        let light = MediaFeatures::new(800., 600.);
+        let dark = MediaFeatures {
+            color_scheme: ColorScheme::Dark,
+            ..MediaFeatures::new(800., 600.)
+        };
+        assert!(!evaluate("(prefers-color-scheme: dark)", &light));
+        assert!(evaluate("(prefers-color-scheme: dark)", &dark));
+        assert!(evaluate("not all and (prefers-color-scheme: dark)", &light));
+        assert!(evaluate("(prefers-color-scheme)", &light));
+        assert!(!evaluate("(prefers-color-scheme: blue)", &dark));
+    }
+
+    #[test]
+    fn match_user_preferences() {
+        let default = MediaFeatures::new(800., 600.);
+        let reduced = MediaFeatures {
+            reduced_motion: true,
+            forced_colors: true,
+            ..MediaFeatures::new(800., 600.)
+        };
+        assert!(!evaluate("(prefers-reduced-motion)", &default));
+        assert!(evaluate(
+            "(prefers-reduced-motion: no-preference)",
+            &default
+        ));
+        assert!(evaluate("(prefers-reduced-motion: reduce)", &reduced));
+        assert!(evaluate("(prefers-reduced-motion)", &reduced));
+        assert!(evaluate("(forced-colors: none)", &default));
+        assert!(!evaluate("(forced-colors)", &default));
+        assert!(evaluate("(forced-colors: active)", &reduced));
+        assert!(!evaluate("(forced-colors: blue)", &reduced));
+    }
+}
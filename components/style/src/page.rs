@@ -0,0 +1,254 @@
+/// The page box of the paginated documents, sized by the `size` &
+/// `margin` declarations of the `@page` rules
+/// https://www.w3.org/TR/css-page-3/#page-model
+use super::values::length::{Length, LengthUnit, DEFAULT_FONT_SIZE};
+use css::cssom::page_rule::PageRule;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The pixels of a millimeter, the unit of the sizes of paper
+const PIXELS_PER_MM: f32 = 96. / 25.4;
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PageStyle {
+    pub width: f32,
+    pub height: f32,
+    /// The margins of the page as (top, right, bottom, left)
+    pub margin: (f32, f32, f32, f32),
+}
+
+impl PageStyle {
+    /// The style of the pages without `@page` rules, as large as the
+    /// viewport & without margins
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            margin: (0., 0., 0., 0.),
+        }
+    }
+
+    /// The style of the pages from the `@page` rules applying to every
+    /// page, in the order of the cascade. The rules with a page selector
+    /// aren't supported since the pages are laid out at the same width.
+    pub fn from_rules(rules: &[&PageRule], default_size: (f32, f32)) -> Self {
+        let mut style = Self::new(default_size.0, default_size.1);
+        let declarations = rules
+            .iter()
+            .filter(|rule| rule.pseudo_classes.is_empty())
+            .flat_map(|rule| &rule.declarations);
+
+        let mut margin = [None; 4];
+        for declaration in declarations {
+            let values = declaration
+                .value
+                .iter()
+                .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+                .collect::<Vec<_>>();
+            match declaration.name.as_str() {
+                "size" => {
+                    if let Some((width, height)) = parse_size(&values, default_size) {
+                        style.width = width;
+                        style.height = height;
+                    }
+                }
+                "margin" => {
+                    if let Some(values) = expand_margin(&values) {
+                        margin = values.map(Some);
+                    }
+                }
+                name => {
+                    let side = match name {
+                        "margin-top" => 0,
+                        "margin-right" => 1,
+                        "margin-bottom" => 2,
+                        "margin-left" => 3,
+                        _ => continue,
+                    };
+                    if let [value] = values.as_slice() {
+                        if let Some(value) = parse_margin(value) {
+                            margin[side] = Some(value);
+                        }
+                    }
+                }
+            }
+        }
+
+        // the percentages of the margins are relative to the size of the page
+        let resolve = |margin: Option<Margin>, size: f32| match margin {
+            Some(Margin::Px(value)) => value,
+            Some(Margin::Percentage(value)) => value / 100. * size,
+            None => 0.,
+        };
+        style.margin = (
+            resolve(margin[0], style.height),
+            resolve(margin[1], style.width),
+            resolve(margin[2], style.height),
+            resolve(margin[3], style.width),
+        );
+        style
+    }
+
+    /// The size of the area of the page the content is laid out in
+    pub fn content_size(&self) -> (f32, f32) {
+        let (top, right, bottom, left) = self.margin;
+        (
+            (self.width - left - right).max(1.),
+            (self.height - top - bottom).max(1.),
+        )
+    }
+}
+
+#[derive(Debug, Clone, Copy)]
+enum Margin {
+    Px(f32),
+    Percentage(f32),
+}
+
+/// Parse `auto | <length>{1,2} | [ <page-size> || [ portrait | landscape ] ]`
+/// https://www.w3.org/TR/css-page-3/#page-size-prop
+fn parse_size(values: &[&ComponentValue], default_size: (f32, f32)) -> Option<(f32, f32)> {
+    let lengths = values
+        .iter()
+        .map(|value| parse_length(value))
+        .collect::<Option<Vec<f32>>>();
+    match lengths.as_deref() {
+        Some([size]) => return Some((*size, *size)),
+        Some([width, height]) => return Some((*width, *height)),
+        _ => {}
+    }
+
+    let keywords = values
+        .iter()
+        .map(|value| match value {
+            ComponentValue::PerservedToken(Token::Ident(keyword)) => {
+                Some(keyword.to_ascii_lowercase())
+            }
+            _ => None,
+        })
+        .collect::<Option<Vec<String>>>()?;
+    if let [keyword] = keywords.as_slice() {
+        if keyword == "auto" {
+            return Some(default_size);
+        }
+    }
+
+    let mut size = None;
+    let mut orientation = None;
+    for keyword in &keywords {
+        match keyword.as_str() {
+            "portrait" | "landscape" if orientation.is_none() => orientation = Some(keyword),
+            keyword if size.is_none() => size = Some(paper_size(keyword)?),
+            _ => return None,
+        }
+    }
+
+    let (width, height) = size.unwrap_or(default_size);
+    let (short, long) = (width.min(height), width.max(height));
+    match orientation.map(|orientation| orientation.as_str()) {
+        Some("portrait") => Some((short, long)),
+        Some("landscape") => Some((long, short)),
+        _ => Some((width, height)),
+    }
+}
+
+/// The portrait size of a paper in pixels
+/// https://www.w3.org/TR/css-page-3/#typedef-page-size-page-size
+fn paper_size(name: &str) -> Option<(f32, f32)> {
+    let (width, height) = match name {
+        "a5" => (148., 210.),
+        "a4" => (210., 297.),
+        "a3" => (297., 420.),
+        "b5" => (176., 250.),
+        "b4" => (250., 353.),
+        "jis-b5" => (182., 257.),
+        "jis-b4" => (257., 364.),
+        "letter" => (215.9, 279.4),
+        "legal" => (215.9, 355.6),
+        "ledger" => (279.4, 431.8),
+        _ => return None,
+    };
+    Some((width * PIXELS_PER_MM, height * PIXELS_PER_MM))
+}
+
+/// Expand the values of `margin` into the margins of the sides, like
+/// the `margin` of the boxes
+fn expand_margin(values: &[&ComponentValue]) -> Option<[Margin; 4]> {
+    let margins = values
+        .iter()
+        .map(|value| parse_margin(value))
+        .collect::<Option<Vec<Margin>>>()?;
+    match margins.as_slice() {
+        [all] => Some([*all, *all, *all, *all]),
+        [y, x] => Some([*y, *x, *y, *x]),
+        [top, x, bottom] => Some([*top, *x, *bottom, *x]),
+        [top, right, bottom, left] => Some([*top, *right, *bottom, *left]),
+        _ => None,
+    }
+}
+
+fn parse_margin(value: &ComponentValue) -> Option<Margin> {
+    match value {
+        ComponentValue::PerservedToken(Token::Percentage(value)) => {
+            Some(Margin::Percentage(*value))
+        }
+        ComponentValue::PerservedToken(Token::Ident(keyword))
+            if keyword.eq_ignore_ascii_case("auto") =>
+        {
+            Some(Margin::Px(0.))
+        }
+        value => parse_length(value).map(Margin::Px),
+    }
+}
+
+/// A length in pixels. The font relative lengths are relative to the
+/// default font size since the page has no element.
+fn parse_length(value: &ComponentValue) -> Option<f32> {
+    let length = Length::parse(std::slice::from_ref(value))?;
+    length
+        .absolute_px()
+        .or_else(|| match length.unit {
+            LengthUnit::Em | LengthUnit::Rem => Some(*length.value * DEFAULT_FONT_SIZE),
+            _ => None,
+        })
+        .filter(|value| *value >= 0.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::cssom::css_rule::CSSRule;
+    use test_utils::css::parse_stylesheet;
+
+    fn page_style(css: &str) -> PageStyle {
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Page(page) => Some(page),
+                _ => None,
+            })
+            .collect::<Vec<_>>();
+        PageStyle::from_rules(&rules, (800., 600.))
+    }
+
+    #[test]
+    fn size_pages() {
+        let style = page_style("@page { size: 8in 4in; margin: 0.5in 10% }");
+        assert_eq!(style.width, 768.);
+        assert_eq!(style.height, 384.);
+        assert_eq!(style.margin, (48., 76.8, 48., 76.8));
+        assert_eq!(style.content_size(), (614.4, 288.));
+
+        let style = page_style(
+            "@page { size: A4 landscape; margin: 1em } @page :first { size: letter }
+             @page { margin-left: 2em; size: nonsense }",
+        );
+        assert_eq!(style.width.round(), 1123.);
+        assert_eq!(style.height.round(), 794.);
+        assert_eq!(style.margin, (16., 16., 16., 32.));
+
+        let style = page_style("@page { size: portrait }");
+        assert_eq!((style.width, style.height), (600., 800.));
+    }
+}
@@ -0,0 +1,128 @@
+/// A map with a slot for every CSS property, indexed by the
+/// discriminant of the property. Used to store the values of a node
+/// instead of a `HashMap`, so a lookup is an array index and no
+/// property is hashed.
+use super::value_processing::Property;
+use std::iter::FromIterator;
+use std::ops::Index;
+use strum::{EnumCount, IntoEnumIterator};
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct PropertyMap<T>(Box<[Option<T>]>);
+
+fn index_of(property: &Property) -> usize {
+    property.clone() as usize
+}
+
+impl<T> PropertyMap<T> {
+    pub fn new() -> Self {
+        Self(
+            std::iter::repeat_with(|| None)
+                .take(Property::COUNT)
+                .collect(),
+        )
+    }
+
+    pub fn get(&self, property: &Property) -> Option<&T> {
+        self.0[index_of(property)].as_ref()
+    }
+
+    pub fn contains_key(&self, property: &Property) -> bool {
+        self.get(property).is_some()
+    }
+
+    /// Set the value of a property, returning its previous value
+    pub fn insert(&mut self, property: Property, value: T) -> Option<T> {
+        self.0[index_of(&property)].replace(value)
+    }
+
+    pub fn remove(&mut self, property: &Property) -> Option<T> {
+        self.0[index_of(property)].take()
+    }
+
+    pub fn len(&self) -> usize {
+        self.0.iter().filter(|value| value.is_some()).count()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.0.iter().all(|value| value.is_none())
+    }
+
+    /// The bytes of the slots of the map, which has a slot for every
+    /// property even without a value
+    pub fn heap_size(&self) -> usize {
+        std::mem::size_of_val(&*self.0)
+    }
+
+    /// The properties with a value, in the order of `Property`
+    pub fn iter(&self) -> impl Iterator<Item = (Property, &T)> {
+        Property::iter()
+            .zip(self.0.iter())
+            .filter_map(|(property, value)| Some((property, value.as_ref()?)))
+    }
+}
+
+impl<T> Default for PropertyMap<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<T> Index<&Property> for PropertyMap<T> {
+    type Output = T;
+
+    fn index(&self, property: &Property) -> &T {
+        self.get(property)
+            .unwrap_or_else(|| panic!("No value for property {:?}", property))
+    }
+}
+
+impl<T> FromIterator<(Property, T)> for PropertyMap<T> {
+    fn from_iter<I: IntoIterator<Item = (Property, T)>>(iter: I) -> Self {
+        let mut map = Self::new();
+        for (property, value) in iter {
+            map.insert(property, value);
+        }
+        map
+    }
+}
+
+impl<T> IntoIterator for PropertyMap<T> {
+    type Item = (Property, T);
+    type IntoIter = std::vec::IntoIter<(Property, T)>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        Property::iter()
+            .zip(self.0.into_vec())
+            .filter_map(|(property, value)| Some((property, value?)))
+            .collect::<Vec<_>>()
+            .into_iter()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn index_properties() {
+        let mut map = PropertyMap::new();
+        assert!(map.is_empty());
+
+        map.insert(Property::Width, 1);
+        map.insert(Property::Color, 2);
+        assert_eq!(map.insert(Property::Width, 3), Some(1));
+
+        assert_eq!(map.get(&Property::Width), Some(&3));
+        assert_eq!(map.get(&Property::Height), None);
+        assert_eq!(map.len(), 2);
+        // iterated in the order the properties are declared
+        assert_eq!(
+            map.iter().collect::<Vec<_>>(),
+            vec![(Property::Color, &2), (Property::Width, &3)]
+        );
+
+        assert_eq!(map.remove(&Property::Color), Some(2));
+        assert!(!map.contains_key(&Property::Color));
+    }
+}
@@ -1,9 +1,25 @@
+use super::animation::Animations;
+use super::bloom::AncestorFilter;
+use super::computed_style::ComputedStyle;
+use super::computes::length::compute_length;
 use super::inheritable::INHERITABLES;
+use super::property_map::PropertyMap;
+use super::rule_index::RuleIndex;
+use super::transition::Transitions;
 use super::value_processing::{
     apply_styles, compute, ComputeContext, ContextualRule, Properties, Property, Value, ValueRef,
 };
 use super::values::display::{Display, DisplayBox};
-use dom::dom_ref::NodeRef;
+use super::values::length::LengthContext;
+use super::values::visibility::Visibility;
+use css::selector::structs::PseudoElement;
+use dom::create_element;
+use dom::dom_ref::{NodeRef, WeakNodeRef};
+use dom::element::Namespace;
+use dom::node::{Node, NodeData};
+use dom::node_list::NodeList;
+use dom::text::Text;
+use io::cancellation;
 use std::collections::{HashMap, HashSet};
 use strum::IntoEnumIterator;
 use tree::{TreeNodeRef, TreeNodeWeakRef};
@@ -11,12 +27,40 @@ use tree::{TreeNodeRef, TreeNodeWeakRef};
 pub type RenderNodeRef = TreeNodeRef<RenderNode>;
 pub type RenderNodeWeak = TreeNodeWeakRef<RenderNode>;
 
+/// The pseudo-elements generating boxes, in the order of their boxes
+pub(crate) const PSEUDO_ELEMENTS: [PseudoElement; 2] =
+    [PseudoElement::Before, PseudoElement::After];
+
 #[derive(Debug)]
 pub struct RenderTree {
     /// The root node of the render tree
     pub root: Option<RenderNodeRef>,
     /// The style cache to share style value and reduce style size
     pub style_cache: HashSet<ValueRef>,
+    /// The sizes the relative lengths of the styles are computed against
+    pub(crate) lengths: LengthContext,
+    /// Whether the next restyle recomputes the styles of the whole tree
+    pub(crate) restyle_all: bool,
+    /// The transitions started by the restyles of the tree
+    pub transitions: Transitions,
+    /// The animations of the elements of the tree
+    pub animations: Animations,
+}
+
+impl RenderTree {
+    pub fn lengths(&self) -> &LengthContext {
+        &self.lengths
+    }
+
+    /// Change the sizes the relative lengths are computed against, e.g.
+    /// when the viewport is resized. The styles are recomputed by the
+    /// next restyle.
+    pub fn set_lengths(&mut self, lengths: LengthContext) {
+        if self.lengths != lengths {
+            self.lengths = lengths;
+            self.restyle_all = true;
+        }
+    }
 }
 
 /// A style node in the style tree
@@ -24,15 +68,29 @@ pub struct RenderTree {
 pub struct RenderNode {
     /// A reference to the DOM node that uses this style
     pub node: NodeRef,
-    /// A property HashMap containing computed styles
-    pub properties: HashMap<Property, ValueRef>,
+    /// The computed styles, indexed by property
+    pub properties: ComputedStyle,
+    /// Indices of the style rules matching the node, used to skip
+    /// recomputing the styles on restyle when they stay the same
+    pub matched_rules: Vec<usize>,
+    /// Whether the node or one of its descendants was (re)styled since
+    /// the last time its layout box was built
+    pub needs_layout: bool,
     /// Child style nodes
     pub children: Vec<RenderNodeRef>,
     /// Parent reference for inheritance
     pub parent_render_node: Option<RenderNodeWeak>,
+    /// The pseudo-element of the node & of its generated content. Their
+    /// DOM nodes are created for them, outside of the document.
+    pub pseudo_element: Option<PseudoElement>,
 }
 
 impl RenderNode {
+    /// The computed styles of the node with typed getters
+    pub fn style(&self) -> &ComputedStyle {
+        &self.properties
+    }
+
     /// Get style value of a property
     /// Ensure that the value return is a shared computed value
     pub fn get_style(&self, property: &Property) -> ValueRef {
@@ -50,13 +108,44 @@ impl RenderNode {
 
         panic!("Oops, we should not reach here");
     }
+
+    /// The DOM node the render node is generated for, i.e. the originating
+    /// element of a pseudo-element
+    pub fn originating_node(&self) -> NodeRef {
+        let parent = self
+            .parent_render_node
+            .as_ref()
+            .and_then(|parent| parent.upgrade());
+        match (&self.pseudo_element, parent) {
+            (Some(_), Some(parent)) => parent.borrow().originating_node(),
+            _ => self.node.clone(),
+        }
+    }
+
+    /// Whether the node is painted. Invisible nodes still take space in the layout.
+    pub fn is_visible(&self) -> bool {
+        match self.get_style(&Property::Visibility).inner() {
+            Value::Visibility(Visibility::Hidden) | Value::Visibility(Visibility::Collapse) => {
+                false
+            }
+            _ => true,
+        }
+    }
+
+    /// Whether the contents of the node are laid out again on their own
+    /// when they change, without its ancestors. An element with size &
+    /// layout containment keeps its size & position whatever its contents.
+    pub fn is_layout_root(&self) -> bool {
+        self.node.is_element() && self.style().contain().is_layout_root()
+    }
 }
 
 pub fn compute_styles(
     properties: Properties,
     parent: Option<RenderNodeWeak>,
     cache: &mut HashSet<ValueRef>,
-) -> HashMap<Property, ValueRef> {
+    lengths: &LengthContext,
+) -> ComputedStyle {
     // get inherit value for a property
     let inherit = |property: Property| {
         if let Some(parent) = &parent {
@@ -103,54 +192,45 @@ pub fn compute_styles(
             // we will use the initial value for that property
             return (property.clone(), Value::initial(&property));
         })
-        .collect::<HashMap<Property, Value>>();
+        .collect::<PropertyMap<Value>>();
 
     // Step 4
-    // TODO: Might be an expensive clone when we support all properties
-    let temp_specified = specified_values.clone();
     let mut context = ComputeContext {
         parent: &parent,
-        properties: temp_specified,
+        properties: &specified_values,
         style_cache: cache,
+        lengths,
     };
     let computed_values = specified_values
-        .into_iter()
+        .iter()
         .map(|(property, value)| {
-            // some properties requires layout to compute
-            let is_not_compute = match property {
-                Property::Width
-                | Property::Height
-                | Property::MarginTop
-                | Property::MarginRight
-                | Property::MarginBottom
-                | Property::MarginLeft
-                | Property::PaddingTop
-                | Property::PaddingRight
-                | Property::PaddingBottom
-                | Property::PaddingLeft
-                | Property::Top
-                | Property::Right
-                | Property::Bottom
-                | Property::Left => true,
-                _ => false,
-            };
+            // the percentages of some properties are resolved by the layout
+            let is_not_compute = property.percentage_basis().is_some();
             let computed_value = if is_not_compute {
-                if !context.style_cache.contains(&value) {
-                    context.style_cache.insert(ValueRef::new(value.clone()));
-                }
-                context.style_cache.get(&value).unwrap().clone()
+                compute_length(value, &mut context)
             } else {
-                compute(&property, &value, &mut context)
+                compute(&property, value, &mut context)
             };
             return (property.clone(), computed_value);
         })
-        .collect::<HashMap<Property, ValueRef>>();
+        .collect::<PropertyMap<ValueRef>>();
 
-    computed_values
+    ComputedStyle::new(computed_values)
 }
 
 pub fn build_render_tree(node: NodeRef, rules: &[ContextualRule]) -> RenderTree {
+    build_render_tree_with_lengths(node, rules, LengthContext::default())
+}
+
+/// Build the render tree of a node, computing the relative lengths
+/// against the sizes of a viewport
+pub fn build_render_tree_with_lengths(
+    node: NodeRef,
+    rules: &[ContextualRule],
+    lengths: LengthContext,
+) -> RenderTree {
     let mut style_cache = HashSet::new();
+    let node_root = node.clone();
     let render_root = if node.is_document() {
         // the first child is HTML tag
         node.borrow().first_child()
@@ -158,30 +238,142 @@ pub fn build_render_tree(node: NodeRef, rules: &[ContextualRule]) -> RenderTree
         Some(node)
     };
 
+    let rules = RuleIndex::new(rules);
+
     let root = match render_root {
-        Some(node) => build_render_tree_from_node(node, rules, None, &mut style_cache),
+        Some(node) => {
+            let mut ancestors = ancestor_filter_for(&node);
+            build_render_tree_from_node(
+                node,
+                &rules,
+                &mut ancestors,
+                None,
+                &mut style_cache,
+                &lengths,
+            )
+        }
         None => None,
     };
 
-    RenderTree { root, style_cache }
+    if let Some(root) = &root {
+        resolve_quotes(root, &mut 0);
+    }
+
+    // the whole tree is up to date now
+    clear_style_mutations(&node_root);
+
+    RenderTree {
+        root,
+        style_cache,
+        lengths,
+        restyle_all: false,
+        transitions: Transitions::new(),
+        animations: Animations::new(),
+    }
+}
+
+/// Create an ancestor filter containing all the ancestors of a node
+pub(crate) fn ancestor_filter_for(node: &NodeRef) -> AncestorFilter {
+    let mut ancestors = Vec::new();
+    let mut parent = node.borrow().parent();
+    while let Some(node) = parent {
+        parent = node.borrow().parent();
+        ancestors.push(node);
+    }
+
+    // the ancestors of a node in a shadow tree stop at its shadow root
+    let shadow_root = ancestors
+        .last()
+        .filter(|root| root.borrow().shadow_host().is_some());
+    let mut filter = match shadow_root {
+        Some(shadow_root) => AncestorFilter::for_shadow_tree(shadow_root),
+        None => AncestorFilter::new(),
+    };
+    for ancestor in ancestors.iter().rev() {
+        if let Some(element) = ancestor.borrow().as_element_opt() {
+            filter.push_element(element);
+        }
+    }
+    filter
+}
+
+/// Forget the style mutations recorded in the subtree of a node.
+/// A subtree inserted with pending mutations doesn't flag its new
+/// ancestors, so every descendant is visited.
+pub(crate) fn clear_style_mutations(node: &NodeRef) {
+    {
+        let mut node = node.borrow_mut();
+        node.take_style_mutations();
+        node.clear_dirty_descendants();
+    }
+
+    for child in node.borrow().child_nodes() {
+        clear_style_mutations(&child);
+    }
+    if let Some(shadow_root) = shadow_root_of(node) {
+        clear_style_mutations(&shadow_root);
+    }
+}
+
+/// The shadow root attached to an element
+pub(crate) fn shadow_root_of(node: &NodeRef) -> Option<NodeRef> {
+    node.borrow().as_element_opt()?.shadow_root()
+}
+
+/// The nodes rendered as the children of a node: the children of its
+/// shadow root if it hosts one
+pub(crate) fn rendered_child_nodes(node: &NodeRef) -> NodeList {
+    match shadow_root_of(node) {
+        Some(shadow_root) => shadow_root.borrow().child_nodes(),
+        None => node.borrow().child_nodes(),
+    }
+}
+
+/// Whether the node is an SVG element. The content of an SVG image isn't
+/// laid out, it's painted by its `<svg>` element.
+pub(crate) fn is_svg_element(node: &NodeRef) -> bool {
+    match node.borrow().as_element_opt() {
+        Some(element) => element.namespace() == Namespace::Svg,
+        None => false,
+    }
 }
 
 /// Build the render tree using the root node & list of stylesheets
-fn build_render_tree_from_node(
+pub(crate) fn build_render_tree_from_node(
     node: NodeRef,
-    rules: &[ContextualRule],
+    rules: &RuleIndex,
+    ancestors: &mut AncestorFilter,
     parent: Option<RenderNodeWeak>,
     cache: &mut HashSet<ValueRef>,
+    lengths: &LengthContext,
 ) -> Option<RenderNodeRef> {
+    let matched_rules = if node.is_element() {
+        rules.matching_rules(&node, ancestors)
+    } else {
+        Vec::new()
+    };
+    // the pseudo-elements are matched like the element, before it is an ancestor
+    let pseudo_rules = if node.is_element() {
+        PSEUDO_ELEMENTS
+            .iter()
+            .map(|pseudo| rules.matching_pseudo_rules(&node, ancestors, Some(*pseudo)))
+            .collect()
+    } else {
+        Vec::new()
+    };
     let properties = if node.is_text() {
         HashMap::new()
     } else {
-        apply_styles(&node, &rules)
+        let matched = matched_rules
+            .iter()
+            .map(|rule_index| rules.rule(*rule_index))
+            .collect::<Vec<_>>();
+        apply_styles(&node, &matched)
     };
 
     // Filter head from render tree
     if let Some(element) = node.borrow().as_element_opt() {
-        if element.tag_name() == "head" {
+        if element.has_tag_name("head") {
             return None;
         }
     }
@@ -197,23 +389,185 @@ fn build_render_tree_from_node(
 
     let render_node = TreeNodeRef::new(RenderNode {
         node: node.clone(),
-        properties: compute_styles(properties, parent.clone(), cache),
+        properties: compute_styles(properties, parent.clone(), cache, lengths),
+        matched_rules,
+        needs_layout: true,
         parent_render_node: parent,
         children: Vec::new(),
+        pseudo_element: None,
     });
 
-    render_node.borrow_mut().children = node
-        .borrow()
-        .child_nodes()
+    if is_svg_element(&node) {
+        return Some(render_node);
+    }
+
+    if let Some(element) = node.borrow().as_element_opt() {
+        ancestors.push_element(element);
+    }
+
+    let mut pseudo_elements = PSEUDO_ELEMENTS
+        .iter()
+        .zip(pseudo_rules)
+        .map(|(pseudo, matched_rules)| {
+            build_pseudo_element(&render_node, *pseudo, matched_rules, rules, cache, lengths)
+        })
+        .collect::<Vec<_>>();
+    let after = pseudo_elements.pop().flatten();
+    let before = pseudo_elements.pop().flatten();
+
+    // the selectors don't match across the root of a shadow tree
+    let mut shadow_ancestors =
+        shadow_root_of(&node).map(|root| AncestorFilter::for_shadow_tree(&root));
+    let child_ancestors = match &mut shadow_ancestors {
+        Some(shadow_ancestors) => shadow_ancestors,
+        None => &mut *ancestors,
+    };
+    // the children left once cancelled aren't rendered
+    let children = rendered_child_nodes(&node)
         .into_iter() // this is fine because we clone the node when iterate
+        .take_while(|_| !cancellation::is_cancelled())
         .filter_map(|child| {
-            build_render_tree_from_node(child, &rules, Some(render_node.downgrade()), cache)
+            build_render_tree_from_node(
+                child,
+                rules,
+                child_ancestors,
+                Some(render_node.downgrade()),
+                cache,
+                lengths,
+            )
         })
-        .collect();
+        .collect::<Vec<_>>();
+    render_node.borrow_mut().children = before.into_iter().chain(children).chain(after).collect();
+
+    if node.is_element() {
+        ancestors.pop_element();
+    }
 
     Some(render_node)
 }
 
+/// Build the render node of a pseudo-element & of its content, if its
+/// content generates a box
+/// https://www.w3.org/TR/css-pseudo-4/#generated-content
+pub(crate) fn build_pseudo_element(
+    originating: &RenderNodeRef,
+    pseudo_element: PseudoElement,
+    matched_rules: Vec<usize>,
+    rules: &RuleIndex,
+    cache: &mut HashSet<ValueRef>,
+    lengths: &LengthContext,
+) -> Option<RenderNodeRef> {
+    if matched_rules.is_empty() {
+        return None;
+    }
+    let document = originating
+        .borrow()
+        .node
+        .borrow()
+        .owner_document()
+        .map(|document| document.downgrade())
+        .unwrap_or_else(WeakNodeRef::empty);
+    let node = create_element(document.clone(), &format!("::{}", pseudo_element.name()));
+
+    let matched = matched_rules
+        .iter()
+        .map(|rule_index| rules.rule(*rule_index))
+        .collect::<Vec<_>>();
+    let properties = apply_styles(&node, &matched);
+    if let Some(Some(Value::Display(Display::Box(DisplayBox::None)))) =
+        properties.get(&Property::Display)
+    {
+        return None;
+    }
+
+    let properties = compute_styles(properties, Some(originating.downgrade()), cache, lengths);
+    // the depth of the quotes is resolved with the whole tree
+    let text = properties.content().text(&properties.quotes(), &mut 0)?;
+    let render_node = TreeNodeRef::new(RenderNode {
+        node,
+        properties,
+        matched_rules,
+        needs_layout: true,
+        parent_render_node: Some(originating.downgrade()),
+        children: Vec::new(),
+        pseudo_element: Some(pseudo_element),
+    });
+
+    let mut text_node = Node::new(NodeData::Text(Text::new(text)));
+    text_node.set_document(document);
+    let text_render_node = TreeNodeRef::new(RenderNode {
+        node: NodeRef::new(text_node),
+        properties: compute_styles(
+            HashMap::new(),
+            Some(render_node.downgrade()),
+            cache,
+            lengths,
+        ),
+        matched_rules: Vec::new(),
+        needs_layout: true,
+        parent_render_node: Some(render_node.downgrade()),
+        children: Vec::new(),
+        pseudo_element: Some(pseudo_element),
+    });
+    render_node.borrow_mut().children.push(text_render_node);
+    Some(render_node)
+}
+
+/// Resolve the text of the quotes generated in a subtree, nesting from
+/// the depth of the quotes before it. The quotes nest in the order of the
+/// document, so they are resolved once the tree is built or restyled.
+/// Returns whether the text of any quote changed.
+pub(crate) fn resolve_quotes(render_node: &RenderNodeRef, depth: &mut usize) -> bool {
+    let mut node = render_node.borrow_mut();
+    if node.pseudo_element.is_some() && node.node.is_element() {
+        let content = node.style().content();
+        if !content.has_quotes() {
+            return false;
+        }
+        let text = content
+            .text(&node.style().quotes(), depth)
+            .unwrap_or_default();
+        let text_node = match node.children.first() {
+            Some(text_node) => text_node.clone(),
+            None => return false,
+        };
+        let mut text_node = text_node.borrow_mut();
+        let is_same = text_node
+            .node
+            .borrow()
+            .as_text_opt()
+            .map_or(false, |current| current.get_data() == text);
+        if is_same {
+            return false;
+        }
+        let document = text_node.node.borrow().owner_document();
+        let mut new_node = Node::new(NodeData::Text(Text::new(text)));
+        if let Some(document) = document {
+            new_node.set_document(document.downgrade());
+        }
+        text_node.node = NodeRef::new(new_node);
+        text_node.needs_layout = true;
+        node.needs_layout = true;
+        return true;
+    }
+
+    // the quotes nesting inside a box with style containment don't change
+    // the depth of the quotes after it
+    let mut scoped_depth = *depth;
+    let depth = if node.style().contain().style {
+        &mut scoped_depth
+    } else {
+        depth
+    };
+
+    let mut changed = false;
+    for child in &node.children {
+        changed |= resolve_quotes(child, depth);
+    }
+    node.needs_layout |= changed;
+    changed
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
@@ -224,6 +578,8 @@ mod tests {
     use crate::values::display::Display;
     use crate::values::length::{Length, LengthUnit};
     use crate::values::number::Number;
+    use crate::values::text_decoration_line::TextDecorationLine;
+    use crate::values::text_decoration_style::TextDecorationStyle;
     use css::cssom::css_rule::CSSRule;
     use std::rc::Rc;
     use test_utils::css::parse_stylesheet;
@@ -258,12 +614,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -314,12 +671,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -372,12 +730,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -450,12 +809,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -516,12 +876,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -563,12 +924,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -621,12 +983,13 @@ mod tests {
 
         let rules = stylesheet
             .iter()
-            .map(|rule| match rule {
-                CSSRule::Style(style) => ContextualRule {
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
                     inner: style,
                     location: CSSLocation::Embedded,
                     origin: CascadeOrigin::User,
-                },
+                }),
+                _ => None,
             })
             .collect::<Vec<ContextualRule>>();
 
@@ -696,4 +1059,253 @@ mod tests {
             Some(&ValueRef(Rc::new(Value::BorderStyle(BorderStyle::Dotted))))
         );
     }
+
+    #[test]
+    fn visibility_hidden_stays_in_tree() {
+        let document = document();
+        let dom_tree = element(
+            "div#parent",
+            document.clone(),
+            vec![
+                element("div#hidden", document.clone(), vec![]),
+                element("div#visible", document.clone(), vec![]),
+                element("div#none", document.clone(), vec![]),
+            ],
+        );
+
+        let css = r#"
+        #parent {
+            visibility: hidden;
+        }
+        #visible {
+            visibility: visible;
+        }
+        #none {
+            display: none;
+        }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom_tree.clone(), &rules);
+
+        let root = render_tree.root.expect("No root node");
+        let root = root.borrow();
+        assert!(!root.is_visible());
+
+        // invisible nodes are kept for layout while display none nodes are not
+        assert_eq!(root.children.len(), 2);
+        assert!(!root.children[0].borrow().is_visible());
+        assert!(root.children[1].borrow().is_visible());
+    }
+
+    #[test]
+    fn text_decoration_shorthand() {
+        let document = document();
+        let dom_tree = element("div#parent", document.clone(), vec![]);
+
+        let css = r#"
+        #parent {
+            color: white;
+            text-decoration: underline dashed line-through;
+        }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom_tree.clone(), &rules);
+
+        let render_tree_inner = render_tree.root.expect("No root node");
+        let render_tree_inner = render_tree_inner.borrow();
+        let styles = &render_tree_inner.properties;
+        assert_eq!(
+            styles.get(&Property::TextDecorationLine),
+            Some(&ValueRef(Rc::new(Value::TextDecorationLine(
+                TextDecorationLine {
+                    underline: true,
+                    overline: false,
+                    line_through: true,
+                }
+            ))))
+        );
+        assert_eq!(
+            styles.get(&Property::TextDecorationStyle),
+            Some(&ValueRef(Rc::new(Value::TextDecorationStyle(
+                TextDecorationStyle::Dashed
+            ))))
+        );
+        // the omitted color is reset to currentColor
+        assert_eq!(
+            styles.get(&Property::TextDecorationColor),
+            styles.get(&Property::Color)
+        );
+    }
+
+    #[test]
+    fn generate_pseudo_elements() {
+        let document = document();
+        let dom_tree = element(
+            "div#parent",
+            document.clone(),
+            vec![
+                element(
+                    "p.note",
+                    document.clone(),
+                    vec![text("Hello", document.clone())],
+                ),
+                element("p", document.clone(), vec![]),
+            ],
+        );
+
+        let css = r#"
+        .note::before { content: "Note: "; color: red; }
+        .note:after { content: none; }
+        #parent::after { content: "!"; display: block; }
+        p + p::before { display: none; content: "hidden"; }
+        p + p::after { color: blue; }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom_tree.clone(), &rules);
+        let root = render_tree.root.expect("No root node");
+        let root = root.borrow();
+        assert_eq!(root.children.len(), 3);
+        // the rules of the pseudo-elements don't apply to the elements
+        assert_eq!(root.style().display(), Display::new_inline());
+
+        let after = root.children[2].borrow();
+        assert_eq!(after.pseudo_element, Some(PseudoElement::After));
+        assert_eq!(after.style().display(), Display::new_block());
+        assert_eq!(after.originating_node(), dom_tree);
+
+        let note = root.children[0].borrow();
+        assert_eq!(note.children.len(), 2);
+        let before = note.children[0].borrow();
+        assert_eq!(before.pseudo_element, Some(PseudoElement::Before));
+        assert_eq!(
+            before.style().color(),
+            Color::Rgba(255.0.into(), 0.0.into(), 0.0.into(), 255.0.into())
+        );
+        let content = before.children[0].borrow();
+        assert_eq!(
+            content
+                .node
+                .borrow()
+                .as_text_opt()
+                .map(|text| text.get_data()),
+            Some("Note: ".to_string())
+        );
+        assert_eq!(content.style().color(), before.style().color());
+        assert_eq!(content.originating_node(), note.node);
+
+        // no box without content, or with `display: none`
+        assert!(root.children[1].borrow().children.is_empty());
+    }
+
+    fn generated_text(render_node: &RenderNodeRef) -> String {
+        render_node.borrow().children[0]
+            .borrow()
+            .node
+            .borrow()
+            .as_text_opt()
+            .map(|text| text.get_data())
+            .unwrap()
+    }
+
+    #[test]
+    fn nest_quotes() {
+        let document = document();
+        let inner = element("q", document.clone(), vec![text("b", document.clone())]);
+        let outer = element(
+            "q#outer",
+            document.clone(),
+            vec![text("a", document.clone()), inner],
+        );
+        let dom_tree = element(
+            "div",
+            document.clone(),
+            vec![
+                outer,
+                element("q", document.clone(), vec![]),
+                element("span.close", document.clone(), vec![]),
+            ],
+        );
+
+        let css = r#"
+        q::before { content: open-quote; }
+        q::after { content: close-quote; }
+        #outer { quotes: "<" ">" "[" "]"; }
+        .close::before { content: "(" close-quote ")"; }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let render_tree = build_render_tree(dom_tree, &rules);
+        let root = render_tree.root.expect("No root node");
+        let root = root.borrow();
+
+        let outer = root.children[0].borrow();
+        assert_eq!(outer.children.len(), 4);
+        assert_eq!(generated_text(&outer.children[0]), "<");
+        assert_eq!(generated_text(&outer.children[3]), ">");
+        let inner = outer.children[2].borrow();
+        assert_eq!(generated_text(&inner.children[0]), "[");
+        assert_eq!(generated_text(&inner.children[2]), "]");
+
+        // the quotes of the next quotation nest from the outermost level
+        let next = root.children[1].borrow();
+        assert_eq!(generated_text(&next.children[0]), "\u{201C}");
+        assert_eq!(generated_text(&next.children[1]), "\u{201D}");
+
+        // no quote is open anymore
+        let close = root.children[2].borrow();
+        assert_eq!(generated_text(&close.children[0]), "()");
+    }
 }
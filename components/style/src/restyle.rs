@@ -0,0 +1,651 @@
+/// Incremental restyle of a render tree. DOM mutations are recorded on the
+/// mutated nodes and turned into restyle hints using the selectors of the
+/// style rules, so only the nodes that could match differently are restyled
+/// instead of rebuilding the whole render tree.
+use super::animation::Animations;
+use super::bloom::{AncestorFilter, HashKind};
+use super::render_tree::{
+    ancestor_filter_for, build_render_tree_from_node, clear_style_mutations, is_svg_element,
+    rendered_child_nodes, resolve_quotes, shadow_root_of, RenderNodeRef, RenderTree,
+    PSEUDO_ELEMENTS,
+};
+use super::rule_index::RuleIndex;
+use super::transition::Transitions;
+use super::value_processing::{ContextualRule, ValueRef};
+use super::values::length::LengthContext;
+use css::selector::structs::PseudoElement;
+use dom::dom_ref::NodeRef;
+use dom::mutation::StyleMutation;
+use io::cancellation;
+use std::collections::HashSet;
+
+/// Which nodes have to be restyled after a mutation
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum RestyleHint {
+    /// The mutation doesn't affect any style
+    None,
+    /// Only the node could match different rules
+    RestyleSelf,
+    /// The node and its descendants could match different rules
+    RestyleSubtree,
+    /// The node, its descendants and its later siblings could match different rules
+    RestyleLaterSiblings,
+}
+
+/// Compute the restyle hint of a style mutation
+pub fn restyle_hint_for(mutation: &StyleMutation, rules: &RuleIndex) -> RestyleHint {
+    match mutation {
+        StyleMutation::Id { old, new } => {
+            if old == new {
+                return RestyleHint::None;
+            }
+            rules
+                .restyle_hint(HashKind::Id, old)
+                .max(rules.restyle_hint(HashKind::Id, new))
+        }
+        StyleMutation::Class { old, new } => old
+            .iter()
+            .filter(|class| !new.contains(class))
+            .chain(new.iter().filter(|class| !old.contains(class)))
+            .map(|class| rules.restyle_hint(HashKind::Class, class))
+            .max()
+            .unwrap_or(RestyleHint::None),
+        StyleMutation::Attribute(name) => rules.attribute_restyle_hint(name),
+        // new children are not styled yet and structural selectors
+        // of the existing ones could match differently
+        StyleMutation::ChildList => RestyleHint::RestyleSubtree,
+        // the render node of a text is rebuilt to lay out its new data
+        StyleMutation::CharacterData => RestyleHint::RestyleSubtree,
+    }
+}
+
+/// Restyle the nodes of a render tree affected by the DOM mutations
+/// recorded since the tree was built or last restyled
+pub fn restyle_render_tree(tree: &mut RenderTree, rules: &[ContextualRule]) {
+    let root = match &tree.root {
+        Some(root) => root.clone(),
+        None => return,
+    };
+    let rules = RuleIndex::new(rules);
+    let node = root.borrow().node.clone();
+    let mut ancestors = ancestor_filter_for(&node);
+
+    // the relative lengths of any style could compute differently
+    let restyle_all = std::mem::take(&mut tree.restyle_all);
+    let reusable = match take_restyle_hint(&node, &rules) {
+        _ if restyle_all => false,
+        RestyleHint::None => true,
+        RestyleHint::RestyleSelf => has_same_matched_rules(&root, &rules, &ancestors),
+        _ => false,
+    };
+
+    if !reusable {
+        tree.root = build_render_tree_from_node(
+            node.clone(),
+            &rules,
+            &mut ancestors,
+            None,
+            &mut tree.style_cache,
+            &tree.lengths,
+        );
+        if let Some(new_root) = &tree.root {
+            tree.animations.restyled(
+                &root,
+                new_root,
+                rules.rules(),
+                &mut tree.style_cache,
+                &tree.lengths,
+            );
+            tree.transitions.restyled(&root, new_root);
+        }
+        clear_style_mutations(&node);
+    } else {
+        restyle_children(
+            &root,
+            &rules,
+            &mut ancestors,
+            &mut tree.style_cache,
+            &tree.lengths,
+            &mut tree.transitions,
+            &mut tree.animations,
+        );
+    }
+
+    // the restyled nodes could change the depth of the quotes after them
+    if let Some(root) = &tree.root {
+        resolve_quotes(root, &mut 0);
+    }
+
+    // mutations above the render root, e.g. on the document, don't
+    // affect any style but their dirty bits must be reset
+    let mut parent = node.borrow().parent();
+    while let Some(node) = parent {
+        node.borrow_mut().take_style_mutations();
+        node.borrow_mut().clear_dirty_descendants();
+        parent = node.borrow().parent();
+    }
+}
+
+/// Restyle the children of a render node that are affected by mutations,
+/// reusing the render nodes of the unaffected ones. Returns whether any
+/// render node of the subtree changed.
+fn restyle_children(
+    render_node: &RenderNodeRef,
+    rules: &RuleIndex,
+    ancestors: &mut AncestorFilter,
+    cache: &mut HashSet<ValueRef>,
+    lengths: &LengthContext,
+    transitions: &mut Transitions,
+    animations: &mut Animations,
+) -> bool {
+    let node = render_node.borrow().node.clone();
+    // the descendants stay dirty for the next restyle once cancelled
+    if !node.borrow().has_dirty_descendants() || cancellation::is_cancelled() {
+        return false;
+    }
+    node.borrow_mut().clear_dirty_descendants();
+
+    // the content of an SVG image changed the painted image
+    if is_svg_element(&node) {
+        for child in node.borrow().child_nodes() {
+            clear_style_mutations(&child);
+        }
+        render_node.borrow_mut().needs_layout = true;
+        return true;
+    }
+
+    if let Some(element) = node.borrow().as_element_opt() {
+        ancestors.push_element(element);
+    }
+
+    // the children of a shadow host are the children of its shadow root,
+    // matched without the ancestors outside of the shadow tree
+    let shadow_root = shadow_root_of(&node);
+    let mut shadow_ancestors = shadow_root.as_ref().map(AncestorFilter::for_shadow_tree);
+    let mut shadow_child_list_changed = false;
+    if let Some(shadow_root) = &shadow_root {
+        let mut shadow_root = shadow_root.borrow_mut();
+        shadow_root.clear_dirty_descendants();
+        shadow_child_list_changed = !shadow_root.take_style_mutations().is_empty();
+    }
+
+    // the styles of the pseudo-elements of a reused node are the same
+    let (pseudo_elements, old_children): (Vec<_>, Vec<_>) =
+        std::mem::take(&mut render_node.borrow_mut().children)
+            .into_iter()
+            .partition(|child| child.borrow().pseudo_element.is_some());
+    let pseudo_element = |pseudo| {
+        pseudo_elements
+            .iter()
+            .find(|child| child.borrow().pseudo_element == Some(pseudo))
+            .cloned()
+    };
+
+    // render nodes are in the same order as their DOM nodes
+    let mut old_children = old_children.into_iter().peekable();
+    let mut children = Vec::new();
+    let mut restyle_later_siblings = shadow_child_list_changed;
+    let mut changed = false;
+
+    let child_ancestors = match &mut shadow_ancestors {
+        Some(shadow_ancestors) => shadow_ancestors,
+        None => &mut *ancestors,
+    };
+    let child_nodes = rendered_child_nodes(&node);
+    for child in child_nodes {
+        let existing = match old_children.peek() {
+            Some(render_child) if render_child.borrow().node == child => old_children.next(),
+            _ => None,
+        };
+
+        let mut hint = take_restyle_hint(&child, rules);
+        if restyle_later_siblings {
+            hint = hint.max(RestyleHint::RestyleSubtree);
+        }
+        if hint == RestyleHint::RestyleLaterSiblings {
+            restyle_later_siblings = true;
+        }
+
+        let reusable = match (&existing, hint) {
+            (Some(_), RestyleHint::None) => true,
+            (Some(render_child), RestyleHint::RestyleSelf) => {
+                has_same_matched_rules(render_child, rules, child_ancestors)
+            }
+            _ => false,
+        };
+
+        match existing {
+            Some(render_child) if reusable => {
+                let child_changed = restyle_children(
+                    &render_child,
+                    rules,
+                    child_ancestors,
+                    cache,
+                    lengths,
+                    transitions,
+                    animations,
+                );
+                // the contents of a layout root are laid out again on their
+                // own, without its ancestors
+                changed |= child_changed && !render_child.borrow().is_layout_root();
+                children.push(render_child);
+            }
+            // an unaffected node that wasn't rendered stays unrendered
+            None if hint == RestyleHint::None => clear_style_mutations(&child),
+            _ => {
+                changed = true;
+                let render_child = build_render_tree_from_node(
+                    child.clone(),
+                    rules,
+                    child_ancestors,
+                    Some(render_node.downgrade()),
+                    cache,
+                    lengths,
+                );
+                if let (Some(old_child), Some(new_child)) = (&existing, &render_child) {
+                    // animated values don't start transitions
+                    animations.restyled(old_child, new_child, rules.rules(), cache, lengths);
+                    transitions.restyled(old_child, new_child);
+                }
+                children.extend(render_child);
+                clear_style_mutations(&child);
+            }
+        }
+    }
+
+    // removed children are not in the DOM anymore
+    changed |= old_children.next().is_some();
+
+    let mut render_node = render_node.borrow_mut();
+    render_node.children = pseudo_element(PseudoElement::Before)
+        .into_iter()
+        .chain(children)
+        .chain(pseudo_element(PseudoElement::After))
+        .collect();
+    render_node.needs_layout |= changed;
+
+    if node.is_element() {
+        ancestors.pop_element();
+    }
+    changed
+}
+
+/// Consume the style mutations of a node and compute its restyle hint
+fn take_restyle_hint(node: &NodeRef, rules: &RuleIndex) -> RestyleHint {
+    node.borrow_mut()
+        .take_style_mutations()
+        .iter()
+        .map(|mutation| restyle_hint_for(mutation, rules))
+        .max()
+        .unwrap_or(RestyleHint::None)
+}
+
+fn has_same_matched_rules(
+    render_node: &RenderNodeRef,
+    rules: &RuleIndex,
+    ancestors: &AncestorFilter,
+) -> bool {
+    let render_node = render_node.borrow();
+    if !render_node.node.is_element() {
+        return true;
+    }
+    if rules.matching_rules(&render_node.node, ancestors) != render_node.matched_rules {
+        return false;
+    }
+    PSEUDO_ELEMENTS.iter().all(|pseudo| {
+        let matched_rules = render_node
+            .children
+            .iter()
+            .find(|child| child.borrow().pseudo_element == Some(*pseudo))
+            .map(|child| child.borrow().matched_rules.clone())
+            .unwrap_or_default();
+        rules.matching_pseudo_rules(&render_node.node, ancestors, Some(*pseudo)) == matched_rules
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_tree::{build_render_tree, build_render_tree_with_lengths};
+    use crate::value_processing::{CSSLocation, CascadeOrigin, Property, Value};
+    use crate::values::color::Color;
+    use css::cssom::css_rule::CSSRule;
+    use dom::node::Node;
+    use dom::visited::VisitedLinks;
+    use std::cell::RefCell;
+    use std::rc::Rc;
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+    use url::Url;
+
+    #[test]
+    fn restyle_affected_nodes_only() {
+        let doc = document();
+        let first = element("span", doc.clone(), vec![]);
+        let second = element("span", doc.clone(), vec![]);
+        let hidden = element("p.hidden", doc.clone(), vec![]);
+        let list = element(
+            "div",
+            doc.clone(),
+            vec![first.clone(), second.clone(), hidden.clone()],
+        );
+        let root = element("div", doc.clone(), vec![list.clone()]);
+
+        let css = r#"
+        span { color: black; }
+        .active span { color: red; }
+        .hidden { display: none; }
+        .selected { color: blue; }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let mut tree = build_render_tree(root.clone(), &rules);
+        assert!(!root.borrow().has_dirty_descendants());
+
+        let render_list = tree.root.clone().unwrap().borrow().children[0].clone();
+        assert_eq!(render_list.borrow().children.len(), 2);
+        let render_first = render_list.borrow().children[0].clone();
+        let render_second = render_list.borrow().children[1].clone();
+
+        // an unused class doesn't restyle anything
+        Node::set_attribute(&first, "class", "unused");
+        assert!(root.borrow().has_dirty_descendants());
+        restyle_render_tree(&mut tree, &rules);
+        assert!(!root.borrow().has_dirty_descendants());
+        assert!(render_list.borrow().children[0] == render_first);
+
+        // a class used by the subject only restyles the element
+        Node::set_attribute(&first, "class", "selected");
+        restyle_render_tree(&mut tree, &rules);
+        let new_first = render_list.borrow().children[0].clone();
+        assert!(new_first != render_first);
+        assert!(render_list.borrow().children[1] == render_second);
+        assert_eq!(
+            **new_first.borrow().get_style(&Property::Color),
+            Value::Color(Color::Rgba(
+                0.0.into(),
+                0.0.into(),
+                255.0.into(),
+                255.0.into()
+            ))
+        );
+
+        // a class used by an ancestor selector restyles the descendants
+        Node::set_attribute(&list, "class", "active");
+        restyle_render_tree(&mut tree, &rules);
+        let render_list = tree.root.clone().unwrap().borrow().children[0].clone();
+        let new_second = render_list.borrow().children[1].clone();
+        assert!(new_second != render_second);
+        assert_eq!(
+            **new_second.borrow().get_style(&Property::Color),
+            Value::Color(Color::Rgba(
+                255.0.into(),
+                0.0.into(),
+                0.0.into(),
+                255.0.into()
+            ))
+        );
+
+        // a node that is no longer hidden gets rendered
+        Node::set_attribute(&hidden, "class", "");
+        restyle_render_tree(&mut tree, &rules);
+        assert_eq!(render_list.borrow().children.len(), 3);
+    }
+
+    #[test]
+    fn restyle_quotes() {
+        let doc = document();
+        let first = element("q", doc.clone(), vec![]);
+        let second = element("q", doc.clone(), vec![]);
+        let root = element("div", doc.clone(), vec![first.clone(), second.clone()]);
+
+        let css = r#"
+        q::before { content: open-quote; }
+        q::after { content: close-quote; }
+        .unclosed::after { content: none; }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let open_quote = |render_node: &RenderNodeRef| {
+            render_node.borrow().children[0].borrow().children[0]
+                .borrow()
+                .node
+                .borrow()
+                .as_text_opt()
+                .map(|text| text.get_data())
+                .unwrap()
+        };
+
+        let mut tree = build_render_tree(root.clone(), &rules);
+        let render_root = tree.root.clone().unwrap();
+        let render_second = render_root.borrow().children[1].clone();
+        assert_eq!(open_quote(&render_second), "\u{201C}");
+
+        // the quote of the reused node nests in the quote left open before it
+        render_second.borrow_mut().needs_layout = false;
+        Node::set_attribute(&first, "class", "unclosed");
+        restyle_render_tree(&mut tree, &rules);
+        assert!(render_root.borrow().children[1] == render_second);
+        assert_eq!(open_quote(&render_second), "\u{2018}");
+        assert!(render_second.borrow().needs_layout);
+    }
+
+    #[test]
+    fn restyle_attr_content() {
+        let doc = document();
+        let item = element("li", doc.clone(), vec![]);
+        Node::set_attribute(&item, "data-label", "A");
+        let root = element("ul", doc.clone(), vec![item.clone()]);
+
+        let css = r#"
+        li::before { content: attr(data-label) ". "; }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let label = |tree: &RenderTree| {
+            let root = tree.root.clone().unwrap();
+            let item = root.borrow().children[0].clone();
+            let before = item.borrow().children[0].clone();
+            let text = before.borrow().children[0].clone();
+            let text = text.borrow();
+            let node = text.node.borrow();
+            node.as_text_opt().map(|text| text.get_data()).unwrap()
+        };
+
+        let mut tree = build_render_tree(root.clone(), &rules);
+        assert_eq!(label(&tree), "A. ");
+
+        // the content is computed again with the attribute
+        Node::set_attribute(&item, "data-label", "B");
+        restyle_render_tree(&mut tree, &rules);
+        assert_eq!(label(&tree), "B. ");
+
+        // other attributes don't restyle the element
+        let render_item = tree.root.clone().unwrap().borrow().children[0].clone();
+        Node::set_attribute(&item, "title", "item");
+        restyle_render_tree(&mut tree, &rules);
+        assert!(tree.root.clone().unwrap().borrow().children[0] == render_item);
+    }
+
+    #[test]
+    fn restyle_shadow_tree() {
+        let doc = document();
+        let outer = element("p", doc.clone(), vec![]);
+        let host = element("div", doc.clone(), vec![element("p", doc.clone(), vec![])]);
+        let root = element("div", doc.clone(), vec![outer.clone(), host.clone()]);
+        let shadow_root = Node::attach_shadow(&host).unwrap();
+        assert_eq!(Node::attach_shadow(&host), None);
+        let inner = element("p", doc.clone(), vec![]);
+        Node::append_child(shadow_root.clone(), inner.clone());
+
+        let user_agent = parse_stylesheet("p { margin-top: 1px; }");
+        let document = parse_stylesheet("p { color: red; }");
+        let shadow =
+            parse_stylesheet("p { color: blue; } div p { color: green; } .b { color: black; }");
+        let rules = [
+            (&user_agent, CascadeOrigin::UserAgent, CSSLocation::External),
+            (&document, CascadeOrigin::Author, CSSLocation::Embedded),
+            (
+                &shadow,
+                CascadeOrigin::Author,
+                CSSLocation::ShadowTree(shadow_root.clone()),
+            ),
+        ]
+        .iter()
+        .flat_map(|(stylesheet, origin, location)| {
+            stylesheet.iter().filter_map(move |rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: location.clone(),
+                    origin: origin.clone(),
+                }),
+                _ => None,
+            })
+        })
+        .collect::<Vec<ContextualRule>>();
+
+        // the host renders its shadow tree, the rules of the document don't
+        // match in the shadow tree & the rules of the shadow root don't match
+        // outside of it or across its root
+        let mut tree = build_render_tree(root.clone(), &rules);
+        let matched_rules =
+            |render_node: &RenderNodeRef| render_node.borrow().matched_rules.clone();
+        let render_root = tree.root.clone().unwrap();
+        let render_host = render_root.borrow().children[1].clone();
+        assert_eq!(matched_rules(&render_root.borrow().children[0]), vec![0, 1]);
+        assert_eq!(render_host.borrow().children.len(), 1);
+        assert_eq!(matched_rules(&render_host.borrow().children[0]), vec![0, 2]);
+
+        // the mutations of the shadow tree restyle its nodes
+        Node::set_attribute(&inner, "class", "b");
+        assert!(root.borrow().has_dirty_descendants());
+        restyle_render_tree(&mut tree, &rules);
+        assert!(!root.borrow().has_dirty_descendants());
+        assert_eq!(
+            matched_rules(&render_host.borrow().children[0]),
+            vec![0, 2, 4]
+        );
+
+        Node::append_child(shadow_root.clone(), element("p", doc.clone(), vec![]));
+        restyle_render_tree(&mut tree, &rules);
+        assert_eq!(render_host.borrow().children.len(), 2);
+    }
+
+    #[test]
+    fn restyle_viewport_lengths() {
+        let doc = document();
+        let child = element("p", doc.clone(), vec![]);
+        let root = element("div", doc.clone(), vec![child.clone()]);
+
+        let stylesheet = parse_stylesheet("div { width: 50vw; } p { margin-top: 2rem; }");
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let width = |tree: &RenderTree| {
+            let root = tree.root.clone().unwrap();
+            let width = root.borrow().get_style(&Property::Width);
+            width.to_px(0.)
+        };
+        let mut tree =
+            build_render_tree_with_lengths(root.clone(), &rules, LengthContext::new(800., 600.));
+        assert_eq!(width(&tree), 400.);
+        let render_child = tree.root.clone().unwrap().borrow().children[0].clone();
+        let margin = render_child.borrow().get_style(&Property::MarginTop);
+        assert_eq!(margin.to_px(0.), 32.);
+
+        // the same sizes don't restyle anything
+        tree.set_lengths(LengthContext::new(800., 600.));
+        restyle_render_tree(&mut tree, &rules);
+        assert_eq!(width(&tree), 400.);
+
+        tree.set_lengths(LengthContext::new(400., 600.));
+        restyle_render_tree(&mut tree, &rules);
+        assert_eq!(width(&tree), 200.);
+    }
+
+    #[test]
+    fn restyle_links() {
+        let doc = document();
+        let link = element("a", doc.clone(), vec![]);
+        Node::set_attribute(&link, "href", "https://example.com/sun");
+        let root = element("div", doc.clone(), vec![link.clone()]);
+        let visited = Rc::new(RefCell::new(VisitedLinks::new()));
+        visited
+            .borrow_mut()
+            .add(&Url::parse("https://example.com/moon").unwrap());
+        doc.borrow_mut()
+            .as_document_mut()
+            .set_visited_links(visited);
+
+        let stylesheet = parse_stylesheet("a:link { width: 10px; } a:visited { width: 20px; }");
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let width = |tree: &RenderTree| {
+            let link = tree.root.clone().unwrap().borrow().children[0].clone();
+            let width = link.borrow().get_style(&Property::Width);
+            width.to_px(0.)
+        };
+        let mut tree = build_render_tree(root.clone(), &rules);
+        assert_eq!(width(&tree), 10.);
+
+        // the link is visited once it links to a visited URL
+        Node::set_attribute(&link, "href", "https://example.com/moon#top");
+        restyle_render_tree(&mut tree, &rules);
+        assert_eq!(width(&tree), 20.);
+    }
+}
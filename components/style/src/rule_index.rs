@@ -0,0 +1,384 @@
+/// Index of style rules bucketed by the rightmost id, class or tag name of
+/// their selectors. Only the buckets an element could match are tested, so
+/// the matching cost scales with the relevant rules instead of the size of
+/// the stylesheets.
+use super::bloom::{selector_hash, AncestorFilter, HashKind};
+use super::restyle::RestyleHint;
+use super::selector_matching::is_match_pseudo_selector;
+use super::value_processing::{
+    parse_function, CSSLocation, CascadeOrigin, ContextualRule, ValueFunction,
+};
+use atom::Atom;
+use css::parser::structs::ComponentValue;
+use css::selector::structs::*;
+use dom::dom_ref::NodeRef;
+use std::collections::{HashMap, HashSet};
+
+pub struct RuleIndex<'a, 'b> {
+    rules: &'b [ContextualRule<'a>],
+    by_id: HashMap<Atom, Vec<usize>>,
+    by_class: HashMap<Atom, Vec<usize>>,
+    by_tag: HashMap<Atom, Vec<usize>>,
+    universal: Vec<usize>,
+    /// For each rule and each of its selectors, the hashes of the ancestors
+    /// the selector requires
+    ancestor_hashes: Vec<Vec<Vec<u32>>>,
+    /// Hashes of the ids & classes used in the rightmost compound selectors
+    subject_dependencies: HashSet<u32>,
+    /// Hashes of the ids & classes used in compound selectors followed by a
+    /// descendant or child combinator
+    descendant_dependencies: HashSet<u32>,
+    /// Hashes of the ids & classes used in compound selectors followed by a
+    /// sibling combinator
+    sibling_dependencies: HashSet<u32>,
+    has_attribute_selectors: bool,
+    /// The nodes restyled when a `href` changes, which links match
+    /// `:link` or `:visited`
+    link_hint: RestyleHint,
+    /// The nodes restyled when a `lang` changes, which the descendants
+    /// inherit for `:lang()`
+    lang_hint: RestyleHint,
+    /// The attributes whose values are used by the declarations, with `attr()`
+    attr_dependencies: HashSet<Atom>,
+    /// The pseudo-elements matched by the rules
+    pseudo_elements: HashSet<Atom>,
+}
+
+/// Whether a rule applies to the tree of a shadow root, or to the document
+/// tree without a shadow root. The user agent rules apply to every tree.
+fn is_in_scope(rule: &ContextualRule, scope: Option<&NodeRef>) -> bool {
+    match (&rule.location, scope) {
+        (CSSLocation::ShadowTree(shadow_root), scope) => Some(shadow_root) == scope,
+        (_, Some(_)) => rule.origin == CascadeOrigin::UserAgent,
+        (_, None) => true,
+    }
+}
+
+enum BucketKey<'s> {
+    Id(&'s Atom),
+    Class(&'s Atom),
+    Tag(&'s Atom),
+    Universal,
+}
+
+impl<'a, 'b> RuleIndex<'a, 'b> {
+    pub fn new(rules: &'b [ContextualRule<'a>]) -> Self {
+        let mut index = Self {
+            rules,
+            by_id: HashMap::new(),
+            by_class: HashMap::new(),
+            by_tag: HashMap::new(),
+            universal: Vec::new(),
+            ancestor_hashes: Vec::with_capacity(rules.len()),
+            subject_dependencies: HashSet::new(),
+            descendant_dependencies: HashSet::new(),
+            sibling_dependencies: HashSet::new(),
+            has_attribute_selectors: false,
+            link_hint: RestyleHint::None,
+            lang_hint: RestyleHint::None,
+            attr_dependencies: HashSet::new(),
+            pseudo_elements: HashSet::new(),
+        };
+
+        for (rule_index, rule) in rules.iter().enumerate() {
+            for selector in &rule.inner.selectors {
+                let bucket = match bucket_key(selector) {
+                    BucketKey::Id(id) => index.by_id.entry(id.clone()).or_default(),
+                    BucketKey::Class(class) => index.by_class.entry(class.clone()).or_default(),
+                    // the tag names are matched case-insensitively in HTML
+                    BucketKey::Tag(tag) => index
+                        .by_tag
+                        .entry(Atom::from(tag.to_ascii_lowercase()))
+                        .or_default(),
+                    BucketKey::Universal => &mut index.universal,
+                };
+                // a rule with several selectors in the same bucket is only added once
+                if bucket.last() != Some(&rule_index) {
+                    bucket.push(rule_index);
+                }
+                index.add_dependencies(selector);
+                if let Some(pseudo_element) = selector.pseudo_element() {
+                    index.pseudo_elements.insert(pseudo_element.clone());
+                }
+            }
+            for declaration in &rule.inner.declarations {
+                add_attr_dependencies(&declaration.value, &mut index.attr_dependencies);
+            }
+            index.ancestor_hashes.push(
+                rule.inner
+                    .selectors
+                    .iter()
+                    .map(|selector| ancestor_hashes(selector))
+                    .collect(),
+            );
+        }
+
+        index
+    }
+
+    /// A rule of the index
+    pub fn rule(&self, rule_index: usize) -> &'b ContextualRule<'a> {
+        &self.rules[rule_index]
+    }
+
+    /// All the rules of the index, in the order they were given
+    pub fn rules(&self) -> &'b [ContextualRule<'a>] {
+        self.rules
+    }
+
+    /// The indices of the rules matching an element, in the order they were
+    /// given. `ancestors` must contain all the ancestors of the element.
+    pub fn matching_rules(&self, node: &NodeRef, ancestors: &AncestorFilter) -> Vec<usize> {
+        self.matching_pseudo_rules(node, ancestors, None)
+    }
+
+    /// The indices of the rules matching a pseudo-element of an element,
+    /// or the element itself without a pseudo-element
+    pub fn matching_pseudo_rules(
+        &self,
+        node: &NodeRef,
+        ancestors: &AncestorFilter,
+        pseudo_element: Option<PseudoElement>,
+    ) -> Vec<usize> {
+        if let Some(pseudo_element) = pseudo_element {
+            if !self.pseudo_elements.contains(pseudo_element.name()) {
+                return Vec::new();
+            }
+        }
+
+        let mut candidates: Vec<usize> = Vec::new();
+        if let Some(element) = node.borrow().as_element_opt() {
+            if let Some(rules) = self.by_id.get(element.id()) {
+                candidates.extend(rules.iter().copied());
+            }
+            for class in element.class_list().iter() {
+                if let Some(rules) = self.by_class.get(class) {
+                    candidates.extend(rules.iter().copied());
+                }
+            }
+            let tag_name = Atom::from(element.tag_name().to_ascii_lowercase());
+            if let Some(rules) = self.by_tag.get(&tag_name) {
+                candidates.extend(rules.iter().copied());
+            }
+            candidates.extend(self.universal.iter().copied());
+        }
+
+        // keep the source order for the cascade
+        candidates.sort_unstable();
+        candidates.dedup();
+
+        candidates.retain(|rule_index| {
+            let rule = &self.rules[*rule_index];
+            if !is_in_scope(rule, ancestors.scope()) {
+                return false;
+            }
+            rule.inner
+                .selectors
+                .iter()
+                .zip(&self.ancestor_hashes[*rule_index])
+                .any(|(selector, hashes)| {
+                    ancestors.might_contain_all(hashes)
+                        && is_match_pseudo_selector(node.clone(), selector, pseudo_element)
+                })
+        });
+        candidates
+    }
+
+    /// Which nodes have to be restyled when an element gains or loses an id or class
+    pub fn restyle_hint(&self, kind: HashKind, value: &str) -> RestyleHint {
+        let hash = selector_hash(kind, value);
+        if self.sibling_dependencies.contains(&hash) {
+            RestyleHint::RestyleLaterSiblings
+        } else if self.descendant_dependencies.contains(&hash) {
+            RestyleHint::RestyleSubtree
+        } else if self.subject_dependencies.contains(&hash) {
+            RestyleHint::RestyleSelf
+        } else {
+            RestyleHint::None
+        }
+    }
+
+    /// Which nodes have to be restyled when any other attribute of an element changes
+    pub fn attribute_restyle_hint(&self, name: &Atom) -> RestyleHint {
+        let hint = if self.has_attribute_selectors {
+            RestyleHint::RestyleLaterSiblings
+        } else if self.attr_dependencies.contains(name) {
+            // the values are computed again with the element, even if it
+            // matches the same rules
+            RestyleHint::RestyleSubtree
+        } else {
+            RestyleHint::None
+        };
+        match name.as_ref() {
+            "href" => hint.max(self.link_hint),
+            "lang" => hint.max(self.lang_hint),
+            _ => hint,
+        }
+    }
+
+    fn add_dependencies(&mut self, selector: &Selector) {
+        self.add_nested_dependencies(selector, RestyleHint::RestyleSelf);
+    }
+
+    /// Add the dependencies of a selector, nested in a compound selector
+    /// restyled with `outer` hint, e.g. the selectors of `:not()`
+    fn add_nested_dependencies(&mut self, selector: &Selector, outer: RestyleHint) {
+        for (sequence, combinator) in selector.values() {
+            let hint = match combinator {
+                None => RestyleHint::RestyleSelf,
+                Some(Combinator::Child) | Some(Combinator::Descendant) => {
+                    RestyleHint::RestyleSubtree
+                }
+                Some(Combinator::NextSibling) | Some(Combinator::SubsequentSibling) => {
+                    RestyleHint::RestyleLaterSiblings
+                }
+            }
+            .max(outer);
+            for simple in sequence.values() {
+                let dependencies = match hint {
+                    RestyleHint::RestyleLaterSiblings => &mut self.sibling_dependencies,
+                    RestyleHint::RestyleSubtree => &mut self.descendant_dependencies,
+                    _ => &mut self.subject_dependencies,
+                };
+                match (simple.selector_type(), simple.value()) {
+                    (SimpleSelectorType::ID, Some(id)) => {
+                        dependencies.insert(selector_hash(HashKind::Id, id));
+                    }
+                    (SimpleSelectorType::Class, Some(class)) => {
+                        dependencies.insert(selector_hash(HashKind::Class, class));
+                    }
+                    (SimpleSelectorType::Attribute, _) => self.has_attribute_selectors = true,
+                    (SimpleSelectorType::Pseudo, Some(name))
+                        if matches!(name.as_ref(), "link" | "visited" | "any-link") =>
+                    {
+                        self.link_hint = self.link_hint.max(hint)
+                    }
+                    (SimpleSelectorType::Pseudo, Some(name)) if name.as_ref() == "lang" => {
+                        self.lang_hint = self.lang_hint.max(hint).max(RestyleHint::RestyleSubtree)
+                    }
+                    (SimpleSelectorType::Pseudo, _) => {
+                        if let Some(PseudoClassArguments::Selectors(selectors)) = simple.arguments()
+                        {
+                            for selector in selectors {
+                                self.add_nested_dependencies(selector, hint);
+                            }
+                        }
+                    }
+                    _ => {}
+                }
+            }
+        }
+    }
+}
+
+/// Collect the attributes used by the `attr()` functions of a value
+fn add_attr_dependencies(values: &[ComponentValue], dependencies: &mut HashSet<Atom>) {
+    for value in values {
+        if let Some(ValueFunction::Attr(name)) = parse_function(value) {
+            dependencies.insert(name);
+        } else if let ComponentValue::Function(function) = value {
+            add_attr_dependencies(&function.value, dependencies);
+        }
+    }
+}
+
+/// The most specific bucket of the rightmost compound selector
+fn bucket_key(selector: &Selector) -> BucketKey {
+    let subject = match selector.values().last() {
+        Some((sequence, _)) => sequence,
+        None => return BucketKey::Universal,
+    };
+
+    let find = |type_: SimpleSelectorType| {
+        subject
+            .values()
+            .iter()
+            .find(|simple| *simple.selector_type() == type_)
+            .and_then(|simple| simple.value().as_ref())
+    };
+
+    if let Some(id) = find(SimpleSelectorType::ID) {
+        return BucketKey::Id(id);
+    }
+    if let Some(class) = find(SimpleSelectorType::Class) {
+        return BucketKey::Class(class);
+    }
+    if let Some(tag) = find(SimpleSelectorType::Type) {
+        return BucketKey::Tag(tag);
+    }
+    BucketKey::Universal
+}
+
+/// Hashes of the ids, classes and tag names of the compound selectors that
+/// must match an ancestor of the subject
+fn ancestor_hashes(selector: &Selector) -> Vec<u32> {
+    let mut hashes = Vec::new();
+    for (sequence, combinator) in selector.values() {
+        match combinator {
+            Some(Combinator::Child) | Some(Combinator::Descendant) => {}
+            _ => continue,
+        }
+        for simple in sequence.values() {
+            let value = match simple.value() {
+                Some(value) => value,
+                None => continue,
+            };
+            match simple.selector_type() {
+                SimpleSelectorType::ID => hashes.push(selector_hash(HashKind::Id, value)),
+                SimpleSelectorType::Class => hashes.push(selector_hash(HashKind::Class, value)),
+                SimpleSelectorType::Type => hashes.push(selector_hash(HashKind::Tag, value)),
+                _ => {}
+            }
+        }
+    }
+    hashes
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::value_processing::{CSSLocation, CascadeOrigin};
+    use css::cssom::css_rule::CSSRule;
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+
+    #[test]
+    fn match_indexed_rules() {
+        let doc = document();
+        let button = element("button#submit.primary", doc.clone(), vec![]);
+        let form = element("form.login", doc.clone(), vec![button.clone()]);
+
+        let css = r#"
+        #submit { color: red; }
+        .primary { color: green; }
+        button { color: blue; }
+        * { color: black; }
+        p { color: white; }
+        .secondary { color: white; }
+        .login button { color: yellow; }
+        .signup button { color: white; }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let index = RuleIndex::new(&rules);
+        let mut ancestors = AncestorFilter::new();
+        ancestors.push_element(form.borrow().as_element());
+
+        assert_eq!(
+            index.matching_rules(&button, &ancestors),
+            vec![0, 1, 2, 3, 6]
+        );
+    }
+}
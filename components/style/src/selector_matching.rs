@@ -1,6 +1,9 @@
 use css::selector::structs::*;
+use dom::activation::is_link;
 use dom::dom_ref::NodeRef;
 use dom::element::Element;
+use dom::lang::{language, matches_language_range};
+use dom::visited::{is_unvisited_link, is_visited_link};
 
 fn get_parent(el: &NodeRef) -> Option<NodeRef> {
     let parent = el.borrow().parent();
@@ -13,7 +16,14 @@ fn get_parent(el: &NodeRef) -> Option<NodeRef> {
 }
 
 fn get_prev_sibling(el: &NodeRef) -> Option<NodeRef> {
-    el.borrow().prev_sibling()
+    let mut sibling = el.borrow().prev_sibling();
+    while let Some(node) = sibling {
+        if node.is_element() {
+            return Some(node);
+        }
+        sibling = node.borrow().prev_sibling();
+    }
+    None
 }
 
 pub fn is_match_selectors(element: &NodeRef, selectors: &Vec<Selector>) -> bool {
@@ -23,84 +33,162 @@ pub fn is_match_selectors(element: &NodeRef, selectors: &Vec<Selector>) -> bool
 }
 
 pub fn is_match_selector(element: NodeRef, selector: &Selector) -> bool {
-    let mut current_element = Some(element);
+    is_match_pseudo_selector(element, selector, None)
+}
+
+/// Whether a selector matches a pseudo-element of an element, or the
+/// element itself without a pseudo-element
+pub fn is_match_pseudo_selector(
+    element: NodeRef,
+    selector: &Selector,
+    pseudo_element: Option<PseudoElement>,
+) -> bool {
+    let selector_pseudo_element = selector.pseudo_element().map(|name| name.as_str());
+    if selector_pseudo_element != pseudo_element.map(|pseudo| pseudo.name()) {
+        return false;
+    }
+
+    let mut current_element = element;
     for (selector_seq, combinator) in selector.values().iter().rev() {
-        if let Some(el) = current_element.clone() {
-            match combinator {
-                Some(Combinator::Child) => {
-                    let parent = get_parent(&el);
-                    if let Some(p) = &parent {
-                        if !is_match_simple_selector_seq(p, selector_seq) {
-                            return false;
-                        }
-                    }
-                    current_element = parent;
-                }
-                Some(Combinator::Descendant) => loop {
-                    let parent = get_parent(&el);
-                    if let Some(p) = &parent {
-                        if is_match_simple_selector_seq(p, selector_seq) {
-                            current_element = parent;
-                            break;
-                        }
-                    } else {
-                        return false;
-                    }
-                },
-                Some(Combinator::NextSibling) => {
-                    let sibling = get_prev_sibling(&el);
-                    if let Some(sibling) = &sibling {
-                        if !is_match_simple_selector_seq(sibling, selector_seq) {
-                            return false;
-                        }
-                    }
-                    current_element = sibling;
-                }
-                Some(Combinator::SubsequentSibling) => loop {
-                    let sibling = get_prev_sibling(&el);
-                    if let Some(s) = &sibling {
-                        if is_match_simple_selector_seq(s, selector_seq) {
-                            current_element = sibling;
-                            break;
-                        }
-                    } else {
-                        return false;
-                    }
-                },
-                None => {
-                    if !is_match_simple_selector_seq(&el, selector_seq) {
-                        return false;
-                    }
-                }
+        let next_element = match combinator {
+            Some(Combinator::Child) => get_parent(&current_element)
+                .filter(|parent| is_match_simple_selector_seq(parent, selector_seq)),
+            Some(Combinator::Descendant) => {
+                find_matching(&current_element, selector_seq, get_parent)
             }
-        } else {
-            return false;
+            Some(Combinator::NextSibling) => get_prev_sibling(&current_element)
+                .filter(|sibling| is_match_simple_selector_seq(sibling, selector_seq)),
+            Some(Combinator::SubsequentSibling) => {
+                find_matching(&current_element, selector_seq, get_prev_sibling)
+            }
+            None => Some(current_element.clone())
+                .filter(|el| is_match_simple_selector_seq(el, selector_seq)),
+        };
+
+        match next_element {
+            Some(el) => current_element = el,
+            None => return false,
         }
     }
     true
 }
 
-fn is_match_simple_selector_seq(element: &NodeRef, sequence: &SimpleSelectorSequence) -> bool {
-    let element = element.borrow();
+/// Walk from an element using `next` until an element matches the sequence
+fn find_matching<F>(
+    element: &NodeRef,
+    sequence: &SimpleSelectorSequence,
+    next: F,
+) -> Option<NodeRef>
+where
+    F: Fn(&NodeRef) -> Option<NodeRef>,
+{
+    let mut current = next(element);
+    while let Some(el) = current {
+        if is_match_simple_selector_seq(&el, sequence) {
+            return Some(el);
+        }
+        current = next(&el);
+    }
+    None
+}
+
+fn get_next_sibling(el: &NodeRef) -> Option<NodeRef> {
+    let mut sibling = el.borrow().next_sibling();
+    while let Some(node) = sibling {
+        if node.is_element() {
+            return Some(node);
+        }
+        sibling = node.borrow().next_sibling();
+    }
+    None
+}
+
+fn is_match_simple_selector_seq(node: &NodeRef, sequence: &SimpleSelectorSequence) -> bool {
+    let element = node.borrow();
     let element = element.as_element();
     sequence
         .values()
         .iter()
-        .all(|selector| is_match_simple_selector(element, selector))
+        .all(|selector| is_match_simple_selector(node, element, selector))
+}
+
+/// The position of an element among the elements of its parent, counted
+/// from 1 in the order of `next`
+fn position_by<F>(element: &NodeRef, next: F) -> i32
+where
+    F: Fn(&NodeRef) -> Option<NodeRef>,
+{
+    let mut position = 1;
+    let mut current = next(element);
+    while let Some(sibling) = current {
+        position += 1;
+        current = next(&sibling);
+    }
+    position
+}
+
+/// Whether a pseudo-class matches an element. The pseudo-classes of the
+/// user actions, e.g. `:hover`, never match. A link is either `:link` or
+/// `:visited`, & `:any-link` either way.
+fn is_match_pseudo_class(node: &NodeRef, selector: &SimpleSelector) -> bool {
+    let name = match selector.value() {
+        Some(name) => name.as_ref(),
+        None => return false,
+    };
+    match (name, selector.arguments()) {
+        ("not", Some(PseudoClassArguments::Selectors(selectors))) => {
+            !is_match_selectors(node, selectors)
+        }
+        ("is", Some(PseudoClassArguments::Selectors(selectors)))
+        | ("where", Some(PseudoClassArguments::Selectors(selectors))) => {
+            is_match_selectors(node, selectors)
+        }
+        ("nth-child", Some(PseudoClassArguments::Nth(positions))) => {
+            positions.matches(position_by(node, get_prev_sibling))
+        }
+        ("nth-last-child", Some(PseudoClassArguments::Nth(positions))) => {
+            positions.matches(position_by(node, get_next_sibling))
+        }
+        ("lang", Some(PseudoClassArguments::Languages(ranges))) => match language(node) {
+            Some(lang) => ranges
+                .iter()
+                .any(|range| matches_language_range(&lang, range)),
+            None => false,
+        },
+        ("link", None) => is_unvisited_link(node),
+        ("visited", None) => is_visited_link(node),
+        ("any-link", None) => is_link(node),
+        ("first-child", None) => get_prev_sibling(node).is_none(),
+        ("last-child", None) => get_next_sibling(node).is_none(),
+        ("only-child", None) => {
+            get_prev_sibling(node).is_none() && get_next_sibling(node).is_none()
+        }
+        _ => false,
+    }
+}
+
+fn is_match_namespace(element: &Element, namespace: &NamespaceConstraint) -> bool {
+    match namespace {
+        NamespaceConstraint::Any => true,
+        // the elements always have a namespace
+        NamespaceConstraint::None => false,
+        NamespaceConstraint::Url(url) => element.namespace().url() == url.as_str(),
+    }
 }
 
-fn is_match_simple_selector(element: &Element, selector: &SimpleSelector) -> bool {
+fn is_match_simple_selector(node: &NodeRef, element: &Element, selector: &SimpleSelector) -> bool {
     match selector.selector_type() {
-        SimpleSelectorType::Universal => true,
+        SimpleSelectorType::Universal => is_match_namespace(element, selector.namespace()),
         SimpleSelectorType::Type => {
             if let Some(type_name) = selector.value() {
-                return element.tag_name() == *type_name;
+                return element.has_tag_name(type_name)
+                    && is_match_namespace(element, selector.namespace());
             }
             false
         }
         SimpleSelectorType::Class => {
             if let Some(type_name) = selector.value() {
-                return element.class_list().contains(&type_name);
+                return element.class_list().contains_atom(type_name);
             }
             false
         }
@@ -110,6 +198,9 @@ fn is_match_simple_selector(element: &Element, selector: &SimpleSelector) -> boo
             }
             false
         }
+        // checked against the pseudo-element being matched
+        SimpleSelectorType::PseudoElement => true,
+        SimpleSelectorType::Pseudo => is_match_pseudo_class(node, selector),
         _ => false,
     }
 }
@@ -121,8 +212,9 @@ mod tests {
     use css::parser::Parser;
     use css::tokenizer::token::Token;
     use css::tokenizer::Tokenizer;
-    use dom::create_element;
+    use dom::element::Namespace;
     use dom::node::Node;
+    use dom::{create_element, create_element_ns};
     use test_utils::dom_creator::document;
 
     #[test]
@@ -142,6 +234,7 @@ mod tests {
                 let selectors = &style.selectors;
                 assert!(is_match_selectors(&element, selectors));
             }
+            _ => panic!("expected a style rule"),
         }
     }
 
@@ -166,9 +259,37 @@ mod tests {
                 let selectors = &style.selectors;
                 assert!(is_match_selectors(&element_node, selectors));
             }
+            _ => panic!("expected a style rule"),
         }
     }
 
+    #[test]
+    fn match_distant_decendant() {
+        let doc = document();
+        let grand_parent = create_element(doc.clone().downgrade(), "h1");
+        let parent = create_element(doc.clone().downgrade(), "div");
+        let child = create_element(doc.clone().downgrade(), "button");
+        Node::append_child(grand_parent.clone(), parent.clone());
+        Node::append_child(parent.clone(), child.clone());
+
+        let css = "h1 button { color: red; } p button { color: red; }";
+
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let matches = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(is_match_selectors(&child, &style.selectors)),
+                _ => None,
+            })
+            .collect::<Vec<bool>>();
+
+        assert_eq!(matches, vec![true, false]);
+    }
+
     #[test]
     fn match_simple_decendant() {
         let doc = document();
@@ -190,6 +311,7 @@ mod tests {
                 let selectors = &style.selectors;
                 assert!(is_match_selectors(&child, selectors));
             }
+            _ => panic!("expected a style rule"),
         }
     }
 
@@ -214,6 +336,7 @@ mod tests {
                 let selectors = &style.selectors;
                 assert!(is_match_selectors(&child, selectors));
             }
+            _ => panic!("expected a style rule"),
         }
     }
 
@@ -238,6 +361,7 @@ mod tests {
                 let selectors = &style.selectors;
                 assert!(!is_match_selectors(&child, selectors));
             }
+            _ => panic!("expected a style rule"),
         }
     }
 
@@ -262,6 +386,7 @@ mod tests {
                 let selectors = &style.selectors;
                 assert!(!is_match_selectors(&child, selectors));
             }
+            _ => panic!("expected a style rule"),
         }
     }
 
@@ -287,6 +412,134 @@ mod tests {
                 assert!(is_match_selectors(&child, selectors));
                 assert!(is_match_selectors(&parent, selectors));
             }
+            _ => panic!("expected a style rule"),
         }
     }
+
+    #[test]
+    fn match_namespaced_types() {
+        let doc = document();
+        let div = create_element(doc.clone().downgrade(), "div");
+        let svg = create_element_ns(doc.clone().downgrade(), "svg", Namespace::Svg);
+        let rect = create_element_ns(doc.clone().downgrade(), "rect", Namespace::Svg);
+
+        let css = r#"
+        @namespace url(http://www.w3.org/1999/xhtml);
+        @namespace svg "http://www.w3.org/2000/svg";
+        svg|rect { color: red; }
+        *|rect { color: red; }
+        rect { color: red; }
+        |rect { color: red; }
+        svg|* { color: red; }
+        * { color: red; }
+        math|* { color: red; }
+        "#;
+
+        let tokenizer = Tokenizer::new(css.chars());
+        let tokens = tokenizer.run();
+        let mut parser = Parser::<Token>::new(tokens);
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        // the rule with an undeclared prefix is dropped
+        let matches = |element: &NodeRef| {
+            stylesheet
+                .iter()
+                .filter_map(|rule| match rule {
+                    CSSRule::Style(style) => Some(is_match_selectors(element, &style.selectors)),
+                    _ => None,
+                })
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matches(&rect), [true, true, false, false, true, false]);
+        assert_eq!(matches(&svg), [false, false, false, false, true, false]);
+        assert_eq!(matches(&div), [false, false, false, false, false, true]);
+    }
+
+    #[test]
+    fn match_functional_pseudo_classes() {
+        let doc = document();
+        let list = create_element(doc.clone().downgrade(), "ul");
+        let items = (0..5)
+            .map(|_| {
+                let item = create_element(doc.clone().downgrade(), "li");
+                Node::append_child(list.clone(), item.clone());
+                item
+            })
+            .collect::<Vec<_>>();
+        Node::set_attribute(&items[1], "class", "done");
+        Node::set_attribute(&items[3], "class", "done");
+
+        let css = r#"
+        li:not(.done) { color: red; }
+        ul :is(.done, :first-child) { color: red; }
+        :where(ul > li):nth-child(odd) { color: red; }
+        li:nth-last-child(-n + 2) { color: red; }
+        li:last-child { color: red; }
+        li:not(:not(.done)) { color: red; }
+        "#;
+
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let matches = |rule_index: usize| {
+            let selectors = match &stylesheet[rule_index] {
+                CSSRule::Style(style) => &style.selectors,
+                _ => panic!("expected a style rule"),
+            };
+            items
+                .iter()
+                .map(|item| is_match_selectors(item, selectors))
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(matches(0), [true, false, true, false, true]);
+        assert_eq!(matches(1), [true, true, false, true, false]);
+        assert_eq!(matches(2), [true, false, true, false, true]);
+        assert_eq!(matches(3), [false, false, false, true, true]);
+        assert_eq!(matches(4), [false, false, false, false, true]);
+        assert_eq!(matches(5), [false, true, false, true, false]);
+    }
+
+    #[test]
+    fn match_lang_pseudo_class() {
+        let doc = document();
+        let article = create_element(doc.clone().downgrade(), "article");
+        Node::set_attribute(&article, "lang", "de-Latn-CH");
+        let quotes = ["", "fr", "en-US"]
+            .iter()
+            .map(|lang| {
+                let quote = create_element(doc.clone().downgrade(), "q");
+                if !lang.is_empty() {
+                    Node::set_attribute(&quote, "lang", lang);
+                }
+                Node::append_child(article.clone(), quote.clone());
+                quote
+            })
+            .collect::<Vec<_>>();
+
+        let css = r#"
+        q:lang(de-CH) { color: red; }
+        q:lang(fr, "en") { color: red; }
+        :lang(\*-US) { color: red; }
+        "#;
+
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        let stylesheet = parser.parse_a_css_stylesheet();
+
+        let matches = |rule_index: usize| {
+            let selectors = match &stylesheet[rule_index] {
+                CSSRule::Style(style) => &style.selectors,
+                _ => panic!("expected a style rule"),
+            };
+            quotes
+                .iter()
+                .map(|quote| is_match_selectors(quote, selectors))
+                .collect::<Vec<_>>()
+        };
+        // the language is inherited from the article
+        assert_eq!(matches(0), [true, false, false]);
+        assert_eq!(matches(1), [false, true, true]);
+        assert_eq!(matches(2), [false, false, true]);
+    }
 }
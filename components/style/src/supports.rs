@@ -0,0 +1,84 @@
+/// The rules of `@supports`, applied when the engine supports their
+/// condition, i.e. when the declarations of the condition parse into
+/// the values of the properties they set.
+/// https://www.w3.org/TR/css-conditional-3/#at-supports
+use super::media::{self, MediaFeatures};
+use super::value_processing::parse_declaration;
+use css::cssom::css_rule::CSSRule;
+use css::parser::structs::Declaration;
+
+/// Whether the property of a declaration is supported with its value
+pub fn supports_declaration(declaration: &Declaration) -> bool {
+    !parse_declaration(declaration).is_empty()
+}
+
+/// The rules of a list applying to the documents, with the rules of the
+/// supported `@supports` rules & of the `@media` rules matching the
+/// environment in place of them
+pub fn applicable_rules<'a>(rules: &'a [CSSRule], features: &MediaFeatures) -> Vec<&'a CSSRule> {
+    let mut applicable = Vec::new();
+    for rule in rules {
+        match rule {
+            CSSRule::Supports(supports) => {
+                if supports.condition.evaluate(&supports_declaration) {
+                    applicable.extend(applicable_rules(&supports.css_rules, features));
+                }
+            }
+            CSSRule::Media(rule) => {
+                if media::matches(&rule.queries, features) {
+                    applicable.extend(applicable_rules(&rule.css_rules, features));
+                }
+            }
+            rule => applicable.push(rule),
+        }
+    }
+    applicable
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::structs::ComponentValue;
+    use css::tokenizer::token::Token;
+    use test_utils::css::parse_stylesheet;
+
+    fn widths(rules: Vec<&CSSRule>) -> Vec<f32> {
+        rules
+            .into_iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => match style.declarations[0].value[0] {
+                    ComponentValue::PerservedToken(Token::Dimension { value, .. }) => Some(value),
+                    _ => None,
+                },
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn apply_supported_rules() {
+        let stylesheet = parse_stylesheet(
+            r#"
+            div { width: 1px; }
+            @supports (display: flex) { div { width: 2px; } }
+            @supports (display: foo) { div { width: 3px; } }
+            @supports not (foo: bar) {
+                div { width: 4px; }
+                @supports (width: 5px) or (--unknown) { div { width: 5px; } }
+            }
+            @supports (color: red) and (float: left) { div { width: 6px; } }
+            @supports (--unknown) or selector(div > p) { div { width: 7px; } }
+            @media (max-width: 600px) { div { width: 8px; } }
+            @media (min-width: 600px) {
+                @supports (display: block) { div { width: 9px; } }
+            }
+            "#,
+        );
+
+        let features = MediaFeatures::new(800., 600.);
+        assert_eq!(
+            widths(applicable_rules(&stylesheet, &features)),
+            [1., 2., 4., 5., 6., 7., 9.]
+        );
+    }
+}
@@ -0,0 +1,373 @@
+/// CSS transitions. When a restyle changes the computed value of a property
+/// listed in `transition-property`, the value moves from the old value to the
+/// new one over the duration of the transition, on the clock of the document,
+/// instead of changing at once.
+/// https://www.w3.org/TR/css-transitions-1/
+use super::inheritable::INHERITABLES;
+use super::interpolate::{can_interpolate, interpolate};
+use super::render_tree::{RenderNode, RenderNodeRef, RenderNodeWeak};
+use super::value_processing::{Property, Value, ValueRef};
+use super::values::timing_function::TimingFunction;
+use std::time::Duration;
+use strum::IntoEnumIterator;
+
+/// A running transition of a property of a render node
+#[derive(Debug)]
+struct Transition {
+    render_node: RenderNodeWeak,
+    property: Property,
+    from: Value,
+    to: Value,
+    /// The time the transition starts after its delay, in milliseconds.
+    /// A negative delay starts the transition part way through.
+    start: f64,
+    duration: f64,
+    timing_function: TimingFunction,
+}
+
+/// The duration, delay & easing of the transitions of a property
+struct TransitionParams {
+    duration: f64,
+    delay: f64,
+    timing_function: TimingFunction,
+}
+
+/// The running transitions of a render tree and the time of its clock
+#[derive(Debug, Default)]
+pub struct Transitions {
+    running: Vec<Transition>,
+    /// The time of the last sample, when the transitions started by a restyle begin
+    current_time: Duration,
+    /// Whether restyles start no transition
+    disabled: bool,
+}
+
+impl Transition {
+    fn targets(&self, render_node: &RenderNodeRef) -> bool {
+        self.render_node.upgrade().as_ref() == Some(render_node)
+    }
+
+    fn value_at(&self, time: f64) -> Value {
+        let progress = ((time - self.start) / self.duration).max(0.).min(1.);
+        let progress = self.timing_function.output(progress as f32);
+        interpolate(&self.from, &self.to, progress).unwrap_or_else(|| self.to.clone())
+    }
+
+    fn is_finished(&self, time: f64) -> bool {
+        time >= self.start + self.duration
+    }
+}
+
+impl Transitions {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Whether some transitions are still running, i.e. the styles of
+    /// the render tree change when the clock advances
+    pub fn is_running(&self) -> bool {
+        !self.running.is_empty()
+    }
+
+    /// Advance the clock & apply the values of the running transitions at
+    /// the new time to their render nodes. Returns whether any style changed.
+    pub fn sample(&mut self, time: Duration) -> bool {
+        self.current_time = time;
+        let now = as_millis(time);
+        let mut changed = false;
+
+        self.running.retain(|transition| {
+            let render_node = match transition.render_node.upgrade() {
+                Some(render_node) => render_node,
+                None => return false,
+            };
+
+            let value = ValueRef::new(transition.value_at(now));
+            let current = render_node
+                .borrow()
+                .properties
+                .get(&transition.property)
+                .cloned();
+            if current.as_ref() != Some(&value) {
+                apply_value(&render_node, &transition.property, &value);
+                if affects_layout(&value) {
+                    mark_needs_layout(&render_node);
+                }
+                changed = true;
+            }
+
+            !transition.is_finished(now)
+        });
+
+        changed
+    }
+
+    /// Update the transitions for a render node replaced by a restyle and
+    /// its descendants. Transitions start for the properties whose computed
+    /// value changed and the running ones move to the new render nodes.
+    /// Start the transitions of the render tree or not, e.g. to render
+    /// every style change right away
+    pub fn set_enabled(&mut self, enabled: bool) {
+        self.disabled = !enabled;
+        if self.disabled {
+            self.running.clear();
+        }
+    }
+
+    pub(crate) fn restyled(&mut self, old: &RenderNodeRef, new: &RenderNodeRef) {
+        if self.disabled {
+            return;
+        }
+        if new.borrow().node.is_element() {
+            self.restyled_node(old, new);
+        }
+
+        let old_children = old.borrow().children.clone();
+        for new_child in &new.borrow().children {
+            let old_child = old_children
+                .iter()
+                .find(|old_child| old_child.borrow().node == new_child.borrow().node);
+            if let Some(old_child) = old_child {
+                self.restyled(old_child, new_child);
+            }
+        }
+    }
+
+    fn restyled_node(&mut self, old: &RenderNodeRef, new: &RenderNodeRef) {
+        let now = as_millis(self.current_time);
+
+        for property in Property::iter() {
+            let old_value = old.borrow().properties.get(&property).cloned();
+            let new_value = new.borrow().properties.get(&property).cloned();
+            let (old_value, new_value) = match (old_value, new_value) {
+                (Some(old_value), Some(new_value)) => (old_value, new_value),
+                _ => continue,
+            };
+            let params = transition_params(&new.borrow(), &property);
+
+            let running = self
+                .running
+                .iter()
+                .position(|transition| transition.targets(old) && transition.property == property);
+            if let Some(index) = running {
+                // the transition keeps running as long as its end value is the same
+                if params.is_some() && self.running[index].to == **new_value {
+                    self.running[index].render_node = new.downgrade();
+                    continue;
+                }
+                self.running.remove(index);
+            }
+
+            if old_value == new_value || !can_interpolate(&old_value, &new_value) {
+                continue;
+            }
+            if let Some(params) = params {
+                // the transition starts from the value shown before the restyle,
+                // which is the current value of an interrupted transition
+                self.running.push(Transition {
+                    render_node: new.downgrade(),
+                    property,
+                    from: (**old_value).clone(),
+                    to: (**new_value).clone(),
+                    start: now + params.delay,
+                    duration: params.duration,
+                    timing_function: params.timing_function,
+                });
+            }
+        }
+
+        // the new render node shows the current values of its transitions
+        for transition in &self.running {
+            if transition.targets(new) {
+                let value = ValueRef::new(transition.value_at(now));
+                apply_value(new, &transition.property, &value);
+            }
+        }
+    }
+}
+
+pub(crate) fn as_millis(time: Duration) -> f64 {
+    time.as_secs_f64() * 1000.
+}
+
+/// The parameters of the transitions of a property of a render node,
+/// if the property is transitioned
+fn transition_params(render_node: &RenderNode, property: &Property) -> Option<TransitionParams> {
+    let index = match render_node.get_style(&Property::TransitionProperty).inner() {
+        Value::TransitionProperty(transition_property) => transition_property.index_of(property)?,
+        _ => return None,
+    };
+
+    let time_at = |list_property: &Property| match render_node.get_style(list_property).inner() {
+        Value::TimeList(times) => *times.get(index).milliseconds as f64,
+        _ => 0.,
+    };
+    let duration = time_at(&Property::TransitionDuration);
+    let delay = time_at(&Property::TransitionDelay);
+    let timing_function = match render_node
+        .get_style(&Property::TransitionTimingFunction)
+        .inner()
+    {
+        Value::TimingFunctionList(functions) => functions.get(index),
+        _ => TimingFunction::ease(),
+    };
+
+    if duration <= 0. {
+        return None;
+    }
+    Some(TransitionParams {
+        duration,
+        delay,
+        timing_function,
+    })
+}
+
+/// Set the value of a property of a render node. The descendants
+/// inheriting the value of an inherited property get the value too.
+pub(crate) fn apply_value(render_node: &RenderNodeRef, property: &Property, value: &ValueRef) {
+    let previous = render_node
+        .borrow_mut()
+        .properties
+        .insert(property.clone(), value.clone());
+
+    if INHERITABLES.contains(property) {
+        for child in &render_node.borrow().children {
+            let inherits = child.borrow().properties.get(property) == previous.as_ref();
+            if inherits {
+                apply_value(child, property, value);
+            }
+        }
+    }
+}
+
+/// Colors only change the painting of the boxes
+pub(crate) fn affects_layout(value: &Value) -> bool {
+    match value {
+        Value::Color(_) => false,
+        _ => true,
+    }
+}
+
+/// Mark a render node to be laid out again. Its ancestors are marked
+/// too so their boxes aren't reused with the previous layout, up to the
+/// nearest layout root whose contents are laid out again on their own.
+pub(crate) fn mark_needs_layout(render_node: &RenderNodeRef) {
+    render_node.borrow_mut().needs_layout = true;
+    let mut current = render_node.borrow().parent_render_node.clone();
+    while let Some(node) = current.and_then(|parent| parent.upgrade()) {
+        node.borrow_mut().needs_layout = true;
+        if node.borrow().is_layout_root() {
+            break;
+        }
+        current = node.borrow().parent_render_node.clone();
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::render_tree::{build_render_tree, RenderTree};
+    use crate::restyle::restyle_render_tree;
+    use crate::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
+    use crate::values::color::Color;
+    use crate::values::length::Length;
+    use css::cssom::css_rule::CSSRule;
+    use dom::node::Node;
+    use test_utils::css::parse_stylesheet;
+    use test_utils::dom_creator::*;
+
+    fn style(render_node: &RenderNodeRef, property: Property) -> Value {
+        (**render_node.borrow().get_style(&property)).clone()
+    }
+
+    fn box_node(tree: &RenderTree) -> RenderNodeRef {
+        tree.root.clone().unwrap().borrow().children[0].clone()
+    }
+
+    #[test]
+    fn transition_changed_values() {
+        let doc = document();
+        let inner = element("span", doc.clone(), vec![]);
+        let target = element("div.box", doc.clone(), vec![inner.clone()]);
+        let root = element("div", doc.clone(), vec![target.clone()]);
+
+        let css = r#"
+        .box {
+            width: 10px;
+            height: 10px;
+            color: rgb(0, 0, 0);
+            transition: width 100ms linear, color 200ms linear 100ms;
+        }
+        .box.wide {
+            width: 110px;
+            height: 50px;
+            color: rgb(200, 0, 0);
+        }
+        "#;
+
+        let stylesheet = parse_stylesheet(css);
+        let rules = stylesheet
+            .iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+            .collect::<Vec<ContextualRule>>();
+
+        let mut tree = build_render_tree(root.clone(), &rules);
+        assert!(!tree.transitions.is_running());
+
+        tree.transitions.sample(Duration::from_millis(1000));
+        Node::set_attribute(&target, "class", "box wide");
+        restyle_render_tree(&mut tree, &rules);
+        assert!(tree.transitions.is_running());
+
+        let rgb =
+            |r: f32| Value::Color(Color::Rgba(r.into(), 0.0.into(), 0.0.into(), 255.0.into()));
+
+        // the transitions start from the old values, the other properties change at once
+        assert_eq!(
+            style(&box_node(&tree), Property::Width),
+            Value::Length(Length::new_px(10.))
+        );
+        assert_eq!(
+            style(&box_node(&tree), Property::Height),
+            Value::Length(Length::new_px(50.))
+        );
+
+        box_node(&tree).borrow_mut().needs_layout = false;
+        assert!(tree.transitions.sample(Duration::from_millis(1050)));
+        assert_eq!(
+            style(&box_node(&tree), Property::Width),
+            Value::Length(Length::new_px(60.))
+        );
+        assert!(box_node(&tree).borrow().needs_layout);
+        // the color transition is delayed
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(0.));
+
+        tree.transitions.sample(Duration::from_millis(1200));
+        assert_eq!(
+            style(&box_node(&tree), Property::Width),
+            Value::Length(Length::new_px(110.))
+        );
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(100.));
+        // the descendants inherit the transitioned color
+        let inner_node = box_node(&tree).borrow().children[0].clone();
+        assert_eq!(style(&inner_node, Property::Color), rgb(100.));
+
+        // reverting the change transitions back from the current value
+        Node::set_attribute(&target, "class", "box");
+        restyle_render_tree(&mut tree, &rules);
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(100.));
+
+        tree.transitions.sample(Duration::from_millis(1400));
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(50.));
+        tree.transitions.sample(Duration::from_millis(1500));
+        assert_eq!(style(&box_node(&tree), Property::Color), rgb(0.));
+        assert!(!tree.transitions.is_running());
+    }
+}
@@ -1,5 +1,6 @@
+use super::property_map::PropertyMap;
 use super::render_tree::RenderNodeWeak;
-use super::selector_matching::is_match_selectors;
+use atom::Atom;
 use css::cssom::style_rule::StyleRule;
 use css::parser::structs::ComponentValue;
 use css::parser::structs::Declaration;
@@ -14,23 +15,33 @@ use std::rc::Rc;
 use strum_macros::*;
 
 use super::expand::prelude::*;
+use super::values::length::LengthContext;
 use super::values::prelude::*;
 
 // computes
 use super::computes::color::compute_color;
+use super::computes::content::compute_content;
+use super::computes::font_size::compute_font_size;
+use super::computes::length::compute_length;
 
 type DeclaredValuesMap = HashMap<Property, Vec<PropertyDeclaration>>;
 
 pub type Properties = HashMap<Property, Option<Value>>;
 
 /// CSS property name
-#[derive(Debug, Clone, Hash, Eq, PartialEq, EnumIter)]
+#[derive(Debug, Clone, Hash, Eq, PartialEq, EnumIter, EnumCount)]
 pub enum Property {
     BackgroundColor,
+    BackgroundImage,
     Color,
     Display,
     Width,
     Height,
+    MinWidth,
+    MinHeight,
+    MaxWidth,
+    MaxHeight,
+    BoxSizing,
     MarginTop,
     MarginRight,
     MarginBottom,
@@ -62,12 +73,57 @@ pub enum Property {
     Top,
     Bottom,
     Direction,
+    Transform,
+    Visibility,
+    ListStyleType,
+    ListStylePosition,
+    BorderCollapse,
+    BorderSpacing,
+    TableLayout,
+    WhiteSpace,
+    OverflowWrap,
+    Hyphens,
+    TextDecorationLine,
+    TextDecorationStyle,
+    TextDecorationColor,
+    TextAlign,
+    VerticalAlign,
+    LineHeight,
+    TransitionProperty,
+    TransitionDuration,
+    TransitionTimingFunction,
+    TransitionDelay,
+    AnimationName,
+    AnimationDuration,
+    AnimationTimingFunction,
+    AnimationIterationCount,
+    AnimationDirection,
+    AnimationPlayState,
+    AnimationDelay,
+    AnimationFillMode,
+    Content,
+    Quotes,
+    ContentVisibility,
+    Contain,
+    Cursor,
+    FontSize,
+    FontFamily,
+}
+
+/// The size the percentages of a property are relative to. The
+/// percentages of these properties are kept in their computed values and
+/// resolved by the layout, once the size is known.
+#[derive(Debug, Clone, Copy, Eq, PartialEq)]
+pub enum PercentageBasis {
+    ContainingBlockWidth,
+    ContainingBlockHeight,
 }
 
 /// CSS property value
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Value {
     Color(Color),
+    BackgroundImage(BackgroundImage),
     Display(Display),
     Length(Length),
     Percentage(Percentage),
@@ -77,7 +133,40 @@ pub enum Value {
     Position(Position),
     Direction(Direction),
     BorderRadius(BorderRadius),
+    Transform(Transform),
+    Visibility(Visibility),
+    ListStyleType(ListStyleType),
+    ListStylePosition(ListStylePosition),
+    BorderCollapse(BorderCollapse),
+    BorderSpacing(BorderSpacing),
+    TableLayout(TableLayout),
+    WhiteSpace(WhiteSpace),
+    OverflowWrap(OverflowWrap),
+    Hyphens(Hyphens),
+    TextDecorationLine(TextDecorationLine),
+    TextDecorationStyle(TextDecorationStyle),
+    TextAlign(TextAlign),
+    VerticalAlign(VerticalAlign),
+    LineHeight(LineHeight),
+    TransitionProperty(TransitionProperty),
+    TimeList(TimeList),
+    TimingFunctionList(TimingFunctionList),
+    AnimationName(AnimationName),
+    Content(Content),
+    Quotes(Quotes),
+    IterationCountList(IterationCountList),
+    AnimationDirectionList(AnimationDirectionList),
+    AnimationPlayStateList(AnimationPlayStateList),
+    AnimationFillModeList(AnimationFillModeList),
+    BoxSizing(BoxSizing),
+    ContentVisibility(ContentVisibility),
+    Contain(Contain),
+    Cursor(Cursor),
+    FontSize(FontSize),
+    FontFamily(FontFamily),
     Auto,
+    /// The `none` keyword of the properties without a value, e.g. `max-width`
+    None,
     Inherit,
     Initial,
     Unset,
@@ -102,6 +191,19 @@ pub enum CSSLocation {
     Embedded,
     /// External CSS (in external css file)
     External,
+    /// CSS in a stylesheet of a shadow root, matching only its tree
+    ShadowTree(NodeRef),
+}
+
+impl CSSLocation {
+    /// The rank of the location in the cascade, the inline CSS wins
+    fn rank(&self) -> u8 {
+        match self {
+            CSSLocation::External => 0,
+            CSSLocation::Embedded | CSSLocation::ShadowTree(_) => 1,
+            CSSLocation::Inline => 2,
+        }
+    }
 }
 
 /// Cascade origin
@@ -111,6 +213,8 @@ pub enum CascadeOrigin {
     Author,
     User,
     UserAgent,
+    /// The values of the running CSS animations
+    Animation,
 }
 
 /// Style rule with context for cascading
@@ -124,8 +228,9 @@ pub struct ContextualRule<'a> {
 /// Context for computing values
 pub struct ComputeContext<'a> {
     pub parent: &'a Option<RenderNodeWeak>,
-    pub properties: HashMap<Property, Value>,
+    pub properties: &'a PropertyMap<Value>,
     pub style_cache: &'a mut HashSet<ValueRef>,
+    pub lengths: &'a LengthContext,
 }
 
 // TODO: drop the value from cache when rc is dropped to 1
@@ -192,6 +297,63 @@ fn parse_keyword(tokens: &[ComponentValue], keyword: &str) -> bool {
     }
 }
 
+/// A function of a property value, parsed by the values accepting it
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ValueFunction {
+    /// `url(moon.svg)` or `url("moon.svg")`
+    Url(String),
+    /// The value of an attribute of the element, resolved when the value
+    /// is computed
+    /// https://www.w3.org/TR/css-values-4/#attr-notation
+    Attr(Atom),
+}
+
+type FunctionParser = fn(&[ComponentValue]) -> Option<ValueFunction>;
+
+/// The parsers of the arguments of the functions, by name. The values
+/// using other functions, e.g. `calc()` or `var()`, are invalid.
+const VALUE_FUNCTIONS: &[(&str, FunctionParser)] =
+    &[("url", parse_url_function), ("attr", parse_attr_function)];
+
+/// Parse a function of a property value with the parser registered for
+/// its name
+pub fn parse_function(value: &ComponentValue) -> Option<ValueFunction> {
+    match value {
+        // the unquoted URLs are tokenized as a whole
+        ComponentValue::PerservedToken(Token::Url(url)) => Some(ValueFunction::Url(url.clone())),
+        ComponentValue::Function(function) => {
+            let (_, parse) = VALUE_FUNCTIONS
+                .iter()
+                .find(|(name, _)| function.name.eq_ignore_ascii_case(name))?;
+            let arguments = function
+                .value
+                .iter()
+                .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+                .cloned()
+                .collect::<Vec<_>>();
+            parse(&arguments)
+        }
+        _ => None,
+    }
+}
+
+fn parse_url_function(arguments: &[ComponentValue]) -> Option<ValueFunction> {
+    match arguments {
+        [ComponentValue::PerservedToken(Token::Str(url))] => Some(ValueFunction::Url(url.clone())),
+        _ => None,
+    }
+}
+
+/// Parse `attr(<attr-name>)`, the attribute types & fallbacks aren't supported
+fn parse_attr_function(arguments: &[ComponentValue]) -> Option<ValueFunction> {
+    match arguments {
+        [ComponentValue::PerservedToken(Token::Ident(name))] => {
+            Some(ValueFunction::Attr(Atom::from(name.to_ascii_lowercase())))
+        }
+        _ => None,
+    }
+}
+
 macro_rules! parse_value {
     (Auto; $tokens:ident) => {{
         if parse_keyword($tokens, "auto") {
@@ -200,6 +362,13 @@ macro_rules! parse_value {
             None
         }
     }};
+    (None; $tokens:ident) => {{
+        if parse_keyword($tokens, "none") {
+            Some(Value::None)
+        } else {
+            None
+        }
+    }};
     (Inherit; $tokens:ident) => {{
         if parse_keyword($tokens, "inherit") {
             Some(Value::Inherit)
@@ -244,6 +413,10 @@ impl Value {
                 Color | Inherit | Initial | Unset;
                 tokens
             ),
+            Property::BackgroundImage => parse_value!(
+                BackgroundImage | Inherit | Initial | Unset;
+                tokens
+            ),
             Property::Color => parse_value!(
                 Color | Inherit | Initial | Unset;
                 tokens
@@ -260,6 +433,26 @@ impl Value {
                 Length | Percentage | Auto | Inherit | Initial | Unset;
                 tokens
             ),
+            Property::MinWidth => parse_value!(
+                Length | Percentage | Auto | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::MinHeight => parse_value!(
+                Length | Percentage | Auto | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::MaxWidth => parse_value!(
+                Length | Percentage | None | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::MaxHeight => parse_value!(
+                Length | Percentage | None | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::BoxSizing => parse_value!(
+                BoxSizing | Inherit | Initial | Unset;
+                tokens
+            ),
             Property::MarginTop => parse_value!(
                 Length | Percentage | Auto | Inherit | Initial | Unset;
                 tokens
@@ -384,16 +577,162 @@ impl Value {
                 BorderRadius | Inherit | Initial | Unset;
                 tokens
             ),
+            Property::Transform => parse_value!(
+                Transform | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::Visibility => parse_value!(
+                Visibility | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::ListStyleType => parse_value!(
+                ListStyleType | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::ListStylePosition => parse_value!(
+                ListStylePosition | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::BorderCollapse => parse_value!(
+                BorderCollapse | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::BorderSpacing => parse_value!(
+                BorderSpacing | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TableLayout => parse_value!(
+                TableLayout | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::WhiteSpace => parse_value!(
+                WhiteSpace | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::OverflowWrap => parse_value!(
+                OverflowWrap | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::Hyphens => parse_value!(
+                Hyphens | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TextDecorationLine => parse_value!(
+                TextDecorationLine | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TextDecorationStyle => parse_value!(
+                TextDecorationStyle | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TextDecorationColor => parse_value!(
+                Color | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TextAlign => parse_value!(
+                TextAlign | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::VerticalAlign => parse_value!(
+                VerticalAlign | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::LineHeight => parse_value!(
+                LineHeight | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TransitionProperty => parse_value!(
+                TransitionProperty | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TransitionDuration => parse_value!(
+                TimeList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TransitionTimingFunction => parse_value!(
+                TimingFunctionList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::TransitionDelay => parse_value!(
+                TimeList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationName => parse_value!(
+                AnimationName | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationDuration => parse_value!(
+                TimeList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationTimingFunction => parse_value!(
+                TimingFunctionList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationIterationCount => parse_value!(
+                IterationCountList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationDirection => parse_value!(
+                AnimationDirectionList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationPlayState => parse_value!(
+                AnimationPlayStateList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationDelay => parse_value!(
+                TimeList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::AnimationFillMode => parse_value!(
+                AnimationFillModeList | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::Content => parse_value!(
+                Content | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::Quotes => parse_value!(
+                Quotes | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::ContentVisibility => parse_value!(
+                ContentVisibility | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::Contain => parse_value!(
+                Contain | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::Cursor => parse_value!(
+                Cursor | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::FontSize => parse_value!(
+                FontSize | Inherit | Initial | Unset;
+                tokens
+            ),
+            Property::FontFamily => parse_value!(
+                FontFamily | Inherit | Initial | Unset;
+                tokens
+            ),
         }
     }
 
     pub fn initial(property: &Property) -> Value {
         match property {
             Property::BackgroundColor => Value::Color(Color::transparent()),
+            Property::BackgroundImage => Value::BackgroundImage(BackgroundImage::None),
             Property::Color => Value::Color(Color::black()),
             Property::Display => Value::Display(Display::new_inline()),
             Property::Width => Value::Auto,
             Property::Height => Value::Auto,
+            Property::MinWidth => Value::Auto,
+            Property::MinHeight => Value::Auto,
+            Property::MaxWidth => Value::None,
+            Property::MaxHeight => Value::None,
+            Property::BoxSizing => Value::BoxSizing(BoxSizing::ContentBox),
             Property::MarginTop => Value::Length(Length::zero()),
             Property::MarginRight => Value::Length(Length::zero()),
             Property::MarginBottom => Value::Length(Length::zero()),
@@ -425,18 +764,102 @@ impl Value {
             Property::BorderTopRightRadius => Value::BorderRadius(BorderRadius::zero()),
             Property::BorderBottomLeftRadius => Value::BorderRadius(BorderRadius::zero()),
             Property::BorderBottomRightRadius => Value::BorderRadius(BorderRadius::zero()),
+            Property::Transform => Value::Transform(Transform::None),
+            Property::Visibility => Value::Visibility(Visibility::Visible),
+            Property::ListStyleType => Value::ListStyleType(ListStyleType::Disc),
+            Property::ListStylePosition => Value::ListStylePosition(ListStylePosition::Outside),
+            Property::BorderCollapse => Value::BorderCollapse(BorderCollapse::Separate),
+            Property::BorderSpacing => Value::BorderSpacing(BorderSpacing::zero()),
+            Property::TableLayout => Value::TableLayout(TableLayout::Auto),
+            Property::WhiteSpace => Value::WhiteSpace(WhiteSpace::Normal),
+            Property::OverflowWrap => Value::OverflowWrap(OverflowWrap::Normal),
+            Property::Hyphens => Value::Hyphens(Hyphens::Manual),
+            Property::TextDecorationLine => Value::TextDecorationLine(TextDecorationLine::none()),
+            Property::TextDecorationStyle => Value::TextDecorationStyle(TextDecorationStyle::Solid),
+            Property::TextDecorationColor => Value::Color(Color::CurrentColor),
+            Property::TextAlign => Value::TextAlign(TextAlign::Start),
+            Property::VerticalAlign => Value::VerticalAlign(VerticalAlign::Baseline),
+            Property::LineHeight => Value::LineHeight(LineHeight::Normal),
+            Property::TransitionProperty => Value::TransitionProperty(TransitionProperty::all()),
+            Property::TransitionDuration => Value::TimeList(TimeList::zero()),
+            Property::TransitionTimingFunction => {
+                Value::TimingFunctionList(TimingFunctionList(vec![TimingFunction::ease()]))
+            }
+            Property::TransitionDelay => Value::TimeList(TimeList::zero()),
+            Property::AnimationName => Value::AnimationName(AnimationName::none()),
+            Property::AnimationDuration => Value::TimeList(TimeList::zero()),
+            Property::AnimationTimingFunction => {
+                Value::TimingFunctionList(TimingFunctionList(vec![TimingFunction::ease()]))
+            }
+            Property::AnimationIterationCount => {
+                Value::IterationCountList(IterationCountList::one())
+            }
+            Property::AnimationDirection => {
+                Value::AnimationDirectionList(AnimationDirectionList(vec![
+                    AnimationDirection::Normal,
+                ]))
+            }
+            Property::AnimationPlayState => {
+                Value::AnimationPlayStateList(AnimationPlayStateList(vec![
+                    AnimationPlayState::Running,
+                ]))
+            }
+            Property::AnimationDelay => Value::TimeList(TimeList::zero()),
+            Property::AnimationFillMode => {
+                Value::AnimationFillModeList(AnimationFillModeList(vec![AnimationFillMode::None]))
+            }
+            Property::Content => Value::Content(Content::Normal),
+            Property::Quotes => Value::Quotes(Quotes::Auto),
+            Property::ContentVisibility => Value::ContentVisibility(ContentVisibility::Visible),
+            Property::Contain => Value::Contain(Contain::none()),
+            Property::Cursor => Value::Cursor(Cursor::Auto),
+            Property::FontSize => Value::FontSize(FontSize::medium()),
+            Property::FontFamily => Value::FontFamily(FontFamily::initial()),
         }
     }
 }
 
 impl Property {
+    /// The size the percentages of the property are relative to, if they
+    /// are resolved by the layout
+    pub fn percentage_basis(&self) -> Option<PercentageBasis> {
+        match self {
+            // the vertical margins & paddings are relative to the width too
+            Property::Width
+            | Property::MinWidth
+            | Property::MaxWidth
+            | Property::MarginTop
+            | Property::MarginRight
+            | Property::MarginBottom
+            | Property::MarginLeft
+            | Property::PaddingTop
+            | Property::PaddingRight
+            | Property::PaddingBottom
+            | Property::PaddingLeft
+            | Property::Left
+            | Property::Right => Some(PercentageBasis::ContainingBlockWidth),
+            Property::Height
+            | Property::MinHeight
+            | Property::MaxHeight
+            | Property::Top
+            | Property::Bottom => Some(PercentageBasis::ContainingBlockHeight),
+            _ => None,
+        }
+    }
+
     pub fn parse(property: &str) -> Option<Self> {
         match property {
             "background-color" => Some(Property::BackgroundColor),
+            "background-image" => Some(Property::BackgroundImage),
             "color" => Some(Property::Color),
             "display" => Some(Property::Display),
             "width" => Some(Property::Width),
             "height" => Some(Property::Height),
+            "min-width" => Some(Property::MinWidth),
+            "min-height" => Some(Property::MinHeight),
+            "max-width" => Some(Property::MaxWidth),
+            "max-height" => Some(Property::MaxHeight),
+            "box-sizing" => Some(Property::BoxSizing),
             "margin-top" => Some(Property::MarginTop),
             "margin-right" => Some(Property::MarginRight),
             "margin-bottom" => Some(Property::MarginBottom),
@@ -456,16 +879,76 @@ impl Property {
             "border-top-right-radius" => Some(Property::BorderTopRightRadius),
             "border-bottom-left-radius" => Some(Property::BorderBottomLeftRadius),
             "border-bottom-right-radius" => Some(Property::BorderBottomRightRadius),
+            "transform" => Some(Property::Transform),
+            "visibility" => Some(Property::Visibility),
+            "list-style-type" => Some(Property::ListStyleType),
+            "list-style-position" => Some(Property::ListStylePosition),
+            "border-collapse" => Some(Property::BorderCollapse),
+            "border-spacing" => Some(Property::BorderSpacing),
+            "table-layout" => Some(Property::TableLayout),
+            "white-space" => Some(Property::WhiteSpace),
+            // word-wrap is the legacy name of overflow-wrap
+            "overflow-wrap" | "word-wrap" => Some(Property::OverflowWrap),
+            "hyphens" => Some(Property::Hyphens),
+            "text-decoration-line" => Some(Property::TextDecorationLine),
+            "text-decoration-style" => Some(Property::TextDecorationStyle),
+            "text-decoration-color" => Some(Property::TextDecorationColor),
+            "text-align" => Some(Property::TextAlign),
+            "vertical-align" => Some(Property::VerticalAlign),
+            "line-height" => Some(Property::LineHeight),
+            "transition-property" => Some(Property::TransitionProperty),
+            "transition-duration" => Some(Property::TransitionDuration),
+            "transition-timing-function" => Some(Property::TransitionTimingFunction),
+            "transition-delay" => Some(Property::TransitionDelay),
+            "animation-name" => Some(Property::AnimationName),
+            "animation-duration" => Some(Property::AnimationDuration),
+            "animation-timing-function" => Some(Property::AnimationTimingFunction),
+            "animation-iteration-count" => Some(Property::AnimationIterationCount),
+            "animation-direction" => Some(Property::AnimationDirection),
+            "animation-play-state" => Some(Property::AnimationPlayState),
+            "animation-delay" => Some(Property::AnimationDelay),
+            "animation-fill-mode" => Some(Property::AnimationFillMode),
+            "content" => Some(Property::Content),
+            "quotes" => Some(Property::Quotes),
+            "content-visibility" => Some(Property::ContentVisibility),
+            "contain" => Some(Property::Contain),
+            "cursor" => Some(Property::Cursor),
+            "font-size" => Some(Property::FontSize),
+            "font-family" => Some(Property::FontFamily),
             _ => None,
         }
     }
 }
 
-/// Apply a list of style rules for a node
-pub fn apply_styles(node: &NodeRef, rules: &[ContextualRule]) -> Properties {
+/// Apply the style rules matching a node
+pub fn apply_styles(node: &NodeRef, matched_rules: &[&ContextualRule]) -> Properties {
+    apply_animated_styles(node, matched_rules, &HashMap::new())
+}
+
+/// Apply the style rules matching a node and the values of its running
+/// animations, which are cascaded at the animation origin
+pub fn apply_animated_styles(
+    node: &NodeRef,
+    matched_rules: &[&ContextualRule],
+    animated_values: &HashMap<Property, Value>,
+) -> Properties {
     // https://www.w3.org/TR/css3-cascade/#value-stages
     // Step 1
-    let mut declared_values = collect_declared_values(&node, rules);
+    let mut declared_values = collect_declared_values(&node, matched_rules);
+    if node.is_element() {
+        for (property, value) in animated_values {
+            declared_values
+                .entry(property.clone())
+                .or_default()
+                .push(PropertyDeclaration {
+                    value: value.clone(),
+                    important: false,
+                    origin: CascadeOrigin::Animation,
+                    location: CSSLocation::Inline,
+                    specificity: Specificity::new(0, 0, 0),
+                });
+        }
+    }
 
     // Step 2
     let cascade_values = declared_values
@@ -480,12 +963,9 @@ pub fn apply_styles(node: &NodeRef, rules: &[ContextualRule]) -> Properties {
 pub fn compute(property: &Property, value: &Value, context: &mut ComputeContext) -> ValueRef {
     match value {
         Value::Color(_) => compute_color(value, property, context),
-        _ => {
-            if !context.style_cache.contains(value) {
-                context.style_cache.insert(ValueRef::new(value.clone()));
-            }
-            context.style_cache.get(value).unwrap().clone()
-        }
+        Value::Content(_) => compute_content(value, context),
+        Value::FontSize(_) => compute_font_size(value, context),
+        _ => compute_length(value, context),
     }
 }
 
@@ -512,24 +992,23 @@ fn get_expander_shorthand_property(
         "border-width" => Some(&expand_border_width),
         "border-color" => Some(&expand_border_color),
         "border-radius" => Some(&expand_border_radius),
+        "list-style" => Some(&expand_list_style),
+        "text-decoration" => Some(&expand_text_decoration),
+        "transition" => Some(&expand_transition),
+        "animation" => Some(&expand_animation),
         _ => None,
     }
 }
 
 /// Collect declared values for each property
 /// found in each style rule
-fn collect_declared_values(node: &NodeRef, rules: &[ContextualRule]) -> DeclaredValuesMap {
+fn collect_declared_values(node: &NodeRef, matched_rules: &[&ContextualRule]) -> DeclaredValuesMap {
     let mut result: DeclaredValuesMap = HashMap::new();
 
     if !node.is_element() {
         return result;
     }
 
-    let matched_rules = rules
-        .iter()
-        .filter(|rule| is_match_selectors(node, &rule.inner.selectors))
-        .collect::<Vec<&ContextualRule>>();
-
     let mut insert_declaration =
         |value: Value, property: Property, rule: &ContextualRule, declaration: &Declaration| {
             let declaration = PropertyDeclaration {
@@ -548,34 +1027,8 @@ fn collect_declared_values(node: &NodeRef, rules: &[ContextualRule]) -> Declared
 
     for rule in matched_rules {
         for declaration in &rule.inner.declarations {
-            if let Some(expand) = get_expander_shorthand_property(&declaration.name) {
-                // process short hand property
-                let tokens = declaration
-                    .value
-                    .split(|val| match val {
-                        ComponentValue::PerservedToken(Token::Whitespace) => true,
-                        _ => false,
-                    })
-                    .collect::<Vec<&[ComponentValue]>>();
-
-                if let Some(values) = expand(&tokens) {
-                    for (property, value) in values {
-                        if let Some(v) = value {
-                            insert_declaration(v, property, rule, declaration);
-                        }
-                    }
-                }
-            } else {
-                // process long hand css property
-                let property = Property::parse(&declaration.name);
-                if let Some(property) = property {
-                    let values = &declaration.value;
-                    let value = Value::parse(&property, values);
-
-                    if let Some(value) = value {
-                        insert_declaration(value, property, rule, declaration);
-                    }
-                }
+            for (property, value) in parse_declaration(declaration) {
+                insert_declaration(value, property, rule, declaration);
             }
         }
     }
@@ -583,18 +1036,53 @@ fn collect_declared_values(node: &NodeRef, rules: &[ContextualRule]) -> Declared
     result
 }
 
+/// Parse the value of a declaration into the values of the properties
+/// it sets. A short-hand property sets all of its long-hand properties.
+/// Invalid declarations are ignored.
+pub fn parse_declaration(declaration: &Declaration) -> Vec<(Property, Value)> {
+    if let Some(expand) = get_expander_shorthand_property(&declaration.name) {
+        // process short hand property
+        let tokens = declaration
+            .value
+            .split(|val| match val {
+                ComponentValue::PerservedToken(Token::Whitespace) => true,
+                _ => false,
+            })
+            .collect::<Vec<&[ComponentValue]>>();
+
+        match expand(&tokens) {
+            Some(values) => values
+                .into_iter()
+                .filter_map(|(property, value)| Some((property, value?)))
+                .collect(),
+            None => Vec::new(),
+        }
+    } else {
+        // process long hand css property
+        let property = match Property::parse(&declaration.name) {
+            Some(property) => property,
+            None => return Vec::new(),
+        };
+        match Value::parse(&property, &declaration.value) {
+            Some(value) => vec![(property, value)],
+            None => Vec::new(),
+        }
+    }
+}
+
 /// The implementation for ordering for cascade sort
 ///
 /// These are the steps to compare the order:
-/// 1. Comparing the location of the property declaration (Inline, Embedded, etc.)
-/// 2. If step 1 result in equal ordering compare the cascade origin
+/// 1. Comparing the cascade origin & importance
+/// 2. If step 1 result in equal ordering compare the location of the
+///    property declaration (Inline, Embedded, etc.)
 /// 3. If step 2 result in equal ordering compare the specificity
 impl Ord for PropertyDeclaration {
     fn cmp(&self, other: &Self) -> Ordering {
-        match cmp_location(self, other) {
+        match cmp_cascade_origin(self, other) {
             Ordering::Greater => Ordering::Greater,
             Ordering::Less => Ordering::Less,
-            Ordering::Equal => match cmp_cascade_origin(self, other) {
+            Ordering::Equal => match cmp_location(self, other) {
                 Ordering::Greater => Ordering::Greater,
                 Ordering::Less => Ordering::Less,
                 Ordering::Equal => self.specificity.cmp(&other.specificity),
@@ -610,15 +1098,7 @@ impl PartialOrd for PropertyDeclaration {
 }
 
 fn cmp_location(a: &PropertyDeclaration, b: &PropertyDeclaration) -> Ordering {
-    match (&a.location, &b.location) {
-        (CSSLocation::Inline, CSSLocation::Embedded)
-        | (CSSLocation::Inline, CSSLocation::External)
-        | (CSSLocation::Embedded, CSSLocation::External) => Ordering::Greater,
-        (CSSLocation::Inline, CSSLocation::Inline)
-        | (CSSLocation::Embedded, CSSLocation::Embedded)
-        | (CSSLocation::External, CSSLocation::External) => Ordering::Equal,
-        _ => Ordering::Less,
-    }
+    a.location.rank().cmp(&b.location.rank())
 }
 
 /// Comparing cascade origin and importance
@@ -649,10 +1129,11 @@ fn cmp_cascade_origin(a: &PropertyDeclaration, b: &PropertyDeclaration) -> Order
                 _ => Ordering::Less,
             }
         }
-        // #5 #6 #7 #8
         (false, false) => {
-            // #6 #7 #8
+            // #5 #6 #7 #8
             return match (&a.origin, &b.origin) {
+                (CascadeOrigin::Animation, CascadeOrigin::Animation) => Ordering::Equal,
+                (CascadeOrigin::Animation, _) => Ordering::Greater,
                 (CascadeOrigin::Author, CascadeOrigin::User)
                 | (CascadeOrigin::User, CascadeOrigin::UserAgent)
                 | (CascadeOrigin::Author, CascadeOrigin::UserAgent) => Ordering::Greater,
@@ -669,6 +1150,7 @@ fn cmp_cascade_origin(a: &PropertyDeclaration, b: &PropertyDeclaration) -> Order
 mod tests {
     use super::*;
     use crate::values::color::Color;
+    use atom::Atom;
     use css::parser::structs::ComponentValue;
     use css::tokenizer::token::Token;
 
@@ -704,19 +1186,83 @@ mod tests {
         assert_eq!(win, Some(c.value));
     }
 
+    #[test]
+    fn cascade_origins() {
+        let user_agent = PropertyDeclaration {
+            location: CSSLocation::External,
+            origin: CascadeOrigin::UserAgent,
+            important: false,
+            value: Value::Color(Color::black()),
+            specificity: Specificity::new(1, 0, 0),
+        };
+
+        let user = PropertyDeclaration {
+            location: CSSLocation::External,
+            origin: CascadeOrigin::User,
+            important: false,
+            value: Value::Color(Color::Rgba(
+                255.0.into(),
+                255.0.into(),
+                255.0.into(),
+                255.0.into(),
+            )),
+            specificity: Specificity::new(0, 0, 1),
+        };
+
+        let author = PropertyDeclaration {
+            location: CSSLocation::Embedded,
+            origin: CascadeOrigin::Author,
+            important: false,
+            value: Value::Color(Color::transparent()),
+            specificity: Specificity::new(0, 0, 1),
+        };
+
+        let mut declared = vec![user.clone(), user_agent.clone()];
+        assert_eq!(cascade(&mut declared), Some(user.value.clone()));
+
+        let mut declared = vec![author.clone(), user.clone(), user_agent.clone()];
+        assert_eq!(cascade(&mut declared), Some(author.value.clone()));
+
+        // important user declarations win over the author declarations
+        let important_user = PropertyDeclaration {
+            important: true,
+            ..user
+        };
+        let mut declared = vec![author.clone(), important_user.clone()];
+        assert_eq!(cascade(&mut declared), Some(important_user.value));
+
+        // animations win over the normal declarations only
+        let animation = PropertyDeclaration {
+            location: CSSLocation::Inline,
+            origin: CascadeOrigin::Animation,
+            important: false,
+            value: Value::Color(Color::black()),
+            specificity: Specificity::new(0, 0, 0),
+        };
+        let mut declared = vec![animation.clone(), author.clone()];
+        assert_eq!(cascade(&mut declared), Some(animation.value.clone()));
+
+        let important_author = PropertyDeclaration {
+            important: true,
+            ..author
+        };
+        let mut declared = vec![important_author.clone(), animation];
+        assert_eq!(cascade(&mut declared), Some(important_author.value));
+    }
+
     #[test]
     fn parse_multiple_value_types() {
-        let tokens_auto = vec![ComponentValue::PerservedToken(Token::Ident(
-            "auto".to_string(),
-        ))];
+        let tokens_auto = vec![ComponentValue::PerservedToken(Token::Ident(Atom::from(
+            "auto",
+        )))];
         let value_auto = Value::parse(&Property::Width, &tokens_auto);
 
         let tokens_percentage = vec![ComponentValue::PerservedToken(Token::Percentage(20.5))];
         let value_percentage = Value::parse(&Property::Width, &tokens_percentage);
 
-        let tokens_inherit = vec![ComponentValue::PerservedToken(Token::Ident(
-            "inherit".to_string(),
-        ))];
+        let tokens_inherit = vec![ComponentValue::PerservedToken(Token::Ident(Atom::from(
+            "inherit",
+        )))];
         let value_inherit = Value::parse(&Property::Width, &tokens_inherit);
 
         assert_eq!(value_auto, Some(Value::Auto));
@@ -750,4 +1296,29 @@ mod tests {
         let win = cascade(&mut declared);
         assert_eq!(win, Some(b.value));
     }
+
+    #[test]
+    fn parse_functions() {
+        let parse = |css: &str| {
+            let tokenizer = css::tokenizer::Tokenizer::new(css.chars());
+            let mut parser = css::parser::Parser::<Token>::new(tokenizer.run());
+            parse_function(&parser.parse_a_list_of_component_values()[0])
+        };
+
+        assert_eq!(
+            parse("url(moon.svg)"),
+            Some(ValueFunction::Url("moon.svg".to_string()))
+        );
+        assert_eq!(
+            parse("URL( 'moon.svg' )"),
+            Some(ValueFunction::Url("moon.svg".to_string()))
+        );
+        assert_eq!(
+            parse("attr(title)"),
+            Some(ValueFunction::Attr(Atom::from("title")))
+        );
+        assert_eq!(parse("attr('title')"), None);
+        assert_eq!(parse("calc(1px + 2px)"), None);
+        assert_eq!(parse("title"), None);
+    }
 }
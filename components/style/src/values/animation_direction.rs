@@ -0,0 +1,58 @@
+use super::parse_comma_separated;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Whether an animation plays its keyframes backwards on some iterations
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AnimationDirection {
+    Normal,
+    Reverse,
+    Alternate,
+    AlternateReverse,
+}
+
+impl AnimationDirection {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))] => {
+                match keyword.to_ascii_lowercase().as_str() {
+                    "normal" => Some(AnimationDirection::Normal),
+                    "reverse" => Some(AnimationDirection::Reverse),
+                    "alternate" => Some(AnimationDirection::Alternate),
+                    "alternate-reverse" => Some(AnimationDirection::AlternateReverse),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether an iteration, counting from 0, plays backwards
+    pub fn is_reversed(&self, iteration: u64) -> bool {
+        match self {
+            AnimationDirection::Normal => false,
+            AnimationDirection::Reverse => true,
+            AnimationDirection::Alternate => iteration % 2 == 1,
+            AnimationDirection::AlternateReverse => iteration % 2 == 0,
+        }
+    }
+}
+
+/// A comma separated list of animation directions
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct AnimationDirectionList(pub Vec<AnimationDirection>);
+
+impl AnimationDirectionList {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        parse_comma_separated(values, AnimationDirection::parse).map(Self)
+    }
+
+    /// The direction at an index of the list, repeating the list
+    /// when it is shorter than the list of animations it applies to
+    pub fn get(&self, index: usize) -> AnimationDirection {
+        match self.0.len() {
+            0 => AnimationDirection::Normal,
+            len => self.0[index % len].clone(),
+        }
+    }
+}
@@ -0,0 +1,62 @@
+use super::parse_comma_separated;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Whether the values of an animation apply before it starts & after it ends
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AnimationFillMode {
+    None,
+    Forwards,
+    Backwards,
+    Both,
+}
+
+impl AnimationFillMode {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))] => {
+                match keyword.to_ascii_lowercase().as_str() {
+                    "none" => Some(AnimationFillMode::None),
+                    "forwards" => Some(AnimationFillMode::Forwards),
+                    "backwards" => Some(AnimationFillMode::Backwards),
+                    "both" => Some(AnimationFillMode::Both),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    pub fn fills_backwards(&self) -> bool {
+        match self {
+            AnimationFillMode::Backwards | AnimationFillMode::Both => true,
+            _ => false,
+        }
+    }
+
+    pub fn fills_forwards(&self) -> bool {
+        match self {
+            AnimationFillMode::Forwards | AnimationFillMode::Both => true,
+            _ => false,
+        }
+    }
+}
+
+/// A comma separated list of animation fill modes
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct AnimationFillModeList(pub Vec<AnimationFillMode>);
+
+impl AnimationFillModeList {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        parse_comma_separated(values, AnimationFillMode::parse).map(Self)
+    }
+
+    /// The fill mode at an index of the list, repeating the list
+    /// when it is shorter than the list of animations it applies to
+    pub fn get(&self, index: usize) -> AnimationFillMode {
+        match self.0.len() {
+            0 => AnimationFillMode::None,
+            len => self.0[index % len].clone(),
+        }
+    }
+}
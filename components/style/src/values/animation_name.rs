@@ -0,0 +1,35 @@
+use super::parse_comma_separated;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The names of the `@keyframes` rules animating an element.
+/// A `none` item doesn't run any animation but still takes a place in the list
+/// so the other `animation-*` values are matched correctly.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct AnimationName(pub Vec<Option<String>>);
+
+impl AnimationName {
+    pub fn none() -> Self {
+        Self(vec![None])
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        parse_comma_separated(values, parse_keyframes_name).map(Self)
+    }
+}
+
+/// Parse `none | <custom-ident> | <string>`
+pub fn parse_keyframes_name(values: &[ComponentValue]) -> Option<Option<String>> {
+    match values {
+        [ComponentValue::PerservedToken(Token::Ident(name))] => {
+            match name.to_ascii_lowercase().as_str() {
+                "none" => Some(None),
+                // CSS-wide keywords can't be used in a list
+                "initial" | "inherit" | "unset" | "default" => None,
+                _ => Some(Some(name.to_string())),
+            }
+        }
+        [ComponentValue::PerservedToken(Token::Str(name))] => Some(Some(name.clone())),
+        _ => None,
+    }
+}
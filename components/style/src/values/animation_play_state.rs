@@ -0,0 +1,44 @@
+use super::parse_comma_separated;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Whether an animation is running or paused
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum AnimationPlayState {
+    Running,
+    Paused,
+}
+
+impl AnimationPlayState {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))] => {
+                match keyword.to_ascii_lowercase().as_str() {
+                    "running" => Some(AnimationPlayState::Running),
+                    "paused" => Some(AnimationPlayState::Paused),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
+
+/// A comma separated list of animation play states
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct AnimationPlayStateList(pub Vec<AnimationPlayState>);
+
+impl AnimationPlayStateList {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        parse_comma_separated(values, AnimationPlayState::parse).map(Self)
+    }
+
+    /// The play state at an index of the list, repeating the list
+    /// when it is shorter than the list of animations it applies to
+    pub fn get(&self, index: usize) -> AnimationPlayState {
+        match self.0.len() {
+            0 => AnimationPlayState::Running,
+            len => self.0[index % len].clone(),
+        }
+    }
+}
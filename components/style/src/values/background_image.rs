@@ -0,0 +1,64 @@
+use crate::value_processing::{parse_function, ValueFunction};
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The value of the `background-image` property. Only a single image
+/// loaded from a URL is supported, gradients & layers aren't.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum BackgroundImage {
+    None,
+    Url(String),
+}
+
+impl BackgroundImage {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))]
+                if keyword.eq_ignore_ascii_case("none") =>
+            {
+                Some(BackgroundImage::None)
+            }
+            [value] => match parse_function(value)? {
+                ValueFunction::Url(url) => Some(BackgroundImage::Url(url)),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+
+    pub fn url(&self) -> Option<&str> {
+        match self {
+            BackgroundImage::Url(url) => Some(url),
+            BackgroundImage::None => None,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<BackgroundImage> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        BackgroundImage::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    #[test]
+    fn parse_background_images() {
+        assert_eq!(parse("none"), Some(BackgroundImage::None));
+        assert_eq!(
+            parse("url(file:///moon.svg)"),
+            Some(BackgroundImage::Url("file:///moon.svg".to_string()))
+        );
+        assert_eq!(
+            parse("url(\"data:image/svg+xml,<svg width='1'/>\" )"),
+            Some(BackgroundImage::Url(
+                "data:image/svg+xml,<svg width='1'/>".to_string()
+            ))
+        );
+        assert_eq!(parse("url(a.svg) url(b.svg)"), None);
+    }
+}
@@ -0,0 +1,21 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum BorderCollapse {
+    Separate,
+    Collapse,
+}
+
+impl BorderCollapse {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => match value {
+                v if v.eq_ignore_ascii_case("separate") => Some(BorderCollapse::Separate),
+                v if v.eq_ignore_ascii_case("collapse") => Some(BorderCollapse::Collapse),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
@@ -1,3 +1,4 @@
+use super::length::LengthContext;
 use super::prelude::{Length, LengthPercentage};
 use css::parser::structs::ComponentValue;
 use css::tokenizer::token::Token;
@@ -43,6 +44,10 @@ impl BorderRadius {
         None
     }
 
+    pub fn resolve(&self, context: &LengthContext) -> Self {
+        Self(self.0.resolve(context), self.1.resolve(context))
+    }
+
     pub fn zero() -> Self {
         Self(
             LengthPercentage::Length(Length::zero()),
@@ -0,0 +1,39 @@
+use super::length::{Length, LengthContext};
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The horizontal & vertical spacing between the cells of a table
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct BorderSpacing(pub Length, pub Length);
+
+impl BorderSpacing {
+    pub fn zero() -> Self {
+        BorderSpacing(Length::zero(), Length::zero())
+    }
+
+    pub fn resolve(&self, context: &LengthContext) -> Self {
+        BorderSpacing(self.0.resolve(context), self.1.resolve(context))
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let values = values
+            .split(|value| match value {
+                ComponentValue::PerservedToken(Token::Whitespace) => true,
+                _ => false,
+            })
+            .filter(|value| !value.is_empty())
+            .collect::<Vec<&[ComponentValue]>>();
+
+        match values.as_slice() {
+            [spacing] => {
+                let spacing = Length::parse(spacing)?;
+                Some(BorderSpacing(spacing.clone(), spacing))
+            }
+            [horizontal, vertical] => Some(BorderSpacing(
+                Length::parse(horizontal)?,
+                Length::parse(vertical)?,
+            )),
+            _ => None,
+        }
+    }
+}
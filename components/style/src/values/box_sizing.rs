@@ -0,0 +1,23 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The box whose size is set by the `width` & `height` of an element
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum BoxSizing {
+    ContentBox,
+    /// The sizes include the padding & the border of the box
+    BorderBox,
+}
+
+impl BoxSizing {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => match value {
+                v if v.eq_ignore_ascii_case("content-box") => Some(BoxSizing::ContentBox),
+                v if v.eq_ignore_ascii_case("border-box") => Some(BoxSizing::BorderBox),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,96 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The containment of a box, which makes its subtree independent of the
+/// rest of the page
+/// https://www.w3.org/TR/css-contain-2/#contain-property
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Contain {
+    /// The box is sized as if it had no content
+    pub size: bool,
+    /// The contents of the box are laid out independently of the page
+    pub layout: bool,
+    /// The quotes of the contents don't affect the rest of the page
+    pub style: bool,
+    /// The contents are clipped to the box & painted together
+    pub paint: bool,
+}
+
+impl Contain {
+    pub fn none() -> Self {
+        Self {
+            size: false,
+            layout: false,
+            style: false,
+            paint: false,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::none()
+    }
+
+    /// Whether a change of the contents of the box changes neither its
+    /// size nor the layout of the page around it, so the contents can be
+    /// laid out again on their own
+    pub fn is_layout_root(&self) -> bool {
+        self.size && self.layout
+    }
+
+    /// Parse `none | strict | content | [ size || layout || style || paint ]`
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let keywords = values
+            .iter()
+            .filter_map(|value| match value {
+                ComponentValue::PerservedToken(Token::Whitespace) => None,
+                ComponentValue::PerservedToken(Token::Ident(keyword)) => {
+                    Some(Some(keyword.to_ascii_lowercase()))
+                }
+                _ => Some(None),
+            })
+            .collect::<Option<Vec<String>>>()?;
+
+        if let [keyword] = keywords.as_slice() {
+            match keyword.as_str() {
+                "none" => return Some(Self::none()),
+                "strict" => {
+                    return Some(Self {
+                        size: true,
+                        layout: true,
+                        style: true,
+                        paint: true,
+                    })
+                }
+                "content" => {
+                    return Some(Self {
+                        size: false,
+                        layout: true,
+                        style: true,
+                        paint: true,
+                    })
+                }
+                _ => {}
+            }
+        }
+
+        let mut result = Self::none();
+        for keyword in &keywords {
+            let containment = match keyword.as_str() {
+                "size" => &mut result.size,
+                "layout" => &mut result.layout,
+                "style" => &mut result.style,
+                "paint" => &mut result.paint,
+                _ => return None,
+            };
+            if *containment {
+                return None;
+            }
+            *containment = true;
+        }
+
+        if result.is_none() {
+            return None;
+        }
+        Some(result)
+    }
+}
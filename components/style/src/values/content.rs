@@ -0,0 +1,232 @@
+use super::quotes::Quotes;
+use crate::value_processing::{parse_function, ValueFunction};
+use atom::Atom;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The content generated in the boxes of the `::before` & `::after`
+/// pseudo-elements
+/// https://www.w3.org/TR/css-content-3/#content-property
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Content {
+    /// No box is generated for the pseudo-elements
+    Normal,
+    None,
+    Items(Vec<ContentItem>),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ContentItem {
+    String(String),
+    /// The value of an attribute of the originating element, replaced by
+    /// a string when the content is computed
+    Attr(Atom),
+    /// The open mark of the quotes at the nesting depth, nesting deeper
+    OpenQuote,
+    /// The close mark of the quotes of the enclosing depth
+    CloseQuote,
+    /// Nest deeper without any mark
+    NoOpenQuote,
+    NoCloseQuote,
+}
+
+impl Content {
+    /// Parse `normal | none | [<string> | <quote> | <attr()>]+`
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let values = values
+            .iter()
+            .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+            .collect::<Vec<_>>();
+
+        if let [ComponentValue::PerservedToken(Token::Ident(keyword))] = values.as_slice() {
+            match keyword.to_ascii_lowercase().as_str() {
+                "normal" => return Some(Content::Normal),
+                "none" => return Some(Content::None),
+                _ => {}
+            }
+        }
+
+        let items = values
+            .into_iter()
+            .map(|value| match value {
+                ComponentValue::PerservedToken(Token::Str(string)) => {
+                    Some(ContentItem::String(string.clone()))
+                }
+                ComponentValue::PerservedToken(Token::Ident(keyword)) => {
+                    match keyword.to_ascii_lowercase().as_str() {
+                        "open-quote" => Some(ContentItem::OpenQuote),
+                        "close-quote" => Some(ContentItem::CloseQuote),
+                        "no-open-quote" => Some(ContentItem::NoOpenQuote),
+                        "no-close-quote" => Some(ContentItem::NoCloseQuote),
+                        _ => None,
+                    }
+                }
+                value => match parse_function(value)? {
+                    ValueFunction::Attr(name) => Some(ContentItem::Attr(name)),
+                    _ => None,
+                },
+            })
+            .collect::<Option<Vec<_>>>()?;
+        if items.is_empty() {
+            return None;
+        }
+        Some(Content::Items(items))
+    }
+
+    /// Whether the content has quotes, whose text depends on their
+    /// nesting depth in the document
+    pub fn has_quotes(&self) -> bool {
+        match self {
+            Content::Items(items) => items
+                .iter()
+                .any(|item| !matches!(item, ContentItem::String(_) | ContentItem::Attr(_))),
+            Content::Normal | Content::None => false,
+        }
+    }
+
+    /// Replace the attributes of the content with their values, or with
+    /// nothing when the element doesn't have the attribute
+    pub fn resolve_attributes<F>(&self, attribute: F) -> Self
+    where
+        F: Fn(&Atom) -> Option<String>,
+    {
+        match self {
+            Content::Items(items) => Content::Items(
+                items
+                    .iter()
+                    .map(|item| match item {
+                        ContentItem::Attr(name) => {
+                            ContentItem::String(attribute(name).unwrap_or_default())
+                        }
+                        item => item.clone(),
+                    })
+                    .collect(),
+            ),
+            content => content.clone(),
+        }
+    }
+
+    /// The text generated by the content, if it generates a box. The
+    /// quotes nest from the depth of the quotes before the content, which
+    /// is updated to the depth after it.
+    /// https://www.w3.org/TR/css-content-3/#quote-values
+    pub fn text(&self, quotes: &Quotes, depth: &mut usize) -> Option<String> {
+        let items = match self {
+            Content::Items(items) => items,
+            Content::Normal | Content::None => return None,
+        };
+
+        let mut text = String::new();
+        for item in items {
+            match item {
+                ContentItem::String(string) => text.push_str(string),
+                // the content of the computed values has no attributes
+                ContentItem::Attr(_) => {}
+                ContentItem::OpenQuote => {
+                    if let Some((open, _)) = quotes.marks(*depth) {
+                        text.push_str(open);
+                    }
+                    *depth += 1;
+                }
+                ContentItem::NoOpenQuote => *depth += 1,
+                // a close quote without an open quote closes nothing
+                ContentItem::CloseQuote if *depth > 0 => {
+                    *depth -= 1;
+                    if let Some((_, close)) = quotes.marks(*depth) {
+                        text.push_str(close);
+                    }
+                }
+                ContentItem::NoCloseQuote if *depth > 0 => *depth -= 1,
+                ContentItem::CloseQuote | ContentItem::NoCloseQuote => {}
+            }
+        }
+        Some(text)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<Content> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        Content::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    #[test]
+    fn parse_content() {
+        assert_eq!(parse("normal"), Some(Content::Normal));
+        assert_eq!(parse("none"), Some(Content::None));
+        assert_eq!(
+            parse("\"\\2192\" ' next'"),
+            Some(Content::Items(vec![
+                ContentItem::String("\u{2192}".to_string()),
+                ContentItem::String(" next".to_string()),
+            ]))
+        );
+        assert_eq!(parse("\"a\" normal"), None);
+        assert_eq!(parse("12px"), None);
+        assert_eq!(
+            parse("open-quote 'a'"),
+            Some(Content::Items(vec![
+                ContentItem::OpenQuote,
+                ContentItem::String("a".to_string()),
+            ]))
+        );
+        assert_eq!(
+            parse("'#' attr(Data-Label)"),
+            Some(Content::Items(vec![
+                ContentItem::String("#".to_string()),
+                ContentItem::Attr(Atom::from("data-label")),
+            ]))
+        );
+        assert_eq!(parse("attr(a b)"), None);
+        assert_eq!(parse("url(a.svg)"), None);
+        assert_eq!(
+            parse("no-close-quote"),
+            Some(Content::Items(vec![ContentItem::NoCloseQuote]))
+        );
+
+        let mut depth = 0;
+        assert_eq!(
+            parse("'a' \"b\"").unwrap().text(&Quotes::Auto, &mut depth),
+            Some("ab".to_string())
+        );
+        assert_eq!(parse("none").unwrap().text(&Quotes::Auto, &mut depth), None);
+    }
+
+    #[test]
+    fn nest_quotes() {
+        let quotes = Quotes::Pairs(vec![
+            ("<".to_string(), ">".to_string()),
+            ("[".to_string(), "]".to_string()),
+        ]);
+        let open = parse("open-quote").unwrap();
+        let close = parse("close-quote").unwrap();
+        let mut depth = 0;
+
+        assert_eq!(close.text(&quotes, &mut depth), Some(String::new()));
+        assert_eq!(depth, 0);
+        assert_eq!(open.text(&quotes, &mut depth), Some("<".to_string()));
+        assert_eq!(open.text(&quotes, &mut depth), Some("[".to_string()));
+        assert_eq!(open.text(&quotes, &mut depth), Some("[".to_string()));
+        assert_eq!(depth, 3);
+        assert_eq!(
+            parse("close-quote no-close-quote close-quote")
+                .unwrap()
+                .text(&quotes, &mut depth),
+            Some("]>".to_string())
+        );
+        assert_eq!(depth, 0);
+        assert_eq!(
+            parse("no-open-quote open-quote")
+                .unwrap()
+                .text(&Quotes::None, &mut depth),
+            Some(String::new())
+        );
+        assert_eq!(depth, 2);
+    }
+}
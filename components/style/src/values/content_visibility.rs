@@ -0,0 +1,27 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Whether the contents of an element are laid out & painted
+/// https://www.w3.org/TR/css-contain-2/#content-visibility
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ContentVisibility {
+    Visible,
+    /// The contents are skipped while they aren't relevant to the user,
+    /// e.g. below the viewport of a screenshot
+    Auto,
+    Hidden,
+}
+
+impl ContentVisibility {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => match value {
+                v if v.eq_ignore_ascii_case("visible") => Some(ContentVisibility::Visible),
+                v if v.eq_ignore_ascii_case("auto") => Some(ContentVisibility::Auto),
+                v if v.eq_ignore_ascii_case("hidden") => Some(ContentVisibility::Hidden),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,138 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The cursor shown while the mouse is over a box
+/// https://www.w3.org/TR/css-ui-3/#cursor
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
+pub enum Cursor {
+    /// The text cursor over text, the default cursor elsewhere
+    Auto,
+    Default,
+    None,
+    ContextMenu,
+    Help,
+    Pointer,
+    Progress,
+    Wait,
+    Cell,
+    Crosshair,
+    Text,
+    VerticalText,
+    Alias,
+    Copy,
+    Move,
+    NoDrop,
+    NotAllowed,
+    Grab,
+    Grabbing,
+    AllScroll,
+    ColResize,
+    RowResize,
+    NResize,
+    EResize,
+    SResize,
+    WResize,
+    NeResize,
+    NwResize,
+    SeResize,
+    SwResize,
+    EwResize,
+    NsResize,
+    NeswResize,
+    NwseResize,
+    ZoomIn,
+    ZoomOut,
+}
+
+const KEYWORDS: &[(&str, Cursor)] = &[
+    ("auto", Cursor::Auto),
+    ("default", Cursor::Default),
+    ("none", Cursor::None),
+    ("context-menu", Cursor::ContextMenu),
+    ("help", Cursor::Help),
+    ("pointer", Cursor::Pointer),
+    ("progress", Cursor::Progress),
+    ("wait", Cursor::Wait),
+    ("cell", Cursor::Cell),
+    ("crosshair", Cursor::Crosshair),
+    ("text", Cursor::Text),
+    ("vertical-text", Cursor::VerticalText),
+    ("alias", Cursor::Alias),
+    ("copy", Cursor::Copy),
+    ("move", Cursor::Move),
+    ("no-drop", Cursor::NoDrop),
+    ("not-allowed", Cursor::NotAllowed),
+    ("grab", Cursor::Grab),
+    ("grabbing", Cursor::Grabbing),
+    ("all-scroll", Cursor::AllScroll),
+    ("col-resize", Cursor::ColResize),
+    ("row-resize", Cursor::RowResize),
+    ("n-resize", Cursor::NResize),
+    ("e-resize", Cursor::EResize),
+    ("s-resize", Cursor::SResize),
+    ("w-resize", Cursor::WResize),
+    ("ne-resize", Cursor::NeResize),
+    ("nw-resize", Cursor::NwResize),
+    ("se-resize", Cursor::SeResize),
+    ("sw-resize", Cursor::SwResize),
+    ("ew-resize", Cursor::EwResize),
+    ("ns-resize", Cursor::NsResize),
+    ("nesw-resize", Cursor::NeswResize),
+    ("nwse-resize", Cursor::NwseResize),
+    ("zoom-in", Cursor::ZoomIn),
+    ("zoom-out", Cursor::ZoomOut),
+];
+
+impl Cursor {
+    /// Parse `[<url> [<x> <y>]?,]* <keyword>`. The cursor images aren't
+    /// supported, so the keyword at the end of the list is used.
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let items = super::parse_comma_separated(values, |item| match item {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))] => KEYWORDS
+                .iter()
+                .find(|(name, _)| keyword.eq_ignore_ascii_case(name))
+                .map(|(_, cursor)| Some(*cursor)),
+            [image, ..] if is_url(image) => Some(None),
+            _ => None,
+        })?;
+        let (last, images) = items.split_last()?;
+        if images.iter().any(Option::is_some) {
+            return None;
+        }
+        *last
+    }
+}
+
+fn is_url(value: &ComponentValue) -> bool {
+    match value {
+        ComponentValue::PerservedToken(Token::Url(_)) => true,
+        ComponentValue::Function(function) => function.name.eq_ignore_ascii_case("url"),
+        _ => false,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<Cursor> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        Cursor::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    #[test]
+    fn parse_cursors() {
+        assert_eq!(parse("pointer"), Some(Cursor::Pointer));
+        assert_eq!(parse("NWSE-resize"), Some(Cursor::NwseResize));
+        assert_eq!(
+            parse("url(hand.png) 4 4, url(hand.svg), grab"),
+            Some(Cursor::Grab)
+        );
+        assert_eq!(parse("url(hand.png)"), None);
+        assert_eq!(parse("pointer, text"), None);
+        assert_eq!(parse("hand"), None);
+    }
+}
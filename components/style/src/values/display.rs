@@ -1,20 +1,26 @@
 use css::parser::structs::ComponentValue;
 use css::tokenizer::token::Token;
 
+/// The display type of a box, made of the type of the box in the flow of
+/// its parent (outside) & the layout of its children (inside)
+/// https://www.w3.org/TR/css-display-3/#the-display-properties
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum Display {
     Full(OuterDisplayType, InnerDisplayType),
+    /// A box generating a `::marker` box in addition to its principal box
+    ListItem(OuterDisplayType, InnerDisplayType),
+    Internal(InternalDisplayType),
     Box(DisplayBox),
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum OuterDisplayType {
     Block,
     Inline,
     RunIn,
 }
 
-#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+#[derive(Debug, Clone, Copy, Eq, PartialEq, Hash)]
 pub enum InnerDisplayType {
     Flow,
     FlowRoot,
@@ -23,36 +29,113 @@ pub enum InnerDisplayType {
     Grid,
 }
 
+/// Display types only meaningful inside of a parent box with a
+/// particular display type, e.g. a table
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum InternalDisplayType {
+    TableRowGroup,
+    TableHeaderGroup,
+    TableFooterGroup,
+    TableRow,
+    TableCell,
+    TableColumnGroup,
+    TableColumn,
+    TableCaption,
+}
+
 #[derive(Debug, Clone, Eq, PartialEq, Hash)]
 pub enum DisplayBox {
     Contents,
     None,
 }
 
-macro_rules! match_ident {
-    ($value:expr, {$($matcher:expr => $result:expr),*}) => {
-        match $value {
-            ComponentValue::PerservedToken(Token::Ident(v)) => match v {
-                $(
-                    v if v.eq_ignore_ascii_case($matcher) => Some($result)
-                ),*,
-                _ => None
+impl Display {
+    /// Parse a display keyword, e.g. `inline-block`, or the outside &
+    /// inside types in any order, e.g. `inline flow-root` or
+    /// `list-item block flow`
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let keywords = values
+            .iter()
+            .filter_map(|value| match value {
+                ComponentValue::PerservedToken(Token::Whitespace) => None,
+                ComponentValue::PerservedToken(Token::Ident(keyword)) => {
+                    Some(Some(keyword.to_ascii_lowercase()))
+                }
+                _ => Some(None),
+            })
+            .collect::<Option<Vec<String>>>()?;
+
+        if let [keyword] = keywords.as_slice() {
+            let display = match keyword.as_str() {
+                "none" => Some(Display::Box(DisplayBox::None)),
+                "contents" => Some(Display::Box(DisplayBox::Contents)),
+                "inline-block" => Some(Display::Full(
+                    OuterDisplayType::Inline,
+                    InnerDisplayType::FlowRoot,
+                )),
+                "inline-table" => Some(Display::Full(
+                    OuterDisplayType::Inline,
+                    InnerDisplayType::Table,
+                )),
+                "inline-flex" => Some(Display::Full(
+                    OuterDisplayType::Inline,
+                    InnerDisplayType::Flex,
+                )),
+                "inline-grid" => Some(Display::Full(
+                    OuterDisplayType::Inline,
+                    InnerDisplayType::Grid,
+                )),
+                "table-row-group" => Some(Display::Internal(InternalDisplayType::TableRowGroup)),
+                "table-header-group" => {
+                    Some(Display::Internal(InternalDisplayType::TableHeaderGroup))
+                }
+                "table-footer-group" => {
+                    Some(Display::Internal(InternalDisplayType::TableFooterGroup))
+                }
+                "table-row" => Some(Display::Internal(InternalDisplayType::TableRow)),
+                "table-cell" => Some(Display::Internal(InternalDisplayType::TableCell)),
+                "table-column-group" => {
+                    Some(Display::Internal(InternalDisplayType::TableColumnGroup))
+                }
+                "table-column" => Some(Display::Internal(InternalDisplayType::TableColumn)),
+                "table-caption" => Some(Display::Internal(InternalDisplayType::TableCaption)),
+                _ => None,
+            };
+            if display.is_some() {
+                return display;
             }
-            _ => None
         }
-    };
-}
 
-impl Display {
-    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
-        match values.len() {
-            1 => match_ident!(&values[0], {
-                "none" => Display::Box(DisplayBox::None),
-                "contents" => Display::Box(DisplayBox::Contents),
-                "block" => Self::new_block(),
-                "inline" => Self::new_inline(),
-                "inline-block" => Display::Full(OuterDisplayType::Inline, InnerDisplayType::FlowRoot)
-            }),
+        if keywords.is_empty() {
+            return None;
+        }
+        let mut outside = None;
+        let mut inside = None;
+        let mut list_item = false;
+        for keyword in &keywords {
+            match keyword.as_str() {
+                "block" if outside.is_none() => outside = Some(OuterDisplayType::Block),
+                "inline" if outside.is_none() => outside = Some(OuterDisplayType::Inline),
+                "run-in" if outside.is_none() => outside = Some(OuterDisplayType::RunIn),
+                "flow" if inside.is_none() => inside = Some(InnerDisplayType::Flow),
+                "flow-root" if inside.is_none() => inside = Some(InnerDisplayType::FlowRoot),
+                "table" if inside.is_none() => inside = Some(InnerDisplayType::Table),
+                "flex" if inside.is_none() => inside = Some(InnerDisplayType::Flex),
+                "grid" if inside.is_none() => inside = Some(InnerDisplayType::Grid),
+                "list-item" if !list_item => list_item = true,
+                _ => return None,
+            }
+        }
+        // a box is block-level & lays out its children in flow by default
+        let outside = outside.unwrap_or(OuterDisplayType::Block);
+        let inside = inside.unwrap_or(InnerDisplayType::Flow);
+        if !list_item {
+            return Some(Display::Full(outside, inside));
+        }
+        match inside {
+            InnerDisplayType::Flow | InnerDisplayType::FlowRoot => {
+                Some(Display::ListItem(outside, inside))
+            }
             _ => None,
         }
     }
@@ -64,4 +147,118 @@ impl Display {
     pub fn new_inline() -> Self {
         Display::Full(OuterDisplayType::Inline, InnerDisplayType::Flow)
     }
+
+    /// The type of the box in the flow of its parent, if it generates a
+    /// box that isn't internal to a table
+    pub fn outside(&self) -> Option<OuterDisplayType> {
+        match self {
+            Display::Full(outside, _) | Display::ListItem(outside, _) => Some(*outside),
+            _ => None,
+        }
+    }
+
+    /// The layout of the children of the box, if it generates a box that
+    /// isn't internal to a table
+    pub fn inside(&self) -> Option<InnerDisplayType> {
+        match self {
+            Display::Full(_, inside) | Display::ListItem(_, inside) => Some(*inside),
+            _ => None,
+        }
+    }
+
+    pub fn is_list_item(&self) -> bool {
+        matches!(self, Display::ListItem(_, _))
+    }
+
+    /// Whether the box breaks the lines of its parent. Run-in boxes are
+    /// laid out as blocks.
+    pub fn is_block_level(&self) -> bool {
+        match self.outside() {
+            Some(OuterDisplayType::Block) | Some(OuterDisplayType::RunIn) => true,
+            Some(OuterDisplayType::Inline) => false,
+            None => matches!(self, Display::Internal(_)),
+        }
+    }
+
+    /// Whether the element generates no box, its children being laid out
+    /// in place of the element
+    pub fn is_contents(&self) -> bool {
+        *self == Display::Box(DisplayBox::Contents)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<Display> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        Display::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    #[test]
+    fn parse_single_keywords() {
+        assert_eq!(parse("block"), Some(Display::new_block()));
+        assert_eq!(parse("INLINE"), Some(Display::new_inline()));
+        assert_eq!(
+            parse("flow-root"),
+            Some(Display::Full(
+                OuterDisplayType::Block,
+                InnerDisplayType::FlowRoot
+            ))
+        );
+        assert_eq!(
+            parse("inline-block"),
+            Some(Display::Full(
+                OuterDisplayType::Inline,
+                InnerDisplayType::FlowRoot
+            ))
+        );
+        assert_eq!(
+            parse("list-item"),
+            Some(Display::ListItem(
+                OuterDisplayType::Block,
+                InnerDisplayType::Flow
+            ))
+        );
+        assert_eq!(parse("contents"), Some(Display::Box(DisplayBox::Contents)));
+        assert_eq!(
+            parse("table-cell"),
+            Some(Display::Internal(InternalDisplayType::TableCell))
+        );
+    }
+
+    #[test]
+    fn parse_multiple_keywords() {
+        assert_eq!(
+            parse("inline flow-root"),
+            Some(Display::Full(
+                OuterDisplayType::Inline,
+                InnerDisplayType::FlowRoot
+            ))
+        );
+        assert_eq!(parse("flow block"), Some(Display::new_block()));
+        assert_eq!(
+            parse("inline list-item"),
+            Some(Display::ListItem(
+                OuterDisplayType::Inline,
+                InnerDisplayType::Flow
+            ))
+        );
+        assert_eq!(
+            parse("list-item flow-root block"),
+            Some(Display::ListItem(
+                OuterDisplayType::Block,
+                InnerDisplayType::FlowRoot
+            ))
+        );
+
+        assert!(parse("block inline").is_none());
+        assert!(parse("list-item table").is_none());
+        assert!(parse("inline none").is_none());
+        assert!(parse("block 1px").is_none());
+    }
 }
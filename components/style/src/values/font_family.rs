@@ -0,0 +1,123 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The generic font families, the last resort of the families of a text
+/// https://www.w3.org/TR/css-fonts-4/#generic-font-families
+const GENERIC_FAMILIES: &[&str] = &[
+    "serif",
+    "sans-serif",
+    "cursive",
+    "fantasy",
+    "monospace",
+    "system-ui",
+    "emoji",
+    "math",
+    "ui-serif",
+    "ui-sans-serif",
+    "ui-monospace",
+    "ui-rounded",
+];
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum FamilyName {
+    /// The name of a font family, e.g. `Noto Sans CJK JP`
+    Named(String),
+    /// A generic family, e.g. `sans-serif`
+    Generic(String),
+}
+
+/// The font families of the text of an element, in the order they're
+/// to be looked up for the glyph of each character. The text is measured
+/// with the same metrics whatever its families until faces are loaded.
+/// https://www.w3.org/TR/css-fonts-4/#font-family-prop
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct FontFamily(pub Vec<FamilyName>);
+
+impl FontFamily {
+    pub fn initial() -> Self {
+        FontFamily(vec![FamilyName::Generic("sans-serif".to_string())])
+    }
+
+    /// Parse a comma separated list of family names, each being a string
+    /// or a sequence of identifiers
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let mut families = Vec::new();
+        for family in values.split(|value| *value == ComponentValue::PerservedToken(Token::Comma)) {
+            let family = family
+                .iter()
+                .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+                .collect::<Vec<_>>();
+            families.push(parse_family_name(&family)?);
+        }
+        Some(FontFamily(families))
+    }
+}
+
+fn parse_family_name(values: &[&ComponentValue]) -> Option<FamilyName> {
+    match values {
+        [ComponentValue::PerservedToken(Token::Str(name))] => Some(FamilyName::Named(name.clone())),
+        [ComponentValue::PerservedToken(Token::Ident(name))] => {
+            let name = name.to_string();
+            let keyword = name.to_ascii_lowercase();
+            if GENERIC_FAMILIES.contains(&keyword.as_str()) {
+                return Some(FamilyName::Generic(keyword));
+            }
+            match keyword.as_str() {
+                "inherit" | "initial" | "unset" | "default" => None,
+                _ => Some(FamilyName::Named(name)),
+            }
+        }
+        [] => None,
+        _ => {
+            // the identifiers are joined by single spaces
+            let names = values
+                .iter()
+                .map(|value| match value {
+                    ComponentValue::PerservedToken(Token::Ident(name)) => Some(name.to_string()),
+                    _ => None,
+                })
+                .collect::<Option<Vec<_>>>()?;
+            Some(FamilyName::Named(names.join(" ")))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<FontFamily> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        FontFamily::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    fn named(name: &str) -> FamilyName {
+        FamilyName::Named(name.to_string())
+    }
+
+    #[test]
+    fn parse_font_families() {
+        assert_eq!(
+            parse("\"Helvetica Neue\", Noto  Sans CJK JP, Sans-Serif"),
+            Some(FontFamily(vec![
+                named("Helvetica Neue"),
+                named("Noto Sans CJK JP"),
+                FamilyName::Generic("sans-serif".to_string()),
+            ]))
+        );
+        // a quoted generic family is the name of a family
+        assert_eq!(
+            parse("'serif', emoji"),
+            Some(FontFamily(vec![
+                named("serif"),
+                FamilyName::Generic("emoji".to_string())
+            ]))
+        );
+        assert_eq!(parse("Arial,"), None);
+        assert_eq!(parse("Arial 12"), None);
+        assert_eq!(parse("default"), None);
+    }
+}
@@ -0,0 +1,149 @@
+use super::length::{Length, LengthContext, LengthUnit, DEFAULT_FONT_SIZE};
+use super::number::Number;
+use super::percentage::Percentage;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The absolute size keywords, as their ratio to `medium`
+/// https://www.w3.org/TR/css-fonts-4/#absolute-size-mapping
+const ABSOLUTE_SIZES: &[(&str, f32)] = &[
+    ("xx-small", 3. / 5.),
+    ("x-small", 3. / 4.),
+    ("small", 8. / 9.),
+    ("medium", 1.),
+    ("large", 6. / 5.),
+    ("x-large", 3. / 2.),
+    ("xx-large", 2.),
+    ("xxx-large", 3.),
+];
+
+/// The ratio between the font sizes `larger` & `smaller` step through
+const RELATIVE_SIZE_RATIO: f32 = 1.2;
+
+/// The size of the font of the text of an element
+/// https://www.w3.org/TR/css-fonts-4/#font-size-prop
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum FontSize {
+    /// An absolute size keyword, as its ratio to `medium`
+    Absolute(Number),
+    /// `larger` or `smaller`, as the ratio to the font size of the parent
+    Relative(Number),
+    Length(Length),
+    /// Relative to the font size of the parent
+    Percentage(Percentage),
+    /// The computed font size in px, with the text zoom applied. It's
+    /// inherited as it is, without being zoomed again.
+    Computed(Number),
+}
+
+impl FontSize {
+    pub fn medium() -> Self {
+        FontSize::Absolute(1.0.into())
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))] => {
+                if keyword.eq_ignore_ascii_case("larger") {
+                    return Some(FontSize::Relative(RELATIVE_SIZE_RATIO.into()));
+                }
+                if keyword.eq_ignore_ascii_case("smaller") {
+                    return Some(FontSize::Relative((1. / RELATIVE_SIZE_RATIO).into()));
+                }
+                ABSOLUTE_SIZES
+                    .iter()
+                    .find(|(name, _)| keyword.eq_ignore_ascii_case(name))
+                    .map(|(_, ratio)| FontSize::Absolute((*ratio).into()))
+            }
+            [ComponentValue::PerservedToken(Token::Percentage(value))] if *value >= 0. => {
+                Some(FontSize::Percentage(Percentage((*value).into())))
+            }
+            [ComponentValue::PerservedToken(Token::Dimension { .. })]
+            | [ComponentValue::PerservedToken(Token::Number { .. })] => {
+                let length = Length::parse(values)?;
+                if *length.value < 0. {
+                    return None;
+                }
+                Some(FontSize::Length(length))
+            }
+            _ => None,
+        }
+    }
+
+    /// The computed font size, from the font size of the parent. The
+    /// sizes that don't depend on the parent are scaled by the text zoom.
+    pub fn compute(&self, parent_size: Option<f32>, context: &LengthContext) -> Self {
+        let medium = DEFAULT_FONT_SIZE * context.text_zoom;
+        let parent_size = parent_size.unwrap_or(medium);
+        let px = match self {
+            FontSize::Absolute(ratio) => **ratio * medium,
+            FontSize::Relative(ratio) => **ratio * parent_size,
+            FontSize::Percentage(percentage) => percentage.to_px(parent_size),
+            FontSize::Length(length) => match length.unit {
+                LengthUnit::Em => *length.value * parent_size,
+                // without font metrics, the x-height is half of the font size
+                LengthUnit::Ex => *length.value * parent_size / 2.,
+                LengthUnit::Rem => *length.value * context.root_font_size,
+                _ => match length.absolute_px() {
+                    Some(px) => px * context.text_zoom,
+                    None => length.resolve(context).to_px(),
+                },
+            },
+            FontSize::Computed(_) => return self.clone(),
+        };
+        FontSize::Computed(px.into())
+    }
+
+    /// The size of a computed font size in px
+    pub fn to_px(&self) -> f32 {
+        match self {
+            FontSize::Computed(px) => **px,
+            _ => DEFAULT_FONT_SIZE,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<FontSize> {
+        // a dimension is only tokenized once followed by another character
+        let css = format!("{} ", css);
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        let mut values = parser.parse_a_list_of_component_values();
+        values.pop();
+        FontSize::parse(&values)
+    }
+
+    fn compute(css: &str, parent_size: Option<f32>, text_zoom: f32) -> f32 {
+        let context = LengthContext::new(800., 600.).with_text_zoom(text_zoom);
+        parse(css).unwrap().compute(parent_size, &context).to_px()
+    }
+
+    #[test]
+    fn compute_font_sizes() {
+        assert_eq!(compute("medium", Some(30.), 1.), 16.);
+        assert_eq!(compute("x-large", None, 1.), 24.);
+        assert_eq!(compute("larger", Some(10.), 1.), 12.);
+        assert_eq!(compute("150%", Some(10.), 1.), 15.);
+        assert_eq!(compute("2em", Some(10.), 1.), 20.);
+        assert_eq!(compute("12pt", None, 1.), 16.);
+        assert_eq!(compute("10vw", None, 1.), 80.);
+        assert_eq!(parse("big"), None);
+        assert_eq!(parse("-2px"), None);
+    }
+
+    #[test]
+    fn zoom_text() {
+        assert_eq!(compute("medium", None, 1.5), 24.);
+        assert_eq!(compute("20px", None, 1.5), 30.);
+        assert_eq!(compute("2rem", None, 1.5), 48.);
+        // the size of the parent is zoomed already
+        assert_eq!(compute("2em", Some(30.), 1.5), 60.);
+        assert_eq!(compute("10vw", None, 1.5), 80.);
+    }
+}
@@ -0,0 +1,31 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Where the words can be hyphenated when they're wrapped
+/// https://www.w3.org/TR/css-text-3/#hyphens-property
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Hyphens {
+    /// Words are never hyphenated, even at soft hyphens
+    None,
+    /// Words are only hyphenated at soft hyphens
+    Manual,
+    /// Words are hyphenated at soft hyphens, or at the hyphenation points
+    /// of the language of the text when they have none
+    Auto,
+}
+
+impl Hyphens {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => {
+                match value.to_ascii_lowercase().as_str() {
+                    "none" => Some(Hyphens::None),
+                    "manual" => Some(Hyphens::Manual),
+                    "auto" => Some(Hyphens::Auto),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
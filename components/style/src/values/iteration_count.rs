@@ -0,0 +1,57 @@
+use super::number::Number;
+use super::parse_comma_separated;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The number of times an animation plays
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum IterationCount {
+    Number(Number),
+    Infinite,
+}
+
+impl IterationCount {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))]
+                if keyword.eq_ignore_ascii_case("infinite") =>
+            {
+                Some(IterationCount::Infinite)
+            }
+            [ComponentValue::PerservedToken(Token::Number { value, .. })] if *value >= 0. => {
+                Some(IterationCount::Number((*value).into()))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn to_f32(&self) -> f32 {
+        match self {
+            IterationCount::Number(count) => **count,
+            IterationCount::Infinite => f32::INFINITY,
+        }
+    }
+}
+
+/// A comma separated list of iteration counts
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct IterationCountList(pub Vec<IterationCount>);
+
+impl IterationCountList {
+    pub fn one() -> Self {
+        Self(vec![IterationCount::Number(1.0.into())])
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        parse_comma_separated(values, IterationCount::parse).map(Self)
+    }
+
+    /// The count at an index of the list, repeating the list
+    /// when it is shorter than the list of animations it applies to
+    pub fn get(&self, index: usize) -> IterationCount {
+        match self.0.len() {
+            0 => IterationCount::Number(1.0.into()),
+            len => self.0[index % len].clone(),
+        }
+    }
+}
@@ -20,6 +20,57 @@ pub enum LengthUnit {
     Pt,
     Pc,
     Px,
+    /// Relative to the font size of the root element
+    Rem,
+    /// Relative to 1% of the width of the viewport
+    Vw,
+    /// Relative to 1% of the height of the viewport
+    Vh,
+    /// Relative to 1% of the smaller dimension of the viewport
+    Vmin,
+    /// Relative to 1% of the larger dimension of the viewport
+    Vmax,
+}
+
+/// The font size lengths are relative to when the element has no font size
+pub const DEFAULT_FONT_SIZE: f32 = 16.;
+
+/// The sizes the root font & viewport relative lengths are resolved
+/// against when their values are computed
+/// https://www.w3.org/TR/css-values-4/#relative-lengths
+#[derive(Debug, Clone, PartialEq)]
+pub struct LengthContext {
+    pub root_font_size: f32,
+    pub viewport_width: f32,
+    pub viewport_height: f32,
+    /// The factor the font sizes are scaled by, e.g. when the user zooms
+    /// the text of the page only
+    pub text_zoom: f32,
+}
+
+impl LengthContext {
+    pub fn new(viewport_width: f32, viewport_height: f32) -> Self {
+        Self {
+            root_font_size: DEFAULT_FONT_SIZE,
+            viewport_width,
+            viewport_height,
+            text_zoom: 1.,
+        }
+    }
+
+    pub fn with_text_zoom(self, text_zoom: f32) -> Self {
+        Self {
+            root_font_size: DEFAULT_FONT_SIZE * text_zoom,
+            text_zoom,
+            ..self
+        }
+    }
+}
+
+impl Default for LengthContext {
+    fn default() -> Self {
+        Self::new(0., 0.)
+    }
 }
 
 impl LengthUnit {
@@ -33,6 +84,11 @@ impl LengthUnit {
             "pt" => Some(LengthUnit::Pt),
             "pc" => Some(LengthUnit::Pc),
             "px" => Some(LengthUnit::Px),
+            "rem" => Some(LengthUnit::Rem),
+            "vw" => Some(LengthUnit::Vw),
+            "vh" => Some(LengthUnit::Vh),
+            "vmin" => Some(LengthUnit::Vmin),
+            "vmax" => Some(LengthUnit::Vmax),
             _ => None,
         }
     }
@@ -63,6 +119,36 @@ impl Length {
             _ => 0.0,
         }
     }
+
+    /// The length in pixels if its unit is absolute, with 96 pixels per inch
+    /// https://www.w3.org/TR/css-values-4/#absolute-lengths
+    pub fn absolute_px(&self) -> Option<f32> {
+        let pixels_per_unit = match self.unit {
+            LengthUnit::Px => 1.,
+            LengthUnit::In => 96.,
+            LengthUnit::Cm => 96. / 2.54,
+            LengthUnit::Mm => 96. / 25.4,
+            LengthUnit::Pt => 96. / 72.,
+            LengthUnit::Pc => 96. / 6.,
+            _ => return None,
+        };
+        Some(*self.value * pixels_per_unit)
+    }
+
+    /// The computed length, the root font & viewport relative lengths
+    /// are absolute once computed
+    pub fn resolve(&self, context: &LengthContext) -> Self {
+        let viewport = |size: f32| size / 100.;
+        let px = match self.unit {
+            LengthUnit::Rem => context.root_font_size,
+            LengthUnit::Vw => viewport(context.viewport_width),
+            LengthUnit::Vh => viewport(context.viewport_height),
+            LengthUnit::Vmin => viewport(context.viewport_width.min(context.viewport_height)),
+            LengthUnit::Vmax => viewport(context.viewport_width.max(context.viewport_height)),
+            _ => return self.clone(),
+        };
+        Self::new_px(*self.value * px)
+    }
 }
 
 impl Length {
@@ -87,3 +173,20 @@ impl Length {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn resolve_relative_lengths() {
+        let context = LengthContext::new(800., 600.);
+        let resolve = |value: f32, unit: LengthUnit| Length::new(value, unit).resolve(&context);
+        assert_eq!(resolve(2., LengthUnit::Rem), Length::new_px(32.));
+        assert_eq!(resolve(10., LengthUnit::Vw), Length::new_px(80.));
+        assert_eq!(resolve(10., LengthUnit::Vh), Length::new_px(60.));
+        assert_eq!(resolve(50., LengthUnit::Vmin), Length::new_px(300.));
+        assert_eq!(resolve(50., LengthUnit::Vmax), Length::new_px(400.));
+        assert_eq!(resolve(2., LengthUnit::Em), Length::new(2., LengthUnit::Em));
+    }
+}
@@ -1,6 +1,6 @@
 use css::parser::structs::ComponentValue;
 
-use super::length::Length;
+use super::length::{Length, LengthContext};
 use super::percentage::Percentage;
 
 #[derive(Debug, Clone, PartialEq, Hash, Eq)]
@@ -24,6 +24,13 @@ impl LengthPercentage {
         }
     }
 
+    pub fn resolve(&self, context: &LengthContext) -> Self {
+        match self {
+            LengthPercentage::Length(l) => LengthPercentage::Length(l.resolve(context)),
+            LengthPercentage::Percentage(_) => self.clone(),
+        }
+    }
+
     pub fn parse(values: &[ComponentValue]) -> Option<Self> {
         match Length::parse(values) {
             Some(l) => Some(Self::Length(l)),
@@ -34,3 +41,28 @@ impl LengthPercentage {
         }
     }
 }
+
+/// A length, a percentage or `auto`, as computed for the sizes,
+/// margins & offsets of a box
+#[derive(Debug, Clone, PartialEq, Hash, Eq)]
+pub enum LengthPercentageAuto {
+    LengthPercentage(LengthPercentage),
+    Auto,
+}
+
+impl LengthPercentageAuto {
+    pub fn is_auto(&self) -> bool {
+        match self {
+            LengthPercentageAuto::Auto => true,
+            _ => false,
+        }
+    }
+
+    /// The length in px, `auto` being 0
+    pub fn to_px(&self, containing: f32) -> f32 {
+        match self {
+            LengthPercentageAuto::LengthPercentage(value) => value.to_px(containing),
+            LengthPercentageAuto::Auto => 0.,
+        }
+    }
+}
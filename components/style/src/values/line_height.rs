@@ -0,0 +1,60 @@
+use super::length::{Length, LengthContext};
+use super::number::Number;
+use super::percentage::Percentage;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum LineHeight {
+    /// A line height computed from the metrics of the font
+    Normal,
+    /// A multiple of the font size. Unlike lengths & percentages, the
+    /// number itself is inherited rather than the computed height.
+    Number(Number),
+    Length(Length),
+    Percentage(Percentage),
+}
+
+impl LineHeight {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))]
+                if value.eq_ignore_ascii_case("normal") =>
+            {
+                Some(LineHeight::Normal)
+            }
+            [ComponentValue::PerservedToken(Token::Number { value, .. })] if *value >= 0. => {
+                Some(LineHeight::Number((*value).into()))
+            }
+            [ComponentValue::PerservedToken(Token::Percentage(value))] if *value >= 0. => {
+                Some(LineHeight::Percentage(Percentage((*value).into())))
+            }
+            [ComponentValue::PerservedToken(Token::Dimension { .. })] => {
+                let length = Length::parse(values)?;
+                if *length.value < 0. {
+                    return None;
+                }
+                Some(LineHeight::Length(length))
+            }
+            _ => None,
+        }
+    }
+
+    pub fn resolve(&self, context: &LengthContext) -> Self {
+        match self {
+            LineHeight::Length(length) => LineHeight::Length(length.resolve(context)),
+            _ => self.clone(),
+        }
+    }
+
+    /// The used line height for a font size, `normal` is resolved
+    /// by the caller from the font metrics
+    pub fn to_px(&self, font_size: f32) -> Option<f32> {
+        match self {
+            LineHeight::Normal => None,
+            LineHeight::Number(number) => Some(**number * font_size),
+            LineHeight::Length(length) => Some(length.to_px()),
+            LineHeight::Percentage(percentage) => Some(percentage.to_px(font_size)),
+        }
+    }
+}
@@ -0,0 +1,39 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum ListStyleType {
+    Disc,
+    Circle,
+    Square,
+    Decimal,
+    DecimalLeadingZero,
+    LowerRoman,
+    UpperRoman,
+    LowerAlpha,
+    UpperAlpha,
+    None,
+}
+
+impl ListStyleType {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => {
+                match value.to_ascii_lowercase().as_str() {
+                    "disc" => Some(ListStyleType::Disc),
+                    "circle" => Some(ListStyleType::Circle),
+                    "square" => Some(ListStyleType::Square),
+                    "decimal" => Some(ListStyleType::Decimal),
+                    "decimal-leading-zero" => Some(ListStyleType::DecimalLeadingZero),
+                    "lower-roman" => Some(ListStyleType::LowerRoman),
+                    "upper-roman" => Some(ListStyleType::UpperRoman),
+                    "lower-alpha" | "lower-latin" => Some(ListStyleType::LowerAlpha),
+                    "upper-alpha" | "upper-latin" => Some(ListStyleType::UpperAlpha),
+                    "none" => Some(ListStyleType::None),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
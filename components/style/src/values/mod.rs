@@ -1,28 +1,114 @@
+pub mod animation_direction;
+pub mod animation_fill_mode;
+pub mod animation_name;
+pub mod animation_play_state;
+pub mod background_image;
+pub mod border_collapse;
 pub mod border_radius;
+pub mod border_spacing;
 pub mod border_style;
 pub mod border_width;
+pub mod box_sizing;
 pub mod color;
+pub mod contain;
+pub mod content;
+pub mod content_visibility;
+pub mod cursor;
 pub mod direction;
 pub mod display;
 pub mod float;
+pub mod font_family;
+pub mod font_size;
+pub mod hyphens;
+pub mod iteration_count;
 pub mod length;
 pub mod length_percentage;
+pub mod line_height;
+pub mod list_style_position;
+pub mod list_style_type;
 pub mod number;
+pub mod overflow_wrap;
 pub mod percentage;
 pub mod position;
+pub mod quotes;
+pub mod table_layout;
+pub mod text_align;
+pub mod text_decoration_line;
+pub mod text_decoration_style;
+pub mod time;
+pub mod timing_function;
+pub mod transform;
+pub mod transition_property;
+pub mod vertical_align;
+pub mod visibility;
+pub mod white_space;
+
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// Parse a comma separated list of values, e.g. the durations of
+/// transitions. Every item of the list must be valid.
+pub(crate) fn parse_comma_separated<T, F>(values: &[ComponentValue], parse: F) -> Option<Vec<T>>
+where
+    F: Fn(&[ComponentValue]) -> Option<T>,
+{
+    values
+        .split(|value| *value == ComponentValue::PerservedToken(Token::Comma))
+        .map(|item| {
+            let is_whitespace = |value: &ComponentValue| {
+                *value == ComponentValue::PerservedToken(Token::Whitespace)
+            };
+            let start = item.iter().position(|value| !is_whitespace(value))?;
+            let end = item.iter().rposition(|value| !is_whitespace(value))?;
+            parse(&item[start..=end])
+        })
+        .collect()
+}
 
 // Let this pub because in the future we may want to use this in other places.
 // Just maybe....
 pub mod prelude {
+    pub use super::animation_direction::{AnimationDirection, AnimationDirectionList};
+    pub use super::animation_fill_mode::{AnimationFillMode, AnimationFillModeList};
+    pub use super::animation_name::AnimationName;
+    pub use super::animation_play_state::{AnimationPlayState, AnimationPlayStateList};
+    pub use super::background_image::BackgroundImage;
+    pub use super::border_collapse::BorderCollapse;
     pub use super::border_radius::BorderRadius;
+    pub use super::border_spacing::BorderSpacing;
     pub use super::border_style::BorderStyle;
     pub use super::border_width::BorderWidth;
+    pub use super::box_sizing::BoxSizing;
     pub use super::color::Color;
+    pub use super::contain::Contain;
+    pub use super::content::Content;
+    pub use super::content_visibility::ContentVisibility;
+    pub use super::cursor::Cursor;
     pub use super::direction::Direction;
     pub use super::display::Display;
     pub use super::float::Float;
+    pub use super::font_family::FontFamily;
+    pub use super::font_size::FontSize;
+    pub use super::hyphens::Hyphens;
+    pub use super::iteration_count::IterationCountList;
     pub use super::length::Length;
-    pub use super::length_percentage::LengthPercentage;
+    pub use super::length_percentage::{LengthPercentage, LengthPercentageAuto};
+    pub use super::line_height::LineHeight;
+    pub use super::list_style_position::ListStylePosition;
+    pub use super::list_style_type::ListStyleType;
+    pub use super::overflow_wrap::OverflowWrap;
     pub use super::percentage::Percentage;
     pub use super::position::Position;
+    pub use super::quotes::Quotes;
+    pub use super::table_layout::TableLayout;
+    pub use super::text_align::TextAlign;
+    pub use super::text_decoration_line::TextDecorationLine;
+    pub use super::text_decoration_style::TextDecorationStyle;
+    pub use super::time::{Time, TimeList};
+    pub use super::timing_function::{TimingFunction, TimingFunctionList};
+    pub use super::transform::Transform;
+    pub use super::transition_property::TransitionProperty;
+    pub use super::vertical_align::VerticalAlign;
+    pub use super::visibility::Visibility;
+    pub use super::white_space::WhiteSpace;
 }
@@ -0,0 +1,31 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum OverflowWrap {
+    Normal,
+    Anywhere,
+    BreakWord,
+}
+
+impl OverflowWrap {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => {
+                match value.to_ascii_lowercase().as_str() {
+                    "normal" => Some(OverflowWrap::Normal),
+                    "anywhere" => Some(OverflowWrap::Anywhere),
+                    "break-word" => Some(OverflowWrap::BreakWord),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether a word can be broken at an arbitrary point if it
+    /// doesn't fit on a line on its own
+    pub fn breaks_words(&self) -> bool {
+        *self != OverflowWrap::Normal
+    }
+}
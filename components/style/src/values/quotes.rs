@@ -0,0 +1,93 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The quotation marks of the `open-quote` & `close-quote` content
+/// https://www.w3.org/TR/css-content-3/#quotes-property
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum Quotes {
+    /// The quotation marks of the typography of the content
+    Auto,
+    /// The quotes don't generate any text
+    None,
+    /// The open & close marks of each nesting level, from the outermost
+    Pairs(Vec<(String, String)>),
+}
+
+impl Quotes {
+    /// Parse `auto | none | [<string> <string>]+`
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let values = values
+            .iter()
+            .filter(|value| **value != ComponentValue::PerservedToken(Token::Whitespace))
+            .collect::<Vec<_>>();
+
+        if let [ComponentValue::PerservedToken(Token::Ident(keyword))] = values.as_slice() {
+            return match keyword.to_ascii_lowercase().as_str() {
+                "auto" => Some(Quotes::Auto),
+                "none" => Some(Quotes::None),
+                _ => None,
+            };
+        }
+
+        if values.is_empty() || values.len() % 2 != 0 {
+            return None;
+        }
+        let strings = values
+            .into_iter()
+            .map(|value| match value {
+                ComponentValue::PerservedToken(Token::Str(string)) => Some(string.clone()),
+                _ => None,
+            })
+            .collect::<Option<Vec<_>>>()?;
+        Some(Quotes::Pairs(
+            strings
+                .chunks(2)
+                .map(|pair| (pair[0].clone(), pair[1].clone()))
+                .collect(),
+        ))
+    }
+
+    /// The open & close marks of a nesting level. The levels deeper than
+    /// the pairs use the innermost pair.
+    pub fn marks(&self, depth: usize) -> Option<(&str, &str)> {
+        match self {
+            Quotes::Auto => Some(if depth == 0 {
+                ("\u{201C}", "\u{201D}")
+            } else {
+                ("\u{2018}", "\u{2019}")
+            }),
+            Quotes::None => None,
+            Quotes::Pairs(pairs) => pairs
+                .get(depth.min(pairs.len() - 1))
+                .map(|(open, close)| (open.as_str(), close.as_str())),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<Quotes> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        Quotes::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    #[test]
+    fn parse_quotes() {
+        assert_eq!(parse("auto"), Some(Quotes::Auto));
+        assert_eq!(parse("none"), Some(Quotes::None));
+        assert_eq!(parse("'«' '»' \"<\""), None);
+        assert_eq!(parse("'«' normal"), None);
+
+        let quotes = parse("'«' '»' \"<\" \">\"").unwrap();
+        assert_eq!(quotes.marks(0), Some(("«", "»")));
+        assert_eq!(quotes.marks(1), Some(("<", ">")));
+        assert_eq!(quotes.marks(5), Some(("<", ">")));
+        assert_eq!(Quotes::None.marks(0), None);
+        assert_eq!(Quotes::Auto.marks(1), Some(("\u{2018}", "\u{2019}")));
+    }
+}
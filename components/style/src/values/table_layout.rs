@@ -0,0 +1,21 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TableLayout {
+    Auto,
+    Fixed,
+}
+
+impl TableLayout {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => match value {
+                v if v.eq_ignore_ascii_case("auto") => Some(TableLayout::Auto),
+                v if v.eq_ignore_ascii_case("fixed") => Some(TableLayout::Fixed),
+                _ => None,
+            },
+            _ => None,
+        }
+    }
+}
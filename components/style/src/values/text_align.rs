@@ -0,0 +1,45 @@
+use super::direction::Direction;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TextAlign {
+    Start,
+    End,
+    Left,
+    Right,
+    Center,
+    Justify,
+}
+
+impl TextAlign {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => {
+                match value.to_ascii_lowercase().as_str() {
+                    "start" => Some(TextAlign::Start),
+                    "end" => Some(TextAlign::End),
+                    "left" => Some(TextAlign::Left),
+                    "right" => Some(TextAlign::Right),
+                    "center" => Some(TextAlign::Center),
+                    "justify" => Some(TextAlign::Justify),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Resolve the start & end alignments to a physical side
+    pub fn resolve(&self, direction: &Direction) -> Self {
+        match (self, direction) {
+            (TextAlign::Start, Direction::Ltr) | (TextAlign::End, Direction::Rtl) => {
+                TextAlign::Left
+            }
+            (TextAlign::Start, Direction::Rtl) | (TextAlign::End, Direction::Ltr) => {
+                TextAlign::Right
+            }
+            _ => self.clone(),
+        }
+    }
+}
@@ -0,0 +1,63 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The decoration lines drawn over the text of a box
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TextDecorationLine {
+    pub underline: bool,
+    pub overline: bool,
+    pub line_through: bool,
+}
+
+impl TextDecorationLine {
+    pub fn none() -> Self {
+        Self {
+            underline: false,
+            overline: false,
+            line_through: false,
+        }
+    }
+
+    pub fn is_none(&self) -> bool {
+        *self == Self::none()
+    }
+
+    /// Parse `none | [ underline || overline || line-through ]`
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let keywords = values
+            .iter()
+            .filter_map(|value| match value {
+                ComponentValue::PerservedToken(Token::Whitespace) => None,
+                ComponentValue::PerservedToken(Token::Ident(keyword)) => {
+                    Some(Some(keyword.to_ascii_lowercase()))
+                }
+                _ => Some(None),
+            })
+            .collect::<Option<Vec<String>>>()?;
+
+        if let [keyword] = keywords.as_slice() {
+            if keyword == "none" {
+                return Some(Self::none());
+            }
+        }
+
+        let mut result = Self::none();
+        for keyword in &keywords {
+            let line = match keyword.as_str() {
+                "underline" => &mut result.underline,
+                "overline" => &mut result.overline,
+                "line-through" => &mut result.line_through,
+                _ => return None,
+            };
+            if *line {
+                return None;
+            }
+            *line = true;
+        }
+
+        if result.is_none() {
+            return None;
+        }
+        Some(result)
+    }
+}
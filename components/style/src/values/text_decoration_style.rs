@@ -0,0 +1,29 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TextDecorationStyle {
+    Solid,
+    Double,
+    Dotted,
+    Dashed,
+    Wavy,
+}
+
+impl TextDecorationStyle {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => {
+                match value.to_ascii_lowercase().as_str() {
+                    "solid" => Some(TextDecorationStyle::Solid),
+                    "double" => Some(TextDecorationStyle::Double),
+                    "dotted" => Some(TextDecorationStyle::Dotted),
+                    "dashed" => Some(TextDecorationStyle::Dashed),
+                    "wavy" => Some(TextDecorationStyle::Wavy),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+}
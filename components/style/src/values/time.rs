@@ -0,0 +1,96 @@
+use super::number::Number;
+use super::parse_comma_separated;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+use std::time::Duration;
+
+/// A CSS time, e.g. the duration of a transition. Delays can be negative.
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct Time {
+    pub milliseconds: Number,
+}
+
+impl Time {
+    pub fn zero() -> Self {
+        Self::from_ms(0.)
+    }
+
+    pub fn from_ms(milliseconds: f32) -> Self {
+        Self {
+            milliseconds: milliseconds.into(),
+        }
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Dimension { value, unit, .. })] => {
+                match unit.to_ascii_lowercase().as_str() {
+                    "s" => Some(Self::from_ms(*value * 1000.)),
+                    "ms" => Some(Self::from_ms(*value)),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// The time as a duration, negative times are clamped to zero
+    pub fn to_duration(&self) -> Duration {
+        Duration::from_secs_f32(self.milliseconds.max(0.) / 1000.)
+    }
+}
+
+/// A comma separated list of times
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TimeList(pub Vec<Time>);
+
+impl TimeList {
+    pub fn zero() -> Self {
+        Self(vec![Time::zero()])
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        parse_comma_separated(values, Time::parse).map(Self)
+    }
+
+    /// The time at an index of the list, repeating the list
+    /// when it is shorter than the list of properties it applies to
+    pub fn get(&self, index: usize) -> Time {
+        match self.0.len() {
+            0 => Time::zero(),
+            len => self.0[index % len].clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<TimeList> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        TimeList::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    #[test]
+    fn parse_time_list() {
+        let list = parse("1s, -0.5s,250ms ").unwrap();
+        assert_eq!(
+            list,
+            TimeList(vec![
+                Time::from_ms(1000.),
+                Time::from_ms(-500.),
+                Time::from_ms(250.)
+            ])
+        );
+        assert_eq!(list.get(4), Time::from_ms(-500.));
+        assert_eq!(list.get(1).to_duration(), Duration::ZERO);
+
+        assert!(parse("1s 2s").is_none());
+        assert!(parse("1s,").is_none());
+        assert!(parse("10px").is_none());
+    }
+}
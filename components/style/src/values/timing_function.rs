@@ -0,0 +1,262 @@
+use super::number::Number;
+use super::parse_comma_separated;
+use css::parser::structs::{ComponentValue, Function};
+use css::tokenizer::token::Token;
+
+/// An easing function, mapping the progress of a transition
+/// to the progress of the transitioned value
+/// https://www.w3.org/TR/css-easing-1/#easing-functions
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TimingFunction {
+    Linear,
+    /// The control points (x1, y1, x2, y2) of a cubic bezier curve
+    CubicBezier(Number, Number, Number, Number),
+    Steps(u32, StepPosition),
+}
+
+/// Where the jumps of a step easing function happen
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum StepPosition {
+    JumpStart,
+    JumpEnd,
+    JumpNone,
+    JumpBoth,
+}
+
+impl TimingFunction {
+    pub fn ease() -> Self {
+        Self::cubic_bezier(0.25, 0.1, 0.25, 1.)
+    }
+
+    fn cubic_bezier(x1: f32, y1: f32, x2: f32, y2: f32) -> Self {
+        TimingFunction::CubicBezier(x1.into(), y1.into(), x2.into(), y2.into())
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(keyword))] => {
+                match keyword.to_ascii_lowercase().as_str() {
+                    "linear" => Some(TimingFunction::Linear),
+                    "ease" => Some(Self::ease()),
+                    "ease-in" => Some(Self::cubic_bezier(0.42, 0., 1., 1.)),
+                    "ease-out" => Some(Self::cubic_bezier(0., 0., 0.58, 1.)),
+                    "ease-in-out" => Some(Self::cubic_bezier(0.42, 0., 0.58, 1.)),
+                    "step-start" => Some(TimingFunction::Steps(1, StepPosition::JumpStart)),
+                    "step-end" => Some(TimingFunction::Steps(1, StepPosition::JumpEnd)),
+                    _ => None,
+                }
+            }
+            [ComponentValue::Function(function)] => {
+                match function.name.to_ascii_lowercase().as_str() {
+                    "cubic-bezier" => Self::parse_cubic_bezier(function),
+                    "steps" => Self::parse_steps(function),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_cubic_bezier(function: &Function) -> Option<Self> {
+        let points = parse_comma_separated(&function.value, |values| match values {
+            [ComponentValue::PerservedToken(Token::Number { value, .. })] => Some(*value),
+            _ => None,
+        })?;
+
+        match points.as_slice() {
+            // the x coordinates must be in [0, 1] for the curve to be a function of time
+            [x1, y1, x2, y2] if (0. ..=1.).contains(x1) && (0. ..=1.).contains(x2) => {
+                Some(Self::cubic_bezier(*x1, *y1, *x2, *y2))
+            }
+            _ => None,
+        }
+    }
+
+    fn parse_steps(function: &Function) -> Option<Self> {
+        let arguments = parse_comma_separated(&function.value, |values| match values {
+            [value] => Some(value.clone()),
+            _ => None,
+        })?;
+
+        let (count, position) = match arguments.as_slice() {
+            [ComponentValue::PerservedToken(Token::Number { value, .. })] => {
+                (*value, StepPosition::JumpEnd)
+            }
+            [ComponentValue::PerservedToken(Token::Number { value, .. }), ComponentValue::PerservedToken(Token::Ident(position))] =>
+            {
+                let position = match position.to_ascii_lowercase().as_str() {
+                    "jump-start" | "start" => StepPosition::JumpStart,
+                    "jump-end" | "end" => StepPosition::JumpEnd,
+                    "jump-none" => StepPosition::JumpNone,
+                    "jump-both" => StepPosition::JumpBoth,
+                    _ => return None,
+                };
+                (*value, position)
+            }
+            _ => return None,
+        };
+
+        let min_count = match position {
+            StepPosition::JumpNone => 2.,
+            _ => 1.,
+        };
+        if count.fract() != 0. || count < min_count {
+            return None;
+        }
+        Some(TimingFunction::Steps(count as u32, position))
+    }
+
+    /// The output progress of the function for an input progress in [0, 1]
+    pub fn output(&self, progress: f32) -> f32 {
+        match self {
+            TimingFunction::Linear => progress,
+            TimingFunction::CubicBezier(x1, y1, x2, y2) => {
+                let t = solve_bezier_x(progress, **x1, **x2);
+                bezier(t, **y1, **y2)
+            }
+            TimingFunction::Steps(_, _) if progress >= 1. => 1.,
+            TimingFunction::Steps(count, position) => {
+                let count = *count as f32;
+                let mut step = (progress * count).floor();
+                if let StepPosition::JumpStart | StepPosition::JumpBoth = position {
+                    step += 1.;
+                }
+                let jumps = match position {
+                    StepPosition::JumpStart | StepPosition::JumpEnd => count,
+                    StepPosition::JumpNone => count - 1.,
+                    StepPosition::JumpBoth => count + 1.,
+                };
+                (step / jumps).min(1.)
+            }
+        }
+    }
+}
+
+/// A coordinate of a cubic bezier curve from (0, 0) to (1, 1)
+fn bezier(t: f32, p1: f32, p2: f32) -> f32 {
+    let u = 1. - t;
+    3. * u * u * t * p1 + 3. * u * t * t * p2 + t * t * t
+}
+
+/// Find the parameter of the curve at which its x coordinate is `x`
+fn solve_bezier_x(x: f32, x1: f32, x2: f32) -> f32 {
+    const EPSILON: f32 = 1e-6;
+
+    // Newton's method converges quickly for most curves
+    let mut t = x;
+    for _ in 0..8 {
+        let error = bezier(t, x1, x2) - x;
+        if error.abs() < EPSILON {
+            return t;
+        }
+        let u = 1. - t;
+        let derivative = 3. * u * u * x1 + 6. * u * t * (x2 - x1) + 3. * t * t * (1. - x2);
+        if derivative.abs() < EPSILON {
+            break;
+        }
+        t -= error / derivative;
+    }
+
+    // fall back to bisection, x is monotonic in t
+    let (mut low, mut high) = (0., 1.);
+    t = x;
+    while high - low > EPSILON {
+        if bezier(t, x1, x2) < x {
+            low = t;
+        } else {
+            high = t;
+        }
+        t = (low + high) / 2.;
+    }
+    t
+}
+
+/// A comma separated list of easing functions
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub struct TimingFunctionList(pub Vec<TimingFunction>);
+
+impl TimingFunctionList {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        parse_comma_separated(values, TimingFunction::parse).map(Self)
+    }
+
+    /// The function at an index of the list, repeating the list
+    /// when it is shorter than the list of properties it applies to
+    pub fn get(&self, index: usize) -> TimingFunction {
+        match self.0.len() {
+            0 => TimingFunction::ease(),
+            len => self.0[index % len].clone(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<TimingFunction> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        TimingFunction::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    fn assert_output(function: &TimingFunction, progress: f32, expected: f32) {
+        let output = function.output(progress);
+        assert!(
+            (output - expected).abs() < 0.001,
+            "{:?} at {} is {}, expected {}",
+            function,
+            progress,
+            output,
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_timing_functions() {
+        assert_eq!(parse("linear"), Some(TimingFunction::Linear));
+        assert_eq!(parse("ease"), Some(TimingFunction::ease()));
+        assert_eq!(
+            parse("cubic-bezier(0.1, -2, 0.3, 3)"),
+            Some(TimingFunction::cubic_bezier(0.1, -2., 0.3, 3.))
+        );
+        assert_eq!(
+            parse("steps(4, jump-both)"),
+            Some(TimingFunction::Steps(4, StepPosition::JumpBoth))
+        );
+        assert_eq!(
+            parse("step-end"),
+            Some(TimingFunction::Steps(1, StepPosition::JumpEnd))
+        );
+
+        assert_eq!(parse("cubic-bezier(2, 0, 0, 1)"), None);
+        assert_eq!(parse("steps(1, jump-none)"), None);
+        assert_eq!(parse("steps(1.5)"), None);
+    }
+
+    #[test]
+    fn evaluate_timing_functions() {
+        assert_output(&TimingFunction::Linear, 0.3, 0.3);
+
+        let ease_in_out = parse("ease-in-out").unwrap();
+        assert_output(&ease_in_out, 0., 0.);
+        assert_output(&ease_in_out, 0.5, 0.5);
+        assert_output(&ease_in_out, 1., 1.);
+        assert!(ease_in_out.output(0.25) < 0.25);
+
+        let steps = parse("steps(4)").unwrap();
+        assert_output(&steps, 0.2, 0.);
+        assert_output(&steps, 0.3, 0.25);
+        assert_output(&steps, 1., 1.);
+
+        let steps = parse("steps(2, jump-start)").unwrap();
+        assert_output(&steps, 0., 0.5);
+        assert_output(&steps, 0.6, 1.);
+
+        let steps = parse("steps(3, jump-none)").unwrap();
+        assert_output(&steps, 0.5, 0.5);
+        assert_output(&steps, 0.9, 1.);
+    }
+}
@@ -0,0 +1,221 @@
+use super::length::LengthContext;
+use super::length_percentage::LengthPercentage;
+use super::number::Number;
+use super::prelude::Length;
+use css::parser::structs::{ComponentValue, Function};
+use css::tokenizer::token::Token;
+
+/// The value of the `transform` property
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum Transform {
+    None,
+    /// Transform functions, applied from right to left
+    Functions(Vec<TransformFunction>),
+}
+
+/// https://www.w3.org/TR/css-transforms-1/#two-d-transform-functions
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum TransformFunction {
+    /// `matrix(a, b, c, d, e, f)`
+    Matrix([Number; 6]),
+    Translate(LengthPercentage, LengthPercentage),
+    Scale(Number, Number),
+    /// Rotation angle in degrees, clockwise
+    Rotate(Number),
+    /// Skew angles along the x & y axis in degrees
+    Skew(Number, Number),
+}
+
+impl Transform {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        let mut values = values.iter().filter(|value| match value {
+            ComponentValue::PerservedToken(Token::Whitespace) => false,
+            _ => true,
+        });
+
+        let mut functions = Vec::new();
+        while let Some(value) = values.next() {
+            match value {
+                ComponentValue::PerservedToken(Token::Ident(keyword))
+                    if keyword.eq_ignore_ascii_case("none") && functions.is_empty() =>
+                {
+                    return match values.next() {
+                        Some(_) => None,
+                        None => Some(Transform::None),
+                    };
+                }
+                ComponentValue::Function(function) => {
+                    functions.push(TransformFunction::parse(function)?)
+                }
+                _ => return None,
+            }
+        }
+
+        if functions.is_empty() {
+            return None;
+        }
+        Some(Transform::Functions(functions))
+    }
+
+    pub fn resolve(&self, context: &LengthContext) -> Self {
+        match self {
+            Transform::None => Transform::None,
+            Transform::Functions(functions) => Transform::Functions(
+                functions
+                    .iter()
+                    .map(|function| match function {
+                        TransformFunction::Translate(x, y) => {
+                            TransformFunction::Translate(x.resolve(context), y.resolve(context))
+                        }
+                        _ => function.clone(),
+                    })
+                    .collect(),
+            ),
+        }
+    }
+}
+
+impl TransformFunction {
+    pub fn parse(function: &Function) -> Option<Self> {
+        let args = function
+            .value
+            .split(|value| match value {
+                ComponentValue::PerservedToken(Token::Comma) => true,
+                _ => false,
+            })
+            .map(|arg| {
+                arg.iter()
+                    .filter(|value| match value {
+                        ComponentValue::PerservedToken(Token::Whitespace) => false,
+                        _ => true,
+                    })
+                    .cloned()
+                    .collect::<Vec<ComponentValue>>()
+            })
+            .collect::<Vec<Vec<ComponentValue>>>();
+
+        let name = function.name.to_ascii_lowercase();
+        match (name.as_str(), args.len()) {
+            ("matrix", 6) => {
+                let mut matrix = [0.0; 6];
+                for (index, arg) in args.iter().enumerate() {
+                    matrix[index] = parse_number(arg)?;
+                }
+                Some(TransformFunction::Matrix([
+                    matrix[0].into(),
+                    matrix[1].into(),
+                    matrix[2].into(),
+                    matrix[3].into(),
+                    matrix[4].into(),
+                    matrix[5].into(),
+                ]))
+            }
+            ("translate", 1) => Some(TransformFunction::Translate(
+                LengthPercentage::parse(&args[0])?,
+                LengthPercentage::Length(Length::zero()),
+            )),
+            ("translate", 2) => Some(TransformFunction::Translate(
+                LengthPercentage::parse(&args[0])?,
+                LengthPercentage::parse(&args[1])?,
+            )),
+            ("translatex", 1) => Some(TransformFunction::Translate(
+                LengthPercentage::parse(&args[0])?,
+                LengthPercentage::Length(Length::zero()),
+            )),
+            ("translatey", 1) => Some(TransformFunction::Translate(
+                LengthPercentage::Length(Length::zero()),
+                LengthPercentage::parse(&args[0])?,
+            )),
+            ("scale", 1) => {
+                let scale = parse_number(&args[0])?;
+                Some(TransformFunction::Scale(scale.into(), scale.into()))
+            }
+            ("scale", 2) => Some(TransformFunction::Scale(
+                parse_number(&args[0])?.into(),
+                parse_number(&args[1])?.into(),
+            )),
+            ("scalex", 1) => Some(TransformFunction::Scale(
+                parse_number(&args[0])?.into(),
+                1.0.into(),
+            )),
+            ("scaley", 1) => Some(TransformFunction::Scale(
+                1.0.into(),
+                parse_number(&args[0])?.into(),
+            )),
+            ("rotate", 1) => Some(TransformFunction::Rotate(parse_angle(&args[0])?.into())),
+            ("skew", 1) => Some(TransformFunction::Skew(
+                parse_angle(&args[0])?.into(),
+                0.0.into(),
+            )),
+            ("skew", 2) => Some(TransformFunction::Skew(
+                parse_angle(&args[0])?.into(),
+                parse_angle(&args[1])?.into(),
+            )),
+            ("skewx", 1) => Some(TransformFunction::Skew(
+                parse_angle(&args[0])?.into(),
+                0.0.into(),
+            )),
+            ("skewy", 1) => Some(TransformFunction::Skew(
+                0.0.into(),
+                parse_angle(&args[0])?.into(),
+            )),
+            _ => None,
+        }
+    }
+}
+
+fn parse_number(values: &[ComponentValue]) -> Option<f32> {
+    match values {
+        [ComponentValue::PerservedToken(Token::Number { value, .. })] => Some(*value),
+        _ => None,
+    }
+}
+
+/// Parse an angle in degrees
+fn parse_angle(values: &[ComponentValue]) -> Option<f32> {
+    match values {
+        [ComponentValue::PerservedToken(Token::Dimension { value, unit, .. })] => {
+            match unit.to_ascii_lowercase().as_str() {
+                "deg" => Some(*value),
+                "rad" => Some(value.to_degrees()),
+                "grad" => Some(*value * 0.9),
+                "turn" => Some(*value * 360.0),
+                _ => None,
+            }
+        }
+        [ComponentValue::PerservedToken(Token::Number { value, .. })] if *value == 0.0 => Some(0.0),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::values::percentage::Percentage;
+    use css::parser::Parser;
+    use css::tokenizer::Tokenizer;
+
+    fn parse(css: &str) -> Option<Transform> {
+        let tokenizer = Tokenizer::new(css.chars());
+        let mut parser = Parser::<Token>::new(tokenizer.run());
+        Transform::parse(&parser.parse_a_list_of_component_values())
+    }
+
+    #[test]
+    fn parse_transform_functions() {
+        assert_eq!(parse("none"), Some(Transform::None));
+        assert_eq!(
+            parse("translate(10px, 50%) rotate(0.25turn) scale(2)"),
+            Some(Transform::Functions(vec![
+                TransformFunction::Translate(
+                    LengthPercentage::Length(Length::new_px(10.0)),
+                    LengthPercentage::Percentage(Percentage(50.0.into())),
+                ),
+                TransformFunction::Rotate(90.0.into()),
+                TransformFunction::Scale(2.0.into(), 2.0.into()),
+            ]))
+        );
+        assert_eq!(parse("rotate(10px)"), None);
+        assert_eq!(parse("none rotate(10deg)"), None);
+    }
+}
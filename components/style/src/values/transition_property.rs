@@ -0,0 +1,68 @@
+use super::parse_comma_separated;
+use crate::value_processing::Property;
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The properties transitioned when their computed value changes
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum TransitionProperty {
+    None,
+    List(Vec<SingleTransitionProperty>),
+}
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum SingleTransitionProperty {
+    All,
+    Property(Property),
+    /// A property we don't support. It still takes a place in the
+    /// list so the durations of the other properties are matched correctly.
+    Unknown(String),
+}
+
+impl TransitionProperty {
+    pub fn all() -> Self {
+        TransitionProperty::List(vec![SingleTransitionProperty::All])
+    }
+
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        if let [ComponentValue::PerservedToken(Token::Ident(keyword))] = values {
+            if keyword.eq_ignore_ascii_case("none") {
+                return Some(TransitionProperty::None);
+            }
+        }
+        parse_comma_separated(values, SingleTransitionProperty::parse).map(TransitionProperty::List)
+    }
+
+    /// The index in the list of the last item transitioning a property,
+    /// used to find the duration, delay & easing of the transition
+    pub fn index_of(&self, property: &Property) -> Option<usize> {
+        match self {
+            TransitionProperty::None => None,
+            TransitionProperty::List(items) => items.iter().rposition(|item| match item {
+                SingleTransitionProperty::All => true,
+                SingleTransitionProperty::Property(item) => item == property,
+                SingleTransitionProperty::Unknown(_) => false,
+            }),
+        }
+    }
+}
+
+impl SingleTransitionProperty {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(name))] => {
+                let name = name.to_ascii_lowercase();
+                match name.as_str() {
+                    "all" => Some(SingleTransitionProperty::All),
+                    // CSS-wide keywords & none can't be used in a list
+                    "none" | "initial" | "inherit" | "unset" => None,
+                    _ => match Property::parse(&name) {
+                        Some(property) => Some(SingleTransitionProperty::Property(property)),
+                        None => Some(SingleTransitionProperty::Unknown(name)),
+                    },
+                }
+            }
+            _ => None,
+        }
+    }
+}
@@ -0,0 +1,55 @@
+use super::length::{Length, LengthContext};
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+/// The vertical alignment of an inline-level box in its line box
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum VerticalAlign {
+    Baseline,
+    Sub,
+    Super,
+    TextTop,
+    TextBottom,
+    Middle,
+    Top,
+    Bottom,
+    /// Raise the box above the baseline by a length
+    Length(Length),
+}
+
+impl VerticalAlign {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => {
+                match value.to_ascii_lowercase().as_str() {
+                    "baseline" => Some(VerticalAlign::Baseline),
+                    "sub" => Some(VerticalAlign::Sub),
+                    "super" => Some(VerticalAlign::Super),
+                    "text-top" => Some(VerticalAlign::TextTop),
+                    "text-bottom" => Some(VerticalAlign::TextBottom),
+                    "middle" => Some(VerticalAlign::Middle),
+                    "top" => Some(VerticalAlign::Top),
+                    "bottom" => Some(VerticalAlign::Bottom),
+                    _ => None,
+                }
+            }
+            _ => Length::parse(values).map(VerticalAlign::Length),
+        }
+    }
+
+    pub fn resolve(&self, context: &LengthContext) -> Self {
+        match self {
+            VerticalAlign::Length(length) => VerticalAlign::Length(length.resolve(context)),
+            _ => self.clone(),
+        }
+    }
+
+    /// Whether the box is aligned relative to the line box instead
+    /// of the baseline of its parent
+    pub fn is_line_relative(&self) -> bool {
+        match self {
+            VerticalAlign::Top | VerticalAlign::Bottom => true,
+            _ => false,
+        }
+    }
+}
@@ -0,0 +1,53 @@
+use css::parser::structs::ComponentValue;
+use css::tokenizer::token::Token;
+
+#[derive(Debug, Clone, Eq, PartialEq, Hash)]
+pub enum WhiteSpace {
+    Normal,
+    Pre,
+    Nowrap,
+    PreWrap,
+    PreLine,
+}
+
+impl WhiteSpace {
+    pub fn parse(values: &[ComponentValue]) -> Option<Self> {
+        match values {
+            [ComponentValue::PerservedToken(Token::Ident(value))] => {
+                match value.to_ascii_lowercase().as_str() {
+                    "normal" => Some(WhiteSpace::Normal),
+                    "pre" => Some(WhiteSpace::Pre),
+                    "nowrap" => Some(WhiteSpace::Nowrap),
+                    "pre-wrap" => Some(WhiteSpace::PreWrap),
+                    "pre-line" => Some(WhiteSpace::PreLine),
+                    _ => None,
+                }
+            }
+            _ => None,
+        }
+    }
+
+    /// Whether the newlines of the text are forced line breaks
+    pub fn preserves_newlines(&self) -> bool {
+        match self {
+            WhiteSpace::Pre | WhiteSpace::PreWrap | WhiteSpace::PreLine => true,
+            _ => false,
+        }
+    }
+
+    /// Whether sequences of spaces & tabs are kept as is
+    pub fn preserves_spaces(&self) -> bool {
+        match self {
+            WhiteSpace::Pre | WhiteSpace::PreWrap => true,
+            _ => false,
+        }
+    }
+
+    /// Whether lines can be wrapped at soft wrap opportunities
+    pub fn wraps(&self) -> bool {
+        match self {
+            WhiteSpace::Pre | WhiteSpace::Nowrap => false,
+            _ => true,
+        }
+    }
+}
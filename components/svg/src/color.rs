@@ -0,0 +1,124 @@
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Color {
+    pub r: u8,
+    pub g: u8,
+    pub b: u8,
+    pub a: u8,
+}
+
+impl Color {
+    pub const fn new(r: u8, g: u8, b: u8, a: u8) -> Self {
+        Self { r, g, b, a }
+    }
+
+    pub const fn black() -> Self {
+        Self::new(0, 0, 0, 255)
+    }
+}
+
+/// Parse `#rgb`, `#rrggbb`, `rgb()`, `rgba()` & the common named colors
+pub fn parse_color(value: &str) -> Option<Color> {
+    let value = value.trim();
+    if let Some(hex) = value.strip_prefix('#') {
+        return parse_hex(hex);
+    }
+    let lowercase = value.to_ascii_lowercase();
+    if let Some(args) = lowercase
+        .strip_prefix("rgba(")
+        .or_else(|| lowercase.strip_prefix("rgb("))
+    {
+        return parse_rgb(args.strip_suffix(')')?);
+    }
+    named_color(&lowercase)
+}
+
+fn parse_hex(hex: &str) -> Option<Color> {
+    let digit = |index: usize| u8::from_str_radix(hex.get(index..index + 1)?, 16).ok();
+    let byte = |index: usize| u8::from_str_radix(hex.get(index..index + 2)?, 16).ok();
+    match hex.len() {
+        3 => Some(Color::new(
+            digit(0)? * 17,
+            digit(1)? * 17,
+            digit(2)? * 17,
+            255,
+        )),
+        6 => Some(Color::new(byte(0)?, byte(2)?, byte(4)?, 255)),
+        _ => None,
+    }
+}
+
+fn parse_rgb(args: &str) -> Option<Color> {
+    let args = args
+        .split(|ch: char| ch == ',' || ch.is_whitespace() || ch == '/')
+        .filter(|arg| !arg.is_empty())
+        .collect::<Vec<&str>>();
+    let channel = |arg: &str| -> Option<u8> {
+        let value = match arg.strip_suffix('%') {
+            Some(percentage) => percentage.parse::<f32>().ok()? * 2.55,
+            None => arg.parse::<f32>().ok()?,
+        };
+        Some(value.max(0.).min(255.).round() as u8)
+    };
+    let alpha = |arg: &str| -> Option<u8> {
+        let value = match arg.strip_suffix('%') {
+            Some(percentage) => percentage.parse::<f32>().ok()? / 100.,
+            None => arg.parse::<f32>().ok()?,
+        };
+        Some((value.max(0.).min(1.) * 255.).round() as u8)
+    };
+    match args.as_slice() {
+        [r, g, b] => Some(Color::new(channel(r)?, channel(g)?, channel(b)?, 255)),
+        [r, g, b, a] => Some(Color::new(channel(r)?, channel(g)?, channel(b)?, alpha(a)?)),
+        _ => None,
+    }
+}
+
+fn named_color(name: &str) -> Option<Color> {
+    let (r, g, b) = match name {
+        "black" => (0, 0, 0),
+        "white" => (255, 255, 255),
+        "red" => (255, 0, 0),
+        "green" => (0, 128, 0),
+        "blue" => (0, 0, 255),
+        "yellow" => (255, 255, 0),
+        "orange" => (255, 165, 0),
+        "purple" => (128, 0, 128),
+        "gray" | "grey" => (128, 128, 128),
+        "silver" => (192, 192, 192),
+        "maroon" => (128, 0, 0),
+        "navy" => (0, 0, 128),
+        "teal" => (0, 128, 128),
+        "olive" => (128, 128, 0),
+        "lime" => (0, 255, 0),
+        "aqua" | "cyan" => (0, 255, 255),
+        "fuchsia" | "magenta" => (255, 0, 255),
+        "pink" => (255, 192, 203),
+        "brown" => (165, 42, 42),
+        "gold" => (255, 215, 0),
+        "transparent" => return Some(Color::new(0, 0, 0, 0)),
+        _ => return None,
+    };
+    Some(Color::new(r, g, b, 255))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_colors() {
+        assert_eq!(parse_color("#f80"), Some(Color::new(255, 136, 0, 255)));
+        assert_eq!(parse_color("#00FF7f"), Some(Color::new(0, 255, 127, 255)));
+        assert_eq!(
+            parse_color("rgb(10, 20, 30)"),
+            Some(Color::new(10, 20, 30, 255))
+        );
+        assert_eq!(
+            parse_color("rgba(100%,0%,0%,0.5)"),
+            Some(Color::new(255, 0, 0, 128))
+        );
+        assert_eq!(parse_color("Navy"), Some(Color::new(0, 0, 128, 255)));
+        assert_eq!(parse_color("#ff"), None);
+        assert_eq!(parse_color("moon"), None);
+    }
+}
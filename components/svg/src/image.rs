@@ -0,0 +1,587 @@
+use super::color::{parse_color, Color};
+use super::number::{parse_length, parse_numbers};
+use super::path::Path;
+use super::raster::{stroke_polygons, Canvas, FillRule};
+use super::transform::Transform;
+use super::xml::{self, Element};
+
+/// How close the flattened curves are to the real curves, in pixels
+const TOLERANCE: f32 = 0.2;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewBox {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
+/// https://www.w3.org/TR/SVG11/coords.html#PreserveAspectRatioAttribute
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct AspectRatio {
+    /// Where the view box is aligned in the viewport, from 0 to 1 on each
+    /// axis, or `None` to stretch it
+    align: Option<(f32, f32)>,
+    slice: bool,
+}
+
+impl Default for AspectRatio {
+    fn default() -> Self {
+        Self {
+            align: Some((0.5, 0.5)),
+            slice: false,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Fill {
+    pub color: Color,
+    pub rule: FillRule,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Stroke {
+    pub color: Color,
+    pub width: f32,
+}
+
+/// A painted shape, in the coordinates of the view box
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shape {
+    pub path: Path,
+    pub fill: Option<Fill>,
+    pub stroke: Option<Stroke>,
+}
+
+/// A parsed SVG document, painted at any size. Shapes are filled &
+/// stroked with solid colors; gradients, patterns, text, clipping &
+/// references between elements aren't supported.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SvgImage {
+    width: Option<f32>,
+    height: Option<f32>,
+    view_box: Option<ViewBox>,
+    aspect_ratio: AspectRatio,
+    shapes: Vec<Shape>,
+}
+
+/// The inherited presentation attributes of the element being built
+#[derive(Debug, Clone)]
+struct Context {
+    transform: Transform,
+    fill: Option<Color>,
+    fill_rule: FillRule,
+    fill_opacity: f32,
+    stroke: Option<Color>,
+    stroke_width: f32,
+    stroke_opacity: f32,
+    /// The opacity of the groups, applied to the shapes they contain
+    opacity: f32,
+    color: Color,
+    visible: bool,
+    /// The size percentages are resolved against
+    viewport: (f32, f32),
+}
+
+impl SvgImage {
+    pub fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let text = std::str::from_utf8(bytes).map_err(|e| format!("Invalid SVG: {}", e))?;
+        let root = xml::parse(text)?;
+        Self::from_element(&root)
+    }
+
+    /// Build the image of an `<svg>` element
+    pub fn from_element(root: &Element) -> Result<Self, String> {
+        if root.name != "svg" {
+            return Err(format!("Expected an <svg> element, found <{}>", root.name));
+        }
+        let view_box = root.attribute("viewBox").and_then(parse_view_box);
+        let size = |name: &str| {
+            root.attribute(name)
+                .filter(|value| !value.trim().ends_with('%'))
+                .and_then(|value| parse_length(value, 0.))
+                .filter(|length| *length >= 0.)
+        };
+        let (width, height) = (size("width"), size("height"));
+        let viewport = match view_box {
+            Some(view_box) => (view_box.width, view_box.height),
+            None => (width.unwrap_or(300.), height.unwrap_or(150.)),
+        };
+
+        let mut image = Self {
+            width,
+            height,
+            view_box,
+            aspect_ratio: root
+                .attribute("preserveAspectRatio")
+                .and_then(parse_aspect_ratio)
+                .unwrap_or_default(),
+            shapes: Vec::new(),
+        };
+        let context = Context {
+            transform: Transform::identity(),
+            fill: Some(Color::black()),
+            fill_rule: FillRule::NonZero,
+            fill_opacity: 1.,
+            stroke: None,
+            stroke_width: 1.,
+            stroke_opacity: 1.,
+            opacity: 1.,
+            color: Color::black(),
+            visible: true,
+            viewport,
+        };
+        let context = apply_presentation(root, &context);
+        for child in &root.children {
+            image.build(child, &context);
+        }
+        Ok(image)
+    }
+
+    pub fn shapes(&self) -> &[Shape] {
+        &self.shapes
+    }
+
+    /// The width of the image when it's not sized by the layout
+    pub fn intrinsic_width(&self) -> Option<f32> {
+        self.width
+            .or_else(|| Some(self.height? * self.aspect_ratio()?))
+    }
+
+    pub fn intrinsic_height(&self) -> Option<f32> {
+        self.height
+            .or_else(|| Some(self.width? / self.aspect_ratio()?))
+    }
+
+    /// The ratio of the width to the height of the image
+    pub fn aspect_ratio(&self) -> Option<f32> {
+        match (self.width, self.height, self.view_box) {
+            (Some(width), Some(height), _) if width > 0. && height > 0. => Some(width / height),
+            (_, _, Some(view_box)) => Some(view_box.width / view_box.height),
+            _ => None,
+        }
+    }
+
+    /// Paint the image at a size, returning its RGBA pixels row by row
+    pub fn rasterize(&self, width: u32, height: u32) -> Vec<u8> {
+        let transform = self.view_transform(width as f32, height as f32);
+        let scale = transform.mean_scale();
+        let mut canvas = Canvas::new(width, height);
+
+        for shape in &self.shapes {
+            let path = shape.path.transform(&transform);
+            let polylines = path.flatten(TOLERANCE);
+            if let Some(fill) = &shape.fill {
+                canvas.fill(&polylines, fill.rule, fill.color);
+            }
+            if let Some(stroke) = &shape.stroke {
+                let polygons = stroke_polygons(&polylines, stroke.width * scale);
+                canvas.fill(&polygons, FillRule::NonZero, stroke.color);
+            }
+        }
+        canvas.to_rgba()
+    }
+
+    /// Map the view box to the painted size. Without a view box, the
+    /// user units are pixels.
+    fn view_transform(&self, width: f32, height: f32) -> Transform {
+        let view_box = match self.view_box {
+            Some(view_box) => view_box,
+            None => return Transform::identity(),
+        };
+        let scale_x = width / view_box.width;
+        let scale_y = height / view_box.height;
+        let (scale_x, scale_y, (align_x, align_y)) = match self.aspect_ratio.align {
+            None => (scale_x, scale_y, (0., 0.)),
+            Some(align) => {
+                let scale = if self.aspect_ratio.slice {
+                    scale_x.max(scale_y)
+                } else {
+                    scale_x.min(scale_y)
+                };
+                (scale, scale, align)
+            }
+        };
+        let offset_x = (width - view_box.width * scale_x) * align_x - view_box.x * scale_x;
+        let offset_y = (height - view_box.height * scale_y) * align_y - view_box.y * scale_y;
+        Transform::new(scale_x, 0., 0., scale_y, offset_x, offset_y)
+    }
+
+    fn build(&mut self, element: &Element, parent: &Context) {
+        let property = |name| property(element, name);
+        if property("display").map(str::trim) == Some("none") {
+            return;
+        }
+        let context = apply_presentation(element, parent);
+
+        match element.name.as_str() {
+            "g" | "a" | "switch" => {
+                for child in &element.children {
+                    self.build(child, &context);
+                }
+            }
+            "svg" => {
+                // TODO: map the view box of nested viewports
+                let mut context = context;
+                let x = self.length(element, "x", context.viewport.0);
+                let y = self.length(element, "y", context.viewport.1);
+                context.transform = context.transform.multiply(&Transform::translate(x, y));
+                for child in &element.children {
+                    self.build(child, &context);
+                }
+            }
+            name => {
+                if let Some(path) = self.shape_path(name, element, &context) {
+                    self.add_shape(path, &context);
+                }
+            }
+        }
+    }
+
+    fn length(&self, element: &Element, name: &str, reference: f32) -> f32 {
+        element
+            .attribute(name)
+            .and_then(|value| parse_length(value, reference))
+            .unwrap_or(0.)
+    }
+
+    /// The outline of the basic shapes
+    /// https://www.w3.org/TR/SVG11/shapes.html
+    fn shape_path(&self, name: &str, element: &Element, context: &Context) -> Option<Path> {
+        let (viewport_width, viewport_height) = context.viewport;
+        let diagonal = (viewport_width.powi(2) + viewport_height.powi(2)).sqrt() / 2f32.sqrt();
+        let x = |name| self.length(element, name, viewport_width);
+        let y = |name| self.length(element, name, viewport_height);
+        let mut path = Path::new();
+
+        match name {
+            "path" => return Some(Path::parse(element.attribute("d")?)),
+            "rect" => {
+                let (left, top, width, height) = (x("x"), y("y"), x("width"), y("height"));
+                if width <= 0. || height <= 0. {
+                    return None;
+                }
+                let rx = element
+                    .attribute("rx")
+                    .and_then(|v| parse_length(v, viewport_width));
+                let ry = element
+                    .attribute("ry")
+                    .and_then(|v| parse_length(v, viewport_height));
+                let (rx, ry) = match (rx, ry) {
+                    (Some(rx), Some(ry)) => (rx, ry),
+                    (Some(r), None) | (None, Some(r)) => (r, r),
+                    (None, None) => (0., 0.),
+                };
+                let (rx, ry) = (rx.max(0.).min(width / 2.), ry.max(0.).min(height / 2.));
+                let (right, bottom) = (left + width, top + height);
+
+                path.move_to(left + rx, top);
+                path.line_to(right - rx, top);
+                path.arc_to(
+                    (right - rx, top),
+                    rx,
+                    ry,
+                    0.,
+                    false,
+                    true,
+                    (right, top + ry),
+                );
+                path.line_to(right, bottom - ry);
+                path.arc_to(
+                    (right, bottom - ry),
+                    rx,
+                    ry,
+                    0.,
+                    false,
+                    true,
+                    (right - rx, bottom),
+                );
+                path.line_to(left + rx, bottom);
+                path.arc_to(
+                    (left + rx, bottom),
+                    rx,
+                    ry,
+                    0.,
+                    false,
+                    true,
+                    (left, bottom - ry),
+                );
+                path.line_to(left, top + ry);
+                path.arc_to((left, top + ry), rx, ry, 0., false, true, (left + rx, top));
+                path.close();
+            }
+            "circle" | "ellipse" => {
+                let (cx, cy) = (x("cx"), y("cy"));
+                let (rx, ry) = if name == "circle" {
+                    let r = self.length(element, "r", diagonal);
+                    (r, r)
+                } else {
+                    (x("rx"), y("ry"))
+                };
+                if rx <= 0. || ry <= 0. {
+                    return None;
+                }
+                path.move_to(cx + rx, cy);
+                path.arc_to((cx + rx, cy), rx, ry, 0., false, true, (cx - rx, cy));
+                path.arc_to((cx - rx, cy), rx, ry, 0., false, true, (cx + rx, cy));
+                path.close();
+            }
+            "line" => {
+                path.move_to(x("x1"), y("y1"));
+                path.line_to(x("x2"), y("y2"));
+            }
+            "polyline" | "polygon" => {
+                let points = parse_numbers(element.attribute("points")?).unwrap_or_default();
+                let mut pairs = points.chunks_exact(2);
+                let first = pairs.next()?;
+                path.move_to(first[0], first[1]);
+                for pair in pairs {
+                    path.line_to(pair[0], pair[1]);
+                }
+                if name == "polygon" {
+                    path.close();
+                }
+            }
+            // containers that aren't rendered, e.g. <defs>, & unsupported elements
+            _ => return None,
+        }
+        Some(path)
+    }
+
+    fn add_shape(&mut self, path: Path, context: &Context) {
+        if path.is_empty() || !context.visible {
+            return;
+        }
+        let fill = context.fill.map(|color| Fill {
+            color: with_opacity(color, context.fill_opacity * context.opacity),
+            rule: context.fill_rule,
+        });
+        let scale = context.transform.mean_scale();
+        let stroke = context
+            .stroke
+            .filter(|_| context.stroke_width > 0.)
+            .map(|color| Stroke {
+                color: with_opacity(color, context.stroke_opacity * context.opacity),
+                width: context.stroke_width * scale,
+            });
+        if fill.is_none() && stroke.is_none() {
+            return;
+        }
+        self.shapes.push(Shape {
+            path: path.transform(&context.transform),
+            fill,
+            stroke,
+        });
+    }
+}
+
+/// The value of a property set by the `style` attribute or by its
+/// presentation attribute, the `style` attribute taking precedence
+fn property<'a>(element: &'a Element, name: &str) -> Option<&'a str> {
+    let from_style = element.attribute("style").and_then(|style| {
+        style
+            .split(';')
+            .filter_map(|declaration| {
+                let colon = declaration.find(':')?;
+                let (property, value) = (&declaration[..colon], &declaration[colon + 1..]);
+                if property.trim() == name {
+                    Some(value.trim())
+                } else {
+                    None
+                }
+            })
+            .last()
+    });
+    from_style.or_else(|| element.attribute(name))
+}
+
+fn apply_presentation(element: &Element, parent: &Context) -> Context {
+    let mut context = parent.clone();
+    let property = |name| property(element, name).map(str::trim);
+    let number = |name| property(name).and_then(|value| value.parse::<f32>().ok());
+    let opacity = |name| number(name).map(|value: f32| value.max(0.).min(1.));
+
+    if let Some(transform) = element.attribute("transform").and_then(Transform::parse) {
+        context.transform = context.transform.multiply(&transform);
+    }
+    if let Some(color) = property("color").and_then(parse_color) {
+        context.color = color;
+    }
+    if let Some(fill) = property("fill").and_then(|value| parse_paint(value, &context)) {
+        context.fill = fill;
+    }
+    if let Some(stroke) = property("stroke").and_then(|value| parse_paint(value, &context)) {
+        context.stroke = stroke;
+    }
+    match property("fill-rule") {
+        Some("evenodd") => context.fill_rule = FillRule::EvenOdd,
+        Some("nonzero") => context.fill_rule = FillRule::NonZero,
+        _ => {}
+    }
+    if let Some(width) = property("stroke-width").and_then(|value| parse_length(value, 0.)) {
+        context.stroke_width = width;
+    }
+    if let Some(value) = opacity("fill-opacity") {
+        context.fill_opacity = value;
+    }
+    if let Some(value) = opacity("stroke-opacity") {
+        context.stroke_opacity = value;
+    }
+    if let Some(value) = opacity("opacity") {
+        context.opacity *= value;
+    }
+    match property("visibility") {
+        Some("hidden") | Some("collapse") => context.visible = false,
+        Some("visible") => context.visible = true,
+        _ => {}
+    }
+    context
+}
+
+/// Parse a paint, `Some(None)` being `none`. Paint servers like gradients
+/// aren't supported & use their fallback color.
+fn parse_paint(value: &str, context: &Context) -> Option<Option<Color>> {
+    let value = value.trim();
+    if value.starts_with("url(") {
+        let fallback = value[value.find(')')? + 1..].trim();
+        if fallback.is_empty() {
+            return Some(None);
+        }
+        return parse_paint(fallback, context);
+    }
+    match value {
+        "none" => Some(None),
+        "currentColor" => Some(Some(context.color)),
+        _ => parse_color(value).map(Some),
+    }
+}
+
+fn with_opacity(color: Color, opacity: f32) -> Color {
+    Color {
+        a: (color.a as f32 * opacity).round() as u8,
+        ..color
+    }
+}
+
+fn parse_view_box(value: &str) -> Option<ViewBox> {
+    match parse_numbers(value)?.as_slice() {
+        [x, y, width, height] if *width > 0. && *height > 0. => Some(ViewBox {
+            x: *x,
+            y: *y,
+            width: *width,
+            height: *height,
+        }),
+        _ => None,
+    }
+}
+
+fn parse_aspect_ratio(value: &str) -> Option<AspectRatio> {
+    let mut words = value.split_whitespace();
+    let align = match words.next()? {
+        "none" => None,
+        align if align.len() == 8 => {
+            let position = |value: &str| match value {
+                "Min" => Some(0.),
+                "Mid" => Some(0.5),
+                "Max" => Some(1.),
+                _ => None,
+            };
+            if !align.starts_with('x') || &align[4..5] != "Y" {
+                return None;
+            }
+            Some((position(&align[1..4])?, position(&align[5..8])?))
+        }
+        _ => return None,
+    };
+    let slice = match words.next() {
+        None | Some("meet") => false,
+        Some("slice") => true,
+        Some(_) => return None,
+    };
+    Some(AspectRatio { align, slice })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pixel(pixels: &[u8], width: u32, x: u32, y: u32) -> &[u8] {
+        let index = ((y * width + x) * 4) as usize;
+        &pixels[index..index + 4]
+    }
+
+    #[test]
+    fn intrinsic_sizes() {
+        let image = SvgImage::parse(br#"<svg width="40" height="20"></svg>"#).unwrap();
+        assert_eq!(image.intrinsic_width(), Some(40.));
+        assert_eq!(image.aspect_ratio(), Some(2.));
+
+        let image = SvgImage::parse(br#"<svg width="30" viewBox="0 0 10 20"></svg>"#).unwrap();
+        assert_eq!(image.intrinsic_height(), Some(60.));
+
+        let image = SvgImage::parse(br#"<svg width="100%" viewBox="0 0 4 3"/>"#).unwrap();
+        assert_eq!(image.intrinsic_width(), None);
+        assert_eq!(image.aspect_ratio(), Some(4. / 3.));
+
+        assert!(SvgImage::parse(b"<html></html>").is_err());
+    }
+
+    #[test]
+    fn build_shapes_with_inherited_styles() {
+        let image = SvgImage::parse(
+            br#"<svg viewBox="0 0 10 10">
+                <defs><rect width="5" height="5" /></defs>
+                <g fill="red" stroke="blue" transform="translate(1 1)" opacity="0.5">
+                    <rect width="2" height="2" style="fill: #00ff00; stroke: none" />
+                    <circle r="1" fill-opacity="0.5" stroke-width="2" />
+                    <line x2="5" y2="5" display="none" />
+                </g>
+            </svg>"#,
+        )
+        .unwrap();
+
+        let shapes = image.shapes();
+        assert_eq!(shapes.len(), 2);
+        assert_eq!(
+            shapes[0].fill,
+            Some(Fill {
+                color: Color::new(0, 255, 0, 128),
+                rule: FillRule::NonZero
+            })
+        );
+        assert_eq!(shapes[0].stroke, None);
+        assert_eq!(
+            shapes[1].fill.as_ref().unwrap().color,
+            Color::new(255, 0, 0, 64)
+        );
+        assert_eq!(
+            shapes[1].stroke,
+            Some(Stroke {
+                color: Color::new(0, 0, 255, 128),
+                width: 2.
+            })
+        );
+    }
+
+    #[test]
+    fn rasterize_at_any_size() {
+        let image = SvgImage::parse(
+            br#"<svg xmlns="http://www.w3.org/2000/svg" viewBox="0 0 2 1">
+                <rect width="1" height="1" fill="red" />
+                <rect x="1" width="1" height="1" fill="blue" />
+            </svg>"#,
+        )
+        .unwrap();
+
+        let pixels = image.rasterize(20, 10);
+        assert_eq!(pixels.len(), 20 * 10 * 4);
+        assert_eq!(pixel(&pixels, 20, 2, 5), &[255, 0, 0, 255]);
+        assert_eq!(pixel(&pixels, 20, 17, 5), &[0, 0, 255, 255]);
+
+        // the view box is centered in a taller viewport
+        let pixels = image.rasterize(20, 20);
+        assert_eq!(pixel(&pixels, 20, 2, 2), &[0, 0, 0, 0]);
+        assert_eq!(pixel(&pixels, 20, 2, 10), &[255, 0, 0, 255]);
+    }
+}
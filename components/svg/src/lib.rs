@@ -0,0 +1,16 @@
+/// A minimal SVG renderer for vector images: the basic shapes & paths
+/// filled & stroked with solid colors are rasterized at any size. The
+/// rasterizer is also used to paint the bitmaps of canvas elements.
+mod color;
+mod image;
+mod number;
+mod path;
+mod raster;
+mod transform;
+pub mod xml;
+
+pub use color::{parse_color, Color};
+pub use image::{Fill, Shape, Stroke, SvgImage, ViewBox};
+pub use path::{Path, Point, Polyline, Segment};
+pub use raster::{stroke_polygons, Canvas, FillRule};
+pub use transform::Transform;
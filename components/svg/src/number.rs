@@ -0,0 +1,149 @@
+/// Reads the numbers of attributes like `points` & `viewBox`, which may be
+/// separated by whitespace, commas or nothing at all as in `1-2.5.5`
+pub struct NumberScanner<'a> {
+    input: &'a [u8],
+    index: usize,
+}
+
+impl<'a> NumberScanner<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self {
+            input: input.as_bytes(),
+            index: 0,
+        }
+    }
+
+    pub fn skip_separators(&mut self) {
+        while let Some(byte) = self.input.get(self.index) {
+            if byte.is_ascii_whitespace() || *byte == b',' {
+                self.index += 1;
+            } else {
+                break;
+            }
+        }
+    }
+
+    pub fn is_at_end(&mut self) -> bool {
+        self.skip_separators();
+        self.index >= self.input.len()
+    }
+
+    pub fn peek(&mut self) -> Option<u8> {
+        self.skip_separators();
+        self.input.get(self.index).copied()
+    }
+
+    pub fn advance(&mut self) {
+        self.index += 1;
+    }
+
+    pub fn next_number(&mut self) -> Option<f32> {
+        self.skip_separators();
+        let start = self.index;
+        let mut index = self.index;
+        let byte_at = |index: usize| self.input.get(index).copied();
+
+        if matches!(byte_at(index), Some(b'+') | Some(b'-')) {
+            index += 1;
+        }
+        let mut has_digits = false;
+        while matches!(byte_at(index), Some(b'0'..=b'9')) {
+            index += 1;
+            has_digits = true;
+        }
+        if byte_at(index) == Some(b'.') {
+            index += 1;
+            while matches!(byte_at(index), Some(b'0'..=b'9')) {
+                index += 1;
+                has_digits = true;
+            }
+        }
+        if !has_digits {
+            return None;
+        }
+        if matches!(byte_at(index), Some(b'e') | Some(b'E')) {
+            let mut exponent = index + 1;
+            if matches!(byte_at(exponent), Some(b'+') | Some(b'-')) {
+                exponent += 1;
+            }
+            if matches!(byte_at(exponent), Some(b'0'..=b'9')) {
+                index = exponent;
+                while matches!(byte_at(index), Some(b'0'..=b'9')) {
+                    index += 1;
+                }
+            }
+        }
+
+        self.index = index;
+        std::str::from_utf8(&self.input[start..index])
+            .ok()?
+            .parse()
+            .ok()
+    }
+
+    /// The flags of arcs are a single digit, e.g. `a1 1 0 011 1`
+    pub fn next_flag(&mut self) -> Option<bool> {
+        let flag = match self.peek()? {
+            b'0' => false,
+            b'1' => true,
+            _ => return None,
+        };
+        self.advance();
+        Some(flag)
+    }
+}
+
+pub fn parse_numbers(value: &str) -> Option<Vec<f32>> {
+    let mut scanner = NumberScanner::new(value);
+    let mut numbers = Vec::new();
+    while !scanner.is_at_end() {
+        numbers.push(scanner.next_number()?);
+    }
+    Some(numbers)
+}
+
+/// Parse a length in user units, resolving percentages against a
+/// reference length, e.g. the width of the viewport
+pub fn parse_length(value: &str, reference: f32) -> Option<f32> {
+    let value = value.trim();
+    let unit_start = value
+        .rfind(|ch: char| ch.is_ascii_digit() || ch == '.')
+        .map_or(0, |index| index + 1);
+    let (number, unit) = value.split_at(unit_start);
+    let number = number.parse::<f32>().ok()?;
+    let scale = match unit.to_ascii_lowercase().as_str() {
+        "" | "px" => 1.,
+        "%" => reference / 100.,
+        "pt" => 4. / 3.,
+        "pc" => 16.,
+        "in" => 96.,
+        "cm" => 96. / 2.54,
+        "mm" => 96. / 25.4,
+        // there's no font size in images
+        "em" => 16.,
+        _ => return None,
+    };
+    Some(number * scale)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn scan_numbers() {
+        assert_eq!(
+            parse_numbers("1-2.5.5, 3e2 ,-.5"),
+            Some(vec![1., -2.5, 0.5, 300., -0.5])
+        );
+        assert_eq!(parse_numbers("1 x"), None);
+    }
+
+    #[test]
+    fn parse_lengths() {
+        assert_eq!(parse_length("12", 0.), Some(12.));
+        assert_eq!(parse_length("1in", 0.), Some(96.));
+        assert_eq!(parse_length("50%", 300.), Some(150.));
+        assert_eq!(parse_length("auto", 0.), None);
+    }
+}
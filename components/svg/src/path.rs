@@ -0,0 +1,466 @@
+use super::number::NumberScanner;
+use super::transform::Transform;
+use std::f32::consts::PI;
+
+pub type Point = (f32, f32);
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Segment {
+    MoveTo(Point),
+    LineTo(Point),
+    QuadTo(Point, Point),
+    CubicTo(Point, Point, Point),
+    Close,
+}
+
+/// The outline of a shape, in absolute coordinates
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct Path {
+    pub segments: Vec<Segment>,
+}
+
+/// A flattened subpath
+#[derive(Debug, Clone, PartialEq)]
+pub struct Polyline {
+    pub points: Vec<Point>,
+    pub closed: bool,
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    pub fn move_to(&mut self, x: f32, y: f32) {
+        self.segments.push(Segment::MoveTo((x, y)));
+    }
+
+    pub fn line_to(&mut self, x: f32, y: f32) {
+        self.segments.push(Segment::LineTo((x, y)));
+    }
+
+    pub fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.segments.push(Segment::QuadTo((x1, y1), (x, y)));
+    }
+
+    pub fn cubic_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.segments
+            .push(Segment::CubicTo((x1, y1), (x2, y2), (x, y)));
+    }
+
+    pub fn close(&mut self) {
+        self.segments.push(Segment::Close);
+    }
+
+    /// An elliptical arc from a point to another, converted to cubic curves
+    /// https://www.w3.org/TR/SVG11/implnote.html#ArcImplementationNotes
+    #[allow(clippy::too_many_arguments)]
+    pub fn arc_to(
+        &mut self,
+        from: Point,
+        rx: f32,
+        ry: f32,
+        x_axis_rotation: f32,
+        large_arc: bool,
+        sweep: bool,
+        to: Point,
+    ) {
+        let (mut rx, mut ry) = (rx.abs(), ry.abs());
+        if from == to {
+            return;
+        }
+        if rx == 0. || ry == 0. {
+            return self.line_to(to.0, to.1);
+        }
+
+        let (sin, cos) = x_axis_rotation.to_radians().sin_cos();
+        let dx = (from.0 - to.0) / 2.;
+        let dy = (from.1 - to.1) / 2.;
+        let x1 = cos * dx + sin * dy;
+        let y1 = -sin * dx + cos * dy;
+
+        // scale up radii that are too small to reach the end point
+        let lambda = (x1 * x1) / (rx * rx) + (y1 * y1) / (ry * ry);
+        if lambda > 1. {
+            rx *= lambda.sqrt();
+            ry *= lambda.sqrt();
+        }
+
+        let numerator = rx * rx * ry * ry - rx * rx * y1 * y1 - ry * ry * x1 * x1;
+        let denominator = rx * rx * y1 * y1 + ry * ry * x1 * x1;
+        let mut coefficient = (numerator / denominator).max(0.).sqrt();
+        if large_arc == sweep {
+            coefficient = -coefficient;
+        }
+        let cx1 = coefficient * rx * y1 / ry;
+        let cy1 = -coefficient * ry * x1 / rx;
+        let cx = cos * cx1 - sin * cy1 + (from.0 + to.0) / 2.;
+        let cy = sin * cx1 + cos * cy1 + (from.1 + to.1) / 2.;
+
+        let angle = |ux: f32, uy: f32, vx: f32, vy: f32| {
+            let sign = if ux * vy - uy * vx < 0. { -1. } else { 1. };
+            let dot = ux * vx + uy * vy;
+            let length = (ux * ux + uy * uy).sqrt() * (vx * vx + vy * vy).sqrt();
+            sign * (dot / length).max(-1.).min(1.).acos()
+        };
+        let start_x = (x1 - cx1) / rx;
+        let start_y = (y1 - cy1) / ry;
+        let start = angle(1., 0., start_x, start_y);
+        let mut delta = angle(start_x, start_y, (-x1 - cx1) / rx, (-y1 - cy1) / ry);
+        if !sweep && delta > 0. {
+            delta -= 2. * PI;
+        } else if sweep && delta < 0. {
+            delta += 2. * PI;
+        }
+
+        // each curve spans at most a quarter of the ellipse
+        let count = (delta.abs() / (PI / 2.)).ceil().max(1.) as usize;
+        let step = delta / count as f32;
+        let k = 4. / 3. * (step / 4.).tan();
+        let point = |theta: f32| {
+            let (sin_t, cos_t) = theta.sin_cos();
+            let x = rx * cos_t;
+            let y = ry * sin_t;
+            (cos * x - sin * y + cx, sin * x + cos * y + cy)
+        };
+        let derivative = |theta: f32| {
+            let (sin_t, cos_t) = theta.sin_cos();
+            let x = -rx * sin_t;
+            let y = ry * cos_t;
+            (cos * x - sin * y, sin * x + cos * y)
+        };
+
+        let mut theta = start;
+        for index in 0..count {
+            let next = theta + step;
+            let p0 = point(theta);
+            let d0 = derivative(theta);
+            let d1 = derivative(next);
+            // land exactly on the end point
+            let p1 = if index + 1 == count { to } else { point(next) };
+            self.cubic_to(
+                p0.0 + k * d0.0,
+                p0.1 + k * d0.1,
+                p1.0 - k * d1.0,
+                p1.1 - k * d1.1,
+                p1.0,
+                p1.1,
+            );
+            theta = next;
+        }
+    }
+
+    /// Parse the path data of the `d` attribute. The path is rendered up
+    /// to the first error, like the browsers do.
+    /// https://www.w3.org/TR/SVG11/paths.html#PathDataBNF
+    pub fn parse(data: &str) -> Path {
+        let mut path = Path::new();
+        let mut scanner = NumberScanner::new(data);
+        let mut current = (0., 0.);
+        let mut subpath_start = (0., 0.);
+        // the second control point of the previous curve, for the shorthands
+        let mut last_cubic_control: Option<Point> = None;
+        let mut last_quad_control: Option<Point> = None;
+        let mut command: Option<u8> = None;
+
+        loop {
+            match scanner.peek() {
+                None => break,
+                Some(byte) if byte.is_ascii_alphabetic() => {
+                    scanner.advance();
+                    command = Some(byte);
+                }
+                // numbers repeat the previous command
+                Some(_) if command.is_some() => {}
+                Some(_) => break,
+            }
+            let name = match command {
+                Some(name) => name,
+                None => break,
+            };
+            if path.is_empty() && !matches!(name, b'M' | b'm') {
+                break;
+            }
+            let relative = name.is_ascii_lowercase();
+            let offset = if relative { current } else { (0., 0.) };
+
+            let read_point = |scanner: &mut NumberScanner| -> Option<Point> {
+                let x = scanner.next_number()?;
+                let y = scanner.next_number()?;
+                Some((x + offset.0, y + offset.1))
+            };
+
+            let parsed = match name.to_ascii_uppercase() {
+                b'M' => read_point(&mut scanner).map(|point| {
+                    path.move_to(point.0, point.1);
+                    subpath_start = point;
+                    current = point;
+                    // the coordinates following a move are lines
+                    command = Some(if relative { b'l' } else { b'L' });
+                    last_cubic_control = None;
+                    last_quad_control = None;
+                }),
+                b'L' => read_point(&mut scanner).map(|point| {
+                    path.line_to(point.0, point.1);
+                    current = point;
+                    last_cubic_control = None;
+                    last_quad_control = None;
+                }),
+                b'H' => scanner.next_number().map(|x| {
+                    current.0 = x + offset.0;
+                    path.line_to(current.0, current.1);
+                    last_cubic_control = None;
+                    last_quad_control = None;
+                }),
+                b'V' => scanner.next_number().map(|y| {
+                    current.1 = y + offset.1;
+                    path.line_to(current.0, current.1);
+                    last_cubic_control = None;
+                    last_quad_control = None;
+                }),
+                b'C' => (|| {
+                    let c1 = read_point(&mut scanner)?;
+                    let c2 = read_point(&mut scanner)?;
+                    let end = read_point(&mut scanner)?;
+                    Some((c1, c2, end))
+                })()
+                .map(|(c1, c2, end)| {
+                    path.cubic_to(c1.0, c1.1, c2.0, c2.1, end.0, end.1);
+                    current = end;
+                    last_cubic_control = Some(c2);
+                    last_quad_control = None;
+                }),
+                b'S' => (|| {
+                    let c2 = read_point(&mut scanner)?;
+                    let end = read_point(&mut scanner)?;
+                    Some((c2, end))
+                })()
+                .map(|(c2, end)| {
+                    let c1 = reflect(last_cubic_control, current);
+                    path.cubic_to(c1.0, c1.1, c2.0, c2.1, end.0, end.1);
+                    current = end;
+                    last_cubic_control = Some(c2);
+                    last_quad_control = None;
+                }),
+                b'Q' => (|| {
+                    let control = read_point(&mut scanner)?;
+                    let end = read_point(&mut scanner)?;
+                    Some((control, end))
+                })()
+                .map(|(control, end)| {
+                    path.quad_to(control.0, control.1, end.0, end.1);
+                    current = end;
+                    last_quad_control = Some(control);
+                    last_cubic_control = None;
+                }),
+                b'T' => read_point(&mut scanner).map(|end| {
+                    let control = reflect(last_quad_control, current);
+                    path.quad_to(control.0, control.1, end.0, end.1);
+                    current = end;
+                    last_quad_control = Some(control);
+                    last_cubic_control = None;
+                }),
+                b'A' => (|| {
+                    let rx = scanner.next_number()?;
+                    let ry = scanner.next_number()?;
+                    let rotation = scanner.next_number()?;
+                    let large_arc = scanner.next_flag()?;
+                    let sweep = scanner.next_flag()?;
+                    let end = read_point(&mut scanner)?;
+                    Some((rx, ry, rotation, large_arc, sweep, end))
+                })()
+                .map(|(rx, ry, rotation, large_arc, sweep, end)| {
+                    path.arc_to(current, rx, ry, rotation, large_arc, sweep, end);
+                    current = end;
+                    last_cubic_control = None;
+                    last_quad_control = None;
+                }),
+                b'Z' => {
+                    path.close();
+                    current = subpath_start;
+                    last_cubic_control = None;
+                    last_quad_control = None;
+                    // a close command doesn't take any number
+                    command = None;
+                    Some(())
+                }
+                _ => None,
+            };
+            if parsed.is_none() {
+                break;
+            }
+        }
+        path
+    }
+
+    pub fn transform(&self, transform: &Transform) -> Path {
+        let map = |(x, y): Point| transform.apply(x, y);
+        Path {
+            segments: self
+                .segments
+                .iter()
+                .map(|segment| match segment {
+                    Segment::MoveTo(point) => Segment::MoveTo(map(*point)),
+                    Segment::LineTo(point) => Segment::LineTo(map(*point)),
+                    Segment::QuadTo(control, end) => Segment::QuadTo(map(*control), map(*end)),
+                    Segment::CubicTo(c1, c2, end) => {
+                        Segment::CubicTo(map(*c1), map(*c2), map(*end))
+                    }
+                    Segment::Close => Segment::Close,
+                })
+                .collect(),
+        }
+    }
+
+    /// Approximate the curves with lines no further than a tolerance from them
+    pub fn flatten(&self, tolerance: f32) -> Vec<Polyline> {
+        let mut polylines = Vec::new();
+        let mut points: Vec<Point> = Vec::new();
+        let finish = |points: &mut Vec<Point>, polylines: &mut Vec<Polyline>, closed: bool| {
+            if points.len() > 1 {
+                polylines.push(Polyline {
+                    points: std::mem::take(points),
+                    closed,
+                });
+            } else {
+                points.clear();
+            }
+        };
+
+        for segment in &self.segments {
+            let current = points.last().copied().unwrap_or((0., 0.));
+            match segment {
+                Segment::MoveTo(point) => {
+                    finish(&mut points, &mut polylines, false);
+                    points.push(*point);
+                }
+                Segment::LineTo(point) => {
+                    if points.is_empty() {
+                        points.push(current);
+                    }
+                    points.push(*point);
+                }
+                Segment::QuadTo(control, end) => {
+                    if points.is_empty() {
+                        points.push(current);
+                    }
+                    let count =
+                        segment_count(distance_from_line(current, *control, *end), tolerance);
+                    for index in 1..=count {
+                        let t = index as f32 / count as f32;
+                        let mt = 1. - t;
+                        points.push((
+                            mt * mt * current.0 + 2. * mt * t * control.0 + t * t * end.0,
+                            mt * mt * current.1 + 2. * mt * t * control.1 + t * t * end.1,
+                        ));
+                    }
+                }
+                Segment::CubicTo(c1, c2, end) => {
+                    if points.is_empty() {
+                        points.push(current);
+                    }
+                    let deviation = distance_from_line(current, *c1, *c2)
+                        .max(distance_from_line(*c1, *c2, *end));
+                    let count = segment_count(deviation * 1.5, tolerance);
+                    for index in 1..=count {
+                        let t = index as f32 / count as f32;
+                        let mt = 1. - t;
+                        let (a, b, c, d) =
+                            (mt * mt * mt, 3. * mt * mt * t, 3. * mt * t * t, t * t * t);
+                        points.push((
+                            a * current.0 + b * c1.0 + c * c2.0 + d * end.0,
+                            a * current.1 + b * c1.1 + c * c2.1 + d * end.1,
+                        ));
+                    }
+                }
+                Segment::Close => {
+                    let start = points.first().copied();
+                    finish(&mut points, &mut polylines, true);
+                    // the next segment starts where the subpath started
+                    if let Some(start) = start {
+                        points.push(start);
+                    }
+                }
+            }
+        }
+        finish(&mut points, &mut polylines, false);
+        polylines
+    }
+}
+
+/// The first control point of a shorthand curve, the reflection of the
+/// previous control point around the current point
+fn reflect(control: Option<Point>, current: Point) -> Point {
+    match control {
+        Some(control) => (2. * current.0 - control.0, 2. * current.1 - control.1),
+        None => current,
+    }
+}
+
+/// How far the middle point is from the middle of the two others, which
+/// bounds how far a curve is from its chord
+fn distance_from_line(start: Point, middle: Point, end: Point) -> f32 {
+    let x = start.0 - 2. * middle.0 + end.0;
+    let y = start.1 - 2. * middle.1 + end.1;
+    (x * x + y * y).sqrt()
+}
+
+fn segment_count(deviation: f32, tolerance: f32) -> usize {
+    ((deviation / (4. * tolerance)).sqrt().ceil() as usize)
+        .max(1)
+        .min(256)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_path_data() {
+        let path = Path::parse("M10 10h5v5H10zm1,1 l2-2 2 2");
+        assert_eq!(
+            path.segments,
+            vec![
+                Segment::MoveTo((10., 10.)),
+                Segment::LineTo((15., 10.)),
+                Segment::LineTo((15., 15.)),
+                Segment::LineTo((10., 15.)),
+                Segment::Close,
+                Segment::MoveTo((11., 11.)),
+                Segment::LineTo((13., 9.)),
+                Segment::LineTo((15., 11.)),
+            ]
+        );
+
+        let path = Path::parse("M0 0 C 1 1 2 1 3 0 s 2 -1 3 0 L");
+        assert_eq!(
+            path.segments[2],
+            Segment::CubicTo((4., -1.), (5., -1.), (6., 0.))
+        );
+        assert_eq!(path.segments.len(), 3);
+
+        assert!(Path::parse("L 1 1").is_empty());
+    }
+
+    #[test]
+    fn flatten_arcs() {
+        let mut path = Path::new();
+        path.move_to(0., 10.);
+        path.arc_to((0., 10.), 10., 10., 0., false, true, (20., 10.));
+        let polylines = path.flatten(0.1);
+
+        assert_eq!(polylines.len(), 1);
+        let points = &polylines[0].points;
+        assert_eq!(points.last(), Some(&(20., 10.)));
+        // a half circle centered at (10, 10) above the chord
+        for (x, y) in points {
+            let radius = ((x - 10.).powi(2) + (y - 10.).powi(2)).sqrt();
+            assert!((radius - 10.).abs() < 0.2, "{} is off the arc", radius);
+            assert!(*y <= 10.001);
+        }
+    }
+}
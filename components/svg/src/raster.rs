@@ -0,0 +1,380 @@
+/// A scanline rasterizer filling polygons with antialiasing. Each row of
+/// pixels is sampled on a few horizontal lines & the spans inside the
+/// polygons cover the pixels by the exact horizontal area they overlap.
+use super::color::Color;
+use super::path::{Point, Polyline};
+
+/// The number of lines sampled for each row of pixels
+const SUBSAMPLES: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FillRule {
+    NonZero,
+    EvenOdd,
+}
+
+struct Edge {
+    x0: f32,
+    y0: f32,
+    x1: f32,
+    y1: f32,
+    /// Whether the edge goes down, in the order of the points
+    winding: i32,
+}
+
+/// A pixel buffer with premultiplied colors
+pub struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<[f32; 4]>,
+}
+
+impl Canvas {
+    pub fn new(width: u32, height: u32) -> Self {
+        let (width, height) = (width as usize, height as usize);
+        Self {
+            width,
+            height,
+            pixels: vec![[0.; 4]; width * height],
+        }
+    }
+
+    /// Fill the area inside polygons, the polylines being closed
+    pub fn fill(&mut self, polygons: &[Polyline], rule: FillRule, color: Color) {
+        if color.a == 0 || self.width == 0 || self.height == 0 {
+            return;
+        }
+        let mut edges = Vec::new();
+        for polygon in polygons {
+            let points = &polygon.points;
+            for index in 0..points.len() {
+                let (x0, y0) = points[index];
+                let (x1, y1) = points[(index + 1) % points.len()];
+                // only the exactly horizontal edges never cross a scanline,
+                // the nearly horizontal ones still cover some pixels
+                #[allow(clippy::float_cmp)]
+                let is_horizontal = y0 == y1;
+                if is_horizontal
+                    || !(x0.is_finite() && y0.is_finite() && x1.is_finite() && y1.is_finite())
+                {
+                    continue;
+                }
+                edges.push(if y0 < y1 {
+                    Edge {
+                        x0,
+                        y0,
+                        x1,
+                        y1,
+                        winding: 1,
+                    }
+                } else {
+                    Edge {
+                        x0: x1,
+                        y0: y1,
+                        x1: x0,
+                        y1: y0,
+                        winding: -1,
+                    }
+                });
+            }
+        }
+        if edges.is_empty() {
+            return;
+        }
+
+        let top = edges
+            .iter()
+            .map(|edge| edge.y0)
+            .fold(f32::INFINITY, f32::min);
+        let bottom = edges
+            .iter()
+            .map(|edge| edge.y1)
+            .fold(f32::NEG_INFINITY, f32::max);
+        let first_row = top.floor().max(0.) as usize;
+        let last_row = (bottom.ceil().max(0.) as usize).min(self.height);
+
+        let weight = 1. / SUBSAMPLES as f32;
+        let mut coverage = vec![0f32; self.width];
+        let mut crossings: Vec<(f32, i32)> = Vec::new();
+        for row in first_row..last_row {
+            coverage.iter_mut().for_each(|value| *value = 0.);
+            for sample in 0..SUBSAMPLES {
+                let y = row as f32 + (sample as f32 + 0.5) * weight;
+                crossings.clear();
+                for edge in &edges {
+                    if y >= edge.y0 && y < edge.y1 {
+                        let t = (y - edge.y0) / (edge.y1 - edge.y0);
+                        crossings.push((edge.x0 + t * (edge.x1 - edge.x0), edge.winding));
+                    }
+                }
+                crossings
+                    .sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+
+                let mut winding = 0;
+                for pair in crossings.windows(2) {
+                    winding += pair[0].1;
+                    let inside = match rule {
+                        FillRule::NonZero => winding != 0,
+                        FillRule::EvenOdd => winding % 2 != 0,
+                    };
+                    if inside {
+                        add_span(&mut coverage, pair[0].0, pair[1].0, weight);
+                    }
+                }
+            }
+            self.composite_row(row, &coverage, color);
+        }
+    }
+
+    fn composite_row(&mut self, row: usize, coverage: &[f32], color: Color) {
+        let alpha = color.a as f32 / 255.;
+        let source = [
+            color.r as f32 / 255. * alpha,
+            color.g as f32 / 255. * alpha,
+            color.b as f32 / 255. * alpha,
+            alpha,
+        ];
+        let pixels = &mut self.pixels[row * self.width..(row + 1) * self.width];
+        for (pixel, coverage) in pixels.iter_mut().zip(coverage) {
+            let coverage = coverage.min(1.);
+            if coverage <= 0. {
+                continue;
+            }
+            let source_alpha = source[3] * coverage;
+            for channel in 0..4 {
+                pixel[channel] = source[channel] * coverage + pixel[channel] * (1. - source_alpha);
+            }
+        }
+    }
+
+    /// Draw RGBA pixels with straight alpha stretched over a rect. The
+    /// pixels are sampled without filtering.
+    pub fn draw_image(
+        &mut self,
+        pixels: &[u8],
+        width: u32,
+        height: u32,
+        rect: (f32, f32, f32, f32),
+    ) {
+        let (x, y, rect_width, rect_height) = rect;
+        if width == 0 || height == 0 || rect_width <= 0. || rect_height <= 0. {
+            return;
+        }
+        let first_row = y.round().max(0.) as usize;
+        let last_row = ((y + rect_height).round().max(0.) as usize).min(self.height);
+        let first_column = x.round().max(0.) as usize;
+        let last_column = ((x + rect_width).round().max(0.) as usize).min(self.width);
+        for row in first_row..last_row {
+            let source_y = ((row as f32 + 0.5 - y) / rect_height * height as f32) as usize;
+            for column in first_column..last_column {
+                let source_x = ((column as f32 + 0.5 - x) / rect_width * width as f32) as usize;
+                let index = (source_y.min(height as usize - 1) * width as usize
+                    + source_x.min(width as usize - 1))
+                    * 4;
+                let source = &pixels[index..index + 4];
+                let alpha = source[3] as f32 / 255.;
+                let pixel = &mut self.pixels[row * self.width + column];
+                for channel in 0..3 {
+                    pixel[channel] =
+                        source[channel] as f32 / 255. * alpha + pixel[channel] * (1. - alpha);
+                }
+                pixel[3] = alpha + pixel[3] * (1. - alpha);
+            }
+        }
+    }
+
+    /// Make the pixels inside a rect transparent
+    pub fn clear_rect(&mut self, rect: (f32, f32, f32, f32)) {
+        let (x, y, width, height) = rect;
+        let first_row = y.round().max(0.) as usize;
+        let last_row = ((y + height).round().max(0.) as usize).min(self.height);
+        let first_column = x.round().max(0.) as usize;
+        let last_column = ((x + width).round().max(0.) as usize).min(self.width);
+        for row in first_row..last_row {
+            for column in first_column..last_column {
+                self.pixels[row * self.width + column] = [0.; 4];
+            }
+        }
+    }
+
+    /// The pixels as RGBA bytes with straight alpha, row by row
+    pub fn to_rgba(&self) -> Vec<u8> {
+        let mut bytes = Vec::with_capacity(self.pixels.len() * 4);
+        for &[r, g, b, a] in &self.pixels {
+            let to_byte = |value: f32| (value * 255.).round().max(0.).min(255.) as u8;
+            if a <= 0. {
+                bytes.extend_from_slice(&[0, 0, 0, 0]);
+            } else {
+                bytes.extend_from_slice(&[
+                    to_byte(r / a),
+                    to_byte(g / a),
+                    to_byte(b / a),
+                    to_byte(a),
+                ]);
+            }
+        }
+        bytes
+    }
+}
+
+/// Cover the pixels of a row between two positions
+fn add_span(coverage: &mut [f32], start: f32, end: f32, weight: f32) {
+    let width = coverage.len() as f32;
+    let (start, end) = (start.max(0.), end.min(width));
+    if start >= end {
+        return;
+    }
+    let (first, last) = (start.floor() as usize, end.floor() as usize);
+    if first == last {
+        coverage[first] += (end - start) * weight;
+        return;
+    }
+    coverage[first] += (first as f32 + 1. - start) * weight;
+    for value in &mut coverage[first + 1..last] {
+        *value += weight;
+    }
+    if last < coverage.len() {
+        coverage[last] += (end - last as f32) * weight;
+    }
+}
+
+/// The polygons covering the outline of polylines with a width. The
+/// segments are joined with round joins & the polygons all wind the same
+/// way, so filling them with the non-zero rule paints their union.
+pub fn stroke_polygons(polylines: &[Polyline], width: f32) -> Vec<Polyline> {
+    let half = width / 2.;
+    let mut polygons = Vec::new();
+    for polyline in polylines {
+        let points = &polyline.points;
+        let segment_count = if polyline.closed {
+            points.len()
+        } else {
+            points.len() - 1
+        };
+        for index in 0..segment_count {
+            let (x0, y0) = points[index];
+            let (x1, y1) = points[(index + 1) % points.len()];
+            let length = ((x1 - x0).powi(2) + (y1 - y0).powi(2)).sqrt();
+            if length == 0. {
+                continue;
+            }
+            let (nx, ny) = (-(y1 - y0) / length * half, (x1 - x0) / length * half);
+            polygons.push(oriented(vec![
+                (x0 + nx, y0 + ny),
+                (x1 + nx, y1 + ny),
+                (x1 - nx, y1 - ny),
+                (x0 - nx, y0 - ny),
+            ]));
+        }
+
+        let joins = if polyline.closed {
+            &points[..]
+        } else if points.len() > 2 {
+            &points[1..points.len() - 1]
+        } else {
+            &[]
+        };
+        for point in joins {
+            polygons.push(circle(*point, half));
+        }
+    }
+    polygons
+}
+
+fn circle((cx, cy): Point, radius: f32) -> Polyline {
+    let count = ((radius * 4.).ceil() as usize).max(8).min(64);
+    oriented(
+        (0..count)
+            .map(|index| {
+                let angle = index as f32 / count as f32 * std::f32::consts::PI * 2.;
+                (cx + radius * angle.cos(), cy + radius * angle.sin())
+            })
+            .collect(),
+    )
+}
+
+/// Make a polygon wind clockwise
+fn oriented(mut points: Vec<Point>) -> Polyline {
+    let area: f32 = (0..points.len())
+        .map(|index| {
+            let (x0, y0) = points[index];
+            let (x1, y1) = points[(index + 1) % points.len()];
+            x0 * y1 - x1 * y0
+        })
+        .sum();
+    if area < 0. {
+        points.reverse();
+    }
+    Polyline {
+        points,
+        closed: true,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn square(x: f32, y: f32, size: f32) -> Polyline {
+        Polyline {
+            points: vec![(x, y), (x + size, y), (x + size, y + size), (x, y + size)],
+            closed: true,
+        }
+    }
+
+    fn alpha_at(pixels: &[u8], width: usize, x: usize, y: usize) -> u8 {
+        pixels[(y * width + x) * 4 + 3]
+    }
+
+    #[test]
+    fn fill_with_antialiasing() {
+        let mut canvas = Canvas::new(4, 4);
+        canvas.fill(
+            &[square(0.5, 1., 2.)],
+            FillRule::NonZero,
+            Color::new(255, 0, 0, 255),
+        );
+        let pixels = canvas.to_rgba();
+
+        assert_eq!(&pixels[(4 + 1) * 4..(4 + 1) * 4 + 4], &[255, 0, 0, 255]);
+        assert_eq!(alpha_at(&pixels, 4, 0, 1), 128);
+        assert_eq!(alpha_at(&pixels, 4, 2, 2), 128);
+        assert_eq!(alpha_at(&pixels, 4, 1, 0), 0);
+        assert_eq!(alpha_at(&pixels, 4, 1, 3), 0);
+    }
+
+    #[test]
+    fn fill_rules() {
+        // a square with a hole wound the same way
+        let shapes = [square(0., 0., 4.), square(1., 1., 2.)];
+        let color = Color::new(0, 0, 0, 255);
+
+        let mut canvas = Canvas::new(4, 4);
+        canvas.fill(&shapes, FillRule::NonZero, color);
+        assert_eq!(alpha_at(&canvas.to_rgba(), 4, 1, 1), 255);
+
+        let mut canvas = Canvas::new(4, 4);
+        canvas.fill(&shapes, FillRule::EvenOdd, color);
+        let pixels = canvas.to_rgba();
+        assert_eq!(alpha_at(&pixels, 4, 1, 1), 0);
+        assert_eq!(alpha_at(&pixels, 4, 0, 0), 255);
+    }
+
+    #[test]
+    fn stroke_outlines() {
+        let line = Polyline {
+            points: vec![(0., 2.), (4., 2.)],
+            closed: false,
+        };
+        let mut canvas = Canvas::new(4, 4);
+        canvas.fill(
+            &stroke_polygons(&[line], 2.),
+            FillRule::NonZero,
+            Color::new(0, 0, 255, 255),
+        );
+        let pixels = canvas.to_rgba();
+        assert_eq!(alpha_at(&pixels, 4, 2, 1), 255);
+        assert_eq!(alpha_at(&pixels, 4, 2, 2), 255);
+        assert_eq!(alpha_at(&pixels, 4, 2, 0), 0);
+    }
+}
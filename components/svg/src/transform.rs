@@ -0,0 +1,115 @@
+use super::number::parse_numbers;
+
+/// An affine transform mapping `(x, y)` to
+/// `(a * x + c * y + e, b * x + d * y + f)`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Transform {
+    pub a: f32,
+    pub b: f32,
+    pub c: f32,
+    pub d: f32,
+    pub e: f32,
+    pub f: f32,
+}
+
+impl Transform {
+    pub const fn new(a: f32, b: f32, c: f32, d: f32, e: f32, f: f32) -> Self {
+        Self { a, b, c, d, e, f }
+    }
+
+    pub const fn identity() -> Self {
+        Self::new(1., 0., 0., 1., 0., 0.)
+    }
+
+    pub const fn translate(x: f32, y: f32) -> Self {
+        Self::new(1., 0., 0., 1., x, y)
+    }
+
+    pub const fn scale(x: f32, y: f32) -> Self {
+        Self::new(x, 0., 0., y, 0., 0.)
+    }
+
+    /// The transform applying `other` first, then this transform
+    pub fn multiply(&self, other: &Transform) -> Transform {
+        Transform::new(
+            self.a * other.a + self.c * other.b,
+            self.b * other.a + self.d * other.b,
+            self.a * other.c + self.c * other.d,
+            self.b * other.c + self.d * other.d,
+            self.a * other.e + self.c * other.f + self.e,
+            self.b * other.e + self.d * other.f + self.f,
+        )
+    }
+
+    pub fn apply(&self, x: f32, y: f32) -> (f32, f32) {
+        (
+            self.a * x + self.c * y + self.e,
+            self.b * x + self.d * y + self.f,
+        )
+    }
+
+    /// How much lengths are scaled on average, e.g. for the width of strokes
+    pub fn mean_scale(&self) -> f32 {
+        (self.a * self.d - self.b * self.c).abs().sqrt()
+    }
+
+    /// Parse the list of transform functions of a `transform` attribute
+    pub fn parse(value: &str) -> Option<Transform> {
+        let mut result = Transform::identity();
+        let mut rest = value.trim();
+        while !rest.is_empty() {
+            let open = rest.find('(')?;
+            let close = rest.find(')')?;
+            let name = rest[..open].trim();
+            let args = parse_numbers(&rest[open + 1..close])?;
+            let transform = match (name, args.as_slice()) {
+                ("matrix", [a, b, c, d, e, f]) => Transform::new(*a, *b, *c, *d, *e, *f),
+                ("translate", [x]) => Transform::translate(*x, 0.),
+                ("translate", [x, y]) => Transform::translate(*x, *y),
+                ("scale", [s]) => Transform::scale(*s, *s),
+                ("scale", [x, y]) => Transform::scale(*x, *y),
+                ("rotate", [angle]) => rotate(*angle),
+                ("rotate", [angle, x, y]) => Transform::translate(*x, *y)
+                    .multiply(&rotate(*angle))
+                    .multiply(&Transform::translate(-x, -y)),
+                ("skewX", [angle]) => Transform::new(1., 0., angle.to_radians().tan(), 1., 0., 0.),
+                ("skewY", [angle]) => Transform::new(1., angle.to_radians().tan(), 0., 1., 0., 0.),
+                _ => return None,
+            };
+            result = result.multiply(&transform);
+            rest = rest[close + 1..].trim_start_matches(|ch: char| ch == ',' || ch.is_whitespace());
+        }
+        Some(result)
+    }
+}
+
+fn rotate(degrees: f32) -> Transform {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    Transform::new(cos, sin, -sin, cos, 0., 0.)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn assert_point(actual: (f32, f32), expected: (f32, f32)) {
+        assert!(
+            (actual.0 - expected.0).abs() < 1e-4 && (actual.1 - expected.1).abs() < 1e-4,
+            "{:?} != {:?}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn parse_transform_lists() {
+        let transform = Transform::parse("translate(10, 5) scale(2)").unwrap();
+        assert_point(transform.apply(1., 1.), (12., 7.));
+
+        let transform = Transform::parse("rotate(90 10 10)").unwrap();
+        assert_point(transform.apply(20., 10.), (10., 20.));
+
+        assert_eq!(Transform::parse("scale(1, 2, 3)"), None);
+        assert_eq!(Transform::parse("spin(2)"), None);
+    }
+}
@@ -0,0 +1,275 @@
+/// A small XML parser for SVG documents. Only elements & their attributes
+/// are kept: text, comments, processing instructions & doctypes are
+/// skipped since none of the supported SVG elements use them.
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Element {
+    /// The local name of the element, without its namespace prefix
+    pub name: String,
+    pub attributes: Vec<(String, String)>,
+    pub children: Vec<Element>,
+}
+
+impl Element {
+    pub fn new(name: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            attributes: Vec::new(),
+            children: Vec::new(),
+        }
+    }
+
+    pub fn attribute(&self, name: &str) -> Option<&str> {
+        self.attributes
+            .iter()
+            .find(|(attr, _)| attr == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Parse a document, returning its root element
+pub fn parse(text: &str) -> Result<Element, String> {
+    let mut parser = Parser { text, index: 0 };
+    parser.skip_prolog()?;
+    let root = parser.parse_element()?;
+    Ok(root)
+}
+
+struct Parser<'a> {
+    text: &'a str,
+    index: usize,
+}
+
+impl<'a> Parser<'a> {
+    fn rest(&self) -> &'a str {
+        &self.text[self.index..]
+    }
+
+    fn skip_whitespace(&mut self) {
+        let rest = self.rest();
+        self.index += rest.len() - rest.trim_start().len();
+    }
+
+    fn skip_past(&mut self, end: &str) -> Result<(), String> {
+        match self.rest().find(end) {
+            Some(index) => {
+                self.index += index + end.len();
+                Ok(())
+            }
+            None => Err(format!("Unexpected end of document, expected {}", end)),
+        }
+    }
+
+    /// Skip the declarations, comments & doctype before the root element
+    fn skip_prolog(&mut self) -> Result<(), String> {
+        self.index += self.rest().len() - self.rest().trim_start_matches('\u{feff}').len();
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<!") {
+                self.skip_doctype()?;
+            } else if rest.starts_with('<') {
+                return Ok(());
+            } else {
+                return Err("Expected an element".to_string());
+            }
+        }
+    }
+
+    /// Skip a doctype, which may have an internal subset in brackets
+    fn skip_doctype(&mut self) -> Result<(), String> {
+        let mut in_subset = false;
+        for (index, ch) in self.rest().char_indices() {
+            match ch {
+                '[' => in_subset = true,
+                ']' => in_subset = false,
+                '>' if !in_subset => {
+                    self.index += index + 1;
+                    return Ok(());
+                }
+                _ => {}
+            }
+        }
+        Err("Unexpected end of document in doctype".to_string())
+    }
+
+    fn parse_name(&mut self) -> Result<String, String> {
+        let rest = self.rest();
+        let end = rest
+            .find(|ch: char| ch.is_whitespace() || matches!(ch, '/' | '>' | '='))
+            .unwrap_or_else(|| rest.len());
+        if end == 0 {
+            return Err(format!("Expected a name at {}", self.index));
+        }
+        self.index += end;
+        Ok(rest[..end].to_string())
+    }
+
+    fn parse_element(&mut self) -> Result<Element, String> {
+        // skip the '<'
+        self.index += 1;
+        let name = self.parse_name()?;
+        let mut element = Element::new(local_name(&name));
+
+        loop {
+            self.skip_whitespace();
+            let rest = self.rest();
+            if rest.starts_with("/>") {
+                self.index += 2;
+                return Ok(element);
+            }
+            if rest.starts_with('>') {
+                self.index += 1;
+                break;
+            }
+            if rest.is_empty() {
+                return Err(format!("Unexpected end of document in <{}>", name));
+            }
+            let attribute = self.parse_attribute()?;
+            element.attributes.push(attribute);
+        }
+
+        loop {
+            let text_end = self
+                .rest()
+                .find('<')
+                .ok_or_else(|| format!("Unclosed element <{}>", name))?;
+            self.index += text_end;
+
+            let rest = self.rest();
+            if rest.starts_with("</") {
+                self.index += 2;
+                let closing = self.parse_name()?;
+                if closing != name {
+                    return Err(format!("Expected </{}>, found </{}>", name, closing));
+                }
+                self.skip_past(">")?;
+                return Ok(element);
+            } else if rest.starts_with("<!--") {
+                self.skip_past("-->")?;
+            } else if rest.starts_with("<![CDATA[") {
+                self.skip_past("]]>")?;
+            } else if rest.starts_with("<?") {
+                self.skip_past("?>")?;
+            } else {
+                let child = self.parse_element()?;
+                element.children.push(child);
+            }
+        }
+    }
+
+    fn parse_attribute(&mut self) -> Result<(String, String), String> {
+        let name = self.parse_name()?;
+        self.skip_whitespace();
+        if !self.rest().starts_with('=') {
+            return Err(format!("Expected a value for the attribute {}", name));
+        }
+        self.index += 1;
+        self.skip_whitespace();
+
+        let quote = match self.rest().chars().next() {
+            Some(quote @ '"') | Some(quote @ '\'') => quote,
+            _ => {
+                return Err(format!(
+                    "Expected a quoted value for the attribute {}",
+                    name
+                ))
+            }
+        };
+        self.index += 1;
+        let rest = self.rest();
+        let end = rest
+            .find(quote)
+            .ok_or_else(|| format!("Unclosed value of the attribute {}", name))?;
+        self.index += end + 1;
+        Ok((name, decode_entities(&rest[..end])))
+    }
+}
+
+/// `svg:rect` is a `rect`, the prefixes of the attributes are kept so
+/// `xlink:href` isn't mistaken for `href`
+fn local_name(name: &str) -> &str {
+    match name.rfind(':') {
+        Some(index) => &name[index + 1..],
+        None => name,
+    }
+}
+
+fn decode_entities(value: &str) -> String {
+    if !value.contains('&') {
+        return value.to_string();
+    }
+    let mut result = String::with_capacity(value.len());
+    let mut rest = value;
+    while let Some(start) = rest.find('&') {
+        result.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let end = match rest.find(';') {
+            Some(end) => end,
+            None => break,
+        };
+        let decoded = match &rest[1..end] {
+            "amp" => Some('&'),
+            "lt" => Some('<'),
+            "gt" => Some('>'),
+            "quot" => Some('"'),
+            "apos" => Some('\''),
+            entity if entity.starts_with("#x") => u32::from_str_radix(&entity[2..], 16)
+                .ok()
+                .and_then(std::char::from_u32),
+            entity if entity.starts_with('#') => entity[1..]
+                .parse::<u32>()
+                .ok()
+                .and_then(std::char::from_u32),
+            _ => None,
+        };
+        match decoded {
+            Some(ch) => {
+                result.push(ch);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                result.push('&');
+                rest = &rest[1..];
+            }
+        }
+    }
+    result.push_str(rest);
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_document() {
+        let root = parse(
+            r#"<?xml version="1.0"?>
+            <!DOCTYPE svg [ <!ENTITY a "b"> ]>
+            <!-- icon -->
+            <svg:svg xmlns:svg="http://www.w3.org/2000/svg" width='10'>
+                <title>Moon &amp; stars</title>
+                <rect fill="&#x23;f00" />
+            </svg:svg>"#,
+        )
+        .unwrap();
+
+        assert_eq!(root.name, "svg");
+        assert_eq!(root.attribute("width"), Some("10"));
+        assert_eq!(root.children.len(), 2);
+        assert_eq!(root.children[1].name, "rect");
+        assert_eq!(root.children[1].attribute("fill"), Some("#f00"));
+    }
+
+    #[test]
+    fn reject_malformed_documents() {
+        assert!(parse("<svg><rect></svg>").is_err());
+        assert!(parse("<svg width=10/>").is_err());
+        assert!(parse("not an image").is_err());
+    }
+}
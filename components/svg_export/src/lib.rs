@@ -0,0 +1,5 @@
+/// Export of the display list into SVG documents, e.g. to look at the
+/// shapes painted for a page or to include a render in the documentation
+mod painter;
+
+pub use painter::SvgPainter;
@@ -0,0 +1,303 @@
+use painting::{Color, Image, Painter, RRect, Rect, Transform};
+use std::fmt::Write;
+
+/// A painter writing the commands of a display list as the elements of
+/// an SVG document, in the CSS pixels of the page. The images are
+/// embedded as PNG data URLs.
+pub struct SvgPainter {
+    width: f32,
+    height: f32,
+    /// The elements painted so far
+    content: String,
+    transform: Transform,
+    /// The clip paths written, named `clip<index>`
+    clip_count: usize,
+    /// Whether the elements are painted in a group clipped by the last
+    /// clip path
+    is_clipped: bool,
+}
+
+impl SvgPainter {
+    /// Create a painter for a document of a size in CSS pixels
+    pub fn new(width: f32, height: f32) -> Self {
+        Self {
+            width,
+            height,
+            content: String::new(),
+            transform: Transform::identity(),
+            clip_count: 0,
+            is_clipped: false,
+        }
+    }
+
+    /// Write the SVG document painted so far
+    pub fn finish(mut self) -> String {
+        self.set_clip(None);
+        format!(
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"{0}\" height=\"{1}\" viewBox=\"0 0 {0} {1}\">\n{2}</svg>\n",
+            number(self.width),
+            number(self.height),
+            self.content
+        )
+    }
+
+    /// The transform attribute of the elements painted in the current
+    /// transform
+    fn transform_attribute(&self) -> String {
+        if self.transform.is_identity() {
+            return String::new();
+        }
+        let t = &self.transform;
+        format!(
+            " transform=\"matrix({} {} {} {} {} {})\"",
+            number(t.a),
+            number(t.b),
+            number(t.c),
+            number(t.d),
+            number(t.e),
+            number(t.f)
+        )
+    }
+}
+
+impl Painter for SvgPainter {
+    fn fill_rect(&mut self, rect: Rect, color: Color) {
+        if color.a == 0 {
+            return;
+        }
+        let transform = self.transform_attribute();
+        writeln!(
+            self.content,
+            "<rect x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\"{}{}/>",
+            number(rect.x),
+            number(rect.y),
+            number(rect.width),
+            number(rect.height),
+            paint("fill", &color),
+            transform
+        )
+        .unwrap();
+    }
+
+    fn fill_rrect(&mut self, rect: RRect, color: Color) {
+        if color.a == 0 {
+            return;
+        }
+        let transform = self.transform_attribute();
+        writeln!(
+            self.content,
+            "<path d=\"{}\"{}{}/>",
+            rrect_path(&rect),
+            paint("fill", &color),
+            transform
+        )
+        .unwrap();
+    }
+
+    fn stroke_rrect(&mut self, rect: RRect, color: Color, width: f32) {
+        if color.a == 0 {
+            return;
+        }
+        let transform = self.transform_attribute();
+        writeln!(
+            self.content,
+            "<path d=\"{}\" fill=\"none\"{} stroke-width=\"{}\"{}/>",
+            rrect_path(&rect),
+            paint("stroke", &color),
+            number(width),
+            transform
+        )
+        .unwrap();
+    }
+
+    fn set_transform(&mut self, transform: Transform) {
+        self.transform = transform;
+    }
+
+    fn set_clip(&mut self, clip: Option<Rect>) {
+        if self.is_clipped {
+            self.content.push_str("</g>\n");
+        }
+        self.is_clipped = clip.is_some();
+        // the group has no transform, so the clip path is in the
+        // coordinates of the document
+        if let Some(clip) = clip {
+            writeln!(
+                self.content,
+                "<clipPath id=\"clip{0}\"><rect x=\"{1}\" y=\"{2}\" width=\"{3}\" height=\"{4}\"/></clipPath>\n<g clip-path=\"url(#clip{0})\">",
+                self.clip_count,
+                number(clip.x),
+                number(clip.y),
+                number(clip.width),
+                number(clip.height)
+            )
+            .unwrap();
+            self.clip_count += 1;
+        }
+    }
+
+    fn draw_image(&mut self, rect: Rect, image: &Image) {
+        if image.width == 0 || image.height == 0 {
+            return;
+        }
+        let png = match encode_png(image) {
+            Ok(png) => png,
+            Err(e) => {
+                log::error!("Unable to encode image: {}", e);
+                return;
+            }
+        };
+        let transform = self.transform_attribute();
+        writeln!(
+            self.content,
+            "<image x=\"{}\" y=\"{}\" width=\"{}\" height=\"{}\" preserveAspectRatio=\"none\" href=\"data:image/png;base64,{}\"{}/>",
+            number(rect.x),
+            number(rect.y),
+            number(rect.width),
+            number(rect.height),
+            base64::encode(png),
+            transform
+        )
+        .unwrap();
+    }
+}
+
+/// The attributes painting a fill or a stroke with a color
+fn paint(property: &str, color: &Color) -> String {
+    let mut attributes = format!(
+        " {}=\"#{:02x}{:02x}{:02x}\"",
+        property, color.r, color.g, color.b
+    );
+    if color.a < 255 {
+        write!(
+            attributes,
+            " {}-opacity=\"{}\"",
+            property,
+            number(color.a as f32 / 255.)
+        )
+        .unwrap();
+    }
+    attributes
+}
+
+fn rrect_path(rect: &RRect) -> String {
+    let corners = &rect.corners;
+    let (left, top) = (rect.x, rect.y);
+    let (right, bottom) = (rect.x + rect.width, rect.y + rect.height);
+    let points = [
+        (left + corners.top_left.horizontal_r(), top),
+        (right - corners.top_right.horizontal_r(), top),
+        (right, top),
+        (right, top + corners.top_right.vertical_r()),
+        (right, bottom - corners.bottom_right.vertical_r()),
+        (right, bottom),
+        (right - corners.bottom_right.horizontal_r(), bottom),
+        (left + corners.bottom_left.horizontal_r(), bottom),
+        (left, bottom),
+        (left, bottom - corners.bottom_left.vertical_r()),
+        (left, top + corners.top_left.vertical_r()),
+        (left, top),
+        (left + corners.top_left.horizontal_r(), top),
+    ];
+    let point = |index: usize| format!("{} {}", number(points[index].0), number(points[index].1));
+
+    // a line to each corner, then a curve around it
+    let mut path = format!("M{}", point(0));
+    for corner in 0..4 {
+        let start = corner * 3;
+        write!(
+            path,
+            "L{}Q{} {}",
+            point(start + 1),
+            point(start + 2),
+            point(start + 3)
+        )
+        .unwrap();
+    }
+    path.push('Z');
+    path
+}
+
+fn encode_png(image: &Image) -> Result<Vec<u8>, png::EncodingError> {
+    let mut png = Vec::new();
+    {
+        let mut encoder = png::Encoder::new(&mut png, image.width, image.height);
+        encoder.set_color(png::ColorType::RGBA);
+        encoder.set_depth(png::BitDepth::Eight);
+        let mut writer = encoder.write_header()?;
+        writer.write_image_data(&image.pixels)?;
+    }
+    Ok(png)
+}
+
+/// A number without a long tail of decimals
+fn number(value: f32) -> String {
+    let value = format!("{:.3}", value);
+    let value = value.trim_end_matches('0').trim_end_matches('.');
+    match value {
+        "-0" | "" => "0".to_string(),
+        value => value.to_string(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use painting::{Corners, Radii};
+
+    #[test]
+    fn paint_shapes() {
+        let mut painter = SvgPainter::new(100., 50.5);
+        painter.fill_rect(
+            Rect::new(0., 0., 10., 5.),
+            Color {
+                r: 255,
+                g: 0,
+                b: 0,
+                a: 255,
+            },
+        );
+        painter.fill_rect(Rect::new(0., 0., 10., 5.), Color::default());
+        painter.set_transform(Transform::translate(5., 0.));
+        let radius = Radii::new(2., 2.);
+        painter.stroke_rrect(
+            RRect::new(
+                0.,
+                0.,
+                10.,
+                10.,
+                Corners::new(radius.clone(), radius.clone(), radius.clone(), radius),
+            ),
+            Color {
+                r: 0,
+                g: 0,
+                b: 255,
+                a: 51,
+            },
+            2.,
+        );
+
+        assert_eq!(
+            painter.finish(),
+            "<svg xmlns=\"http://www.w3.org/2000/svg\" width=\"100\" height=\"50.5\" viewBox=\"0 0 100 50.5\">\n\
+             <rect x=\"0\" y=\"0\" width=\"10\" height=\"5\" fill=\"#ff0000\"/>\n\
+             <path d=\"M2 0L8 0Q10 0 10 2L10 8Q10 10 8 10L2 10Q0 10 0 8L0 2Q0 0 2 0Z\" fill=\"none\" \
+             stroke=\"#0000ff\" stroke-opacity=\"0.2\" stroke-width=\"2\" transform=\"matrix(1 0 0 1 5 0)\"/>\n\
+             </svg>\n"
+        );
+    }
+
+    #[test]
+    fn embed_images() {
+        let mut painter = SvgPainter::new(100., 50.);
+        let image = Image::new(2, 1, vec![255, 0, 0, 255, 0, 0, 255, 128]);
+        painter.draw_image(Rect::new(0., 0., 20., 10.), &image);
+
+        let output = painter.finish();
+        let start = output.find("base64,").unwrap() + "base64,".len();
+        let end = start + output[start..].find('"').unwrap();
+        let png = base64::decode(&output[start..end]).unwrap();
+        assert!(png.starts_with(b"\x89PNG\r\n\x1a\n"));
+        assert!(output.contains("<image x=\"0\" y=\"0\" width=\"20\" height=\"10\""));
+    }
+}
@@ -1,5 +1,21 @@
+//! URL parsing & resolution, following the subset of the WHATWG URL
+//! standard the browser needs: the schemes & hosts are lowercased, the
+//! backslashes of the special URLs are slashes & relative URLs are
+//! resolved against a base URL.
+//! https://url.spec.whatwg.org/
 use io::input_stream::CharInputStream;
 
+/// The schemes with a host & a hierarchical path, & their default port
+/// https://url.spec.whatwg.org/#special-scheme
+const SPECIAL_SCHEMES: &[(&str, Option<u16>)] = &[
+    ("ftp", Some(21)),
+    ("file", None),
+    ("http", Some(80)),
+    ("https", Some(443)),
+    ("ws", Some(80)),
+    ("wss", Some(443)),
+];
+
 #[derive(Debug, PartialEq, Eq)]
 pub enum ParseUrlError {
     InvalidCharacterInProtocol(char),
@@ -7,6 +23,8 @@ pub enum ParseUrlError {
     UnexpectedEndOfString,
     InvalidCharacterInPort(char),
     InvalidPort(String),
+    /// A relative URL resolved against a URL without a host, e.g. a `data:` URL
+    CannotBeABase,
 }
 
 #[derive(Debug, Clone)]
@@ -18,6 +36,10 @@ pub struct Url {
     path_start: u32,
     path_end: u32,
     port: Option<u16>,
+    /// The index of the `?` starting the query
+    query_start: Option<usize>,
+    /// The index of the `#` starting the fragment
+    fragment_start: Option<usize>,
 }
 
 enum ParseState {
@@ -38,8 +60,9 @@ macro_rules! expect_or_throw {
 
 impl Url {
     pub fn parse(input: &str) -> Result<Self, ParseUrlError> {
-        let mut stream = CharInputStream::new(input.trim().chars());
-        let raw_url = input.to_string();
+        let input = normalize(input);
+        let mut stream = CharInputStream::new(input.chars());
+        let raw_url = input.clone();
 
         let mut state = ParseState::InProtocol;
         let mut index = 0;
@@ -52,6 +75,8 @@ impl Url {
             path_start: 0,
             path_end: 0,
             port: None,
+            query_start: None,
+            fragment_start: None,
         };
         let mut buffer = String::new();
 
@@ -80,8 +105,19 @@ impl Url {
             match state {
                 ParseState::InProtocol => {
                     match ch {
-                        ':' => {
+                        ':' if index > 0 => {
                             url.protocol_end = index - 1;
+
+                            // URLs without a host, e.g. `data:text/plain,moon`,
+                            // are the protocol followed by the path
+                            if stream.peek() != Some('/') {
+                                if index + 1 < url.raw_url.len() as u32 {
+                                    url.path_start = index + 1;
+                                    url.path_end = url.raw_url.len() as u32 - 1;
+                                }
+                                break;
+                            }
+
                             state = ParseState::InHost;
 
                             expect_or_throw!(
@@ -103,6 +139,10 @@ impl Url {
                             index += 1;
                             continue;
                         }
+                        '0'..='9' | '+' | '-' | '.' if index > 0 => {
+                            index += 1;
+                            continue;
+                        }
                         c => return Err(ParseUrlError::InvalidCharacterInProtocol(c)),
                     }
                 }
@@ -114,8 +154,13 @@ impl Url {
                     }
                     ':' => {
                         url.host_end = index;
+                        index += 1;
                         state = ParseState::InPort;
                     }
+                    '?' | '#' => {
+                        url.host_end = index;
+                        break;
+                    }
                     // TODO: This is a temporary fix for relative protocol that I made up
                     '.' if url.protocol() == "relative" => {
                         url.host_end = index;
@@ -137,8 +182,17 @@ impl Url {
                         buffer.clear();
 
                         url.path_start = index + 1;
+                        state = ParseState::InPath;
+                    }
+                    '?' | '#' => {
+                        let port = match buffer.parse::<u16>() {
+                            Ok(p) => p,
+                            _ => return Err(ParseUrlError::InvalidPort(buffer)),
+                        };
+                        url.port = Some(port);
+                        break;
                     }
-                    c if c.is_numeric() => {
+                    c if c.is_ascii_digit() => {
                         buffer.push(c);
                         index += 1;
                         continue;
@@ -146,8 +200,8 @@ impl Url {
                     c => return Err(ParseUrlError::InvalidCharacterInPort(c)),
                 },
                 ParseState::InPath => match ch {
-                    '?' => {
-                        url.path_end = index;
+                    '?' | '#' => {
+                        url.path_end = index + 1;
                         break;
                     }
                     _ => {
@@ -158,10 +212,24 @@ impl Url {
             }
         }
 
+        if url.host_start != 0 {
+            let start = url.host_start as usize;
+            if url.host_end as usize >= start {
+                url.raw_url[start..=url.host_end as usize].make_ascii_lowercase();
+            }
+            // the hosts & the paths end at the query or the fragment
+            url.fragment_start = url.raw_url[start..].find('#').map(|index| start + index);
+            let end = url.fragment_start.unwrap_or_else(|| url.raw_url.len());
+            url.query_start = url.raw_url[start..end].find('?').map(|index| start + index);
+        }
+
         Ok(url)
     }
 
     pub fn host(&self) -> &str {
+        if self.host_start == 0 {
+            return "";
+        }
         &self.raw_url[self.host_start as usize..=self.host_end as usize]
     }
 
@@ -180,9 +248,164 @@ impl Url {
         self.port
     }
 
+    /// The port of the URL, or the default port of its scheme, e.g. 443
+    /// for `https`
+    pub fn port_or_default(&self) -> Option<u16> {
+        self.port.or_else(|| {
+            SPECIAL_SCHEMES
+                .iter()
+                .find(|(scheme, _)| *scheme == self.protocol())
+                .and_then(|(_, port)| *port)
+        })
+    }
+
+    /// The query of the URL without its `?`, e.g. `q=moon`
+    pub fn query(&self) -> Option<&str> {
+        let start = self.query_start? + 1;
+        let end = self.fragment_start.unwrap_or_else(|| self.raw_url.len());
+        Some(&self.raw_url[start..end])
+    }
+
+    /// The fragment of the URL without its `#`, e.g. `top`
+    pub fn fragment(&self) -> Option<&str> {
+        Some(&self.raw_url[self.fragment_start? + 1..])
+    }
+
+    /// The URL without its fragment, e.g. to tell whether two URLs are
+    /// the same document
+    pub fn without_fragment(&self) -> &str {
+        &self.raw_url[..self.fragment_start.unwrap_or_else(|| self.raw_url.len())]
+    }
+
+    /// The URL with its query replaced & without a fragment, e.g. the
+    /// URL a form is submitted to with GET
+    pub fn with_query(&self, query: &str) -> Result<Self, ParseUrlError> {
+        let end = self
+            .query_start
+            .or(self.fragment_start)
+            .unwrap_or_else(|| self.raw_url.len());
+        Url::parse(&format!("{}?{}", &self.raw_url[..end], query))
+    }
+
+    /// Whether the scheme of the URL is a special scheme, e.g. `http` or
+    /// `file`
+    pub fn is_special(&self) -> bool {
+        is_special_scheme(self.protocol())
+    }
+
     pub fn raw(&self) -> &str {
         &self.raw_url[..]
     }
+
+    /// Resolve a URL relative to this URL, e.g. `style.css` against
+    /// `http://moon.dev/docs/index.html`. Absolute URLs are parsed as is.
+    pub fn join(&self, input: &str) -> Result<Self, ParseUrlError> {
+        if let Ok(url) = Url::parse(input) {
+            return Ok(url);
+        }
+        let mut input = normalize(input);
+        if self.is_special() {
+            input = replace_backslashes(&input);
+        }
+        let input = input.as_str();
+        if self.host_start == 0 {
+            return Err(ParseUrlError::CannotBeABase);
+        }
+
+        let origin = match self.port {
+            Some(port) => format!("{}://{}:{}", self.protocol(), self.host(), port),
+            None => format!("{}://{}", self.protocol(), self.host()),
+        };
+        let path = self.path();
+
+        let resolved = if input.starts_with("//") {
+            format!("{}:{}", self.protocol(), input)
+        } else if input.starts_with('/') {
+            format!("{}{}", origin, remove_dot_segments(input))
+        } else if input.is_empty() || input.starts_with('#') {
+            format!("{}{}", self.without_fragment(), input)
+        } else if input.starts_with('?') {
+            let end = self.query_start.or(self.fragment_start);
+            format!(
+                "{}{}",
+                &self.raw_url[..end.unwrap_or(self.raw_url.len())],
+                input
+            )
+        } else {
+            let directory = &path[..path.rfind('/').map_or(0, |index| index + 1)];
+            let directory = if directory.is_empty() { "/" } else { directory };
+            format!(
+                "{}{}",
+                origin,
+                remove_dot_segments(&format!("{}{}", directory, input))
+            )
+        };
+        Url::parse(&resolved)
+    }
+}
+
+fn is_special_scheme(scheme: &str) -> bool {
+    SPECIAL_SCHEMES
+        .iter()
+        .any(|(special, _)| *special == scheme)
+}
+
+/// Strip the leading & trailing spaces & the tabs & newlines of a URL,
+/// lowercase its scheme & turn the backslashes of a special URL into
+/// slashes
+/// https://url.spec.whatwg.org/#concept-basic-url-parser
+fn normalize(input: &str) -> String {
+    let mut url = input
+        .trim_matches(|c: char| c <= ' ')
+        .chars()
+        .filter(|c| !matches!(c, '\t' | '\n' | '\r'))
+        .collect::<String>();
+    let scheme_end = url.find(':').filter(|end| {
+        let scheme = &url[..*end];
+        scheme.starts_with(|c: char| c.is_ascii_alphabetic())
+            && scheme
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '+' | '-' | '.'))
+    });
+    if let Some(end) = scheme_end {
+        url[..end].make_ascii_lowercase();
+        if is_special_scheme(&url[..end]) {
+            url = replace_backslashes(&url);
+        }
+    }
+    url
+}
+
+/// Replace the backslashes before the query & the fragment of a URL
+fn replace_backslashes(url: &str) -> String {
+    let end = url.find(|c| c == '?' || c == '#').unwrap_or(url.len());
+    format!("{}{}", url[..end].replace('\\', "/"), &url[end..])
+}
+
+/// Remove the `.` & `..` segments of an absolute path, keeping its query
+/// https://datatracker.ietf.org/doc/html/rfc3986#section-5.2.4
+fn remove_dot_segments(path: &str) -> String {
+    let end = path.find(|c| c == '?' || c == '#').unwrap_or(path.len());
+    let (path, rest) = path.split_at(end);
+
+    let mut segments: Vec<&str> = Vec::new();
+    let parts = path.split('/').skip(1).collect::<Vec<_>>();
+    for (index, segment) in parts.iter().enumerate() {
+        let is_last = index == parts.len() - 1;
+        match *segment {
+            "." | ".." => {
+                if *segment == ".." {
+                    segments.pop();
+                }
+                // a path ending with a dot segment is a directory
+                if is_last {
+                    segments.push("");
+                }
+            }
+            segment => segments.push(segment),
+        }
+    }
+    format!("/{}{}", segments.join("/"), rest)
 }
 
 #[cfg(test)]
@@ -213,6 +436,27 @@ mod tests {
         assert_eq!(url.path(), "");
     }
 
+    #[test]
+    fn path_with_query() {
+        let input_url = "file:///tmp/index.html?q=a";
+
+        let url = Url::parse(input_url).ok().unwrap();
+
+        assert_eq!(url.protocol(), "file");
+        assert_eq!(url.path(), "/tmp/index.html");
+    }
+
+    #[test]
+    fn without_host() {
+        let input_url = "data:text/plain;base64,bW9vbg==?#";
+
+        let url = Url::parse(input_url).ok().unwrap();
+
+        assert_eq!(url.protocol(), "data");
+        assert_eq!(url.host(), "");
+        assert_eq!(url.path(), "text/plain;base64,bW9vbg==?#");
+    }
+
     #[test]
     fn with_port() {
         let input_url = "https://google.com:443";
@@ -225,9 +469,102 @@ mod tests {
         assert_eq!(url.path(), "");
     }
 
+    #[test]
+    fn with_port_and_path() {
+        let input_url = "http://127.0.0.1:8080/index.html";
+
+        let url = Url::parse(input_url).ok().unwrap();
+
+        assert_eq!(url.host(), "127.0.0.1");
+        assert_eq!(url.port(), Some(8080));
+        assert_eq!(url.path(), "/index.html");
+    }
+
+    #[test]
+    fn join_relative_urls() {
+        let base = Url::parse("http://moon.dev:8080/docs/guide/index.html?q=1").unwrap();
+        let join = |input: &str| base.join(input).unwrap().raw().to_string();
+
+        assert_eq!(
+            join("style.css"),
+            "http://moon.dev:8080/docs/guide/style.css"
+        );
+        assert_eq!(join("../img/a.png"), "http://moon.dev:8080/docs/img/a.png");
+        assert_eq!(join("./"), "http://moon.dev:8080/docs/guide/");
+        assert_eq!(join("/about?x=2"), "http://moon.dev:8080/about?x=2");
+        assert_eq!(join("//cdn.dev/a.js"), "http://cdn.dev/a.js");
+        assert_eq!(
+            join("?q=2"),
+            "http://moon.dev:8080/docs/guide/index.html?q=2"
+        );
+        assert_eq!(join("https://other.dev/"), "https://other.dev/");
+
+        let host_only = Url::parse("https://moon.dev").unwrap();
+        assert_eq!(
+            host_only.join("a.html").unwrap().raw(),
+            "https://moon.dev/a.html"
+        );
+
+        let data = Url::parse("data:text/plain,moon").unwrap();
+        assert_eq!(
+            data.join("a.html").err(),
+            Some(ParseUrlError::CannotBeABase)
+        );
+    }
+
+    #[test]
+    fn query_and_fragment() {
+        let url = Url::parse("https://moon.dev/search?q=a#results").unwrap();
+        assert_eq!(url.path(), "/search");
+        assert_eq!(url.query(), Some("q=a"));
+        assert_eq!(url.fragment(), Some("results"));
+        assert_eq!(url.without_fragment(), "https://moon.dev/search?q=a");
+        assert_eq!(
+            url.with_query("q=b").unwrap().raw(),
+            "https://moon.dev/search?q=b"
+        );
+
+        let url = Url::parse("http://moon.dev:8080#top").unwrap();
+        assert_eq!(url.host(), "moon.dev");
+        assert_eq!(url.port(), Some(8080));
+        assert_eq!(url.path(), "");
+        assert_eq!(url.query(), None);
+        assert_eq!(url.fragment(), Some("top"));
+
+        let url = Url::parse("http://moon.dev?q").unwrap();
+        assert_eq!(url.host(), "moon.dev");
+        assert_eq!(url.query(), Some("q"));
+    }
+
+    #[test]
+    fn normalize_urls() {
+        let url = Url::parse(" HTTPS://Moon.DEV\\docs\\a.html?x=\\ ").unwrap();
+        assert_eq!(url.raw(), "https://moon.dev/docs/a.html?x=\\");
+        assert_eq!(url.protocol(), "https");
+        assert_eq!(url.port_or_default(), Some(443));
+
+        let url = Url::parse("ht\ttp://moon.dev/\na").unwrap();
+        assert_eq!(url.raw(), "http://moon.dev/a");
+
+        let url = Url::parse("web+moon://Moon.dev/a").unwrap();
+        assert_eq!(url.protocol(), "web+moon");
+        assert!(!url.is_special());
+        assert_eq!(url.port_or_default(), None);
+
+        let base = Url::parse("http://moon.dev/docs/index.html").unwrap();
+        assert_eq!(
+            base.join("..\\img\\a.png").unwrap().raw(),
+            "http://moon.dev/img/a.png"
+        );
+        assert_eq!(
+            base.join("#top").unwrap().raw(),
+            "http://moon.dev/docs/index.html#top"
+        );
+    }
+
     #[test]
     fn invalid_protocol() {
-        let input_url = "htt1ps://google.com:443";
+        let input_url = "1https://google.com:443";
 
         let url = Url::parse(input_url);
 
@@ -235,6 +572,10 @@ mod tests {
             url.err().unwrap(),
             ParseUrlError::InvalidCharacterInProtocol('1')
         );
+        assert_eq!(
+            Url::parse(":moon").err(),
+            Some(ParseUrlError::InvalidCharacterInProtocol(':'))
+        );
     }
 
     #[test]
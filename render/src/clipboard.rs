@@ -0,0 +1,8 @@
+use arboard::Clipboard;
+
+/// Put a text on the system clipboard, e.g. the text copied from a page
+pub fn copy_text(text: String) -> Result<(), String> {
+    Clipboard::new()
+        .and_then(|mut clipboard| clipboard.set_text(text))
+        .map_err(|e| e.to_string())
+}
@@ -0,0 +1,109 @@
+use std::process::Command;
+use style::media::ColorScheme;
+
+/// The color scheme of the window system, read from the settings of the
+/// desktop. None if the platform or the desktop isn't supported.
+pub fn system_color_scheme() -> Option<ColorScheme> {
+    if cfg!(target_os = "macos") {
+        // the key only exists in dark mode
+        let output = Command::new("defaults")
+            .args(&["read", "-g", "AppleInterfaceStyle"])
+            .output()
+            .ok()?;
+        return Some(macos_color_scheme(&String::from_utf8_lossy(&output.stdout)));
+    }
+    if cfg!(target_os = "windows") {
+        let output = command_output(
+            "reg",
+            &[
+                "query",
+                r"HKCU\Software\Microsoft\Windows\CurrentVersion\Themes\Personalize",
+                "/v",
+                "AppsUseLightTheme",
+            ],
+        )?;
+        return windows_color_scheme(&output);
+    }
+    if let Ok(theme) = std::env::var("GTK_THEME") {
+        return Some(gtk_theme_color_scheme(&theme));
+    }
+    let output = command_output(
+        "gsettings",
+        &["get", "org.gnome.desktop.interface", "color-scheme"],
+    )?;
+    gnome_color_scheme(&output)
+}
+
+/// The standard output of a command that succeeded
+fn command_output(program: &str, args: &[&str]) -> Option<String> {
+    let output = Command::new(program).args(args).output().ok()?;
+    if !output.status.success() {
+        return None;
+    }
+    Some(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+fn macos_color_scheme(interface_style: &str) -> ColorScheme {
+    if interface_style.trim().eq_ignore_ascii_case("dark") {
+        ColorScheme::Dark
+    } else {
+        ColorScheme::Light
+    }
+}
+
+/// The scheme of the `AppsUseLightTheme` value printed by `reg query`
+fn windows_color_scheme(output: &str) -> Option<ColorScheme> {
+    let value = output
+        .lines()
+        .find(|line| line.contains("AppsUseLightTheme"))?
+        .split_whitespace()
+        .last()?;
+    match value {
+        "0x0" => Some(ColorScheme::Dark),
+        "0x1" => Some(ColorScheme::Light),
+        _ => None,
+    }
+}
+
+/// The scheme of a GTK theme, e.g. `Adwaita:dark`
+fn gtk_theme_color_scheme(theme: &str) -> ColorScheme {
+    if theme.to_ascii_lowercase().contains("dark") {
+        ColorScheme::Dark
+    } else {
+        ColorScheme::Light
+    }
+}
+
+/// The scheme of the GNOME `color-scheme` setting, e.g. `'prefer-dark'`
+fn gnome_color_scheme(setting: &str) -> Option<ColorScheme> {
+    match setting.trim().trim_matches('\'') {
+        "prefer-dark" => Some(ColorScheme::Dark),
+        "prefer-light" | "default" => Some(ColorScheme::Light),
+        _ => None,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn read_system_settings() {
+        assert_eq!(macos_color_scheme("Dark\n"), ColorScheme::Dark);
+        assert_eq!(macos_color_scheme(""), ColorScheme::Light);
+        assert_eq!(
+            windows_color_scheme(
+                "\r\nHKEY_CURRENT_USER\\...\\Personalize\r\n    AppsUseLightTheme    REG_DWORD    0x0\r\n"
+            ),
+            Some(ColorScheme::Dark)
+        );
+        assert_eq!(windows_color_scheme("ERROR"), None);
+        assert_eq!(gtk_theme_color_scheme("Adwaita:dark"), ColorScheme::Dark);
+        assert_eq!(
+            gnome_color_scheme("'prefer-dark'\n"),
+            Some(ColorScheme::Dark)
+        );
+        assert_eq!(gnome_color_scheme("'default'\n"), Some(ColorScheme::Light));
+        assert_eq!(gnome_color_scheme(""), None);
+    }
+}
@@ -1,10 +1,42 @@
+use super::heap_stats::{self, HeapStats};
 use super::loader::frame::FrameLoader;
+use super::profiler;
+use super::user_agent::{
+    parse_stylesheet, quirks_stylesheet, user_agent_stylesheet, user_stylesheet,
+};
 use css::cssom::css_rule::CSSRule;
+use css::cssom::keyframes_rule::KeyframesRule;
+use css::cssom::page_rule::PageRule;
+use css::cssom::stylesheet::StyleSheet;
+use dom::activation;
+use dom::canvas::CanvasRenderingContext2D;
+use dom::document::{Document, Refresh};
+use dom::document_loader::Destination;
 use dom::dom_ref::NodeRef;
+use dom::elements::{ElementData, InputType};
+use dom::event::KeyboardEvent;
+use dom::focus;
+use dom::images::load_image;
+use dom::visited::{VisitedLinks, VisitedLinksRef};
+use io::cancellation::{self, CancellationToken};
+use io::parse_error::ParseError;
+use loaders::scheme::SchemeHandlers;
+use painting::{MatchRange, TextSelection};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use url::Url;
 
-use layout::{box_model::Rect, build_layout_tree, layout_box::LayoutBox};
-use style::render_tree::{build_render_tree, RenderTree};
+use layout::{box_model::Rect, build_layout_tree, layout_box::LayoutBox, rebuild_layout_tree};
+use style::animation::{sample_animations, update_animations};
+use style::media::{ColorScheme, MediaFeatures};
+use style::page::PageStyle;
+use style::render_tree::{build_render_tree_with_lengths, RenderNodeRef, RenderTree};
+use style::restyle::restyle_render_tree;
+use style::supports::applicable_rules;
 use style::value_processing::{CSSLocation, CascadeOrigin, ContextualRule};
+use style::values::length::LengthContext;
+use style::values::prelude::Cursor;
 
 pub type FrameSize = (u32, u32);
 
@@ -12,16 +44,63 @@ pub struct Frame {
     document: Option<NodeRef>,
     layout: FrameLayout,
     size: FrameSize,
+    parse_errors: Rc<RefCell<Vec<ParseError>>>,
+    /// The protocols the resources of the document are loaded from
+    schemes: SchemeHandlers,
+    /// The URLs visited by the user, matching the `:visited` links
+    visited_links: VisitedLinksRef,
+    /// The time of the document timeline, since the document was loaded
+    current_time: Duration,
+    /// The text selected with the mouse, in the current layout
+    selection: Option<TextSelection>,
+    /// How far the frame is scrolled down, in CSS pixels
+    scroll_y: f32,
+    /// The text searched in the frame, e.g. with the find bar
+    find: Option<FindInPage>,
+}
+
+struct FindInPage {
+    query: String,
+    /// The index of the match moved to, none if nothing matches
+    active: Option<usize>,
 }
 
 pub struct FrameLayout {
     layout_tree: Option<LayoutBox>,
     render_tree: Option<RenderTree>,
+    user_agent_stylesheet: StyleSheet,
+    quirks_stylesheet: StyleSheet,
+    user_stylesheet: Option<StyleSheet>,
+    /// The stylesheets given along with the document, applied at the
+    /// author origin like stylesheets linked before the styles of the
+    /// document
+    author_stylesheets: Vec<StyleSheet>,
+    /// The time of the document timeline the styles are computed at
+    current_time: Duration,
+    /// Whether the CSS animations & transitions run. Without them, the
+    /// elements keep the styles they have without any animation.
+    animations_enabled: bool,
+    /// Whether the layout skips the contents of the `content-visibility:
+    /// auto` elements outside of the viewport
+    lazy_layout: bool,
+    /// The factor the font sizes are scaled by
+    text_zoom: f32,
+    /// The color scheme the `prefers-color-scheme` media feature matches
+    color_scheme: ColorScheme,
+    /// Whether the user prefers reduced motion. The transitions are
+    /// disabled & the animations skip to their end.
+    reduced_motion: bool,
+    /// Whether the `forced-colors` media feature is active
+    forced_colors: bool,
+    /// Stop styling & laying out the document once cancelled
+    cancellation: CancellationToken,
 }
 
 #[derive(Debug)]
 pub enum ReflowType {
     All(NodeRef),
+    /// Only restyle the nodes affected by DOM mutations since the last reflow
+    Restyle(NodeRef),
     LayoutOnly,
 }
 
@@ -31,30 +110,566 @@ impl Frame {
             document: None,
             layout: FrameLayout::new(),
             size: (0, 0),
+            parse_errors: Rc::new(RefCell::new(Vec::new())),
+            schemes: SchemeHandlers::new(),
+            visited_links: Rc::new(RefCell::new(VisitedLinks::new())),
+            current_time: Duration::ZERO,
+            selection: None,
+            scroll_y: 0.,
+            find: None,
         }
     }
 
     pub fn resize(&mut self, new_size: FrameSize) {
         self.size = new_size;
-        self.layout.reflow(self.size, ReflowType::LayoutOnly);
+        // the viewport relative lengths compute differently
+        match &self.document {
+            Some(document) => self
+                .layout
+                .reflow(self.size, ReflowType::Restyle(document.clone())),
+            None => self.layout.reflow(self.size, ReflowType::LayoutOnly),
+        }
     }
 
     pub fn size(&self) -> FrameSize {
         self.size.clone()
     }
 
+    #[cfg(test)]
+    pub fn document(&self) -> Option<&NodeRef> {
+        self.document.as_ref()
+    }
+
     pub fn set_document(&mut self, document: NodeRef) {
         self.document = Some(document.clone());
+        self.selection = None;
+        self.scroll_y = 0.;
+        self.find = None;
         self.layout.reflow(self.size, ReflowType::All(document));
     }
 
+    /// Apply the DOM mutations of the current document & the resources
+    /// that arrived since the last reflow
+    pub fn restyle(&mut self) {
+        if let Some(document) = &self.document {
+            let has_responses = FrameLoader::process_responses(document);
+
+            // stylesheets of the links inserted since the last reflow
+            let has_new_stylesheets = document.borrow().as_document().has_pending_stylesheets()
+                && document.borrow_mut().as_document_mut().join_stylesheets();
+            // the images that arrived change the size of their boxes
+            if has_new_stylesheets || has_responses {
+                self.layout
+                    .reflow(self.size, ReflowType::All(document.clone()));
+            } else if document.borrow().has_dirty_descendants() {
+                self.layout
+                    .reflow(self.size, ReflowType::Restyle(document.clone()));
+            }
+        }
+    }
+
+    /// Replace the default styles of the elements, applied at the user
+    /// agent origin of the cascade
+    pub fn set_user_agent_css(&mut self, css: &str) {
+        self.layout.user_agent_stylesheet = parse_stylesheet(css);
+        if let Some(document) = &self.document {
+            self.layout
+                .reflow(self.size, ReflowType::All(document.clone()));
+        }
+    }
+
+    /// Set the styles supplied by the user, applied at the user origin
+    /// of the cascade
+    pub fn set_user_css(&mut self, css: &str) {
+        self.layout.user_stylesheet = Some(user_stylesheet(css));
+        if let Some(document) = &self.document {
+            self.layout
+                .reflow(self.size, ReflowType::All(document.clone()));
+        }
+    }
+
+    /// Set the stylesheets applied at the author origin before the
+    /// stylesheets of the document, in order
+    pub fn set_author_css(&mut self, css_list: &[String]) {
+        self.layout.author_stylesheets = css_list.iter().map(|css| parse_stylesheet(css)).collect();
+        if let Some(document) = &self.document {
+            self.layout
+                .reflow(self.size, ReflowType::All(document.clone()));
+        }
+    }
+
+    /// Move the document timeline to a time since the document was loaded
+    /// and update the styles that depend on the time
+    pub fn advance_time(&mut self, time: Duration) {
+        if time == self.current_time {
+            return;
+        }
+        self.current_time = time;
+        if let Some(document) = &self.document {
+            self.layout.sample_animations(self.size, document, time);
+        }
+        // the transitions & animations started by DOM mutations begin at the new time
+        self.restyle();
+    }
+
+    /// Whether the styles of the frame change as the time advances, so
+    /// new frames have to be painted even without DOM mutations
+    pub fn has_running_animations(&self) -> bool {
+        self.layout.has_running_animations()
+    }
+
+    /// Use scheme handlers for the documents loaded from now on
+    /// Share the URLs visited by the user with the frame, from the next
+    /// document loaded
+    pub fn set_visited_links(&mut self, visited_links: VisitedLinksRef) {
+        self.visited_links = visited_links;
+    }
+
+    pub fn set_scheme_handlers(&mut self, schemes: SchemeHandlers) {
+        self.schemes = schemes;
+    }
+
     pub fn load_html(&mut self, html: String) {
-        self.set_document(FrameLoader::load_html(html));
+        self.load_document(html, None);
+    }
+
+    /// Load the HTML of a document from a URL, against which the relative
+    /// URLs of the document are resolved
+    pub fn load_document(&mut self, html: String, url: Option<Url>) {
+        self.parse_errors.borrow_mut().clear();
+        self.current_time = Duration::ZERO;
+        self.layout.current_time = Duration::ZERO;
+        let document = {
+            let _span = profiler::span("parse");
+            FrameLoader::load_html(
+                html,
+                url,
+                self.parse_errors.clone(),
+                self.schemes.clone(),
+                self.layout.cancellation.clone(),
+            )
+        };
+        document
+            .borrow_mut()
+            .as_document_mut()
+            .set_visited_links(self.visited_links.clone());
+        self.set_document(document.clone());
+
+        // the first rendering shows the images of the document, including
+        // the background images found by styling it
+        if FrameLoader::wait_for(&document, Destination::Image) {
+            self.layout.reflow(self.size, ReflowType::All(document));
+        }
+    }
+
+    /// Focus the element painted at a point of the frame, e.g. when it
+    /// is clicked. Clicking outside of a focusable element unfocuses the
+    /// focused element.
+    pub fn focus_at(&mut self, x: f32, y: f32) -> Option<NodeRef> {
+        let document = self.document.clone()?;
+        let target = self
+            .layout
+            .root()
+            .as_ref()
+            .and_then(|root| painting::hit_test(root, x, y + self.scroll_y))
+            .and_then(|layout_box| layout_box.render_node.as_ref())
+            .map(|render_node| render_node.borrow().originating_node());
+        let element = target.and_then(|node| focus::focusable_ancestor(&node));
+        focus::focus(&document, element.clone());
+        element
+    }
+
+    /// The cursor to show while the mouse is over a point of the frame.
+    /// The `auto` cursor is the pointer over links, the text cursor over
+    /// text & text fields and the default cursor elsewhere.
+    pub fn cursor_at(&self, x: f32, y: f32) -> Cursor {
+        let layout_box = match self
+            .layout
+            .root()
+            .as_ref()
+            .and_then(|root| painting::hit_test(root, x, y + self.scroll_y))
+        {
+            Some(layout_box) => layout_box,
+            None => return Cursor::Default,
+        };
+        let render_node = match &layout_box.render_node {
+            Some(render_node) => render_node.borrow(),
+            None => return Cursor::Default,
+        };
+        let cursor = render_node.style().cursor();
+        if cursor != Cursor::Auto {
+            return cursor;
+        }
+        let node = render_node.originating_node();
+        if activation::link_ancestor(&node).is_some() {
+            return Cursor::Pointer;
+        }
+        let is_text_field = match node.borrow().as_element_opt().map(|element| element.data()) {
+            Some(ElementData::Input(input)) => input.input_type() == InputType::Text,
+            _ => false,
+        };
+        if layout_box.is_text() || is_text_field {
+            Cursor::Text
+        } else {
+            Cursor::Default
+        }
+    }
+
+    /// Start selecting text at the point of the frame the mouse is
+    /// pressed at, clearing the previous selection
+    pub fn select_from(&mut self, x: f32, y: f32) {
+        self.selection = self
+            .layout
+            .root()
+            .as_ref()
+            .and_then(|root| painting::text_position_at(root, x, y + self.scroll_y))
+            .map(TextSelection::collapsed);
+    }
+
+    /// Extend the selection to the point the mouse is dragged to
+    pub fn select_to(&mut self, x: f32, y: f32) {
+        let focus = self
+            .layout
+            .root()
+            .as_ref()
+            .and_then(|root| painting::text_position_at(root, x, y + self.scroll_y));
+        if let (Some(selection), Some(focus)) = (&mut self.selection, focus) {
+            selection.focus = focus;
+        }
+    }
+
+    pub fn selection(&self) -> Option<&TextSelection> {
+        self.selection.as_ref()
+    }
+
+    /// The text copied from the frame: the selection of the focused text
+    /// field if there's one, otherwise the text selected on the page
+    pub fn selected_text(&self) -> Option<String> {
+        let focused = self.document.as_ref().and_then(focus::focused_element);
+        if let Some(element) = focused {
+            if let ElementData::Input(input) = element.borrow().as_element().data() {
+                if let Some(text) = input.selected_text() {
+                    return Some(text);
+                }
+            }
+        }
+
+        let selection = self
+            .selection
+            .as_ref()
+            .filter(|selection| !selection.is_collapsed())?;
+        let root = self.layout.root().as_ref()?;
+        Some(painting::selected_text(root, selection)).filter(|text| !text.is_empty())
+    }
+
+    /// How far the frame is scrolled down, in CSS pixels. The points of
+    /// the frame given to the events are in the scrolled viewport.
+    pub fn scroll_y(&self) -> f32 {
+        self.scroll_y
+    }
+
+    /// Scroll the frame down to a position, no further than the bottom of
+    /// the document
+    pub fn scroll_to(&mut self, y: f32) {
+        let bottom = self
+            .layout
+            .root()
+            .as_ref()
+            .map_or(0., |root| root.content_bottom());
+        let max = (bottom - self.size.1 as f32).max(0.);
+        self.scroll_y = y.max(0.).min(max);
+    }
+
+    /// Scroll a rectangle of the document to the middle of the viewport,
+    /// unless it's already in the viewport
+    fn scroll_into_view(&mut self, rect: &painting::Rect) {
+        let height = self.size.1 as f32;
+        if rect.y >= self.scroll_y && rect.y + rect.height <= self.scroll_y + height {
+            return;
+        }
+        self.scroll_to(rect.y + rect.height / 2. - height / 2.);
+    }
+
+    /// Search a text in the rendered text of the document, highlighting
+    /// the matches & scrolling to the first one
+    pub fn find(&mut self, text: &str) -> Vec<MatchRange> {
+        let matches = self.matches_of(text);
+        self.find = Some(FindInPage {
+            query: text.to_string(),
+            active: if matches.is_empty() { None } else { Some(0) },
+        });
+        if let Some(bounds) = matches.first().and_then(MatchRange::bounds) {
+            self.scroll_into_view(&bounds);
+        }
+        matches
+    }
+
+    /// Move to the next match of the search, or to the previous one, and
+    /// scroll to it. The search wraps around the document.
+    pub fn find_next(&mut self, backwards: bool) -> Option<MatchRange> {
+        let query = self.find.as_ref()?.query.clone();
+        let matches = self.matches_of(&query);
+        let find = self.find.as_mut()?;
+        let count = matches.len();
+        if count == 0 {
+            find.active = None;
+            return None;
+        }
+        let active = match (find.active, backwards) {
+            (None, false) => 0,
+            (None, true) => count - 1,
+            (Some(active), false) => (active % count + 1) % count,
+            (Some(active), true) => (active % count + count - 1) % count,
+        };
+        find.active = Some(active);
+
+        let range = matches[active].clone();
+        if let Some(bounds) = range.bounds() {
+            self.scroll_into_view(&bounds);
+        }
+        Some(range)
+    }
+
+    /// Stop highlighting the matches of the search
+    pub fn stop_finding(&mut self) {
+        self.find = None;
+    }
+
+    /// The matches of the search in the current layout & the index of the
+    /// match moved to
+    pub fn find_matches(&self) -> (Vec<MatchRange>, Option<usize>) {
+        match &self.find {
+            Some(find) => (self.matches_of(&find.query), find.active),
+            None => (Vec::new(), None),
+        }
+    }
+
+    fn matches_of(&self, text: &str) -> Vec<MatchRange> {
+        match self.layout.root() {
+            Some(root) => painting::find(root, text),
+            None => Vec::new(),
+        }
+    }
+
+    /// Deliver a keyboard event to the focused element of the document
+    pub fn dispatch_key_event(&mut self, event: &KeyboardEvent) -> bool {
+        match &self.document {
+            Some(document) => focus::dispatch_key_event(document, event),
+            None => false,
+        }
+    }
+
+    /// Draw on the 2D context of the canvas with an id. Returns false if
+    /// there's no such canvas.
+    pub fn draw_canvas<F>(&mut self, id: &str, draw: F) -> bool
+    where
+        F: FnOnce(&mut CanvasRenderingContext2D),
+    {
+        let canvas = match self
+            .document
+            .as_ref()
+            .and_then(|document| document.borrow().get_element_by_id(id))
+        {
+            Some(canvas) => canvas,
+            None => return false,
+        };
+        let mut canvas = canvas.borrow_mut();
+        match canvas.as_element_mut().data_mut() {
+            ElementData::Canvas(canvas) => {
+                draw(canvas.context_2d_mut());
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Send the request of the form submitted from the document, returning
+    /// the URL & the HTML of the response to navigate to
+    pub fn submit_form(&mut self) -> Option<(String, String)> {
+        let document = self.document.clone()?;
+        let submission = document
+            .borrow_mut()
+            .as_document_mut()
+            .take_form_submission()?;
+        let url = submission.url(document.borrow().as_document());
+        let url = match url {
+            Some(url) => url,
+            None => {
+                log::info!("Invalid form action: {}", submission.action);
+                return None;
+            }
+        };
+
+        match FrameLoader::load_form_submission(&document, &url, submission) {
+            Ok(html) => Some((url.raw().to_string(), html)),
+            Err(e) => {
+                log::info!("Unable to submit form to {}: {}", url.raw(), e);
+                None
+            }
+        }
+    }
+
+    /// Take the URL of the link followed from the document by the last event
+    pub fn take_navigation(&mut self) -> Option<Url> {
+        let document = self.document.as_ref()?;
+        let url = document.borrow_mut().as_document_mut().take_navigation();
+        url
+    }
+
+    /// Take the refresh requested by the document once its delay elapsed
+    /// at the current time of the frame
+    pub fn take_due_refresh(&mut self) -> Option<Refresh> {
+        let document = self.document.as_ref()?;
+        let is_due = document
+            .borrow()
+            .as_document()
+            .refresh()
+            .map_or(false, |refresh| refresh.delay <= self.current_time);
+        if !is_due {
+            return None;
+        }
+        let refresh = document.borrow_mut().as_document_mut().take_refresh();
+        refresh
+    }
+
+    /// Fetch the HTML of a document navigated to from the document of
+    /// the frame
+    pub fn load_url(&self, url: &Url) -> Option<String> {
+        let document = self.document.as_ref()?;
+        match FrameLoader::load_url(document, url) {
+            Ok(html) => Some(html),
+            Err(e) => {
+                log::info!("Unable to load {}: {}", url.raw(), e);
+                None
+            }
+        }
+    }
+
+    /// The title of the document, empty without a `<title>`
+    pub fn title(&self) -> String {
+        self.document
+            .as_ref()
+            .map(Document::title)
+            .unwrap_or_default()
+    }
+
+    /// Run the CSS animations & transitions or not, from the next
+    /// document loaded
+    pub fn set_animations_enabled(&mut self, enabled: bool) {
+        self.layout.animations_enabled = enabled;
+    }
+
+    /// Skip the layout of the contents of the `content-visibility: auto`
+    /// elements outside of the viewport or not, from the next layout
+    pub fn set_lazy_layout(&mut self, enabled: bool) {
+        self.layout.lazy_layout = enabled;
+    }
+
+    /// Scale the font sizes of the document, e.g. when the user zooms the
+    /// text only. The document is styled & laid out again.
+    #[allow(clippy::float_cmp)]
+    pub fn set_text_zoom(&mut self, zoom: f32) {
+        // setting the zoom the text is already at doesn't restyle it
+        if self.layout.text_zoom == zoom {
+            return;
+        }
+        self.layout.text_zoom = zoom;
+        if let Some(document) = &self.document {
+            self.layout
+                .reflow(self.size, ReflowType::Restyle(document.clone()));
+        }
+    }
+
+    /// Set the color scheme the user prefers, matching the `@media` rules
+    /// of the scheme. The document is styled & laid out again.
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        if self.layout.color_scheme == color_scheme {
+            return;
+        }
+        self.layout.color_scheme = color_scheme;
+        if let Some(document) = &self.document {
+            self.layout
+                .reflow(self.size, ReflowType::All(document.clone()));
+        }
+    }
+
+    /// Prefer reduced motion or not, matching the `prefers-reduced-motion`
+    /// media feature. The document is styled & laid out again.
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        if self.layout.reduced_motion == reduced_motion {
+            return;
+        }
+        self.layout.reduced_motion = reduced_motion;
+        if let Some(document) = &self.document {
+            self.layout
+                .reflow(self.size, ReflowType::All(document.clone()));
+        }
+    }
+
+    /// Activate the `forced-colors` media feature or not. The document is
+    /// styled & laid out again.
+    pub fn set_forced_colors(&mut self, forced_colors: bool) {
+        if self.layout.forced_colors == forced_colors {
+            return;
+        }
+        self.layout.forced_colors = forced_colors;
+        if let Some(document) = &self.document {
+            self.layout
+                .reflow(self.size, ReflowType::All(document.clone()));
+        }
+    }
+
+    /// Stop parsing, styling & laying out the documents once a token is
+    /// cancelled, keeping what was done by then
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.layout.cancellation = token;
+    }
+
+    /// Errors encountered while parsing the HTML & CSS of the current document
+    pub fn parse_errors(&self) -> Vec<ParseError> {
+        self.parse_errors.borrow().clone()
     }
 
     pub fn layout(&self) -> &FrameLayout {
         &self.layout
     }
+
+    /// The style of the pages the document is printed on, as large as
+    /// the frame without `@page` rules
+    pub fn page_style(&self) -> PageStyle {
+        let (width, height) = self.size;
+        let stylesheets = self
+            .document
+            .as_ref()
+            .map(document_stylesheets)
+            .unwrap_or_default();
+        let rules = page_rules(
+            &self.layout.user_agent_stylesheet,
+            self.layout.user_stylesheet.as_ref(),
+            &self.layout.author_stylesheets,
+            &stylesheets,
+            &self.layout.media_features(self.size),
+        );
+        PageStyle::from_rules(&rules, (width as f32, height as f32))
+    }
+
+    /// The nodes & the memory of the trees of the current document
+    pub fn heap_stats(&self) -> HeapStats {
+        let mut stats = HeapStats {
+            live_dom_nodes: dom::node::live_node_count(),
+            ..HeapStats::default()
+        };
+        if let Some(document) = &self.document {
+            stats.dom = heap_stats::dom_stats(document);
+        }
+        if let Some(render_tree) = &self.layout.render_tree {
+            stats.styles = heap_stats::style_stats(render_tree);
+        }
+        if let Some(root) = &self.layout.layout_tree {
+            stats.layout = heap_stats::layout_stats(root);
+        }
+        stats
+    }
 }
 
 impl FrameLayout {
@@ -62,6 +677,29 @@ impl FrameLayout {
         Self {
             layout_tree: None,
             render_tree: None,
+            user_agent_stylesheet: user_agent_stylesheet(),
+            quirks_stylesheet: quirks_stylesheet(),
+            user_stylesheet: None,
+            author_stylesheets: Vec::new(),
+            current_time: Duration::ZERO,
+            animations_enabled: true,
+            lazy_layout: false,
+            text_zoom: 1.,
+            color_scheme: ColorScheme::default(),
+            reduced_motion: false,
+            forced_colors: false,
+            cancellation: CancellationToken::new(),
+        }
+    }
+
+    /// The values of the media features the `@media` rules match
+    fn media_features(&self, size: FrameSize) -> MediaFeatures {
+        let (width, height) = size;
+        MediaFeatures {
+            color_scheme: self.color_scheme,
+            reduced_motion: self.reduced_motion,
+            forced_colors: self.forced_colors,
+            ..MediaFeatures::new(width as f32, height as f32)
         }
     }
 
@@ -69,63 +707,361 @@ impl FrameLayout {
         &self.layout_tree
     }
 
-    pub fn recalculate_styles(&mut self, document: NodeRef) {
-        let document_clone = document.clone();
-        let document_borrow = document_clone.borrow();
-        let document_borrow = document_borrow.as_document();
-        let stylesheets = document_borrow.stylesheets();
-        // TODO: cache this step so we don't have to flat map on every reflow
-        let contextual_rules: Vec<ContextualRule> = stylesheets
-            .iter()
-            .flat_map(|stylesheet| {
-                stylesheet.iter().map(|rule| match rule {
-                    CSSRule::Style(style) => ContextualRule {
-                        inner: style,
-                        location: CSSLocation::Embedded,
-                        origin: CascadeOrigin::User,
-                    },
-                })
-            })
-            .collect();
+    pub fn recalculate_styles(&mut self, size: FrameSize, document: NodeRef) {
+        let _span = profiler::span("style");
+        // the document is borrowed mutably while its style mutations are cleared
+        let stylesheets = document_stylesheets(&document);
+        let shadow_stylesheets = shadow_stylesheets(&document);
+        let features = self.media_features(size);
+        let contextual_rules = contextual_rules(
+            &self.user_agent_stylesheet,
+            document_quirks(&self.quirks_stylesheet, &document),
+            self.user_stylesheet.as_ref(),
+            &self.author_stylesheets,
+            &stylesheets,
+            &shadow_stylesheets,
+            &features,
+        );
 
         log::debug!("Building render tree");
-        self.render_tree = Some(build_render_tree(document, &contextual_rules));
+        let mut render_tree = build_render_tree_with_lengths(
+            document.clone(),
+            &contextual_rules,
+            lengths(size, self.text_zoom),
+        );
+        // the clock of the new tree starts at the current time of the document
+        render_tree.transitions.sample(self.current_time);
+        render_tree
+            .transitions
+            .set_enabled(self.animations_enabled && !self.reduced_motion);
+        render_tree
+            .animations
+            .set_reduced_motion(self.reduced_motion);
+        render_tree.animations.set_keyframes(&keyframes_rules(
+            &self.user_agent_stylesheet,
+            self.user_stylesheet.as_ref(),
+            &self.author_stylesheets,
+            &stylesheets,
+            &features,
+        ));
+        if self.animations_enabled {
+            update_animations(&mut render_tree, &contextual_rules, self.current_time);
+        }
+        self.render_tree = Some(render_tree);
         log::debug!("Finished render tree");
     }
 
+    pub fn restyle(&mut self, size: FrameSize, document: NodeRef) {
+        let _span = profiler::span("style");
+        let features = self.media_features(size);
+        let render_tree = match &mut self.render_tree {
+            Some(render_tree) => render_tree,
+            None => return self.recalculate_styles(size, document),
+        };
+        render_tree.set_lengths(lengths(size, self.text_zoom));
+        let stylesheets = document_stylesheets(&document);
+        let shadow_stylesheets = shadow_stylesheets(&document);
+        let contextual_rules = contextual_rules(
+            &self.user_agent_stylesheet,
+            document_quirks(&self.quirks_stylesheet, &document),
+            self.user_stylesheet.as_ref(),
+            &self.author_stylesheets,
+            &stylesheets,
+            &shadow_stylesheets,
+            &features,
+        );
+
+        log::debug!("Restyling render tree");
+        restyle_render_tree(render_tree, &contextual_rules);
+        render_tree.animations.set_keyframes(&keyframes_rules(
+            &self.user_agent_stylesheet,
+            self.user_stylesheet.as_ref(),
+            &self.author_stylesheets,
+            &stylesheets,
+            &features,
+        ));
+        if self.animations_enabled {
+            update_animations(render_tree, &contextual_rules, self.current_time);
+        }
+        log::debug!("Finished restyling render tree");
+    }
+
+    /// Apply the values of the running animations & transitions at a time
+    /// and lay out the boxes whose styles changed
+    pub fn sample_animations(&mut self, size: FrameSize, document: &NodeRef, time: Duration) {
+        self.current_time = time;
+        let features = self.media_features(size);
+        let render_tree = match &mut self.render_tree {
+            Some(render_tree) => render_tree,
+            None => return,
+        };
+        let stylesheets = document_stylesheets(document);
+        let shadow_stylesheets = shadow_stylesheets(document);
+        let contextual_rules = contextual_rules(
+            &self.user_agent_stylesheet,
+            document_quirks(&self.quirks_stylesheet, document),
+            self.user_stylesheet.as_ref(),
+            &self.author_stylesheets,
+            &stylesheets,
+            &shadow_stylesheets,
+            &features,
+        );
+
+        let mut changed = sample_animations(render_tree, &contextual_rules, time);
+        // transitions win over animations
+        changed |= render_tree.transitions.sample(time);
+        if changed {
+            self.reflow(size, ReflowType::LayoutOnly);
+        }
+    }
+
+    pub fn has_running_animations(&self) -> bool {
+        match &self.render_tree {
+            Some(render_tree) => {
+                render_tree.transitions.is_running() || render_tree.animations.is_running()
+            }
+            None => false,
+        }
+    }
+
     pub fn recalculate_layout(&mut self, size: FrameSize) {
+        let _span = profiler::span("layout");
         if let Some(render_tree) = &self.render_tree {
             log::debug!("Building layout tree");
-            self.layout_tree = build_layout_tree(render_tree);
+            self.layout_tree = match self.layout_tree.take() {
+                Some(previous) => rebuild_layout_tree(render_tree, previous),
+                None => build_layout_tree(render_tree),
+            };
             log::debug!("Finished layout tree");
 
             if let Some(layout_tree) = &mut self.layout_tree {
                 let (width, height) = size;
+                let viewport = Rect {
+                    x: 0.,
+                    y: 0.,
+                    width: width as f32,
+                    height: height as f32,
+                };
 
-                layout::compute_layout(
-                    layout_tree,
-                    &Rect {
-                        x: 0.,
-                        y: 0.,
-                        width: width as f32,
-                        height: height as f32,
-                    },
-                );
+                if self.lazy_layout {
+                    layout::compute_lazy_layout(layout_tree, &viewport, &viewport);
+                } else {
+                    layout::compute_layout(layout_tree, &viewport);
+                }
             }
         }
     }
 
+    /// Start loading the background images used by the styles
+    fn load_background_images(&self, document: &NodeRef) {
+        fn visit(render_node: &RenderNodeRef, document: &NodeRef) {
+            let render_node = render_node.borrow();
+            if let Some(url) = render_node.style().background_image().url() {
+                let resolved = document.borrow().as_document().parse_url(url);
+                match resolved {
+                    Some(url) => load_image(document, &url),
+                    None => log::info!("Invalid background image URL: {}", url),
+                }
+            }
+            for child in &render_node.children {
+                visit(child, document);
+            }
+        }
+
+        if let Some(root) = self
+            .render_tree
+            .as_ref()
+            .and_then(|tree| tree.root.as_ref())
+        {
+            visit(root, document);
+        }
+    }
+
     pub fn reflow(&mut self, size: FrameSize, type_: ReflowType) {
         log::debug!("Start reflowing with type: {:?}", type_);
-        match &type_ {
+        let token = self.cancellation.clone();
+        cancellation::with_token(&token, || self.reflow_with_token(size, &type_));
+        log::debug!("Finished reflowing with type: {:?}", type_);
+    }
+
+    fn reflow_with_token(&mut self, size: FrameSize, type_: &ReflowType) {
+        match type_ {
             ReflowType::LayoutOnly => {
                 self.recalculate_layout(size);
             }
             ReflowType::All(document) => {
-                self.recalculate_styles(document.clone());
+                self.recalculate_styles(size, document.clone());
+                self.load_background_images(document);
+                self.recalculate_layout(size);
+            }
+            ReflowType::Restyle(document) => {
+                self.restyle(size, document.clone());
+                self.load_background_images(document);
                 self.recalculate_layout(size);
             }
         }
-        log::debug!("Finished reflowing with type: {:?}", type_);
     }
 }
+
+/// The sizes the relative lengths are computed against in a frame
+fn lengths(size: FrameSize, text_zoom: f32) -> LengthContext {
+    let (width, height) = size;
+    LengthContext::new(width as f32, height as f32).with_text_zoom(text_zoom)
+}
+
+fn document_stylesheets(document: &NodeRef) -> Vec<Rc<StyleSheet>> {
+    document.borrow().as_document().stylesheets().to_vec()
+}
+
+/// The stylesheets of the shadow roots of a document, with their shadow root
+fn shadow_stylesheets(document: &NodeRef) -> Vec<(NodeRef, Rc<StyleSheet>)> {
+    let shadow_roots = document.borrow().as_document().shadow_roots();
+    shadow_roots
+        .into_iter()
+        .flat_map(|shadow_root| {
+            let stylesheets = shadow_root
+                .borrow()
+                .as_document_fragment_opt()
+                .map(|fragment| fragment.stylesheets().to_vec())
+                .unwrap_or_default();
+            stylesheets
+                .into_iter()
+                .map(move |stylesheet| (shadow_root.clone(), stylesheet))
+        })
+        .collect()
+}
+
+/// The quirks stylesheet if the document is in quirks mode
+fn document_quirks<'a>(
+    quirks_stylesheet: &'a StyleSheet,
+    document: &NodeRef,
+) -> Option<&'a StyleSheet> {
+    if document.borrow().as_document().is_quirks_mode() {
+        Some(quirks_stylesheet)
+    } else {
+        None
+    }
+}
+
+// TODO: cache this step so we don't have to flat map on every reflow
+fn contextual_rules<'a>(
+    user_agent_stylesheet: &'a StyleSheet,
+    quirks_stylesheet: Option<&'a StyleSheet>,
+    user_stylesheet: Option<&'a StyleSheet>,
+    author_stylesheets: &'a [StyleSheet],
+    stylesheets: &'a [Rc<StyleSheet>],
+    shadow_stylesheets: &'a [(NodeRef, Rc<StyleSheet>)],
+    features: &MediaFeatures,
+) -> Vec<ContextualRule<'a>> {
+    let user_agent_rules = std::iter::once(user_agent_stylesheet)
+        .chain(quirks_stylesheet)
+        .flat_map(|stylesheet| applicable_rules(stylesheet, features))
+        .filter_map(|rule| match rule {
+            CSSRule::Style(style) => Some(ContextualRule {
+                inner: style,
+                location: CSSLocation::External,
+                origin: CascadeOrigin::UserAgent,
+            }),
+            _ => None,
+        });
+
+    let user_rules = user_stylesheet.into_iter().flat_map(|stylesheet| {
+        applicable_rules(stylesheet, features)
+            .into_iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::External,
+                    origin: CascadeOrigin::User,
+                }),
+                _ => None,
+            })
+    });
+
+    let author_rules = author_stylesheets.iter().flat_map(|stylesheet| {
+        applicable_rules(stylesheet, features)
+            .into_iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::External,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+    });
+
+    let document_rules = stylesheets.iter().flat_map(|stylesheet| {
+        applicable_rules(stylesheet, features)
+            .into_iter()
+            .filter_map(|rule| match rule {
+                CSSRule::Style(style) => Some(ContextualRule {
+                    inner: style,
+                    location: CSSLocation::Embedded,
+                    origin: CascadeOrigin::Author,
+                }),
+                _ => None,
+            })
+    });
+
+    // the rules of a shadow root only match the nodes of its tree
+    let shadow_tree_rules = shadow_stylesheets
+        .iter()
+        .flat_map(|(shadow_root, stylesheet)| {
+            applicable_rules(stylesheet, features)
+                .into_iter()
+                .filter_map(move |rule| match rule {
+                    CSSRule::Style(style) => Some(ContextualRule {
+                        inner: style,
+                        location: CSSLocation::ShadowTree(shadow_root.clone()),
+                        origin: CascadeOrigin::Author,
+                    }),
+                    _ => None,
+                })
+        });
+
+    user_agent_rules
+        .chain(user_rules)
+        .chain(author_rules)
+        .chain(document_rules)
+        .chain(shadow_tree_rules)
+        .collect()
+}
+
+fn page_rules<'a>(
+    user_agent_stylesheet: &'a StyleSheet,
+    user_stylesheet: Option<&'a StyleSheet>,
+    author_stylesheets: &'a [StyleSheet],
+    stylesheets: &'a [Rc<StyleSheet>],
+    features: &MediaFeatures,
+) -> Vec<&'a PageRule> {
+    std::iter::once(user_agent_stylesheet)
+        .chain(user_stylesheet)
+        .chain(author_stylesheets)
+        .chain(stylesheets.iter().map(|stylesheet| &**stylesheet))
+        .flat_map(|stylesheet| applicable_rules(stylesheet, features))
+        .filter_map(|rule| match rule {
+            CSSRule::Page(page) => Some(page),
+            _ => None,
+        })
+        .collect()
+}
+
+fn keyframes_rules<'a>(
+    user_agent_stylesheet: &'a StyleSheet,
+    user_stylesheet: Option<&'a StyleSheet>,
+    author_stylesheets: &'a [StyleSheet],
+    stylesheets: &'a [Rc<StyleSheet>],
+    features: &MediaFeatures,
+) -> Vec<&'a KeyframesRule> {
+    std::iter::once(user_agent_stylesheet)
+        .chain(user_stylesheet)
+        .chain(author_stylesheets)
+        .chain(stylesheets.iter().map(|stylesheet| &**stylesheet))
+        .flat_map(|stylesheet| applicable_rules(stylesheet, features))
+        .filter_map(|rule| match rule {
+            CSSRule::Keyframes(keyframes) => Some(keyframes),
+            _ => None,
+        })
+        .collect()
+}
@@ -0,0 +1,153 @@
+/// The nodes & the approximate memory retained by the trees of a page,
+/// to find what keeps growing between renders. The bytes are estimated
+/// from the sizes of the nodes & the data they own, without the
+/// overhead of the allocator.
+use dom::dom_ref::NodeRef;
+use dom::node::Node;
+use layout::layout_box::LayoutBox;
+use layout::text::TextFragment;
+use std::cell::RefCell;
+use std::fmt;
+use std::mem::{size_of, size_of_val};
+use std::ops::AddAssign;
+use style::render_tree::{RenderNode, RenderNodeRef, RenderTree};
+use style::value_processing::Value;
+
+/// The strong & weak counts of a reference counted allocation
+const RC_HEADER: usize = 2 * size_of::<usize>();
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct TreeStats {
+    pub nodes: usize,
+    pub bytes: usize,
+}
+
+impl AddAssign for TreeStats {
+    fn add_assign(&mut self, other: TreeStats) {
+        self.nodes += other.nodes;
+        self.bytes += other.bytes;
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
+pub struct HeapStats {
+    /// The nodes of the document
+    pub dom: TreeStats,
+    /// The DOM nodes alive on the thread, in a document or not. More
+    /// nodes alive than in the documents hints at a leak, e.g. nodes
+    /// kept alive by a reference cycle.
+    pub live_dom_nodes: usize,
+    /// The computed styles of the nodes, with the values they share
+    pub styles: TreeStats,
+    pub layout: TreeStats,
+    /// The textures & buffers of the painter
+    pub gpu_bytes: usize,
+}
+
+impl fmt::Display for HeapStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let trees = [
+            ("DOM", &self.dom),
+            ("Styles", &self.styles),
+            ("Layout", &self.layout),
+        ];
+        for (name, stats) in trees.iter() {
+            writeln!(
+                f,
+                "{:<8}{:>10} nodes{:>14}",
+                name,
+                stats.nodes,
+                format_bytes(stats.bytes)
+            )?;
+        }
+        writeln!(f, "{:<8}{:>10} nodes alive", "DOM", self.live_dom_nodes)?;
+        write!(f, "{:<8}{:>30}", "GPU", format_bytes(self.gpu_bytes))
+    }
+}
+
+fn format_bytes(bytes: usize) -> String {
+    match bytes {
+        bytes if bytes >= 1024 * 1024 => format!("{:.1} MiB", bytes as f64 / (1024. * 1024.)),
+        bytes if bytes >= 1024 => format!("{:.1} KiB", bytes as f64 / 1024.),
+        bytes => format!("{} B", bytes),
+    }
+}
+
+pub fn dom_stats(document: &NodeRef) -> TreeStats {
+    let mut stats = TreeStats::default();
+    add_dom_node(&document.borrow(), &mut stats);
+    stats
+}
+
+fn add_dom_node(node: &Node, stats: &mut TreeStats) {
+    stats.nodes += 1;
+    stats.bytes += RC_HEADER + size_of::<RefCell<Node>>();
+    if let Some(element) = node.as_element_opt() {
+        for (name, value) in element.attributes().iter() {
+            stats.bytes += size_of_val(name) + size_of_val(value) + value.len();
+        }
+    } else if let Some(text) = node.as_text_opt() {
+        stats.bytes += text.get_data().len();
+    } else if let Some(comment) = node.as_comment_opt() {
+        stats.bytes += comment.get_data().len();
+    }
+
+    for child in node.child_nodes() {
+        add_dom_node(&child.borrow(), stats);
+    }
+}
+
+pub fn style_stats(render_tree: &RenderTree) -> TreeStats {
+    let mut stats = TreeStats::default();
+    if let Some(root) = &render_tree.root {
+        add_render_node(root, &mut stats);
+    }
+    // the nodes share the values of the style cache
+    stats.bytes += render_tree.style_cache.len() * (RC_HEADER + size_of::<Value>());
+    stats
+}
+
+fn add_render_node(node: &RenderNodeRef, stats: &mut TreeStats) {
+    let node = node.borrow();
+    stats.nodes += 1;
+    stats.bytes += RC_HEADER
+        + size_of::<RefCell<RenderNode>>()
+        + node.properties.heap_size()
+        + node.matched_rules.capacity() * size_of::<usize>()
+        + node.children.capacity() * size_of::<RenderNodeRef>();
+
+    for child in &node.children {
+        add_render_node(child, stats);
+    }
+}
+
+pub fn layout_stats(root: &LayoutBox) -> TreeStats {
+    let mut stats = TreeStats::default();
+    add_layout_box(root, &mut stats);
+    stats
+}
+
+fn add_layout_box(layout_box: &LayoutBox, stats: &mut TreeStats) {
+    stats.nodes += 1;
+    stats.bytes +=
+        size_of::<LayoutBox>() + layout_box.text_fragments.capacity() * size_of::<TextFragment>();
+    for fragment in &layout_box.text_fragments {
+        stats.bytes += fragment.text.len();
+    }
+
+    for child in &layout_box.children {
+        add_layout_box(child, stats);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn format_sizes() {
+        assert_eq!(format_bytes(12), "12 B");
+        assert_eq!(format_bytes(2048), "2.0 KiB");
+        assert_eq!(format_bytes(3 * 1024 * 1024 + 512 * 1024), "3.5 MiB");
+    }
+}
@@ -0,0 +1,91 @@
+/// A document visited in a tab
+#[derive(Debug, Clone, PartialEq)]
+pub struct HistoryEntry {
+    pub url: String,
+    pub html: String,
+}
+
+/// The session history of a tab, moved through with `Renderer::go_back`
+/// & `Renderer::go_forward`. Navigating to a new document drops the entries after the
+/// current one.
+/// https://html.spec.whatwg.org/multipage/history.html#session-history
+#[derive(Debug, Default)]
+pub struct SessionHistory {
+    entries: Vec<HistoryEntry>,
+    current: Option<usize>,
+}
+
+impl SessionHistory {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn push(&mut self, entry: HistoryEntry) {
+        let next = self.current.map(|index| index + 1).unwrap_or(0);
+        self.entries.truncate(next);
+        self.entries.push(entry);
+        self.current = Some(next);
+    }
+
+    pub fn current(&self) -> Option<&HistoryEntry> {
+        self.entries.get(self.current?)
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        matches!(self.current, Some(index) if index > 0)
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        matches!(self.current, Some(index) if index + 1 < self.entries.len())
+    }
+
+    /// Move to the previous entry & return it
+    pub fn back(&mut self) -> Option<&HistoryEntry> {
+        if !self.can_go_back() {
+            return None;
+        }
+        self.current = self.current.map(|index| index - 1);
+        self.current()
+    }
+
+    /// Move to the next entry & return it
+    pub fn forward(&mut self) -> Option<&HistoryEntry> {
+        if !self.can_go_forward() {
+            return None;
+        }
+        self.current = self.current.map(|index| index + 1);
+        self.current()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(url: &str) -> HistoryEntry {
+        HistoryEntry {
+            url: url.to_string(),
+            html: String::new(),
+        }
+    }
+
+    #[test]
+    fn navigate_back_and_forward() {
+        let mut history = SessionHistory::new();
+        assert!(!history.can_go_back());
+
+        history.push(entry("a"));
+        history.push(entry("b"));
+        history.push(entry("c"));
+        assert_eq!(history.back(), Some(&entry("b")));
+        assert_eq!(history.back(), Some(&entry("a")));
+        assert_eq!(history.back(), None);
+        assert_eq!(history.forward(), Some(&entry("b")));
+
+        // navigating drops the forward entries
+        history.push(entry("d"));
+        assert!(!history.can_go_forward());
+        assert_eq!(history.back(), Some(&entry("b")));
+        assert_eq!(history.current(), Some(&entry("b")));
+    }
+}
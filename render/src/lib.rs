@@ -1,23 +1,411 @@
+mod clipboard;
+mod color_scheme;
+mod cursor;
 mod frame;
+mod heap_stats;
+mod history;
 mod loader;
 mod page;
+mod profiler;
+mod render_handle;
 mod renderer;
+mod scheduler;
+mod tabs;
+mod user_agent;
+mod viewport;
+mod zoom;
 
 use gfx::Bitmap;
-use renderer::{Renderer, RendererInitializeParams};
+
+use std::path::PathBuf;
+use std::time::Duration;
+
+pub use color_scheme::system_color_scheme;
+pub use cursor::cursor_icon;
+pub use dom::event::{KeyEventKind, KeyboardEvent, Modifiers};
+pub use gfx::{gpu_adapter, Antialiasing, ColorSpace, GpuBackend, PaintBackend, PixelFormat};
+pub use heap_stats::{HeapStats, TreeStats};
+pub use io::parse_error::ParseError;
+pub use loaders::http::HttpConfig;
+pub use loaders::scheme::SchemeHandler;
+pub use profiler::{finish_profiling, start_profiling, Profile};
+pub use render_handle::{Cancelled, RenderHandle};
+pub use renderer::{Renderer, RendererInitializeParams};
+pub use scheduler::{FrameScheduler, DEFAULT_FPS};
+pub use style::media::ColorScheme;
+pub use tabs::TabId;
+pub use url::Url;
+pub use viewport::{
+    ViewportConfig, MAX_BITMAP_AREA, MAX_DEVICE_PIXEL_RATIO, MAX_VIEWPORT_DIMENSION,
+};
+pub use zoom::{Zoom, ZoomMode, MAX_ZOOM, MIN_ZOOM};
+
+/// What a render reports besides its output
+pub struct RenderDiagnostics {
+    pub parse_errors: Vec<ParseError>,
+    /// The memory used by the renderer once the page is rendered
+    pub heap_stats: HeapStats,
+}
+
+impl RenderDiagnostics {
+    fn of(renderer: &Renderer) -> Self {
+        Self {
+            parse_errors: renderer.parse_errors(),
+            heap_stats: renderer.heap_stats(),
+        }
+    }
+}
+
+pub struct RenderOnceOutput {
+    pub bitmap: Bitmap,
+    /// The size of the bitmap in device pixels, which is taller than
+    /// the viewport for full page renders of long documents
+    pub size: (u32, u32),
+    pub diagnostics: RenderDiagnostics,
+}
+
+pub struct RenderPdfOutput {
+    /// The bytes of the PDF file
+    pub pdf: Vec<u8>,
+    /// The size of the page in CSS pixels
+    pub size: (u32, u32),
+    pub diagnostics: RenderDiagnostics,
+}
+
+pub struct RenderSvgOutput {
+    /// The SVG document
+    pub svg: String,
+    /// The size of the document in CSS pixels
+    pub size: (u32, u32),
+    pub diagnostics: RenderDiagnostics,
+}
+
+pub struct RenderAnimationOutput {
+    /// The frames rendered, one every `frame_duration`
+    pub frames: Vec<Bitmap>,
+    pub frame_duration: Duration,
+    /// The size of the frames in device pixels
+    pub size: (u32, u32),
+    pub diagnostics: RenderDiagnostics,
+}
+
+pub struct RenderPagesOutput {
+    /// The bitmaps of the pages, in order
+    pub pages: Vec<Bitmap>,
+    /// The size of the pages, from the `@page` rules of the document
+    pub size: (u32, u32),
+    pub diagnostics: RenderDiagnostics,
+}
 
 pub fn version() -> &'static str {
     env!("CARGO_PKG_VERSION")
 }
 
-pub async fn render_once(html: String, size: (u32, u32)) -> Bitmap {
-    let mut renderer = Renderer::new().await;
+/// The settings of a render shared by all its outputs
+#[derive(Debug, Clone)]
+pub struct RenderOptions {
+    pub viewport: ViewportConfig,
+    /// How the bitmaps are painted, the vector outputs are always painted
+    /// on the CPU without antialiasing
+    pub antialiasing: Antialiasing,
+    pub gpu_backend: Option<GpuBackend>,
+    pub output_color_space: ColorSpace,
+    /// Whether the same page always gives the same pixels, painted on the
+    /// CPU without animations
+    pub deterministic: bool,
+    pub user_agent_css: Option<String>,
+    pub user_css: Option<String>,
+    pub http_config: HttpConfig,
+    /// Where the cookies of the pages are kept between the renders
+    pub profile_dir: Option<PathBuf>,
+    /// Cancels the render, e.g. when it takes longer than its timeout
+    pub handle: RenderHandle,
+    /// The height the full page bitmaps are cut at, in device pixels
+    pub max_full_page_height: u32,
+}
+
+impl RenderOptions {
+    pub fn new(viewport: ViewportConfig) -> Self {
+        Self {
+            viewport,
+            antialiasing: Antialiasing::default(),
+            gpu_backend: None,
+            output_color_space: ColorSpace::default(),
+            deterministic: false,
+            user_agent_css: None,
+            user_css: None,
+            http_config: HttpConfig::default(),
+            profile_dir: None,
+            handle: RenderHandle::new(),
+            max_full_page_height: MAX_VIEWPORT_DIMENSION,
+        }
+    }
+}
+
+/// Create a renderer painting bitmaps with the settings of a render,
+/// which can be given to `render_with` to render several pages
+pub async fn bitmap_renderer<'a>(options: &RenderOptions) -> Renderer<'a> {
+    let mut renderer = painting_renderer(options).await;
+    // without animations started by the time the page takes to load
+    if options.deterministic {
+        renderer.set_animations_enabled(false);
+    }
+    renderer
+}
+
+/// Create a renderer on the backend of a render, the CPU for the
+/// deterministic renders as the same page always gives the same pixels
+/// on the CPU
+async fn painting_renderer<'a>(options: &RenderOptions) -> Renderer<'a> {
+    let mut renderer = if options.deterministic {
+        Renderer::with_backend(PaintBackend::Cpu, options.antialiasing).await
+    } else {
+        Renderer::with_gpu_backend(options.gpu_backend, options.antialiasing).await
+    };
+    renderer.set_output_color_space(options.output_color_space);
+    renderer
+}
+
+/// Load a document in a renderer with the viewport & the settings of a render
+fn load_page(renderer: &mut Renderer, html: String, options: &RenderOptions) {
+    let viewport = &options.viewport;
+    renderer.initialize(RendererInitializeParams {
+        viewport: viewport.size(),
+        device_pixel_ratio: viewport.device_pixel_ratio,
+    });
+    renderer.set_zoom(viewport.zoom);
+    renderer.set_color_scheme(viewport.color_scheme);
+    renderer.set_reduced_motion(viewport.reduced_motion);
+    renderer.set_forced_colors(viewport.forced_colors);
+    renderer.set_cancellation(options.handle.token());
+    renderer.set_max_full_page_height(options.max_full_page_height);
 
-    renderer.initialize(RendererInitializeParams { viewport: size });
+    if let Some(css) = &options.user_agent_css {
+        renderer.set_user_agent_css(css);
+    }
+
+    if let Some(css) = &options.user_css {
+        renderer.set_user_css(css);
+    }
+
+    if let Err(e) = renderer.set_http_config(&options.http_config) {
+        log::error!("Unable to configure HTTP: {}", e);
+    }
+
+    if let Some(dir) = &options.profile_dir {
+        renderer.set_profile_dir(dir.clone());
+    }
 
     renderer.load_html(html);
+}
+
+pub async fn render_once(
+    html: String,
+    options: RenderOptions,
+    full_page: bool,
+) -> Result<RenderOnceOutput, Cancelled<RenderOnceOutput>> {
+    let mut renderer = bitmap_renderer(&options).await;
+    render_with(&mut renderer, html, options, full_page).await
+}
+
+/// Render a page into a bitmap with an existing renderer. The renderer
+/// keeps its GPU device & its caches for the next pages, so rendering
+/// many pages with one renderer is faster than with `render_once`.
+pub async fn render_with(
+    renderer: &mut Renderer<'_>,
+    html: String,
+    options: RenderOptions,
+    full_page: bool,
+) -> Result<RenderOnceOutput, Cancelled<RenderOnceOutput>> {
+    // only the content above the fold is relevant to a screenshot of the viewport
+    renderer.set_lazy_layout(!full_page);
+    load_page(renderer, html, &options);
+
+    let (size, bitmap) = if full_page {
+        renderer.output_full_page().await
+    } else {
+        (
+            options.viewport.device_size(),
+            renderer.output_viewport().await,
+        )
+    };
+
+    renderer.save_profile();
+
+    options.handle.finish(RenderOnceOutput {
+        bitmap,
+        size,
+        diagnostics: RenderDiagnostics::of(&renderer),
+    })
+}
+
+/// Render the pages the document would be printed on, sized by its
+/// `@page` rules or as large as the viewport without them
+pub async fn render_pages(
+    html: String,
+    options: RenderOptions,
+) -> Result<RenderPagesOutput, Cancelled<RenderPagesOutput>> {
+    let mut renderer = bitmap_renderer(&options).await;
+    load_page(&mut renderer, html, &options);
+
+    let (size, pages) = renderer.output_pages().await;
+
+    renderer.save_profile();
+
+    options.handle.finish(RenderPagesOutput {
+        pages,
+        size,
+        diagnostics: RenderDiagnostics::of(&renderer),
+    })
+}
+
+/// Render a page with stylesheets applied as if the document linked
+/// them before its own styles, without editing the document
+pub async fn render_once_with_styles(
+    html: String,
+    css_list: Vec<String>,
+    size: (u32, u32),
+) -> RenderOnceOutput {
+    let mut renderer = Renderer::new(Antialiasing::default()).await;
+    renderer.set_author_css(&css_list);
+    load_page(&mut renderer, html, &RenderOptions::new(size.into()));
+
+    let bitmap = renderer.output_viewport().await;
+
+    RenderOnceOutput {
+        bitmap,
+        size,
+        diagnostics: RenderDiagnostics::of(&renderer),
+    }
+}
+
+/// Render the page into a PDF document instead of a bitmap, on a single
+/// page or on the pages of its `@page` rules when paginated
+pub async fn render_pdf(
+    html: String,
+    options: RenderOptions,
+    full_page: bool,
+    paginate: bool,
+) -> Result<RenderPdfOutput, Cancelled<RenderPdfOutput>> {
+    // the vector painters don't need the GPU
+    let mut renderer = Renderer::with_backend(PaintBackend::Cpu, Antialiasing::Off).await;
+    load_page(&mut renderer, html, &options);
+
+    let (size, pdf) = if paginate {
+        renderer.output_paginated_pdf()
+    } else {
+        renderer.output_pdf(full_page)
+    };
+
+    renderer.save_profile();
+
+    options.handle.finish(RenderPdfOutput {
+        pdf,
+        size,
+        diagnostics: RenderDiagnostics::of(&renderer),
+    })
+}
+
+/// Render the page into an SVG document instead of a bitmap
+pub async fn render_svg(
+    html: String,
+    options: RenderOptions,
+    full_page: bool,
+) -> Result<RenderSvgOutput, Cancelled<RenderSvgOutput>> {
+    // the vector painters don't need the GPU
+    let mut renderer = Renderer::with_backend(PaintBackend::Cpu, Antialiasing::Off).await;
+    load_page(&mut renderer, html, &options);
+
+    let (size, svg) = renderer.output_svg(full_page);
+
+    renderer.save_profile();
+
+    options.handle.finish(RenderSvgOutput {
+        svg,
+        size,
+        diagnostics: RenderDiagnostics::of(&renderer),
+    })
+}
+
+/// Render the page at a number of frames per second for a duration,
+/// advancing a virtual clock between the frames so every frame shows
+/// the page at its exact time regardless of how long painting takes
+pub async fn render_animation(
+    html: String,
+    options: RenderOptions,
+    duration: Duration,
+    fps: u32,
+) -> Result<RenderAnimationOutput, Cancelled<RenderAnimationOutput>> {
+    // the animations run on the virtual clock, so they are kept for the
+    // deterministic renders
+    let mut renderer = painting_renderer(&options).await;
+    load_page(&mut renderer, html, &options);
+    let handle = &options.handle;
+
+    let fps = fps.max(1);
+    let frame_duration = Duration::from_secs(1) / fps;
+    let frame_count = scheduler::frame_count(duration, fps);
+
+    let mut frames = Vec::with_capacity(frame_count as usize);
+    let mut scheduler = FrameScheduler::new(fps);
+    scheduler.invalidate();
+    for frame in 0..frame_count {
+        if handle.is_cancelled() {
+            break;
+        }
+        let time = frame_duration * frame;
+        renderer.advance_time(time);
+
+        match frames.last() {
+            // the page doesn't change anymore once its animations are finished
+            Some(last_frame) if !scheduler.needs_frame() => {
+                let last_frame = Bitmap::clone(last_frame);
+                frames.push(last_frame);
+            }
+            _ => {
+                frames.push(renderer.output_viewport().await);
+                scheduler.frame_painted(time, renderer.has_running_animations());
+            }
+        }
+    }
+
+    renderer.save_profile();
+
+    handle.finish(RenderAnimationOutput {
+        frames,
+        frame_duration,
+        size: options.viewport.device_size(),
+        diagnostics: RenderDiagnostics::of(&renderer),
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
 
-    renderer.paint();
+    #[test]
+    fn render_deterministic_animations_on_the_cpu() {
+        let mut options = RenderOptions::new((100, 20).into());
+        options.deterministic = true;
+        assert_eq!(
+            block_on(painting_renderer(&options)).paint_backend(),
+            PaintBackend::Cpu
+        );
 
-    renderer.output().await
+        options.user_css = Some(
+            "@keyframes grow { from { width: 0px; } to { width: 100px; } }
+            div { height: 10px; background-color: red; animation: grow 1s linear; }"
+                .to_string(),
+        );
+        let html = "<div></div>".to_string();
+        let output = block_on(render_animation(html, options, Duration::from_secs(1), 2));
+        let frames = output.ok().unwrap().frames;
+        assert_eq!(frames.len(), 2);
+        // the div is half as wide after half of the animation
+        let pixel = |frame: &Bitmap, x: usize| frame[x * 4..x * 4 + 4].to_vec();
+        assert_eq!(pixel(&frames[1], 25), [255, 0, 0, 255]);
+        assert_eq!(pixel(&frames[1], 75), [255, 255, 255, 255]);
+        assert_ne!(frames[0], frames[1]);
+    }
 }
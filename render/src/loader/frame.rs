@@ -1,20 +1,128 @@
 use dom::document::Document;
+use dom::document_loader::{Destination, LoadRequest};
 use dom::dom_ref::NodeRef;
+use dom::forms::FormSubmission;
 use dom::node::{Node, NodeData};
-use loaders::inprocess::InprocessLoader;
+use io::cancellation::CancellationToken;
+use io::parse_error::ParseErrorSinkRef;
+use loaders::resource::ResourceLoader;
+use loaders::scheme::SchemeHandlers;
+use std::cell::RefCell;
+use std::rc::Rc;
+use url::Url;
 
 pub struct FrameLoader;
 
 impl FrameLoader {
-    pub fn load_html(html: String) -> NodeRef {
+    /// Parse the HTML of a document loaded from a URL, against which the
+    /// relative URLs of the document are resolved. The parsing stops
+    /// early once the token is cancelled.
+    pub fn load_html(
+        html: String,
+        url: Option<Url>,
+        error_sink: ParseErrorSinkRef,
+        schemes: SchemeHandlers,
+        cancellation: CancellationToken,
+    ) -> NodeRef {
         let document = NodeRef::new(Node::new(NodeData::Document(Document::new())));
-        document
-            .borrow_mut()
-            .as_document_mut()
-            .set_loader(InprocessLoader::new());
+        {
+            let mut document = document.borrow_mut();
+            let document = document.as_document_mut();
+            document.set_loader(ResourceLoader::new().with_scheme_handlers(schemes));
+            document.set_error_sink(error_sink.clone());
+            if let Some(url) = url {
+                document.set_url(url);
+            }
+        }
 
         let tokenizer = html::tokenizer::Tokenizer::new(html.chars());
-        let tree_builder = html::tree_builder::TreeBuilder::new(tokenizer, document);
-        tree_builder.run()
+        let mut tree_builder = html::tree_builder::TreeBuilder::new(tokenizer, document);
+        tree_builder.set_error_sink(error_sink);
+        tree_builder.set_cancellation(cancellation);
+        let document = tree_builder.run();
+
+        // the stylesheets block the first rendering of the document, the
+        // other resources are applied as they arrive
+        Self::wait_for(&document, Destination::Style);
+        // the stylesheets are parsed while the tree is built
+        document.borrow_mut().as_document_mut().join_stylesheets();
+        document
+    }
+
+    /// Run the callbacks of the resources of a document that arrived since
+    /// the last call. Returns whether any resource arrived.
+    pub fn process_responses(document: &NodeRef) -> bool {
+        let loader = document.borrow().as_document().loader();
+        let responses = match loader {
+            Some(loader) => loader.borrow_mut().take_responses(),
+            None => return false,
+        };
+        let has_responses = !responses.is_empty();
+        for response in responses {
+            response();
+        }
+        has_responses
+    }
+
+    /// Block until the resources of a document for a destination arrived.
+    /// Returns whether any resource arrived.
+    pub fn wait_for(document: &NodeRef, destination: Destination) -> bool {
+        let loader = document.borrow().as_document().loader();
+        let responses = match loader {
+            Some(loader) => loader.borrow_mut().wait_for(destination),
+            None => return false,
+        };
+        let has_responses = !responses.is_empty();
+        for response in responses {
+            response();
+        }
+        has_responses
+    }
+
+    /// Send the request of a form submitted from a document with the
+    /// loader of the document, returning the HTML of the response
+    pub fn load_form_submission(
+        document: &NodeRef,
+        url: &Url,
+        submission: FormSubmission,
+    ) -> Result<String, String> {
+        let mut request = LoadRequest::new(url.clone()).with_method(submission.method);
+        if let Some(body) = submission.body {
+            request = request.with_body(body);
+        }
+        Self::load_document(document, request)
+    }
+
+    /// Fetch the HTML of a document navigated to from a document, e.g. by
+    /// a `<meta http-equiv=refresh>`, with the loader of the document
+    pub fn load_url(document: &NodeRef, url: &Url) -> Result<String, String> {
+        Self::load_document(document, LoadRequest::new(url.clone()))
+    }
+
+    fn load_document(document: &NodeRef, request: LoadRequest) -> Result<String, String> {
+        let loader = document
+            .borrow()
+            .as_document()
+            .loader()
+            .ok_or_else(|| "Document loader is not set".to_string())?;
+
+        let response = Rc::new(RefCell::new(None));
+        let success = response.clone();
+        let error = response.clone();
+        let request = request
+            .with_destination(Destination::Document)
+            .on_success(Box::new(move |bytes| {
+                *success.borrow_mut() = Some(Ok(bytes))
+            }))
+            .on_error(Box::new(move |e| *error.borrow_mut() = Some(Err(e))));
+        loader.borrow_mut().load(request);
+        Self::wait_for(document, Destination::Document);
+
+        let result = response.borrow_mut().take();
+        match result {
+            Some(Ok(bytes)) => Ok(String::from_utf8_lossy(&bytes).to_string()),
+            Some(Err(e)) => Err(e),
+            None => Err("No response".to_string()),
+        }
     }
 }
@@ -1,25 +1,788 @@
 use super::frame::Frame;
+use super::heap_stats::HeapStats;
+use super::history::{HistoryEntry, SessionHistory};
+use dom::activation;
+use dom::canvas::CanvasRenderingContext2D;
+use dom::document::Refresh;
+use dom::event::KeyboardEvent;
+use dom::visited::{VisitedLinks, VisitedLinksRef};
+use io::cancellation::CancellationToken;
+use loaders::scheme::SchemeHandlers;
+use painting::MatchRange;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use style::media::ColorScheme;
+use style::values::prelude::Cursor;
+use url::Url;
 
 pub struct Page {
     main_frame: Frame,
+    history: SessionHistory,
+    /// The URLs navigated to, shared with the other pages
+    visited_links: VisitedLinksRef,
 }
 
 impl Page {
     pub fn new() -> Self {
-        Self {
+        let mut page = Self {
             main_frame: Frame::new(),
-        }
+            history: SessionHistory::new(),
+            visited_links: Rc::new(RefCell::new(VisitedLinks::new())),
+        };
+        page.set_visited_links(page.visited_links.clone());
+        page
     }
 
     pub fn main_frame(&self) -> &Frame {
         &self.main_frame
     }
 
+    pub fn heap_stats(&self) -> HeapStats {
+        self.main_frame.heap_stats()
+    }
+
     pub fn resize(&mut self, size: (u32, u32)) {
         self.main_frame.resize(size);
     }
 
+    /// Restyle the frames for the DOM mutations since the last reflow
+    pub fn restyle(&mut self) {
+        self.main_frame.restyle();
+    }
+
+    /// Advance the timelines of the frames to a time since the page was
+    /// loaded, following the refresh of the document once it's due
+    pub fn advance_time(&mut self, time: Duration) {
+        self.main_frame.advance_time(time);
+        if let Some(refresh) = self.main_frame.take_due_refresh() {
+            self.follow_refresh(refresh);
+        }
+    }
+
+    /// Navigate to the URL of a refresh, or reload the current document
+    /// https://html.spec.whatwg.org/multipage/semantics.html#shared-declarative-refresh-steps
+    fn follow_refresh(&mut self, refresh: Refresh) {
+        match refresh.url {
+            Some(url) => {
+                if let Some(html) = self.main_frame.load_url(&url) {
+                    self.navigate(url.raw().to_string(), html);
+                }
+            }
+            None => {
+                if let Some(entry) = self.history.current().cloned() {
+                    self.load_entry(entry);
+                }
+            }
+        }
+    }
+
+    pub fn has_running_animations(&self) -> bool {
+        self.main_frame.has_running_animations()
+    }
+
+    pub fn set_user_agent_css(&mut self, css: &str) {
+        self.main_frame.set_user_agent_css(css);
+    }
+
+    pub fn set_user_css(&mut self, css: &str) {
+        self.main_frame.set_user_css(css);
+    }
+
+    pub fn set_author_css(&mut self, css_list: &[String]) {
+        self.main_frame.set_author_css(css_list);
+    }
+
+    pub fn set_animations_enabled(&mut self, enabled: bool) {
+        self.main_frame.set_animations_enabled(enabled);
+    }
+
+    pub fn set_lazy_layout(&mut self, enabled: bool) {
+        self.main_frame.set_lazy_layout(enabled);
+    }
+
+    pub fn set_text_zoom(&mut self, zoom: f32) {
+        self.main_frame.set_text_zoom(zoom);
+    }
+
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        self.main_frame.set_color_scheme(color_scheme);
+    }
+
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        self.main_frame.set_reduced_motion(reduced_motion);
+    }
+
+    pub fn set_forced_colors(&mut self, forced_colors: bool) {
+        self.main_frame.set_forced_colors(forced_colors);
+    }
+
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.main_frame.set_cancellation(token);
+    }
+
+    /// Record the URLs navigated to in a store shared with other pages,
+    /// the links to them matching `:visited`
+    pub fn set_visited_links(&mut self, visited_links: VisitedLinksRef) {
+        self.main_frame.set_visited_links(visited_links.clone());
+        self.visited_links = visited_links;
+    }
+
+    pub fn set_scheme_handlers(&mut self, schemes: SchemeHandlers) {
+        self.main_frame.set_scheme_handlers(schemes);
+    }
+
     pub fn load_html(&mut self, html: String) {
         self.main_frame.load_html(html);
     }
+
+    /// Focus & activate the element clicked at a point of the viewport
+    pub fn click(&mut self, x: f32, y: f32) {
+        if let Some(element) = self.main_frame.focus_at(x, y) {
+            activation::activate(&element);
+        }
+        self.submit_form();
+        self.follow_link();
+    }
+
+    /// Start selecting text where the mouse is pressed
+    pub fn select_from(&mut self, x: f32, y: f32) {
+        self.main_frame.select_from(x, y);
+    }
+
+    /// Extend the selection to where the mouse is dragged
+    pub fn select_to(&mut self, x: f32, y: f32) {
+        self.main_frame.select_to(x, y);
+    }
+
+    pub fn selected_text(&self) -> Option<String> {
+        self.main_frame.selected_text()
+    }
+
+    /// Scroll the viewport down to a position of the document
+    pub fn scroll_to(&mut self, y: f32) {
+        self.main_frame.scroll_to(y);
+    }
+
+    /// Search a text in the document & scroll to the first match
+    pub fn find(&mut self, text: &str) -> Vec<MatchRange> {
+        self.main_frame.find(text)
+    }
+
+    /// Move to the next match of the search, or the previous one
+    pub fn find_next(&mut self, backwards: bool) -> Option<MatchRange> {
+        self.main_frame.find_next(backwards)
+    }
+
+    pub fn stop_finding(&mut self) {
+        self.main_frame.stop_finding();
+    }
+
+    /// The cursor to show while the mouse is over a point of the viewport
+    pub fn cursor_at(&self, x: f32, y: f32) -> Cursor {
+        self.main_frame.cursor_at(x, y)
+    }
+
+    pub fn dispatch_key_event(&mut self, event: &KeyboardEvent) -> bool {
+        let handled = self.main_frame.dispatch_key_event(event);
+        self.submit_form();
+        self.follow_link();
+        handled
+    }
+
+    /// Draw on the 2D context of a canvas of the main frame by its id
+    pub fn draw_canvas<F>(&mut self, id: &str, draw: F) -> bool
+    where
+        F: FnOnce(&mut CanvasRenderingContext2D),
+    {
+        self.main_frame.draw_canvas(id, draw)
+    }
+
+    /// Navigate to the response of the form submitted by the last event
+    fn submit_form(&mut self) {
+        if let Some((url, html)) = self.main_frame.submit_form() {
+            self.navigate(url, html);
+        }
+    }
+
+    /// Navigate to the link followed by the last event. Links to a
+    /// fragment of the current document don't load it again.
+    fn follow_link(&mut self) {
+        let url = match self.main_frame.take_navigation() {
+            Some(url) => url,
+            None => return,
+        };
+        let current = self.url().and_then(|url| Url::parse(url).ok());
+        if url.fragment().is_some()
+            && current.map_or(false, |current| {
+                current.without_fragment() == url.without_fragment()
+            })
+        {
+            return;
+        }
+        if let Some(html) = self.main_frame.load_url(&url) {
+            self.navigate(url.raw().to_string(), html);
+        }
+    }
+
+    /// Load a document & add it to the session history
+    pub fn navigate(&mut self, url: String, html: String) {
+        if let Ok(url) = Url::parse(&url) {
+            self.visited_links.borrow_mut().add(&url);
+        }
+        let entry = HistoryEntry { url, html };
+        self.history.push(entry.clone());
+        self.load_entry(entry);
+    }
+
+    fn load_entry(&mut self, entry: HistoryEntry) {
+        let url = Url::parse(&entry.url).ok();
+        self.main_frame.load_document(entry.html, url);
+    }
+
+    /// The title of the current document, shown in the title of the tab
+    pub fn title(&self) -> String {
+        self.main_frame.title()
+    }
+
+    /// The URL of the current document in the history
+    pub fn url(&self) -> Option<&str> {
+        self.history.current().map(|entry| entry.url.as_str())
+    }
+
+    pub fn history(&self) -> &SessionHistory {
+        &self.history
+    }
+
+    /// Load the previous document of the session history. Returns false
+    /// if there's no previous document.
+    pub fn go_back(&mut self) -> bool {
+        match self.history.back().cloned() {
+            Some(entry) => {
+                self.load_entry(entry);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Load the next document of the session history
+    pub fn go_forward(&mut self) -> bool {
+        match self.history.forward().cloned() {
+            Some(entry) => {
+                self.load_entry(entry);
+                true
+            }
+            None => false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use dom::dom_ref::NodeRef;
+    use dom::elements::ElementData;
+    use dom::focus;
+    use painting::Rect;
+    use style::render_tree::RenderNodeRef;
+
+    fn focused_element(page: &Page) -> NodeRef {
+        let layout = page.main_frame().layout();
+        let root = layout.root().as_ref().unwrap().render_node.clone().unwrap();
+        let document = root.borrow().node.borrow().owner_document().unwrap();
+        focus::focused_element(&document).unwrap()
+    }
+
+    #[test]
+    fn apply_quirks_styles() {
+        fn find(node: &RenderNodeRef, tag_name: &str) -> Option<RenderNodeRef> {
+            let is_match = node
+                .borrow()
+                .node
+                .borrow()
+                .as_element_opt()
+                .map_or(false, |element| element.has_tag_name(tag_name));
+            if is_match {
+                return Some(node.clone());
+            }
+            let children = node.borrow().children.clone();
+            children.iter().find_map(|child| find(child, tag_name))
+        }
+        let form_margin = |html: &str| {
+            let mut page = Page::new();
+            page.resize((300, 300));
+            page.load_html(html.to_string());
+            let layout = page.main_frame().layout();
+            let root = layout.root().as_ref().unwrap().render_node.clone().unwrap();
+            let form = find(&root, "form").unwrap();
+            let margin = form.borrow().properties.margin_bottom();
+            margin
+        };
+
+        // the forms of the documents without a doctype have a bottom margin
+        let no_quirks_margin = form_margin("<!DOCTYPE html><form>a</form>");
+        assert_eq!(no_quirks_margin.to_px(0.), 0.);
+        assert_ne!(form_margin("<form>a</form>"), no_quirks_margin);
+    }
+
+    #[test]
+    fn interact_with_form_controls() {
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.load_html("<input type=checkbox><input value=hi>".to_string());
+
+        // the checkbox is 13px wide, the text input is next to it
+        page.click(5., 15.);
+        let checkbox = focused_element(&page);
+        page.dispatch_key_event(&KeyboardEvent::key_down(" "));
+        page.dispatch_key_event(&KeyboardEvent::key_up(" "));
+        match checkbox.borrow().as_element().data() {
+            ElementData::Input(input) => assert!(!input.checked()),
+            _ => unreachable!(),
+        }
+
+        page.click(20., 15.);
+        page.dispatch_key_event(&KeyboardEvent::key_down("!"));
+        let input = focused_element(&page);
+        match input.borrow().as_element().data() {
+            ElementData::Input(input) => assert_eq!(input.value(), "hi!"),
+            _ => unreachable!(),
+        };
+    }
+
+    #[test]
+    fn submit_form() {
+        let response = std::env::temp_dir().join("moon_form_response.html");
+        std::fs::write(&response, "<p>results</p>").unwrap();
+        let action = format!("file://{}", response.display());
+
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.load_html(format!(
+            "<form action='{}'><input name=q value=hi></form>",
+            action
+        ));
+
+        page.click(5., 10.);
+        page.dispatch_key_event(&KeyboardEvent::key_down("Enter"));
+        assert_eq!(page.url(), Some(format!("{}?q=hi", action).as_str()));
+
+        let layout = page.main_frame().layout();
+        let root = layout.root().as_ref().unwrap().render_node.clone().unwrap();
+        assert_eq!(
+            root.borrow().node.borrow().descendant_text_content(),
+            "results"
+        );
+    }
+
+    #[test]
+    fn follow_links() {
+        let dir = std::env::temp_dir().join("moon_links");
+        std::fs::create_dir_all(dir.join("pages")).unwrap();
+        std::fs::write(dir.join("pages/next.html"), "<title>Next</title>").unwrap();
+        let url = format!("file://{}/index.html", dir.display());
+
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css("html, body, a { display: block; } a { height: 20px; }");
+        page.navigate(
+            url.clone(),
+            "<a href='#top'>top</a><a href='pages\\next.html#intro'>next</a>".to_string(),
+        );
+
+        // links to a fragment stay on the document
+        page.click(5., 10.);
+        assert_eq!(page.url(), Some(url.as_str()));
+
+        page.click(5., 30.);
+        let next = format!("file://{}/pages/next.html#intro", dir.display());
+        assert_eq!(page.url(), Some(next.as_str()));
+        assert_eq!(page.title(), "Next");
+    }
+
+    #[test]
+    fn style_visited_links() {
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css(
+            "html, body, a { display: block; } \
+            a:link { font-size: 10px; } a:visited { font-size: 20px; }",
+        );
+        let html = "<a href='index.html#top'>home</a><a href='next.html'>next</a>";
+        page.navigate("file:///index.html".to_string(), html.to_string());
+        let height = |page: &mut Page, text: &str| page.find(text)[0].rects[0].height;
+        assert_eq!(height(&mut page, "home"), 24.);
+        assert_eq!(height(&mut page, "next"), 12.);
+
+        // the URLs navigated to are visited in the other documents too
+        page.navigate("file:///next.html".to_string(), String::new());
+        page.navigate("file:///other.html".to_string(), html.to_string());
+        assert_eq!(height(&mut page, "next"), 24.);
+    }
+
+    #[test]
+    fn cursors() {
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css(
+            "html, body, a, div { display: block; } a, div { height: 20px; } \
+            .move { cursor: move; }",
+        );
+        page.navigate(
+            "file:///index.html".to_string(),
+            "<a href='next.html'>next</a><div>text</div><div class=move>move</div>".to_string(),
+        );
+
+        assert_eq!(page.cursor_at(5., 10.), Cursor::Pointer);
+        assert_eq!(page.cursor_at(5., 30.), Cursor::Text);
+        assert_eq!(page.cursor_at(250., 30.), Cursor::Default);
+        assert_eq!(page.cursor_at(250., 50.), Cursor::Move);
+        assert_eq!(page.cursor_at(5., 200.), Cursor::Default);
+    }
+
+    #[test]
+    fn select_text() {
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css("html, body, p { display: block; margin: 0; }");
+        page.load_html("<p>hello world</p><p>second line</p>".to_string());
+
+        page.select_from(50., 5.);
+        assert_eq!(page.selected_text(), None);
+        page.select_to(25., 25.);
+        assert_eq!(page.selected_text().as_deref(), Some("world\nsec"));
+
+        // dragging backwards selects the text before the anchor
+        page.select_to(10., 5.);
+        assert_eq!(page.selected_text().as_deref(), Some("ello "));
+
+        // below the text, the selection extends to the end of the text
+        page.select_from(0., 5.);
+        page.select_to(0., 200.);
+        assert_eq!(
+            page.selected_text().as_deref(),
+            Some("hello world\nsecond line")
+        );
+        let rects = painting::selection_rects(
+            page.main_frame().layout().root().as_ref().unwrap(),
+            page.main_frame().selection().unwrap(),
+        );
+        assert_eq!(rects.len(), 2);
+        assert_eq!(rects[0].width, 88.);
+    }
+
+    #[test]
+    fn find_text() {
+        let mut page = Page::new();
+        page.resize((300, 100));
+        page.set_user_css(
+            "html, body, p { display: block; margin: 0; } p { height: 200px; } \
+            .narrow { width: 64px; }",
+        );
+        page.load_html("<p>Moon</p><p class=narrow>a moon light</p><p>no MOON</p>".to_string());
+
+        let matches = page.find("moon");
+        assert_eq!(matches.len(), 3);
+        assert_eq!(matches[1].rects, vec![Rect::new(16., 200., 32., 19.2)]);
+        // the first match is visible already
+        assert_eq!(page.main_frame().scroll_y(), 0.);
+
+        // the matches are scrolled to the middle of the viewport
+        assert_eq!(page.find_next(false), Some(matches[1].clone()));
+        assert_eq!(page.main_frame().scroll_y(), 200. + 9.6 - 50.);
+        page.find_next(false);
+        page.find_next(false);
+        assert_eq!(page.main_frame().find_matches().1, Some(0));
+        assert_eq!(page.main_frame().scroll_y(), 0.);
+        assert_eq!(page.find_next(true), Some(matches[2].clone()));
+
+        // a match can span lines
+        assert_eq!(page.find("MOON LIGHT").len(), 1);
+        assert!(page.find("moonlight").is_empty());
+        assert_eq!(page.find_next(false), None);
+
+        page.stop_finding();
+        assert!(page.main_frame().find_matches().0.is_empty());
+    }
+
+    #[test]
+    fn zoom_text() {
+        let mut page = Page::new();
+        page.resize((300, 100));
+        page.set_user_css(
+            "html, body, p { display: block; margin: 0; } .small { font-size: 8px; }",
+        );
+        page.load_html("<p>moon</p><p class=small>moon</p>".to_string());
+        let rects = |page: &mut Page| {
+            page.find("moon")
+                .into_iter()
+                .flat_map(|range| range.rects)
+                .collect::<Vec<_>>()
+        };
+        assert_eq!(
+            rects(&mut page),
+            vec![Rect::new(0., 0., 32., 19.2), Rect::new(0., 19.2, 16., 9.6)]
+        );
+
+        // the font sizes are scaled, not the viewport
+        page.set_text_zoom(1.25);
+        assert_eq!(
+            rects(&mut page),
+            vec![Rect::new(0., 0., 40., 24.), Rect::new(0., 24., 20., 12.)]
+        );
+        assert_eq!(page.main_frame().size(), (300, 100));
+    }
+
+    #[test]
+    fn emulate_color_scheme() {
+        let mut page = Page::new();
+        page.resize((300, 100));
+        page.set_user_css(
+            "html, body, p { display: block; margin: 0; font-size: 10px; }
+            @media (prefers-color-scheme: dark) { p { font-size: 20px; } }
+            @media (max-width: 200px) { p { font-size: 30px; } }",
+        );
+        page.load_html("<p>moon</p>".to_string());
+        let height = |page: &mut Page| page.find("moon")[0].rects[0].height;
+        assert_eq!(height(&mut page), 12.);
+
+        page.set_color_scheme(ColorScheme::Dark);
+        assert_eq!(height(&mut page), 24.);
+
+        // the rules matching the viewport change when it's resized
+        page.resize((200, 100));
+        assert_eq!(height(&mut page), 36.);
+    }
+
+    #[test]
+    fn follow_meta_refresh() {
+        let dir = std::env::temp_dir().join("moon_refresh");
+        std::fs::create_dir_all(dir.join("pages")).unwrap();
+        std::fs::write(dir.join("pages/next.html"), "<title>Next</title>").unwrap();
+        let url = format!("file://{}/index.html", dir.display());
+
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.navigate(
+            url.clone(),
+            "<head><title> First\n page </title><base href='pages/'>\
+            <meta http-equiv=Refresh content='1; url=next.html'></head>"
+                .to_string(),
+        );
+        assert_eq!(page.title(), "First page");
+
+        page.advance_time(Duration::from_millis(500));
+        assert_eq!(page.url(), Some(url.as_str()));
+
+        page.advance_time(Duration::from_secs(1));
+        let next = format!("file://{}/pages/next.html", dir.display());
+        assert_eq!(page.url(), Some(next.as_str()));
+        assert_eq!(page.title(), "Next");
+        assert!(page.history().can_go_back());
+    }
+
+    #[test]
+    fn disable_animations() {
+        let css = "html, body, div { display: block; }\
+            @keyframes grow { from { width: 10px; } to { width: 110px; } }\
+            div { width: 50px; height: 10px; background-color: red; animation: grow 100ms linear; }";
+        let div_width = |page: &Page| {
+            let layout = page.main_frame().layout();
+            let root = layout.root().as_ref().unwrap();
+            let mut painter = AreaPainter::default();
+            painting::paint(&painting::build_display_list(root), &mut painter);
+            painter.area(255, 0, 0) / 10.
+        };
+
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css(css);
+        page.load_html("<div></div>".to_string());
+        assert_eq!(div_width(&page), 10.);
+        assert!(page.has_running_animations());
+
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css(css);
+        page.set_animations_enabled(false);
+        page.load_html("<div></div>".to_string());
+        assert_eq!(div_width(&page), 50.);
+        page.advance_time(Duration::from_millis(50));
+        assert_eq!(div_width(&page), 50.);
+        assert!(!page.has_running_animations());
+    }
+
+    #[test]
+    fn apply_author_stylesheets() {
+        let area = |page: &Page, (r, g, b)| {
+            let layout = page.main_frame().layout();
+            let root = layout.root().as_ref().unwrap();
+            let mut painter = AreaPainter::default();
+            painting::paint(&painting::build_display_list(root), &mut painter);
+            painter.area(r, g, b)
+        };
+
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css(
+            "html, body, div { display: block; } div { background-color: lime; } \
+             .a { background-color: lime !important; }",
+        );
+        page.set_author_css(&[
+            "div { width: 50px; height: 10px; background-color: blue; }".to_string(),
+            "div { background-color: red; }".to_string(),
+        ]);
+        page.load_html("<div></div><div class=a></div>".to_string());
+
+        // the author styles win over the normal user styles, in order
+        assert_eq!(area(&page, (255, 0, 0)), 500.);
+        assert_eq!(area(&page, (0, 0, 255)), 0.);
+        assert_eq!(area(&page, (0, 255, 0)), 500.);
+    }
+
+    #[test]
+    fn replace_user_agent_stylesheet() {
+        let red_area = |page: &Page| {
+            let layout = page.main_frame().layout();
+            let root = layout.root().as_ref().unwrap();
+            let mut painter = AreaPainter::default();
+            painting::paint(&painting::build_display_list(root), &mut painter);
+            painter.area(255, 0, 0)
+        };
+
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_agent_css(
+            "html, body, div { display: block; } \
+             div { width: 50px; height: 10px; background-color: red; }",
+        );
+        page.load_html("<div></div><div class=a></div>".to_string());
+        assert_eq!(red_area(&page), 1000.);
+
+        // the user styles win over the user agent styles
+        page.set_user_css(".a { background-color: blue; }");
+        assert_eq!(red_area(&page), 500.);
+
+        page.set_user_agent_css("div { background-color: red; }");
+        assert_eq!(red_area(&page), 0.);
+    }
+
+    #[test]
+    fn report_heap_stats() {
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.load_html("<div><p>hello</p></div>".to_string());
+
+        // the document, html, head, body, div, p & the text
+        let stats = page.heap_stats();
+        assert_eq!(stats.dom.nodes, 7);
+        assert!(stats.dom.bytes > 7 * std::mem::size_of::<dom::node::Node>());
+        assert!(stats.styles.nodes > 0 && stats.styles.bytes > 0);
+        assert!(stats.layout.nodes > 0 && stats.layout.bytes > 0);
+
+        // the nodes of the previous document are dropped
+        page.load_html("<p>bye</p>".to_string());
+        let stats = page.heap_stats();
+        assert_eq!(stats.dom.nodes, 6);
+        assert_eq!(stats.live_dom_nodes, stats.dom.nodes);
+    }
+
+    /// Records the area painted with each color
+    #[derive(Default)]
+    struct AreaPainter(Vec<(painting::Color, f32)>);
+
+    impl painting::Painter for AreaPainter {
+        fn fill_rect(&mut self, rect: painting::Rect, color: painting::Color) {
+            self.0.push((color, rect.width * rect.height));
+        }
+        fn fill_rrect(&mut self, _: painting::RRect, _: painting::Color) {}
+        fn stroke_rrect(&mut self, _: painting::RRect, _: painting::Color, _: f32) {}
+        fn set_transform(&mut self, _: painting::Transform) {}
+        fn set_clip(&mut self, _: Option<painting::Rect>) {}
+    }
+
+    impl AreaPainter {
+        fn area(&self, r: u8, g: u8, b: u8) -> f32 {
+            self.0
+                .iter()
+                .filter(|(color, _)| (color.r, color.g, color.b, color.a) == (r, g, b, 255))
+                .map(|(_, area)| area)
+                .sum()
+        }
+    }
+
+    #[test]
+    fn paint_svg_images() {
+        let svg = |size: u32, color: &str| {
+            format!(
+                "data:image/svg+xml,<svg xmlns='http://www.w3.org/2000/svg' width='{0}' height='{1}'><rect width='{0}' height='{1}' fill='{2}'/></svg>",
+                size * 2,
+                size,
+                color
+            )
+        };
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.set_user_css(&format!(
+            "html, body, div {{ display: block; margin: 0; }} div {{ height: 10px; background-image: url(\"{}\"); }}",
+            svg(4, "blue")
+        ));
+        page.load_html(format!(
+            "<div></div><img src=\"{}\"><img src='data:,moon'>",
+            svg(20, "red")
+        ));
+
+        let layout = page.main_frame().layout();
+        let root = layout.root().as_ref().unwrap();
+        let mut painter = AreaPainter::default();
+        painting::paint(&painting::build_display_list(root), &mut painter);
+
+        // the image is painted at its size & its background tiles cover the div
+        assert_eq!(painter.area(255, 0, 0), 40. * 20.);
+        assert_eq!(painter.area(0, 0, 255), 300. * 10.);
+    }
+
+    #[test]
+    fn paint_inline_svg() {
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.load_html(
+            "<svg width=40 height=20 viewBox='0 0 4 2'>\
+                <rect width=2 height=2 fill=red />\
+                <path d='M2 0h2v2h-2z' fill='#00f' />\
+            </svg>"
+                .to_string(),
+        );
+
+        let layout = page.main_frame().layout();
+        let root = layout.root().as_ref().unwrap();
+        let mut painter = AreaPainter::default();
+        painting::paint(&painting::build_display_list(root), &mut painter);
+
+        assert_eq!(painter.area(255, 0, 0), 20. * 20.);
+        assert_eq!(painter.area(0, 0, 255), 20. * 20.);
+    }
+
+    #[test]
+    fn paint_canvas() {
+        let mut page = Page::new();
+        page.resize((300, 300));
+        page.load_html("<canvas id=chart width=20 height=10></canvas>".to_string());
+        assert!(!page.draw_canvas("missing", |_| {}));
+        assert!(page.draw_canvas("chart", |context| {
+            context.set_fill_style("red");
+            context.fill_rect(0., 0., 10., 10.);
+            context.set_fill_style("#00f");
+            context.fill_rect(10., 0., 5., 5.);
+        }));
+
+        let layout = page.main_frame().layout();
+        let root = layout.root().as_ref().unwrap();
+        let mut painter = AreaPainter::default();
+        painting::paint(&painting::build_display_list(root), &mut painter);
+
+        assert_eq!(painter.area(255, 0, 0), 10. * 10.);
+        assert_eq!(painter.area(0, 0, 255), 5. * 5.);
+    }
 }
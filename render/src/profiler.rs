@@ -0,0 +1,200 @@
+/// Timings of the stages of the rendering pipeline, recorded while the
+/// profiler runs on the thread rendering the pages. The profile is written
+/// in the Chrome trace format, opened by chrome://tracing & Perfetto.
+/// https://docs.google.com/document/d/1CvAClvFfyA5R-PhYUmn5OOQtYMH4h6I0nSsKchNAySU
+use log::Level;
+use serde::Serialize;
+use std::cell::RefCell;
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+/// The target of the events logged at the end of every stage, whether
+/// the profiler runs or not
+const STAGE_TARGET: &str = "render::pipeline";
+
+thread_local! {
+    static PROFILE: RefCell<Option<Profile>> = RefCell::new(None);
+}
+
+#[derive(Debug)]
+pub struct Profile {
+    start: Instant,
+    events: Vec<TraceEvent>,
+}
+
+#[derive(Debug, Serialize)]
+struct TraceEvent {
+    name: String,
+    cat: &'static str,
+    /// `X` for a stage with a duration, `C` for counters
+    ph: &'static str,
+    /// The time since the profiler started, in microseconds
+    ts: f64,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    dur: Option<f64>,
+    pid: u32,
+    tid: u32,
+    #[serde(skip_serializing_if = "BTreeMap::is_empty")]
+    args: BTreeMap<String, usize>,
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct Trace<'a> {
+    trace_events: &'a [TraceEvent],
+    display_time_unit: &'static str,
+}
+
+impl Profile {
+    fn new() -> Self {
+        Self {
+            start: Instant::now(),
+            events: Vec::new(),
+        }
+    }
+
+    fn micros_since_start(&self, time: Instant) -> f64 {
+        time.saturating_duration_since(self.start).as_secs_f64() * 1e6
+    }
+
+    /// The total time spent in a stage, in microseconds
+    pub fn total(&self, stage: &str) -> f64 {
+        self.events
+            .iter()
+            .filter(|event| event.name == stage)
+            .filter_map(|event| event.dur)
+            .sum()
+    }
+
+    /// The profile as a Chrome trace
+    pub fn to_json(&self) -> String {
+        let trace = Trace {
+            trace_events: &self.events,
+            display_time_unit: "ms",
+        };
+        serde_json::to_string_pretty(&trace).expect("The trace is serializable")
+    }
+}
+
+/// Start recording the stages rendered on this thread, dropping the
+/// profile recorded so far
+pub fn start_profiling() {
+    PROFILE.with(|profile| *profile.borrow_mut() = Some(Profile::new()));
+}
+
+/// Stop recording & take the profile recorded since the profiler started
+pub fn finish_profiling() -> Option<Profile> {
+    PROFILE.with(|profile| profile.borrow_mut().take())
+}
+
+fn is_profiling() -> bool {
+    PROFILE.with(|profile| profile.borrow().is_some())
+}
+
+/// A stage of the pipeline, recorded when the span is dropped
+pub struct Span {
+    name: &'static str,
+    start: Option<Instant>,
+}
+
+/// Measure a stage until the returned span is dropped
+pub fn span(name: &'static str) -> Span {
+    Span {
+        name,
+        start: if is_profiling() || log::log_enabled!(target: STAGE_TARGET, Level::Debug) {
+            Some(Instant::now())
+        } else {
+            None
+        },
+    }
+}
+
+impl Drop for Span {
+    fn drop(&mut self) {
+        let start = match self.start {
+            Some(start) => start,
+            None => return,
+        };
+        let end = Instant::now();
+        log::debug!(
+            target: STAGE_TARGET,
+            "{} took {:.3}ms",
+            self.name,
+            (end - start).as_secs_f64() * 1e3
+        );
+        PROFILE.with(|profile| {
+            if let Some(profile) = profile.borrow_mut().as_mut() {
+                let ts = profile.micros_since_start(start);
+                let event = TraceEvent {
+                    name: self.name.to_string(),
+                    cat: "stage",
+                    ph: "X",
+                    ts,
+                    dur: Some(profile.micros_since_start(end) - ts),
+                    pid: 1,
+                    tid: 1,
+                    args: BTreeMap::new(),
+                };
+                profile.events.push(event);
+            }
+        });
+    }
+}
+
+/// Record counts at the current time, e.g. the display items of a frame
+pub fn count(name: &'static str, counts: BTreeMap<&'static str, usize>) {
+    PROFILE.with(|profile| {
+        if let Some(profile) = profile.borrow_mut().as_mut() {
+            let event = TraceEvent {
+                name: name.to_string(),
+                cat: "count",
+                ph: "C",
+                ts: profile.micros_since_start(Instant::now()),
+                dur: None,
+                pid: 1,
+                tid: 1,
+                args: counts
+                    .into_iter()
+                    .map(|(key, value)| (key.to_string(), value))
+                    .collect(),
+            };
+            profile.events.push(event);
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn record_stages() {
+        drop(span("ignored"));
+
+        start_profiling();
+        {
+            let _layout = span("layout");
+            let _style = span("style");
+        }
+        let mut counts = BTreeMap::new();
+        counts.insert("fill_rect", 3);
+        count("display items", counts);
+        let profile = finish_profiling().unwrap();
+        assert!(finish_profiling().is_none());
+
+        let names = profile
+            .events
+            .iter()
+            .map(|event| event.name.as_str())
+            .collect::<Vec<_>>();
+        // the inner span ends first
+        assert_eq!(names, ["style", "layout", "display items"]);
+        assert!(profile.total("layout") >= profile.total("style"));
+
+        let json: serde_json::Value = serde_json::from_str(&profile.to_json()).unwrap();
+        let events = json["traceEvents"].as_array().unwrap();
+        assert_eq!(events[0]["ph"], "X");
+        assert_eq!(events[2]["args"]["fill_rect"], 3);
+        assert!(events[2].get("dur").is_none());
+    }
+}
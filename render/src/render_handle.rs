@@ -0,0 +1,60 @@
+use io::cancellation::CancellationToken;
+use std::fmt;
+use std::time::Duration;
+
+/// A handle on a render, given to the render functions, to abort the
+/// render from another thread or once a duration elapsed. The render
+/// stops as soon as the stage it's in notices, keeping what it has done.
+#[derive(Debug, Clone, Default)]
+pub struct RenderHandle {
+    token: CancellationToken,
+}
+
+impl RenderHandle {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// A handle cancelling its render once a duration elapsed from now
+    pub fn with_timeout(timeout: Duration) -> Self {
+        Self {
+            token: CancellationToken::with_timeout(timeout),
+        }
+    }
+
+    pub fn cancel(&self) {
+        self.token.cancel();
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.token.is_cancelled()
+    }
+
+    pub(crate) fn token(&self) -> CancellationToken {
+        self.token.clone()
+    }
+
+    /// The output of a render, or the error of a cancelled render
+    /// holding the output rendered until the cancellation
+    pub(crate) fn finish<T>(&self, output: T) -> Result<T, Cancelled<T>> {
+        if self.is_cancelled() {
+            Err(Cancelled { partial: output })
+        } else {
+            Ok(output)
+        }
+    }
+}
+
+/// The error of a render cancelled before it finished. The partial
+/// output has the part of the document parsed, laid out & painted by
+/// then, e.g. to show how far a document too slow to render got.
+#[derive(Debug)]
+pub struct Cancelled<T> {
+    pub partial: T,
+}
+
+impl<T> fmt::Display for Cancelled<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "The render was cancelled before it finished")
+    }
+}
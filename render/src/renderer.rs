@@ -1,45 +1,1053 @@
+use super::clipboard;
+use super::cursor::cursor_icon;
 use super::frame::FrameSize;
+use super::heap_stats::HeapStats;
 use super::page::Page;
-use gfx::{Bitmap, Painter};
+use super::profiler;
+use super::tabs::{TabId, Tabs};
+use super::viewport::{bitmap_len, clamp_bitmap_size, device_size, MAX_VIEWPORT_DIMENSION};
+use super::zoom::Zoom;
+use dom::canvas::CanvasRenderingContext2D;
+use dom::event::{KeyEventKind, KeyboardEvent};
+use gfx::{Antialiasing, Bitmap, ColorSpace, GpuBackend, PaintBackend, Painter, PixelFormat};
+use io::cancellation::CancellationToken;
+use io::parse_error::ParseError;
+use layout::layout_box::LayoutBox;
+use layout::pagination::paginate;
+use loaders::cookies::CookieJar;
+use loaders::http::HttpConfig;
+use loaders::scheme::SchemeHandler;
+use message::{CursorIcon, RenderedBitmap};
+use painting::{Color, DisplayList, MatchRange, Rect, TileIndex, Transform};
+use pdf::PdfPainter;
+use std::path::PathBuf;
+use std::time::{Duration, SystemTime};
+use style::media::ColorScheme;
+use style::page::PageStyle;
+use style::values::prelude::Cursor;
+use svg_export::SvgPainter;
+
+/// The file of the profile directory keeping the cookies
+const COOKIES_FILE: &str = "cookies.txt";
 
 pub struct Renderer<'a> {
     painter: Painter<'a>,
-    page: Page,
+    tabs: Tabs,
+    /// The display list of the last painted frame
+    display_list: Option<DisplayList>,
+    /// The regions painted since the last rendered bitmap, as
+    /// (x, y, width, height)
+    damage: Vec<(u32, u32, u32, u32)>,
+    /// Where the data of the pages is kept between runs
+    profile_dir: Option<PathBuf>,
+    /// Stop the work on the pages once cancelled
+    cancellation: CancellationToken,
+    /// The device pixels per CSS pixel of the output bitmaps
+    device_pixel_ratio: f32,
+    /// The size of the viewport the frames are painted in, in CSS pixels
+    /// at a zoom of 1
+    viewport: FrameSize,
+    zoom: Zoom,
+    /// The height the full page bitmaps are cut at, in device pixels
+    max_full_page_height: u32,
 }
 
 pub struct RendererInitializeParams {
     pub viewport: FrameSize,
+    /// The device pixels per CSS pixel of the viewport & full page
+    /// bitmaps, the frames painted for a window are in CSS pixels
+    pub device_pixel_ratio: f32,
 }
 
 impl<'a> Renderer<'a> {
-    pub async fn new() -> Renderer<'a> {
+    /// Create a renderer with a single empty tab, painting on the GPU
+    pub async fn new(antialiasing: Antialiasing) -> Renderer<'a> {
+        Self::with_gpu_backend(None, antialiasing).await
+    }
+
+    /// Create a renderer with a single empty tab, painting on the GPU
+    /// with a graphics API, or the best one of the machine when none
+    pub async fn with_gpu_backend(
+        gpu_backend: Option<GpuBackend>,
+        antialiasing: Antialiasing,
+    ) -> Renderer<'a> {
+        Self::create(PaintBackend::Gpu, gpu_backend, antialiasing).await
+    }
+
+    /// Create a renderer with a single empty tab, painting on a backend
+    pub async fn with_backend(backend: PaintBackend, antialiasing: Antialiasing) -> Renderer<'a> {
+        Self::create(backend, None, antialiasing).await
+    }
+
+    async fn create(
+        backend: PaintBackend,
+        gpu_backend: Option<GpuBackend>,
+        antialiasing: Antialiasing,
+    ) -> Renderer<'a> {
+        let mut tabs = Tabs::new();
+        tabs.open();
         Self {
-            painter: Painter::new().await,
-            page: Page::new(),
+            painter: Painter::new(backend, gpu_backend, antialiasing).await,
+            tabs,
+            display_list: None,
+            damage: Vec::new(),
+            profile_dir: None,
+            cancellation: CancellationToken::new(),
+            device_pixel_ratio: 1.,
+            viewport: (0, 0),
+            zoom: Zoom::default(),
+            max_full_page_height: MAX_VIEWPORT_DIMENSION,
         }
     }
 
     pub fn initialize(&mut self, params: RendererInitializeParams) {
-        self.page.resize(params.viewport);
-        self.painter.resize(params.viewport);
+        self.device_pixel_ratio = params.device_pixel_ratio;
+        self.viewport = params.viewport;
+        self.tabs.resize(self.layout_size());
+        self.resize_painter(params.viewport);
+        self.display_list = None;
+    }
+
+    /// The backend the frames are painted on
+    pub fn paint_backend(&self) -> PaintBackend {
+        self.painter.backend()
+    }
+
+    /// Cut the full page bitmaps at a height in device pixels, at most
+    /// `MAX_VIEWPORT_DIMENSION`, the content below isn't painted
+    pub fn set_max_full_page_height(&mut self, height: u32) {
+        self.max_full_page_height = height.clamp(1, MAX_VIEWPORT_DIMENSION);
+    }
+
+    /// Zoom the pages, either scaling the whole pages or only their
+    /// text. The pages are laid out again & painted from scratch.
+    pub fn set_zoom(&mut self, zoom: Zoom) {
+        self.zoom = Zoom::new(zoom.factor, zoom.mode);
+        self.tabs.set_text_zoom(self.zoom.text_factor());
+        self.tabs.resize(self.layout_size());
+        self.display_list = None;
+    }
+
+    pub fn zoom(&self) -> Zoom {
+        self.zoom
+    }
+
+    /// Zoom in to the next zoom level, e.g. with Ctrl+=
+    pub fn zoom_in(&mut self) {
+        self.set_zoom(self.zoom.zoom_in());
+    }
+
+    /// Zoom out to the previous zoom level, e.g. with Ctrl+-
+    pub fn zoom_out(&mut self) {
+        self.set_zoom(self.zoom.zoom_out());
+    }
+
+    /// Go back to the size the pages are designed at, e.g. with Ctrl+0
+    pub fn reset_zoom(&mut self) {
+        self.set_zoom(Zoom::new(1., self.zoom.mode));
+    }
+
+    /// The size the pages are laid out at: the viewport in CSS pixels,
+    /// which are larger than the pixels of the viewport once page zoomed
+    fn layout_size(&self) -> FrameSize {
+        let zoom = self.zoom.page_factor();
+        let scale = |dimension: u32| ((dimension as f32 / zoom).round() as u32).max(1);
+        (scale(self.viewport.0), scale(self.viewport.1))
+    }
+
+    /// A point of the viewport in the CSS pixels of the pages
+    fn page_point(&self, x: f32, y: f32) -> (f32, f32) {
+        let zoom = self.zoom.page_factor();
+        (x / zoom, y / zoom)
+    }
+
+    /// Resize the frame of the painter, no larger than the largest
+    /// texture. The bitmaps larger than the frame are painted in tiles.
+    fn resize_painter(&mut self, size: FrameSize) {
+        let max_dimension = self.painter.max_texture_dimension();
+        self.painter
+            .resize((size.0.min(max_dimension), size.1.min(max_dimension)));
+    }
+
+    /// Connect to the servers of the pages with a config, e.g. through a proxy
+    pub fn set_http_config(&mut self, config: &HttpConfig) -> Result<(), String> {
+        self.tabs.set_http_config(config)
+    }
+
+    /// Load the URLs of a custom protocol, e.g. `app://`, with a handler
+    /// instead of failing as an unsupported protocol
+    pub fn register_scheme_handler<H: SchemeHandler + 'static>(
+        &mut self,
+        protocol: &str,
+        handler: H,
+    ) {
+        self.tabs.register_scheme_handler(protocol, handler);
+    }
+
+    /// Replace the default styles of the elements of the pages, e.g. to
+    /// render with the defaults of another browser
+    pub fn set_user_agent_css(&mut self, css: &str) {
+        self.tabs.set_user_agent_css(css);
+    }
+
+    /// Apply a user stylesheet to the pages, e.g. to override
+    /// the colors or sizes of the page
+    pub fn set_user_css(&mut self, css: &str) {
+        self.tabs.set_user_css(css);
+    }
+
+    /// Apply stylesheets to the pages as if the documents linked them
+    /// before their own styles, at the author origin of the cascade
+    pub fn set_author_css(&mut self, css_list: &[String]) {
+        self.tabs.set_author_css(css_list);
+    }
+
+    /// Run the CSS animations & transitions of the pages or not, from the
+    /// next document loaded
+    pub fn set_animations_enabled(&mut self, enabled: bool) {
+        self.tabs.set_animations_enabled(enabled);
+    }
+
+    /// Skip the layout & paint of the contents of the `content-visibility:
+    /// auto` elements outside of the viewport, from the next layout. The
+    /// outputs painting more than the viewport, e.g. the full page, need
+    /// the whole document laid out so they shouldn't be used with it.
+    pub fn set_lazy_layout(&mut self, enabled: bool) {
+        self.tabs.set_lazy_layout(enabled);
+    }
+
+    /// Render the pages in the light or the dark color scheme, matching
+    /// the `prefers-color-scheme` media feature of their styles
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        self.tabs.set_color_scheme(color_scheme);
+    }
+
+    /// Render the pages for the users who prefer reduced motion or not,
+    /// matching the `prefers-reduced-motion` media feature. With reduced
+    /// motion, the transitions are disabled & the CSS animations skip to
+    /// their end.
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        self.tabs.set_reduced_motion(reduced_motion);
+    }
+
+    /// Render the pages with the `forced-colors` media feature active or
+    /// not. The colors themselves aren't forced.
+    pub fn set_forced_colors(&mut self, forced_colors: bool) {
+        self.tabs.set_forced_colors(forced_colors);
+    }
+
+    /// Stop parsing, styling, laying out & painting the pages once a
+    /// token is cancelled, e.g. from another thread or by a timeout. The
+    /// outputs painted by then show what was done until the cancellation.
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.tabs.set_cancellation(token.clone());
+        self.cancellation = token;
+    }
+
+    /// Whether the token of the renderer is cancelled
+    pub fn is_cancelled(&self) -> bool {
+        self.cancellation.is_cancelled()
+    }
+
+    /// Encode the pixels of the output bitmaps into a color space
+    pub fn set_output_color_space(&mut self, color_space: ColorSpace) {
+        self.painter.set_output_color_space(color_space);
     }
 
+    /// Keep the cookies of the pages in a profile directory between runs.
+    /// The cookies saved in the directory are loaded right away.
+    pub fn set_profile_dir(&mut self, dir: PathBuf) {
+        match CookieJar::load(&dir.join(COOKIES_FILE)) {
+            Ok(jar) => *self.tabs.cookie_jar().lock().unwrap() = jar,
+            Err(e) => log::error!("Unable to load cookies from {}: {}", dir.display(), e),
+        }
+        self.profile_dir = Some(dir);
+    }
+
+    /// Save the persistent cookies to the profile directory
+    pub fn save_profile(&self) {
+        let dir = match &self.profile_dir {
+            Some(dir) => dir,
+            None => return,
+        };
+        let result = std::fs::create_dir_all(dir).and_then(|_| {
+            self.tabs
+                .cookie_jar()
+                .lock()
+                .unwrap()
+                .save(&dir.join(COOKIES_FILE), SystemTime::now())
+        });
+        if let Err(e) = result {
+            log::error!("Unable to save cookies to {}: {}", dir.display(), e);
+        }
+    }
+
+    /// Load a document in the active tab
     pub fn load_html(&mut self, html: String) {
-        self.page.load_html(html);
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.load_html(html);
+        }
+    }
+
+    /// Open a tab with a document and make it the active tab
+    pub fn new_tab(&mut self, html: String) -> TabId {
+        let id = self.tabs.open();
+        self.load_html(html);
+        self.display_list = None;
+        id
+    }
+
+    /// Show another tab in the next paint. The tab keeps its document
+    /// & layout, so it is painted without being parsed again.
+    pub fn switch_tab(&mut self, id: TabId) -> bool {
+        if self.tabs.active() == Some(id) {
+            return true;
+        }
+        let switched = self.tabs.switch_to(id);
+        if switched {
+            self.display_list = None;
+        }
+        switched
+    }
+
+    pub fn close_tab(&mut self, id: TabId) -> bool {
+        let was_active = self.tabs.active() == Some(id);
+        let closed = self.tabs.close(id);
+        if closed && was_active {
+            self.display_list = None;
+        }
+        closed
+    }
+
+    /// Load a document in the active tab & add it to the history of the
+    /// tab, e.g. when a link is followed
+    pub fn navigate(&mut self, url: String, html: String) {
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.navigate(url, html);
+        }
+    }
+
+    /// The title of the document of the active tab
+    pub fn title(&self) -> String {
+        self.tabs.active_page().map(Page::title).unwrap_or_default()
+    }
+
+    /// Go to the previous document of the active tab
+    pub fn go_back(&mut self) -> bool {
+        self.tabs
+            .active_page_mut()
+            .map(Page::go_back)
+            .unwrap_or(false)
+    }
+
+    /// Go to the next document of the active tab
+    pub fn go_forward(&mut self) -> bool {
+        self.tabs
+            .active_page_mut()
+            .map(Page::go_forward)
+            .unwrap_or(false)
+    }
+
+    /// The URL of the document in the active tab
+    pub fn url(&self) -> Option<&str> {
+        self.tabs.active_page()?.url()
+    }
+
+    pub fn can_go_back(&self) -> bool {
+        self.tabs
+            .active_page()
+            .map(|page| page.history().can_go_back())
+            .unwrap_or(false)
+    }
+
+    pub fn can_go_forward(&self) -> bool {
+        self.tabs
+            .active_page()
+            .map(|page| page.history().can_go_forward())
+            .unwrap_or(false)
+    }
+
+    /// Handle a click at a point of the viewport in the active tab
+    pub fn click(&mut self, x: f32, y: f32) {
+        let (x, y) = self.page_point(x, y);
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.click(x, y);
+        }
+    }
+
+    /// The OS cursor to show while the mouse is over a point of the
+    /// viewport of the active tab, e.g. the hand over links. None if the
+    /// page hides the cursor.
+    pub fn cursor_at(&self, x: f32, y: f32) -> Option<CursorIcon> {
+        let (x, y) = self.page_point(x, y);
+        let cursor = self
+            .tabs
+            .active_page()
+            .map(|page| page.cursor_at(x, y))
+            .unwrap_or(Cursor::Default);
+        cursor_icon(cursor)
+    }
+
+    /// Start selecting the text of the active tab at the point the mouse
+    /// is pressed at. A click should only be sent for a release without a
+    /// drag, otherwise the selection ending over a link follows it.
+    pub fn mouse_down(&mut self, x: f32, y: f32) {
+        let (x, y) = self.page_point(x, y);
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.select_from(x, y);
+        }
+    }
+
+    /// Extend the selection of the active tab while the mouse is dragged
+    /// with the button held down
+    pub fn mouse_drag(&mut self, x: f32, y: f32) {
+        let (x, y) = self.page_point(x, y);
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.select_to(x, y);
+        }
+    }
+
+    /// The text selected in the active tab, or in its focused text field
+    pub fn selected_text(&self) -> Option<String> {
+        self.tabs.active_page().and_then(Page::selected_text)
+    }
+
+    /// Copy the selected text to the system clipboard. Returns false if
+    /// nothing is selected or the clipboard is unavailable.
+    pub fn copy(&self) -> bool {
+        let text = match self.selected_text() {
+            Some(text) => text,
+            None => return false,
+        };
+        match clipboard::copy_text(text) {
+            Ok(_) => true,
+            Err(e) => {
+                log::error!("Unable to copy to the clipboard: {}", e);
+                false
+            }
+        }
+    }
+
+    /// Scroll the active tab down to a position of its document, in CSS
+    /// pixels
+    pub fn scroll_to(&mut self, y: f32) {
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.scroll_to(y);
+        }
+    }
+
+    /// How far the active tab is scrolled down
+    pub fn scroll_y(&self) -> f32 {
+        self.tabs
+            .active_page()
+            .map_or(0., |page| page.main_frame().scroll_y())
+    }
+
+    /// Search a text in the rendered text of the active tab, ignoring the
+    /// case. The matches are highlighted until the search stops & the tab
+    /// scrolls to the first one.
+    pub fn find(&mut self, text: &str) -> Vec<MatchRange> {
+        self.tabs
+            .active_page_mut()
+            .map(|page| page.find(text))
+            .unwrap_or_default()
+    }
+
+    /// Scroll to the next match of the search of the active tab, after
+    /// the last match going back to the first one
+    pub fn find_next(&mut self) -> Option<MatchRange> {
+        self.tabs.active_page_mut()?.find_next(false)
+    }
+
+    /// Scroll to the previous match of the search of the active tab
+    pub fn find_previous(&mut self) -> Option<MatchRange> {
+        self.tabs.active_page_mut()?.find_next(true)
+    }
+
+    /// Stop highlighting the matches of the search of the active tab
+    pub fn stop_finding(&mut self) {
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.stop_finding();
+        }
+    }
+
+    /// Deliver a keyboard event to the focused element of the active tab.
+    /// Ctrl+C (Cmd+C on macOS) copies the selected text instead, and
+    /// Ctrl+= / Ctrl+- / Ctrl+0 zoom in, out & back to 1. Returns whether
+    /// the event was handled.
+    pub fn key_event(&mut self, event: &KeyboardEvent) -> bool {
+        if is_copy_shortcut(event) {
+            return self.copy();
+        }
+        if let Some(zoom) = zoom_shortcut(event) {
+            match zoom {
+                ZoomShortcut::In => self.zoom_in(),
+                ZoomShortcut::Out => self.zoom_out(),
+                ZoomShortcut::Reset => self.reset_zoom(),
+            }
+            return true;
+        }
+        self.tabs
+            .active_page_mut()
+            .map(|page| page.dispatch_key_event(event))
+            .unwrap_or(false)
+    }
+
+    /// Draw on the 2D context of a canvas of the active tab by its id.
+    /// Returns false if there's no such canvas.
+    pub fn draw_canvas<F>(&mut self, id: &str, draw: F) -> bool
+    where
+        F: FnOnce(&mut CanvasRenderingContext2D),
+    {
+        self.tabs
+            .active_page_mut()
+            .map(|page| page.draw_canvas(id, draw))
+            .unwrap_or(false)
     }
 
+    pub fn active_tab(&self) -> Option<TabId> {
+        self.tabs.active()
+    }
+
+    pub fn tabs(&self) -> Vec<TabId> {
+        self.tabs.ids()
+    }
+
+    /// Move the clock of the active page to a time since the page was
+    /// loaded. The next paint renders the page as it is at that time.
+    pub fn advance_time(&mut self, time: Duration) {
+        if let Some(page) = self.tabs.active_page_mut() {
+            page.advance_time(time);
+        }
+    }
+
+    /// Whether the active page changes as the time advances. A windowed
+    /// renderer keeps scheduling frames while this is true.
+    pub fn has_running_animations(&self) -> bool {
+        self.tabs
+            .active_page()
+            .map(Page::has_running_animations)
+            .unwrap_or(false)
+    }
+
+    pub fn parse_errors(&self) -> Vec<ParseError> {
+        self.tabs
+            .active_page()
+            .map(|page| page.main_frame().parse_errors())
+            .unwrap_or_default()
+    }
+
+    /// The nodes & the memory of the trees of every tab, with the memory
+    /// of the painter
+    pub fn heap_stats(&self) -> HeapStats {
+        let mut stats = HeapStats {
+            live_dom_nodes: dom::node::live_node_count(),
+            gpu_bytes: self.painter.memory_size(),
+            ..HeapStats::default()
+        };
+        for page in self
+            .tabs
+            .ids()
+            .into_iter()
+            .filter_map(|id| self.tabs.get(id))
+        {
+            let page_stats = page.heap_stats();
+            stats.dom += page_stats.dom;
+            stats.styles += page_stats.styles;
+            stats.layout += page_stats.layout;
+        }
+        stats
+    }
+
+    /// Paint the active tab
     pub fn paint(&mut self) {
-        let main_frame = self.page.main_frame();
+        let page = match self.tabs.active_page_mut() {
+            Some(page) => page,
+            None => return,
+        };
+        page.restyle();
+        let main_frame = page.main_frame();
 
         if let Some(layout_root) = main_frame.layout().root() {
-            let display_list = painting::build_display_list(layout_root);
-            painting::paint(display_list, &mut self.painter);
+            // the commands far from the viewport are neither diffed nor painted
+            let (width, height) = main_frame.size();
+            let scroll_y = main_frame.scroll_y();
+            let viewport = Rect::new(0., scroll_y, width as f32, height as f32);
+            let mut display_list = build_display_list(layout_root);
+            // the matches of the search & the selection are highlighted over the page
+            let (matches, active_match) = main_frame.find_matches();
+            display_list.extend(painting::paint_matches(&matches, active_match));
+            if let Some(selection) = main_frame.selection() {
+                display_list.extend(painting::paint_selection(layout_root, selection));
+            }
+            // scrolling moves every command, so the whole viewport is painted again
+            let zoom = self.zoom.page_factor();
+            let display_list = painting::transform_display_list(
+                painting::cull(display_list, &viewport),
+                &Transform::scale(zoom, zoom).multiply(&Transform::translate(0., -scroll_y)),
+            );
+
+            match &self.display_list {
+                Some(previous) => {
+                    // only repaint what changed since the last frame
+                    if let Some(region) = painting::damaged_region(previous, &display_list) {
+                        let _span = profiler::span("paint");
+                        painting::paint_region(&display_list, &region, &mut self.painter);
+                        self.painter.paint_region(&region);
+                        self.damage.push(pixel_region(&region));
+                    }
+                }
+                None => {
+                    let _span = profiler::span("paint");
+                    painting::paint(&display_list, &mut self.painter);
+                    self.painter.paint();
+                    self.damage = vec![(0, 0, self.viewport.0, self.viewport.1)];
+                }
+            }
 
-            self.painter.paint();
+            self.display_list = Some(display_list);
         }
     }
 
+    /// The pixels of the painted frame as a tightly packed RGBA bitmap
     pub async fn output(&mut self) -> Bitmap {
+        let _span = profiler::span("readback");
         self.painter.output().await
     }
+
+    /// The pixels of the painted frame in a format, e.g. the BGRA of a
+    /// window surface
+    pub async fn output_as(&mut self, format: PixelFormat) -> Bitmap {
+        let _span = profiler::span("readback");
+        self.painter.output_as(format).await
+    }
+
+    /// Paint the active tab into a bitmap as large as the viewport in
+    /// device pixels. The viewports larger than the largest texture, or
+    /// scaled by a device pixel ratio, are painted in tiles.
+    pub async fn output_viewport(&mut self) -> Bitmap {
+        let size = self.viewport;
+        let ratio = self.device_pixel_ratio * self.zoom.page_factor();
+        let max_dimension = self.painter.max_texture_dimension();
+        // only an unscaled bitmap can be painted as it is
+        #[allow(clippy::float_cmp)]
+        let is_unscaled = ratio == 1.;
+        if is_unscaled && size.0 <= max_dimension && size.1 <= max_dimension {
+            self.paint();
+            return self.output().await;
+        }
+
+        let page = self.tabs.active_page_mut().expect("No tab to paint");
+        page.restyle();
+        let index = match page.main_frame().layout().root() {
+            Some(layout_root) => TileIndex::new(build_display_list(layout_root)),
+            None => TileIndex::new(Vec::new()),
+        };
+        let (_, bitmap) = self
+            .paint_tiled(
+                device_size(size, self.device_pixel_ratio),
+                |tile, painter| paint_scaled(&index, tile, ratio, painter),
+            )
+            .await;
+
+        // the next frame is painted from scratch
+        self.resize_painter(size);
+        self.display_list = None;
+        self.damage.clear();
+        bitmap
+    }
+
+    /// Paint a bitmap of a size in tiles no larger than the largest
+    /// texture, stitching the tiles together. The tiles are given to the
+    /// paint function as rects of the bitmap. A bitmap larger than
+    /// `MAX_BITMAP_AREA` is cut to the rows that fit, so the size of the
+    /// bitmap painted is returned with it.
+    async fn paint_tiled<F>(&mut self, size: FrameSize, paint: F) -> (FrameSize, Bitmap)
+    where
+        F: Fn(&Rect, &mut dyn painting::Painter),
+    {
+        let (size, len) = match bitmap_len(size) {
+            Some(len) => (size, len),
+            None => {
+                let clamped = clamp_bitmap_size(size);
+                log::warn!(
+                    "A bitmap of {}x{} pixels is too large, only {}x{} pixels are painted",
+                    size.0,
+                    size.1,
+                    clamped.0,
+                    clamped.1
+                );
+                (
+                    clamped,
+                    bitmap_len(clamped).expect("Clamped bitmap too large"),
+                )
+            }
+        };
+        let max_dimension = self.painter.max_texture_dimension();
+        let frame_size = (size.0.min(max_dimension), size.1.min(max_dimension));
+        self.painter.resize(frame_size);
+
+        let mut bitmap = vec![0; len];
+        for tile in gfx::tiles(size, max_dimension) {
+            // the tiles left once cancelled are transparent
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            let (x, y, width, height) = tile;
+            {
+                let _span = profiler::span("paint");
+                let area = Rect::new(x as f32, y as f32, width as f32, height as f32);
+                paint(&area, &mut self.painter);
+                self.painter.paint();
+            }
+            let frame = {
+                let _span = profiler::span("readback");
+                self.painter.output().await
+            };
+            gfx::stitch(&mut bitmap, size.0, &frame, frame_size.0, tile);
+        }
+        (size, bitmap)
+    }
+
+    /// The regions of the frame painted since the last rendered bitmap,
+    /// which the receiver copies into the previous frame it keeps
+    pub async fn rendered_bitmap(&mut self) -> RenderedBitmap {
+        let (width, height) = self.viewport;
+        let damage = std::mem::take(&mut self.damage);
+        if damage.is_empty() {
+            return RenderedBitmap::from_frame(width, height, &[], &[]);
+        }
+        let frame = self.output().await;
+        RenderedBitmap::from_frame(width, height, &frame, &damage)
+    }
+
+    /// Paint the whole document, laid out at the viewport size, into a
+    /// bitmap as tall as the content of the document, in device pixels
+    /// scaled by the page zoom. The document is painted in tiles when it
+    /// is larger than the largest texture. The bitmap is at most as tall
+    /// as set by `set_max_full_page_height`, the content below is cut off.
+    pub async fn output_full_page(&mut self) -> (FrameSize, Bitmap) {
+        let page = self.tabs.active_page_mut().expect("No tab to paint");
+        page.restyle();
+        let main_frame = page.main_frame();
+        let (width, viewport_height) = main_frame.size();
+        let index = match main_frame.layout().root() {
+            Some(layout_root) => TileIndex::new(build_display_list(layout_root)),
+            None => TileIndex::new(Vec::new()),
+        };
+        let height = match main_frame.layout().root() {
+            Some(layout_root) => (layout_root.content_bottom().ceil() as u32).max(viewport_height),
+            None => viewport_height,
+        };
+
+        let ratio = self.device_pixel_ratio * self.zoom.page_factor();
+        let (device_width, device_height) = device_size((width, height), ratio);
+        let size = (device_width, device_height.min(self.max_full_page_height));
+        let (size, bitmap) = self
+            .paint_tiled(size, |tile, painter| {
+                paint_scaled(&index, tile, ratio, painter)
+            })
+            .await;
+
+        // the painter is back at the viewport size, so the next frame is painted from scratch
+        self.resize_painter(self.viewport);
+        self.display_list = None;
+        self.damage.clear();
+
+        (size, bitmap)
+    }
+
+    /// Paint the pages of the active tab into bitmaps of the size of the
+    /// pages, like the pages the document would be printed on
+    pub async fn output_pages(&mut self) -> (FrameSize, Vec<Bitmap>) {
+        let (style, tiles, page_count) = self.paginated_display_list();
+        let mut size = (style.width.round() as u32, style.height.round() as u32);
+
+        let mut pages = Vec::with_capacity(page_count);
+        for index in 0..page_count {
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            let (page_size, page) = self
+                .paint_tiled(size, |tile, painter| {
+                    paint_page(&tiles, &style, index, tile, painter)
+                })
+                .await;
+            size = page_size;
+            pages.push(page);
+        }
+
+        // the painter is back at the viewport size, so the next frame is painted from scratch
+        self.resize_painter(self.viewport);
+        self.display_list = None;
+        self.damage.clear();
+
+        (size, pages)
+    }
+
+    /// Write the pages of the active tab into the pages of a PDF document
+    pub fn output_paginated_pdf(&mut self) -> (FrameSize, Vec<u8>) {
+        let (style, tiles, page_count) = self.paginated_display_list();
+        let mut painter = PdfPainter::new(style.width, style.height);
+        for index in 0..page_count {
+            if self.cancellation.is_cancelled() {
+                break;
+            }
+            if index > 0 {
+                painter.add_page();
+            }
+            let page = Rect::new(0., 0., style.width, style.height);
+            paint_page(&tiles, &style, index, &page, &mut painter);
+        }
+        let size = (style.width.round() as u32, style.height.round() as u32);
+        (size, painter.finish())
+    }
+
+    /// Lay out the active tab in the content area of the pages of its
+    /// `@page` rules, pushing the content straddling the end of a page
+    /// to the next page. Returns the style of the pages, the indexed
+    /// display list of the pages placed one below the other & the page
+    /// count.
+    fn paginated_display_list(&mut self) -> (PageStyle, TileIndex, usize) {
+        let page = self.tabs.active_page_mut().expect("No tab to paint");
+        page.restyle();
+        let viewport = page.main_frame().size();
+        let style = page.main_frame().page_style();
+        let (width, height) = style.content_size();
+
+        page.resize((width.round() as u32, height.round() as u32));
+        let (display_list, page_count) = match page.main_frame().layout().root() {
+            Some(layout_root) => {
+                // the layout tree of the frame is kept for the next reflows
+                let mut layout_root = layout_root.clone();
+                let page_count = paginate(&mut layout_root, height.round());
+                (build_display_list(&layout_root), page_count)
+            }
+            None => (Vec::new(), 1),
+        };
+        page.resize(viewport);
+
+        (style, TileIndex::new(display_list), page_count)
+    }
+
+    /// Write the active tab into a PDF document of a single page as large
+    /// as the viewport, or as tall as the content of the document for a
+    /// full page. The page is painted as vector shapes rather than pixels.
+    pub fn output_pdf(&mut self, full_page: bool) -> (FrameSize, Vec<u8>) {
+        let (size, display_list) = self.vector_display_list(full_page);
+        let mut painter = PdfPainter::new(size.0 as f32, size.1 as f32);
+        painting::paint(&display_list, &mut painter);
+        (size, painter.finish())
+    }
+
+    /// Write the active tab into an SVG document, sized like the pages of
+    /// `output_pdf`
+    pub fn output_svg(&mut self, full_page: bool) -> (FrameSize, String) {
+        let (size, display_list) = self.vector_display_list(full_page);
+        let mut painter = SvgPainter::new(size.0 as f32, size.1 as f32);
+        painting::paint(&display_list, &mut painter);
+        (size, painter.finish())
+    }
+
+    /// The display list of the active tab for the vector painters & the
+    /// size of the document they paint, both scaled by the page zoom
+    fn vector_display_list(&mut self, full_page: bool) -> (FrameSize, DisplayList) {
+        let zoom = self.zoom.page_factor();
+        let page = self.tabs.active_page_mut().expect("No tab to paint");
+        page.restyle();
+        let main_frame = page.main_frame();
+        let (width, viewport_height) = main_frame.size();
+
+        let (size, display_list) = match main_frame.layout().root() {
+            Some(layout_root) if full_page => {
+                let height = (layout_root.content_bottom().ceil() as u32).max(viewport_height);
+                ((width, height), build_display_list(layout_root))
+            }
+            Some(layout_root) => ((width, viewport_height), build_display_list(layout_root)),
+            None => ((width, viewport_height), Vec::new()),
+        };
+        let display_list =
+            painting::transform_display_list(display_list, &Transform::scale(zoom, zoom));
+        (device_size(size, zoom), display_list)
+    }
+}
+
+/// Paint the commands under a tile of a bitmap of the document painted
+/// at a device pixel ratio. The tile is in device pixels.
+fn paint_scaled(index: &TileIndex, tile: &Rect, ratio: f32, painter: &mut dyn painting::Painter) {
+    let area = Rect::new(
+        tile.x / ratio,
+        tile.y / ratio,
+        tile.width / ratio,
+        tile.height / ratio,
+    );
+    let transform =
+        Transform::translate(-tile.x, -tile.y).multiply(&Transform::scale(ratio, ratio));
+    painting::paint_area(index, &area, &transform, painter);
+}
+
+/// Paint a tile of a page of a paginated display list with the margins
+/// of the page, hiding the content of the other pages
+fn paint_page(
+    tiles: &TileIndex,
+    style: &PageStyle,
+    index: usize,
+    tile: &Rect,
+    painter: &mut dyn painting::Painter,
+) {
+    let (top, right, bottom, left) = style.margin;
+    let (_, content_height) = style.content_size();
+    let offset = top - index as f32 * content_height.round();
+    // the area of the document under the tile, margins included
+    let area = Rect::new(tile.x - left, tile.y - offset, tile.width, tile.height);
+    let transform = Transform::translate(left - tile.x, offset - tile.y);
+    painting::paint_area(tiles, &area, &transform, painter);
+
+    painter.set_transform(Transform::translate(-tile.x, -tile.y));
+    let white = Color {
+        r: 255,
+        g: 255,
+        b: 255,
+        a: 255,
+    };
+    let margins = [
+        Rect::new(0., 0., style.width, top),
+        Rect::new(0., style.height - bottom, style.width, bottom),
+        Rect::new(0., 0., left, style.height),
+        Rect::new(style.width - right, 0., right, style.height),
+    ];
+    for margin in margins {
+        if margin.width > 0. && margin.height > 0. {
+            painter.fill_rect(margin, white.clone());
+        }
+    }
+}
+
+/// The pixels covered by a region of the frame
+fn pixel_region(region: &Rect) -> (u32, u32, u32, u32) {
+    let x = region.x.max(0.).floor() as u32;
+    let y = region.y.max(0.).floor() as u32;
+    let right = (region.x + region.width).ceil().max(0.) as u32;
+    let bottom = (region.y + region.height).ceil().max(0.) as u32;
+    (x, y, right.saturating_sub(x), bottom.saturating_sub(y))
+}
+
+/// Build the display list of a layout tree, recording the time spent &
+/// the commands of the list while profiling
+fn build_display_list(layout_root: &LayoutBox) -> DisplayList {
+    let display_list = {
+        let _span = profiler::span("display list");
+        painting::build_display_list(layout_root)
+    };
+    profiler::count("display items", painting::command_counts(&display_list));
+    display_list
+}
+
+enum ZoomShortcut {
+    In,
+    Out,
+    Reset,
+}
+
+/// The zoom of a key event with Ctrl (Cmd on macOS): `=` or `+` zoom in,
+/// `-` zooms out & `0` resets the zoom
+fn zoom_shortcut(event: &KeyboardEvent) -> Option<ZoomShortcut> {
+    let modifiers = &event.modifiers;
+    if event.kind != KeyEventKind::KeyDown || !(modifiers.ctrl || modifiers.meta) || modifiers.alt {
+        return None;
+    }
+    match event.key.as_str() {
+        "=" | "+" => Some(ZoomShortcut::In),
+        "-" => Some(ZoomShortcut::Out),
+        "0" => Some(ZoomShortcut::Reset),
+        _ => None,
+    }
+}
+
+/// Whether a key event is the shortcut copying the selected text
+fn is_copy_shortcut(event: &KeyboardEvent) -> bool {
+    let modifiers = &event.modifiers;
+    event.kind == KeyEventKind::KeyDown
+        && (modifiers.ctrl || modifiers.meta)
+        && !modifiers.alt
+        && event.key.eq_ignore_ascii_case("c")
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use futures::executor::block_on;
+
+    const SIZE: FrameSize = (100, 100);
+    const RED: [u8; 4] = [255, 0, 0, 255];
+    const WHITE: [u8; 4] = [255, 255, 255, 255];
+
+    /// Render a document in the viewport with the user agent stylesheet
+    /// & the styles of the document
+    fn render(html: &str, css: &str) -> Bitmap {
+        block_on(load(html, css).output_viewport())
+    }
+
+    fn load(html: &str, css: &str) -> Renderer<'static> {
+        let mut renderer = block_on(Renderer::with_backend(PaintBackend::Cpu, Antialiasing::Off));
+        renderer.initialize(RendererInitializeParams {
+            viewport: SIZE,
+            device_pixel_ratio: 1.,
+        });
+        renderer.set_author_css(&[css.to_string()]);
+        renderer.load_html(html.to_string());
+        renderer
+    }
+
+    fn pixel(bitmap: &Bitmap, x: u32, y: u32) -> &[u8] {
+        let index = ((y * SIZE.0 + x) * 4) as usize;
+        &bitmap[index..index + 4]
+    }
+
+    #[test]
+    fn render_lists() {
+        let bitmap = render("<ul><li>one</li></ul>", "li { background-color: red }");
+        // the items are indented by the padding of the list
+        assert_eq!(pixel(&bitmap, 70, 20), RED);
+        assert_eq!(pixel(&bitmap, 10, 20), WHITE);
+    }
+
+    #[test]
+    fn render_tables() {
+        assert_eq!(pixel(&render("<table></table>", ""), 0, 0), WHITE);
+
+        let bitmap = render(
+            "<table><tr><td><div></div></td></tr></table>",
+            "td { background-color: red } div { width: 50px; height: 10px }",
+        );
+        // the cell is placed after the border spacing of the table
+        assert_eq!(pixel(&bitmap, 1, 7), WHITE);
+        assert_eq!(pixel(&bitmap, 20, 7), RED);
+        assert_eq!(pixel(&bitmap, 70, 7), WHITE);
+    }
+
+    #[test]
+    fn render_preformatted_text() {
+        let bitmap = render(
+            "<pre>a\n\nb</pre><center>c</center>",
+            "pre { background-color: red }",
+        );
+        // the line breaks are kept, the block is three lines tall
+        assert_eq!(pixel(&bitmap, 50, 15), WHITE);
+        assert_eq!(pixel(&bitmap, 50, 16), RED);
+        assert_eq!(pixel(&bitmap, 50, 70), RED);
+        assert_eq!(pixel(&bitmap, 50, 75), WHITE);
+    }
+
+    #[test]
+    fn clamp_full_page_height() {
+        let mut renderer = load("<div></div>", "div { height: 10000000px }");
+        let (size, bitmap) = block_on(renderer.output_full_page());
+        assert_eq!(size, (SIZE.0, MAX_VIEWPORT_DIMENSION));
+        assert_eq!(bitmap.len(), (size.0 * size.1 * 4) as usize);
+
+        renderer.set_max_full_page_height(1000);
+        let (size, _) = block_on(renderer.output_full_page());
+        assert_eq!(size, (SIZE.0, 1000));
+    }
 }
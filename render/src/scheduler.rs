@@ -0,0 +1,144 @@
+use std::time::Duration;
+
+/// The default frame rate of a windowed renderer
+pub const DEFAULT_FPS: u32 = 60;
+
+/// Decides when a host loop paints the next frame, like the
+/// `requestAnimationFrame` loop of a browser. Invalidations between two
+/// frames are coalesced into a single repaint and frames are aligned to a
+/// fixed interval, the vsync of the display, so a loop receiving many
+/// messages still paints at most once per interval.
+///
+/// The times are the time since the page was loaded, the same clock
+/// `Renderer::advance_time` uses.
+#[derive(Debug)]
+pub struct FrameScheduler {
+    frame_interval: Duration,
+    /// The vsync tick of the last painted frame
+    last_frame: Option<Duration>,
+    /// Whether something changed since the last frame
+    invalidated: bool,
+    /// Whether the page kept animating after the last frame
+    animating: bool,
+}
+
+impl Default for FrameScheduler {
+    fn default() -> Self {
+        Self::new(DEFAULT_FPS)
+    }
+}
+
+impl FrameScheduler {
+    pub fn new(fps: u32) -> Self {
+        Self {
+            frame_interval: Duration::from_secs(1) / fps.max(1),
+            last_frame: None,
+            invalidated: false,
+            animating: false,
+        }
+    }
+
+    pub fn frame_interval(&self) -> Duration {
+        self.frame_interval
+    }
+
+    /// Request a repaint in the next frame. Requests made before
+    /// the frame is painted are coalesced into one repaint.
+    pub fn invalidate(&mut self) {
+        self.invalidated = true;
+    }
+
+    /// Whether a frame needs to be painted at some point
+    pub fn needs_frame(&self) -> bool {
+        self.invalidated || self.animating
+    }
+
+    /// The time the next frame should be painted at, or `None` if nothing
+    /// changed & the loop can sleep until the next invalidation
+    pub fn next_frame_time(&self) -> Option<Duration> {
+        if !self.needs_frame() {
+            return None;
+        }
+        Some(match self.last_frame {
+            Some(last_frame) => last_frame + self.frame_interval,
+            None => Duration::from_secs(0),
+        })
+    }
+
+    /// Whether a frame should be painted at this time
+    pub fn is_frame_due(&self, now: Duration) -> bool {
+        match self.next_frame_time() {
+            Some(time) => now >= time,
+            None => false,
+        }
+    }
+
+    /// Record that a frame was painted at this time. The page keeps
+    /// scheduling frames while its animations are running.
+    pub fn frame_painted(&mut self, now: Duration, has_running_animations: bool) {
+        self.invalidated = false;
+        self.animating = has_running_animations;
+        // snap to the last vsync tick so a late frame doesn't push
+        // the following frames off the interval
+        let interval = self.frame_interval.as_nanos();
+        let tick = now.as_nanos() / interval * interval;
+        self.last_frame = Some(Duration::from_nanos(tick as u64));
+    }
+}
+
+/// The number of frames rendered at a frame rate for a duration, the
+/// last frame starting before the end of the duration. A render is at
+/// least one frame long.
+pub fn frame_count(duration: Duration, fps: u32) -> u32 {
+    const NANOS_PER_SEC: u128 = 1_000_000_000;
+    // in integers, so a duration of whole frames isn't rounded up
+    let count = (duration.as_nanos() * fps as u128 + NANOS_PER_SEC - 1) / NANOS_PER_SEC;
+    count.clamp(1, u32::MAX as u128) as u32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ms(millis: u64) -> Duration {
+        Duration::from_millis(millis)
+    }
+
+    #[test]
+    fn schedule_frames() {
+        let mut scheduler = FrameScheduler::new(50);
+        assert_eq!(scheduler.next_frame_time(), None);
+
+        // the first frame is painted at once
+        scheduler.invalidate();
+        assert!(scheduler.is_frame_due(ms(0)));
+        scheduler.frame_painted(ms(5), false);
+        assert_eq!(scheduler.next_frame_time(), None);
+
+        // invalidations are coalesced into the next vsync tick
+        scheduler.invalidate();
+        scheduler.invalidate();
+        assert_eq!(scheduler.next_frame_time(), Some(ms(20)));
+        assert!(!scheduler.is_frame_due(ms(10)));
+        assert!(scheduler.is_frame_due(ms(20)));
+
+        // a late frame is aligned to the tick it was painted in
+        scheduler.frame_painted(ms(47), true);
+        assert_eq!(scheduler.next_frame_time(), Some(ms(60)));
+
+        // animations keep scheduling frames until they finish
+        scheduler.frame_painted(ms(60), false);
+        assert!(!scheduler.needs_frame());
+    }
+
+    #[test]
+    fn count_frames() {
+        assert_eq!(frame_count(ms(1000), 30), 30);
+        assert_eq!(frame_count(ms(100), 30), 3);
+        assert_eq!(frame_count(ms(1500), 24), 36);
+        // a frame started before the end is rendered whole
+        assert_eq!(frame_count(ms(1001), 1), 2);
+        assert_eq!(frame_count(ms(10), 60), 1);
+        assert_eq!(frame_count(ms(0), 60), 1);
+    }
+}
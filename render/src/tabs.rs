@@ -0,0 +1,351 @@
+use super::frame::FrameSize;
+use super::page::Page;
+use dom::visited::{VisitedLinks, VisitedLinksRef};
+use io::cancellation::CancellationToken;
+use loaders::cookies::{CookieJar, CookieJarRef};
+use loaders::http::{HttpConfig, HttpHandler};
+use loaders::scheme::{SchemeHandler, SchemeHandlers};
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use style::media::ColorScheme;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct TabId(u32);
+
+/// The pages opened in a renderer. Every tab keeps its own document,
+/// render tree & layout tree, so switching to a tab shows its document
+/// as it was left without parsing it again. Only the active tab is
+/// painted.
+pub struct Tabs {
+    tabs: Vec<(TabId, Page)>,
+    active: Option<TabId>,
+    next_id: u32,
+    /// The viewport size, user & author stylesheets, animation, layout &
+    /// text zoom settings, cookies & scheme handlers shared by all the tabs
+    size: FrameSize,
+    user_agent_css: Option<String>,
+    user_css: Option<String>,
+    author_css: Vec<String>,
+    animations_enabled: bool,
+    lazy_layout: bool,
+    text_zoom: f32,
+    color_scheme: ColorScheme,
+    reduced_motion: bool,
+    forced_colors: bool,
+    cancellation: CancellationToken,
+    cookie_jar: CookieJarRef,
+    /// The URLs visited in every tab
+    visited_links: VisitedLinksRef,
+    schemes: SchemeHandlers,
+}
+
+impl Tabs {
+    pub fn new() -> Self {
+        let mut tabs = Self {
+            tabs: Vec::new(),
+            active: None,
+            next_id: 0,
+            size: (0, 0),
+            user_agent_css: None,
+            user_css: None,
+            author_css: Vec::new(),
+            animations_enabled: true,
+            lazy_layout: false,
+            text_zoom: 1.,
+            color_scheme: ColorScheme::default(),
+            reduced_motion: false,
+            forced_colors: false,
+            cancellation: CancellationToken::new(),
+            cookie_jar: Arc::new(Mutex::new(CookieJar::new())),
+            visited_links: Rc::new(RefCell::new(VisitedLinks::new())),
+            schemes: SchemeHandlers::new(),
+        };
+        tabs.set_http_config(&HttpConfig::default())
+            .expect("The default HTTP config is valid");
+        tabs
+    }
+
+    /// Open a new empty tab and make it the active tab
+    pub fn open(&mut self) -> TabId {
+        let id = TabId(self.next_id);
+        self.next_id += 1;
+
+        let mut page = Page::new();
+        page.resize(self.size);
+        page.set_scheme_handlers(self.schemes.clone());
+        if let Some(css) = &self.user_agent_css {
+            page.set_user_agent_css(css);
+        }
+        if let Some(css) = &self.user_css {
+            page.set_user_css(css);
+        }
+        if !self.author_css.is_empty() {
+            page.set_author_css(&self.author_css);
+        }
+        page.set_animations_enabled(self.animations_enabled);
+        page.set_lazy_layout(self.lazy_layout);
+        page.set_text_zoom(self.text_zoom);
+        page.set_color_scheme(self.color_scheme);
+        page.set_visited_links(self.visited_links.clone());
+        page.set_reduced_motion(self.reduced_motion);
+        page.set_forced_colors(self.forced_colors);
+        page.set_cancellation(self.cancellation.clone());
+
+        self.tabs.push((id, page));
+        self.active = Some(id);
+        id
+    }
+
+    /// Make a tab the active tab. Returns false if there's no such tab.
+    pub fn switch_to(&mut self, id: TabId) -> bool {
+        if self.get(id).is_none() {
+            return false;
+        }
+        self.active = Some(id);
+        true
+    }
+
+    /// Close a tab. When the active tab is closed, the tab next to it
+    /// becomes active.
+    pub fn close(&mut self, id: TabId) -> bool {
+        let index = match self.tabs.iter().position(|(tab, _)| *tab == id) {
+            Some(index) => index,
+            None => return false,
+        };
+        self.tabs.remove(index);
+
+        if self.active == Some(id) {
+            let next = index.min(self.tabs.len().saturating_sub(1));
+            self.active = self.tabs.get(next).map(|(tab, _)| *tab);
+        }
+        true
+    }
+
+    pub fn active(&self) -> Option<TabId> {
+        self.active
+    }
+
+    pub fn ids(&self) -> Vec<TabId> {
+        self.tabs.iter().map(|(id, _)| *id).collect()
+    }
+
+    pub fn get(&self, id: TabId) -> Option<&Page> {
+        self.tabs
+            .iter()
+            .find(|(tab, _)| *tab == id)
+            .map(|(_, page)| page)
+    }
+
+    pub fn get_mut(&mut self, id: TabId) -> Option<&mut Page> {
+        self.tabs
+            .iter_mut()
+            .find(|(tab, _)| *tab == id)
+            .map(|(_, page)| page)
+    }
+
+    pub fn active_page(&self) -> Option<&Page> {
+        self.get(self.active?)
+    }
+
+    pub fn active_page_mut(&mut self) -> Option<&mut Page> {
+        self.get_mut(self.active?)
+    }
+
+    /// Resize the viewport of all the tabs, e.g. when the window is resized
+    pub fn resize(&mut self, size: FrameSize) {
+        self.size = size;
+        for (_, page) in &mut self.tabs {
+            page.resize(size);
+        }
+    }
+
+    pub fn cookie_jar(&self) -> &CookieJarRef {
+        &self.cookie_jar
+    }
+
+    /// Connect to the servers with a config in every tab, from the next
+    /// document loaded. The tabs share their cookies whatever the config.
+    pub fn set_http_config(&mut self, config: &HttpConfig) -> Result<(), String> {
+        let handler = HttpHandler::new(config)?.with_cookie_jar(self.cookie_jar.clone());
+        self.schemes.register_http(handler);
+        self.update_scheme_handlers();
+        Ok(())
+    }
+
+    /// Load the URLs of a protocol with a handler in every tab, from the
+    /// next document loaded
+    pub fn register_scheme_handler<H: SchemeHandler + 'static>(
+        &mut self,
+        protocol: &str,
+        handler: H,
+    ) {
+        self.schemes.register(protocol, handler);
+        self.update_scheme_handlers();
+    }
+
+    fn update_scheme_handlers(&mut self) {
+        for (_, page) in &mut self.tabs {
+            page.set_scheme_handlers(self.schemes.clone());
+        }
+    }
+
+    /// Run the CSS animations & transitions or not in every tab, from the
+    /// next document loaded
+    pub fn set_animations_enabled(&mut self, enabled: bool) {
+        self.animations_enabled = enabled;
+        for (_, page) in &mut self.tabs {
+            page.set_animations_enabled(enabled);
+        }
+    }
+
+    /// Skip the layout of the contents outside of the viewport in every
+    /// tab or not, from the next layout
+    pub fn set_lazy_layout(&mut self, enabled: bool) {
+        self.lazy_layout = enabled;
+        for (_, page) in &mut self.tabs {
+            page.set_lazy_layout(enabled);
+        }
+    }
+
+    /// Scale the font sizes of every tab, laying out their documents again
+    pub fn set_text_zoom(&mut self, zoom: f32) {
+        self.text_zoom = zoom;
+        for (_, page) in &mut self.tabs {
+            page.set_text_zoom(zoom);
+        }
+    }
+
+    /// Set the color scheme the user prefers in every tab, styling their
+    /// documents again
+    pub fn set_color_scheme(&mut self, color_scheme: ColorScheme) {
+        self.color_scheme = color_scheme;
+        for (_, page) in &mut self.tabs {
+            page.set_color_scheme(color_scheme);
+        }
+    }
+
+    /// Prefer reduced motion or not in every tab, styling their documents
+    /// again
+    pub fn set_reduced_motion(&mut self, reduced_motion: bool) {
+        self.reduced_motion = reduced_motion;
+        for (_, page) in &mut self.tabs {
+            page.set_reduced_motion(reduced_motion);
+        }
+    }
+
+    /// Activate the forced colors in every tab or not, styling their
+    /// documents again
+    pub fn set_forced_colors(&mut self, forced_colors: bool) {
+        self.forced_colors = forced_colors;
+        for (_, page) in &mut self.tabs {
+            page.set_forced_colors(forced_colors);
+        }
+    }
+
+    /// Stop the work on the documents of every tab once a token is
+    /// cancelled
+    pub fn set_cancellation(&mut self, token: CancellationToken) {
+        self.cancellation = token.clone();
+        for (_, page) in &mut self.tabs {
+            page.set_cancellation(token.clone());
+        }
+    }
+
+    pub fn set_user_agent_css(&mut self, css: &str) {
+        self.user_agent_css = Some(css.to_string());
+        for (_, page) in &mut self.tabs {
+            page.set_user_agent_css(css);
+        }
+    }
+
+    pub fn set_user_css(&mut self, css: &str) {
+        self.user_css = Some(css.to_string());
+        for (_, page) in &mut self.tabs {
+            page.set_user_css(css);
+        }
+    }
+
+    pub fn set_author_css(&mut self, css_list: &[String]) {
+        self.author_css = css_list.to_vec();
+        for (_, page) in &mut self.tabs {
+            page.set_author_css(css_list);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use loaders::scheme::Request;
+    use style::render_tree::RenderNodeRef;
+
+    fn root_style_node(page: &Page) -> RenderNodeRef {
+        let layout = page.main_frame().layout();
+        layout.root().as_ref().unwrap().render_node.clone().unwrap()
+    }
+
+    #[test]
+    fn switch_tabs() {
+        let mut tabs = Tabs::new();
+        tabs.resize((100, 100));
+
+        let first = tabs.open();
+        tabs.active_page_mut()
+            .unwrap()
+            .load_html("<p>first</p>".to_string());
+        let second = tabs.open();
+        tabs.active_page_mut()
+            .unwrap()
+            .load_html("<p>second</p>".to_string());
+        assert_eq!(tabs.active(), Some(second));
+        assert_eq!(tabs.ids(), vec![first, second]);
+
+        // switching back shows the same trees, the document isn't parsed again
+        let first_root = root_style_node(tabs.get(first).unwrap());
+        assert!(tabs.switch_to(first));
+        assert!(root_style_node(tabs.active_page().unwrap()) == first_root);
+        assert_eq!(tabs.active_page().unwrap().main_frame().size(), (100, 100));
+
+        // closing the active tab activates the one next to it
+        assert!(tabs.close(first));
+        assert_eq!(tabs.active(), Some(second));
+        assert!(!tabs.switch_to(first));
+        assert!(tabs.close(second));
+        assert_eq!(tabs.active(), None);
+    }
+
+    #[test]
+    fn load_from_custom_protocol() {
+        struct AppHandler;
+
+        impl SchemeHandler for AppHandler {
+            fn load(&self, request: &Request) -> Result<Vec<u8>, String> {
+                match request.url.path() {
+                    "/style.css" => Ok(b"p { color: red; } a { color: blue; }".to_vec()),
+                    path => Err(format!("Not found: {}", path)),
+                }
+            }
+        }
+
+        let mut tabs = Tabs::new();
+        tabs.open();
+        tabs.register_scheme_handler("app", AppHandler);
+        tabs.active_page_mut().unwrap().load_html(
+            "<link rel=\"stylesheet\" href=\"app://moon/style.css\">\
+             <link rel=\"stylesheet\" href=\"app://moon/missing.css\">\
+             <link rel=\"stylesheet\" href=\"data:text/css;base64,cCB7IH0=\">"
+                .to_string(),
+        );
+
+        let document = tabs.active_page().unwrap().main_frame().document().unwrap();
+        let rule_counts = document
+            .borrow()
+            .as_document()
+            .stylesheets()
+            .iter()
+            .map(|stylesheet| stylesheet.len())
+            .collect::<Vec<usize>>();
+        assert_eq!(rule_counts, vec![2, 1]);
+    }
+}
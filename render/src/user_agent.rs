@@ -0,0 +1,29 @@
+/// The stylesheets of the browser: the default styles of the
+/// elements, and the styles supplied by the user. Both are
+/// applied before the styles of the document. The quirks of the
+/// default styles are only applied to the documents in quirks mode.
+use css::cssom::stylesheet::StyleSheet;
+use css::parser::Parser;
+use css::tokenizer::token::Token;
+use css::tokenizer::Tokenizer;
+
+const USER_AGENT_CSS: &str = include_str!("user_agent.css");
+const QUIRKS_CSS: &str = include_str!("quirks.css");
+
+pub fn user_agent_stylesheet() -> StyleSheet {
+    parse_stylesheet(USER_AGENT_CSS)
+}
+
+pub fn quirks_stylesheet() -> StyleSheet {
+    parse_stylesheet(QUIRKS_CSS)
+}
+
+pub fn user_stylesheet(css: &str) -> StyleSheet {
+    parse_stylesheet(css)
+}
+
+pub fn parse_stylesheet(css: &str) -> StyleSheet {
+    let tokenizer = Tokenizer::new(css.chars());
+    let mut parser = Parser::<Token>::new(tokenizer.run());
+    parser.parse_a_css_stylesheet()
+}
@@ -0,0 +1,234 @@
+use super::frame::FrameSize;
+use super::zoom::Zoom;
+use std::str::FromStr;
+use style::media::ColorScheme;
+
+/// The largest width or height of a viewport, in CSS & in device pixels
+pub const MAX_VIEWPORT_DIMENSION: u32 = 16384;
+
+/// The largest number of pixels of a bitmap, as many as in the largest
+/// viewport, i.e. 1 GiB of RGBA pixels
+pub const MAX_BITMAP_AREA: usize =
+    MAX_VIEWPORT_DIMENSION as usize * MAX_VIEWPORT_DIMENSION as usize;
+
+/// The largest ratio of device pixels to CSS pixels
+pub const MAX_DEVICE_PIXEL_RATIO: f32 = 8.;
+
+/// The viewports of the common devices, as (name, width, height, ratio)
+const PRESETS: &[(&str, u32, u32, f32)] = &[
+    ("mobile", 375, 667, 2.),
+    ("tablet", 768, 1024, 2.),
+    ("desktop", 1280, 800, 1.),
+    ("1080p", 1920, 1080, 1.),
+];
+
+/// The viewport a document is laid out in, in CSS pixels, & the number
+/// of device pixels per CSS pixel of the bitmaps painted from it, e.g. 2
+/// for the screens of phones. The vector outputs & the printed pages are
+/// in CSS pixels, whatever the ratio.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ViewportConfig {
+    pub width: u32,
+    pub height: u32,
+    pub device_pixel_ratio: f32,
+    /// The zoom the document is rendered at. A page zoom scales the
+    /// bitmaps like the ratio, but the document is laid out in a viewport
+    /// smaller by the zoom factor.
+    pub zoom: Zoom,
+    /// The color scheme the `prefers-color-scheme` media feature matches
+    pub color_scheme: ColorScheme,
+    /// Whether the user prefers reduced motion
+    pub reduced_motion: bool,
+    /// Whether the `forced-colors` media feature is active
+    pub forced_colors: bool,
+}
+
+impl ViewportConfig {
+    pub fn new(width: u32, height: u32) -> Self {
+        Self {
+            width,
+            height,
+            device_pixel_ratio: 1.,
+            zoom: Zoom::default(),
+            color_scheme: ColorScheme::default(),
+            reduced_motion: false,
+            forced_colors: false,
+        }
+    }
+
+    /// The size of the viewport in CSS pixels
+    pub fn size(&self) -> FrameSize {
+        (self.width, self.height)
+    }
+
+    /// The size of the bitmaps of the viewport in device pixels
+    pub fn device_size(&self) -> FrameSize {
+        device_size(self.size(), self.device_pixel_ratio)
+    }
+}
+
+impl From<FrameSize> for ViewportConfig {
+    fn from((width, height): FrameSize) -> Self {
+        Self::new(width, height)
+    }
+}
+
+/// Parse a viewport like `1280x720`, `375x667@2x` or a preset, e.g.
+/// `mobile`, `tablet`, `desktop` or `1080p`. The ratio of a preset can be
+/// changed too, e.g. `mobile@3x`.
+impl FromStr for ViewportConfig {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let (size, ratio) = match value.rsplit_once('@') {
+            Some((size, ratio)) => (size, Some(parse_ratio(ratio)?)),
+            None => (value, None),
+        };
+
+        let preset = PRESETS
+            .iter()
+            .find(|(name, ..)| name.eq_ignore_ascii_case(size));
+        let mut viewport = match preset {
+            Some(&(_, width, height, device_pixel_ratio)) => Self {
+                device_pixel_ratio,
+                ..Self::new(width, height)
+            },
+            None => parse_size(size)?,
+        };
+        if let Some(ratio) = ratio {
+            viewport.device_pixel_ratio = ratio;
+        }
+
+        let (width, height) = viewport.device_size();
+        if width.max(height) > MAX_VIEWPORT_DIMENSION {
+            return Err(format!(
+                "The viewport {:?} is {}x{} device pixels, expected at most {} pixels wide & tall",
+                value, width, height, MAX_VIEWPORT_DIMENSION
+            ));
+        }
+        Ok(viewport)
+    }
+}
+
+fn parse_size(size: &str) -> Result<ViewportConfig, String> {
+    let presets = PRESETS
+        .iter()
+        .map(|(name, ..)| *name)
+        .collect::<Vec<_>>()
+        .join(", ");
+    let (width, height) = size.split_once(|c| c == 'x' || c == 'X').ok_or_else(|| {
+        format!(
+            "Invalid viewport {:?}, expected WIDTHxHEIGHT (e.g. 1280x720) or one of {}",
+            size, presets
+        )
+    })?;
+    Ok(ViewportConfig::new(
+        parse_dimension("width", width)?,
+        parse_dimension("height", height)?,
+    ))
+}
+
+fn parse_dimension(name: &str, value: &str) -> Result<u32, String> {
+    let range = 1..=MAX_VIEWPORT_DIMENSION;
+    match value.trim().parse::<u32>() {
+        Ok(dimension) if range.contains(&dimension) => Ok(dimension),
+        _ => Err(format!(
+            "Invalid viewport {} {:?}, expected a number of pixels between {} and {}",
+            name,
+            value,
+            range.start(),
+            range.end()
+        )),
+    }
+}
+
+/// Parse a device pixel ratio like `2x` or `1.5x`
+fn parse_ratio(ratio: &str) -> Result<f32, String> {
+    let number = ratio
+        .strip_suffix(|c| c == 'x' || c == 'X')
+        .unwrap_or(ratio);
+    match number.parse::<f32>() {
+        Ok(ratio) if ratio > 0. && ratio <= MAX_DEVICE_PIXEL_RATIO => Ok(ratio),
+        _ => Err(format!(
+            "Invalid device pixel ratio {:?}, expected a ratio above 0 and up to {}, e.g. 2x",
+            ratio, MAX_DEVICE_PIXEL_RATIO
+        )),
+    }
+}
+
+/// The size in device pixels of a size in CSS pixels
+pub fn device_size(size: FrameSize, device_pixel_ratio: f32) -> FrameSize {
+    let scale = |dimension: u32| ((dimension as f32 * device_pixel_ratio).round() as u32).max(1);
+    (scale(size.0), scale(size.1))
+}
+
+/// The length in bytes of an RGBA bitmap of a size, or `None` when the
+/// bitmap has more pixels than `MAX_BITMAP_AREA`
+pub fn bitmap_len(size: FrameSize) -> Option<usize> {
+    (size.0 as usize)
+        .checked_mul(size.1 as usize)
+        .filter(|area| *area <= MAX_BITMAP_AREA)
+        .map(|area| area * 4)
+}
+
+/// A size cut down to a bitmap of at most `MAX_BITMAP_AREA` pixels,
+/// keeping the width & as many rows as fit
+pub fn clamp_bitmap_size(size: FrameSize) -> FrameSize {
+    let width = (size.0 as usize).clamp(1, MAX_BITMAP_AREA);
+    let height = (size.1 as usize).min(MAX_BITMAP_AREA / width);
+    (width as u32, height as u32)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_viewports() {
+        assert_eq!("1280x720".parse(), Ok(ViewportConfig::new(1280, 720)));
+
+        let viewport = "375x667@2x".parse::<ViewportConfig>().unwrap();
+        assert_eq!(viewport.device_pixel_ratio, 2.);
+        assert_eq!(viewport.device_size(), (750, 1334));
+
+        let viewport = "Mobile".parse::<ViewportConfig>().unwrap();
+        assert_eq!(viewport.size(), (375, 667));
+        assert_eq!(viewport.device_pixel_ratio, 2.);
+        let viewport = "mobile@1.5x".parse::<ViewportConfig>().unwrap();
+        assert_eq!(viewport.device_pixel_ratio, 1.5);
+        assert_eq!("1080p".parse(), Ok(ViewportConfig::new(1920, 1080)));
+
+        assert!("0x600".parse::<ViewportConfig>().is_err());
+        assert!("20000x600".parse::<ViewportConfig>().is_err());
+        assert!("10000x600@2x".parse::<ViewportConfig>().is_err());
+        assert!("800".parse::<ViewportConfig>().is_err());
+        assert!("800x600@0x".parse::<ViewportConfig>().is_err());
+        assert!("800x600@two".parse::<ViewportConfig>().is_err());
+        assert!("phone".parse::<ViewportConfig>().is_err());
+    }
+
+    #[test]
+    fn limit_bitmap_sizes() {
+        assert_eq!(bitmap_len((2, 3)), Some(24));
+        assert_eq!(
+            bitmap_len((MAX_VIEWPORT_DIMENSION, MAX_VIEWPORT_DIMENSION)),
+            Some(MAX_BITMAP_AREA * 4)
+        );
+        assert_eq!(
+            bitmap_len((MAX_VIEWPORT_DIMENSION, MAX_VIEWPORT_DIMENSION + 1)),
+            None
+        );
+        assert_eq!(bitmap_len((u32::MAX, u32::MAX)), None);
+
+        assert_eq!(clamp_bitmap_size((800, 600)), (800, 600));
+        assert_eq!(
+            clamp_bitmap_size((1024, u32::MAX)),
+            (1024, (MAX_BITMAP_AREA / 1024) as u32)
+        );
+        assert_eq!(
+            clamp_bitmap_size((u32::MAX, u32::MAX)),
+            (MAX_BITMAP_AREA as u32, 1)
+        );
+    }
+}
@@ -0,0 +1,137 @@
+use std::str::FromStr;
+
+/// The smallest & the largest zoom factors
+pub const MIN_ZOOM: f32 = 0.3;
+pub const MAX_ZOOM: f32 = 5.;
+
+/// The factors zooming in & out step through, like the zoom levels of
+/// the other browsers
+const ZOOM_LEVELS: &[f32] = &[
+    0.3, 0.5, 0.67, 0.8, 0.9, 1., 1.1, 1.25, 1.5, 1.75, 2., 2.5, 3., 4., 5.,
+];
+
+/// What a zoom scales
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ZoomMode {
+    /// Everything, as if the CSS pixels were larger: the page is laid out
+    /// in a viewport smaller by the zoom factor & painted scaled up
+    Page,
+    /// Only the font sizes, the page is laid out again in the same viewport
+    Text,
+}
+
+/// The zoom of the pages, 1 being the size the pages are designed at
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Zoom {
+    pub factor: f32,
+    pub mode: ZoomMode,
+}
+
+impl Zoom {
+    pub fn new(factor: f32, mode: ZoomMode) -> Self {
+        Self {
+            factor: factor.max(MIN_ZOOM).min(MAX_ZOOM),
+            mode,
+        }
+    }
+
+    /// The next zoom level above the zoom
+    pub fn zoom_in(&self) -> Self {
+        let factor = ZOOM_LEVELS
+            .iter()
+            .copied()
+            .find(|level| *level > self.factor + f32::EPSILON)
+            .unwrap_or(MAX_ZOOM);
+        Self::new(factor, self.mode)
+    }
+
+    /// The next zoom level below the zoom
+    pub fn zoom_out(&self) -> Self {
+        let factor = ZOOM_LEVELS
+            .iter()
+            .copied()
+            .rev()
+            .find(|level| *level < self.factor - f32::EPSILON)
+            .unwrap_or(MIN_ZOOM);
+        Self::new(factor, self.mode)
+    }
+
+    /// The factor the CSS pixels are scaled by when painted
+    pub fn page_factor(&self) -> f32 {
+        match self.mode {
+            ZoomMode::Page => self.factor,
+            ZoomMode::Text => 1.,
+        }
+    }
+
+    /// The factor the font sizes are scaled by
+    pub fn text_factor(&self) -> f32 {
+        match self.mode {
+            ZoomMode::Page => 1.,
+            ZoomMode::Text => self.factor,
+        }
+    }
+}
+
+impl Default for Zoom {
+    fn default() -> Self {
+        Self::new(1., ZoomMode::Page)
+    }
+}
+
+/// Parse a page zoom like `1.5` or `150%`
+impl FromStr for Zoom {
+    type Err = String;
+
+    fn from_str(value: &str) -> Result<Self, Self::Err> {
+        let value = value.trim();
+        let factor = match value.strip_suffix('%') {
+            Some(percentage) => percentage.trim().parse::<f32>().map(|factor| factor / 100.),
+            None => value.parse::<f32>(),
+        };
+        match factor {
+            Ok(factor) if factor >= MIN_ZOOM && factor <= MAX_ZOOM => {
+                Ok(Self::new(factor, ZoomMode::Page))
+            }
+            _ => Err(format!(
+                "Invalid zoom {:?}, expected a factor between {} and {}, e.g. 1.5 or 150%",
+                value, MIN_ZOOM, MAX_ZOOM
+            )),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn step_through_zoom_levels() {
+        let zoom = Zoom::default();
+        assert_eq!(zoom.zoom_in().factor, 1.1);
+        assert_eq!(zoom.zoom_in().zoom_in().factor, 1.25);
+        assert_eq!(zoom.zoom_out().factor, 0.9);
+        // between two levels
+        assert_eq!(Zoom::new(1.2, ZoomMode::Text).zoom_in().factor, 1.25);
+        assert_eq!(Zoom::new(1.2, ZoomMode::Text).zoom_out().factor, 1.1);
+        assert_eq!(
+            Zoom::new(MAX_ZOOM, ZoomMode::Page).zoom_in().factor,
+            MAX_ZOOM
+        );
+        assert_eq!(
+            Zoom::new(MIN_ZOOM, ZoomMode::Page).zoom_out().factor,
+            MIN_ZOOM
+        );
+        assert_eq!(Zoom::new(2., ZoomMode::Text).zoom_in().mode, ZoomMode::Text);
+    }
+
+    #[test]
+    fn parse_zoom() {
+        assert_eq!("1.5".parse(), Ok(Zoom::new(1.5, ZoomMode::Page)));
+        assert_eq!("150%".parse(), Ok(Zoom::new(1.5, ZoomMode::Page)));
+        assert_eq!(" 50 % ".parse(), Ok(Zoom::new(0.5, ZoomMode::Page)));
+        assert!("0".parse::<Zoom>().is_err());
+        assert!("10".parse::<Zoom>().is_err());
+        assert!("large".parse::<Zoom>().is_err());
+    }
+}
@@ -0,0 +1,208 @@
+//! Rendering of many documents listed in a manifest with a single
+//! renderer, so the GPU device & the caches of the renderer are created
+//! once for all the renders instead of once per document. A manifest is
+//! a JSON or TOML file of jobs, e.g. in TOML:
+//!
+//! ```toml
+//! viewport = "1280x720"
+//!
+//! [[jobs]]
+//! input = "index.html"
+//! output = "index.png"
+//!
+//! [[jobs]]
+//! input = "about.html"
+//! output = "about.png"
+//! viewport = "375x667"
+//! full_page = true
+//! ```
+//!
+//! The paths of the jobs are relative to the directory of the manifest.
+use crate::error::CliError;
+use image::{ImageBuffer, Rgba};
+use render::{
+    Antialiasing, ColorScheme, ColorSpace, GpuBackend, RenderHandle, RenderOptions, ViewportConfig,
+};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, Instant};
+
+/// The viewport of the jobs when neither the manifest nor the config
+/// give one
+const DEFAULT_SIZE: (u32, u32) = (1024, 768);
+
+pub struct BatchParams {
+    pub manifest_path: String,
+    /// The viewport of the jobs when the manifest doesn't give one
+    pub viewport: Option<ViewportConfig>,
+    /// The color scheme of every job
+    pub color_scheme: ColorScheme,
+    pub reduced_motion: bool,
+    pub forced_colors: bool,
+    pub antialiasing: Antialiasing,
+    pub gpu_backend: Option<GpuBackend>,
+    pub output_color_space: ColorSpace,
+    pub user_agent_css_path: Option<String>,
+    pub user_css_path: Option<String>,
+    pub deterministic: bool,
+    /// The time after which the render of a job is given up on
+    pub timeout: Option<Duration>,
+    pub log_parse_errors: bool,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Manifest {
+    /// The viewport of the jobs without their own, e.g. `1280x720`,
+    /// `375x667@2x` or `mobile`
+    viewport: Option<String>,
+    jobs: Vec<Job>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct Job {
+    input: PathBuf,
+    /// The image file to write, in the format of its extension
+    output: PathBuf,
+    viewport: Option<String>,
+    #[serde(default)]
+    full_page: bool,
+}
+
+/// Read a manifest, in TOML for a `.toml` file or in JSON otherwise
+pub fn read_manifest(path: &Path) -> Result<Manifest, CliError> {
+    let content = std::fs::read_to_string(path).map_err(|e| CliError::read(path, e))?;
+    let is_toml = path
+        .extension()
+        .map_or(false, |extension| extension.eq_ignore_ascii_case("toml"));
+    let manifest = if is_toml {
+        toml::from_str(&content).map_err(|e| e.to_string())
+    } else {
+        serde_json::from_str(&content).map_err(|e| e.to_string())
+    };
+    manifest.map_err(|e| CliError::Parse(format!("Invalid manifest {}: {}", path.display(), e)))
+}
+
+/// Render the jobs of a manifest one after the other. A job failing is
+/// logged & the other jobs are rendered regardless.
+pub async fn run(params: BatchParams) -> Result<(), CliError> {
+    let manifest_path = Path::new(&params.manifest_path);
+    let manifest = read_manifest(manifest_path)?;
+    let base = manifest_path.parent().unwrap_or_else(|| Path::new(""));
+    let read_css = |path: &Option<String>| match path {
+        Some(path) => std::fs::read_to_string(path)
+            .map(Some)
+            .map_err(|e| CliError::read(path, e)),
+        None => Ok(None),
+    };
+
+    let mut options = RenderOptions::new(DEFAULT_SIZE.into());
+    options.antialiasing = params.antialiasing;
+    options.gpu_backend = params.gpu_backend;
+    options.output_color_space = params.output_color_space;
+    options.deterministic = params.deterministic;
+    options.user_agent_css = read_css(&params.user_agent_css_path)?;
+    options.user_css = read_css(&params.user_css_path)?;
+    options.http_config.timeout = params.timeout;
+    let mut renderer = render::bitmap_renderer(&options).await;
+
+    let mut failures = 0;
+    for job in &manifest.jobs {
+        let start = Instant::now();
+        let viewport = job
+            .viewport
+            .as_deref()
+            .or_else(|| manifest.viewport.as_deref());
+        let result = render_job(&mut renderer, job, base, viewport, &options, &params).await;
+        match result {
+            Ok(()) => log::info!(
+                "Rendered {} into {} in {:?}",
+                job.input.display(),
+                job.output.display(),
+                start.elapsed()
+            ),
+            Err(e) => {
+                log::error!("Unable to render {}: {}", job.input.display(), e);
+                failures += 1;
+            }
+        }
+    }
+
+    if failures > 0 {
+        return Err(CliError::BatchFailed(failures));
+    }
+    Ok(())
+}
+
+async fn render_job(
+    renderer: &mut render::Renderer<'_>,
+    job: &Job,
+    base: &Path,
+    viewport: Option<&str>,
+    options: &RenderOptions,
+    params: &BatchParams,
+) -> Result<(), String> {
+    let mut viewport = match viewport {
+        Some(viewport) => viewport.parse::<ViewportConfig>()?,
+        None => params.viewport.unwrap_or_else(|| DEFAULT_SIZE.into()),
+    };
+    viewport.color_scheme = params.color_scheme;
+    viewport.reduced_motion = params.reduced_motion;
+    viewport.forced_colors = params.forced_colors;
+    let html = std::fs::read_to_string(base.join(&job.input)).map_err(|e| e.to_string())?;
+
+    // every job has its own timeout
+    let options = RenderOptions {
+        viewport,
+        handle: match params.timeout {
+            Some(timeout) => RenderHandle::with_timeout(timeout),
+            None => RenderHandle::new(),
+        },
+        ..options.clone()
+    };
+    let output = render::render_with(renderer, html, options, job.full_page)
+        .await
+        .map_err(|e| e.to_string())?;
+
+    if params.log_parse_errors {
+        for error in &output.diagnostics.parse_errors {
+            log::warn!("{}", error);
+        }
+    }
+
+    let (width, height) = output.size;
+    let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, output.bitmap)
+        .ok_or_else(|| "The bitmap doesn't match its size".to_string())?;
+    buffer
+        .save(base.join(&job.output))
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_manifests() {
+        let json = r#"{
+            "viewport": "1280x720",
+            "jobs": [
+                { "input": "a.html", "output": "a.png" },
+                { "input": "b.html", "output": "b.png", "viewport": "375x667", "full_page": true }
+            ]
+        }"#;
+        let manifest: Manifest = serde_json::from_str(json).unwrap();
+        assert_eq!(manifest.viewport.as_deref(), Some("1280x720"));
+        assert_eq!(manifest.jobs.len(), 2);
+        assert!(!manifest.jobs[0].full_page);
+        assert!(manifest.jobs[1].full_page);
+
+        let toml = r#"
+            [[jobs]]
+            input = "a.html"
+            output = "out/a.png"
+        "#;
+        let manifest: Manifest = toml::from_str(toml).unwrap();
+        assert_eq!(manifest.viewport, None);
+        assert_eq!(manifest.jobs[0].output, PathBuf::from("out/a.png"));
+    }
+}
@@ -1,48 +1,261 @@
+use super::config::Config;
+use crate::batch::BatchParams;
+use crate::error::CliError;
+use crate::logger::LogFilter;
+use crate::serve::ServeParams;
 use clap::ArgMatches;
+use render::{
+    Antialiasing, ColorScheme, ColorSpace, GpuBackend, HttpConfig, ViewportConfig, ZoomMode,
+};
 use std::str::FromStr;
+use std::time::Duration;
 
 pub enum Action {
     RenderOnce(RenderOnceParams),
+    Serve(ServeParams),
+    RenderBatch(BatchParams),
 }
 
 pub struct RenderOnceParams {
     pub html_path: String,
-    pub viewport_size: (u32, u32),
+    pub viewport: ViewportConfig,
     pub output_path: String,
+    pub log_parse_errors: bool,
+    pub antialiasing: Antialiasing,
+    pub gpu_backend: Option<GpuBackend>,
+    pub output_color_space: ColorSpace,
+    pub user_agent_css_path: Option<String>,
+    pub user_css_path: Option<String>,
+    pub full_page: bool,
+    pub paginate: bool,
+    pub deterministic: bool,
+    pub animation: Option<AnimationParams>,
+    pub profile_path: Option<String>,
+    pub print_stats: bool,
+    pub profile_dir: Option<String>,
+    pub http_config: HttpConfig,
+    /// The time after which the render is given up on
+    pub timeout: Option<Duration>,
 }
 
-pub fn get_action<'a>(matches: ArgMatches<'a>) -> Action {
+pub struct AnimationParams {
+    pub duration: Duration,
+    pub fps: u32,
+}
+
+/// The matches of the subcommand of the action
+fn action_matches<'a>(matches: &'a ArgMatches) -> Option<&'a ArgMatches<'a>> {
+    matches
+        .subcommand_matches("render")
+        .or_else(|| matches.subcommand_matches("serve"))
+        .or_else(|| matches.subcommand_matches("render-batch"))
+}
+
+/// The config file of the action, given with `--config` or found in
+/// the current directory
+pub fn get_config(matches: &ArgMatches) -> Result<Config, CliError> {
+    Config::load(action_matches(matches).and_then(|matches| matches.value_of("config")))
+}
+
+/// The filter of the messages logged while running the action
+pub fn get_log_filter(matches: &ArgMatches, config: &Config) -> LogFilter {
+    action_matches(matches)
+        .and_then(|matches| get_arg(matches, "log"))
+        .or_else(|| config.log.clone())
+        .unwrap_or_default()
+}
+
+/// Whether the errors are written to stderr as JSON objects
+pub fn get_json_errors(matches: &ArgMatches) -> bool {
+    action_matches(matches).map_or(false, |matches| get_flag(matches, "json-errors"))
+}
+
+/// Get the action of the command line, with the settings of a config for
+/// the flags that aren't given
+pub fn get_action<'a>(matches: ArgMatches<'a>, config: &Config) -> Result<Action, CliError> {
+    if config.fonts_dir.is_some() {
+        log::warn!("Text isn't painted with fonts yet, ignoring fonts_dir");
+    }
+
     if let Some(matches) = matches.subcommand_matches("render") {
         let html: String = get_arg(&matches, "html").unwrap();
-        let raw_size: String = get_arg(&matches, "size").unwrap();
         let output_path: String = get_arg(&matches, "output").unwrap();
 
         let is_render_once = get_flag(&matches, "once");
+        let log_parse_errors = get_flag(&matches, "log-parse-errors");
+        let antialiasing = get_antialiasing(&matches, config);
+        let gpu_backend: Option<GpuBackend> =
+            get_arg(&matches, "gpu-backend").or(config.gpu_backend);
+        let output_color_space: ColorSpace = get_arg(&matches, "output-colorspace").unwrap();
+        let user_agent_css_path: Option<String> =
+            get_arg(&matches, "user-agent-css").or_else(|| config.user_agent_css_path.clone());
+        let user_css_path: Option<String> =
+            get_arg(&matches, "user-css").or_else(|| config.user_css_path.clone());
+        let full_page = get_flag(&matches, "full-page");
+        let paginate = get_flag(&matches, "paginate");
+        let deterministic = get_flag(&matches, "deterministic");
+        let profile_path: Option<String> = get_arg(&matches, "profile");
+        let print_stats = get_flag(&matches, "stats");
+        let profile_dir: Option<String> = get_arg(&matches, "profile-dir");
+        let timeout = get_timeout(&matches, config)?;
+        let mut http_config = HttpConfig::default();
+        // a request can't take longer than the whole render
+        http_config.timeout = timeout;
+        http_config.proxy = get_arg(&matches, "proxy").or_else(|| config.proxy.clone());
+        http_config.insecure = get_flag(&matches, "insecure") || config.insecure == Some(true);
+        if let Some(user_agent) =
+            get_arg(&matches, "user-agent").or_else(|| config.user_agent.clone())
+        {
+            http_config.user_agent = user_agent;
+        }
+        let animation = match matches.values_of("animate") {
+            Some(values) => {
+                let values = values.collect::<Vec<&str>>();
+                Some(AnimationParams {
+                    duration: parse_duration(values[0])?,
+                    fps: values[1]
+                        .parse()
+                        .ok()
+                        .filter(|fps| *fps > 0)
+                        .ok_or_else(|| {
+                            CliError::Parse(format!("Invalid frame rate: {}", values[1]))
+                        })?,
+                })
+            }
+            None => None,
+        };
 
-        let viewport_size = parse_size(&raw_size);
+        let mut viewport: ViewportConfig = match matches.value_of("size") {
+            Some(size) => size.parse().map_err(CliError::Parse)?,
+            None => config.viewport.ok_or_else(|| {
+                CliError::Parse(
+                    "No viewport, give --size or set the viewport of the config".to_string(),
+                )
+            })?,
+        };
+        if let Some(zoom) = matches.value_of("zoom") {
+            viewport.zoom = zoom.parse().map_err(CliError::Parse)?;
+        }
+        if get_flag(&matches, "text-zoom") {
+            viewport.zoom.mode = ZoomMode::Text;
+        }
+        viewport.color_scheme = get_color_scheme(&matches, config)?;
+        viewport.reduced_motion =
+            get_flag(&matches, "reduced-motion") || config.reduced_motion == Some(true);
+        viewport.forced_colors =
+            get_flag(&matches, "forced-colors") || config.forced_colors == Some(true);
 
         if is_render_once {
-            return Action::RenderOnce(RenderOnceParams {
+            return Ok(Action::RenderOnce(RenderOnceParams {
                 html_path: html,
                 output_path,
-                viewport_size,
-            });
+                viewport,
+                log_parse_errors,
+                antialiasing,
+                gpu_backend,
+                output_color_space,
+                user_agent_css_path,
+                user_css_path,
+                full_page,
+                paginate,
+                deterministic,
+                animation,
+                profile_path,
+                print_stats,
+                profile_dir,
+                http_config,
+                timeout,
+            }));
         }
     }
 
-    unreachable!("Invalid action provided!");
+    if let Some(matches) = matches.subcommand_matches("serve") {
+        let port = matches.value_of("port").unwrap();
+        return Ok(Action::Serve(ServeParams {
+            port: port
+                .parse()
+                .map_err(|_| CliError::Parse(format!("Invalid port: {}", port)))?,
+            root: get_arg(&matches, "root").unwrap(),
+            antialiasing: get_antialiasing(&matches, config),
+            gpu_backend: get_arg(&matches, "gpu-backend").or(config.gpu_backend),
+            deterministic: get_flag(&matches, "deterministic"),
+            timeout: get_timeout(&matches, config)?,
+        }));
+    }
+
+    if let Some(matches) = matches.subcommand_matches("render-batch") {
+        return Ok(Action::RenderBatch(BatchParams {
+            manifest_path: get_arg(&matches, "manifest").unwrap(),
+            viewport: config.viewport,
+            color_scheme: get_color_scheme(&matches, config)?,
+            reduced_motion: get_flag(&matches, "reduced-motion")
+                || config.reduced_motion == Some(true),
+            forced_colors: get_flag(&matches, "forced-colors")
+                || config.forced_colors == Some(true),
+            antialiasing: get_antialiasing(&matches, config),
+            gpu_backend: get_arg(&matches, "gpu-backend").or(config.gpu_backend),
+            output_color_space: get_arg(&matches, "output-colorspace").unwrap(),
+            user_agent_css_path: get_arg(&matches, "user-agent-css")
+                .or_else(|| config.user_agent_css_path.clone()),
+            user_css_path: get_arg(&matches, "user-css").or_else(|| config.user_css_path.clone()),
+            deterministic: get_flag(&matches, "deterministic"),
+            timeout: get_timeout(&matches, config)?,
+            log_parse_errors: get_flag(&matches, "log-parse-errors"),
+        }));
+    }
+
+    Err(CliError::Parse(
+        "No action to run, e.g. render --once, see --help".to_string(),
+    ))
+}
+
+/// The antialiasing given on the command line, or of the config
+fn get_antialiasing(matches: &ArgMatches, config: &Config) -> Antialiasing {
+    // the flag has a default value when it isn't given
+    if matches.occurrences_of("aa") > 0 {
+        return get_arg(matches, "aa").unwrap();
+    }
+    config.antialiasing.unwrap_or_default()
+}
+
+/// The color scheme given on the command line, or of the config
+fn get_color_scheme(matches: &ArgMatches, config: &Config) -> Result<ColorScheme, CliError> {
+    match matches.value_of("color-scheme") {
+        Some(color_scheme) => parse_color_scheme(color_scheme).map_err(CliError::Parse),
+        None => Ok(config.color_scheme.unwrap_or_default()),
+    }
+}
+
+/// Parse a color scheme, `light`, `dark` or `system` for the scheme of the
+/// window system, which is light when it can't be detected
+pub(super) fn parse_color_scheme(value: &str) -> Result<ColorScheme, String> {
+    if value.trim().eq_ignore_ascii_case("system") {
+        return Ok(render::system_color_scheme().unwrap_or_default());
+    }
+    value.parse()
+}
+
+fn get_timeout(matches: &ArgMatches, config: &Config) -> Result<Option<Duration>, CliError> {
+    match matches.value_of("timeout") {
+        Some(timeout) => parse_duration(timeout).map(Some),
+        None => Ok(config.timeout),
+    }
 }
 
-fn parse_size(raw_size: &str) -> (u32, u32) {
-    let size_params = raw_size
-        .split('x')
-        .filter_map(|size| size.parse::<u32>().ok())
-        .take(2)
-        .collect::<Vec<u32>>();
+/// Parse a duration in seconds or milliseconds, e.g. `2s` or `500ms`
+pub(super) fn parse_duration(raw_duration: &str) -> Result<Duration, CliError> {
+    let (value, unit) = match raw_duration.strip_suffix("ms") {
+        Some(value) => (value, 0.001),
+        None => (raw_duration.strip_suffix('s').unwrap_or(raw_duration), 1.),
+    };
 
-    match &size_params[..] {
-        &[width, height, ..] => (width, height),
-        _ => unreachable!(),
+    // a duration too long to be represented, e.g. `inf`, is invalid too
+    match value.parse::<f64>().map(|value| value * unit) {
+        Ok(secs) if secs >= 0. && secs < u64::MAX as f64 => Ok(Duration::from_secs_f64(secs)),
+        _ => Err(CliError::Parse(format!(
+            "Invalid duration: {}",
+            raw_duration
+        ))),
     }
 }
 
@@ -56,3 +269,22 @@ fn get_arg<'a, T: FromStr>(matches: &ArgMatches, name: &'a str) -> Option<T> {
 fn get_flag<'a>(matches: &ArgMatches, flag: &'a str) -> bool {
     matches.is_present(flag)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_durations() {
+        let duration = |value| parse_duration(value).ok();
+        assert_eq!(duration("2s"), Some(Duration::from_secs(2)));
+        assert_eq!(duration("1.5"), Some(Duration::from_millis(1500)));
+        assert_eq!(duration("250ms"), Some(Duration::from_millis(250)));
+        assert_eq!(duration("0s"), Some(Duration::from_secs(0)));
+        assert_eq!(duration("-1s"), None);
+        assert_eq!(duration("2m"), None);
+        assert_eq!(duration("inf"), None);
+        assert_eq!(duration("NaN"), None);
+        assert_eq!(duration("1e30s"), None);
+    }
+}
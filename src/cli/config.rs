@@ -0,0 +1,185 @@
+//! The defaults of the flags read from a config file, `nox.toml` of the
+//! current directory or the file given with `--config`, so the settings
+//! used on every run don't need to be given as flags. The flags given
+//! on the command line win over the config, e.g.:
+//!
+//! ```toml
+//! viewport = "1280x720@2x"
+//! gpu_backend = "vulkan"
+//! aa = "msaa4"
+//! color_scheme = "dark"
+//! reduced_motion = true
+//! user_agent_css = "styles/ua.css"
+//! user_css = "styles/user.css"
+//! log = "info,layout=debug"
+//! timeout = "30s"
+//!
+//! [network]
+//! proxy = "http://localhost:8080"
+//! user_agent = "Moon/0.1"
+//! insecure = true
+//! ```
+//!
+//! The paths are relative to the directory of the config file.
+use super::action::{parse_color_scheme, parse_duration};
+use crate::error::CliError;
+use crate::logger::LogFilter;
+use render::{Antialiasing, ColorScheme, GpuBackend, ViewportConfig};
+use serde::Deserialize;
+use std::path::{Path, PathBuf};
+use std::str::FromStr;
+use std::time::Duration;
+
+/// The config file read when `--config` isn't given, if it exists
+pub const DEFAULT_CONFIG_FILE: &str = "nox.toml";
+
+#[derive(Debug, Default, PartialEq)]
+pub struct Config {
+    pub viewport: Option<ViewportConfig>,
+    pub gpu_backend: Option<GpuBackend>,
+    pub antialiasing: Option<Antialiasing>,
+    pub color_scheme: Option<ColorScheme>,
+    pub reduced_motion: Option<bool>,
+    pub forced_colors: Option<bool>,
+    /// The directory of the fonts, unused until text is painted with fonts
+    pub fonts_dir: Option<PathBuf>,
+    pub user_agent_css_path: Option<String>,
+    pub user_css_path: Option<String>,
+    pub log: Option<LogFilter>,
+    pub timeout: Option<Duration>,
+    pub proxy: Option<String>,
+    pub user_agent: Option<String>,
+    pub insecure: Option<bool>,
+}
+
+/// The config as written in the file
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ConfigFile {
+    viewport: Option<String>,
+    gpu_backend: Option<String>,
+    aa: Option<String>,
+    color_scheme: Option<String>,
+    reduced_motion: Option<bool>,
+    forced_colors: Option<bool>,
+    fonts_dir: Option<PathBuf>,
+    user_agent_css: Option<PathBuf>,
+    user_css: Option<PathBuf>,
+    log: Option<String>,
+    timeout: Option<String>,
+    #[serde(default)]
+    network: NetworkFile,
+}
+
+#[derive(Debug, Default, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct NetworkFile {
+    proxy: Option<String>,
+    user_agent: Option<String>,
+    insecure: Option<bool>,
+}
+
+impl Config {
+    /// Read a config file, or `nox.toml` of the current directory when
+    /// none is given. Without either, every flag has its default value.
+    pub fn load(path: Option<&str>) -> Result<Config, CliError> {
+        let path = match path {
+            Some(path) => PathBuf::from(path),
+            None if Path::new(DEFAULT_CONFIG_FILE).is_file() => PathBuf::from(DEFAULT_CONFIG_FILE),
+            None => return Ok(Config::default()),
+        };
+        let content = std::fs::read_to_string(&path).map_err(|e| CliError::read(&path, e))?;
+        let base = path.parent().unwrap_or_else(|| Path::new(""));
+        Config::parse(&content, base)
+            .map_err(|e| CliError::Parse(format!("Invalid config {}: {}", path.display(), e)))
+    }
+
+    /// Parse the content of a config file, with paths relative to a
+    /// directory
+    fn parse(content: &str, base: &Path) -> Result<Config, String> {
+        let file: ConfigFile = toml::from_str(content).map_err(|e| e.to_string())?;
+        let path = |path: Option<PathBuf>| path.map(|path| base.join(path));
+        let css_path =
+            |css: Option<PathBuf>| path(css).map(|path| path.to_string_lossy().into_owned());
+
+        Ok(Config {
+            viewport: parse_value("viewport", file.viewport)?,
+            gpu_backend: parse_value("gpu_backend", file.gpu_backend)?,
+            antialiasing: parse_value("aa", file.aa)?,
+            color_scheme: file
+                .color_scheme
+                .map(|value| {
+                    parse_color_scheme(&value).map_err(|e| format!("{} (color_scheme)", e))
+                })
+                .transpose()?,
+            reduced_motion: file.reduced_motion,
+            forced_colors: file.forced_colors,
+            fonts_dir: path(file.fonts_dir),
+            user_agent_css_path: css_path(file.user_agent_css),
+            user_css_path: css_path(file.user_css),
+            log: parse_value("log", file.log)?,
+            timeout: file
+                .timeout
+                .map(|timeout| parse_duration(&timeout).map_err(|e| e.to_string()))
+                .transpose()?,
+            proxy: file.network.proxy,
+            user_agent: file.network.user_agent,
+            insecure: file.network.insecure,
+        })
+    }
+}
+
+fn parse_value<T: FromStr<Err = String>>(
+    key: &str,
+    value: Option<String>,
+) -> Result<Option<T>, String> {
+    value
+        .map(|value| value.parse().map_err(|e| format!("{} ({})", e, key)))
+        .transpose()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_configs() {
+        let config = Config::parse(
+            r#"
+            viewport = "mobile"
+            gpu_backend = "gl"
+            color_scheme = "dark"
+            reduced_motion = true
+            user_css = "styles/user.css"
+            log = "warn,layout=debug"
+            timeout = "500ms"
+
+            [network]
+            proxy = "http://localhost:8080"
+            insecure = true
+            "#,
+            Path::new("config"),
+        )
+        .unwrap();
+        assert_eq!(config.viewport, Some("375x667@2x".parse().unwrap()));
+        assert_eq!(config.gpu_backend, Some(GpuBackend::Gl));
+        assert_eq!(config.antialiasing, None);
+        assert_eq!(config.color_scheme, Some(ColorScheme::Dark));
+        assert_eq!(config.reduced_motion, Some(true));
+        assert_eq!(config.forced_colors, None);
+        assert_eq!(
+            config.user_css_path.map(PathBuf::from),
+            Some(Path::new("config").join("styles").join("user.css"))
+        );
+        assert_eq!(config.log, Some("warn,layout=debug".parse().unwrap()));
+        assert_eq!(config.timeout, Some(Duration::from_millis(500)));
+        assert_eq!(config.proxy.as_deref(), Some("http://localhost:8080"));
+        assert_eq!(config.insecure, Some(true));
+
+        assert_eq!(Config::parse("", Path::new("")), Ok(Config::default()));
+        assert!(Config::parse("viewport = \"0x10\"", Path::new("")).is_err());
+        assert!(Config::parse("aa = \"msaa2\"", Path::new("")).is_err());
+        assert!(Config::parse("color_scheme = \"blue\"", Path::new("")).is_err());
+        assert!(Config::parse("size = \"10x10\"", Path::new("")).is_err());
+    }
+}
@@ -1,11 +1,19 @@
 mod action;
+mod config;
 
+use crate::logger::LogFilter;
 pub use action::*;
 use clap::{App, Arg, ArgMatches};
+pub use config::Config;
 
 const AUTHOR: &'static str = "Viet-Hung Nguyen <viethungax@gmail.com>";
 
 pub fn accept_cli<'a>() -> ArgMatches<'a> {
+    app().get_matches()
+}
+
+/// The command line of the renderer & its subcommands
+fn app<'a, 'b>() -> App<'a, 'b> {
     let html_file_arg = Arg::with_name("html")
         .long("html")
         .required(false)
@@ -13,15 +21,158 @@ pub fn accept_cli<'a>() -> ArgMatches<'a> {
 
     let size_arg = Arg::with_name("size")
         .long("size")
-        .required(true)
-        .takes_value(true);
+        .takes_value(true)
+        .value_name("viewport")
+        .help("The viewport in CSS pixels, e.g. 1280x720, with a device pixel ratio scaling the output image, e.g. 375x667@2x, \
+               or a preset: mobile (375x667@2x), tablet (768x1024@2x), desktop (1280x800) or 1080p (1920x1080). Required unless the config has a viewport");
 
     let once_flag = Arg::with_name("once").long("once");
 
+    let zoom_arg = Arg::with_name("zoom")
+        .long("zoom")
+        .takes_value(true)
+        .value_name("factor")
+        .validator(|zoom| zoom.parse::<render::Zoom>().map(|_| ()))
+        .help("Render the page zoomed in or out, e.g. 1.5 or 150%. The page is laid out in a viewport smaller by the factor & scaled up to the size of the viewport");
+
+    let text_zoom_flag = Arg::with_name("text-zoom")
+        .long("text-zoom")
+        .requires("zoom")
+        .help("Only scale the font sizes with --zoom, laying out the page in the viewport");
+
+    let color_scheme_arg = Arg::with_name("color-scheme")
+        .long("color-scheme")
+        .takes_value(true)
+        .possible_values(&["light", "dark", "system"])
+        .help("The color scheme matched by the prefers-color-scheme media feature, light by default. system uses the scheme of the window system");
+
+    let reduced_motion_flag = Arg::with_name("reduced-motion")
+        .long("reduced-motion")
+        .help("Match the prefers-reduced-motion: reduce media feature. The transitions are disabled & the CSS animations skip to their end");
+
+    let forced_colors_flag = Arg::with_name("forced-colors")
+        .long("forced-colors")
+        .help("Match the forced-colors: active media feature");
+
     let ouput_arg = Arg::with_name("output")
         .long("output")
         .required(true)
-        .takes_value(true);
+        .takes_value(true)
+        .help("The image file to write. A file ending with .pdf or .svg is written as a PDF or SVG document of vector shapes");
+
+    let log_parse_errors_flag = Arg::with_name("log-parse-errors")
+        .long("log-parse-errors")
+        .help("Print the errors encountered while parsing HTML & CSS");
+
+    let aa_arg = Arg::with_name("aa")
+        .long("aa")
+        .takes_value(true)
+        .possible_values(&["off", "msaa4", "msaa8"])
+        .default_value("off")
+        .help("Anti-aliasing method used to paint shapes");
+
+    let gpu_backend_arg = Arg::with_name("gpu-backend")
+        .long("gpu-backend")
+        .takes_value(true)
+        .possible_values(&["vulkan", "metal", "dx12", "gl"])
+        .help("The graphics API to paint on. The best GPU of the machine is used by default, or the CPU when there is none");
+
+    let output_colorspace_arg = Arg::with_name("output-colorspace")
+        .long("output-colorspace")
+        .takes_value(true)
+        .possible_values(&["srgb", "linear"])
+        .default_value("srgb")
+        .help("The color space of the pixels of the output image. The colors are blended in linear space either way");
+
+    let user_css_arg = Arg::with_name("user-css")
+        .long("user-css")
+        .takes_value(true)
+        .value_name("file")
+        .help("Apply a user stylesheet. Use !important to override the styles of the page");
+
+    let user_agent_css_arg = Arg::with_name("user-agent-css")
+        .long("user-agent-css")
+        .takes_value(true)
+        .value_name("file")
+        .help("Replace the default styles of the elements with a stylesheet");
+
+    let config_arg = Arg::with_name("config")
+        .long("config")
+        .takes_value(true)
+        .value_name("file")
+        .help("A TOML file of the defaults of the flags, e.g. viewport = \"1280x720\" or the proxy of [network]. \
+               The flags given on the command line win. The nox.toml of the current directory is read when it isn't given");
+
+    let profile_dir_arg = Arg::with_name("profile-dir")
+        .long("profile-dir")
+        .takes_value(true)
+        .value_name("dir")
+        .help("Keep the cookies of the pages in a directory between runs");
+
+    let timeout_arg = Arg::with_name("timeout")
+        .long("timeout")
+        .takes_value(true)
+        .value_name("duration")
+        .help("Give up on the render after a duration (e.g. 30s or 500ms), including the requests to servers, exiting with an error instead of writing the output");
+
+    let proxy_arg = Arg::with_name("proxy")
+        .long("proxy")
+        .takes_value(true)
+        .value_name("url")
+        .help("Send the requests through a proxy, e.g. http://localhost:8080");
+
+    let insecure_flag = Arg::with_name("insecure")
+        .long("insecure")
+        .help("Accept invalid TLS certificates, e.g. self-signed certificates");
+
+    let user_agent_arg = Arg::with_name("user-agent")
+        .long("user-agent")
+        .takes_value(true)
+        .value_name("string")
+        .help("The User-Agent header sent to the servers");
+
+    let full_page_flag = Arg::with_name("full-page")
+        .long("full-page")
+        .help("Capture the whole page instead of the viewport. The page is laid out at the viewport width");
+
+    let animate_arg = Arg::with_name("animate")
+        .long("animate")
+        .number_of_values(2)
+        .value_names(&["duration", "fps"])
+        .conflicts_with("full-page")
+        .help("Render the animations of the page for a duration (e.g. 2s or 500ms) at a frame rate into an animated GIF");
+
+    let paginate_flag = Arg::with_name("paginate")
+        .long("paginate")
+        .help("Render the pages the document would be printed on, sized by its @page rules. A PDF output gets a page per page, an image output is written as an image per page numbered from 1");
+
+    let deterministic_flag = Arg::with_name("deterministic")
+        .long("deterministic")
+        .conflicts_with("animate")
+        .help("Paint on the CPU without animations, so a page always gives the same bitmap on any machine");
+
+    let profile_arg = Arg::with_name("profile")
+        .long("profile")
+        .takes_value(true)
+        .value_name("file")
+        .help("Record the time spent in each stage of rendering & the items painted into a Chrome trace file (chrome://tracing)");
+
+    let stats_flag = Arg::with_name("stats")
+        .long("stats")
+        .help("Print the nodes & the approximate memory of the DOM, the styles, the layout & the GPU buffers once rendered");
+
+    let log_arg = Arg::with_name("log")
+        .long("log")
+        .takes_value(true)
+        .value_name("level[,component=level]")
+        .validator(|spec| spec.parse::<LogFilter>().map(|_| ()))
+        .help("The level of the messages logged, e.g. debug or info,html::tokenizer=trace,paint=off. \
+               The components are html::tokenizer, css::parser, style, layout, paint & render::pipeline for the time of each stage");
+
+    let json_errors_flag = Arg::with_name("json-errors")
+        .long("json-errors")
+        .help("Write the error ending the run to stderr as a JSON object with its error code, message & exit code. \
+               The exit codes are 2 for invalid arguments or manifests, 3 for missing files, 4 when --gpu-backend has no adapter, 5 when the output can't be written & 6 for the timeout");
 
     let render_once_subcommand = App::new("render")
         .about("Start a rendering process of Moon and render once")
@@ -29,13 +180,129 @@ pub fn accept_cli<'a>() -> ArgMatches<'a> {
         .author(AUTHOR)
         .arg(html_file_arg.clone().required(true))
         .arg(size_arg.clone())
+        .arg(zoom_arg.clone())
+        .arg(text_zoom_flag.clone())
+        .arg(color_scheme_arg.clone())
+        .arg(reduced_motion_flag.clone())
+        .arg(forced_colors_flag.clone())
         .arg(once_flag.clone())
-        .arg(ouput_arg.clone());
+        .arg(ouput_arg.clone())
+        .arg(log_parse_errors_flag.clone())
+        .arg(aa_arg.clone())
+        .arg(gpu_backend_arg.clone())
+        .arg(output_colorspace_arg.clone())
+        .arg(user_agent_css_arg.clone())
+        .arg(user_css_arg.clone())
+        .arg(full_page_flag.clone())
+        .arg(paginate_flag.clone())
+        .arg(animate_arg.clone())
+        .arg(deterministic_flag.clone())
+        .arg(profile_arg.clone())
+        .arg(stats_flag.clone())
+        .arg(log_arg.clone())
+        .arg(json_errors_flag.clone())
+        .arg(config_arg.clone())
+        .arg(profile_dir_arg.clone())
+        .arg(timeout_arg.clone())
+        .arg(proxy_arg.clone())
+        .arg(insecure_flag.clone())
+        .arg(user_agent_arg.clone());
+
+    let port_arg = Arg::with_name("port")
+        .long("port")
+        .takes_value(true)
+        .default_value("8080")
+        .help("The port of localhost to listen on");
+
+    let root_arg = Arg::with_name("root")
+        .long("root")
+        .takes_value(true)
+        .value_name("dir")
+        .default_value(".")
+        .help("The directory of the HTML files that can be rendered");
+
+    let serve_subcommand = App::new("serve")
+        .about("Start an HTTP server rendering the HTML files of a directory into PNG images on request, e.g. GET /render?file=index.html&width=800&height=600&full_page=1")
+        .version(render::version())
+        .author(AUTHOR)
+        .arg(port_arg)
+        .arg(root_arg)
+        .arg(aa_arg.clone())
+        .arg(gpu_backend_arg.clone())
+        .arg(deterministic_flag.clone())
+        .arg(log_arg.clone())
+        .arg(json_errors_flag.clone())
+        .arg(config_arg.clone())
+        .arg(timeout_arg.clone());
+
+    let manifest_arg = Arg::with_name("manifest")
+        .long("manifest")
+        .required(true)
+        .takes_value(true)
+        .value_name("file")
+        .help("A JSON or TOML file of the jobs to render, each with an input HTML file, an output image file & optionally a viewport (e.g. 1280x720) or full_page = true. \
+               A viewport at the top of the file is used by the jobs without one. The paths are relative to the manifest");
+
+    let render_batch_subcommand = App::new("render-batch")
+        .about("Render the jobs of a manifest one after the other with a single renderer, which is faster than a render per job")
+        .version(render::version())
+        .author(AUTHOR)
+        .arg(manifest_arg)
+        .arg(color_scheme_arg.clone())
+        .arg(reduced_motion_flag.clone())
+        .arg(forced_colors_flag.clone())
+        .arg(log_parse_errors_flag.clone())
+        .arg(aa_arg.clone())
+        .arg(gpu_backend_arg.clone())
+        .arg(output_colorspace_arg.clone())
+        .arg(user_agent_css_arg.clone())
+        .arg(user_css_arg.clone())
+        .arg(deterministic_flag.clone())
+        .arg(log_arg.clone())
+        .arg(json_errors_flag.clone())
+        .arg(config_arg.clone())
+        .arg(timeout_arg.clone().help("Give up on the render of a job after a duration (e.g. 30s or 500ms), including the requests to servers. The other jobs are rendered regardless"));
 
     App::new("Moon Renderer")
         .version("1.0")
         .author(AUTHOR)
         .about("Moon web browser!")
         .subcommand(render_once_subcommand)
-        .get_matches()
+        .subcommand(serve_subcommand)
+        .subcommand(render_batch_subcommand)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn render_args(extra: &[&str]) -> Vec<String> {
+        let args = [
+            "moon", "render", "--once", "--html", "a.html", "--output", "a.png", "--size",
+            "800x600",
+        ];
+        args.iter()
+            .chain(extra)
+            .map(|arg| arg.to_string())
+            .collect()
+    }
+
+    fn antialiasing(extra: &[&str]) -> Option<render::Antialiasing> {
+        let matches = app().get_matches_from_safe(render_args(extra)).ok()?;
+        match get_action(matches, &Config::default()) {
+            Ok(Action::RenderOnce(params)) => Some(params.antialiasing),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn parse_antialiasing() {
+        use render::Antialiasing;
+        assert_eq!(antialiasing(&[]), Some(Antialiasing::Off));
+        assert_eq!(antialiasing(&["--aa", "msaa4"]), Some(Antialiasing::Msaa4));
+        assert_eq!(antialiasing(&["--aa", "msaa8"]), Some(Antialiasing::Msaa8));
+        // only the sample counts of the GPU painter are accepted
+        assert_eq!(antialiasing(&["--aa", "msaa2"]), None);
+        assert_eq!(antialiasing(&["--aa", "msaa16"]), None);
+    }
 }
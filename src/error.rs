@@ -0,0 +1,151 @@
+//! The errors ending a run of the CLI. Each kind of error exits with its
+//! own code, and `--json-errors` writes it to stderr as a JSON object,
+//! e.g. `{"error":"file-not-found","exit_code":3,"message":"..."}`, so
+//! the scripts running the renderer can tell the failures apart.
+use std::fmt;
+use std::io;
+use std::path::PathBuf;
+
+#[derive(Debug)]
+pub enum CliError {
+    /// A file to read doesn't exist
+    FileNotFound(PathBuf),
+    /// A file to read exists but can't be read
+    Read { path: PathBuf, message: String },
+    /// An argument or a manifest is invalid
+    Parse(String),
+    /// The graphics API given with `--gpu-backend` has no adapter
+    GpuUnavailable(String),
+    /// The output can't be encoded or written
+    Encode { path: PathBuf, message: String },
+    /// The render was cancelled by `--timeout` before it finished
+    Timeout,
+    /// Some of the jobs of a batch failed, the others were rendered
+    BatchFailed(usize),
+    /// The server can't listen or accept connections
+    Serve(String),
+}
+
+impl CliError {
+    /// The error of a file that can't be read
+    pub fn read(path: impl Into<PathBuf>, error: io::Error) -> Self {
+        let path = path.into();
+        match error.kind() {
+            io::ErrorKind::NotFound => CliError::FileNotFound(path),
+            _ => CliError::Read {
+                path,
+                message: error.to_string(),
+            },
+        }
+    }
+
+    /// The error of an output that can't be encoded or written
+    pub fn encode(path: impl Into<PathBuf>, error: impl fmt::Display) -> Self {
+        CliError::Encode {
+            path: path.into(),
+            message: error.to_string(),
+        }
+    }
+
+    /// Short error code, reported in the `error` field of the JSON errors
+    pub fn code(&self) -> &'static str {
+        match self {
+            CliError::FileNotFound(_) => "file-not-found",
+            CliError::Read { .. } => "read-failure",
+            CliError::Parse(_) => "parse-failure",
+            CliError::GpuUnavailable(_) => "gpu-unavailable",
+            CliError::Encode { .. } => "encode-failure",
+            CliError::Timeout => "timeout",
+            CliError::BatchFailed(_) => "batch-failure",
+            CliError::Serve(_) => "serve-failure",
+        }
+    }
+
+    /// The status the process exits with
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            CliError::Read { .. } | CliError::BatchFailed(_) | CliError::Serve(_) => 1,
+            CliError::Parse(_) => 2,
+            CliError::FileNotFound(_) => 3,
+            CliError::GpuUnavailable(_) => 4,
+            CliError::Encode { .. } => 5,
+            CliError::Timeout => 6,
+        }
+    }
+
+    pub fn to_json(&self) -> String {
+        serde_json::json!({
+            "error": self.code(),
+            "message": self.to_string(),
+            "exit_code": self.exit_code(),
+        })
+        .to_string()
+    }
+
+    /// Report the error on stderr, as JSON or through the logger, and
+    /// exit with its code
+    pub fn exit(&self, json: bool) -> ! {
+        if json {
+            eprintln!("{}", self.to_json());
+        } else {
+            log::error!("{}", self);
+        }
+        std::process::exit(self.exit_code())
+    }
+}
+
+impl fmt::Display for CliError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            CliError::FileNotFound(path) => write!(f, "No such file: {}", path.display()),
+            CliError::Read { path, message } => {
+                write!(f, "Unable to read {}: {}", path.display(), message)
+            }
+            CliError::Parse(message) => write!(f, "{}", message),
+            CliError::GpuUnavailable(message) => {
+                write!(f, "Unable to paint on the GPU: {}", message)
+            }
+            CliError::Encode { path, message } => {
+                write!(f, "Unable to write {}: {}", path.display(), message)
+            }
+            CliError::Timeout => write!(
+                f,
+                "The render was cancelled before it finished: the timeout elapsed"
+            ),
+            CliError::BatchFailed(failures) => write!(f, "{} jobs of the batch failed", failures),
+            CliError::Serve(message) => write!(f, "Unable to serve: {}", message),
+        }
+    }
+}
+
+impl std::error::Error for CliError {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn report_errors_as_json() {
+        let missing = io::Error::new(io::ErrorKind::NotFound, "gone");
+        let error = CliError::read("page.html", missing);
+        assert_eq!(error.code(), "file-not-found");
+        assert_eq!(
+            error.to_json(),
+            r#"{"error":"file-not-found","exit_code":3,"message":"No such file: page.html"}"#
+        );
+
+        let denied = io::Error::new(io::ErrorKind::PermissionDenied, "denied");
+        assert_eq!(CliError::read("page.html", denied).code(), "read-failure");
+
+        let mut exit_codes = vec![
+            CliError::Parse(String::new()).exit_code(),
+            CliError::FileNotFound(PathBuf::new()).exit_code(),
+            CliError::GpuUnavailable(String::new()).exit_code(),
+            CliError::encode("out.png", "full").exit_code(),
+            CliError::Timeout.exit_code(),
+        ];
+        exit_codes.sort_unstable();
+        exit_codes.dedup();
+        assert_eq!(exit_codes.len(), 5);
+    }
+}
@@ -0,0 +1,165 @@
+//! The diagnostics of the renderer, written to stderr. The components
+//! log through the `log` facade with their module path as target, e.g.
+//! `html::tokenizer` or `css::parser`, so each component has its own
+//! level, set with `--log level[,component=level]`.
+use log::{Level, LevelFilter, Log, Metadata, Record};
+use std::str::FromStr;
+use std::time::Instant;
+
+/// The components logging under several targets
+const ALIASES: &[(&str, &[&str])] = &[("paint", &["painting", "gfx", "pdf", "svg_export"])];
+
+/// The crates of the GPU stack, which log a lot below warnings
+const GPU_CRATES: &[&str] = &[
+    "wgpu",
+    "wgpu_core",
+    "wgpu_hal",
+    "gfx_backend_vulkan",
+    "naga",
+];
+
+/// The level of the messages logged for each target
+#[derive(Debug, Clone, PartialEq)]
+pub struct LogFilter {
+    default: LevelFilter,
+    /// The levels of the targets & the targets below them
+    targets: Vec<(String, LevelFilter)>,
+}
+
+impl LogFilter {
+    /// The level of a target, set by the longest component containing it
+    pub fn level(&self, target: &str) -> LevelFilter {
+        self.targets
+            .iter()
+            .filter(|(component, _)| {
+                target == component
+                    || (target.starts_with(component.as_str())
+                        && target[component.len()..].starts_with("::"))
+            })
+            .max_by_key(|(component, _)| component.len())
+            .map_or(self.default, |(_, level)| *level)
+    }
+
+    /// The most verbose level of all the targets
+    pub fn max_level(&self) -> LevelFilter {
+        self.targets
+            .iter()
+            .map(|(_, level)| *level)
+            .fold(self.default, std::cmp::max)
+    }
+}
+
+impl Default for LogFilter {
+    fn default() -> Self {
+        Self {
+            default: LevelFilter::Info,
+            targets: GPU_CRATES
+                .iter()
+                .map(|target| (target.to_string(), LevelFilter::Warn))
+                .collect(),
+        }
+    }
+}
+
+impl FromStr for LogFilter {
+    type Err = String;
+
+    /// Parse a filter like `info,html::tokenizer=trace,paint=off`
+    fn from_str(spec: &str) -> Result<Self, Self::Err> {
+        let mut filter = LogFilter::default();
+        let parse_level = |level: &str| {
+            LevelFilter::from_str(level.trim()).map_err(|_| format!("Unknown log level: {}", level))
+        };
+
+        for directive in spec.split(',').filter(|d| !d.trim().is_empty()) {
+            let (component, level) = match directive.split_once('=') {
+                Some((component, level)) => (component.trim(), parse_level(level)?),
+                None => {
+                    filter.default = parse_level(directive)?;
+                    continue;
+                }
+            };
+
+            let targets = ALIASES
+                .iter()
+                .find(|(alias, _)| *alias == component)
+                .map_or_else(|| vec![component], |(_, targets)| targets.to_vec());
+            for target in targets {
+                filter.targets.retain(|(existing, _)| existing != target);
+                filter.targets.push((target.to_string(), level));
+            }
+        }
+        Ok(filter)
+    }
+}
+
+struct Logger {
+    filter: LogFilter,
+    start: Instant,
+}
+
+impl Log for Logger {
+    fn enabled(&self, metadata: &Metadata) -> bool {
+        metadata.level() <= self.filter.level(metadata.target())
+    }
+
+    fn log(&self, record: &Record) {
+        if !self.enabled(record.metadata()) {
+            return;
+        }
+        let level = match record.level() {
+            Level::Error => "\x1b[31mERROR\x1b[0m",
+            Level::Warn => "\x1b[33mWARN \x1b[0m",
+            Level::Info => "\x1b[34mINFO \x1b[0m",
+            Level::Debug => "\x1b[36mDEBUG\x1b[0m",
+            Level::Trace => "\x1b[35mTRACE\x1b[0m",
+        };
+        eprintln!(
+            "{:>9.3}s {} {}: {}",
+            self.start.elapsed().as_secs_f64(),
+            level,
+            record.target(),
+            record.args()
+        );
+    }
+
+    fn flush(&self) {}
+}
+
+/// Log the messages passing a filter for the rest of the process
+pub fn init(filter: LogFilter) {
+    log::set_max_level(filter.max_level());
+    let logger = Box::new(Logger {
+        filter,
+        start: Instant::now(),
+    });
+    log::set_logger(Box::leak(logger)).expect("The logger is initialized once");
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_filters() {
+        let filter: LogFilter = "debug,html::tokenizer=trace,paint=off,wgpu=info"
+            .parse()
+            .unwrap();
+        assert_eq!(filter.level("render::frame"), LevelFilter::Debug);
+        assert_eq!(filter.level("html::tokenizer"), LevelFilter::Trace);
+        assert_eq!(filter.level("html::tokenizer::state"), LevelFilter::Trace);
+        assert_eq!(filter.level("html::tree_builder"), LevelFilter::Debug);
+        assert_eq!(filter.level("html::tokenizers"), LevelFilter::Debug);
+        assert_eq!(filter.level("gfx::painters::rect"), LevelFilter::Off);
+        assert_eq!(filter.level("svg_export"), LevelFilter::Off);
+        assert_eq!(filter.level("wgpu"), LevelFilter::Info);
+        assert_eq!(filter.level("naga::front"), LevelFilter::Warn);
+        assert_eq!(filter.max_level(), LevelFilter::Trace);
+
+        assert_eq!(
+            "".parse::<LogFilter>().unwrap().level("layout"),
+            LevelFilter::Info
+        );
+        assert!("layout=loud".parse::<LogFilter>().is_err());
+    }
+}
@@ -1,49 +1,240 @@
+mod batch;
 mod cli;
+mod error;
+mod logger;
+mod serve;
 
-use image::{ImageBuffer, Rgba};
-use simplelog::*;
-use std::io::Read;
+use error::CliError;
+use image::codecs::gif::{GifEncoder, Repeat};
+use image::{Delay, Frame, ImageBuffer, Rgba};
+use std::path::{Path, PathBuf};
 
-fn read_file(path: String) -> String {
-    let mut file = std::fs::File::open(path).expect("Unable to open file");
-    let mut result = String::new();
+fn read_file(path: String) -> Result<String, CliError> {
+    std::fs::read_to_string(&path).map_err(|e| CliError::read(path, e))
+}
+
+/// Encode a bitmap into an image in the format of the extension of a path
+fn save_bitmap(bitmap: Vec<u8>, size: (u32, u32), path: &Path) -> Result<(), CliError> {
+    let (width, height) = size;
+    let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, bitmap)
+        .ok_or_else(|| CliError::encode(path, "the bitmap doesn't match its size"))?;
+    buffer.save(path).map_err(|e| CliError::encode(path, e))
+}
+
+/// Encode the frames of an animation into an endlessly looping GIF
+fn save_animation(output: &render::RenderAnimationOutput, path: &str) -> Result<(), CliError> {
+    let (width, height) = output.size;
+    let delay = Delay::from_saturating_duration(output.frame_duration);
+
+    let frames = output.frames.iter().filter_map(|bitmap| {
+        let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, bitmap.clone())?;
+        Some(Frame::from_parts(buffer, 0, 0, delay))
+    });
+
+    let file = std::fs::File::create(path).map_err(|e| CliError::encode(path, e))?;
+    let mut encoder = GifEncoder::new(file);
+    encoder
+        .set_repeat(Repeat::Infinite)
+        .and_then(|_| encoder.encode_frames(frames))
+        .map_err(|e| CliError::encode(path, e))
+}
+
+/// The path of the image of a page, numbered before the extension of the
+/// output path, e.g. `out-2.png` for the second page of `out.png`
+fn page_path(path: &str, number: usize) -> PathBuf {
+    let path = Path::new(path);
+    let stem = path.file_stem().unwrap_or_default().to_string_lossy();
+    let file_name = match path.extension() {
+        Some(extension) => format!("{}-{}.{}", stem, number, extension.to_string_lossy()),
+        None => format!("{}-{}", stem, number),
+    };
+    path.with_file_name(file_name)
+}
+
+/// Log the parse errors & the memory usage of a render, when asked for
+fn report(diagnostics: &render::RenderDiagnostics, log_parse_errors: bool, print_stats: bool) {
+    if log_parse_errors {
+        for error in &diagnostics.parse_errors {
+            log::warn!("{}", error);
+        }
+    }
+
+    if print_stats {
+        log::info!("Memory usage:\n{}", diagnostics.heap_stats);
+    }
+}
 
-    file.read_to_string(&mut result)
-        .expect("Unable to read file!");
+/// Write the profile recorded while rendering into a Chrome trace file
+fn save_profile(path: &Option<String>) -> Result<(), CliError> {
+    if let (Some(path), Some(profile)) = (path, render::finish_profiling()) {
+        std::fs::write(path, profile.to_json()).map_err(|e| CliError::encode(path, e))?;
+    }
+    Ok(())
+}
 
-    return result;
+/// The output of a finished render. A render cancelled by the timeout
+/// exits with an error, without writing a partial output.
+fn finish_render<T>(output: Result<T, render::Cancelled<T>>) -> Result<T, CliError> {
+    output.map_err(|_| CliError::Timeout)
+}
+
+/// Fail when the GPU of a graphics API is asked for but the machine has
+/// none, instead of painting on the CPU like without `--gpu-backend`
+fn check_gpu(gpu_backend: Option<render::GpuBackend>, deterministic: bool) -> Result<(), CliError> {
+    match gpu_backend {
+        Some(backend) if !deterministic => render::gpu_adapter(Some(backend))
+            .map(|_| ())
+            .map_err(CliError::GpuUnavailable),
+        _ => Ok(()),
+    }
 }
 
 #[tokio::main]
 async fn main() {
-    let config = ConfigBuilder::new()
-        .add_filter_ignore_str("wgpu")
-        .add_filter_ignore_str("gfx_backend_vulkan")
-        .add_filter_ignore_str("naga")
-        .set_target_level(LevelFilter::Info)
-        .build();
-    TermLogger::init(
-        LevelFilter::Debug,
-        config,
-        TerminalMode::Mixed,
-        ColorChoice::Auto,
-    )
-    .unwrap();
-
-    let action = cli::get_action(cli::accept_cli());
+    let matches = cli::accept_cli();
+    let json_errors = cli::get_json_errors(&matches);
+    let config = cli::get_config(&matches);
+    let log_filter = match &config {
+        Ok(config) => cli::get_log_filter(&matches, config),
+        Err(_) => cli::get_log_filter(&matches, &cli::Config::default()),
+    };
+    logger::init(log_filter);
 
+    let result = match config.and_then(|config| cli::get_action(matches, &config)) {
+        Ok(action) => run(action).await,
+        Err(e) => Err(e),
+    };
+    if let Err(e) = result {
+        e.exit(json_errors);
+    }
+}
+
+async fn run(action: cli::Action) -> Result<(), CliError> {
     match action {
         cli::Action::RenderOnce(params) => {
-            let html_code = read_file(params.html_path);
-            let viewport = params.viewport_size;
+            let html_code = read_file(params.html_path)?;
+            let viewport = params.viewport;
             let output_path = params.output_path;
+            let options = render::RenderOptions {
+                viewport,
+                antialiasing: params.antialiasing,
+                gpu_backend: params.gpu_backend,
+                output_color_space: params.output_color_space,
+                deterministic: params.deterministic,
+                user_agent_css: params.user_agent_css_path.map(read_file).transpose()?,
+                user_css: params.user_css_path.map(read_file).transpose()?,
+                http_config: params.http_config,
+                profile_dir: params.profile_dir.map(PathBuf::from),
+                handle: match params.timeout {
+                    Some(timeout) => render::RenderHandle::with_timeout(timeout),
+                    None => render::RenderHandle::new(),
+                },
+                max_full_page_height: render::MAX_VIEWPORT_DIMENSION,
+            };
+
+            if params.profile_path.is_some() {
+                render::start_profiling();
+            }
+
+            let is_vector = [".pdf", ".svg"]
+                .iter()
+                .any(|extension| output_path.to_lowercase().ends_with(extension));
+            #[allow(clippy::float_cmp)]
+            let is_scaled = viewport.device_pixel_ratio != 1.;
+            if is_scaled && (is_vector || params.paginate) {
+                log::warn!(
+                    "The documents & the pages are in CSS pixels, ignoring the device pixel ratio"
+                );
+            }
+
+            if output_path.to_lowercase().ends_with(".pdf") {
+                let output =
+                    render::render_pdf(html_code, options, params.full_page, params.paginate).await;
+                save_profile(&params.profile_path)?;
+                let output = finish_render(output)?;
+
+                report(
+                    &output.diagnostics,
+                    params.log_parse_errors,
+                    params.print_stats,
+                );
+
+                return std::fs::write(&output_path, output.pdf)
+                    .map_err(|e| CliError::encode(&output_path, e));
+            }
+
+            if output_path.to_lowercase().ends_with(".svg") {
+                if params.paginate {
+                    log::warn!("An SVG document has a single page, ignoring --paginate");
+                }
+                let output = render::render_svg(html_code, options, params.full_page).await;
+                save_profile(&params.profile_path)?;
+                let output = finish_render(output)?;
+
+                report(
+                    &output.diagnostics,
+                    params.log_parse_errors,
+                    params.print_stats,
+                );
 
-            let bitmap = render::render_once(html_code, viewport).await;
+                return std::fs::write(&output_path, output.svg)
+                    .map_err(|e| CliError::encode(&output_path, e));
+            }
 
-            let (width, height) = viewport;
+            check_gpu(params.gpu_backend, params.deterministic)?;
 
-            let buffer = ImageBuffer::<Rgba<u8>, _>::from_raw(width, height, bitmap).unwrap();
-            buffer.save(output_path).unwrap();
+            if let Some(animation) = params.animation {
+                let output =
+                    render::render_animation(html_code, options, animation.duration, animation.fps)
+                        .await;
+                save_profile(&params.profile_path)?;
+                let output = finish_render(output)?;
+
+                report(
+                    &output.diagnostics,
+                    params.log_parse_errors,
+                    params.print_stats,
+                );
+
+                return save_animation(&output, &output_path);
+            }
+
+            if params.paginate {
+                let output = render::render_pages(html_code, options).await;
+                save_profile(&params.profile_path)?;
+                let output = finish_render(output)?;
+
+                report(
+                    &output.diagnostics,
+                    params.log_parse_errors,
+                    params.print_stats,
+                );
+
+                for (index, page) in output.pages.into_iter().enumerate() {
+                    save_bitmap(page, output.size, &page_path(&output_path, index + 1))?;
+                }
+                return Ok(());
+            }
+
+            let output = render::render_once(html_code, options, params.full_page).await;
+            save_profile(&params.profile_path)?;
+            let output = finish_render(output)?;
+
+            report(
+                &output.diagnostics,
+                params.log_parse_errors,
+                params.print_stats,
+            );
+
+            save_bitmap(output.bitmap, output.size, Path::new(&output_path))
+        }
+        cli::Action::Serve(params) => {
+            check_gpu(params.gpu_backend, params.deterministic)?;
+            serve::run(params).map_err(|e| CliError::Serve(e.to_string()))
+        }
+        cli::Action::RenderBatch(params) => {
+            check_gpu(params.gpu_backend, params.deterministic)?;
+            batch::run(params).await
         }
     }
 }
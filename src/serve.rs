@@ -0,0 +1,419 @@
+//! A local HTTP server rendering the HTML files of a directory into PNG
+//! images on request, e.g. `GET /render?file=page.html&width=800`, to
+//! use the renderer as a screenshot service. The renders are queued and
+//! run one at a time on a single worker, so the concurrent requests
+//! don't compete for the GPU. The connections, the size of the requests
+//! & the height of the full page renders are limited, so the clients
+//! can't take all the memory of the server.
+use image::codecs::png::PngEncoder;
+use image::ColorType;
+use render::{Antialiasing, GpuBackend, RenderHandle, RenderOptions, MAX_VIEWPORT_DIMENSION};
+use std::collections::HashMap;
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::path::{Path, PathBuf};
+use std::time::Duration;
+
+/// The renders waiting for the worker before the requests are refused
+const MAX_QUEUED_JOBS: usize = 16;
+
+/// The connections handled at once, the others wait to be accepted
+const MAX_CONNECTIONS: usize = 64;
+
+/// The largest request line & headers of a request, in bytes
+const MAX_REQUEST_SIZE: u64 = 8 * 1024;
+
+/// The height the full page renders are cut at, in pixels
+const MAX_FULL_PAGE_HEIGHT: u32 = 8192;
+
+/// The viewport of the requests without a width or a height
+const DEFAULT_SIZE: (u32, u32) = (1024, 768);
+
+/// The time a client has to send its request, or to read the response,
+/// before its connection is dropped
+const CONNECTION_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub struct ServeParams {
+    pub port: u16,
+    /// The directory of the HTML files that can be rendered
+    pub root: PathBuf,
+    pub antialiasing: Antialiasing,
+    pub gpu_backend: Option<GpuBackend>,
+    pub deterministic: bool,
+    /// The time after which a render is given up on
+    pub timeout: Option<Duration>,
+}
+
+struct RenderJob {
+    html: String,
+    size: (u32, u32),
+    full_page: bool,
+    /// Where the PNG image or the error of the render is sent
+    response: flume::Sender<Result<Vec<u8>, String>>,
+}
+
+struct Response {
+    status: &'static str,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn png(body: Vec<u8>) -> Self {
+        Self {
+            status: "200 OK",
+            content_type: "image/png",
+            body,
+        }
+    }
+
+    fn error(status: &'static str, message: &str) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: format!("{}\n", message).into_bytes(),
+        }
+    }
+}
+
+/// Serve the renders of the files of a directory until the process is
+/// stopped
+pub fn run(params: ServeParams) -> std::io::Result<()> {
+    let root = params.root.canonicalize()?;
+    let listener = TcpListener::bind(("127.0.0.1", params.port))?;
+    log::info!(
+        "Rendering the files of {} on http://{}/render?file=...",
+        root.display(),
+        listener.local_addr()?
+    );
+
+    let (jobs, queue) = flume::bounded::<RenderJob>(MAX_QUEUED_JOBS);
+    std::thread::spawn(move || render_jobs(queue, params));
+
+    // a connection is sent before it is handled & received once closed,
+    // so no more than the capacity of the channel are handled at once
+    let (connections, closed) = flume::bounded::<()>(MAX_CONNECTIONS);
+    for stream in listener.incoming() {
+        match stream {
+            Ok(stream) => {
+                if connections.send(()).is_err() {
+                    break;
+                }
+                let jobs = jobs.clone();
+                let root = root.clone();
+                let closed = closed.clone();
+                std::thread::spawn(move || {
+                    handle_connection(stream, &root, &jobs);
+                    let _ = closed.recv();
+                });
+            }
+            Err(e) => log::warn!("Unable to accept a connection: {}", e),
+        }
+    }
+    Ok(())
+}
+
+/// Render the queued jobs one after the other
+fn render_jobs(queue: flume::Receiver<RenderJob>, params: ServeParams) {
+    let runtime = tokio::runtime::Builder::new_current_thread()
+        .build()
+        .expect("Unable to start the render worker");
+
+    for job in queue.iter() {
+        let mut options = RenderOptions::new(job.size.into());
+        options.antialiasing = params.antialiasing;
+        options.gpu_backend = params.gpu_backend;
+        options.deterministic = params.deterministic;
+        options.max_full_page_height = MAX_FULL_PAGE_HEIGHT;
+        options.http_config.timeout = params.timeout;
+        if let Some(timeout) = params.timeout {
+            options.handle = RenderHandle::with_timeout(timeout);
+        }
+        let output = runtime.block_on(render::render_once(job.html, options, job.full_page));
+
+        let result = output.map_err(|e| e.to_string()).and_then(|output| {
+            let (width, height) = output.size;
+            let mut png = Vec::new();
+            PngEncoder::new(&mut png)
+                .encode(&output.bitmap, width, height, ColorType::Rgba8)
+                .map_err(|e| e.to_string())?;
+            Ok(png)
+        });
+        // the client may be gone
+        let _ = job.response.send(result);
+    }
+}
+
+fn handle_connection(mut stream: TcpStream, root: &Path, jobs: &flume::Sender<RenderJob>) {
+    let timeouts = stream
+        .set_read_timeout(Some(CONNECTION_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(CONNECTION_TIMEOUT)));
+    if let Err(e) = timeouts {
+        log::warn!("Unable to set the timeouts of a connection: {}", e);
+        return;
+    }
+    let response = match read_request_target(&stream) {
+        Ok(target) => respond(&target, root, jobs),
+        Err(response) => response,
+    };
+    let header = format!(
+        "HTTP/1.1 {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+        response.status,
+        response.content_type,
+        response.body.len()
+    );
+    let written = stream
+        .write_all(header.as_bytes())
+        .and_then(|_| stream.write_all(&response.body));
+    if let Err(e) = written {
+        log::warn!("Unable to send a response: {}", e);
+    }
+}
+
+/// The target of a GET request, e.g. `/render?file=index.html`. The
+/// headers are read & ignored. The requests larger than
+/// `MAX_REQUEST_SIZE` are refused.
+fn read_request_target(stream: impl Read) -> Result<String, Response> {
+    let mut reader = BufReader::new(stream.take(MAX_REQUEST_SIZE));
+    let mut request_line = String::new();
+    reader
+        .read_line(&mut request_line)
+        .map_err(|_| Response::error("400 Bad Request", "Invalid request"))?;
+    if !request_line.ends_with('\n') && reader.get_ref().limit() == 0 {
+        return Err(Response::error(
+            "414 URI Too Long",
+            "The request line is too long",
+        ));
+    }
+
+    loop {
+        let mut header = String::new();
+        match reader.read_line(&mut header) {
+            Ok(0) if reader.get_ref().limit() == 0 => {
+                return Err(Response::error(
+                    "431 Request Header Fields Too Large",
+                    "The headers are too large",
+                ))
+            }
+            Ok(0) => break,
+            Ok(_) if header.trim_end().is_empty() => break,
+            Ok(_) => {}
+            Err(_) => return Err(Response::error("400 Bad Request", "Invalid request")),
+        }
+    }
+
+    let mut parts = request_line.split_whitespace();
+    match (parts.next(), parts.next()) {
+        (Some("GET"), Some(target)) => Ok(target.to_string()),
+        (Some(_), Some(_)) => Err(Response::error(
+            "405 Method Not Allowed",
+            "Only GET requests are supported",
+        )),
+        _ => Err(Response::error("400 Bad Request", "Invalid request")),
+    }
+}
+
+/// Queue the render of a request & wait for its image
+fn respond(target: &str, root: &Path, jobs: &flume::Sender<RenderJob>) -> Response {
+    let (path, query) = parse_target(target);
+    if path != "/render" {
+        return Response::error("404 Not Found", "Use /render?file=<path>");
+    }
+
+    let file = match query.get("file") {
+        Some(file) => file,
+        None => return Response::error("400 Bad Request", "Missing the file parameter"),
+    };
+    let file = match resolve_file(root, file) {
+        Some(file) => file,
+        None => return Response::error("404 Not Found", "No such file in the served directory"),
+    };
+    let html = match std::fs::read_to_string(&file) {
+        Ok(html) => html,
+        Err(e) => return Response::error("500 Internal Server Error", &e.to_string()),
+    };
+
+    let size = match parse_size(&query) {
+        Some(size) => size,
+        None => {
+            return Response::error(
+                "400 Bad Request",
+                &format!(
+                    "Invalid width or height, expected a number of pixels between 1 and {}",
+                    MAX_VIEWPORT_DIMENSION
+                ),
+            )
+        }
+    };
+    let full_page = matches!(
+        query.get("full_page").map(String::as_str),
+        Some("1") | Some("true")
+    );
+
+    let (response, result) = flume::bounded(1);
+    let job = RenderJob {
+        html,
+        size,
+        full_page,
+        response,
+    };
+    if jobs.try_send(job).is_err() {
+        return Response::error("503 Service Unavailable", "Too many renders are queued");
+    }
+
+    match result.recv() {
+        Ok(Ok(png)) => Response::png(png),
+        Ok(Err(e)) => Response::error("500 Internal Server Error", &e),
+        Err(_) => Response::error("500 Internal Server Error", "The render worker stopped"),
+    }
+}
+
+/// The viewport of a request, from its `width` & `height`. The viewports
+/// larger than the bitmaps the renderer can paint are refused.
+fn parse_size(query: &HashMap<String, String>) -> Option<(u32, u32)> {
+    let dimension = |name: &str, default: u32| match query.get(name) {
+        Some(value) => value
+            .parse::<u32>()
+            .ok()
+            .filter(|value| (1..=MAX_VIEWPORT_DIMENSION).contains(value)),
+        None => Some(default),
+    };
+    Some((
+        dimension("width", DEFAULT_SIZE.0)?,
+        dimension("height", DEFAULT_SIZE.1)?,
+    ))
+}
+
+/// A file of the served directory. The paths leaving the directory,
+/// e.g. through `..` or a symbolic link, aren't served.
+fn resolve_file(root: &Path, file: &str) -> Option<PathBuf> {
+    let path = root
+        .join(file.trim_start_matches('/'))
+        .canonicalize()
+        .ok()?;
+    if path.starts_with(root) && path.is_file() {
+        Some(path)
+    } else {
+        None
+    }
+}
+
+/// Split the target of a request into its path & the decoded parameters
+/// of its query
+fn parse_target(target: &str) -> (&str, HashMap<String, String>) {
+    let (path, query) = match target.split_once('?') {
+        Some((path, query)) => (path, query),
+        None => (target, ""),
+    };
+    let parameters = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| match pair.split_once('=') {
+            Some((name, value)) => (decode(name), decode(value)),
+            None => (decode(pair), String::new()),
+        })
+        .collect();
+    (path, parameters)
+}
+
+/// Decode a component of an `application/x-www-form-urlencoded` query
+fn decode(input: &str) -> String {
+    let bytes = input.as_bytes();
+    let mut result = Vec::with_capacity(bytes.len());
+    let mut index = 0;
+    while index < bytes.len() {
+        let escaped = match bytes[index] {
+            b'%' => input
+                .get(index + 1..index + 3)
+                .and_then(|hex| u8::from_str_radix(hex, 16).ok()),
+            _ => None,
+        };
+        match (escaped, bytes[index]) {
+            (Some(byte), _) => {
+                result.push(byte);
+                index += 3;
+            }
+            (None, b'+') => {
+                result.push(b' ');
+                index += 1;
+            }
+            (None, byte) => {
+                result.push(byte);
+                index += 1;
+            }
+        }
+    }
+    String::from_utf8_lossy(&result).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_request_targets() {
+        let (path, query) = parse_target("/render?file=my%20page.html&width=800&full_page");
+        assert_eq!(path, "/render");
+        assert_eq!(query.get("file").unwrap(), "my page.html");
+        assert_eq!(query.get("width").unwrap(), "800");
+        assert_eq!(query.get("full_page").unwrap(), "");
+
+        let (path, query) = parse_target("/");
+        assert_eq!(path, "/");
+        assert!(query.is_empty());
+
+        assert_eq!(decode("a+b%2Fc%zz"), "a b/c%zz");
+    }
+
+    #[test]
+    fn read_request_targets() {
+        let request = "GET /render?file=a.html HTTP/1.1\r\nHost: localhost\r\n\r\n";
+        assert_eq!(
+            read_request_target(request.as_bytes()).ok().as_deref(),
+            Some("/render?file=a.html")
+        );
+
+        let status = |request: &str| {
+            read_request_target(request.as_bytes())
+                .err()
+                .unwrap()
+                .status
+        };
+        assert_eq!(status("POST / HTTP/1.1\r\n\r\n"), "405 Method Not Allowed");
+
+        let long_target = format!(
+            "GET /{} HTTP/1.1\r\n\r\n",
+            "a".repeat(MAX_REQUEST_SIZE as usize)
+        );
+        assert_eq!(status(&long_target), "414 URI Too Long");
+
+        let long_headers = format!("GET / HTTP/1.1\r\n{}", "Cookie: a=b\r\n".repeat(1000));
+        assert_eq!(status(&long_headers), "431 Request Header Fields Too Large");
+    }
+
+    #[test]
+    fn parse_viewport_sizes() {
+        let size = |target: &str| parse_size(&parse_target(target).1);
+        assert_eq!(size("/render?file=a.html"), Some(DEFAULT_SIZE));
+        assert_eq!(size("/render?width=800&height=600"), Some((800, 600)));
+        assert_eq!(size("/render?width=800"), Some((800, DEFAULT_SIZE.1)));
+        assert_eq!(size("/render?width=0"), None);
+        assert_eq!(size("/render?height=-1"), None);
+        assert_eq!(size("/render?width=4000000000&height=4000000000"), None);
+        let too_wide = format!("/render?width={}", MAX_VIEWPORT_DIMENSION + 1);
+        assert_eq!(size(&too_wide), None);
+    }
+
+    #[test]
+    fn serve_files_of_root_only() {
+        let root = Path::new(env!("CARGO_MANIFEST_DIR"))
+            .join("fixtures")
+            .canonicalize()
+            .unwrap();
+        assert!(resolve_file(&root, "test.html").is_some());
+        assert!(resolve_file(&root, "/test.html").is_some());
+        assert!(resolve_file(&root, "../Cargo.toml").is_none());
+        assert!(resolve_file(&root, "missing.html").is_none());
+        assert!(resolve_file(&root, ".").is_none());
+    }
+}